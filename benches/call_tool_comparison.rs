@@ -30,30 +30,8 @@ impl CommunicationProtocol for BenchmarkProtocol {
             Tool {
                 name: "echo".to_string(),
                 description: "Echo tool".to_string(),
-                inputs: ToolInputOutputSchema {
-                    type_: "object".to_string(),
-                    properties: None,
-                    required: None,
-                    description: None,
-                    title: None,
-                    items: None,
-                    enum_: None,
-                    minimum: None,
-                    maximum: None,
-                    format: None,
-                },
-                outputs: ToolInputOutputSchema {
-                    type_: "object".to_string(),
-                    properties: None,
-                    required: None,
-                    description: None,
-                    title: None,
-                    items: None,
-                    enum_: None,
-                    minimum: None,
-                    maximum: None,
-                    format: None,
-                },
+                inputs: ToolInputOutputSchema::new("object"),
+                outputs: ToolInputOutputSchema::new("object"),
                 tags: vec![],
                 average_response_size: None,
                 provider: None,
@@ -61,30 +39,8 @@ impl CommunicationProtocol for BenchmarkProtocol {
             Tool {
                 name: "stream".to_string(),
                 description: "Streaming tool".to_string(),
-                inputs: ToolInputOutputSchema {
-                    type_: "object".to_string(),
-                    properties: None,
-                    required: None,
-                    description: None,
-                    title: None,
-                    items: None,
-                    enum_: None,
-                    minimum: None,
-                    maximum: None,
-                    format: None,
-                },
-                outputs: ToolInputOutputSchema {
-                    type_: "object".to_string(),
-                    properties: None,
-                    required: None,
-                    description: None,
-                    title: None,
-                    items: None,
-                    enum_: None,
-                    minimum: None,
-                    maximum: None,
-                    format: None,
-                },
+                inputs: ToolInputOutputSchema::new("object"),
+                outputs: ToolInputOutputSchema::new("object"),
                 tags: vec![],
                 average_response_size: None,
                 provider: None,
@@ -173,6 +129,10 @@ fn bench_call_tool_comparison(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
             codemode.execute(black_box(args)).await.unwrap()
         });
@@ -215,6 +175,10 @@ fn bench_call_tool_stream_comparison(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
             codemode.execute(black_box(args)).await.unwrap()
         });
@@ -258,6 +222,10 @@ fn bench_call_many_tools_comparison(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.clone()),
                 timeout: Some(10000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
             codemode.execute(black_box(args)).await.unwrap()
         });
@@ -43,6 +43,10 @@ fn bench_simple_script_execution(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -72,6 +76,10 @@ fn bench_script_complexity(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -92,6 +100,10 @@ fn bench_script_complexity(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -112,6 +124,10 @@ fn bench_script_complexity(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -132,6 +148,10 @@ fn bench_script_complexity(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -183,6 +203,10 @@ fn bench_codemode_call_tool(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -239,6 +263,10 @@ fn bench_codemode_multiple_tool_calls(c: &mut Criterion) {
                     let args = CodeModeArgs {
                         code: black_box(script),
                         timeout: Some(10000),
+                        dry_run: false,
+                        allowed_tools: None,
+                        engine: None,
+                        expected_schema: None,
                     };
 
                     let result = codemode.execute(black_box(args)).await.unwrap();
@@ -281,6 +309,10 @@ fn bench_script_sizes(c: &mut Criterion) {
                     let args = CodeModeArgs {
                         code: black_box(script.clone()),
                         timeout: Some(10000),
+                        dry_run: false,
+                        allowed_tools: None,
+                        engine: None,
+                        expected_schema: None,
                     };
 
                     let result = codemode.execute(black_box(args)).await.unwrap();
@@ -318,6 +350,10 @@ fn bench_string_operations(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -353,6 +389,10 @@ fn bench_function_calls(c: &mut Criterion) {
             let args = CodeModeArgs {
                 code: black_box(script.to_string()),
                 timeout: Some(5000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(black_box(args)).await.unwrap();
@@ -1,5 +1,6 @@
 fn main() {
     println!("cargo:rerun-if-changed=src/grpcpb/utcp.proto");
+    println!("cargo:rerun-if-changed=src/grpcpb/reflection.proto");
     let out_dir = std::path::Path::new("src/grpcpb/generated");
     if !out_dir.exists() {
         std::fs::create_dir_all(out_dir).expect("failed to create gRPC output directory");
@@ -7,6 +8,9 @@ fn main() {
     tonic_build::configure()
         .build_server(true)
         .out_dir(out_dir)
-        .compile(&["src/grpcpb/utcp.proto"], &["src/grpcpb"])
+        .compile(
+            &["src/grpcpb/utcp.proto", "src/grpcpb/reflection.proto"],
+            &["src/grpcpb"],
+        )
         .expect("Failed to compile gRPC protos");
 }
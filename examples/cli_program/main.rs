@@ -62,30 +62,8 @@ async fn run_cli_mode() -> anyhow::Result<()> {
         let tool = Tool {
             name: "echo".to_string(),
             description: "Echo args".to_string(),
-            inputs: rs_utcp::tools::ToolInputOutputSchema {
-                type_: "object".to_string(),
-                properties: None,
-                required: None,
-                description: None,
-                title: None,
-                items: None,
-                enum_: None,
-                minimum: None,
-                maximum: None,
-                format: None,
-            },
-            outputs: rs_utcp::tools::ToolInputOutputSchema {
-                type_: "object".to_string(),
-                properties: None,
-                required: None,
-                description: None,
-                title: None,
-                items: None,
-                enum_: None,
-                minimum: None,
-                maximum: None,
-                format: None,
-            },
+            inputs: rs_utcp::tools::ToolInputOutputSchema::new("object"),
+            outputs: rs_utcp::tools::ToolInputOutputSchema::new("object"),
             tags: vec!["cli".to_string()],
             average_response_size: None,
             provider: None,
@@ -45,6 +45,10 @@ async fn main() -> anyhow::Result<()> {
     let args = CodeModeArgs {
         code: r#"{"hello": "world"}"#.to_string(),
         timeout: Some(2_000),
+        dry_run: false,
+        allowed_tools: None,
+        engine: None,
+        expected_schema: None,
     };
     let res = codemode.execute(args).await?;
     println!(
@@ -63,6 +67,10 @@ async fn main() -> anyhow::Result<()> {
         .execute(CodeModeArgs {
             code: snippet.to_string(),
             timeout: Some(5_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         })
         .await?;
     println!(
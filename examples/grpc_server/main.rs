@@ -1,4 +1,7 @@
 use std::net::SocketAddr;
+use std::pin::Pin;
+
+use futures_util::{Stream, TryStreamExt};
 
 use rs_utcp::{
     grpcpb::generated::{
@@ -8,7 +11,7 @@ use rs_utcp::{
     UtcpClientInterface,
 };
 use tokio_stream::wrappers::TcpListenerStream;
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{transport::Server, Request, Response, Status, Streaming};
 
 #[path = "../common/mod.rs"]
 mod common;
@@ -44,6 +47,19 @@ impl UtcpService for DemoGrpc {
     ) -> Result<Response<Self::CallToolStreamStream>, Status> {
         Ok(Response::new(futures_util::stream::empty()))
     }
+
+    type CallToolBidiStream =
+        Pin<Box<dyn Stream<Item = Result<ToolCallResponse, Status>> + Send + 'static>>;
+    async fn call_tool_bidi(
+        &self,
+        request: Request<Streaming<ToolCallRequest>>,
+    ) -> Result<Response<Self::CallToolBidiStream>, Status> {
+        // Echo each chunk's arguments back as soon as it arrives.
+        let echoed = request.into_inner().map_ok(|chunk| ToolCallResponse {
+            result_json: chunk.args_json,
+        });
+        Ok(Response::new(Box::pin(echoed)))
+    }
 }
 
 #[tokio::main]
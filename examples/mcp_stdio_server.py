@@ -10,6 +10,19 @@ import sys
 import json
 
 
+def handle_initialize(request_id, params):
+    """Agree on a protocol version and advertise the server's capabilities."""
+    return {
+        "jsonrpc": "2.0",
+        "id": request_id,
+        "result": {
+            "protocolVersion": params.get("protocolVersion", "2025-06-18"),
+            "capabilities": {"tools": {}, "experimental": {"streaming": {}}},
+            "serverInfo": {"name": "calculator", "version": "1.0.0"}
+        }
+    }
+
+
 def handle_tools_list(request_id):
     """Return the list of available tools."""
     return {
@@ -159,11 +172,17 @@ def main():
         try:
             request = json.loads(line)
             method = request.get("method")
-            request_id = request.get("id", 1)
             params = request.get("params", {})
+
+            # Notifications such as notifications/initialized get no response
+            if "id" not in request:
+                continue
+            request_id = request["id"]
             
             # Handle different MCP methods
-            if method == "tools/list":
+            if method == "initialize":
+                response = handle_initialize(request_id, params)
+            elif method == "tools/list":
                 response = handle_tools_list(request_id)
             elif method == "tools/call":
                 response = handle_tools_call(request_id, params)
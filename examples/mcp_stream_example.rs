@@ -137,6 +137,22 @@ async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
     let method = payload.get("method").and_then(|m| m.as_str()).unwrap_or("");
 
     match method {
+        "initialize" => {
+            let resp = json!({
+                "jsonrpc": "2.0",
+                "result": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": { "tools": {}, "experimental": { "streaming": {} } },
+                    "serverInfo": { "name": "demo", "version": "1.0.0" }
+                },
+                "id": payload.get("id").cloned().unwrap_or(json!(1))
+            });
+            Ok(json_response(StatusCode::OK, resp))
+        }
+        "notifications/initialized" => Ok(Response::builder()
+            .status(StatusCode::ACCEPTED)
+            .body(Body::empty())
+            .unwrap()),
         "tools/list" => {
             let resp = json!({
                 "jsonrpc": "2.0",
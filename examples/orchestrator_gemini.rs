@@ -72,6 +72,10 @@ async fn main() -> Result<()> {
         .execute(CodeModeArgs {
             code: snippet.to_string(),
             timeout: Some(5_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         })
         .await?;
     println!(
@@ -3,6 +3,7 @@
 // It includes a signaling server and WebRTC peer connection handling
 
 use anyhow::{anyhow, Result};
+use rs_utcp::transports::webrtc::chunking::{self, Reassembler};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -111,11 +112,21 @@ async fn create_peer_connection_and_answer(state: &AppState, offer_sdp: &str) ->
         let dc = data_channel.clone();
 
         Box::pin(async move {
-            // Handle incoming messages
+            // Handle incoming messages, putting chunked requests back together first
+            let mut reassembler = Reassembler::default();
             dc.on_message(Box::new(move |msg: DataChannelMessage| {
                 let dc_clone = data_channel.clone();
+                let request = reassembler.push(msg.data, MAX_REQUEST_BYTES);
                 Box::pin(async move {
-                    if let Err(e) = handle_tool_call(&dc_clone, &msg.data).await {
+                    let request = match request {
+                        Ok(Some(request)) => request,
+                        Ok(None) => return,
+                        Err(e) => {
+                            eprintln!("Error reading tool call: {}", e);
+                            return;
+                        }
+                    };
+                    if let Err(e) = handle_tool_call(&dc_clone, &request).await {
                         eprintln!("Error handling tool call: {}", e);
                     }
                 })
@@ -144,6 +155,20 @@ async fn create_peer_connection_and_answer(state: &AppState, offer_sdp: &str) ->
     Ok(answer.sdp)
 }
 
+/// Replies larger than this are split into `$chunk` frames.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+
+/// Largest request accepted once its chunks are put back together.
+const MAX_REQUEST_BYTES: usize = 10 * 1024 * 1024;
+
+/// Send a JSON message, chunked when it is larger than `MAX_MESSAGE_BYTES`.
+async fn send_json(channel: &RTCDataChannel, message: &Value) -> Result<()> {
+    for frame in chunking::split_message(serde_json::to_vec(message)?, MAX_MESSAGE_BYTES) {
+        channel.send(&frame).await?;
+    }
+    Ok(())
+}
+
 async fn handle_tool_call(channel: &Arc<RTCDataChannel>, data: &[u8]) -> Result<()> {
     // Parse request
     let request: Value = serde_json::from_slice(data)?;
@@ -168,8 +193,7 @@ async fn handle_tool_call(channel: &Arc<RTCDataChannel>, data: &[u8]) -> Result<
     };
 
     // Send response
-    let response_bytes = serde_json::to_vec(&response)?;
-    channel.send(&response_bytes.into()).await?;
+    send_json(channel, &response).await?;
 
     Ok(())
 }
@@ -289,17 +313,16 @@ async fn handle_call_tool_stream(channel: &Arc<RTCDataChannel>, request: &Value)
 
             for i in 1..=count {
                 tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                let item = json!({ "number": i });
-                let item_bytes = serde_json::to_vec(&item)?;
-                channel.send(&item_bytes.into()).await?;
+                send_json(channel, &json!({ "number": i })).await?;
                 println!("    → Sent: {}", i);
             }
+            // Tell the client the stream is complete
+            send_json(channel, &json!({ "$done": true })).await?;
             println!("  ✓ Stream complete\n");
         }
         _ => {
             let error = json!({ "error": format!("Tool {} doesn't support streaming", tool_name) });
-            let error_bytes = serde_json::to_vec(&error)?;
-            channel.send(&error_bytes.into()).await?;
+            send_json(channel, &error).await?;
         }
     }
 
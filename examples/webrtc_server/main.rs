@@ -46,11 +46,18 @@ async fn main() -> Result<()> {
         },
         // Signaling server endpoint (points to the local webrtc_server example)
         signaling_server: "http://127.0.0.1:8080/offer".to_string(),
+        signaling: None,
         ice_servers,
+        credential_endpoint: None,
         channel_label: "utcp-data".to_string(),
         ordered: true, // Ordered delivery
         max_packet_life_time: None,
         max_retransmits: None,
+        ice_gathering_timeout_ms: None,
+        max_message_bytes: None,
+        connect_timeout_ms: None,
+        open_timeout_ms: None,
+        response_timeout_ms: None,
     };
 
     // 3. Create WebRTC transport
@@ -182,4 +189,8 @@ async fn main() -> Result<()> {
  *   "result": { ... },
  *   "error": "error message" (optional)
  * }
+ *
+ * A call_tool_stream request is answered with one message per item, then
+ * { "$done": true }. An { "error": ... } message, or closing the data channel,
+ * also ends the stream.
  */
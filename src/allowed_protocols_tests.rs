@@ -258,18 +258,7 @@ async fn test_call_tool_validates_allowed_protocols() {
         allowed_communication_protocols: Some(vec!["cli".to_string()]), // Only allow CLI, but this is HTTP
     });
 
-    let default_schema = ToolInputOutputSchema {
-        type_: "object".to_string(),
-        properties: None,
-        required: None,
-        description: None,
-        title: None,
-        items: None,
-        enum_: None,
-        minimum: None,
-        maximum: None,
-        format: None,
-    };
+    let default_schema = ToolInputOutputSchema::new("object");
 
     let tool = Tool {
         name: "test_provider.test_tool".to_string(),
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::transports::http_client::HttpClientConfig;
+
 /// Trait for loading configuration variables from various sources.
 #[async_trait]
 pub trait UtcpVariablesConfig: Send + Sync {
@@ -20,8 +22,17 @@ pub struct UtcpClientConfig {
     pub variables: HashMap<String, String>,
     /// Path to the providers configuration file.
     pub providers_file_path: Option<PathBuf>,
+    /// http(s) URL serving the providers configuration, as an alternative to
+    /// `providers_file_path`.
+    pub providers_url: Option<String>,
+    /// Headers sent when fetching `providers_url`, such as `Authorization`. Values may use
+    /// `${VAR}` placeholders.
+    pub providers_url_headers: HashMap<String, String>,
     /// List of variable loaders to use.
     pub load_variables_from: Vec<Arc<dyn UtcpVariablesConfig>>,
+    /// Settings for the HTTP client shared by the HTTP-based transports; `None` uses the
+    /// process-wide default client.
+    pub http_client: Option<HttpClientConfig>,
 }
 
 impl Default for UtcpClientConfig {
@@ -29,7 +40,10 @@ impl Default for UtcpClientConfig {
         Self {
             variables: HashMap::new(),
             providers_file_path: None,
+            providers_url: None,
+            providers_url_headers: HashMap::new(),
             load_variables_from: Vec::new(),
+            http_client: None,
         }
     }
 }
@@ -46,6 +60,18 @@ impl UtcpClientConfig {
         self
     }
 
+    /// Sets the http(s) URL the providers configuration is fetched from.
+    pub fn with_providers_url(mut self, url: String) -> Self {
+        self.providers_url = Some(url);
+        self
+    }
+
+    /// Adds a header sent when fetching the providers URL.
+    pub fn with_providers_url_header(mut self, name: String, value: String) -> Self {
+        self.providers_url_headers.insert(name, value);
+        self
+    }
+
     /// Adds a single variable to the configuration.
     pub fn with_variable(mut self, key: String, value: String) -> Self {
         self.variables.insert(key, value);
@@ -58,6 +84,12 @@ impl UtcpClientConfig {
         self
     }
 
+    /// Sets the pool, keep-alive, user-agent and default timeout settings of the shared HTTP client.
+    pub fn with_http_client(mut self, config: HttpClientConfig) -> Self {
+        self.http_client = Some(config);
+        self
+    }
+
     /// v1.0-style helper to set manual/call template path (reuses providers_file_path).
     pub fn with_manual_path(mut self, path: PathBuf) -> Self {
         self.providers_file_path = Some(path);
@@ -1,5 +1,9 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::tools::Tool;
+
 /// Represents errors that can occur within the UTCP client.
 #[derive(Error, Debug)]
 pub enum UtcpError {
@@ -15,7 +19,148 @@ pub enum UtcpError {
     /// Error related to invalid configuration.
     #[error("Invalid configuration: {0}")]
     Config(String),
+    /// Error when a request exceeded a configured time limit.
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    /// Error when a request was given up at the caller's request before it finished.
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+    /// A GraphQL response reported errors, possibly alongside the data it could resolve.
+    #[error("GraphQL errors: {}", GraphQLErrorDetail::summary(errors))]
+    GraphQL {
+        errors: Vec<GraphQLErrorDetail>,
+        partial_data: Option<Value>,
+    },
+    /// A CLI command exited with a non-zero status.
+    #[error("Command exited with status {}{}", output.exit_code, output.stderr_suffix())]
+    CommandFailed { output: CommandOutput },
+    /// Some of a provider's manual files could not be loaded. The tools of the others are
+    /// registered and listed in `tools`.
+    #[error("Failed to load manuals: {}", ManualFailure::summary(failures))]
+    ManualErrors {
+        failures: Vec<ManualFailure>,
+        tools: Vec<Tool>,
+    },
+    /// A value did not have the shape its schema asked for.
+    #[error(
+        "Value does not match the expected schema: {}",
+        SchemaMismatch::summary(mismatches)
+    )]
+    SchemaMismatch {
+        mismatches: Vec<SchemaMismatch>,
+        value: Value,
+    },
     /// Other errors wrapped by anyhow.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+/// What a CLI command produced, as returned by providers with `structured_output`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    pub exit_code: i32,
+    /// stdout parsed as JSON, or its text when it is not JSON.
+    pub stdout: Value,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+impl CommandOutput {
+    fn stderr_suffix(&self) -> String {
+        match self.stderr.trim() {
+            "" => String::new(),
+            stderr => format!(": {}", stderr),
+        }
+    }
+}
+
+/// A manual file that could not contribute its tools, and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManualFailure {
+    pub path: String,
+    pub error: String,
+}
+
+impl ManualFailure {
+    fn summary(failures: &[Self]) -> String {
+        failures
+            .iter()
+            .map(|f| format!("{}: {}", f.path, f.error))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// A place where a value does not match its schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaMismatch {
+    /// Location in the value, such as `$.items[2].id`.
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaMismatch {
+    fn summary(mismatches: &[Self]) -> String {
+        mismatches
+            .iter()
+            .map(|m| format!("{}: {}", m.path, m.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// One entry of a GraphQL response's `errors` list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLErrorDetail {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<GraphQLErrorLocation>,
+    /// Response path of the failed field, as keys and list indices.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+/// Position in the request document that an error refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphQLErrorLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl GraphQLErrorDetail {
+    /// Read an error entry, keeping non-conforming entries as their JSON text.
+    pub fn from_value(value: &Value) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_else(|_| Self {
+            message: match value {
+                Value::String(message) => message.clone(),
+                other => other.to_string(),
+            },
+            locations: Vec::new(),
+            path: None,
+            extensions: None,
+        })
+    }
+
+    /// Read an `errors` value: a list of entries or a single entry.
+    pub fn list_from_value(value: &Value) -> Vec<Self> {
+        match value {
+            Value::Array(errors) => errors.iter().map(Self::from_value).collect(),
+            other => vec![Self::from_value(other)],
+        }
+    }
+
+    /// The `extensions.code` the server attached, such as `UNAUTHENTICATED`.
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+
+    fn summary(errors: &[Self]) -> String {
+        errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
@@ -189,6 +189,32 @@ pub mod utcp_service_client {
                 .insert(GrpcMethod::new("grpcpb.UTCPService", "CallToolStream"));
             self.inner.server_streaming(req, path, codec).await
         }
+        /// Each request carries one chunk of input; the server answers with any number of results.
+        pub async fn call_tool_bidi(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::ToolCallRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ToolCallResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpcpb.UTCPService/CallToolBidi",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpcpb.UTCPService", "CallToolBidi"));
+            self.inner.streaming(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -222,6 +248,20 @@ pub mod utcp_service_server {
             tonic::Response<Self::CallToolStreamStream>,
             tonic::Status,
         >;
+        /// Server streaming response type for the CallToolBidi method.
+        type CallToolBidiStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ToolCallResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Each request carries one chunk of input; the server answers with any number of results.
+        async fn call_tool_bidi(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::ToolCallRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::CallToolBidiStream>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct UtcpServiceServer<T: UtcpService> {
@@ -439,6 +479,55 @@ pub mod utcp_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/grpcpb.UTCPService/CallToolBidi" => {
+                    #[allow(non_camel_case_types)]
+                    struct CallToolBidiSvc<T: UtcpService>(pub Arc<T>);
+                    impl<
+                        T: UtcpService,
+                    > tonic::server::StreamingService<super::ToolCallRequest>
+                    for CallToolBidiSvc<T> {
+                        type Response = super::ToolCallResponse;
+                        type ResponseStream = T::CallToolBidiStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::ToolCallRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as UtcpService>::call_tool_bidi(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CallToolBidiSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -1,3 +1,8 @@
 pub mod generated {
     include!("generated/grpcpb.rs");
 }
+
+/// Client (and, for tests, server) for the `grpc.reflection.v1alpha` reflection service.
+pub mod reflection {
+    include!("generated/grpc.reflection.v1alpha.rs");
+}
@@ -0,0 +1,132 @@
+use crate::providers::http::HttpProvider;
+use crate::providers::http_stream::StreamableHttpProvider;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::http_stream::StreamableHttpTransport;
+use crate::transports::ClientTransport;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+
+const PNG_HEADER: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Serves a tiny PNG at `/image`, plain text at `/text`, JSON labelled as plain text at
+/// `/json-as-text` and a 4 KiB PNG at `/large`.
+async fn spawn_media_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let (content_type, body) = match req.uri().path() {
+            "/image" | "/files/image" => ("image/png", PNG_HEADER.to_vec()),
+            "/text" => ("text/plain; charset=utf-8", b"plain words".to_vec()),
+            "/json-as-text" => ("text/plain", br#"{"ok": true}"#.to_vec()),
+            _ => ("image/png", PNG_HEADER.repeat(512)),
+        };
+        Ok(Response::builder()
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn provider(url: String) -> HttpProvider {
+    HttpProvider::new("media".to_string(), url, "GET".to_string(), None)
+}
+
+#[tokio::test]
+async fn image_responses_are_returned_as_base64() {
+    let base_url = spawn_media_server().await;
+    let result = HttpClientTransport::new()
+        .call_tool(
+            "media.image",
+            HashMap::new(),
+            &provider(format!("{}/image", base_url)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        json!({ "$binary": {
+            "content_type": "image/png",
+            "size": 8,
+            "base64": "iVBORw0KGgo="
+        }})
+    );
+}
+
+#[tokio::test]
+async fn text_responses_are_returned_as_strings() {
+    let base_url = spawn_media_server().await;
+    let result = HttpClientTransport::new()
+        .call_tool(
+            "media.text",
+            HashMap::new(),
+            &provider(format!("{}/text", base_url)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, json!("plain words"));
+
+    let result = HttpClientTransport::new()
+        .call_tool(
+            "media.json",
+            HashMap::new(),
+            &provider(format!("{}/json-as-text", base_url)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, json!({"ok": true}));
+}
+
+#[tokio::test]
+async fn oversized_binary_responses_are_written_to_a_temp_file() {
+    let base_url = spawn_media_server().await;
+    let mut provider = provider(format!("{}/large", base_url));
+    provider.max_inline_binary_bytes = Some(1024);
+
+    let result = HttpClientTransport::new()
+        .call_tool("media.large", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    let binary = &result["$binary"];
+    assert_eq!(binary["content_type"], "image/png");
+    assert_eq!(binary["size"], 4096);
+    assert!(binary.get("base64").is_none());
+
+    let path = binary["path"].as_str().unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let saved = std::fs::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(saved, PNG_HEADER.repeat(512));
+}
+
+#[tokio::test]
+async fn http_stream_call_tool_decodes_binary_responses() {
+    let base_url = spawn_media_server().await;
+    let mut provider =
+        StreamableHttpProvider::new("media".to_string(), format!("{}/files", base_url), None);
+    provider.http_method = "GET".to_string();
+
+    let result = StreamableHttpTransport::new()
+        .call_tool("media.image", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result["$binary"]["base64"], "iVBORw0KGgo=");
+}
@@ -0,0 +1,143 @@
+use crate::providers::http::HttpProvider;
+use crate::providers::sse::SseProvider;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::sse::SseTransport;
+use crate::transports::ClientTransport;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+fn header(req: &Request<Body>, name: &str) -> Value {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map_or(Value::Null, |v| json!(v))
+}
+
+/// `/report` serves gzip-compressed JSON, `/echo` reports how the request body was encoded,
+/// and `/events/watch` sends the request's `Accept-Encoding` as one SSE event.
+async fn spawn_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        match req.uri().path() {
+            "/report" => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(json!({ "rows": vec![7; 500] }).to_string().as_bytes())
+                    .unwrap();
+                Ok(Response::builder()
+                    .header("content-type", "application/json")
+                    .header("content-encoding", "gzip")
+                    .body(Body::from(encoder.finish().unwrap()))
+                    .unwrap())
+            }
+            "/events/watch" => {
+                let event = json!({ "accept_encoding": header(&req, "accept-encoding") });
+                Ok(Response::builder()
+                    .header("content-type", "text/event-stream")
+                    .body(Body::from(format!("data: {}\n\n", event)))
+                    .unwrap())
+            }
+            _ => {
+                let encoding = header(&req, "content-encoding");
+                let raw = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                let mut body = String::new();
+                if encoding == "gzip" {
+                    GzDecoder::new(&raw[..]).read_to_string(&mut body).unwrap();
+                } else {
+                    body = String::from_utf8(raw.to_vec()).unwrap();
+                }
+                let reply = json!({
+                    "encoding": encoding,
+                    "body": serde_json::from_str::<Value>(&body).unwrap(),
+                });
+                Ok(Response::new(Body::from(reply.to_string())))
+            }
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn gzip_responses_are_decoded() {
+    let base_url = spawn_server().await;
+    let provider = HttpProvider::new(
+        "reports".to_string(),
+        format!("{}/report", base_url),
+        "GET".to_string(),
+        None,
+    );
+
+    let result = HttpClientTransport::new()
+        .call_tool("reports.get", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result, json!({ "rows": vec![7; 500] }));
+}
+
+#[tokio::test]
+async fn large_json_bodies_are_gzipped_when_enabled() {
+    let base_url = spawn_server().await;
+    let transport = HttpClientTransport::new();
+    let mut provider = HttpProvider::new(
+        "ingest".to_string(),
+        format!("{}/echo", base_url),
+        "POST".to_string(),
+        None,
+    );
+    let large = HashMap::from([("text".to_string(), json!("x".repeat(4096)))]);
+    let small = HashMap::from([("text".to_string(), json!("short"))]);
+
+    // Off by default.
+    let result = transport
+        .call_tool("ingest.put", large.clone(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result["encoding"], Value::Null);
+
+    provider.compress_requests = true;
+    let result = transport
+        .call_tool("ingest.put", large.clone(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result["encoding"], "gzip");
+    assert_eq!(result["body"], json!(large));
+
+    // Small bodies are not worth compressing.
+    let result = transport
+        .call_tool("ingest.put", small, &provider)
+        .await
+        .unwrap();
+    assert_eq!(result["encoding"], Value::Null);
+    assert_eq!(result["body"]["text"], "short");
+}
+
+#[tokio::test]
+async fn event_streams_do_not_request_compression() {
+    let base_url = spawn_server().await;
+    let provider = SseProvider::new("feed".to_string(), format!("{}/events", base_url), None);
+
+    let events = SseTransport::new()
+        .call_tool("feed.watch", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(events, json!([{ "accept_encoding": "identity" }]));
+}
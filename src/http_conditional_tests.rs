@@ -0,0 +1,111 @@
+use crate::providers::http::{HttpProvider, ResponseCacheConfig};
+use crate::transports::http::HttpClientTransport;
+use crate::transports::ClientTransport;
+use hyper::header::{ETAG, IF_NONE_MATCH};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+type SeenHeaders = Arc<Mutex<Vec<Option<String>>>>;
+
+/// Serves `/status` with an ETag, answering 304 when the client already holds it, and
+/// records the `If-None-Match` header of every request.
+async fn spawn_etag_server() -> (String, SeenHeaders) {
+    let seen: SeenHeaders = Arc::default();
+    let recorded = seen.clone();
+    let make_svc = make_service_fn(move |_| {
+        let seen = recorded.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let seen = seen.clone();
+                async move {
+                    let if_none_match = req
+                        .headers()
+                        .get(IF_NONE_MATCH)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string);
+                    let fetches = {
+                        let mut seen = seen.lock().unwrap();
+                        seen.push(if_none_match.clone());
+                        seen.len()
+                    };
+                    let response = if if_none_match.as_deref() == Some("\"v1\"") {
+                        Response::builder()
+                            .status(StatusCode::NOT_MODIFIED)
+                            .body(Body::empty())
+                    } else {
+                        Response::builder()
+                            .header(ETAG, "\"v1\"")
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                json!({ "status": "green", "fetch": fetches }).to_string(),
+                            ))
+                    };
+                    Ok::<_, Infallible>(response.unwrap())
+                }
+            }))
+        }
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    (format!("http://{}/status", addr), seen)
+}
+
+#[tokio::test]
+async fn repeated_get_is_revalidated_and_served_from_cache_on_304() {
+    let (url, seen) = spawn_etag_server().await;
+    let transport = HttpClientTransport::new();
+    let mut provider = HttpProvider::new("dash".to_string(), url, "GET".to_string(), None);
+    provider.response_cache = Some(ResponseCacheConfig::default());
+
+    let first = transport
+        .call_tool("dash.get_status", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(first, json!({ "status": "green", "fetch": 1 }));
+
+    let second = transport
+        .call_tool("dash.get_status", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(second, first);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![None, Some("\"v1\"".to_string())]
+    );
+
+    // Different arguments are cached separately and start with a full fetch.
+    let args = HashMap::from([("region".to_string(), json!("eu"))]);
+    transport
+        .call_tool("dash.get_status", args, &provider)
+        .await
+        .unwrap();
+    assert_eq!(seen.lock().unwrap()[2], None);
+}
+
+#[tokio::test]
+async fn responses_are_not_cached_unless_enabled() {
+    let (url, seen) = spawn_etag_server().await;
+    let transport = HttpClientTransport::new();
+    let provider = HttpProvider::new("dash".to_string(), url, "GET".to_string(), None);
+
+    for _ in 0..2 {
+        transport
+            .call_tool("dash.get_status", HashMap::new(), &provider)
+            .await
+            .unwrap();
+    }
+    assert_eq!(*seen.lock().unwrap(), vec![None, None]);
+}
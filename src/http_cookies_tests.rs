@@ -0,0 +1,136 @@
+use crate::providers::http::HttpProvider;
+use crate::providers::sse::SseProvider;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::sse::SseTransport;
+use crate::transports::ClientTransport;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get};
+use axum::{Json, Router};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::TcpListener;
+
+const SESSION: &str = "session=s3cret";
+
+fn has_session(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.split("; ").any(|c| c == SESSION))
+}
+
+/// Legacy server: discovery at `/legacy` hands out a session cookie, and every call
+/// without it is rejected with 401.
+async fn spawn_session_server() -> String {
+    async fn discover() -> Response {
+        let manifest = json!({ "tools": [] });
+        (
+            [(header::SET_COOKIE, format!("{}; Path=/", SESSION))],
+            Json(manifest),
+        )
+            .into_response()
+    }
+
+    async fn call(headers: HeaderMap) -> Response {
+        if !has_session(&headers) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        Json(json!({ "ok": true })).into_response()
+    }
+
+    async fn events(headers: HeaderMap) -> Response {
+        if !has_session(&headers) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        (
+            [(header::CONTENT_TYPE, "text/event-stream")],
+            "data: {\"ok\":true}\n\n",
+        )
+            .into_response()
+    }
+
+    let app = Router::new()
+        .route("/legacy", get(discover).post(call))
+        .route("/legacy/events", any(events));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn http_provider(base_url: &str, use_cookies: bool) -> HttpProvider {
+    let mut provider = HttpProvider::new(
+        "legacy".to_string(),
+        format!("{}/legacy", base_url),
+        "POST".to_string(),
+        None,
+    );
+    provider.use_cookies = use_cookies;
+    provider
+}
+
+#[tokio::test]
+async fn http_session_cookie_from_discovery_is_sent_on_calls() {
+    let base_url = spawn_session_server().await;
+    let transport = HttpClientTransport::new();
+    let provider = http_provider(&base_url, true);
+
+    transport.register_tool_provider(&provider).await.unwrap();
+    let result = transport
+        .call_tool("legacy.call", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result, json!({ "ok": true }));
+
+    transport.clear_cookies("legacy");
+    let err = transport
+        .call_tool("legacy.call", HashMap::new(), &provider)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+
+    // Deregistering forgets the session too.
+    transport.register_tool_provider(&provider).await.unwrap();
+    transport.deregister_tool_provider(&provider).await.unwrap();
+    assert!(transport
+        .call_tool("legacy.call", HashMap::new(), &provider)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn http_cookies_are_ignored_unless_enabled() {
+    let base_url = spawn_session_server().await;
+    let transport = HttpClientTransport::new();
+    let provider = http_provider(&base_url, false);
+
+    transport.register_tool_provider(&provider).await.unwrap();
+    let err = transport
+        .call_tool("legacy.call", HashMap::new(), &provider)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+}
+
+#[tokio::test]
+async fn sse_session_cookie_from_discovery_is_sent_on_calls() {
+    let base_url = spawn_session_server().await;
+    let transport = SseTransport::new();
+    let mut provider = SseProvider::new("legacy".to_string(), format!("{}/legacy", base_url), None);
+    provider.use_cookies = true;
+
+    transport.register_tool_provider(&provider).await.unwrap();
+    let events = transport
+        .call_tool("legacy.events", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(events, json!([{ "ok": true }]));
+}
@@ -0,0 +1,113 @@
+use crate::providers::http::{HttpProvider, ProxyConfig};
+use crate::transports::http::HttpClientTransport;
+use crate::transports::ClientTransport;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+
+/// Answers every request with the target URI and proxy credentials it received, which is
+/// what a forwarding proxy sees for plain-http targets.
+async fn spawn_proxy_stub() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let proxy_auth = req
+            .headers()
+            .get("proxy-authorization")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = json!({ "via_proxy": req.uri().to_string(), "proxy_auth": proxy_auth });
+        Ok(Response::new(Body::from(body.to_string())))
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn provider(url: &str, proxy: ProxyConfig) -> HttpProvider {
+    let mut provider = HttpProvider::new(
+        "proxied".to_string(),
+        url.to_string(),
+        "GET".to_string(),
+        None,
+    );
+    provider.proxy = Some(proxy);
+    provider.max_retries = Some(0);
+    provider
+}
+
+#[tokio::test]
+async fn calls_go_through_the_configured_proxy_with_credentials() {
+    let proxy_url = spawn_proxy_stub().await;
+    let transport = HttpClientTransport::new();
+    let provider = provider(
+        "http://tools.internal/echo",
+        ProxyConfig {
+            url: proxy_url,
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            no_proxy: vec![],
+        },
+    );
+
+    let result = transport
+        .call_tool("proxied.echo", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result["via_proxy"], "http://tools.internal/echo");
+    assert_eq!(result["proxy_auth"], "Basic dXNlcjpwYXNz");
+}
+
+#[tokio::test]
+async fn no_proxy_hosts_are_reached_directly() {
+    // The target is the stub itself; the proxy points at a port nobody listens on.
+    let target = spawn_proxy_stub().await;
+    let dead = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let transport = HttpClientTransport::new();
+    let proxy = ProxyConfig {
+        url: format!("http://{}", dead),
+        username: None,
+        password: None,
+        no_proxy: vec!["127.0.0.1".to_string()],
+    };
+
+    let result = transport
+        .call_tool(
+            "proxied.echo",
+            HashMap::new(),
+            &provider(&format!("{}/echo", target), proxy.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["via_proxy"], "/echo");
+    assert_eq!(result["proxy_auth"], json!(null));
+
+    // Without the exemption the dead proxy is used and the call fails.
+    let err = transport
+        .call_tool(
+            "proxied.echo",
+            HashMap::new(),
+            &provider(
+                &format!("{}/echo", target),
+                ProxyConfig {
+                    no_proxy: vec![],
+                    ..proxy
+                },
+            ),
+        )
+        .await;
+    assert!(err.is_err());
+}
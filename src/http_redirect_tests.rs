@@ -0,0 +1,211 @@
+use crate::auth::{ApiKeyAuth, AuthConfig};
+use crate::providers::http::{HttpProvider, RedirectPolicy};
+use crate::transports::http::HttpClientTransport;
+use crate::transports::ClientTransport;
+use hyper::header::{AUTHORIZATION, LOCATION};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+
+/// Serves redirect chains, redirecting `/signed` to `{other}/final`. `/final` and
+/// `/chain/0` echo the method, credentials and body they received.
+async fn spawn_redirect_server(other: Option<String>) -> String {
+    async fn handle(req: Request<Body>, other: Option<String>) -> Response<Body> {
+        let path = req.uri().path().to_string();
+        let redirect = |status: StatusCode, location: String| {
+            Response::builder()
+                .status(status)
+                .header(LOCATION, location)
+                .body(Body::empty())
+                .unwrap()
+        };
+        match path.as_str() {
+            "/moved" => redirect(StatusCode::TEMPORARY_REDIRECT, "/final".to_string()),
+            "/signed" => redirect(
+                StatusCode::TEMPORARY_REDIRECT,
+                format!("{}/final", other.unwrap()),
+            ),
+            "/loop" => redirect(StatusCode::FOUND, "/loop2".to_string()),
+            "/loop2" => redirect(StatusCode::FOUND, "/loop".to_string()),
+            "/final" | "/chain/0" => {
+                let header = |name: &str| {
+                    req.headers()
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string)
+                };
+                let mut echo = json!({
+                    "method": req.method().as_str(),
+                    "authorization": header(AUTHORIZATION.as_str()),
+                    "api_key": header("x-api-key"),
+                });
+                let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                echo["body"] = serde_json::from_slice(&body).unwrap_or(Value::Null);
+                Response::new(Body::from(echo.to_string()))
+            }
+            _ => match path
+                .strip_prefix("/chain/")
+                .and_then(|n| n.parse::<u32>().ok())
+            {
+                Some(n) => redirect(StatusCode::MOVED_PERMANENTLY, format!("/chain/{}", n - 1)),
+                None => Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty())
+                    .unwrap(),
+            },
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(move |_| {
+        let other = other.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let other = other.clone();
+                async move { Ok::<_, Infallible>(handle(req, other).await) }
+            }))
+        }
+    });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn provider(url: String, redirect: RedirectPolicy) -> HttpProvider {
+    let mut provider = HttpProvider::new(
+        "redirects".to_string(),
+        url,
+        "POST".to_string(),
+        Some(AuthConfig::ApiKey(ApiKeyAuth::new("key-123".to_string()))),
+    );
+    provider.headers = Some(HashMap::from([(
+        "Authorization".to_string(),
+        "Bearer token".to_string(),
+    )]));
+    provider.redirect = Some(redirect);
+    provider
+}
+
+fn args() -> HashMap<String, Value> {
+    HashMap::from([("q".to_string(), json!("rust"))])
+}
+
+#[tokio::test]
+async fn same_origin_307_keeps_method_body_and_credentials() {
+    let base_url = spawn_redirect_server(None).await;
+    let transport = HttpClientTransport::new();
+    let prov = provider(format!("{}/moved", base_url), RedirectPolicy::default());
+
+    let echo = transport.call_tool("r.moved", args(), &prov).await.unwrap();
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["body"], json!({ "q": "rust" }));
+    assert_eq!(echo["authorization"], "Bearer token");
+    assert_eq!(echo["api_key"], "key-123");
+}
+
+#[tokio::test]
+async fn cross_origin_hops_strip_credentials_unless_preserved() {
+    let storage_url = spawn_redirect_server(None).await;
+    let base_url = spawn_redirect_server(Some(storage_url.clone())).await;
+    let transport = HttpClientTransport::new();
+    let url = format!("{}/signed", base_url);
+
+    let echo = transport
+        .call_tool(
+            "r.signed",
+            args(),
+            &provider(url.clone(), RedirectPolicy::default()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(echo["method"], "POST");
+    assert_eq!(echo["body"], json!({ "q": "rust" }));
+    assert_eq!(echo["authorization"], Value::Null);
+    assert_eq!(echo["api_key"], Value::Null);
+
+    let preserve = RedirectPolicy {
+        preserve_auth_header: true,
+        ..RedirectPolicy::default()
+    };
+    let echo = transport
+        .call_tool("r.signed", args(), &provider(url.clone(), preserve))
+        .await
+        .unwrap();
+    assert_eq!(echo["authorization"], "Bearer token");
+    assert_eq!(echo["api_key"], "key-123");
+
+    let same_origin_only = RedirectPolicy {
+        follow_cross_origin: false,
+        ..RedirectPolicy::default()
+    };
+    let err = transport
+        .call_tool("r.signed", args(), &provider(url.clone(), same_origin_only))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Cross-origin redirect not allowed"), "{err}");
+    assert!(
+        err.contains(&format!("{} -> {}/final", url, storage_url)),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn redirect_limit_reports_the_chain() {
+    let base_url = spawn_redirect_server(None).await;
+    let transport = HttpClientTransport::new();
+    let url = format!("{}/chain/3", base_url);
+    let limited = |max_redirects| RedirectPolicy {
+        max_redirects,
+        ..RedirectPolicy::default()
+    };
+
+    let err = transport
+        .call_tool("r.chain", args(), &provider(url.clone(), limited(2)))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Too many redirects (limit 2)"), "{err}");
+    assert!(
+        err.contains(&format!(
+            "{0}/chain/3 -> {0}/chain/2 -> {0}/chain/1 -> {0}/chain/0",
+            base_url
+        )),
+        "{err}"
+    );
+
+    // 301 after a POST continues as a body-less GET.
+    let echo = transport
+        .call_tool("r.chain", args(), &provider(url, limited(3)))
+        .await
+        .unwrap();
+    assert_eq!(echo["method"], "GET");
+    assert_eq!(echo["body"], Value::Null);
+}
+
+#[tokio::test]
+async fn redirect_loop_is_reported() {
+    let base_url = spawn_redirect_server(None).await;
+    let transport = HttpClientTransport::new();
+    let prov = provider(format!("{}/loop", base_url), RedirectPolicy::default());
+
+    let err = transport
+        .call_tool("r.loop", args(), &prov)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Redirect loop"), "{err}");
+    assert!(
+        err.contains(&format!("{0}/loop -> {0}/loop2 -> {0}/loop", base_url)),
+        "{err}"
+    );
+}
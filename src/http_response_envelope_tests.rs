@@ -0,0 +1,97 @@
+use crate::providers::http::HttpProvider;
+use crate::providers::sse::SseProvider;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::sse::SseTransport;
+use crate::transports::ClientTransport;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+
+/// Serves a JSON page at `/items` and two SSE events at `/feed/watch`, both with
+/// pagination and rate-limit headers.
+async fn spawn_paged_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let response = Response::builder()
+            .header("x-next-cursor", "abc123")
+            .header("x-ratelimit-remaining", "41");
+        let response = if req.uri().path() == "/feed/watch" {
+            response
+                .header("content-type", "text/event-stream")
+                .body(Body::from("data: {\"n\":1}\n\ndata: {\"n\":2}\n\n"))
+        } else {
+            response
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "items": [1, 2] }).to_string()))
+        };
+        Ok(response.unwrap())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn envelope_carries_status_and_headers() {
+    let base_url = spawn_paged_server().await;
+    let transport = HttpClientTransport::new();
+    let mut provider = HttpProvider::new(
+        "paged".to_string(),
+        format!("{}/items", base_url),
+        "GET".to_string(),
+        None,
+    );
+
+    // Off by default: only the body comes back.
+    let bare = transport
+        .call_tool("paged.items", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(bare, json!({ "items": [1, 2] }));
+
+    provider.return_envelope = true;
+    let wrapped = transport
+        .call_tool("paged.items", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(wrapped["status"], 200);
+    assert_eq!(wrapped["headers"]["x-next-cursor"], "abc123");
+    assert_eq!(wrapped["headers"]["x-ratelimit-remaining"], "41");
+    assert_eq!(wrapped["body"], bare);
+}
+
+#[tokio::test]
+async fn sse_streams_start_with_response_metadata() {
+    let base_url = spawn_paged_server().await;
+    let transport = SseTransport::new();
+    let mut provider = SseProvider::new("feed".to_string(), format!("{}/feed", base_url), None);
+
+    let events = transport
+        .call_tool("feed.watch", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(events, json!([{ "n": 1 }, { "n": 2 }]));
+
+    provider.return_envelope = true;
+    let mut stream = transport
+        .call_tool_stream("feed.watch", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    let metadata = stream.next().await.unwrap().unwrap();
+    assert_eq!(metadata["status"], 200);
+    assert_eq!(metadata["headers"]["x-next-cursor"], "abc123");
+    assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": 1 })));
+    assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": 2 })));
+    assert_eq!(stream.next().await.unwrap(), None);
+}
@@ -0,0 +1,155 @@
+use crate::errors::UtcpError;
+use crate::providers::http::{HttpProvider, HttpTimeouts};
+use crate::providers::http_stream::StreamableHttpProvider;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::http_stream::StreamableHttpTransport;
+use crate::transports::ClientTransport;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::time::Duration;
+
+/// `/slow` answers after 500ms; `/stream/ticks` sends headers at once and then one
+/// NDJSON line every 100ms.
+async fn spawn_slow_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        if req.uri().path() == "/stream/ticks" {
+            let ticks = futures::stream::unfold(0, |n| async move {
+                if n == 3 {
+                    return None;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let line = format!("{}{}", if n == 0 { "" } else { "\n" }, json!({ "tick": n }));
+                Some((Ok::<_, Infallible>(line), n + 1))
+            });
+            return Ok(Response::new(Body::wrap_stream(ticks)));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        Ok(Response::new(Body::from(json!({ "ok": true }).to_string())))
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn http_provider(url: String, timeouts: HttpTimeouts) -> HttpProvider {
+    let mut provider = HttpProvider::new("slow".to_string(), url, "GET".to_string(), None);
+    provider.timeouts = Some(timeouts);
+    provider
+}
+
+fn is_timeout(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_)))
+}
+
+#[tokio::test]
+async fn request_timeout_is_reported_as_timeout() {
+    let base_url = spawn_slow_server().await;
+    let transport = HttpClientTransport::new();
+    let provider = http_provider(
+        format!("{}/slow", base_url),
+        HttpTimeouts {
+            request_ms: Some(100),
+            ..Default::default()
+        },
+    );
+
+    let err = transport
+        .call_tool("slow.op", HashMap::new(), &provider)
+        .await
+        .unwrap_err();
+    assert!(is_timeout(&err), "{err}");
+}
+
+#[tokio::test]
+async fn total_timeout_bounds_the_whole_call() {
+    let base_url = spawn_slow_server().await;
+    let transport = HttpClientTransport::new();
+    let provider = http_provider(
+        format!("{}/slow", base_url),
+        HttpTimeouts {
+            total_ms: Some(100),
+            ..Default::default()
+        },
+    );
+
+    let err = transport
+        .call_tool("slow.op", HashMap::new(), &provider)
+        .await
+        .unwrap_err();
+    assert!(is_timeout(&err), "{err}");
+
+    // Generous limits let the same slow call through.
+    let provider = http_provider(
+        format!("{}/slow", base_url),
+        HttpTimeouts {
+            connect_ms: Some(1_000),
+            request_ms: Some(2_000),
+            total_ms: Some(3_000),
+        },
+    );
+    let result = transport
+        .call_tool("slow.op", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    assert_eq!(result, json!({ "ok": true }));
+}
+
+#[tokio::test]
+async fn refused_connection_is_not_a_timeout() {
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let transport = HttpClientTransport::new();
+    let mut provider = http_provider(
+        format!("http://{}/", addr),
+        HttpTimeouts {
+            connect_ms: Some(1_000),
+            ..Default::default()
+        },
+    );
+    provider.max_retries = Some(0);
+
+    let err = transport
+        .call_tool("slow.op", HashMap::new(), &provider)
+        .await
+        .unwrap_err();
+    assert!(!is_timeout(&err), "{err}");
+}
+
+#[tokio::test]
+async fn streaming_total_timeout_only_covers_the_initial_response() {
+    let base_url = spawn_slow_server().await;
+    let transport = StreamableHttpTransport::new();
+    let mut provider =
+        StreamableHttpProvider::new("stream".to_string(), format!("{}/stream", base_url), None);
+    provider.http_method = "GET".to_string();
+    provider.timeouts = Some(HttpTimeouts {
+        total_ms: Some(150),
+        ..Default::default()
+    });
+
+    // The body takes ~300ms, twice the limit, yet every tick arrives.
+    let mut stream = transport
+        .call_tool_stream("stream.ticks", HashMap::new(), &provider)
+        .await
+        .unwrap();
+    let mut ticks = Vec::new();
+    while let Some(item) = stream.next().await.unwrap() {
+        ticks.push(item["tick"].clone());
+    }
+    assert_eq!(ticks, vec![json!(0), json!(1), json!(2)]);
+}
@@ -17,31 +17,77 @@ pub mod transports;
 
 #[cfg(test)]
 mod allowed_protocols_tests;
+#[cfg(test)]
+mod http_binary_response_tests;
+#[cfg(test)]
+mod http_compression_tests;
+#[cfg(test)]
+mod http_conditional_tests;
+#[cfg(test)]
+mod http_cookies_tests;
+#[cfg(test)]
+mod http_proxy_tests;
+#[cfg(test)]
+mod http_redirect_tests;
+#[cfg(test)]
+mod http_response_envelope_tests;
+#[cfg(test)]
+mod http_timeouts_tests;
+#[cfg(test)]
+mod mcp_tools_changed_tests;
+#[cfg(test)]
+mod openapi_file_spec_tests;
+#[cfg(test)]
+mod openapi_form_body_tests;
+#[cfg(test)]
+mod openapi_path_params_tests;
+#[cfg(test)]
+mod openapi_query_styles_tests;
+#[cfg(test)]
+mod openapi_streaming_tests;
+#[cfg(test)]
+mod openapi_swagger2_tests;
+#[cfg(test)]
+mod providers_url_tests;
+#[cfg(test)]
+mod shared_http_client_tests;
+#[cfg(test)]
+mod text_manuals_tests;
+#[cfg(test)]
+mod text_watch_tests;
+#[cfg(test)]
+mod tool_listing_tests;
+#[cfg(test)]
+mod tool_stats_tests;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 
 use crate::config::UtcpClientConfig;
 use crate::errors::UtcpError;
 use crate::openapi::OpenApiConverter;
 use crate::providers::base::{Provider, ProviderType};
 use crate::providers::http::HttpProvider;
-use crate::repository::ToolRepository;
-use crate::tools::{Tool, ToolSearchStrategy};
+use crate::repository::{RepositoryEvent, RepositoryEvents, ToolCallOutcome, ToolRepository};
+use crate::tools::{ScoredTool, SearchOptions, Tool, ToolSearchStrategy};
+use crate::transports::http_client::SharedClient;
 use crate::transports::registry::{
     communication_protocols_snapshot, CommunicationProtocolRegistry,
 };
 use crate::transports::stream::StreamResult;
-use crate::transports::CommunicationProtocol;
+use crate::transports::{CommunicationProtocol, ToolsChangedCallback};
 
 /// UtcpClientInterface defines the core operations for a UTCP client.
 /// It allows registering/deregistering tool providers, calling tools, and searching for tools.
 #[async_trait]
 pub trait UtcpClientInterface: Send + Sync {
-    /// Registers a new tool provider and returns the list of tools it offers.
+    /// Registers a new tool provider and returns the list of tools it offers. When only some
+    /// of a provider's manuals load, the tools they hold are registered and
+    /// `UtcpError::ManualErrors` lists them along with the files that failed.
     async fn register_tool_provider(&self, prov: Arc<dyn Provider>) -> Result<Vec<Tool>>;
 
     /// Registers a tool provider with a specific set of tools, overriding automatic discovery.
@@ -64,6 +110,39 @@ pub trait UtcpClientInterface: Send + Sync {
     /// Searches for tools matching the query string, limited by the count.
     async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>>;
 
+    /// Searches with provider/tag filters and pagination, returning scored results.
+    async fn search_tools_with(&self, query: &str, opts: SearchOptions) -> Result<Vec<ScoredTool>> {
+        Ok(opts.apply(self.search_tools(query, 0).await?))
+    }
+
+    /// Lists every registered tool, ordered by name.
+    async fn list_tools(&self) -> Result<Vec<Tool>> {
+        let mut tools = self.search_tools("", 0).await?;
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tools)
+    }
+
+    /// Looks up a registered tool by its qualified `provider.tool` name or its bare name.
+    async fn get_tool(&self, tool_name: &str) -> Result<Option<Tool>> {
+        let tools = self.list_tools().await?;
+        if let Some(tool) = tools.iter().find(|t| t.name == tool_name) {
+            return Ok(Some(tool.clone()));
+        }
+        Ok(tools.into_iter().find(|t| {
+            t.name
+                .split_once('.')
+                .is_some_and(|(_, suffix)| suffix == tool_name)
+        }))
+    }
+
+    /// The qualified `provider.tool` name that `call_tool` would run for `tool_name`.
+    async fn resolve_tool_name(&self, tool_name: &str) -> Result<String> {
+        match self.get_tool(tool_name).await? {
+            Some(tool) => Ok(tool.name),
+            None => Err(UtcpError::ToolNotFound(tool_name.to_string()).into()),
+        }
+    }
+
     /// Returns a map of available transports (communication protocols).
     fn get_transports(&self) -> HashMap<String, Arc<dyn CommunicationProtocol>>;
 
@@ -80,6 +159,115 @@ pub trait UtcpClientInterface: Send + Sync {
     ) -> Result<Box<dyn StreamResult>>;
 }
 
+/// Prefixes tool names with their provider's name, as `provider.tool`.
+fn normalize_tool_names(provider_name: &str, tools: Vec<Tool>) -> Vec<Tool> {
+    let mut normalized_tools = Vec::new();
+    for mut tool in tools {
+        if !tool.name.starts_with(&format!("{}.", provider_name)) {
+            tool.name = format!("{}.{}", provider_name, tool.name.trim_start_matches('.'));
+        }
+        normalized_tools.push(tool);
+    }
+    normalized_tools
+}
+
+/// The callback a client hands its transports. A provider reporting changed tools has them
+/// listed again and saved to the repository, and `refreshed` then tells the client to reload
+/// its caches. Providers this client's repository does not know are ignored.
+fn tools_changed_callback(
+    protocols: CommunicationProtocolRegistry,
+    repo: Arc<dyn ToolRepository>,
+    strat: Arc<dyn ToolSearchStrategy>,
+    refreshed: mpsc::UnboundedSender<String>,
+) -> ToolsChangedCallback {
+    Arc::new(move |provider_name: &str| {
+        let protocols = protocols.clone();
+        let repo = repo.clone();
+        let strat = strat.clone();
+        let refreshed = refreshed.clone();
+        let provider_name = provider_name.to_string();
+        tokio::spawn(async move {
+            match refresh_provider_tools(&protocols, &repo, &strat, &provider_name).await {
+                Ok(true) => {
+                    let _ = refreshed.send(provider_name);
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::warn!(provider = %provider_name, error = %err, "Failed to refresh provider tools")
+                }
+            }
+        });
+    })
+}
+
+/// Lists a provider's tools again and saves them, returning whether the provider is in `repo`
+/// and its tools changed.
+async fn refresh_provider_tools(
+    protocols: &CommunicationProtocolRegistry,
+    repo: &Arc<dyn ToolRepository>,
+    strat: &Arc<dyn ToolSearchStrategy>,
+    provider_name: &str,
+) -> Result<bool> {
+    let Some(prov) = repo.get_provider(provider_name).await? else {
+        return Ok(false);
+    };
+    let provider_type = prov.type_();
+    let protocol = protocols.get(provider_type.as_key()).ok_or_else(|| {
+        anyhow!(
+            "No communication protocol found for provider type: {:?}",
+            provider_type
+        )
+    })?;
+
+    let tools = protocol.register_tool_provider(prov.as_ref()).await?;
+    let tools = normalize_tool_names(provider_name, tools);
+    save_provider_tools(repo, strat, prov, tools).await
+}
+
+/// Saves and indexes a provider's tools unless `repo` already holds the same provider with the
+/// same tools, returning whether anything was saved.
+async fn save_provider_tools(
+    repo: &Arc<dyn ToolRepository>,
+    strat: &Arc<dyn ToolSearchStrategy>,
+    prov: Arc<dyn Provider>,
+    tools: Vec<Tool>,
+) -> Result<bool> {
+    if stored_matches(repo, prov.as_ref(), Some(&tools)).await {
+        return Ok(false);
+    }
+    let provider_name = prov.name();
+    repo.save_provider_with_tools(prov, tools.clone()).await?;
+    let _ = strat.index_provider(&provider_name, &tools).await;
+    Ok(true)
+}
+
+/// Whether `repo` holds a provider with the same definition as `prov` and, when `tools` is
+/// given, with the same tools.
+async fn stored_matches(
+    repo: &Arc<dyn ToolRepository>,
+    prov: &dyn Provider,
+    tools: Option<&[Tool]>,
+) -> bool {
+    let name = prov.name();
+    let Ok(Some(current)) = repo.get_provider(&name).await else {
+        return false;
+    };
+    let same_provider = matches!(
+        (
+            crate::loader::provider_to_value(current.as_ref()),
+            crate::loader::provider_to_value(prov),
+        ),
+        (Ok(a), Ok(b)) if a == b
+    );
+    let Some(tools) = tools else {
+        return same_provider;
+    };
+    let Ok(current_tools) = repo.get_tools_by_provider(&name).await else {
+        return false;
+    };
+    same_provider && serde_json::to_value(current_tools).ok() == serde_json::to_value(tools).ok()
+}
+
 /// UtcpClient is the main entry point for the UTCP library.
 /// It manages tool providers, communication protocols, and tool execution.
 pub struct UtcpClient {
@@ -90,6 +278,31 @@ pub struct UtcpClient {
 
     provider_tools_cache: RwLock<HashMap<String, Vec<Tool>>>,
     resolved_tools_cache: RwLock<HashMap<String, ResolvedTool>>,
+    /// Change feed from the repository, drained before cache lookups so evictions are honored.
+    repository_events: Option<Mutex<RepositoryEvents>>,
+    /// Providers whose tools were listed again after they reported a change.
+    refreshed_providers: Mutex<mpsc::UnboundedReceiver<String>>,
+    /// Transports hold this callback weakly, so the client keeps it alive.
+    _on_tools_changed: ToolsChangedCallback,
+    /// Providers registered from the configured providers file or URL, by name.
+    source_providers: Mutex<BTreeSet<String>>,
+    /// Client the HTTP-based transports share, also used to fetch `providers_url`.
+    http_client: SharedClient,
+}
+
+/// What [`UtcpClient::refresh_providers_from_source`] changed, by provider name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProvidersRefresh {
+    /// Providers new to the source, now registered.
+    pub added: Vec<String>,
+    /// Providers whose definition or inline tools changed, registered or saved again.
+    pub updated: Vec<String>,
+    /// Providers no longer in the source, now deregistered.
+    pub removed: Vec<String>,
+    /// Providers whose definition and inline tools match what is registered, left as they are.
+    pub unchanged: Vec<String>,
+    /// Providers that could not be registered; the errors are logged.
+    pub failed: Vec<String>,
 }
 
 /// ResolvedTool represents a tool that has been resolved to a specific provider and protocol.
@@ -98,6 +311,81 @@ struct ResolvedTool {
     provider: Arc<dyn Provider>,
     protocol: Arc<dyn CommunicationProtocol>,
     call_name: String,
+    /// Fully qualified `provider.tool` name used as the key for usage statistics.
+    tool_name: String,
+}
+
+/// Stream wrapper that reports usage statistics to the repository once the stream finishes.
+/// Streams closed or dropped before their end are recorded as cancelled.
+struct RecordingStream {
+    inner: Box<dyn StreamResult>,
+    repo: Arc<dyn ToolRepository>,
+    tool_name: String,
+    started: Instant,
+    response_size: usize,
+    recorded: bool,
+}
+
+impl RecordingStream {
+    async fn record(&mut self, outcome: ToolCallOutcome) {
+        if self.recorded {
+            return;
+        }
+        self.recorded = true;
+        let _ = self
+            .repo
+            .record_call(&self.tool_name, outcome, self.started.elapsed())
+            .await;
+    }
+}
+
+#[async_trait]
+impl StreamResult for RecordingStream {
+    async fn next(&mut self) -> Result<Option<serde_json::Value>> {
+        match self.inner.next().await {
+            Ok(Some(item)) => {
+                self.response_size += serde_json::to_vec(&item).map(|b| b.len()).unwrap_or(0);
+                Ok(Some(item))
+            }
+            Ok(None) => {
+                self.record(ToolCallOutcome::Success {
+                    response_size: Some(self.response_size),
+                })
+                .await;
+                Ok(None)
+            }
+            Err(e) => {
+                self.record(ToolCallOutcome::Error).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        // Streams that already ended were recorded by `next`
+        self.record(ToolCallOutcome::Cancelled).await;
+        self.inner.close().await
+    }
+}
+
+impl Drop for RecordingStream {
+    fn drop(&mut self) {
+        if self.recorded {
+            return;
+        }
+        self.recorded = true;
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let repo = self.repo.clone();
+        let tool_name = std::mem::take(&mut self.tool_name);
+        let duration = self.started.elapsed();
+        handle.spawn(async move {
+            let _ = repo
+                .record_call(&tool_name, ToolCallOutcome::Cancelled, duration)
+                .await;
+        });
+    }
 }
 
 impl UtcpClient {
@@ -116,7 +404,27 @@ impl UtcpClient {
         repo: Arc<dyn ToolRepository>,
         strat: Arc<dyn ToolSearchStrategy>,
     ) -> Result<Self> {
-        let communication_protocols = communication_protocols_snapshot();
+        let mut communication_protocols = communication_protocols_snapshot();
+        let http_client = match &config.http_client {
+            Some(http_config) => {
+                let shared = SharedClient::new(http_config.clone())?;
+                communication_protocols = communication_protocols.with_http_client(shared.clone());
+                shared
+            }
+            None => SharedClient::default(),
+        };
+        let repository_events = repo.subscribe().map(Mutex::new);
+
+        let (refreshed, refreshed_providers) = mpsc::unbounded_channel();
+        let on_tools_changed = tools_changed_callback(
+            communication_protocols.clone(),
+            repo.clone(),
+            strat.clone(),
+            refreshed,
+        );
+        for protocol in communication_protocols.as_map().values() {
+            protocol.on_tools_changed(&on_tools_changed);
+        }
 
         let client = Self {
             config,
@@ -125,29 +433,25 @@ impl UtcpClient {
             search_strategy: strat,
             provider_tools_cache: RwLock::new(HashMap::new()),
             resolved_tools_cache: RwLock::new(HashMap::new()),
+            repository_events,
+            refreshed_providers: Mutex::new(refreshed_providers),
+            _on_tools_changed: on_tools_changed,
+            source_providers: Mutex::new(BTreeSet::new()),
+            http_client,
         };
 
-        // Load providers if file path is specified
-        if let Some(providers_path) = &client.config.providers_file_path {
-            let providers =
-                crate::loader::load_providers_with_tools_from_file(providers_path, &client.config)
-                    .await?;
-
+        // Load providers if a providers file or URL is specified
+        if let Some(providers) = client.load_source_providers().await? {
+            let mut registered = client.source_providers.lock().await;
             for loaded in providers {
-                let result = if let Some(tools) = loaded.tools {
-                    client
-                        .register_tool_provider_with_tools(loaded.provider.clone(), tools)
-                        .await
-                } else {
-                    client.register_tool_provider(loaded.provider.clone()).await
-                };
-
-                match result {
+                let name = loaded.provider.name();
+                match client.register_loaded(loaded).await {
                     Ok(tools) => {
-                        println!("✓ Loaded provider with {} tools", tools.len());
+                        tracing::info!(provider = %name, tools = tools.len(), "Loaded provider");
+                        registered.insert(name);
                     }
                     Err(e) => {
-                        eprintln!("✗ Failed to load provider: {}", e);
+                        tracing::warn!(provider = %name, error = %e, "Failed to load provider");
                     }
                 }
             }
@@ -156,6 +460,128 @@ impl UtcpClient {
         Ok(client)
     }
 
+    /// Load the providers file or URL again and apply the differences: providers new to it are
+    /// registered, providers whose definition changed are registered again, changed inline tools
+    /// are saved in place, and providers it no longer lists are deregistered. Providers
+    /// registered directly, not through the source, are left alone. Fails without changing
+    /// anything when the source cannot be loaded or none is configured.
+    pub async fn refresh_providers_from_source(&self) -> Result<ProvidersRefresh> {
+        // Held throughout so refreshes apply one at a time
+        let mut registered = self.source_providers.lock().await;
+        let loaded = self.load_source_providers().await?.ok_or_else(|| {
+            UtcpError::Config("No providers file or URL is configured".to_string())
+        })?;
+
+        let mut refresh = ProvidersRefresh::default();
+        let mut listed = BTreeSet::new();
+        for loaded in loaded {
+            let name = loaded.provider.name();
+            listed.insert(name.clone());
+            let known = registered.contains(&name);
+            // The same definition keeps its registration; only inline tools may need saving
+            if known && stored_matches(&self.tool_repository, loaded.provider.as_ref(), None).await
+            {
+                let saved = match loaded.tools {
+                    Some(tools) => {
+                        let tools = normalize_tool_names(&name, tools);
+                        save_provider_tools(
+                            &self.tool_repository,
+                            &self.search_strategy,
+                            loaded.provider,
+                            tools,
+                        )
+                        .await
+                    }
+                    None => Ok(false),
+                };
+                match saved {
+                    Ok(true) => {
+                        self.reload_provider(&name).await;
+                        refresh.updated.push(name);
+                    }
+                    Ok(false) => refresh.unchanged.push(name),
+                    Err(e) => {
+                        tracing::warn!(provider = %name, error = %e, "Failed to save provider tools");
+                        refresh.failed.push(name);
+                    }
+                }
+                continue;
+            }
+            if known {
+                if let Err(e) = self.deregister_tool_provider(&name).await {
+                    tracing::warn!(provider = %name, error = %e, "Failed to deregister provider");
+                }
+                registered.remove(&name);
+            }
+            match self.register_loaded(loaded).await {
+                Ok(_) => {
+                    registered.insert(name.clone());
+                    if known {
+                        refresh.updated.push(name);
+                    } else {
+                        refresh.added.push(name);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(provider = %name, error = %e, "Failed to register provider");
+                    refresh.failed.push(name);
+                }
+            }
+        }
+
+        let stale: Vec<String> = registered.difference(&listed).cloned().collect();
+        for name in stale {
+            if let Err(e) = self.deregister_tool_provider(&name).await {
+                tracing::warn!(provider = %name, error = %e, "Failed to deregister provider");
+            }
+            registered.remove(&name);
+            refresh.removed.push(name);
+        }
+        Ok(refresh)
+    }
+
+    /// The providers of the configured providers file or URL, or `None` when neither is set.
+    async fn load_source_providers(&self) -> Result<Option<Vec<crate::loader::LoadedProvider>>> {
+        match (&self.config.providers_file_path, &self.config.providers_url) {
+            (Some(_), Some(_)) => Err(UtcpError::Config(
+                "Set either providers_file_path or providers_url, not both".to_string(),
+            )
+            .into()),
+            (Some(path), None) => {
+                crate::loader::load_providers_with_tools_from_file(path, &self.config)
+                    .await
+                    .map(Some)
+            }
+            (None, Some(url)) => crate::loader::load_providers_with_tools_from_url(
+                url,
+                &self.config.providers_url_headers,
+                &self.http_client,
+                &self.config,
+            )
+            .await
+            .map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    async fn register_loaded(&self, loaded: crate::loader::LoadedProvider) -> Result<Vec<Tool>> {
+        match loaded.tools {
+            Some(tools) => {
+                self.register_tool_provider_with_tools(loaded.provider, tools)
+                    .await
+            }
+            None => self.register_tool_provider(loaded.provider).await,
+        }
+    }
+
+    /// Write the current provider and tool catalog to `path` as a JSON `RepositorySnapshot`.
+    pub async fn export_catalog(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let snapshot = self.tool_repository.export_snapshot().await?;
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        tokio::fs::write(path, json).await?;
+        Ok(())
+    }
+
     /// Determines the correct call name for a tool based on its provider type.
     fn call_name_for_provider(tool_name: &str, provider_type: &ProviderType) -> String {
         match provider_type {
@@ -185,9 +611,91 @@ impl UtcpClient {
         Ok(())
     }
 
+    /// Drops cached tools and resolutions belonging to a provider.
+    async fn forget_provider(&self, provider_name: &str) {
+        {
+            let mut cache = self.provider_tools_cache.write().await;
+            cache.remove(provider_name);
+        }
+        {
+            let prefix = format!("{}.", provider_name);
+            let mut resolved = self.resolved_tools_cache.write().await;
+            resolved.retain(|_, entry| !entry.tool_name.starts_with(&prefix));
+        }
+    }
+
+    /// Replaces a provider's cached tools with the ones saved to the repository by a refresh.
+    async fn reload_provider(&self, provider_name: &str) {
+        let Ok(tools) = self
+            .tool_repository
+            .get_tools_by_provider(provider_name)
+            .await
+        else {
+            return;
+        };
+        self.forget_provider(provider_name).await;
+        let mut cache = self.provider_tools_cache.write().await;
+        cache.insert(provider_name.to_string(), tools);
+    }
+
+    /// Applies refreshed providers and pending repository change events (removals and
+    /// evictions) to the local caches.
+    async fn sync_repository_events(&self) {
+        let refreshed: Vec<String> = {
+            let mut rx = self.refreshed_providers.lock().await;
+            std::iter::from_fn(|| rx.try_recv().ok()).collect()
+        };
+        for name in refreshed {
+            self.reload_provider(&name).await;
+        }
+
+        let Some(events) = &self.repository_events else {
+            return;
+        };
+
+        let mut stale = Vec::new();
+        let mut lagged = false;
+        {
+            let mut rx = events.lock().await;
+            loop {
+                match rx.try_recv() {
+                    Ok(RepositoryEvent::ProviderRemoved { provider_name })
+                    | Ok(RepositoryEvent::ProviderEvicted { provider_name }) => {
+                        stale.push(provider_name)
+                    }
+                    Ok(RepositoryEvent::ProviderSaved { .. }) => {}
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => lagged = true,
+                    Err(_) => break,
+                }
+            }
+        }
+
+        if lagged {
+            // Missed events: re-check every cached provider against the repository.
+            let cached: Vec<String> = self
+                .provider_tools_cache
+                .read()
+                .await
+                .keys()
+                .cloned()
+                .collect();
+            for name in cached {
+                if let Ok(None) = self.tool_repository.get_provider(&name).await {
+                    stale.push(name);
+                }
+            }
+        }
+
+        for name in stale {
+            self.forget_provider(&name).await;
+        }
+    }
+
     /// Resolves a tool name to a `ResolvedTool` containing the provider and protocol.
     /// Handles both fully qualified names (provider.tool) and bare names.
     async fn resolve_tool(&self, tool_name: &str) -> Result<ResolvedTool> {
+        self.sync_repository_events().await;
+
         {
             let cache = self.resolved_tools_cache.read().await;
             if let Some(resolved) = cache.get(tool_name) {
@@ -225,6 +733,7 @@ impl UtcpClient {
                 provider: prov.clone(),
                 protocol: protocol.clone(),
                 call_name,
+                tool_name: tool_name.to_string(),
             };
 
             let mut cache = self.resolved_tools_cache.write().await;
@@ -267,6 +776,7 @@ impl UtcpClient {
                         provider: prov.clone(),
                         protocol: protocol.clone(),
                         call_name,
+                        tool_name: full_name.clone(),
                     };
 
                     let mut rcache = self.resolved_tools_cache.write().await;
@@ -296,6 +806,8 @@ impl UtcpClientInterface for UtcpClient {
         let provider_name = prov.name();
         let provider_type = prov.type_();
 
+        self.sync_repository_events().await;
+
         // Check cache first
         {
             let cache = self.provider_tools_cache.read().await;
@@ -318,44 +830,67 @@ impl UtcpClientInterface for UtcpClient {
             .clone();
 
         // Register with protocol
+        let mut manual_failures = None;
         let tools = if !tools_override.is_empty() {
             tools_override
         } else if provider_type == ProviderType::Http {
             if let Some(http_prov) = prov.as_any().downcast_ref::<HttpProvider>() {
-                match OpenApiConverter::new_from_url(&http_prov.url, Some(provider_name.clone()))
+                let filters = http_prov.openapi_filters.clone().unwrap_or_default();
+                if let Some(spec_path) = openapi::file_url_path(&http_prov.url) {
+                    // Local specs are converted directly; there is no endpoint to fall back to.
+                    OpenApiConverter::new_from_file(&spec_path, Some(provider_name.clone()))
+                        .await?
+                        .with_options(filters)?
+                        .convert()
+                        .tools
+                } else {
+                    match OpenApiConverter::new_from_url(
+                        &http_prov.url,
+                        Some(provider_name.clone()),
+                    )
                     .await
-                {
-                    Ok(converter) => {
-                        let manual = converter.convert();
-                        if manual.tools.is_empty() {
-                            protocol.register_tool_provider(prov.as_ref()).await?
-                        } else {
-                            manual.tools
+                    {
+                        Ok(converter) => {
+                            let manual = converter.with_options(filters)?.convert();
+                            if manual.tools.is_empty() {
+                                protocol.register_tool_provider(prov.as_ref()).await?
+                            } else {
+                                manual.tools
+                            }
                         }
+                        Err(_) => protocol.register_tool_provider(prov.as_ref()).await?,
                     }
-                    Err(_) => protocol.register_tool_provider(prov.as_ref()).await?,
                 }
             } else {
                 protocol.register_tool_provider(prov.as_ref()).await?
             }
         } else {
-            protocol.register_tool_provider(prov.as_ref()).await?
+            match protocol.register_tool_provider(prov.as_ref()).await {
+                Ok(tools) => tools,
+                // Register the manuals that loaded, then report the ones that did not
+                Err(err) => match err.downcast_ref::<UtcpError>() {
+                    Some(UtcpError::ManualErrors { failures, tools }) if !tools.is_empty() => {
+                        manual_failures = Some(failures.clone());
+                        tools.clone()
+                    }
+                    _ => return Err(err),
+                },
+            }
         };
 
-        // Normalize tool names (prefix with provider name)
-        let mut normalized_tools = Vec::new();
-        for mut tool in tools {
-            if !tool.name.starts_with(&format!("{}.", provider_name)) {
-                tool.name = format!("{}.{}", provider_name, tool.name.trim_start_matches('.'));
-            }
-            normalized_tools.push(tool);
-        }
+        let normalized_tools = normalize_tool_names(&provider_name, tools);
 
         // Save to repository
         self.tool_repository
             .save_provider_with_tools(prov.clone(), normalized_tools.clone())
             .await?;
 
+        // Indexing is an optimization; a failure must not undo a successful registration.
+        let _ = self
+            .search_strategy
+            .index_provider(&provider_name, &normalized_tools)
+            .await;
+
         // Update cache
         {
             let mut cache = self.provider_tools_cache.write().await;
@@ -370,6 +905,7 @@ impl UtcpClientInterface for UtcpClient {
                     provider: prov.clone(),
                     protocol: protocol.clone(),
                     call_name,
+                    tool_name: tool.name.clone(),
                 };
 
                 // Full name
@@ -382,6 +918,13 @@ impl UtcpClientInterface for UtcpClient {
             }
         }
 
+        if let Some(failures) = manual_failures {
+            return Err(UtcpError::ManualErrors {
+                failures,
+                tools: normalized_tools,
+            }
+            .into());
+        }
         Ok(normalized_tools)
     }
 
@@ -411,16 +954,10 @@ impl UtcpClientInterface for UtcpClient {
 
         // Remove from repository
         self.tool_repository.remove_provider(provider_name).await?;
+        let _ = self.search_strategy.remove_provider(provider_name).await;
 
         // Clear cache
-        {
-            let mut cache = self.provider_tools_cache.write().await;
-            cache.remove(provider_name);
-        }
-        {
-            let mut resolved = self.resolved_tools_cache.write().await;
-            resolved.retain(|tool_name, _| !tool_name.starts_with(&format!("{}.", provider_name)));
-        }
+        self.forget_provider(provider_name).await;
 
         Ok(())
     }
@@ -435,16 +972,45 @@ impl UtcpClientInterface for UtcpClient {
         // Validate protocol is allowed by the provider
         Self::validate_allowed_protocol(&resolved, tool_name)?;
 
-        resolved
+        let started = Instant::now();
+        let result = resolved
             .protocol
             .call_tool(&resolved.call_name, args, resolved.provider.as_ref())
-            .await
+            .await;
+
+        let outcome = match &result {
+            Ok(value) => ToolCallOutcome::Success {
+                response_size: serde_json::to_vec(value).ok().map(|b| b.len()),
+            },
+            Err(_) => ToolCallOutcome::Error,
+        };
+        // Usage tracking is best-effort and must never fail the call itself.
+        let _ = self
+            .tool_repository
+            .record_call(&resolved.tool_name, outcome, started.elapsed())
+            .await;
+
+        result
     }
 
     async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
         self.search_strategy.search_tools(query, limit).await
     }
 
+    async fn search_tools_with(&self, query: &str, opts: SearchOptions) -> Result<Vec<ScoredTool>> {
+        self.search_strategy.search_tools_with(query, opts).await
+    }
+
+    async fn list_tools(&self) -> Result<Vec<Tool>> {
+        let mut tools = self.tool_repository.get_tools().await?;
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tools)
+    }
+
+    async fn resolve_tool_name(&self, tool_name: &str) -> Result<String> {
+        Ok(self.resolve_tool(tool_name).await?.tool_name)
+    }
+
     fn get_transports(&self) -> HashMap<String, Arc<dyn CommunicationProtocol>> {
         self.communication_protocols.as_map()
     }
@@ -459,9 +1025,31 @@ impl UtcpClientInterface for UtcpClient {
         // Validate protocol is allowed by the provider
         Self::validate_allowed_protocol(&resolved, tool_name)?;
 
-        resolved
+        let started = Instant::now();
+        match resolved
             .protocol
             .call_tool_stream(&resolved.call_name, args, resolved.provider.as_ref())
             .await
+        {
+            Ok(inner) => Ok(Box::new(RecordingStream {
+                inner,
+                repo: self.tool_repository.clone(),
+                tool_name: resolved.tool_name,
+                started,
+                response_size: 0,
+                recorded: false,
+            })),
+            Err(e) => {
+                let _ = self
+                    .tool_repository
+                    .record_call(
+                        &resolved.tool_name,
+                        ToolCallOutcome::Error,
+                        started.elapsed(),
+                    )
+                    .await;
+                Err(e)
+            }
+        }
     }
 }
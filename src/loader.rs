@@ -1,13 +1,16 @@
-// Provider loading from JSON files
+// Provider loading from JSON and YAML files
 use anyhow::{anyhow, Result};
-use serde_json::Value;
-use std::path::Path;
+use futures::future::BoxFuture;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::call_templates;
 use crate::config::UtcpClientConfig;
 use crate::migration::{migrate_v01_config, validate_v1_config, validate_v1_manual};
-use crate::providers::base::Provider;
+use crate::providers::base::{BaseProvider, Provider};
 use crate::providers::cli::CliProvider;
 use crate::providers::graphql::GraphqlProvider;
 use crate::providers::grpc::GrpcProvider;
@@ -20,14 +23,20 @@ use crate::providers::text::TextProvider;
 use crate::providers::udp::UdpProvider;
 use crate::providers::webrtc::WebRtcProvider;
 use crate::providers::websocket::WebSocketProvider;
+use crate::repository::namespaced::NamespacedProvider;
 use crate::spec::ManualV1;
+use crate::transports::http_client::SharedClient;
+use crate::transports::text::manuals::expand;
 
-/// Parse a providers JSON file
+/// Parse a providers file, written in JSON or YAML (see [`parse_document`])
 /// Supports multiple formats:
 /// - Array: [{"provider_type": "http", ...}, ...]
 /// - Object with providers array: {"providers": [{...}, ...]}
 /// - Object with single provider: {"providers": {...}}
 /// - Single provider: {"provider_type": "http", ...}
+///
+/// An object root may also list other providers files in `include` and shared provider
+/// fields in `defaults` (see [`load_providers_with_tools_from_file`]).
 pub async fn load_providers_from_file(
     path: impl AsRef<Path>,
     config: &UtcpClientConfig,
@@ -49,45 +58,80 @@ pub struct LoadedProvider {
 }
 
 /// Load providers or manuals (v0.1 or v1.0), returning providers and any embedded tools.
+///
+/// A providers file whose root is an object may set two directives:
+/// - `include`: a path or list of paths to other providers files, relative to the including
+///   file. An entry may name a directory, for its `.json`, `.yaml` and `.yml` files, or be a
+///   glob such as `providers.d/*.json`. Included providers follow the file's own, and a file
+///   that includes itself, directly or through others, is an error.
+/// - `defaults`: fields copied into each provider of the file and of the files it includes
+///   that does not set them, such as `auth`, `headers`, `timeouts` or
+///   `allowed_communication_protocols`. `headers` are combined, with the provider's own
+///   headers winning. An included file's defaults apply before the including file's.
+///
+/// Defaults are merged into the provider JSON before it is read, so they work for every
+/// provider type. Manuals with `tools` cannot be included.
 pub async fn load_providers_with_tools_from_file(
     path: impl AsRef<Path>,
     config: &UtcpClientConfig,
 ) -> Result<Vec<LoadedProvider>> {
-    let contents = tokio::fs::read_to_string(path).await?;
-    let json_raw: Value = serde_json::from_str(&contents)?;
-    // Apply v0.1 -> v1.0 migration for configs if needed
-    let json = migrate_v01_config(&json_raw);
+    let path = path.as_ref();
+    let json = read_providers_document(path).await?;
+    if json.get("tools").is_some() {
+        return load_manual(json, config);
+    }
 
-    // Validate v1.0 shapes when applicable
-    if let Some(obj) = json.as_object() {
-        if obj.contains_key("manual_call_templates") {
-            validate_v1_config(&json)?;
-        }
-        if obj.contains_key("tools") {
-            validate_v1_manual(&json)?;
-        }
+    let chain = vec![canonical_path(path)?];
+    let provider_values = resolve_providers(path, json, chain).await?;
+    create_providers(provider_values, config)
+}
+
+/// Fetch a providers document or manual from an http(s) URL through `client` and load it like a
+/// file, sending `headers` (after `${VAR}` substitution) with the request. The response is read as YAML when
+/// the URL path ends in `.yaml` or `.yml`, as JSON when it ends in `.json`, and otherwise as
+/// JSON, then YAML. `defaults` apply as in a file; `include` is not supported.
+pub async fn load_providers_with_tools_from_url(
+    url: &str,
+    headers: &HashMap<String, String>,
+    client: &SharedClient,
+    config: &UtcpClientConfig,
+) -> Result<Vec<LoadedProvider>> {
+    let json = fetch_providers_document(url, headers, client, config).await?;
+    if json.get("tools").is_some() {
+        return load_manual(json, config);
     }
 
-    // If this is a manual with tools, collect tools per provider
-    if let Some(obj) = json.as_object() {
-        if obj.get("tools").is_some() {
-            let _manual: ManualV1 = serde_json::from_value(json.clone())
-                .map_err(|e| anyhow!("Invalid v1.0 manual: {}", e))?;
-
-            let (providers, tools) = parse_manual_tools_with_providers(json.clone(), config)?;
-            return Ok(providers
-                .into_iter()
-                .zip(tools.into_iter())
-                .map(|(provider, tools)| LoadedProvider {
-                    provider,
-                    tools: Some(tools),
-                })
-                .collect());
-        }
+    let (mut provider_values, include, defaults) = split_directives(json)?;
+    if include.is_some() {
+        return Err(anyhow!(
+            "'include' is not supported in providers fetched from {}",
+            url
+        ));
     }
+    apply_defaults_from(&mut provider_values, defaults, url)?;
+    create_providers(provider_values, config)
+}
 
-    let provider_values = parse_providers_json(json)?;
+/// Collect the providers of a manual with tools, along with their tools.
+fn load_manual(json: Value, config: &UtcpClientConfig) -> Result<Vec<LoadedProvider>> {
+    let _manual: ManualV1 =
+        serde_json::from_value(json.clone()).map_err(|e| anyhow!("Invalid v1.0 manual: {}", e))?;
 
+    let (providers, tools) = parse_manual_tools_with_providers(json, config)?;
+    Ok(providers
+        .into_iter()
+        .zip(tools)
+        .map(|(provider, tools)| LoadedProvider {
+            provider,
+            tools: Some(tools),
+        })
+        .collect())
+}
+
+fn create_providers(
+    provider_values: Vec<Value>,
+    config: &UtcpClientConfig,
+) -> Result<Vec<LoadedProvider>> {
     let mut providers = Vec::new();
     for (index, mut provider_value) in provider_values.into_iter().enumerate() {
         // Perform variable substitution
@@ -104,6 +148,228 @@ pub async fn load_providers_with_tools_from_file(
     Ok(providers)
 }
 
+/// Read, parse and migrate a providers file.
+async fn read_providers_document(path: &Path) -> Result<Value> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow!("Failed to read providers file {}: {}", path.display(), e))?;
+    let json_raw = parse_document(path, &contents)
+        .map_err(|e| anyhow!("Failed to parse providers file {}: {}", path.display(), e))?;
+    prepare_document(&json_raw)
+}
+
+/// Fetch a providers document from `url`, failing on transport errors and non-2xx statuses.
+async fn fetch_providers_document(
+    url: &str,
+    headers: &HashMap<String, String>,
+    client: &SharedClient,
+    config: &UtcpClientConfig,
+) -> Result<Value> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid providers URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(anyhow!("Providers URL {} must use http or https", url));
+    }
+
+    let mut request = client.client().get(url);
+    if let Some(ms) = client.config().request_timeout_ms {
+        request = request.timeout(Duration::from_millis(ms));
+    }
+    for (name, value) in headers {
+        let mut value = Value::String(value.clone());
+        substitute_variables(&mut value, config);
+        request = request.header(name, value.as_str().unwrap_or_default());
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch providers from {}: {}", url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Failed to fetch providers from {}: HTTP {}",
+            url,
+            status
+        ));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch providers from {}: {}", url, e))?;
+
+    let json_raw = parse_document(Path::new(parsed.path()), &body)
+        .map_err(|e| anyhow!("Failed to parse providers from {}: {}", url, e))?;
+    prepare_document(&json_raw)
+}
+
+/// Migrate a parsed providers document, validating v1.0 configs and manuals.
+fn prepare_document(json_raw: &Value) -> Result<Value> {
+    // Apply v0.1 -> v1.0 migration for configs if needed
+    let json = migrate_v01_config(json_raw);
+
+    // Validate v1.0 shapes when applicable
+    if let Some(obj) = json.as_object() {
+        if obj.contains_key("manual_call_templates") {
+            validate_v1_config(&json)?;
+        }
+        if obj.contains_key("tools") {
+            validate_v1_manual(&json)?;
+        }
+    }
+    Ok(json)
+}
+
+fn canonical_path(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+        .map_err(|e| anyhow!("Failed to read providers file {}: {}", path.display(), e))
+}
+
+/// The provider objects of the providers document `json`, read from `path`, followed by those
+/// of the files it includes, with its defaults applied. `chain` holds the files being
+/// included, outermost first, to detect cycles.
+fn resolve_providers(
+    path: &Path,
+    json: Value,
+    chain: Vec<PathBuf>,
+) -> BoxFuture<'_, Result<Vec<Value>>> {
+    Box::pin(async move {
+        let (mut providers, include, defaults) = split_directives(json)?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in include_entries(include)? {
+            let files = expand(&base.join(&entry))?;
+            if files.is_empty() {
+                return Err(anyhow!(
+                    "Include '{}' in {} matches no files",
+                    entry,
+                    path.display()
+                ));
+            }
+            for file in files {
+                let canonical = canonical_path(&file)?;
+                if chain.contains(&canonical) {
+                    let cycle: Vec<_> = chain
+                        .iter()
+                        .chain([&canonical])
+                        .map(|p| p.display().to_string())
+                        .collect();
+                    return Err(anyhow!("Include cycle: {}", cycle.join(" -> ")));
+                }
+                let included = read_providers_document(&file).await?;
+                if included.get("tools").is_some() {
+                    return Err(anyhow!(
+                        "Cannot include {}: it is a manual, not a providers file",
+                        file.display()
+                    ));
+                }
+                let mut chain = chain.clone();
+                chain.push(canonical);
+                providers.extend(resolve_providers(&file, included, chain).await?);
+            }
+        }
+
+        apply_defaults_from(&mut providers, defaults, &path.display().to_string())?;
+        Ok(providers)
+    })
+}
+
+/// A providers document's own provider objects, and its `include` and `defaults` directives.
+fn split_directives(mut json: Value) -> Result<(Vec<Value>, Option<Value>, Option<Value>)> {
+    let (include, defaults) = match json.as_object_mut() {
+        Some(obj) => (obj.remove("include"), obj.remove("defaults")),
+        None => (None, None),
+    };
+    // A document holding only directives has no providers of its own
+    let directives_only = (include.is_some() || defaults.is_some())
+        && json.as_object().is_some_and(|obj| obj.is_empty());
+    let providers = if directives_only {
+        Vec::new()
+    } else {
+        parse_providers_json(json)?
+    };
+    Ok((providers, include, defaults))
+}
+
+/// Apply the `defaults` directive of the document read from `source` to its providers.
+fn apply_defaults_from(
+    providers: &mut [Value],
+    defaults: Option<Value>,
+    source: &str,
+) -> Result<()> {
+    let Some(defaults) = defaults else {
+        return Ok(());
+    };
+    let defaults = defaults
+        .as_object()
+        .ok_or_else(|| anyhow!("'defaults' in {} must be an object", source))?;
+    for provider in providers {
+        apply_defaults(provider, defaults);
+    }
+    Ok(())
+}
+
+/// The paths an `include` directive lists: one path or an array of them.
+fn include_entries(include: Option<Value>) -> Result<Vec<String>> {
+    match include {
+        None => Ok(Vec::new()),
+        Some(Value::String(path)) => Ok(vec![path]),
+        Some(Value::Array(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => Ok(path),
+                _ => Err(anyhow!("'include' entries must be strings")),
+            })
+            .collect(),
+        Some(_) => Err(anyhow!("'include' must be a path or an array of paths")),
+    }
+}
+
+/// Copy the fields of `defaults` that `provider` does not set into it. `headers` are
+/// combined, keeping the provider's value for a header both set.
+fn apply_defaults(provider: &mut Value, defaults: &Map<String, Value>) {
+    let Some(obj) = provider.as_object_mut() else {
+        return;
+    };
+    for (key, value) in defaults {
+        match (obj.get_mut(key), value) {
+            (None, _) => {
+                obj.insert(key.clone(), value.clone());
+            }
+            (Some(Value::Object(headers)), Value::Object(shared)) if key == "headers" => {
+                for (name, header) in shared {
+                    headers
+                        .entry(name.clone())
+                        .or_insert_with(|| header.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parse the contents of a JSON or YAML file into JSON. Files named `.json` are read as JSON
+/// and `.yaml` or `.yml` files as YAML; others are tried as JSON, then YAML. YAML errors give
+/// the line and column where parsing failed.
+pub(crate) fn parse_document(path: &Path, contents: &str) -> Result<Value> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("json") => serde_json::from_str(contents).map_err(|e| anyhow!("invalid JSON: {}", e)),
+        Some("yaml" | "yml") => parse_yaml(contents).map_err(|e| anyhow!("invalid YAML: {}", e)),
+        _ => match serde_json::from_str(contents) {
+            Ok(json) => Ok(json),
+            Err(_) => parse_yaml(contents).map_err(|e| anyhow!("not valid JSON or YAML: {}", e)),
+        },
+    }
+}
+
+fn parse_yaml(contents: &str) -> Result<Value> {
+    let yaml: serde_yaml::Value = serde_yaml::from_str(contents)?;
+    Ok(serde_json::to_value(yaml)?)
+}
+
 /// Parses the raw JSON value into a list of provider JSON objects.
 /// Handles various formats: array, object with "providers", object with "manual_call_templates", or single provider object.
 fn parse_providers_json(json: Value) -> Result<Vec<Value>> {
@@ -318,7 +584,10 @@ fn tool_to_provider(tool: &Value) -> Result<Option<Value>> {
 
 /// Creates a Provider instance from a JSON value.
 /// Handles type normalization and defaults.
-fn create_provider_from_value(mut value: Value, index: usize) -> Result<Arc<dyn Provider>> {
+pub(crate) fn create_provider_from_value(
+    mut value: Value,
+    index: usize,
+) -> Result<Arc<dyn Provider>> {
     // Normalize type field: accept both "type" and "provider_type"
     let provider_type = {
         let obj = value
@@ -427,6 +696,61 @@ fn create_provider_from_value(mut value: Value, index: usize) -> Result<Arc<dyn
     }
 }
 
+/// Serializes a Provider back into the type-tagged JSON shape accepted by
+/// `create_provider_from_value`.
+pub(crate) fn provider_to_value(prov: &dyn Provider) -> Result<Value> {
+    let any = prov.as_any();
+    // Namespaced repositories store a wrapper; serialize what it wraps under the stored name
+    if let Some(p) = any.downcast_ref::<NamespacedProvider>() {
+        let mut value = provider_to_value(p.inner.as_ref())?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("name".to_string(), Value::String(p.name.clone()));
+        }
+        return Ok(value);
+    }
+    let mut value = if let Some(p) = any.downcast_ref::<HttpProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<CliProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<SseProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<WebSocketProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<GrpcProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<GraphqlProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<TcpProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<UdpProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<StreamableHttpProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<McpProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<WebRtcProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<TextProvider>() {
+        serde_json::to_value(p)?
+    } else if let Some(p) = any.downcast_ref::<BaseProvider>() {
+        serde_json::to_value(p)?
+    } else {
+        return Err(anyhow!(
+            "Cannot serialize provider '{}': unsupported provider implementation",
+            prov.name()
+        ));
+    };
+
+    // The trait reports the authoritative type; keep the tag in sync with it.
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert(
+            "provider_type".to_string(),
+            Value::String(prov.type_().as_key().to_string()),
+        );
+    }
+    Ok(value)
+}
+
 /// Substitutes variables in the JSON value using the provided configuration.
 /// Replaces ${VAR} and $VAR with values from config or environment.
 fn substitute_variables(value: &mut Value, config: &UtcpClientConfig) {
@@ -544,6 +868,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_document_reads_yaml_and_reports_positions() {
+        let yaml = "providers:\n  - provider_type: cli\n    command_name: ls\n";
+        let value = parse_document(Path::new("providers.yaml"), yaml).unwrap();
+        assert_eq!(value["providers"][0]["command_name"], "ls");
+
+        // Without a known extension, JSON is tried before YAML
+        let value = parse_document(Path::new("providers"), r#"{"a": [1]}"#).unwrap();
+        assert_eq!(value, serde_json::json!({"a": [1]}));
+        let value = parse_document(Path::new("providers"), "a: b").unwrap();
+        assert_eq!(value, serde_json::json!({"a": "b"}));
+
+        let err = parse_document(Path::new("providers.yml"), "providers:\n  - name: [a\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid YAML"), "{}", err);
+        assert!(err.contains("line 3 column 1"), "{}", err);
+
+        let err = parse_document(Path::new("providers.json"), "a: b")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid JSON"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn load_yaml_providers_file_with_manual_call_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("providers.yaml");
+        std::fs::write(
+            &path,
+            r#"
+# Providers are described the same way as in JSON
+manual_call_templates:
+  - name: weather_service
+    call_template_type: http
+    url: http://example.com/${CITY}
+    http_method: GET
+  - name: catalog
+    call_template_type: text
+    base_path: /opt/catalog
+    path: manuals/*.yaml
+"#,
+        )
+        .unwrap();
+
+        let config = UtcpClientConfig::new().with_variable("CITY".into(), "paris".into());
+        let providers = load_providers_from_file(&path, &config).await.unwrap();
+        assert_eq!(providers.len(), 2);
+
+        let http = providers[0]
+            .as_any()
+            .downcast_ref::<HttpProvider>()
+            .unwrap();
+        assert_eq!(http.base.name, "weather_service");
+        assert_eq!(http.url, "http://example.com/paris");
+
+        let text = providers[1]
+            .as_any()
+            .downcast_ref::<TextProvider>()
+            .unwrap();
+        assert_eq!(text.base.name, "catalog");
+        assert_eq!(text.path.as_deref(), Some("manuals/*.yaml"));
+
+        std::fs::write(&path, "manual_call_templates:\n  - name: [broken\n").unwrap();
+        let err = load_providers_from_file(&path, &config)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("providers.yaml"), "{}", err);
+        assert!(err.contains("line "), "{}", err);
+    }
+
     #[tokio::test]
     async fn load_providers_supports_multiple_types() {
         let mut file = NamedTempFile::new().unwrap();
@@ -610,6 +1007,140 @@ mod tests {
             .starts_with(&loaded[0].provider.name()));
     }
 
+    fn api_key(provider: &Arc<dyn Provider>) -> Option<String> {
+        let http = provider.as_any().downcast_ref::<HttpProvider>().unwrap();
+        match http.base.auth.as_ref()? {
+            crate::auth::AuthConfig::ApiKey(auth) => Some(auth.api_key.clone()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn includes_and_defaults_are_merged_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("teams")).unwrap();
+        std::fs::write(
+            dir.path().join("providers.json"),
+            r#"{
+                "include": ["teams/*.json"],
+                "defaults": {
+                    "auth": {"auth_type": "api_key", "api_key": "${API_KEY}", "var_name": "X-Api-Key", "location": "header"},
+                    "headers": {"X-Team": "platform", "X-Trace": "on"},
+                    "allowed_communication_protocols": ["http"]
+                },
+                "providers": [
+                    {"provider_type": "http", "name": "root", "url": "http://example.com/root"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("teams/billing.json"),
+            r#"{
+                "include": "../shared/tools.yaml",
+                "defaults": {"headers": {"X-Team": "billing"}},
+                "providers": [
+                    {"provider_type": "http", "name": "invoices", "url": "http://example.com/invoices",
+                     "headers": {"X-Trace": "off"}},
+                    {"provider_type": "http", "name": "public", "url": "http://example.com/public",
+                     "auth": {"auth_type": "api_key", "api_key": "anon", "var_name": "X-Api-Key", "location": "header"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("shared")).unwrap();
+        std::fs::write(
+            dir.path().join("shared/tools.yaml"),
+            "providers:\n  - provider_type: cli\n    name: lint\n    command_name: lint\n",
+        )
+        .unwrap();
+
+        let config = UtcpClientConfig::new().with_variable("API_KEY".into(), "secret".into());
+        let providers = load_providers_from_file(dir.path().join("providers.json"), &config)
+            .await
+            .unwrap();
+        let names: Vec<_> = providers.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["root", "invoices", "public", "lint"]);
+
+        // Defaults fill what a provider leaves unset, nearer files first
+        assert_eq!(api_key(&providers[0]).as_deref(), Some("secret"));
+        assert_eq!(api_key(&providers[1]).as_deref(), Some("secret"));
+        assert_eq!(api_key(&providers[2]).as_deref(), Some("anon"));
+        let headers = |i: usize| {
+            let http = providers[i]
+                .as_any()
+                .downcast_ref::<HttpProvider>()
+                .unwrap();
+            let mut headers: Vec<_> = http.headers.clone().unwrap().into_iter().collect();
+            headers.sort();
+            headers
+        };
+        assert_eq!(
+            headers(1),
+            vec![
+                ("X-Team".to_string(), "billing".to_string()),
+                ("X-Trace".to_string(), "off".to_string())
+            ]
+        );
+        assert_eq!(
+            headers(0),
+            vec![
+                ("X-Team".to_string(), "platform".to_string()),
+                ("X-Trace".to_string(), "on".to_string())
+            ]
+        );
+
+        // Defaults reach every provider type
+        let cli = providers[3].as_any().downcast_ref::<CliProvider>().unwrap();
+        assert!(cli.base.auth.is_some());
+        assert_eq!(
+            cli.base.allowed_communication_protocols,
+            Some(vec!["http".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn include_errors_name_the_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let config = UtcpClientConfig::default();
+
+        let a = write("a.json", r#"{"include": "b.json"}"#);
+        write(
+            "b.json",
+            r#"{"include": ["c.json"], "providers": [{"provider_type": "cli", "command_name": "ls"}]}"#,
+        );
+        write("c.json", r#"{"include": "a.json"}"#);
+        let err = load_providers_from_file(&a, &config)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.starts_with("Include cycle: "), "{}", err);
+        assert!(err.contains("b.json -> "), "{}", err);
+        assert!(err.ends_with("a.json"), "{}", err);
+
+        let missing = write("missing.json", r#"{"include": ["nope/*.json"]}"#);
+        let err = load_providers_from_file(&missing, &config)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("'nope/*.json'"), "{}", err);
+
+        let bad = write("bad.json", r#"{"defaults": ["auth"], "providers": []}"#);
+        let err = load_providers_from_file(&bad, &config)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("'defaults'"), "{}", err);
+    }
+
     /// Regression test for issue #28:
     /// Multiple tools with the same call_template_type and no explicit name in their
     /// tool_call_template should each get a unique provider name derived from the tool's
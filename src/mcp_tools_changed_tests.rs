@@ -0,0 +1,109 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::mcp::McpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::repository::ToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// A stdio MCP server that gains a `reverse` tool, and announces it, once `unlock` is called.
+const SERVER: &str = r#"
+import json, sys
+
+def send(message):
+    print(json.dumps(message), flush=True)
+
+tools = [{"name": "unlock", "inputSchema": {"type": "object"}}]
+for line in sys.stdin:
+    message = json.loads(line)
+    if "id" not in message or "method" not in message:
+        continue
+    method = message["method"]
+    if method == "initialize":
+        result = {"protocolVersion": "2025-06-18", "capabilities": {"tools": {"listChanged": True}},
+                  "serverInfo": {"name": "vault", "version": "1.0"}}
+    elif method == "tools/list":
+        result = {"tools": tools}
+    elif message["params"]["name"] == "unlock":
+        tools.append({"name": "reverse", "inputSchema": {"type": "object"}})
+        send({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"})
+        result = {"content": [{"type": "text", "text": "unlocked"}]}
+    else:
+        text = message["params"]["arguments"]["text"]
+        result = {"content": [{"type": "text", "text": text[::-1]}]}
+    send({"jsonrpc": "2.0", "id": message["id"], "result": result})
+"#;
+
+#[tokio::test]
+async fn test_mcp_tools_list_changed_refreshes_provider() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("server.py");
+    std::fs::write(&script, SERVER).unwrap();
+    let provider = McpProvider::new_stdio(
+        "vault".to_string(),
+        "python3".to_string(),
+        Some(vec![script.to_string_lossy().into_owned()]),
+        None,
+    );
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+
+    let tools = client
+        .register_tool_provider(Arc::new(provider))
+        .await
+        .unwrap();
+    assert_eq!(tools.len(), 1);
+    assert!(client.call_tool("reverse", HashMap::new()).await.is_err());
+
+    let unlocked = client
+        .call_tool("vault.unlock", HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(unlocked, json!("unlocked"));
+
+    // The refresh runs in the background after the notification.
+    let args = HashMap::from([("text".to_string(), json!("stressed"))]);
+    let reversed = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(value) = client.call_tool("reverse", args.clone()).await {
+                break value;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("provider refreshed");
+    assert_eq!(reversed, json!("desserts"));
+
+    let names: Vec<String> = repo
+        .get_tools_by_provider("vault")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names, vec!["vault.unlock", "vault.reverse"]);
+
+    client.deregister_tool_provider("vault").await.unwrap();
+}
@@ -1,15 +1,45 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
 use reqwest::Url;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
 
 use crate::auth::{ApiKeyAuth, AuthConfig, AuthType, BasicAuth, OAuth2Auth};
 use crate::providers::base::{BaseProvider, ProviderType};
-use crate::providers::http::HttpProvider;
+use crate::providers::http::{HttpProvider, ParamStyle, QueryEncoding, QueryStyle};
+use crate::providers::http_stream::StreamableHttpProvider;
+use crate::providers::sse::{
+    SseDiscoveryMode, SseProvider, DEFAULT_IDLE_TIMEOUT_MS, DEFAULT_MAX_BUFFER_BYTES,
+    DEFAULT_MAX_EVENT_BYTES,
+};
+use crate::tag::pattern_search::glob_to_regex;
 use crate::tools::{Tool, ToolInputOutputSchema};
 
 pub const VERSION: &str = "1.0";
 
+/// Default limit on nested `$ref` resolution before a reference is left unresolved.
+pub const DEFAULT_MAX_REF_DEPTH: usize = 64;
+
+/// Request body media types sent as form fields rather than JSON.
+const FORM_CONTENT_TYPES: [&str; 2] = ["application/x-www-form-urlencoded", "multipart/form-data"];
+
+/// Schema keywords Swagger 2.0 allows directly on non-body parameters.
+const SWAGGER_PARAM_SCHEMA_KEYS: [&str; 7] = [
+    "format", "items", "enum", "default", "minimum", "maximum", "pattern",
+];
+
+/// Tag added to tools generated for streaming operations.
+const STREAMING_TAG: &str = "streaming";
+
+/// Most external documents a single conversion will load through `$ref`s.
+const MAX_EXTERNAL_DOCUMENTS: usize = 64;
+
+/// Most redirects followed while fetching a remote `$ref`.
+const MAX_REF_REDIRECTS: usize = 10;
+
 /// Representation of a generated UTCP manual derived from an OpenAPI spec.
 #[derive(Debug, Clone)]
 pub struct UtcpManual {
@@ -22,6 +52,131 @@ pub struct OpenApiConverter {
     spec: Value,
     spec_url: Option<String>,
     provider_name: String,
+    /// Location external refs in the root document are resolved against (spec URL or file path).
+    base_location: Option<String>,
+    /// External documents loaded by [`OpenApiConverter::load_external_refs`], keyed by location.
+    documents: HashMap<String, Value>,
+    /// Origins besides the spec's own that remote `$ref`s may be fetched from.
+    allowed_ref_origins: HashSet<String>,
+    max_ref_depth: usize,
+    filter: OperationFilter,
+    report: Mutex<ConversionReport>,
+}
+
+/// Problems encountered while converting a spec.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    /// Operations that were skipped.
+    pub errors: Vec<OperationError>,
+    /// Parts of the spec that were ignored or degraded, such as circular `$ref`s.
+    pub warnings: Vec<String>,
+}
+
+impl ConversionReport {
+    /// True when the conversion produced neither errors nor warnings.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+}
+
+/// An operation that could not be converted into a tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationError {
+    pub path: String,
+    pub method: String,
+    pub message: String,
+}
+
+/// Selects which operations of a spec become tools.
+///
+/// Exclusions take precedence over inclusions, and empty include lists match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConverterOptions {
+    /// Keep only operations carrying at least one of these tags.
+    pub include_tags: Vec<String>,
+    /// Drop operations carrying any of these tags.
+    pub exclude_tags: Vec<String>,
+    /// Keep only operations whose path matches one of these globs (e.g. `/users/*`).
+    pub include_paths: Vec<String>,
+    /// Drop operations whose path matches any of these globs.
+    pub exclude_paths: Vec<String>,
+    /// Keep only these HTTP methods (case-insensitive).
+    pub methods: Vec<String>,
+    /// Convert operations marked `deprecated: true`.
+    pub include_deprecated: bool,
+}
+
+/// [`ConverterOptions`] with the path globs compiled.
+#[derive(Default)]
+struct OperationFilter {
+    options: ConverterOptions,
+    include_paths: Vec<Regex>,
+    exclude_paths: Vec<Regex>,
+}
+
+impl OperationFilter {
+    fn new(options: ConverterOptions) -> Result<Self> {
+        let compile = |globs: &[String]| -> Result<Vec<Regex>> {
+            globs
+                .iter()
+                .map(|glob| {
+                    Regex::new(&glob_to_regex(glob)?)
+                        .map_err(|e| anyhow!("Invalid path glob '{}': {}", glob, e))
+                })
+                .collect()
+        };
+        Ok(Self {
+            include_paths: compile(&options.include_paths)?,
+            exclude_paths: compile(&options.exclude_paths)?,
+            options,
+        })
+    }
+
+    fn allows(&self, path: &str, method: &str, op: &Map<String, Value>) -> bool {
+        let options = &self.options;
+        let deprecated = op
+            .get("deprecated")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if deprecated && !options.include_deprecated {
+            return false;
+        }
+        if !options.methods.is_empty()
+            && !options
+                .methods
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(method))
+        {
+            return false;
+        }
+
+        if self.exclude_paths.iter().any(|re| re.is_match(path)) {
+            return false;
+        }
+        if !self.include_paths.is_empty() && !self.include_paths.iter().any(|re| re.is_match(path))
+        {
+            return false;
+        }
+
+        let tags: Vec<&str> = op
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        if options
+            .exclude_tags
+            .iter()
+            .any(|t| tags.contains(&t.as_str()))
+        {
+            return false;
+        }
+        options.include_tags.is_empty()
+            || options
+                .include_tags
+                .iter()
+                .any(|t| tags.contains(&t.as_str()))
+    }
 }
 
 impl OpenApiConverter {
@@ -37,39 +192,183 @@ impl OpenApiConverter {
 
         Self {
             spec: openapi_spec,
+            base_location: spec_url.clone(),
             spec_url,
             provider_name,
+            documents: HashMap::new(),
+            allowed_ref_origins: HashSet::new(),
+            max_ref_depth: DEFAULT_MAX_REF_DEPTH,
+            filter: OperationFilter::default(),
+            report: Mutex::new(ConversionReport::default()),
         }
     }
 
     /// Fetch and parse a remote OpenAPI document, inferring a provider name when missing.
+    /// External `$ref`s are resolved relative to the final URL.
     pub async fn new_from_url(spec_url: &str, provider_name: Option<String>) -> Result<Self> {
         let (spec, final_url) = load_spec_from_url(spec_url).await?;
-        Ok(Self::new(spec, Some(final_url), provider_name))
+        let mut converter = Self::new(spec, Some(final_url), provider_name);
+        converter.load_external_refs().await?;
+        Ok(converter)
+    }
+
+    /// Read an OpenAPI document (JSON or YAML) from disk. External `$ref`s are resolved
+    /// relative to the file's directory.
+    pub async fn new_from_file(
+        path: impl AsRef<Path>,
+        provider_name: Option<String>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("failed to read spec {}", path.display()))?;
+        let mut converter = Self::new(parse_spec(&bytes)?, None, provider_name);
+        converter.base_location = Some(path.to_string_lossy().into_owned());
+        converter.load_external_refs().await?;
+        Ok(converter)
+    }
+
+    /// Name used for the generated providers and tool prefixes.
+    pub fn provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    /// Restrict conversion to the operations selected by `options`. Fails if a path glob
+    /// is invalid.
+    pub fn with_options(mut self, options: ConverterOptions) -> Result<Self> {
+        self.filter = OperationFilter::new(options)?;
+        Ok(self)
+    }
+
+    /// Limit how many `$ref`s may be followed while resolving a single schema.
+    pub fn with_max_ref_depth(mut self, max_ref_depth: usize) -> Self {
+        self.max_ref_depth = max_ref_depth;
+        self
+    }
+
+    /// Allow remote `$ref`s to be fetched from these origins (e.g. `https://schemas.example.com`)
+    /// in addition to the spec's own. Fails if an entry is not an http(s) URL.
+    pub fn with_allowed_ref_origins<I, S>(mut self, origins: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for origin in origins {
+            let origin = origin.as_ref();
+            let normalized =
+                url_origin(origin).ok_or_else(|| anyhow!("invalid $ref origin '{}'", origin))?;
+            self.allowed_ref_origins.insert(normalized);
+        }
+        Ok(self)
+    }
+
+    /// Fetch every document reachable through relative-file or http(s) `$ref`s so they can be
+    /// resolved during conversion. Each document is loaded once, so reference cycles between
+    /// documents terminate.
+    ///
+    /// Remote documents are only fetched from the spec's own origin or one allowed with
+    /// [`Self::with_allowed_ref_origins`], and a remote document may not reference local
+    /// files. Loaded documents belong to this converter; nothing is shared between converters.
+    pub async fn load_external_refs(&mut self) -> Result<()> {
+        let mut allowed = self.allowed_ref_origins.clone();
+        allowed.extend(self.base_location.as_deref().and_then(url_origin));
+        let client = ref_client(allowed.clone())?;
+
+        let mut pending: Vec<(Option<String>, Value)> =
+            vec![(self.base_location.clone(), self.spec.clone())];
+        let mut seen: HashSet<String> = self.base_location.iter().cloned().collect();
+
+        while let Some((location, document)) = pending.pop() {
+            let mut references = Vec::new();
+            collect_refs(&document, &mut references);
+            for reference in references {
+                let Some((target, _)) = split_ref(&reference, location.as_deref()) else {
+                    continue;
+                };
+                if !seen.insert(target.clone()) {
+                    continue;
+                }
+                if is_remote(&target) {
+                    let origin = url_origin(&target).unwrap_or_default();
+                    if !allowed.contains(&origin) {
+                        return Err(anyhow!(
+                            "refusing to load $ref {}: origin '{}' is not the spec's origin or an allowed one",
+                            reference,
+                            origin
+                        ));
+                    }
+                } else if location.as_deref().is_some_and(is_remote) {
+                    return Err(anyhow!(
+                        "refusing to load $ref {}: remote documents may not reference local files",
+                        reference
+                    ));
+                }
+                if self.documents.len() >= MAX_EXTERNAL_DOCUMENTS {
+                    return Err(anyhow!(
+                        "spec references more than {} external documents",
+                        MAX_EXTERNAL_DOCUMENTS
+                    ));
+                }
+                let loaded = fetch_document(&client, &target)
+                    .await
+                    .with_context(|| format!("failed to load $ref {}", reference))?;
+                self.documents.insert(target.clone(), loaded.clone());
+                pending.push((Some(target), loaded));
+            }
+        }
+        Ok(())
     }
 
     /// Convert the OpenAPI document into a UTCP manual containing tools and metadata.
+    /// Operations that cannot be converted are skipped and listed in [`Self::report`].
     pub fn convert(&self) -> UtcpManual {
+        *self.report.lock().unwrap() = ConversionReport::default();
         let mut tools = Vec::new();
         let base_url = self.base_url();
 
-        if let Some(paths) = self.spec.get("paths").and_then(|v| v.as_object()) {
-            for (raw_path, raw_item) in paths {
-                if let Some(path_item) = raw_item.as_object() {
-                    for (method, raw_op) in path_item {
-                        let lower = method.to_ascii_lowercase();
-                        if !matches!(lower.as_str(), "get" | "post" | "put" | "delete" | "patch") {
-                            continue;
-                        }
+        let paths = match self.spec.get("paths") {
+            Some(Value::Object(paths)) => Some(paths),
+            None | Some(Value::Null) => None,
+            Some(_) => {
+                self.warn("`paths` is not an object; no operations were converted".to_string());
+                None
+            }
+        };
+        for (raw_path, raw_item) in paths.into_iter().flatten() {
+            let path_item = match raw_item.as_object() {
+                Some(path_item) => path_item,
+                None => {
+                    self.warn(format!("path item '{}' is not an object", raw_path));
+                    continue;
+                }
+            };
+            let path_base = server_url(path_item.get("servers"));
+            for (method, raw_op) in path_item {
+                let lower = method.to_ascii_lowercase();
+                if !matches!(lower.as_str(), "get" | "post" | "put" | "delete" | "patch") {
+                    continue;
+                }
 
-                        if let Some(op) = raw_op.as_object() {
-                            if let Ok(Some(tool)) =
-                                self.create_tool(raw_path, &lower, op, &base_url)
-                            {
-                                tools.push(tool);
-                            }
-                        }
+                let op = match raw_op.as_object() {
+                    Some(op) => op,
+                    None => {
+                        self.record_error(raw_path, &lower, "operation is not an object");
+                        continue;
                     }
+                };
+                if !self.filter.allows(raw_path, &lower, op) {
+                    continue;
+                }
+                // The most specific `servers` declaration wins.
+                let op_base = server_url(op.get("servers"));
+                let base = op_base
+                    .as_deref()
+                    .or(path_base.as_deref())
+                    .unwrap_or(&base_url);
+                match self.create_tool(raw_path, &lower, op, base) {
+                    Ok(Some(tool)) => tools.push(tool),
+                    Ok(None) => {}
+                    Err(err) => self.record_error(raw_path, &lower, &err.to_string()),
                 }
             }
         }
@@ -80,15 +379,29 @@ impl OpenApiConverter {
         }
     }
 
+    /// Problems found by the most recent [`Self::convert`] call.
+    pub fn report(&self) -> ConversionReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    fn warn(&self, message: String) {
+        let mut report = self.report.lock().unwrap();
+        if !report.warnings.contains(&message) {
+            report.warnings.push(message);
+        }
+    }
+
+    fn record_error(&self, path: &str, method: &str, message: &str) {
+        self.report.lock().unwrap().errors.push(OperationError {
+            path: path.to_string(),
+            method: method.to_string(),
+            message: message.to_string(),
+        });
+    }
+
     fn base_url(&self) -> String {
-        if let Some(servers) = self.spec.get("servers").and_then(|v| v.as_array()) {
-            if let Some(first) = servers.first().and_then(|v| v.as_object()) {
-                if let Some(url) = first.get("url").and_then(|v| v.as_str()) {
-                    if !url.is_empty() {
-                        return url.to_string();
-                    }
-                }
-            }
+        if let Some(url) = server_url(self.spec.get("servers")) {
+            return url;
         }
 
         if let Some(host) = self.spec.get("host").and_then(|v| v.as_str()) {
@@ -118,36 +431,103 @@ impl OpenApiConverter {
         "/".to_string()
     }
 
+    #[cfg(test)]
     fn resolve_ref(&self, reference: &str) -> Result<Value> {
-        if !reference.starts_with("#/") {
-            return Err(anyhow!("only local refs supported, got {}", reference));
-        }
-        let pointer = &reference[1..];
-        self.spec
-            .pointer(pointer)
-            .cloned()
-            .ok_or_else(|| anyhow!("ref {} not found", reference))
+        self.resolve_ref_in(reference, self.base_location.as_deref())
+            .map(|(value, _)| value)
+    }
+
+    /// Look up a reference made from the document at `location` (the root spec when `None`
+    /// or equal to the base location),
+    /// returning the target value and the location of the document it lives in.
+    fn resolve_ref_in(
+        &self,
+        reference: &str,
+        location: Option<&str>,
+    ) -> Result<(Value, Option<String>)> {
+        let (target, pointer) = match split_ref(reference, location) {
+            Some((target, pointer)) => (Some(target), pointer),
+            None => (
+                location.map(str::to_string),
+                reference.trim_start_matches('#').to_string(),
+            ),
+        };
+        let document = match target.as_deref() {
+            None => &self.spec,
+            Some(loc) if Some(loc) == self.base_location.as_deref() => &self.spec,
+            Some(loc) => self
+                .documents
+                .get(loc)
+                .ok_or_else(|| anyhow!("external ref {} was not loaded", reference))?,
+        };
+        let value = if pointer.is_empty() {
+            document.clone()
+        } else {
+            document
+                .pointer(&pointer)
+                .cloned()
+                .ok_or_else(|| anyhow!("ref {} not found", reference))?
+        };
+        Ok((value, target))
     }
 
     fn resolve_schema(&self, schema: Value) -> Value {
+        self.resolve_schema_in(schema, self.base_location.as_deref(), &mut Vec::new())
+    }
+
+    /// Resolve refs inside `schema`, which belongs to the document at `location`. `active`
+    /// holds the refs currently being expanded; a ref that is already active (a cycle), that
+    /// would exceed `max_ref_depth`, or that cannot be resolved degrades to an empty schema
+    /// (keeping sibling keywords such as `description`) and is recorded as a warning.
+    fn resolve_schema_in(
+        &self,
+        schema: Value,
+        location: Option<&str>,
+        active: &mut Vec<String>,
+    ) -> Value {
         match schema {
-            Value::Object(map) => {
+            Value::Object(mut map) => {
                 if let Some(Value::String(reference)) = map.get("$ref").cloned() {
-                    if let Ok(resolved) = self.resolve_ref(&reference) {
-                        return self.resolve_schema(resolved);
-                    }
+                    let key = absolute_ref(&reference, location);
+                    let problem = if active.contains(&key) {
+                        format!("circular $ref '{}' replaced with an empty schema", key)
+                    } else if active.len() >= self.max_ref_depth {
+                        format!(
+                            "$ref '{}' exceeds the maximum depth of {} and was replaced with an empty schema",
+                            key, self.max_ref_depth
+                        )
+                    } else {
+                        match self.resolve_ref_in(&reference, location) {
+                            Ok((resolved, target)) => {
+                                active.push(key);
+                                let out =
+                                    self.resolve_schema_in(resolved, target.as_deref(), active);
+                                active.pop();
+                                return out;
+                            }
+                            Err(err) => format!(
+                                "unresolvable $ref '{}' replaced with an empty schema: {}",
+                                reference, err
+                            ),
+                        }
+                    };
+                    self.warn(problem);
+                    map.remove("$ref");
                     return Value::Object(map);
                 }
 
+                let variant_refs = variant_ref_names(&map);
                 let mut out = Map::new();
                 for (k, v) in map {
-                    out.insert(k, self.resolve_schema(v));
+                    out.insert(k, self.resolve_schema_in(v, location, active));
                 }
+                normalize_schema_keywords(&mut out);
+                merge_composition(&mut out, &variant_refs);
                 Value::Object(out)
             }
             Value::Array(arr) => Value::Array(
                 arr.into_iter()
-                    .map(|item| self.resolve_schema(item))
+                    .map(|item| self.resolve_schema_in(item, location, active))
                     .collect(),
             ),
             other => other,
@@ -155,27 +535,37 @@ impl OpenApiConverter {
     }
 
     fn extract_auth(&self, operation: &Map<String, Value>) -> Option<AuthConfig> {
-        let mut reqs = Vec::new();
-        if let Some(op_sec) = operation.get("security").and_then(|v| v.as_array()) {
-            if !op_sec.is_empty() {
-                reqs = op_sec.clone();
-            }
-        }
-        if reqs.is_empty() {
-            if let Some(global) = self.spec.get("security").and_then(|v| v.as_array()) {
-                reqs = global.clone();
-            }
-        }
+        let global = self
+            .spec
+            .get("security")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let reqs = match operation.get("security").and_then(|v| v.as_array()) {
+            Some(op_sec) if !op_sec.is_empty() => op_sec.clone(),
+            _ => global.clone(),
+        };
         if reqs.is_empty() {
             return None;
         }
 
         let schemes = self.get_security_schemes().unwrap_or_default();
-        for raw in reqs {
+        for raw in &reqs {
             if let Some(sec_map) = raw.as_object() {
-                for name in sec_map.keys() {
+                // Scheme names are sorted so the choice never depends on map ordering.
+                let mut names: Vec<&String> = sec_map.keys().collect();
+                names.sort();
+                for name in names {
                     if let Some(Value::Object(scheme)) = schemes.get(name) {
-                        if let Some(auth) = self.create_auth_from_scheme(scheme) {
+                        if let Some(mut auth) = self.create_auth_from_scheme(scheme) {
+                            if let AuthConfig::OAuth2(oauth) = &mut auth {
+                                // Requested scopes narrow the scheme's declared scopes.
+                                let requested = requirement_scopes(&reqs, name)
+                                    .or_else(|| requirement_scopes(&global, name));
+                                if let Some(requested) = requested {
+                                    oauth.scope = Some(requested.join(" "));
+                                }
+                            }
                             return Some(auth);
                         }
                     }
@@ -358,7 +748,7 @@ impl OpenApiConverter {
             })
             .unwrap_or_default();
 
-        let tags = op
+        let mut tags = op
             .get("tags")
             .and_then(|v| v.as_array())
             .map(|arr| {
@@ -368,27 +758,128 @@ impl OpenApiConverter {
             })
             .unwrap_or_default();
 
-        let (input_schema, headers, body_field) = self.extract_inputs(op);
+        let OperationInputs {
+            schema: input_schema,
+            headers,
+            body_field,
+            content_type,
+            param_styles,
+        } = self.extract_inputs(op);
         let output_schema = self.extract_outputs(op);
         let auth = self.extract_auth(op);
 
+        let base = BaseProvider {
+            name: self.provider_name.clone(),
+            provider_type: ProviderType::Http,
+            auth,
+            allowed_communication_protocols: None,
+        };
+        let url = join_url(base_url, path);
+        let header_fields = if headers.is_empty() {
+            None
+        } else {
+            Some(headers)
+        };
+        let path_params = {
+            let names = path_placeholders(path);
+            if names.is_empty() {
+                None
+            } else {
+                Some(names)
+            }
+        };
+        let param_styles = if param_styles.is_empty() {
+            None
+        } else {
+            Some(param_styles)
+        };
+
+        // Streaming responses go through the SSE or stream transports, which read incrementally.
+        if let Some(stream_type) = streaming_provider_type(op) {
+            tags.push(STREAMING_TAG.to_string());
+            let base = BaseProvider {
+                provider_type: stream_type.clone(),
+                ..base
+            };
+            let provider_value = if stream_type == ProviderType::Sse {
+                serde_json::to_value(SseProvider {
+                    base,
+                    url,
+                    http_method: method.to_ascii_uppercase(),
+                    headers: None,
+                    body_field,
+                    header_fields,
+                    timeouts: None,
+                    proxy: None,
+                    return_envelope: false,
+                    use_cookies: false,
+                    include_event_metadata: false,
+                    event_filter: None,
+                    idle_timeout_ms: Some(DEFAULT_IDLE_TIMEOUT_MS),
+                    discovery_mode: SseDiscoveryMode::Endpoint,
+                    max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+                    max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+                    operation_url: true,
+                    path_params,
+                    param_styles,
+                })?
+            } else {
+                serde_json::to_value(StreamableHttpProvider {
+                    base,
+                    url,
+                    http_method: method.to_ascii_uppercase(),
+                    headers: None,
+                    query_style: QueryEncoding::Repeat,
+                    max_retries: None,
+                    max_retry_delay_ms: None,
+                    retry_non_idempotent: false,
+                    timeouts: None,
+                    proxy: None,
+                    max_inline_binary_bytes: None,
+                    return_envelope: false,
+                    use_cookies: false,
+                    operation_url: true,
+                    path_params,
+                    param_styles,
+                    body_field,
+                })?
+            };
+            return Ok(Some(Tool {
+                name: op_id,
+                description,
+                inputs: input_schema,
+                outputs: output_schema,
+                tags,
+                average_response_size: None,
+                provider: Some(provider_value),
+            }));
+        }
+
         let provider = HttpProvider {
-            base: BaseProvider {
-                name: self.provider_name.clone(),
-                provider_type: ProviderType::Http,
-                auth,
-                allowed_communication_protocols: None,
-            },
+            base,
             http_method: method.to_ascii_uppercase(),
-            url: join_url(base_url, path),
-            content_type: Some("application/json".to_string()),
+            url,
+            content_type: Some(content_type.unwrap_or_else(|| "application/json".to_string())),
             headers: None,
             body_field,
-            header_fields: if headers.is_empty() {
-                None
-            } else {
-                Some(headers)
-            },
+            header_fields,
+            path_params,
+            param_styles,
+            query_style: QueryEncoding::Repeat,
+            openapi_filters: None,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_upload_bytes: None,
+            upload_root: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            compress_requests: false,
+            use_cookies: false,
+            redirect: None,
+            response_cache: None,
         };
 
         let provider_value = serde_json::to_value(provider)?;
@@ -403,20 +894,19 @@ impl OpenApiConverter {
         }))
     }
 
-    fn extract_inputs(
-        &self,
-        op: &Map<String, Value>,
-    ) -> (ToolInputOutputSchema, Vec<String>, Option<String>) {
+    fn extract_inputs(&self, op: &Map<String, Value>) -> OperationInputs {
         let mut props: HashMap<String, Value> = HashMap::new();
         let mut required: Vec<String> = Vec::new();
         let mut headers = Vec::new();
         let mut body_field: Option<String> = None;
+        let mut content_type: Option<String> = None;
+        let mut param_styles = HashMap::new();
 
         if let Some(parameters) = op.get("parameters").and_then(|v| v.as_array()) {
             for raw_param in parameters {
                 let param = self.resolve_schema(raw_param.clone());
                 if let Some(param_obj) = param.as_object() {
-                    let name = param_obj
+                    let mut name = param_obj
                         .get("name")
                         .and_then(|v| v.as_str())
                         .unwrap_or("")
@@ -433,15 +923,32 @@ impl OpenApiConverter {
                         headers.push(name.clone());
                     }
                     if location == "body" {
+                        // Swagger 2.0 body parameters are exposed like an OpenAPI 3 requestBody.
+                        name = "body".to_string();
                         body_field = Some(name.clone());
                     }
+                    if location == "formData" {
+                        let is_file =
+                            param_obj.get("type").and_then(|v| v.as_str()) == Some("file");
+                        if is_file || content_type.as_deref() == Some("multipart/form-data") {
+                            content_type = Some("multipart/form-data".to_string());
+                        } else {
+                            content_type = Some(swagger_form_content_type(&self.spec, op));
+                        }
+                    }
+                    if location == "query" {
+                        if let Some(style) = query_param_style(param_obj) {
+                            param_styles.insert(name.clone(), style);
+                        }
+                    }
 
-                    let schema_val = param_obj
+                    // `param` is already fully resolved; resolving again would unroll
+                    // recursive refs one level further than `max_ref_depth` allows.
+                    let schema_map = param_obj
                         .get("schema")
+                        .and_then(|v| v.as_object())
                         .cloned()
-                        .unwrap_or_else(|| Value::Object(Map::new()));
-                    let schema_obj = self.resolve_schema(schema_val);
-                    let schema_map = schema_obj.as_object().cloned().unwrap_or_default();
+                        .unwrap_or_default();
                     let mut entry = Map::new();
 
                     if let Some(desc) = param_obj.get("description") {
@@ -450,9 +957,32 @@ impl OpenApiConverter {
                     if let Some(typ) = schema_map.get("type").or_else(|| param_obj.get("type")) {
                         entry.insert("type".to_string(), typ.clone());
                     }
+                    // Swagger 2.0 non-body parameters describe their schema inline.
+                    for key in SWAGGER_PARAM_SCHEMA_KEYS {
+                        if let Some(v) = param_obj.get(key) {
+                            entry.entry(key.to_string()).or_insert_with(|| v.clone());
+                        }
+                    }
+                    if entry.get("type").and_then(|v| v.as_str()) == Some("file") {
+                        entry.insert("type".to_string(), Value::String("string".to_string()));
+                        entry.insert("format".to_string(), Value::String("binary".to_string()));
+                    }
                     for (k, v) in schema_map {
                         entry.insert(k, v);
                     }
+                    // Parameter-level examples override those of the schema.
+                    if let Some(example) = param_obj.get("example") {
+                        entry.insert("example".to_string(), example.clone());
+                    }
+                    if let Some(examples) = param_obj.get("examples").and_then(|v| v.as_object()) {
+                        let values: Vec<Value> = examples
+                            .values()
+                            .filter_map(|example| example.get("value").cloned())
+                            .collect();
+                        if !values.is_empty() {
+                            entry.insert("examples".to_string(), Value::Array(values));
+                        }
+                    }
                     if !entry.contains_key("type") {
                         entry.insert("type".to_string(), Value::String("object".to_string()));
                     }
@@ -479,8 +1009,7 @@ impl OpenApiConverter {
                         if let Some(schema) = app_json.get("schema") {
                             let name = "body".to_string();
                             body_field = Some(name.clone());
-                            let schema_obj = self.resolve_schema(schema.clone());
-                            let schema_map = schema_obj.as_object().cloned().unwrap_or_default();
+                            let schema_map = schema.as_object().cloned().unwrap_or_default();
                             let mut entry = Map::new();
                             if let Some(desc) = rb_obj.get("description") {
                                 entry.insert("description".to_string(), desc.clone());
@@ -503,98 +1032,140 @@ impl OpenApiConverter {
                                 required.push(name);
                             }
                         }
+                    } else if let Some((media_type, form)) =
+                        FORM_CONTENT_TYPES.iter().find_map(|media_type| {
+                            content
+                                .get(*media_type)
+                                .and_then(|v| v.as_object())
+                                .map(|form| (*media_type, form))
+                        })
+                    {
+                        // Form fields are sent individually, so each becomes its own argument.
+                        content_type = Some(media_type.to_string());
+                        let schema = form.get("schema").and_then(|v| v.as_object());
+                        if let Some(fields) = schema
+                            .and_then(|s| s.get("properties"))
+                            .and_then(|v| v.as_object())
+                        {
+                            for (name, field) in fields {
+                                props.insert(name.clone(), field.clone());
+                            }
+                        }
+                        for name in
+                            string_slice(schema.and_then(|s| s.get("required"))).unwrap_or_default()
+                        {
+                            if !required.contains(&name) {
+                                required.push(name);
+                            }
+                        }
                     }
                 }
             }
         }
 
         let schema = ToolInputOutputSchema {
-            type_: "object".to_string(),
             properties: if props.is_empty() { None } else { Some(props) },
             required: if required.is_empty() {
                 None
             } else {
                 Some(required)
             },
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
+            ..ToolInputOutputSchema::new("object")
         };
 
-        (schema, headers, body_field)
+        OperationInputs {
+            schema,
+            headers,
+            body_field,
+            content_type,
+            param_styles,
+        }
     }
 
     fn extract_outputs(&self, op: &Map<String, Value>) -> ToolInputOutputSchema {
-        let default_schema = ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
-        };
+        let default_schema = ToolInputOutputSchema::new("object");
 
         let responses = match op.get("responses").and_then(|v| v.as_object()) {
             Some(r) => r,
             None => return default_schema,
         };
-        let resp = match responses
-            .get("200")
-            .or_else(|| responses.get("201"))
-            .cloned()
-        {
-            Some(r) => r,
+        let (status, resp) = match select_response(responses) {
+            Some((status, r)) => (status, r.clone()),
             None => return default_schema,
         };
 
         let resp = self.resolve_schema(resp);
-        if let Some(resp_obj) = resp.as_object() {
-            if let Some(content) = resp_obj.get("content").and_then(|v| v.as_object()) {
-                if let Some(app_json) = content.get("application/json").and_then(|v| v.as_object())
-                {
-                    if let Some(schema) = app_json.get("schema") {
-                        let fallback = resp_obj
-                            .get("description")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                        return self.build_schema_from_value(schema, fallback);
-                    }
+        let resp_obj = match resp.as_object() {
+            Some(obj) => obj,
+            None => return default_schema,
+        };
+        let fallback = resp_obj
+            .get("description")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        if status == "204" {
+            return ToolInputOutputSchema {
+                properties: Some(HashMap::new()),
+                description: Some(match fallback {
+                    Some(desc) => format!("{} (no content)", desc),
+                    None => "No content".to_string(),
+                }),
+                ..default_schema
+            };
+        }
+
+        if let Some(content) = resp_obj.get("content").and_then(|v| v.as_object()) {
+            let json_media = content.get("application/json").or_else(|| {
+                content
+                    .iter()
+                    .find(|(media_type, _)| is_json_media_type(media_type))
+                    .map(|(_, media)| media)
+            });
+            if let Some(media) = json_media {
+                if let Some(schema) = media.get("schema") {
+                    return self.build_schema_from_value(schema, fallback);
                 }
+                return default_schema;
             }
-            if let Some(schema) = resp_obj.get("schema") {
-                let fallback = resp_obj
-                    .get("description")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                return self.build_schema_from_value(schema, fallback);
+
+            // Non-JSON bodies are returned to the caller as text.
+            let mut media_types: Vec<&String> = content.keys().collect();
+            media_types.sort();
+            if let Some(media_type) = media_types.first() {
+                let schema = content[media_type.as_str()]
+                    .get("schema")
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                let mut out = self.build_schema_from_value(&schema, None);
+                out.type_ = "string".to_string();
+                out.properties = None;
+                out.required = None;
+                out.description = Some(match out.description.or(fallback) {
+                    Some(desc) => format!("{} ({})", desc, media_type),
+                    None => format!("{} response", media_type),
+                });
+                return out;
             }
         }
 
+        if let Some(schema) = resp_obj.get("schema") {
+            return self.build_schema_from_value(schema, fallback);
+        }
+
         default_schema
     }
 
+    /// Build a tool schema from an already resolved JSON schema.
     fn build_schema_from_value(
         &self,
         schema: &Value,
         fallback_description: Option<String>,
     ) -> ToolInputOutputSchema {
-        let resolved = self.resolve_schema(schema.clone());
-        let map = resolved.as_object().cloned().unwrap_or_default();
+        let map = schema.as_object().cloned().unwrap_or_default();
 
         let mut out = ToolInputOutputSchema {
-            type_: map
-                .get("type")
-                .and_then(|v| v.as_str())
-                .unwrap_or("object")
-                .to_string(),
             properties: map_from_value(map.get("properties")),
             required: string_slice(map.get("required")),
             description: match map
@@ -609,7 +1180,6 @@ impl OpenApiConverter {
                 .get("title")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
-            items: None,
             enum_: map.get("enum").and_then(|v| interface_slice(v)),
             minimum: cast_float(map.get("minimum")),
             maximum: cast_float(map.get("maximum")),
@@ -617,6 +1187,13 @@ impl OpenApiConverter {
                 .get("format")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            one_of: map
+                .get("oneOf")
+                .or_else(|| map.get("anyOf"))
+                .and_then(interface_slice),
+            ..ToolInputOutputSchema::new(
+                map.get("type").and_then(|v| v.as_str()).unwrap_or("object"),
+            )
         };
 
         if out.type_ == "array" {
@@ -627,6 +1204,144 @@ impl OpenApiConverter {
     }
 }
 
+/// Request details gathered from an operation's parameters and request body.
+struct OperationInputs {
+    schema: ToolInputOutputSchema,
+    headers: Vec<String>,
+    body_field: Option<String>,
+    content_type: Option<String>,
+    param_styles: HashMap<String, ParamStyle>,
+}
+
+/// Serialization rule for a query parameter. Only parameters that declare `style` or
+/// `explode`, or whose schema is an array or object, are recorded; everything else
+/// keeps the transport's plain `name=value` encoding.
+fn query_param_style(param: &Map<String, Value>) -> Option<ParamStyle> {
+    let schema_type = param
+        .get("schema")
+        .and_then(|s| s.get("type"))
+        .or_else(|| param.get("type"))
+        .and_then(|v| v.as_str());
+    let style = param.get("style").and_then(|v| v.as_str());
+    let explode = param.get("explode").and_then(|v| v.as_bool());
+    if style.is_none() && explode.is_none() && !matches!(schema_type, Some("array" | "object")) {
+        return None;
+    }
+
+    let style = match style {
+        Some("spaceDelimited") => QueryStyle::SpaceDelimited,
+        Some("pipeDelimited") => QueryStyle::PipeDelimited,
+        Some("deepObject") => QueryStyle::DeepObject,
+        _ => QueryStyle::Form,
+    };
+    Some(ParamStyle {
+        style,
+        // Per the spec, `explode` defaults to true only for `form`.
+        explode: explode.unwrap_or(style == QueryStyle::Form),
+    })
+}
+
+/// Form encoding for a Swagger 2.0 operation with `formData` parameters, taken from the
+/// operation's (or else the spec's) `consumes` when it names multipart.
+fn swagger_form_content_type(spec: &Value, op: &Map<String, Value>) -> String {
+    let consumes: Vec<&str> = op
+        .get("consumes")
+        .or_else(|| spec.get("consumes"))
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    if consumes.contains(&"multipart/form-data") {
+        "multipart/form-data".to_string()
+    } else {
+        "application/x-www-form-urlencoded".to_string()
+    }
+}
+
+/// URL of the first entry in a `servers` array, with `{variable}` placeholders replaced
+/// by their declared defaults.
+fn server_url(servers: Option<&Value>) -> Option<String> {
+    let server = servers?.as_array()?.first()?.as_object()?;
+    let mut url = server.get("url")?.as_str()?.to_string();
+    if url.is_empty() {
+        return None;
+    }
+
+    if let Some(variables) = server.get("variables").and_then(|v| v.as_object()) {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(|v| v.as_str()) {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+    }
+    Some(url)
+}
+
+/// Provider type for operations whose success responses are streamed: SSE for
+/// `text/event-stream`, streamable HTTP for newline-delimited JSON.
+fn streaming_provider_type(op: &Map<String, Value>) -> Option<ProviderType> {
+    let responses = op.get("responses")?.as_object()?;
+    let mut statuses: Vec<&String> = responses
+        .keys()
+        .filter(|status| status.starts_with('2'))
+        .collect();
+    statuses.sort();
+
+    for status in statuses {
+        let content = match responses[status.as_str()]
+            .get("content")
+            .and_then(|v| v.as_object())
+        {
+            Some(content) => content,
+            None => continue,
+        };
+        for media_type in content.keys() {
+            let essence = media_type.split(';').next().unwrap_or("").trim();
+            match essence {
+                "text/event-stream" => return Some(ProviderType::Sse),
+                "application/x-ndjson" | "application/jsonl" | "application/stream+json" => {
+                    return Some(ProviderType::HttpStream)
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Scopes listed for `scheme` by the first requirement in `reqs` that names it with a
+/// non-empty scope list.
+fn requirement_scopes(reqs: &[Value], scheme: &str) -> Option<Vec<String>> {
+    reqs.iter()
+        .filter_map(|req| string_slice(req.get(scheme)))
+        .find(|scopes| !scopes.is_empty())
+}
+
+/// Pick the response that describes a successful call: 200, 201, any other 2xx in
+/// ascending order, then `default`.
+fn select_response(responses: &Map<String, Value>) -> Option<(&str, &Value)> {
+    let mut success: Vec<(u16, &String)> = responses
+        .keys()
+        .filter_map(|status| match status.parse::<u16>() {
+            Ok(code) if (200..300).contains(&code) => Some((code, status)),
+            _ => None,
+        })
+        .collect();
+    // Ascending order already puts 200 and 201 ahead of the other 2xx codes.
+    success.sort_by_key(|(code, _)| *code);
+
+    success
+        .into_iter()
+        .map(|(_, status)| status.as_str())
+        .chain(std::iter::once("default"))
+        .find_map(|status| responses.get(status).map(|resp| (status, resp)))
+}
+
+/// Whether a media type carries JSON, e.g. `application/problem+json`.
+fn is_json_media_type(media_type: &str) -> bool {
+    let essence = media_type.split(';').next().unwrap_or("").trim();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
 /// Load an OpenAPI/Swagger document from a URL, handling JSON or YAML.
 pub async fn load_spec_from_url(raw_url: &str) -> Result<(Value, String)> {
     let resp = reqwest::get(raw_url).await?;
@@ -637,66 +1352,493 @@ pub async fn load_spec_from_url(raw_url: &str) -> Result<(Value, String)> {
 
     let final_url = resp.url().to_string();
     let bytes = resp.bytes().await?;
+    Ok((parse_spec(&bytes)?, final_url))
+}
 
-    if let Ok(json_spec) = serde_json::from_slice::<Value>(&bytes) {
-        return Ok((json_spec, final_url));
-    }
+/// Convert every OpenAPI document (`.json`, `.yaml`, `.yml`) under `dir`, recursing into
+/// subdirectories. Manuals are keyed by derived provider name; files without `openapi`,
+/// `swagger` or `paths` (such as shared component files) are skipped.
+pub async fn convert_directory(dir: impl AsRef<Path>) -> Result<HashMap<String, UtcpManual>> {
+    let mut manuals = HashMap::new();
+    let mut sources: HashMap<String, PathBuf> = HashMap::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&current)
+            .await
+            .with_context(|| format!("failed to read directory {}", current.display()))?;
+        let mut paths = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            paths.push(entry.path());
+        }
+        paths.sort();
 
-    let yaml_value: serde_yaml::Value = serde_yaml::from_slice(&bytes)
-        .map_err(|err| anyhow!("failed to parse as JSON or YAML: {}", err))?;
-    let json_value = serde_json::to_value(yaml_value)?;
-    Ok((json_value, final_url))
-}
+        for path in paths {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let is_spec_file = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "json" | "yaml" | "yml"))
+                .unwrap_or(false);
+            if !is_spec_file {
+                continue;
+            }
 
-fn derive_provider_name(spec: &Value) -> String {
-    let title = spec
-        .get("info")
-        .and_then(|v| v.as_object())
-        .and_then(|info| info.get("title"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let base = if title.is_empty() {
-        "openapi_provider".to_string()
-    } else {
-        title
-    };
-    let invalid = " -.,!?'\"\\\\/()[]{}#@$%^&*+=~`|;:<>";
+            let bytes = tokio::fs::read(&path)
+                .await
+                .with_context(|| format!("failed to read spec {}", path.display()))?;
+            let is_openapi = parse_spec(&bytes)
+                .map(|spec| {
+                    ["openapi", "swagger", "paths"]
+                        .iter()
+                        .any(|key| spec.get(key).is_some())
+                })
+                .unwrap_or(false);
+            if !is_openapi {
+                continue;
+            }
 
-    let mut output = String::new();
-    for ch in base.chars() {
-        if invalid.contains(ch) {
-            output.push('_');
-        } else {
-            output.push(ch);
+            let converter = OpenApiConverter::new_from_file(&path, None).await?;
+            let name = converter.provider_name().to_string();
+            if let Some(previous) = sources.get(&name) {
+                return Err(anyhow!(
+                    "specs {} and {} both map to provider '{}'",
+                    previous.display(),
+                    path.display(),
+                    name
+                ));
+            }
+            sources.insert(name.clone(), path);
+            manuals.insert(name, converter.convert());
         }
     }
-    if output.is_empty() {
-        "openapi_provider".to_string()
-    } else {
-        output
-    }
+
+    Ok(manuals)
 }
 
-fn optional_string(s: String) -> Option<String> {
-    if s.is_empty() {
-        None
-    } else {
-        Some(s)
+/// Local path named by a `file://` URL, if `raw_url` is one.
+pub fn file_url_path(raw_url: &str) -> Option<PathBuf> {
+    let url = Url::parse(raw_url).ok()?;
+    if url.scheme() != "file" {
+        return None;
     }
+    url.to_file_path().ok()
 }
 
-fn join_url(base: &str, path: &str) -> String {
-    let trimmed_base = base.trim_end_matches('/');
-    let trimmed_path = path.trim_start_matches('/');
-    if trimmed_base.is_empty() {
-        format!("/{}", trimmed_path)
-    } else if trimmed_path.is_empty() {
-        trimmed_base.to_string()
-    } else {
-        format!("{}/{}", trimmed_base, trimmed_path)
+/// Parse a JSON or YAML document.
+fn parse_spec(bytes: &[u8]) -> Result<Value> {
+    if let Ok(json_spec) = serde_json::from_slice::<Value>(bytes) {
+        return Ok(json_spec);
     }
-}
+
+    let yaml_value: serde_yaml::Value = serde_yaml::from_slice(bytes)
+        .map_err(|err| anyhow!("failed to parse as JSON or YAML: {}", err))?;
+    Ok(serde_json::to_value(yaml_value)?)
+}
+
+/// Client for remote `$ref`s that only follows redirects to `allowed` origins.
+fn ref_client(allowed: HashSet<String>) -> Result<reqwest::Client> {
+    let policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REF_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if allowed.contains(&attempt.url().origin().ascii_serialization()) {
+            attempt.follow()
+        } else {
+            attempt.stop()
+        }
+    });
+    Ok(reqwest::Client::builder().redirect(policy).build()?)
+}
+
+/// Load an external `$ref` target: http(s) URLs with `client`, anything else from the
+/// filesystem.
+async fn fetch_document(client: &reqwest::Client, location: &str) -> Result<Value> {
+    if !is_remote(location) {
+        let bytes = tokio::fs::read(location).await?;
+        return parse_spec(&bytes);
+    }
+
+    let resp = client.get(location).send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(anyhow!("unexpected HTTP status: {}", status));
+    }
+    parse_spec(&resp.bytes().await?)
+}
+
+/// The `scheme://host[:port]` origin of an http(s) URL.
+fn url_origin(location: &str) -> Option<String> {
+    if !is_remote(location) {
+        return None;
+    }
+    Url::parse(location)
+        .ok()
+        .map(|url| url.origin().ascii_serialization())
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Gather every `$ref` string in a document.
+fn collect_refs(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                out.push(reference.clone());
+            }
+            for child in map.values() {
+                collect_refs(child, out);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_refs(item, out)),
+        _ => {}
+    }
+}
+
+/// Split a ref that points into another document into that document's absolute location
+/// and the JSON pointer within it. Returns `None` for same-document (`#/...`) refs.
+fn split_ref(reference: &str, base: Option<&str>) -> Option<(String, String)> {
+    let (document, pointer) = match reference.split_once('#') {
+        Some((document, pointer)) => (document, pointer),
+        None => (reference, ""),
+    };
+    if document.is_empty() {
+        return None;
+    }
+    Some((join_location(base, document), pointer.to_string()))
+}
+
+/// The `location#pointer` form of a ref, used to detect cycles across documents.
+fn absolute_ref(reference: &str, location: Option<&str>) -> String {
+    match split_ref(reference, location) {
+        Some((target, pointer)) => format!("{}#{}", target, pointer),
+        None => match location {
+            Some(location) => format!("{}{}", location, reference),
+            None => reference.to_string(),
+        },
+    }
+}
+
+/// Resolve `relative` against the document at `base` (a URL or a file path).
+fn join_location(base: Option<&str>, relative: &str) -> String {
+    if is_remote(relative) {
+        return relative.to_string();
+    }
+    match base {
+        Some(base) if is_remote(base) => Url::parse(base)
+            .and_then(|url| url.join(relative))
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| relative.to_string()),
+        Some(base) => {
+            let dir = Path::new(base).parent().unwrap_or_else(|| Path::new(""));
+            normalize_path(&dir.join(relative))
+        }
+        None => normalize_path(Path::new(relative)),
+    }
+}
+
+/// Lexically collapse `.` and `..` so the same file always maps to the same cache key.
+fn normalize_path(path: &Path) -> String {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out.to_string_lossy().into_owned()
+}
+
+fn derive_provider_name(spec: &Value) -> String {
+    let title = spec
+        .get("info")
+        .and_then(|v| v.as_object())
+        .and_then(|info| info.get("title"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let base = if title.is_empty() {
+        "openapi_provider".to_string()
+    } else {
+        title
+    };
+    let invalid = " -.,!?'\"\\\\/()[]{}#@$%^&*+=~`|;:<>";
+
+    let mut output = String::new();
+    for ch in base.chars() {
+        if invalid.contains(ch) {
+            output.push('_');
+        } else {
+            output.push(ch);
+        }
+    }
+    if output.is_empty() {
+        "openapi_provider".to_string()
+    } else {
+        output
+    }
+}
+
+/// Names of `$ref` variants listed under `oneOf`/`anyOf`, used as implicit discriminator values.
+fn variant_ref_names(map: &Map<String, Value>) -> Vec<String> {
+    ["oneOf", "anyOf"]
+        .iter()
+        .filter_map(|key| map.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|variant| variant.get("$ref").and_then(|v| v.as_str()))
+        .filter_map(|reference| reference.rsplit('/').next())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Rewrite OpenAPI 3.1 (JSON Schema 2020-12) keywords into the 3.0 forms used by the rest of
+/// the converter: `type` arrays become the first non-null type plus `nullable`, `const`
+/// becomes a single-value `enum`. Bounds go the other way, since generated schemas are JSON
+/// Schema: 3.0's boolean `exclusiveMinimum`/`exclusiveMaximum` flags become the numeric form.
+fn normalize_schema_keywords(schema: &mut Map<String, Value>) {
+    if let Some(Value::Array(types)) = schema.get("type") {
+        let nullable = types.iter().any(|t| t == "null");
+        let primary = types
+            .iter()
+            .find(|t| *t != "null")
+            .cloned()
+            .unwrap_or_else(|| Value::String("null".to_string()));
+        schema.insert("type".to_string(), primary);
+        if nullable {
+            schema.insert("nullable".to_string(), Value::Bool(true));
+        }
+    }
+
+    if let Some(value) = schema.remove("const") {
+        if !schema.contains_key("type") {
+            if let Some(type_name) = json_type_name(&value) {
+                schema.insert("type".to_string(), Value::String(type_name.to_string()));
+            }
+        }
+        schema.insert("enum".to_string(), Value::Array(vec![value]));
+    }
+
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        match schema.get(exclusive) {
+            Some(Value::Bool(true)) => match schema.remove(bound) {
+                Some(limit) => {
+                    schema.insert(exclusive.to_string(), limit);
+                }
+                None => {
+                    schema.remove(exclusive);
+                }
+            },
+            Some(Value::Bool(false)) => {
+                schema.remove(exclusive);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn json_type_name(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::String(_) => Some("string"),
+        Value::Bool(_) => Some("boolean"),
+        Value::Number(n) if n.is_i64() || n.is_u64() => Some("integer"),
+        Value::Number(_) => Some("number"),
+        Value::Array(_) => Some("array"),
+        Value::Object(_) => Some("object"),
+        Value::Null => None,
+    }
+}
+
+/// Collapse the `anyOf: [X, {type: null}]` shape generated for optional fields into `X`
+/// marked `nullable`.
+fn collapse_nullable_variant(schema: &mut Map<String, Value>) {
+    for key in ["anyOf", "oneOf"] {
+        let Some(Value::Array(variants)) = schema.get(key) else {
+            continue;
+        };
+        let is_null = |v: &Value| v.get("type").and_then(|t| t.as_str()) == Some("null");
+        if variants.len() != 2 || !variants.iter().any(is_null) {
+            continue;
+        }
+        let Some(Value::Object(inner)) = variants.iter().find(|v| !is_null(v)).cloned() else {
+            continue;
+        };
+        schema.remove(key);
+        for (k, v) in inner {
+            schema.entry(k).or_insert(v);
+        }
+        schema.insert("nullable".to_string(), Value::Bool(true));
+    }
+}
+
+/// Flatten schema composition so tool inputs expose concrete properties.
+///
+/// `allOf` members are merged into the schema (union of properties and required). For
+/// `oneOf`/`anyOf` the variants are kept as-is and their properties are unioned into the
+/// schema; only properties required by every variant become required. A `discriminator`
+/// becomes an enum on the discriminating property.
+fn merge_composition(schema: &mut Map<String, Value>, variant_refs: &[String]) {
+    collapse_nullable_variant(schema);
+    if let Some(Value::Array(members)) = schema.remove("allOf") {
+        for member in members {
+            if let Value::Object(member) = member {
+                merge_schema_into(schema, member);
+            }
+        }
+    }
+
+    for key in ["oneOf", "anyOf"] {
+        let Some(Value::Array(variants)) = schema.get(key).cloned() else {
+            continue;
+        };
+        let mut common_required: Option<Vec<Value>> = None;
+        for variant in &variants {
+            if let Some(props) = variant.get("properties").and_then(|v| v.as_object()) {
+                let target = properties_of(schema);
+                for (name, prop) in props {
+                    target.entry(name.clone()).or_insert_with(|| prop.clone());
+                }
+            }
+            let required = variant
+                .get("required")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            common_required = Some(match common_required {
+                None => required,
+                Some(common) => common
+                    .into_iter()
+                    .filter(|name| required.contains(name))
+                    .collect(),
+            });
+        }
+        union_required(schema, common_required.unwrap_or_default());
+        if schema.contains_key("properties") && !schema.contains_key("type") {
+            schema.insert("type".to_string(), Value::String("object".to_string()));
+        }
+    }
+
+    let Some(discriminator) = schema.get("discriminator").and_then(|v| v.as_object()) else {
+        return;
+    };
+    let Some(property) = discriminator.get("propertyName").and_then(|v| v.as_str()) else {
+        return;
+    };
+    let property = property.to_string();
+    let values: Vec<Value> = match discriminator.get("mapping").and_then(|v| v.as_object()) {
+        Some(mapping) if !mapping.is_empty() => mapping
+            .keys()
+            .map(|value| Value::String(value.clone()))
+            .collect(),
+        _ => variant_refs
+            .iter()
+            .map(|name| Value::String(name.clone()))
+            .collect(),
+    };
+    if values.is_empty() {
+        return;
+    }
+    let entry = properties_of(schema)
+        .entry(property)
+        .or_insert_with(|| serde_json::json!({"type": "string"}));
+    if let Value::Object(prop) = entry {
+        prop.insert("enum".to_string(), Value::Array(values));
+    }
+}
+
+/// Merge an `allOf` member into `target`. Keywords and properties already set on `target`
+/// take precedence over the member's, so a schema's own definitions win over its members and
+/// earlier members win over later ones. `required` lists are unioned.
+fn merge_schema_into(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (key, value) in source {
+        match (key.as_str(), value) {
+            ("properties", Value::Object(props)) => {
+                let existing = properties_of(target);
+                for (name, prop) in props {
+                    existing.entry(name).or_insert(prop);
+                }
+            }
+            ("required", Value::Array(required)) => union_required(target, required),
+            (_, value) => {
+                target.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+fn properties_of(schema: &mut Map<String, Value>) -> &mut Map<String, Value> {
+    let entry = schema
+        .entry("properties".to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    entry.as_object_mut().expect("properties is an object")
+}
+
+fn union_required(schema: &mut Map<String, Value>, names: Vec<Value>) {
+    if names.is_empty() {
+        return;
+    }
+    let entry = schema
+        .entry("required".to_string())
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if let Value::Array(existing) = entry {
+        for name in names {
+            if !existing.contains(&name) {
+                existing.push(name);
+            }
+        }
+    }
+}
+
+fn optional_string(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Names of `{param}` templates in an OpenAPI path, in order of appearance.
+pub(crate) fn path_placeholders(path: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[start + 1 + len + 1..];
+    }
+    names
+}
+
+fn join_url(base: &str, path: &str) -> String {
+    let trimmed_base = base.trim_end_matches('/');
+    let trimmed_path = path.trim_start_matches('/');
+    if trimmed_base.is_empty() {
+        format!("/{}", trimmed_path)
+    } else if trimmed_path.is_empty() {
+        trimmed_base.to_string()
+    } else {
+        format!("{}/{}", trimmed_base, trimmed_path)
+    }
+}
 
 fn map_from_value(value: Option<&Value>) -> Option<HashMap<String, Value>> {
     value.and_then(|v| v.as_object()).map(|obj| {
@@ -877,10 +2019,11 @@ mod tests {
         });
         let op = op_value.as_object().unwrap().clone();
 
-        let (schema, headers, body) = converter.extract_inputs(&op);
-        assert_eq!(schema.properties.as_ref().map(|m| m.len()), Some(3));
-        assert_eq!(headers, vec!["X".to_string()]);
-        assert_eq!(body.as_deref(), Some("body"));
+        let inputs = converter.extract_inputs(&op);
+        assert_eq!(inputs.schema.properties.as_ref().map(|m| m.len()), Some(3));
+        assert_eq!(inputs.headers, vec!["X".to_string()]);
+        assert_eq!(inputs.body_field.as_deref(), Some("body"));
+        assert_eq!(inputs.content_type, None);
 
         let out = converter.extract_outputs(&op);
         assert_eq!(out.type_, "object");
@@ -924,4 +2067,1054 @@ mod tests {
         assert_eq!(manual.tools.len(), 1);
         assert_eq!(manual.tools[0].name, "ping");
     }
+
+    #[test]
+    fn all_of_merge_keeps_the_first_definition_of_each_keyword_and_property() {
+        let mut schema = json!({
+            "description": "Own",
+            "properties": {"id": {"type": "string"}},
+            "allOf": [
+                {
+                    "description": "First",
+                    "format": "first",
+                    "required": ["id"],
+                    "properties": {
+                        "id": {"type": "integer"},
+                        "name": {"type": "string"}
+                    }
+                },
+                {
+                    "format": "second",
+                    "required": ["name", "id"],
+                    "properties": {"name": {"type": "boolean"}}
+                }
+            ]
+        });
+        merge_composition(schema.as_object_mut().unwrap(), &[]);
+        assert_eq!(
+            schema,
+            json!({
+                "description": "Own",
+                "format": "first",
+                "required": ["id", "name"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "name": {"type": "string"}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn resolves_all_of_one_of_and_any_of() {
+        let spec = json!({
+            "info": {"title": "Petstore"},
+            "servers": [{"url": "https://petstore.example.com"}],
+            "components": {
+                "schemas": {
+                    "NewPet": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": {"type": "string"},
+                            "tag": {"type": "string"}
+                        }
+                    },
+                    "Pet": {
+                        "allOf": [
+                            {"$ref": "#/components/schemas/NewPet"},
+                            {
+                                "required": ["id"],
+                                "properties": {"id": {"type": "integer"}}
+                            }
+                        ]
+                    },
+                    "Cat": {
+                        "type": "object",
+                        "required": ["petType", "indoor"],
+                        "properties": {
+                            "petType": {"type": "string"},
+                            "indoor": {"type": "boolean"}
+                        }
+                    },
+                    "Dog": {
+                        "type": "object",
+                        "required": ["petType"],
+                        "properties": {
+                            "petType": {"type": "string"},
+                            "bark": {"type": "boolean"}
+                        }
+                    }
+                }
+            },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "operationId": "addPet",
+                        "requestBody": {
+                            "required": true,
+                            "content": {"application/json": {"schema": {
+                                "$ref": "#/components/schemas/Pet"
+                            }}}
+                        },
+                        "responses": {"200": {"content": {"application/json": {"schema": {
+                            "anyOf": [
+                                {"$ref": "#/components/schemas/Pet"},
+                                {"properties": {"error": {"type": "string"}}}
+                            ]
+                        }}}}}
+                    },
+                    "put": {
+                        "operationId": "adoptPet",
+                        "requestBody": {
+                            "content": {"application/json": {"schema": {
+                                "oneOf": [
+                                    {"$ref": "#/components/schemas/Cat"},
+                                    {"$ref": "#/components/schemas/Dog"}
+                                ],
+                                "discriminator": {"propertyName": "petType"}
+                            }}}
+                        },
+                        "responses": {}
+                    }
+                }
+            }
+        });
+        let manual = OpenApiConverter::new(spec, None, None).convert();
+        let tool = |name: &str| manual.tools.iter().find(|t| t.name == name).unwrap();
+
+        let add_pet = tool("addPet");
+        let body = &add_pet.inputs.properties.as_ref().unwrap()["body"];
+        assert_eq!(body["type"], "object");
+        assert!(body.get("allOf").is_none());
+        for prop in ["name", "tag", "id"] {
+            assert!(body["properties"].get(prop).is_some(), "missing {prop}");
+        }
+        assert_eq!(body["required"], json!(["name", "id"]));
+
+        let output = &add_pet.outputs;
+        assert_eq!(output.one_of.as_ref().map(|v| v.len()), Some(2));
+        let output_props = output.properties.as_ref().unwrap();
+        assert!(output_props.contains_key("id") && output_props.contains_key("error"));
+        assert_eq!(output.required, None);
+
+        let adopt = tool("adoptPet");
+        let body = &adopt.inputs.properties.as_ref().unwrap()["body"];
+        assert_eq!(body["oneOf"].as_array().unwrap().len(), 2);
+        for prop in ["indoor", "bark"] {
+            assert!(body["properties"].get(prop).is_some(), "missing {prop}");
+        }
+        assert_eq!(body["required"], json!(["petType"]));
+        assert_eq!(body["properties"]["petType"]["enum"], json!(["Cat", "Dog"]));
+
+        let mapped = OpenApiConverter::new(json!({}), None, None).resolve_schema(json!({
+            "oneOf": [{"properties": {"kind": {"type": "string"}}}],
+            "discriminator": {"propertyName": "kind", "mapping": {"cat": "#/x", "dog": "#/y"}}
+        }));
+        assert_eq!(mapped["properties"]["kind"]["enum"], json!(["cat", "dog"]));
+    }
+
+    fn user_schema(tool: &Tool) -> Value {
+        Value::Object(
+            tool.inputs.properties.as_ref().unwrap()["body"]
+                .as_object()
+                .unwrap()
+                .clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn resolves_relative_file_refs_with_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("schemas")).unwrap();
+        std::fs::write(
+            dir.path().join("schemas/user.yaml"),
+            r##"
+User:
+  type: object
+  properties:
+    name:
+      type: string
+    address:
+      $ref: "#/Address"
+    manager:
+      $ref: "#/User"
+Address:
+  type: object
+  properties:
+    city:
+      $ref: "../common.json#/City"
+"##,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("common.json"),
+            r#"{"City": {"type": "string", "minLength": 1}}"#,
+        )
+        .unwrap();
+        let spec_path = dir.path().join("openapi.yaml");
+        std::fs::write(
+            &spec_path,
+            r#"
+info: {title: users}
+paths:
+  /users:
+    post:
+      operationId: createUser
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "schemas/user.yaml#/User"
+      responses: {}
+"#,
+        )
+        .unwrap();
+
+        let load = || async {
+            let spec = parse_spec(&std::fs::read(&spec_path).unwrap()).unwrap();
+            let mut converter = OpenApiConverter::new(spec, None, None);
+            converter.base_location = Some(spec_path.to_string_lossy().into_owned());
+            converter.load_external_refs().await.unwrap();
+            converter
+        };
+        let converter = load().await;
+        let manual = converter.convert();
+        let body = user_schema(&manual.tools[0]);
+        assert_eq!(body["properties"]["name"]["type"], "string");
+        assert_eq!(
+            body["properties"]["address"]["properties"]["city"],
+            json!({"type": "string", "minLength": 1})
+        );
+        // The self-reference degrades to an empty schema instead of recursing forever.
+        assert_eq!(body["properties"]["manager"], json!({}));
+        let warnings = converter.report().warnings;
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains("circular") && w.contains("user.yaml#/User")),
+            "{warnings:?}"
+        );
+
+        // A depth limit of one stops after the first hop.
+        let shallow = load().await.with_max_ref_depth(1);
+        let body = user_schema(&shallow.convert().tools[0]);
+        assert_eq!(body["properties"]["address"], json!({}));
+        assert!(shallow.report().warnings[0].contains("maximum depth of 1"));
+    }
+
+    #[tokio::test]
+    async fn resolves_remote_http_refs() {
+        use axum::{routing::get, Json, Router};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let spec = json!({
+            "info": {"title": "pets"},
+            "paths": {"/pets": {"post": {
+                "operationId": "addPet",
+                "requestBody": {"content": {"application/json": {"schema": {
+                    "$ref": "schemas/pet.json#/Pet"
+                }}}},
+                "responses": {}
+            }}}
+        });
+        let app = Router::new()
+            .route("/openapi.json", get(move || async move { Json(spec) }))
+            .route(
+                "/schemas/pet.json",
+                get(|| async {
+                    Json(json!({"Pet": {
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}}
+                    }}))
+                }),
+            );
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let converter = OpenApiConverter::new_from_url(&format!("{}/openapi.json", base), None)
+            .await
+            .unwrap();
+        let body = user_schema(&converter.convert().tools[0]);
+        assert_eq!(body["properties"]["name"]["type"], "string");
+
+        let missing = OpenApiConverter::new(
+            json!({"paths": {"/x": {"get": {"parameters": [
+                {"$ref": "missing.json#/Param"}
+            ]}}}}),
+            Some(format!("{}/openapi.json", base)),
+            None,
+        )
+        .load_external_refs()
+        .await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_refs_are_limited_to_allowed_origins() {
+        use axum::{routing::get, Json, Router};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new().route(
+            "/pet.json",
+            get(|| async { Json(json!({"Pet": {"type": "object"}})) }),
+        );
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        // `localhost` and `127.0.0.1` are different origins for the same server.
+        let spec = json!({"paths": {"/x": {"get": {"parameters": [
+            {"$ref": format!("http://localhost:{}/pet.json#/Pet", port)}
+        ]}}}});
+        let from = |spec_url: Option<String>| OpenApiConverter::new(spec.clone(), spec_url, None);
+
+        let err = from(Some(format!("http://127.0.0.1:{}/openapi.json", port)))
+            .load_external_refs()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("refusing"), "{}", err);
+        // A spec read from disk may not reach the network on its own either.
+        assert!(from(None).load_external_refs().await.is_err());
+
+        let mut allowed = from(None)
+            .with_allowed_ref_origins([format!("http://localhost:{}", port)])
+            .unwrap();
+        allowed.load_external_refs().await.unwrap();
+        assert_eq!(allowed.documents.len(), 1);
+
+        let mut same_origin = from(Some(format!("http://localhost:{}/openapi.json", port)));
+        same_origin.load_external_refs().await.unwrap();
+        assert!(OpenApiConverter::new(json!({}), None, None)
+            .with_allowed_ref_origins(["not a url"])
+            .is_err());
+    }
+
+    #[test]
+    fn converts_openapi_31_keywords() {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": {"title": "FastAPI"},
+            "servers": [{"url": "https://api.example.com"}],
+            "webhooks": {
+                "newItem": {"post": {"requestBody": {"content": {"application/json": {
+                    "schema": {"type": "object"}
+                }}}}}
+            },
+            "paths": {
+                "/items": {
+                    "get": {
+                        "operationId": "list_items",
+                        "parameters": [
+                            {"name": "q", "in": "query", "schema": {"type": ["string", "null"]}},
+                            {"name": "cursor", "in": "query", "schema": {
+                                "anyOf": [{"type": "integer"}, {"type": "null"}]
+                            }},
+                            {"name": "limit", "in": "query", "required": true, "schema": {
+                                "type": "integer", "exclusiveMinimum": 0, "exclusiveMaximum": 100
+                            }},
+                            {"name": "format", "in": "query", "schema": {"const": "json"}}
+                        ],
+                        "responses": {"200": {"content": {"application/json": {"schema": {
+                            "type": ["array", "null"],
+                            "items": {"type": "object", "examples": [{"id": 1}]}
+                        }}}}}
+                    },
+                    "post": {
+                        "operationId": "create_item",
+                        "requestBody": {"content": {"application/json": {"schema": {
+                            "type": "object",
+                            "required": ["name"],
+                            "properties": {
+                                "name": {"type": "string", "examples": ["widget"]},
+                                "price": {"type": ["number", "null"], "exclusiveMinimum": 0.5}
+                            }
+                        }}}},
+                        "responses": {"201": {"content": {"application/json": {"schema": {
+                            "type": "number", "exclusiveMinimum": 1, "const": 2
+                        }}}}}
+                    }
+                }
+            }
+        });
+
+        let manual = OpenApiConverter::new(spec, None, None).convert();
+        assert_eq!(manual.tools.len(), 2, "webhooks must not become tools");
+        let tool = |name: &str| manual.tools.iter().find(|t| t.name == name).unwrap();
+
+        let list = tool("list_items");
+        let props = list.inputs.properties.as_ref().unwrap();
+        assert_eq!(props["q"]["type"], "string");
+        assert_eq!(props["q"]["nullable"], true);
+        assert_eq!(props["cursor"]["type"], "integer");
+        assert_eq!(props["cursor"]["nullable"], true);
+        assert!(props["cursor"].get("anyOf").is_none());
+        assert_eq!(props["limit"]["exclusiveMinimum"], 0);
+        assert_eq!(props["limit"]["exclusiveMaximum"], 100);
+        assert!(props["limit"].get("minimum").is_none());
+        assert_eq!(props["format"]["type"], "string");
+        assert_eq!(props["format"]["enum"], json!(["json"]));
+        assert_eq!(list.inputs.required, Some(vec!["limit".to_string()]));
+        assert_eq!(list.outputs.type_, "array");
+        assert_eq!(list.outputs.items.as_ref().unwrap()["type"], "object");
+
+        let create = tool("create_item");
+        let body = &create.inputs.properties.as_ref().unwrap()["body"];
+        assert_eq!(body["properties"]["price"]["type"], "number");
+        assert_eq!(body["properties"]["price"]["nullable"], true);
+        assert_eq!(body["properties"]["price"]["exclusiveMinimum"], 0.5);
+        assert_eq!(body["properties"]["name"]["examples"], json!(["widget"]));
+        assert_eq!(create.outputs.type_, "number");
+        // An exclusive bound is not an inclusive `minimum`.
+        assert_eq!(create.outputs.minimum, None);
+        assert_eq!(create.outputs.enum_, Some(vec![json!(2)]));
+    }
+
+    #[test]
+    fn boolean_exclusive_bounds_become_numeric() {
+        let normalize = |schema: Value| {
+            let mut schema = schema.as_object().unwrap().clone();
+            normalize_schema_keywords(&mut schema);
+            Value::Object(schema)
+        };
+        assert_eq!(
+            normalize(
+                json!({"minimum": 0, "exclusiveMinimum": true, "maximum": 9, "exclusiveMaximum": false})
+            ),
+            json!({"exclusiveMinimum": 0, "maximum": 9})
+        );
+        assert_eq!(
+            normalize(json!({"exclusiveMinimum": 0, "maximum": 9})),
+            json!({"exclusiveMinimum": 0, "maximum": 9})
+        );
+        assert_eq!(normalize(json!({"exclusiveMaximum": true})), json!({}));
+    }
+
+    fn outputs_for(responses: Value) -> ToolInputOutputSchema {
+        let converter = OpenApiConverter::new(json!({}), None, None);
+        let op = json!({ "responses": responses });
+        converter.extract_outputs(op.as_object().unwrap())
+    }
+
+    fn ok_of(type_: &str) -> Value {
+        json!({"content": {"application/json": {"schema": {"type": type_}}}})
+    }
+
+    #[test]
+    fn outputs_prefer_200_then_201() {
+        let ok = json!({"content": {"application/json": {"schema": {"type": "array"}}}});
+        let created = json!({"content": {"application/json": {"schema": {"type": "integer"}}}});
+        assert_eq!(
+            outputs_for(json!({"201": created.clone(), "200": ok})).type_,
+            "array"
+        );
+        assert_eq!(
+            outputs_for(json!({"202": ok_of("string"), "201": created})).type_,
+            "integer"
+        );
+    }
+
+    #[test]
+    fn outputs_fall_back_to_lowest_other_2xx() {
+        let out = outputs_for(json!({
+            "400": ok_of("boolean"),
+            "206": ok_of("string"),
+            "202": ok_of("integer"),
+            "default": ok_of("number")
+        }));
+        assert_eq!(out.type_, "integer");
+    }
+
+    #[test]
+    fn outputs_use_default_response_last() {
+        let out = outputs_for(json!({"404": ok_of("boolean"), "default": ok_of("number")}));
+        assert_eq!(out.type_, "number");
+        assert_eq!(
+            outputs_for(json!({"404": ok_of("boolean")})).type_,
+            "object"
+        );
+    }
+
+    #[test]
+    fn outputs_for_204_are_empty_object() {
+        let out = outputs_for(json!({"204": {"description": "Deleted"}}));
+        assert_eq!(out.type_, "object");
+        assert_eq!(out.properties, Some(HashMap::new()));
+        assert_eq!(out.description.as_deref(), Some("Deleted (no content)"));
+
+        let out = outputs_for(json!({"204": {}}));
+        assert_eq!(out.description.as_deref(), Some("No content"));
+    }
+
+    #[test]
+    fn outputs_for_non_json_content_are_strings() {
+        let out = outputs_for(json!({"200": {
+            "description": "Plain greeting",
+            "content": {"text/plain": {"schema": {"type": "object"}}}
+        }}));
+        assert_eq!(out.type_, "string");
+        assert_eq!(
+            out.description.as_deref(),
+            Some("Plain greeting (text/plain)")
+        );
+
+        let out = outputs_for(json!({"200": {
+            "content": {"application/problem+json": {"schema": {"type": "object", "properties": {"title": {"type": "string"}}}}}
+        }}));
+        assert_eq!(out.type_, "object");
+        assert!(out.properties.unwrap().contains_key("title"));
+    }
+
+    #[test]
+    fn servers_are_overridden_per_path_and_operation() {
+        let spec = json!({
+            "info": {"title": "jobs"},
+            "servers": [{
+                "url": "https://{region}.api.example.com/v1",
+                "variables": {"region": {"default": "eu", "enum": ["eu", "us"]}}
+            }],
+            "paths": {
+                "/jobs": {
+                    "get": {"operationId": "list_jobs", "responses": {}},
+                    "post": {
+                        "operationId": "submit_job",
+                        "servers": [{"url": "https://async.example.com"}],
+                        "responses": {}
+                    }
+                },
+                "/reports": {
+                    "servers": [{
+                        "url": "https://reports.example.com/{version}",
+                        "variables": {"version": {"default": "v2"}}
+                    }],
+                    "get": {"operationId": "list_reports", "responses": {}},
+                    "delete": {
+                        "operationId": "purge_reports",
+                        "servers": [{"url": "https://admin.example.com"}],
+                        "responses": {}
+                    }
+                }
+            }
+        });
+
+        let manual = OpenApiConverter::new(spec, None, None).convert();
+        let urls: HashMap<String, String> = manual
+            .tools
+            .iter()
+            .map(|tool| {
+                let prov: HttpProvider =
+                    serde_json::from_value(tool.provider.clone().unwrap()).unwrap();
+                (tool.name.clone(), prov.url)
+            })
+            .collect();
+
+        assert_eq!(urls["list_jobs"], "https://eu.api.example.com/v1/jobs");
+        assert_eq!(urls["submit_job"], "https://async.example.com/jobs");
+        assert_eq!(
+            urls["list_reports"],
+            "https://reports.example.com/v2/reports"
+        );
+        assert_eq!(urls["purge_reports"], "https://admin.example.com/reports");
+    }
+
+    #[test]
+    fn base_url_falls_back_to_spec_url_without_servers() {
+        let spec = json!({"paths": {"/ping": {
+            "get": {"operationId": "ping", "servers": [], "responses": {}}
+        }}});
+        let manual = OpenApiConverter::new(
+            spec,
+            Some("https://specs.example.com/openapi.json".to_string()),
+            None,
+        )
+        .convert();
+        let prov: HttpProvider =
+            serde_json::from_value(manual.tools[0].provider.clone().unwrap()).unwrap();
+        assert_eq!(prov.url, "https://specs.example.com/ping");
+    }
+
+    const PETS_JSON: &str = r#"{
+        "openapi": "3.0.0",
+        "info": {"title": "pets"},
+        "servers": [{"url": "https://pets.example.com"}],
+        "paths": {"/pets": {"get": {"operationId": "listPets", "responses": {}}}}
+    }"#;
+
+    const STORE_YAML: &str = r#"
+openapi: 3.0.0
+info: {title: store}
+servers: [{url: "https://store.example.com"}]
+paths:
+  /orders:
+    get: {operationId: listOrders, responses: {}}
+    post: {operationId: createOrder, responses: {}}
+"#;
+
+    #[tokio::test]
+    async fn new_from_file_reads_json_and_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("pets.json");
+        let yaml_path = dir.path().join("store.yml");
+        std::fs::write(&json_path, PETS_JSON).unwrap();
+        std::fs::write(&yaml_path, STORE_YAML).unwrap();
+
+        let pets = OpenApiConverter::new_from_file(&json_path, None)
+            .await
+            .unwrap();
+        assert_eq!(pets.provider_name(), "pets");
+        assert_eq!(pets.convert().tools[0].name, "listPets");
+
+        let store = OpenApiConverter::new_from_file(&yaml_path, Some("shop".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(store.provider_name(), "shop");
+        assert_eq!(store.convert().tools.len(), 2);
+
+        assert!(
+            OpenApiConverter::new_from_file(dir.path().join("missing.json"), None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn convert_directory_collects_specs_by_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("pets.json"), PETS_JSON).unwrap();
+        std::fs::write(dir.path().join("nested/store.yaml"), STORE_YAML).unwrap();
+        std::fs::write(
+            dir.path().join("common.json"),
+            r#"{"Id": {"type": "string"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a spec").unwrap();
+
+        let manuals = convert_directory(dir.path()).await.unwrap();
+        let mut names: Vec<&String> = manuals.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["pets", "store"]);
+        assert_eq!(manuals["store"].tools.len(), 2);
+
+        std::fs::write(dir.path().join("pets_copy.yaml"), PETS_JSON).unwrap();
+        let err = convert_directory(dir.path()).await.unwrap_err();
+        assert!(err.to_string().contains("'pets'"), "{err}");
+    }
+
+    #[test]
+    fn file_url_path_only_accepts_file_urls() {
+        assert_eq!(
+            file_url_path("file:///specs/pets.json"),
+            Some(PathBuf::from("/specs/pets.json"))
+        );
+        assert_eq!(file_url_path("https://example.com/openapi.json"), None);
+        assert_eq!(file_url_path("not a url"), None);
+    }
+
+    fn filtered_tool_names(options: ConverterOptions) -> Vec<String> {
+        let spec = json!({
+            "paths": {
+                "/users": {
+                    "get": {"operationId": "listUsers", "tags": ["users"], "responses": {}},
+                    "post": {"operationId": "createUser", "tags": ["users", "write"], "responses": {}}
+                },
+                "/users/{id}": {
+                    "delete": {"operationId": "deleteUser", "tags": ["users", "write"], "responses": {}}
+                },
+                "/admin/audit": {
+                    "get": {"operationId": "audit", "tags": ["admin"], "responses": {}}
+                },
+                "/legacy": {
+                    "get": {"operationId": "legacy", "tags": ["users"], "deprecated": true, "responses": {}}
+                }
+            }
+        });
+        let mut names: Vec<String> = OpenApiConverter::new(spec, None, None)
+            .with_options(options)
+            .unwrap()
+            .convert()
+            .tools
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn deprecated_operations_are_excluded_by_default() {
+        assert_eq!(
+            filtered_tool_names(ConverterOptions::default()),
+            vec!["audit", "createUser", "deleteUser", "listUsers"]
+        );
+        let all = filtered_tool_names(ConverterOptions {
+            include_deprecated: true,
+            ..Default::default()
+        });
+        assert!(all.contains(&"legacy".to_string()));
+    }
+
+    #[test]
+    fn exclusions_take_precedence_over_inclusions() {
+        let names = filtered_tool_names(ConverterOptions {
+            include_tags: vec!["users".to_string()],
+            exclude_tags: vec!["write".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(names, vec!["listUsers"]);
+
+        let names = filtered_tool_names(ConverterOptions {
+            include_paths: vec!["/users*".to_string()],
+            exclude_paths: vec!["/users/*".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(names, vec!["createUser", "listUsers"]);
+
+        let names = filtered_tool_names(ConverterOptions {
+            include_paths: vec!["/users*".to_string()],
+            methods: vec!["DELETE".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(names, vec!["deleteUser"]);
+    }
+
+    #[test]
+    fn invalid_path_globs_are_rejected() {
+        let result = OpenApiConverter::new(json!({}), None, None).with_options(ConverterOptions {
+            exclude_paths: vec!["/users/[".to_string()],
+            ..Default::default()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parameter_enums_defaults_and_examples_survive_conversion() {
+        let spec = json!({
+            "openapi": "3.0.0",
+            "paths": {"/pets": {"get": {
+                "operationId": "findPets",
+                "parameters": [
+                    {
+                        "name": "status", "in": "query",
+                        "schema": {"type": "string", "enum": ["available", "sold"], "default": "available"}
+                    },
+                    {
+                        "name": "limit", "in": "query", "example": 25,
+                        "schema": {"type": "integer", "format": "int32", "default": 20, "minimum": 1, "maximum": 100}
+                    },
+                    {
+                        "name": "sort", "in": "query",
+                        "examples": {"newest": {"value": "-created"}, "oldest": {"value": "created"}},
+                        "schema": {"$ref": "#/components/schemas/Sort"}
+                    },
+                    {
+                        "name": "filter", "in": "query", "style": "deepObject",
+                        "schema": {"$ref": "#/components/schemas/Filter"}
+                    }
+                ],
+                "responses": {}
+            }}},
+            "components": {"schemas": {
+                "Sort": {"type": "string", "enum": ["created", "-created"]},
+                "Species": {"type": "string", "enum": ["cat", "dog"], "example": "dog"},
+                "Filter": {
+                    "type": "object",
+                    "properties": {
+                        "species": {"type": "array", "items": {"$ref": "#/components/schemas/Species"}},
+                        "age": {"type": "integer", "minimum": 0, "default": 1}
+                    }
+                }
+            }}
+        });
+
+        let manual = OpenApiConverter::new(spec, None, None).convert();
+        let props = manual.tools[0].inputs.properties.clone().unwrap();
+
+        assert_eq!(props["status"]["enum"], json!(["available", "sold"]));
+        assert_eq!(props["status"]["default"], "available");
+
+        assert_eq!(props["limit"]["type"], "integer");
+        assert_eq!(props["limit"]["default"], 20);
+        assert_eq!(props["limit"]["format"], "int32");
+        assert_eq!(props["limit"]["minimum"], 1);
+        assert_eq!(props["limit"]["maximum"], 100);
+        assert_eq!(props["limit"]["example"], 25);
+
+        assert_eq!(props["sort"]["enum"], json!(["created", "-created"]));
+        assert_eq!(props["sort"]["examples"], json!(["-created", "created"]));
+
+        let filter = &props["filter"]["properties"];
+        assert_eq!(filter["species"]["items"]["enum"], json!(["cat", "dog"]));
+        assert_eq!(filter["species"]["items"]["example"], "dog");
+        assert_eq!(filter["age"]["default"], 1);
+    }
+
+    #[test]
+    fn streaming_operations_emit_stream_providers() {
+        let spec = json!({
+            "info": {"title": "feeds"},
+            "servers": [{"url": "https://feeds.example.com"}],
+            "paths": {
+                "/events": {"post": {
+                    "operationId": "watchEvents",
+                    "tags": ["events"],
+                    "responses": {"200": {"content": {"text/event-stream": {"schema": {"type": "string"}}}}}
+                }},
+                "/export": {"get": {
+                    "operationId": "exportRows",
+                    "responses": {"206": {"content": {"application/x-ndjson; charset=utf-8": {}}}}
+                }},
+                "/rows": {"get": {
+                    "operationId": "listRows",
+                    "responses": {"200": {"content": {"application/json": {}}}}
+                }}
+            }
+        });
+        let manual = OpenApiConverter::new(spec, None, None).convert();
+        let tools: HashMap<String, Tool> = manual
+            .tools
+            .into_iter()
+            .map(|t| (t.name.clone(), t))
+            .collect();
+
+        let watch = &tools["watchEvents"];
+        assert_eq!(watch.tags, vec!["events", "streaming"]);
+        let sse: SseProvider = serde_json::from_value(watch.provider.clone().unwrap()).unwrap();
+        assert_eq!(sse.base.provider_type, ProviderType::Sse);
+        assert_eq!(sse.url, "https://feeds.example.com/events");
+
+        let export = &tools["exportRows"];
+        assert_eq!(export.tags, vec!["streaming"]);
+        let stream: StreamableHttpProvider =
+            serde_json::from_value(export.provider.clone().unwrap()).unwrap();
+        assert_eq!(stream.base.provider_type, ProviderType::HttpStream);
+        assert_eq!(stream.http_method, "GET");
+
+        let list = &tools["listRows"];
+        assert!(list.tags.is_empty());
+        assert_eq!(list.provider.as_ref().unwrap()["provider_type"], "http");
+    }
+
+    #[test]
+    fn mutually_recursive_refs_degrade_to_empty_schemas() {
+        let spec = json!({
+            "paths": {"/a": {"post": {
+                "operationId": "a",
+                "requestBody": {"content": {"application/json": {
+                    "schema": {"$ref": "#/components/schemas/A"}
+                }}},
+                "responses": {}
+            }}},
+            "components": {"schemas": {
+                "A": {"$ref": "#/components/schemas/B"},
+                "B": {"description": "loops back", "$ref": "#/components/schemas/A"}
+            }}
+        });
+        let converter = OpenApiConverter::new(spec, None, None);
+        let manual = converter.convert();
+        let body = &manual.tools[0].inputs.properties.as_ref().unwrap()["body"];
+        assert_eq!(body["description"], "loops back");
+        assert!(body.get("$ref").is_none());
+
+        let report = converter.report();
+        assert!(report.errors.is_empty());
+        assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+        assert!(report.warnings[0].contains("#/components/schemas/A"));
+    }
+
+    #[test]
+    fn malformed_specs_are_reported_instead_of_panicking() {
+        let converter =
+            OpenApiConverter::new(json!({"paths": ["/not", "an", "object"]}), None, None);
+        assert!(converter.convert().tools.is_empty());
+        assert!(converter.report().warnings[0].contains("`paths` is not an object"));
+
+        let converter = OpenApiConverter::new(
+            json!({"paths": {
+                "/broken": "nope",
+                "/ops": {"get": 42, "post": {"operationId": "ok", "responses": {}}},
+                "/dangling": {"get": {"operationId": "dangling", "parameters": [
+                    {"name": "q", "in": "query", "schema": {"$ref": "#/nowhere"}}
+                ], "responses": {}}}
+            }}),
+            None,
+            None,
+        );
+        let mut names: Vec<String> = converter
+            .convert()
+            .tools
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["dangling", "ok"]);
+
+        let report = converter.report();
+        assert_eq!(
+            report.errors,
+            vec![OperationError {
+                path: "/ops".to_string(),
+                method: "get".to_string(),
+                message: "operation is not an object".to_string(),
+            }]
+        );
+        assert!(report.warnings.iter().any(|w| w.contains("'/broken'")));
+        assert!(report.warnings.iter().any(|w| w.contains("#/nowhere")));
+
+        // A clean conversion resets the report.
+        let clean = OpenApiConverter::new(json!({"paths": {}}), None, None);
+        clean.convert();
+        assert!(clean.report().is_empty());
+    }
+
+    #[test]
+    fn pathological_specs_never_panic() {
+        let base = json!({
+            "openapi": "3.1.0",
+            "servers": [{"url": "https://api.example.com", "variables": {"v": {"default": "1"}}}],
+            "paths": {"/items/{id}": {
+                "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}}],
+                "post": {
+                    "operationId": "create",
+                    "tags": ["items"],
+                    "security": [{"key": []}],
+                    "parameters": [{"name": "q", "in": "query", "schema": {"$ref": "#/components/schemas/Item"}}],
+                    "requestBody": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Item"}}}},
+                    "responses": {"201": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Item"}}}}}
+                }
+            }},
+            "components": {
+                "schemas": {"Item": {
+                    "type": ["object", "null"],
+                    "allOf": [{"$ref": "#/components/schemas/Item"}],
+                    "properties": {"child": {"$ref": "#/components/schemas/Item"}}
+                }},
+                "securitySchemes": {"key": {"type": "apiKey", "in": "header", "name": "X-Key"}}
+            }
+        });
+        let junk = [
+            json!(null),
+            json!(true),
+            json!(-1),
+            json!("#/components/schemas/Item"),
+            json!([]),
+            json!([null, {"$ref": "#/"}]),
+            json!({}),
+            json!({"$ref": 5}),
+            json!({"$ref": "#/components/schemas/Item"}),
+            json!({"$ref": "#/paths"}),
+        ];
+
+        // Replace every node of the spec, one at a time, with each junk value.
+        fn pointers(value: &Value, prefix: String, out: &mut Vec<String>) {
+            out.push(prefix.clone());
+            match value {
+                Value::Object(map) => {
+                    for (k, v) in map {
+                        let key = k.replace('~', "~0").replace('/', "~1");
+                        pointers(v, format!("{}/{}", prefix, key), out);
+                    }
+                }
+                Value::Array(items) => {
+                    for (i, v) in items.iter().enumerate() {
+                        pointers(v, format!("{}/{}", prefix, i), out);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut all = Vec::new();
+        pointers(&base, String::new(), &mut all);
+
+        for pointer in all.iter().filter(|p| !p.is_empty()) {
+            for replacement in &junk {
+                let mut spec = base.clone();
+                *spec.pointer_mut(pointer).unwrap() = replacement.clone();
+                let converter = OpenApiConverter::new(spec, None, None);
+                let manual = converter.convert();
+                assert!(manual.tools.len() <= 1, "{pointer}");
+                let _ = converter.report();
+            }
+        }
+    }
+
+    fn oauth_scope(spec: Value, op: Value) -> Option<String> {
+        let converter = OpenApiConverter::new(spec, None, None);
+        match converter.extract_auth(op.as_object().unwrap()) {
+            Some(AuthConfig::OAuth2(auth)) => auth.scope,
+            other => panic!("expected OAuth2 auth, got {other:?}"),
+        }
+    }
+
+    fn oauth_spec(global_security: Value) -> Value {
+        json!({
+            "security": global_security,
+            "components": {"securitySchemes": {
+                "oauth": {"type": "oauth2", "flows": {"clientCredentials": {
+                    "tokenUrl": "https://auth.example.com/token",
+                    "scopes": {"read:pets": "", "write:pets": "", "admin": ""}
+                }}},
+                "key": {"type": "apiKey", "in": "header", "name": "X-Key"}
+            }}
+        })
+    }
+
+    #[test]
+    fn operation_scopes_narrow_oauth2_auth() {
+        let spec = oauth_spec(json!([{"oauth": ["admin"]}]));
+        assert_eq!(
+            oauth_scope(
+                spec.clone(),
+                json!({"security": [{"oauth": ["read:pets"]}]})
+            ),
+            Some("read:pets".to_string())
+        );
+        // Without operation scopes the global requirement's scopes apply.
+        assert_eq!(
+            oauth_scope(spec.clone(), json!({"security": [{"oauth": []}]})),
+            Some("admin".to_string())
+        );
+        assert_eq!(oauth_scope(spec, json!({})), Some("admin".to_string()));
+
+        // Without any requested scopes every declared scope is requested.
+        let declared = oauth_scope(oauth_spec(json!([{"oauth": []}])), json!({})).unwrap();
+        let mut declared: Vec<&str> = declared.split(' ').collect();
+        declared.sort();
+        assert_eq!(declared, vec!["admin", "read:pets", "write:pets"]);
+    }
+
+    #[test]
+    fn multiple_schemes_in_one_requirement_pick_deterministically() {
+        let spec = oauth_spec(json!([]));
+        let op = json!({"security": [{"oauth": ["write:pets"], "key": []}]});
+        for _ in 0..3 {
+            let converter = OpenApiConverter::new(spec.clone(), None, None);
+            match converter.extract_auth(op.as_object().unwrap()) {
+                Some(AuthConfig::ApiKey(auth)) => assert_eq!(auth.var_name, "X-Key"),
+                other => panic!("expected the apiKey scheme, got {other:?}"),
+            }
+        }
+
+        // Unsupported schemes are skipped in favour of the next one.
+        let mut spec = oauth_spec(json!([]));
+        spec["components"]["securitySchemes"]["key"] = json!({"type": "mutualTLS"});
+        assert_eq!(
+            oauth_scope(
+                spec,
+                json!({"security": [{"key": [], "oauth": ["write:pets"]}]})
+            ),
+            Some("write:pets".to_string())
+        );
+    }
 }
@@ -0,0 +1,127 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::http::HttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Url;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+async fn client() -> UtcpClient {
+    UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap()
+}
+
+fn file_provider(name: &str, path: &std::path::Path) -> Arc<HttpProvider> {
+    Arc::new(HttpProvider::new(
+        name.to_string(),
+        Url::from_file_path(path).unwrap().to_string(),
+        "GET".to_string(),
+        None,
+    ))
+}
+
+#[tokio::test]
+async fn test_register_http_provider_from_local_spec_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("weather.json");
+    std::fs::write(
+        &json_path,
+        r#"{
+            "openapi": "3.0.0",
+            "info": {"title": "weather"},
+            "servers": [{"url": "https://weather.example.com"}],
+            "paths": {"/forecast": {"get": {"operationId": "forecast", "responses": {}}}}
+        }"#,
+    )
+    .unwrap();
+    let yaml_path = dir.path().join("geo.yaml");
+    std::fs::write(
+        &yaml_path,
+        r#"
+openapi: 3.0.0
+info: {title: geo}
+servers: [{url: "https://geo.example.com"}]
+paths:
+  /lookup:
+    get: {operationId: lookup, responses: {}}
+"#,
+    )
+    .unwrap();
+
+    let client = client().await;
+    let tools = client
+        .register_tool_provider(file_provider("weather", &json_path))
+        .await
+        .unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "weather.forecast");
+    let provider: HttpProvider =
+        serde_json::from_value(tools[0].provider.clone().unwrap()).unwrap();
+    assert_eq!(provider.url, "https://weather.example.com/forecast");
+
+    let tools = client
+        .register_tool_provider(file_provider("geo", &yaml_path))
+        .await
+        .unwrap();
+    assert_eq!(tools[0].name, "geo.lookup");
+
+    let err = client
+        .register_tool_provider(file_provider("missing", &dir.path().join("missing.json")))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing.json"), "{err}");
+}
+
+#[tokio::test]
+async fn test_openapi_filters_in_provider_json_limit_registered_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec_path = dir.path().join("crm.yaml");
+    std::fs::write(
+        &spec_path,
+        r#"
+openapi: 3.0.0
+info: {title: crm}
+servers: [{url: "https://crm.example.com"}]
+paths:
+  /contacts:
+    get: {operationId: listContacts, tags: [contacts], responses: {}}
+    post: {operationId: createContact, tags: [contacts], responses: {}}
+  /reports:
+    get: {operationId: report, tags: [reporting], responses: {}}
+"#,
+    )
+    .unwrap();
+
+    let provider: HttpProvider = serde_json::from_value(serde_json::json!({
+        "name": "crm",
+        "provider_type": "http",
+        "http_method": "GET",
+        "url": Url::from_file_path(&spec_path).unwrap().to_string(),
+        "openapi_filters": {"include_tags": ["contacts"], "methods": ["get"]}
+    }))
+    .unwrap();
+
+    let tools = client()
+        .await
+        .register_tool_provider(Arc::new(provider))
+        .await
+        .unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["crm.listContacts"]);
+}
@@ -0,0 +1,223 @@
+use crate::config::UtcpClientConfig;
+use crate::openapi::OpenApiConverter;
+use crate::providers::http::HttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Split a multipart body into `(headers, content)` pairs using `boundary`.
+fn parse_multipart(body: &str, boundary: &str) -> Option<Vec<(String, String)>> {
+    let delimiter = format!("--{}", boundary);
+    let rest = body.strip_suffix(&format!("{}--\r\n", delimiter))?;
+    let mut parts = Vec::new();
+    for chunk in rest.split(&delimiter).skip(1) {
+        let chunk = chunk.strip_prefix("\r\n")?.strip_suffix("\r\n")?;
+        let (headers, content) = chunk.split_once("\r\n\r\n")?;
+        parts.push((headers.to_string(), content.to_string()));
+    }
+    Some(parts)
+}
+
+/// Answers `POST /upload` with the parts it received, or 400 if the body is not
+/// well-formed multipart for the boundary announced in `Content-Type`.
+async fn spawn_upload_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let content_type = req
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let path = req.uri().path().to_string();
+        let bytes = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&bytes).to_string();
+
+        let parts = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .filter(|_| path == "/upload")
+            .and_then(|boundary| parse_multipart(&body, boundary));
+        let response = match parts {
+            Some(parts) => Response::new(Body::from(
+                json!(parts
+                    .into_iter()
+                    .map(|(headers, content)| json!({"headers": headers, "content": content}))
+                    .collect::<Vec<Value>>())
+                .to_string(),
+            )),
+            None => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(body))
+                .unwrap(),
+        };
+        Ok(response)
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_openapi_multipart_upload_is_posted_as_form_parts() {
+    let base_url = spawn_upload_server().await;
+    let spec = json!({
+        "info": {"title": "files"},
+        "servers": [{"url": base_url}],
+        "paths": {
+            "/upload": {
+                "post": {
+                    "operationId": "upload",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "multipart/form-data": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["file"],
+                                    "properties": {
+                                        "file": {"type": "string", "format": "binary"},
+                                        "description": {"type": "string"}
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {"200": {"description": "ok"}}
+                }
+            }
+        }
+    });
+    let manual = OpenApiConverter::new(spec, None, None).convert();
+    let tool = manual.tools[0].clone();
+    let props = tool.inputs.properties.clone().unwrap();
+    assert!(props.contains_key("file") && props.contains_key("description"));
+    assert_eq!(tool.inputs.required, Some(vec!["file".to_string()]));
+    let provider: HttpProvider = serde_json::from_value(tool.provider.clone().unwrap()).unwrap();
+    assert_eq!(
+        provider.content_type.as_deref(),
+        Some("multipart/form-data")
+    );
+    assert_eq!(provider.body_field, None);
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![tool])
+        .await
+        .unwrap();
+
+    let args = HashMap::from([
+        (
+            "file".to_string(),
+            json!({"$file": {"content": "aGVsbG8=", "filename": "hello.txt"}}),
+        ),
+        ("description".to_string(), json!("greeting")),
+    ]);
+    let result = client.call_tool("files.upload", args).await.unwrap();
+    assert_eq!(
+        result,
+        json!([
+            {
+                "headers": "Content-Disposition: form-data; name=\"description\"",
+                "content": "greeting"
+            },
+            {
+                "headers": "Content-Disposition: form-data; name=\"file\"; filename=\"hello.txt\"\r\nContent-Type: application/octet-stream",
+                "content": "hello"
+            }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_multipart_file_part_reads_from_path() {
+    let base_url = spawn_upload_server().await;
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut file, b"from disk").unwrap();
+
+    let mut provider = HttpProvider::new(
+        "files".to_string(),
+        format!("{}/upload", base_url),
+        "POST".to_string(),
+        None,
+    );
+    provider.content_type = Some("multipart/form-data".to_string());
+    provider.upload_root = file
+        .path()
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    let tool: Tool = serde_json::from_value(json!({
+        "name": "upload",
+        "description": "",
+        "inputs": {"type": "object"},
+        "outputs": {"type": "object"},
+        "tags": []
+    }))
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![tool])
+        .await
+        .unwrap();
+
+    let path = file.path().to_string_lossy().to_string();
+    let filename = file
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let args = HashMap::from([("file".to_string(), json!({"$file": { "path": path }}))]);
+    let result = client.call_tool("files.upload", args).await.unwrap();
+    assert_eq!(result[0]["content"], json!("from disk"));
+    assert!(result[0]["headers"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("name=\"file\"; filename=\"{}\"", filename)));
+
+    // Without the `$file` marker an object with a "path" is plain data, not a file to read.
+    let args = HashMap::from([("file".to_string(), json!({ "path": path }))]);
+    let result = client.call_tool("files.upload", args).await.unwrap();
+    assert_eq!(
+        result[0]["headers"],
+        json!("Content-Disposition: form-data; name=\"file\"")
+    );
+    assert_eq!(result[0]["content"], json!({ "path": path }).to_string());
+}
@@ -0,0 +1,109 @@
+use crate::config::UtcpClientConfig;
+use crate::openapi::OpenApiConverter;
+use crate::providers::http::HttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Answers only `GET /items/42?verbose=true`, so any other final URL fails the call.
+async fn spawn_items_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let uri = req.uri();
+        let status = if uri.path() == "/items/42" && uri.query() == Some("verbose=true") {
+            StatusCode::OK
+        } else {
+            StatusCode::NOT_FOUND
+        };
+        let body = json!({"path": uri.path(), "query": uri.query()}).to_string();
+        Ok(Response::builder()
+            .status(status)
+            .body(Body::from(body))
+            .unwrap())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_openapi_path_params_are_substituted_by_http_transport() {
+    let base_url = spawn_items_server().await;
+    let spec = json!({
+        "info": {"title": "items"},
+        "servers": [{"url": base_url}],
+        "paths": {
+            "/items/{item_id}": {
+                "get": {
+                    "operationId": "get_item",
+                    "parameters": [
+                        {"name": "item_id", "in": "path", "required": true, "schema": {"type": "integer"}},
+                        {"name": "verbose", "in": "query", "schema": {"type": "boolean"}}
+                    ],
+                    "responses": {"200": {"description": "ok"}}
+                }
+            }
+        }
+    });
+    let manual = OpenApiConverter::new(spec, None, None).convert();
+    let tool = manual.tools[0].clone();
+    let provider: HttpProvider = serde_json::from_value(tool.provider.clone().unwrap()).unwrap();
+    assert_eq!(provider.path_params, Some(vec!["item_id".to_string()]));
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![tool])
+        .await
+        .unwrap();
+
+    let args = HashMap::from([
+        ("item_id".to_string(), json!(42)),
+        ("verbose".to_string(), json!(true)),
+    ]);
+    let result = client.call_tool("items.get_item", args).await.unwrap();
+    assert_eq!(
+        result,
+        json!({"path": "/items/42", "query": "verbose=true"})
+    );
+
+    let err = client
+        .call_tool(
+            "items.get_item",
+            HashMap::from([("verbose".to_string(), json!(true))]),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("item_id"), "{err}");
+}
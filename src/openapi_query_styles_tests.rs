@@ -0,0 +1,110 @@
+use crate::config::UtcpClientConfig;
+use crate::openapi::OpenApiConverter;
+use crate::providers::http::HttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Echoes the raw query string of every request.
+async fn spawn_echo_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let body = json!({ "query": req.uri().query() }).to_string();
+        Ok(Response::new(Body::from(body)))
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_openapi_query_styles_are_serialized_by_http_transport() {
+    let base_url = spawn_echo_server().await;
+    let array = json!({"type": "array", "items": {"type": "string"}});
+    let spec = json!({
+        "info": {"title": "search"},
+        "servers": [{"url": base_url}],
+        "paths": {
+            "/search": {
+                "get": {
+                    "operationId": "find",
+                    "parameters": [
+                        {"name": "exploded", "in": "query", "schema": array},
+                        {"name": "csv", "in": "query", "style": "form", "explode": false, "schema": array},
+                        {"name": "spaced", "in": "query", "style": "spaceDelimited", "schema": array},
+                        {"name": "piped", "in": "query", "style": "pipeDelimited", "explode": false, "schema": array},
+                        {"name": "filter", "in": "query", "style": "deepObject", "explode": true, "schema": {"type": "object"}},
+                        {"name": "limit", "in": "query", "schema": {"type": "integer"}}
+                    ],
+                    "responses": {"200": {"description": "ok"}}
+                }
+            }
+        }
+    });
+
+    let manual = OpenApiConverter::new(spec, None, None).convert();
+    let tool = manual.tools[0].clone();
+    let provider: HttpProvider = serde_json::from_value(tool.provider.clone().unwrap()).unwrap();
+    assert_eq!(provider.param_styles.as_ref().map(|s| s.len()), Some(5));
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![tool])
+        .await
+        .unwrap();
+
+    let ids = json!(["a b", "c", "d|e"]);
+    let cases = [
+        (
+            "exploded",
+            ids.clone(),
+            "exploded=a%20b&exploded=c&exploded=d%7Ce",
+        ),
+        ("csv", ids.clone(), "csv=a%20b,c,d%7Ce"),
+        ("spaced", ids.clone(), "spaced=a%20b%20c%20d%7Ce"),
+        ("piped", ids, "piped=a%20b|c|d%7Ce"),
+        (
+            "filter",
+            json!({"role": "admin", "age": 30}),
+            "filter[age]=30&filter[role]=admin",
+        ),
+        ("limit", json!(10), "limit=10"),
+    ];
+    for (arg, value, expected) in cases {
+        let args = HashMap::from([(arg.to_string(), value)]);
+        let result = client.call_tool("search.find", args).await.unwrap();
+        assert_eq!(result["query"], json!(expected), "style of {arg}");
+    }
+}
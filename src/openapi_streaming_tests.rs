@@ -0,0 +1,191 @@
+use crate::config::UtcpClientConfig;
+use crate::openapi::OpenApiConverter;
+use crate::providers::base::ProviderType;
+use crate::providers::http_stream::StreamableHttpProvider;
+use crate::providers::sse::SseProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Serves the operations at their declared paths: `GET /rooms/{room}/events` streams three
+/// SSE events, `POST /rooms/{room}/messages` one NDJSON line per message in the body. Every
+/// item echoes the request path and query.
+async fn spawn_event_server() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let path = req.uri().path().to_string();
+        let query = req.uri().query().unwrap_or("").to_string();
+        let accepts_events =
+            req.headers().get("accept").and_then(|v| v.to_str().ok()) == Some("text/event-stream");
+        let method = req.method().clone();
+        let bytes = hyper::body::to_bytes(req.into_body()).await.unwrap();
+
+        let events = path
+            .strip_prefix("/rooms/lobby%201/")
+            .filter(|rest| *rest == "events" && accepts_events && method == "GET");
+        if events.is_some() {
+            let body = (1..=3)
+                .map(|n| {
+                    format!(
+                        "data: {}\n\n",
+                        json!({ "seq": n, "path": path, "query": query })
+                    )
+                })
+                .collect::<String>();
+            return Ok(Response::builder()
+                .header("content-type", "text/event-stream")
+                .body(Body::from(body))
+                .unwrap());
+        }
+
+        if path == "/rooms/lobby%201/messages" && method == "POST" {
+            let messages: Vec<Value> = serde_json::from_slice(&bytes).unwrap_or_default();
+            let body = messages
+                .into_iter()
+                .map(|message| {
+                    format!(
+                        "{}\n",
+                        json!({ "message": message, "path": path, "query": query })
+                    )
+                })
+                .collect::<String>();
+            return Ok(Response::builder()
+                .header("content-type", "application/x-ndjson")
+                .body(Body::from(body))
+                .unwrap());
+        }
+
+        Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn collect(client: &UtcpClient, tool: &str, args: HashMap<String, Value>) -> Vec<Value> {
+    let mut stream = client.call_tool_stream(tool, args).await.unwrap();
+    let mut items = Vec::new();
+    while let Some(item) = stream.next().await.unwrap() {
+        items.push(item);
+    }
+    stream.close().await.unwrap();
+    items
+}
+
+#[tokio::test]
+async fn test_openapi_streaming_operations_are_called_at_their_declared_paths() {
+    let base_url = spawn_event_server().await;
+    let room =
+        json!({"name": "room", "in": "path", "required": true, "schema": {"type": "string"}});
+    let spec = json!({
+        "info": {"title": "feeds"},
+        "servers": [{"url": base_url}],
+        "paths": {
+            "/rooms/{room}/events": {
+                "get": {
+                    "operationId": "watchEvents",
+                    "parameters": [room, {
+                        "name": "types", "in": "query", "style": "form", "explode": false,
+                        "schema": {"type": "array", "items": {"type": "string"}}
+                    }],
+                    "responses": {"200": {"content": {"text/event-stream": {}}}}
+                }
+            },
+            "/rooms/{room}/messages": {
+                "post": {
+                    "operationId": "postMessages",
+                    "parameters": [room, {"name": "echo", "in": "query", "schema": {"type": "boolean"}}],
+                    "requestBody": {"content": {"application/json": {"schema": {"type": "array"}}}},
+                    "responses": {"200": {"content": {"application/x-ndjson": {}}}}
+                }
+            }
+        }
+    });
+    let tools = OpenApiConverter::new(spec, None, None).convert().tools;
+    let tool = |name: &str| tools.iter().find(|t| t.name == name).unwrap().clone();
+    let events = tool("watchEvents");
+    assert!(events.tags.contains(&"streaming".to_string()));
+    let provider: SseProvider = serde_json::from_value(events.provider.clone().unwrap()).unwrap();
+    assert_eq!(provider.base.provider_type, ProviderType::Sse);
+    assert!(provider.operation_url);
+    let messages = tool("postMessages");
+    let stream_provider: StreamableHttpProvider =
+        serde_json::from_value(messages.provider.clone().unwrap()).unwrap();
+    assert_eq!(stream_provider.body_field.as_deref(), Some("body"));
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![events])
+        .await
+        .unwrap();
+    let items = collect(
+        &client,
+        "feeds.watchEvents",
+        HashMap::from([
+            ("room".to_string(), json!("lobby 1")),
+            ("types".to_string(), json!(["join", "leave"])),
+        ]),
+    )
+    .await;
+    let echo = |seq: i64| json!({"seq": seq, "path": "/rooms/lobby%201/events", "query": "types=join,leave"});
+    assert_eq!(items, vec![echo(1), echo(2), echo(3)]);
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(stream_provider), vec![messages])
+        .await
+        .unwrap();
+    let items = collect(
+        &client,
+        "feeds.postMessages",
+        HashMap::from([
+            ("room".to_string(), json!("lobby 1")),
+            ("echo".to_string(), json!(true)),
+            ("body".to_string(), json!(["hi", "bye"])),
+        ]),
+    )
+    .await;
+    let echo = |message: &str| json!({"message": message, "path": "/rooms/lobby%201/messages", "query": "echo=true"});
+    assert_eq!(items, vec![echo("hi"), echo("bye")]);
+}
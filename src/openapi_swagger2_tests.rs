@@ -0,0 +1,198 @@
+use crate::config::UtcpClientConfig;
+use crate::openapi::OpenApiConverter;
+use crate::providers::http::HttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Trimmed Swagger 2.0 "petstore classic" document served from `host`.
+fn petstore_classic(host: &str) -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": {"title": "petstore", "version": "1.0.0"},
+        "host": host,
+        "basePath": "/v2",
+        "schemes": ["http"],
+        "paths": {
+            "/pet": {
+                "post": {
+                    "operationId": "addPet",
+                    "consumes": ["application/json"],
+                    "parameters": [{
+                        "in": "body",
+                        "name": "pet",
+                        "description": "Pet object that needs to be added to the store",
+                        "required": true,
+                        "schema": {"$ref": "#/definitions/Pet"}
+                    }],
+                    "responses": {"200": {"description": "successful operation", "schema": {"$ref": "#/definitions/Pet"}}}
+                }
+            },
+            "/pet/{petId}": {
+                "post": {
+                    "operationId": "updatePetWithForm",
+                    "consumes": ["application/x-www-form-urlencoded"],
+                    "parameters": [
+                        {"name": "petId", "in": "path", "required": true, "type": "integer", "format": "int64"},
+                        {"name": "name", "in": "formData", "required": false, "type": "string"},
+                        {"name": "status", "in": "formData", "required": false, "type": "string"}
+                    ],
+                    "responses": {"405": {"description": "Invalid input"}}
+                }
+            },
+            "/pet/{petId}/uploadImage": {
+                "post": {
+                    "operationId": "uploadFile",
+                    "consumes": ["multipart/form-data"],
+                    "parameters": [
+                        {"name": "petId", "in": "path", "required": true, "type": "integer"},
+                        {"name": "additionalMetadata", "in": "formData", "type": "string"},
+                        {"name": "file", "in": "formData", "required": true, "type": "file"}
+                    ],
+                    "responses": {"200": {"description": "successful operation"}}
+                }
+            }
+        },
+        "definitions": {
+            "Pet": {
+                "type": "object",
+                "required": ["name", "photoUrls"],
+                "properties": {
+                    "id": {"type": "integer", "format": "int64"},
+                    "name": {"type": "string", "example": "doggie"},
+                    "photoUrls": {"type": "array", "items": {"type": "string"}},
+                    "status": {"type": "string", "enum": ["available", "pending", "sold"]}
+                }
+            }
+        }
+    })
+}
+
+/// Accepts `POST /v2/pet` with a JSON body and echoes the body back.
+async fn spawn_petstore_server() -> SocketAddr {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let is_json = req
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            == Some("application/json");
+        let matches = req.method() == hyper::Method::POST && req.uri().path() == "/v2/pet";
+        let bytes = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let response = if is_json && matches {
+            Response::new(Body::from(bytes))
+        } else {
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::empty())
+                .unwrap()
+        };
+        Ok(response)
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    addr
+}
+
+fn provider_of(tool: &Tool) -> HttpProvider {
+    serde_json::from_value(tool.provider.clone().unwrap()).unwrap()
+}
+
+#[test]
+fn test_swagger2_form_data_parameters_become_form_fields() {
+    let manual = OpenApiConverter::new(petstore_classic("localhost"), None, None).convert();
+    let tools: HashMap<String, Tool> = manual
+        .tools
+        .into_iter()
+        .map(|t| (t.name.clone(), t))
+        .collect();
+
+    let update = &tools["updatePetWithForm"];
+    let props = update.inputs.properties.as_ref().unwrap();
+    assert!(props.contains_key("name") && props.contains_key("status"));
+    assert_eq!(props["petId"]["format"], "int64");
+    assert_eq!(
+        provider_of(update).content_type.as_deref(),
+        Some("application/x-www-form-urlencoded")
+    );
+
+    let upload = &tools["uploadFile"];
+    let props = upload.inputs.properties.as_ref().unwrap();
+    assert_eq!(props["file"], json!({"type": "string", "format": "binary"}));
+    assert!(upload
+        .inputs
+        .required
+        .as_ref()
+        .unwrap()
+        .contains(&"file".to_string()));
+    assert_eq!(
+        provider_of(upload).content_type.as_deref(),
+        Some("multipart/form-data")
+    );
+}
+
+#[tokio::test]
+async fn test_swagger2_body_parameter_is_posted_as_request_body() {
+    let addr = spawn_petstore_server().await;
+    let manual = OpenApiConverter::new(petstore_classic(&addr.to_string()), None, None).convert();
+    let add_pet = manual
+        .tools
+        .into_iter()
+        .find(|t| t.name == "addPet")
+        .unwrap();
+
+    let props = add_pet.inputs.properties.as_ref().unwrap();
+    assert_eq!(props["body"]["properties"]["name"]["type"], "string");
+    assert_eq!(add_pet.inputs.required, Some(vec!["body".to_string()]));
+    let provider = provider_of(&add_pet);
+    assert_eq!(provider.body_field.as_deref(), Some("body"));
+    assert_eq!(provider.url, format!("http://{}/v2/pet", addr));
+
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    client
+        .register_tool_provider_with_tools(Arc::new(provider), vec![add_pet])
+        .await
+        .unwrap();
+
+    let pet = json!({"name": "doggie", "photoUrls": ["https://example.com/d.png"], "status": "available"});
+    let result = client
+        .call_tool(
+            "petstore.addPet",
+            HashMap::from([("body".to_string(), pet.clone())]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, pet);
+}
@@ -0,0 +1,700 @@
+//! JavaScript snippets, run by QuickJS. Scripts get the same helpers as Rhai scripts, served by
+//! the shared [`ScriptHost`]; values cross between the engine and the host as JSON.
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use rquickjs::context::EvalOptions;
+use rquickjs::function::Rest;
+use rquickjs::{Context, Ctx, Exception, Function, Object, Runtime, Value as JsValue};
+use serde_json::Value;
+
+use super::{
+    base64_decode, base64_encode, json_parse, json_stringify, now_ms, CodeModeConfig, RetainedVars,
+    ScriptHost,
+};
+
+/// Native stack (512KB) a script may use, so deep recursion fails instead of overflowing the
+/// blocking thread's stack
+const MAX_STACK_SIZE: usize = 512 * 1024;
+
+/// Calls and loop iterations QuickJS runs between interrupt checks; the operation budget is
+/// spent in steps of this size
+const INTERRUPT_INTERVAL: u64 = 10_000;
+
+/// Removes `eval` and the function constructors, the ways a script can compile a string
+const DISABLE_EVAL: &str = r#"
+    delete globalThis.eval;
+    for (const f of [function () {}, async function () {}, function* () {}, async function* () {}]) {
+        Object.defineProperty(Object.getPrototypeOf(f), "constructor", { value: undefined });
+    }
+    delete globalThis.Function;
+"#;
+
+/// Evaluate a JavaScript snippet on the current (blocking) thread. The script's top-level
+/// `var` declarations and global assignments are returned as its variables; `let` and
+/// `const` bindings last for one execution.
+pub(super) fn eval_snippet(
+    code: &str,
+    config: &CodeModeConfig,
+    vars: Option<RetainedVars>,
+    host: &ScriptHost,
+    cancelled: &Arc<AtomicBool>,
+) -> Result<(Value, Option<RetainedVars>)> {
+    let runtime =
+        Runtime::new().map_err(|e| anyhow!("Failed to start the JavaScript runtime: {}", e))?;
+    runtime.set_memory_limit(config.max_memory_bytes);
+    runtime.set_max_stack_size(MAX_STACK_SIZE);
+    let over_budget = Arc::new(AtomicBool::new(false));
+    runtime.set_interrupt_handler(Some(interrupt_handler(
+        config.max_operations,
+        over_budget.clone(),
+        cancelled.clone(),
+    )));
+    let context = Context::full(&runtime)
+        .map_err(|e| anyhow!("Failed to start the JavaScript runtime: {}", e))?;
+
+    context.with(|ctx| {
+        let stopped = |ctx: &Ctx<'_>, err: rquickjs::Error| {
+            if over_budget.load(Ordering::Relaxed) {
+                format!(
+                    "Script exceeded its budget of {} operations",
+                    config.max_operations
+                )
+            } else if cancelled.load(Ordering::Relaxed) {
+                "Code execution was cancelled".to_string()
+            } else {
+                describe_error(ctx, err)
+            }
+        };
+
+        if !config.allow_eval {
+            ctx.eval::<(), _>(DISABLE_EVAL)
+                .map_err(|e| anyhow!("Failed to disable eval: {}", describe_error(&ctx, e)))?;
+        }
+        define_helpers(&ctx, host)
+            .map_err(|e| anyhow!("Failed to register helpers: {}", describe_error(&ctx, e)))?;
+
+        // Helpers are globals too; only what the script adds counts as its variables
+        let globals = ctx.globals();
+        let helpers = globals
+            .keys::<String>()
+            .collect::<rquickjs::Result<HashSet<_>>>()
+            .map_err(|e| anyhow!("Failed to list globals: {}", e))?;
+        let retain = vars.is_some();
+        for (name, value) in vars.into_iter().flatten() {
+            to_js(&ctx, &value)
+                .and_then(|value| globals.set(name.as_str(), value))
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to restore variable '{}': {}",
+                        name,
+                        describe_error(&ctx, e)
+                    )
+                })?;
+        }
+
+        let mut options = EvalOptions::default();
+        options.strict = false;
+        let result: JsValue = ctx
+            .eval_with_options(code, options)
+            .map_err(|e| anyhow!("codemode eval error: {}", stopped(&ctx, e)))?;
+        let value = from_js(&ctx, result)
+            .map_err(|e| anyhow!("Failed to convert result: {}", stopped(&ctx, e)))?
+            .unwrap_or(Value::Null);
+        Ok((value, retain.then(|| global_vars(&ctx, &helpers))))
+    })
+}
+
+/// Stop the script once it has spent `max_operations`, or when it is cancelled.
+fn interrupt_handler(
+    max_operations: u64,
+    over_budget: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+) -> Box<dyn FnMut() -> bool + Send + 'static> {
+    let mut spent = 0u64;
+    Box::new(move || {
+        spent += INTERRUPT_INTERVAL;
+        if max_operations > 0 && spent > max_operations {
+            over_budget.store(true, Ordering::Relaxed);
+        }
+        over_budget.load(Ordering::Relaxed) || cancelled.load(Ordering::Relaxed)
+    })
+}
+
+fn define_helpers<'js>(ctx: &Ctx<'js>, host: &ScriptHost) -> rquickjs::Result<()> {
+    let globals = ctx.globals();
+
+    let h = host.clone();
+    define(ctx, &globals, "call_tool", move |args| {
+        h.call_tool(&string_arg(&args, 0, "call_tool")?, object_arg(&args, 1))
+    })?;
+    let h = host.clone();
+    define(ctx, &globals, "call_tool_stream", move |args| {
+        h.call_tool_stream(
+            &string_arg(&args, 0, "call_tool_stream")?,
+            object_arg(&args, 1),
+        )
+    })?;
+    let h = host.clone();
+    define(ctx, &globals, "search_tools", move |args| {
+        let limit = args.get(1).and_then(Value::as_i64).unwrap_or(0);
+        h.search_tools(&string_arg(&args, 0, "search_tools")?, limit)
+    })?;
+    let h = host.clone();
+    define(ctx, &globals, "list_tools", move |_| h.list_tools())?;
+    let h = host.clone();
+    define(ctx, &globals, "get_tool", move |args| {
+        h.get_tool(&string_arg(&args, 0, "get_tool")?)
+    })?;
+
+    // Script output is captured per execution instead of reaching the process streams
+    let console = Object::new(ctx.clone())?;
+    for (name, target) in [("print", &globals), ("log", &console), ("info", &console)] {
+        let out = host.output.clone();
+        define(ctx, target, name, move |args| {
+            out.stdout(&render_line(&args));
+            Ok(Value::Null)
+        })?;
+    }
+    for (name, target) in [
+        ("eprint", &globals),
+        ("debug", &console),
+        ("warn", &console),
+        ("error", &console),
+    ] {
+        let out = host.output.clone();
+        define(ctx, target, name, move |args| {
+            out.stderr(&render_line(&args));
+            Ok(Value::Null)
+        })?;
+    }
+    globals.set("console", console)?;
+    let h = host.clone();
+    define(ctx, &globals, "log", move |args| {
+        h.log(
+            &string_arg(&args, 0, "log")?,
+            &render_line(args.get(1..).unwrap_or_default()),
+        )?;
+        Ok(Value::Null)
+    })?;
+
+    define(ctx, &globals, "json_parse", |args| {
+        json_parse(&string_arg(&args, 0, "json_parse")?)
+    })?;
+    let max_string_size = host.max_string_size;
+    define(ctx, &globals, "json_stringify", move |args| {
+        let value = args.first().cloned().unwrap_or(Value::Null);
+        json_stringify(&value, max_string_size).map(Value::String)
+    })?;
+    define(ctx, &globals, "base64_encode", move |args| {
+        base64_encode(&string_arg(&args, 0, "base64_encode")?, max_string_size).map(Value::String)
+    })?;
+    define(ctx, &globals, "base64_decode", |args| {
+        base64_decode(&string_arg(&args, 0, "base64_decode")?).map(Value::String)
+    })?;
+    define(ctx, &globals, "now_ms", |_| Ok(Value::from(now_ms())))?;
+    Ok(())
+}
+
+/// Define `name` on `target` as a function whose arguments reach `helper` as JSON. The
+/// helper's result is returned to the script as JSON; its error is thrown as an `Error`.
+fn define<'js>(
+    ctx: &Ctx<'js>,
+    target: &Object<'js>,
+    name: &str,
+    helper: impl Fn(Vec<Value>) -> Result<Value> + 'js,
+) -> rquickjs::Result<()> {
+    let function = Function::new(
+        ctx.clone(),
+        move |ctx: Ctx<'js>, args: Rest<JsValue<'js>>| -> rquickjs::Result<JsValue<'js>> {
+            let mut values = Vec::with_capacity(args.0.len());
+            for arg in args.0 {
+                values.push(from_js(&ctx, arg)?.unwrap_or(Value::Null));
+            }
+            match helper(values) {
+                Ok(value) => to_js(&ctx, &value),
+                Err(err) => Err(Exception::throw_message(&ctx, &err.to_string())),
+            }
+        },
+    )?
+    .with_name(name)?;
+    target.set(name, function)
+}
+
+fn string_arg(args: &[Value], index: usize, helper: &str) -> Result<String> {
+    match args.get(index) {
+        Some(Value::String(text)) => Ok(text.clone()),
+        _ => Err(anyhow!(
+            "{} expects a string as argument {}",
+            helper,
+            index + 1
+        )),
+    }
+}
+
+/// The tool arguments at `index`, or an empty object when the script left them out.
+fn object_arg(args: &[Value], index: usize) -> Value {
+    match args.get(index) {
+        None | Some(Value::Null) => Value::Object(serde_json::Map::new()),
+        Some(value) => value.clone(),
+    }
+}
+
+/// Output arguments joined by spaces: strings as they are, other values as JSON.
+fn render_line(args: &[Value]) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The JSON form of `value`, or None for values JSON leaves out, such as functions and
+/// `undefined`.
+fn from_js<'js>(ctx: &Ctx<'js>, value: JsValue<'js>) -> rquickjs::Result<Option<Value>> {
+    let Some(text) = ctx.json_stringify(value)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&text.to_string()?)
+        .map(Some)
+        .map_err(|e| Exception::throw_message(ctx, &e.to_string()))
+}
+
+fn to_js<'js>(ctx: &Ctx<'js>, value: &Value) -> rquickjs::Result<JsValue<'js>> {
+    ctx.json_parse(value.to_string())
+}
+
+/// The globals the script added that have a JSON form, by name.
+fn global_vars(ctx: &Ctx<'_>, helpers: &HashSet<String>) -> RetainedVars {
+    ctx.globals()
+        .props::<String, JsValue>()
+        .filter_map(|prop| {
+            let (name, value) = prop.ok()?;
+            if helpers.contains(&name) {
+                return None;
+            }
+            Some((name, from_js(ctx, value).ok()??))
+        })
+        .collect()
+}
+
+/// Describe an error raised by QuickJS, taking the thrown value for exceptions.
+fn describe_error(ctx: &Ctx<'_>, err: rquickjs::Error) -> String {
+    if !err.is_exception() {
+        return err.to_string();
+    }
+    let thrown = ctx.catch();
+    if let Some(exception) = thrown.as_exception() {
+        let name = exception
+            .get::<_, String>("name")
+            .unwrap_or_else(|_| "Error".to_string());
+        return format!("{}: {}", name, exception.message().unwrap_or_default());
+    }
+    if let Some(text) = thrown.as_string().and_then(|s| s.to_string().ok()) {
+        return text;
+    }
+    match from_js(ctx, thrown) {
+        Ok(Some(value)) => value.to_string(),
+        _ => "uncaught exception".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::codemode::{CodeModeArgs, CodeModeEngine, CodeModeUtcp};
+    use crate::tools::Tool;
+    use crate::transports::stream::boxed_vec_stream;
+    use crate::UtcpClientInterface;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    /// Records calls and answers them like the Rhai tests' mock: tools return 5 and streams
+    /// yield one chunk.
+    #[derive(Default)]
+    struct MockClient {
+        called: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl UtcpClientInterface for MockClient {
+        async fn register_tool_provider(
+            &self,
+            _prov: Arc<dyn crate::providers::base::Provider>,
+        ) -> Result<Vec<Tool>> {
+            Ok(vec![])
+        }
+
+        async fn register_tool_provider_with_tools(
+            &self,
+            _prov: Arc<dyn crate::providers::base::Provider>,
+            tools: Vec<Tool>,
+        ) -> Result<Vec<Tool>> {
+            Ok(tools)
+        }
+
+        async fn deregister_tool_provider(&self, _provider_name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn call_tool(&self, tool_name: &str, args: HashMap<String, Value>) -> Result<Value> {
+            let mut keys: Vec<_> = args.keys().cloned().collect();
+            keys.sort();
+            self.called
+                .lock()
+                .await
+                .push(format!("{}({})", tool_name, keys.join(",")));
+            Ok(json!(5))
+        }
+
+        async fn search_tools(&self, query: &str, _limit: usize) -> Result<Vec<Tool>> {
+            self.called.lock().await.push(format!("search:{query}"));
+            Ok(vec![])
+        }
+
+        async fn list_tools(&self) -> Result<Vec<Tool>> {
+            Ok(vec![serde_json::from_value(json!({
+                "name": "math.add",
+                "description": "Add two numbers",
+                "inputs": {
+                    "type": "object",
+                    "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+                    "required": ["a", "b"]
+                },
+                "outputs": {"type": "number"},
+                "tags": ["math"]
+            }))
+            .unwrap()])
+        }
+
+        fn get_transports(&self) -> HashMap<String, Arc<dyn crate::transports::ClientTransport>> {
+            HashMap::new()
+        }
+
+        async fn call_tool_stream(
+            &self,
+            tool_name: &str,
+            _args: HashMap<String, Value>,
+        ) -> Result<Box<dyn crate::transports::stream::StreamResult>> {
+            self.called.lock().await.push(format!("stream:{tool_name}"));
+            Ok(boxed_vec_stream(vec![Value::String("chunk".into())]))
+        }
+    }
+
+    fn js(code: &str) -> CodeModeArgs {
+        CodeModeArgs {
+            code: code.to_string(),
+            timeout: Some(2_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: Some(CodeModeEngine::JavaScript),
+            expected_schema: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_runs_javascript_and_calls_tools() {
+        let client = Arc::new(MockClient::default());
+        let codemode = CodeModeUtcp::new(client.clone());
+
+        let code = r#"
+            const n = call_tool("math.add", {a: 1, b: 2});
+            const chunks = call_tool_stream("demo.tool");
+            const found = search_tools("demo", 3);
+            ({n: n + 2, chunks, found: found.length})
+        "#;
+        let res = codemode.execute(js(code)).await.unwrap();
+        assert_eq!(res.value, json!({"n": 7, "chunks": ["chunk"], "found": 0}));
+        let calls = client.called.lock().await.clone();
+        assert_eq!(
+            calls,
+            vec!["math.add(a,b)", "stream:demo.tool", "search:demo"]
+        );
+    }
+
+    #[tokio::test]
+    async fn config_selects_the_default_engine() {
+        let codemode = CodeModeUtcp::with_config(
+            Arc::new(MockClient::default()),
+            CodeModeConfig {
+                engine: CodeModeEngine::JavaScript,
+                ..CodeModeConfig::default()
+            },
+        );
+        let mut args = js("[1, 2, 3].map((x) => x * 2)");
+        args.engine = None;
+        let res = codemode.execute(args.clone()).await.unwrap();
+        assert_eq!(res.value, json!([2, 4, 6]));
+
+        // The snippet's own choice wins
+        args.code = "[1, 2, 3].map(|x| x * 2)".to_string();
+        args.engine = Some(CodeModeEngine::Rhai);
+        let res = codemode.execute(args).await.unwrap();
+        assert_eq!(res.value, json!([2, 4, 6]));
+    }
+
+    #[tokio::test]
+    async fn scripts_read_tool_metadata() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient::default()));
+        let code = r#"
+            const spec = get_tool("add");
+            ({names: list_tools().map((t) => t.name), required: spec.required, missing: get_tool("nope")})
+        "#;
+        let res = codemode.execute(js(code)).await.unwrap();
+        assert_eq!(
+            res.value,
+            json!({"names": ["math.add"], "required": ["a", "b"], "missing": null})
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_captures_script_output() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient::default()));
+        let code = r#"
+            print("starting");
+            console.log("got", call_tool("math.add", {}), {ok: true});
+            console.error("careful");
+            log("warn", "almost done");
+            undefined
+        "#;
+        let res = codemode.execute(js(code)).await.unwrap();
+        assert_eq!(res.value, Value::Null);
+        assert_eq!(res.stdout, "starting\ngot 5 {\"ok\":true}\n");
+        assert_eq!(res.stderr, "careful\n[WARN] almost done\n");
+
+        let err = codemode
+            .execute(js(r#"log("loud", "hi"); 1"#))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Unknown log level 'loud'"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn helper_errors_are_catchable() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient::default()));
+        let code = r#"
+            const caught = [];
+            for (const call of [
+                () => call_tool("", {}),
+                () => call_tool("math.add", [1, 2]),
+                () => json_parse("{oops"),
+                () => base64_decode("***"),
+            ]) {
+                try { call(); } catch (e) { caught.push(e.message); }
+            }
+            caught
+        "#;
+        let res = codemode.execute(js(code)).await.unwrap();
+        assert_eq!(
+            res.value,
+            json!([
+                "Invalid tool name length",
+                "call_tool expects object args",
+                "json_parse: invalid JSON: key must be a string at line 1 column 2",
+                "base64_decode: invalid base64: Invalid byte 42, offset 0."
+            ])
+        );
+
+        let err = codemode.execute(js(r#"throw "boom""#)).await.unwrap_err();
+        assert_eq!(err.to_string(), "codemode eval error: boom");
+        let err = codemode.execute(js("null.field")).await.unwrap_err();
+        assert!(err.to_string().contains("TypeError"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn utilities_round_trip() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient::default()));
+        let code = r#"
+            const data = json_parse('{"a": [1, 2], "b": "x"}');
+            const text = json_stringify(data);
+            ({same: JSON.stringify(json_parse(text)) === JSON.stringify(data),
+              b64: base64_decode(base64_encode("héllo")),
+              clock: now_ms() > 0})
+        "#;
+        let res = codemode.execute(js(code)).await.unwrap();
+        assert_eq!(
+            res.value,
+            json!({"same": true, "b64": "héllo", "clock": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn config_operation_budget_controls_loops() {
+        let client = Arc::new(MockClient::default());
+        let code = "let sum = 0; for (let i = 0; i < 500000; i++) { sum += i; } sum";
+
+        let err = CodeModeUtcp::new(client.clone())
+            .execute(js(code))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("operations"), "{}", err);
+
+        let raised = CodeModeUtcp::with_config(
+            client.clone(),
+            CodeModeConfig {
+                max_operations: 10_000_000,
+                ..CodeModeConfig::default()
+            },
+        );
+        let res = raised.execute(js(code)).await.unwrap();
+        assert_eq!(res.value, json!(124_999_750_000u64));
+
+        // Callbacks run by built-ins spend the same budget
+        let err = CodeModeUtcp::new(client)
+            .execute(js("[1].forEach(() => { for (;;) {} })"))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("exceeded its budget of 100000 operations"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn security_stops_scripts_at_their_timeout() {
+        let codemode = CodeModeUtcp::with_config(
+            Arc::new(MockClient::default()),
+            CodeModeConfig {
+                max_operations: 0,
+                ..CodeModeConfig::default()
+            },
+        );
+        let mut args = js("while (true) {}");
+        args.timeout = Some(50);
+        let err = codemode.execute(args).await.unwrap_err();
+        assert!(err.to_string().contains("exceeded its timeout"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn security_limits_memory() {
+        let codemode = CodeModeUtcp::with_config(
+            Arc::new(MockClient::default()),
+            CodeModeConfig {
+                max_memory_bytes: 4_000_000,
+                ..CodeModeConfig::default()
+            },
+        );
+        let err = codemode
+            .execute(js(
+                r#"let s = "x"; while (s.length < 8000000) { s += s; } s.length"#,
+            ))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("out of memory"), "{}", err);
+
+        let err = codemode
+            .execute(js("function f(n) { return f(n + 1) + 1; } f(0)"))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("call stack size exceeded"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn security_shares_code_validation_and_tool_policy() {
+        let client = Arc::new(MockClient::default());
+        let codemode = CodeModeUtcp::with_config(
+            client.clone(),
+            CodeModeConfig {
+                max_code_size: 64,
+                extra_banned_patterns: vec!["search_tools".to_string()],
+                ..CodeModeConfig::default()
+            },
+        );
+        let err = codemode
+            .execute(js(r#"search_tools("a", 1)"#))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("prohibited pattern: 'search_tools'"));
+        let err = codemode.execute(js(&"1 + ".repeat(20))).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum allowed 64 bytes"));
+
+        let codemode = CodeModeUtcp::new(client.clone());
+        let mut args = js(r#"call_tool("math.sub", {a: 1})"#);
+        args.allowed_tools = Some(vec!["math.add".to_string()]);
+        let err = codemode.execute(args).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Tool 'math.sub' is not in the allowed tools for this execution"));
+
+        let mut args = js(r#"call_tool("math.add", {a: 1, b: 2})"#);
+        args.dry_run = true;
+        let res = codemode.execute(args).await.unwrap();
+        assert_eq!(res.value, json!(0));
+        assert_eq!(res.plan.len(), 1);
+        assert_eq!(
+            res.plan[0].args,
+            HashMap::from([("a".into(), json!(1)), ("b".into(), json!(2))])
+        );
+        assert!(client.called.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn security_removes_eval_unless_allowed() {
+        let client = Arc::new(MockClient::default());
+        for code in [
+            "eval('1 + 1')",
+            "new Function('return 1')()",
+            "(() => {}).constructor('return 1')()",
+            "(async () => {}).constructor('return 1')",
+        ] {
+            let err = CodeModeUtcp::new(client.clone())
+                .execute(js(code))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Error"), "{}: {}", code, err);
+        }
+
+        let permissive = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                allow_eval: true,
+                ..CodeModeConfig::default()
+            },
+        );
+        let res = permissive.execute(js("eval('1 + 1')")).await.unwrap();
+        assert_eq!(res.value, json!(2));
+    }
+
+    #[tokio::test]
+    async fn sessions_keep_global_variables() {
+        let session = CodeModeUtcp::new(Arc::new(MockClient::default())).session();
+        session
+            .set_var("user", json!({"name": "Ada"}))
+            .await
+            .unwrap();
+
+        session
+            .execute(js(
+                r#"var total = call_tool("math.add", {}); let scratch = 1; user.seen = true;"#,
+            ))
+            .await
+            .unwrap();
+        let res = session
+            .execute(js("`${user.name}: ${total + 1}`"))
+            .await
+            .unwrap();
+        assert_eq!(res.value, json!("Ada: 6"));
+        assert_eq!(session.var_names().await, vec!["total", "user"]);
+        assert_eq!(
+            session.get_var("user").await,
+            Some(json!({"name": "Ada", "seen": true}))
+        );
+    }
+}
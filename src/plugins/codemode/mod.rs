@@ -1,18 +1,30 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 use anyhow::{anyhow, Result};
-use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use rhai::{
+    ASTFlags, ASTNode, Dynamic, Engine, EvalAltResult, Expr, Map, OptimizationLevel, Scope, Stmt,
+    AST,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::runtime::{Builder, RuntimeFlavor};
 
+use crate::errors::{SchemaMismatch, UtcpError};
 use crate::security;
 use crate::tools::{Tool, ToolInputOutputSchema};
 use crate::UtcpClientInterface;
 
+#[cfg(feature = "javascript")]
+mod javascript;
+mod session;
+
+pub use session::CodeModeSession;
+
 // Security configuration constants
 /// Maximum code snippet size (100KB) to prevent DoS attacks
 const MAX_CODE_SIZE: usize = 100_000;
@@ -26,6 +38,18 @@ const DEFAULT_TIMEOUT_MS: u64 = 5_000;
 /// Maximum size for script output (10MB) to prevent memory exhaustion
 const MAX_OUTPUT_SIZE: usize = 10_000_000;
 
+/// Marker appended to a capture buffer once it reaches MAX_OUTPUT_SIZE
+const TRUNCATION_MARKER: &str = "...[truncated]";
+
+/// Log levels accepted by the `log(level, msg)` script helper
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Maximum size (10MB) of the variables a session retains between executions
+const MAX_SESSION_BYTES: usize = 10_000_000;
+
+/// Maximum number of items `call_tool_stream` collects from one stream
+const MAX_STREAM_ITEMS: usize = 10_000;
+
 /// Maximum operations per script execution
 const MAX_OPERATIONS: u64 = 100_000;
 
@@ -42,39 +66,125 @@ const MAX_MAP_SIZE: usize = 10_000;
 /// Maximum number of modules
 const MAX_MODULES: usize = 16;
 
-/// Dangerous code patterns that are prohibited
-const DANGEROUS_PATTERNS: &[&str] = &[
-    "eval(",
-    "import ",
-    "fn ",        // Function definitions could be abused
-    "while true", // Infinite loops
-    "loop {",     // Infinite loops
-];
+/// Maximum heap (64MB) a JavaScript script may allocate
+const MAX_SCRIPT_MEMORY: usize = 64_000_000;
+
+/// Name prefix Rhai gives the functions closures compile to
+const ANONYMOUS_FN_PREFIX: &str = "anon$";
+
+/// The language codemode snippets are written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeModeEngine {
+    /// Rhai, with its Rust-like syntax.
+    #[default]
+    Rhai,
+    /// JavaScript, run by QuickJS; requires the `javascript` feature.
+    JavaScript,
+}
+
+/// Sandbox limits applied to every script a [`CodeModeUtcp`] runs. The defaults match the
+/// built-in limits; a Rhai limit set to 0 is disabled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeModeConfig {
+    /// Operations a script may perform before it is stopped.
+    pub max_operations: u64,
+    /// Largest array a script may build.
+    pub max_array_size: usize,
+    /// Largest object map a script may build.
+    pub max_map_size: usize,
+    /// Longest string, in bytes, a script may build.
+    pub max_string_size: usize,
+    /// Largest snippet, in bytes, accepted for execution.
+    pub max_code_size: usize,
+    /// Highest timeout a caller may request.
+    pub max_timeout_ms: u64,
+    /// Items `call_tool_stream` collects before failing.
+    pub max_stream_items: usize,
+    /// Substrings rejected anywhere in a snippet, comments and strings included.
+    pub extra_banned_patterns: Vec<String>,
+    /// Allow scripts to define named functions with `fn`. Closures are always allowed.
+    pub allow_fn_definitions: bool,
+    /// Allow `import` statements.
+    pub allow_imports: bool,
+    /// Allow calls to `eval`.
+    pub allow_eval: bool,
+    /// Allow `loop` and loops whose condition folds to a constant `true`. Conditions that are
+    /// always true but not constant, like `x == x`, are not detected: `max_operations` is what
+    /// bounds every loop.
+    pub allow_unbounded_loops: bool,
+    /// Total size, in bytes of JSON, of the variables a [`CodeModeSession`] retains.
+    pub max_session_bytes: usize,
+    /// The only tools scripts may call; `None` allows every tool. `CodeModeArgs::allowed_tools`
+    /// can narrow this list but not widen it.
+    pub allowed_tools: Option<Vec<String>>,
+    /// Language of snippets that don't choose one in `CodeModeArgs::engine`.
+    pub engine: CodeModeEngine,
+    /// Heap, in bytes, a JavaScript script may allocate. Rhai scripts are held to the array,
+    /// map and string sizes instead.
+    pub max_memory_bytes: usize,
+}
+
+impl Default for CodeModeConfig {
+    fn default() -> Self {
+        Self {
+            max_operations: MAX_OPERATIONS,
+            max_array_size: MAX_ARRAY_SIZE,
+            max_map_size: MAX_MAP_SIZE,
+            max_string_size: MAX_STRING_SIZE,
+            max_code_size: MAX_CODE_SIZE,
+            max_timeout_ms: MAX_TIMEOUT_MS,
+            max_stream_items: MAX_STREAM_ITEMS,
+            extra_banned_patterns: Vec::new(),
+            allow_fn_definitions: false,
+            allow_imports: false,
+            allow_eval: false,
+            allow_unbounded_loops: false,
+            max_session_bytes: MAX_SESSION_BYTES,
+            allowed_tools: None,
+            engine: CodeModeEngine::Rhai,
+            max_memory_bytes: MAX_SCRIPT_MEMORY,
+        }
+    }
+}
 
-/// Minimal facade exposing UTCP calls to Rhai scripts executed by CodeMode.
+/// Minimal facade exposing UTCP calls to Rhai and JavaScript scripts executed by CodeMode.
 pub struct CodeModeUtcp {
     client: Arc<dyn UtcpClientInterface>,
+    config: CodeModeConfig,
 }
 
 impl CodeModeUtcp {
     /// Wrap an `UtcpClientInterface` so codemode scripts can invoke tools.
     pub fn new(client: Arc<dyn UtcpClientInterface>) -> Self {
-        Self { client }
+        Self::with_config(client, CodeModeConfig::default())
+    }
+
+    /// Like [`CodeModeUtcp::new`], running scripts under the limits in `config`.
+    pub fn with_config(client: Arc<dyn UtcpClientInterface>, config: CodeModeConfig) -> Self {
+        Self { client, config }
     }
 
-    /// Validates code for security issues before execution.
+    /// The sandbox limits scripts run under.
+    pub fn config(&self) -> &CodeModeConfig {
+        &self.config
+    }
+
+    /// Fast checks run before a snippet is parsed; the parsed script is checked by
+    /// `check_script`.
     fn validate_code(&self, code: &str) -> Result<()> {
         // Check code size
-        if code.len() > MAX_CODE_SIZE {
+        if code.len() > self.config.max_code_size {
             return Err(anyhow!(
                 "Code size {} bytes exceeds maximum allowed {} bytes",
                 code.len(),
-                MAX_CODE_SIZE
+                self.config.max_code_size
             ));
         }
 
-        // Check for dangerous patterns
-        for pattern in DANGEROUS_PATTERNS {
+        // Check for banned patterns
+        for pattern in &self.config.extra_banned_patterns {
             if code.contains(pattern) {
                 return Err(anyhow!("Code contains prohibited pattern: '{}'", pattern));
             }
@@ -85,34 +195,96 @@ impl CodeModeUtcp {
 
     /// Execute a snippet or JSON payload, returning the resulting value and captured output.
     pub async fn execute(&self, args: CodeModeArgs) -> Result<CodeModeResult> {
+        let (result, _) = self.run(args, None).await?;
+        Ok(result)
+    }
+
+    /// Start a session whose variables carry over from one execution to the next.
+    pub fn session(&self) -> CodeModeSession {
+        CodeModeSession::new(CodeModeUtcp::with_config(
+            self.client.clone(),
+            self.config.clone(),
+        ))
+    }
+
+    /// Execute a snippet. With `vars`, the script starts with those variables in scope, and
+    /// the variables in scope when it finishes are returned next to the result.
+    async fn run(
+        &self,
+        args: CodeModeArgs,
+        vars: Option<RetainedVars>,
+    ) -> Result<(CodeModeResult, Option<RetainedVars>)> {
         // Validate code before execution
         self.validate_code(&args.code)?;
 
         // Determine and validate timeout
-        let timeout_ms = args.timeout.unwrap_or(DEFAULT_TIMEOUT_MS);
-        security::validate_timeout(timeout_ms, MAX_TIMEOUT_MS)?;
+        let max_timeout_ms = self.config.max_timeout_ms;
+        let timeout_ms = args
+            .timeout
+            .unwrap_or(DEFAULT_TIMEOUT_MS.min(max_timeout_ms));
+        security::validate_timeout(timeout_ms, max_timeout_ms)?;
 
         // If it's JSON already, return it directly (no execution needed)
         if let Ok(json) = serde_json::from_str::<Value>(&args.code) {
-            return Ok(CodeModeResult {
+            check_result(&args, &json)?;
+            let result = CodeModeResult {
                 value: json,
                 stdout: String::new(),
                 stderr: String::new(),
-            });
+                plan: Vec::new(),
+            };
+            return Ok((result, vars));
         }
 
+        // The script runs on a blocking thread and hands its tool calls back to this task,
+        // so execution never blocks the runtime it was called from
+        let engine = args.engine.unwrap_or(self.config.engine);
+        let output = OutputCapture::new(MAX_OUTPUT_SIZE);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (requests, pending) = mpsc::unbounded_channel();
+        let script = {
+            let code = args.code.clone();
+            let cancelled = cancelled.clone();
+            let config = self.config.clone();
+            let host = ScriptHost::new(ToolBridge { requests }, output.clone(), &config);
+            tokio::task::spawn_blocking(move || match engine {
+                CodeModeEngine::Rhai => {
+                    Self::eval_rusty_snippet(&code, &config, vars, &host, &cancelled)
+                }
+                CodeModeEngine::JavaScript => {
+                    eval_javascript(&code, &config, vars, &host, &cancelled)
+                }
+            })
+        };
+        let mut plan = args.dry_run.then(Vec::new);
+        let driver = drive_tool_requests(
+            self.client.clone(),
+            pending,
+            self.config.max_stream_items,
+            [
+                self.config.allowed_tools.clone(),
+                args.allowed_tools.clone(),
+            ],
+            plan.as_mut(),
+        );
+        let run = async {
+            let (_, result) = tokio::join!(driver, script);
+            result.map_err(|e| anyhow!("codemode eval task failed: {}", e))?
+        };
+
         // Execute with timeout
-        let result = tokio::time::timeout(
-            Duration::from_millis(timeout_ms),
-            self.eval_rusty_snippet(&args.code, Some(timeout_ms)),
-        )
-        .await;
+        let result = tokio::time::timeout(Duration::from_millis(timeout_ms), run).await;
 
-        let value = match result {
+        let (value, vars) = match result {
             Ok(Ok(v)) => v,
             Ok(Err(e)) => return Err(e),
             Err(_) => {
-                return Err(anyhow!("Code execution timed out after {}ms", timeout_ms));
+                // Stop the script at its next operation
+                cancelled.store(true, Ordering::Relaxed);
+                return Err(anyhow!(
+                    "Code execution exceeded its timeout of {}ms",
+                    timeout_ms
+                ));
             }
         };
 
@@ -126,11 +298,16 @@ impl CodeModeUtcp {
             ));
         }
 
-        Ok(CodeModeResult {
+        check_result(&args, &value)?;
+
+        let (stdout, stderr) = output.take();
+        let result = CodeModeResult {
             value,
-            stdout: String::new(),
-            stderr: String::new(),
-        })
+            stdout,
+            stderr,
+            plan: plan.unwrap_or_default(),
+        };
+        Ok((result, vars))
     }
 
     fn tool_schema(&self) -> Tool {
@@ -138,7 +315,6 @@ impl CodeModeUtcp {
             name: "codemode.run_code".to_string(),
             description: "Execute a Rust-like snippet with access to UTCP tools.".to_string(),
             inputs: ToolInputOutputSchema {
-                type_: "object".to_string(),
                 properties: Some(HashMap::from([
                     (
                         "code".to_string(),
@@ -148,31 +324,32 @@ impl CodeModeUtcp {
                         "timeout".to_string(),
                         serde_json::json!({"type": "integer", "description": "Timeout ms"}),
                     ),
+                    (
+                        "dry_run".to_string(),
+                        serde_json::json!({"type": "boolean", "description": "Record tool calls without making them"}),
+                    ),
+                    (
+                        "engine".to_string(),
+                        serde_json::json!({"type": "string", "enum": ["rhai", "javascript"], "description": "Snippet language"}),
+                    ),
+                    (
+                        "expected_schema".to_string(),
+                        serde_json::json!({"type": "object", "description": "JSON schema the result must match"}),
+                    ),
                 ])),
                 required: Some(vec!["code".to_string()]),
-                description: None,
                 title: Some("CodeModeArgs".to_string()),
-                items: None,
-                enum_: None,
-                minimum: None,
-                maximum: None,
-                format: None,
+                ..ToolInputOutputSchema::new("object")
             },
             outputs: ToolInputOutputSchema {
-                type_: "object".to_string(),
                 properties: Some(HashMap::from([
                     ("value".to_string(), serde_json::json!({"type": "string"})),
                     ("stdout".to_string(), serde_json::json!({"type": "string"})),
                     ("stderr".to_string(), serde_json::json!({"type": "string"})),
+                    ("plan".to_string(), serde_json::json!({"type": "array"})),
                 ])),
-                required: None,
-                description: None,
                 title: Some("CodeModeResult".to_string()),
-                items: None,
-                enum_: None,
-                minimum: None,
-                maximum: None,
-                format: None,
+                ..ToolInputOutputSchema::new("object")
             },
             tags: vec!["codemode".to_string(), "utcp".to_string()],
             average_response_size: None,
@@ -180,158 +357,128 @@ impl CodeModeUtcp {
         }
     }
 
-    fn build_engine(&self) -> Engine {
+    fn build_engine(
+        config: &CodeModeConfig,
+        host: &ScriptHost,
+        cancelled: &Arc<AtomicBool>,
+    ) -> Engine {
         let mut engine = Engine::new();
 
         // Security: Comprehensive sandboxing using centralized constants
         engine.set_max_expr_depths(MAX_EXPR_DEPTH.0, MAX_EXPR_DEPTH.1);
-        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_operations(config.max_operations);
+        // Folds constant loop conditions such as `1 == 1` before `check_script` sees them
+        engine.set_optimization_level(OptimizationLevel::Simple);
         engine.set_max_modules(MAX_MODULES);
-        engine.set_max_string_size(MAX_STRING_SIZE);
-        engine.set_max_array_size(MAX_ARRAY_SIZE);
-        engine.set_max_map_size(MAX_MAP_SIZE);
+        engine.set_max_string_size(config.max_string_size);
+        engine.set_max_array_size(config.max_array_size);
+        engine.set_max_map_size(config.max_map_size);
+
+        let cancelled = cancelled.clone();
+        engine.on_progress(move |_| {
+            if cancelled.load(Ordering::Relaxed) {
+                Some("Code execution was cancelled".into())
+            } else {
+                None
+            }
+        });
 
         // Note: File I/O and other dangerous operations are disabled by default in Rhai
         // when not explicitly importing the std modules
 
         engine.register_fn("sprintf", sprintf);
+        register_utilities(&mut engine, config.max_string_size);
+
+        // Script output is captured per execution instead of reaching the process streams
+        let out = host.output.clone();
+        engine.on_print(move |text| out.stdout(text));
+        let out = host.output.clone();
+        engine.on_debug(move |text, _source, pos| {
+            if pos.is_none() {
+                out.stderr(text);
+            } else {
+                out.stderr(&format!("{:?} | {}", pos, text));
+            }
+        });
+        let out = host.output.clone();
+        engine.register_fn("eprint", move |text: &str| out.stderr(text));
+        let helpers = host.clone();
+        engine.register_fn(
+            "log",
+            move |level: &str, msg: &str| -> Result<(), Box<EvalAltResult>> {
+                helpers
+                    .log(level, msg)
+                    .map_err(|e| runtime_error(e.to_string()))
+            },
+        );
 
-        let client = self.client.clone();
+        let helpers = host.clone();
         engine.register_fn(
             "call_tool",
             move |name: &str, map: Map| -> Result<Dynamic, Box<EvalAltResult>> {
-                // Security: Validate tool name format
-                if name.is_empty() || name.len() > 200 {
-                    return Err(EvalAltResult::ErrorRuntime(
-                        "Invalid tool name length".into(),
-                        rhai::Position::NONE,
-                    )
-                    .into());
-                }
-
-                let args_val = serde_json::to_value(map).map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?;
-                let args = value_to_map(args_val)?;
-
-                let res = block_on_any_runtime(async { client.call_tool(name, args).await })
-                    .map_err(|e| {
-                        EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                    })?;
-
-                Ok(rhai::serde::to_dynamic(res).map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?)
+                to_script(helpers.call_tool(name, map_to_value(map)?))
             },
         );
 
-        let client = self.client.clone();
+        let helpers = host.clone();
         engine.register_fn(
             "call_tool_stream",
             move |name: &str, map: Map| -> Result<Dynamic, Box<EvalAltResult>> {
-                // Security: Validate tool name format
-                if name.is_empty() || name.len() > 200 {
-                    return Err(EvalAltResult::ErrorRuntime(
-                        "Invalid tool name length".into(),
-                        rhai::Position::NONE,
-                    )
-                    .into());
-                }
-
-                let args_val = serde_json::to_value(map).map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?;
-                let args = value_to_map(args_val)?;
-
-                let mut stream =
-                    block_on_any_runtime(async { client.call_tool_stream(name, args).await })
-                        .map_err(|e| {
-                            EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                        })?;
-
-                let mut items = Vec::new();
-                // Security: Limit maximum number of stream items to prevent memory exhaustion
-                const MAX_STREAM_ITEMS: usize = 10_000;
-
-                loop {
-                    if items.len() >= MAX_STREAM_ITEMS {
-                        return Err(EvalAltResult::ErrorRuntime(
-                            format!("Stream exceeded maximum {} items", MAX_STREAM_ITEMS).into(),
-                            rhai::Position::NONE,
-                        )
-                        .into());
-                    }
-
-                    let next =
-                        block_on_any_runtime(async { stream.next().await }).map_err(|e| {
-                            EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                        })?;
-                    match next {
-                        Some(value) => items.push(value),
-                        None => break,
-                    }
-                }
-
-                if let Err(e) = block_on_any_runtime(async { stream.close().await }) {
-                    return Err(EvalAltResult::ErrorRuntime(
-                        e.to_string().into(),
-                        rhai::Position::NONE,
-                    )
-                    .into());
-                }
-
-                Ok(rhai::serde::to_dynamic(items).map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?)
+                to_script(helpers.call_tool_stream(name, map_to_value(map)?))
             },
         );
 
-        let client = self.client.clone();
+        let helpers = host.clone();
         engine.register_fn(
             "search_tools",
             move |query: &str, limit: i64| -> Result<Dynamic, Box<EvalAltResult>> {
-                // Security: Validate query length
-                if query.len() > 1000 {
-                    return Err(EvalAltResult::ErrorRuntime(
-                        "Search query too long (max 1000 chars)".into(),
-                        rhai::Position::NONE,
-                    )
-                    .into());
-                }
+                to_script(helpers.search_tools(query, limit))
+            },
+        );
 
-                // Security: Enforce reasonable search limit
-                const MAX_SEARCH_LIMIT: i64 = 500;
-                let safe_limit = if limit <= 0 || limit > MAX_SEARCH_LIMIT {
-                    MAX_SEARCH_LIMIT
-                } else {
-                    limit
-                };
+        let helpers = host.clone();
+        engine.register_fn(
+            "list_tools",
+            move || -> Result<Dynamic, Box<EvalAltResult>> { to_script(helpers.list_tools()) },
+        );
 
-                let res = block_on_any_runtime(async {
-                    client.search_tools(query, safe_limit as usize).await
-                })
-                .map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?;
-                Ok(rhai::serde::to_dynamic(res).map_err(|e| {
-                    EvalAltResult::ErrorRuntime(e.to_string().into(), rhai::Position::NONE)
-                })?)
+        let helpers = host.clone();
+        engine.register_fn(
+            "get_tool",
+            move |name: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                to_script(helpers.get_tool(name))
             },
         );
 
         engine
     }
 
-    async fn eval_rusty_snippet(&self, code: &str, _timeout_ms: Option<u64>) -> Result<Value> {
-        let wrapped = format!("let __out = {{ {} }};\n__out", code);
-        let engine = self.build_engine();
+    /// Evaluate a snippet on the current (blocking) thread, sending tool calls through `host`.
+    fn eval_rusty_snippet(
+        code: &str,
+        config: &CodeModeConfig,
+        vars: Option<RetainedVars>,
+        host: &ScriptHost,
+        cancelled: &Arc<AtomicBool>,
+    ) -> Result<(Value, Option<RetainedVars>)> {
+        let engine = Self::build_engine(config, host, cancelled);
+        let ast = engine
+            .compile(code)
+            .map_err(|e| anyhow!("codemode eval error: {}", e))?;
+        check_script(&ast, config)?;
         let mut scope = Scope::new();
+        let retain = vars.is_some();
+        for (name, value) in vars.into_iter().flatten() {
+            let value = rhai::serde::to_dynamic(value)
+                .map_err(|e| anyhow!("Failed to restore variable '{}': {}", name, e))?;
+            scope.push_dynamic(name, value);
+        }
 
-        let dyn_result = engine.eval_with_scope::<Dynamic>(&mut scope, &wrapped);
+        let dyn_result = engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast);
         let dyn_value = dyn_result.map_err(|e| anyhow!("codemode eval error: {}", e))?;
         let value: Value = rhai::serde::from_dynamic(&dyn_value)
             .map_err(|e| anyhow!("Failed to convert result: {}", e))?;
-        Ok(value)
+        Ok((value, retain.then(|| scope_vars(&scope))))
     }
 
     /// Expose the codemode tool definition for registration.
@@ -355,6 +502,14 @@ impl CodeModeUtcp {
     pub async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
         self.client.search_tools(query, limit).await
     }
+
+    pub async fn list_tools(&self) -> Result<Vec<Tool>> {
+        self.client.list_tools().await
+    }
+
+    pub async fn get_tool(&self, name: &str) -> Result<Option<Tool>> {
+        self.client.get_tool(name).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -366,12 +521,14 @@ pub trait LlmModel: Send + Sync {
 /// High-level orchestrator that mirrors go-utcp's CodeMode flow:
 /// 1) Decide if tools are needed
 /// 2) Select tools by name
-/// 3) Ask the model to emit a Rhai snippet using call_tool helpers
+/// 3) Ask the model to emit a snippet, in the configured engine's language, using call_tool helpers
 /// 4) Execute the snippet via CodeMode
 pub struct CodemodeOrchestrator {
     codemode: Arc<CodeModeUtcp>,
     model: Arc<dyn LlmModel>,
     tool_specs_cache: RwLock<Option<String>>,
+    expected_schema: Option<ToolInputOutputSchema>,
+    schema_repair: bool,
 }
 
 impl CodemodeOrchestrator {
@@ -381,12 +538,105 @@ impl CodemodeOrchestrator {
             codemode,
             model,
             tool_specs_cache: RwLock::new(None),
+            expected_schema: None,
+            schema_repair: true,
         }
     }
 
+    /// Require the value `call_prompt` returns to match `schema`. The model is shown the
+    /// schema when it writes the snippet, and a snippet whose value does not match gets one
+    /// repair attempt before `UtcpError::SchemaMismatch` is returned.
+    pub fn with_expected_schema(mut self, schema: ToolInputOutputSchema) -> Self {
+        self.expected_schema = Some(schema);
+        self
+    }
+
+    /// Whether a snippet whose value does not match the expected schema is sent back to the
+    /// model, with the mismatches, to be fixed once. On by default.
+    pub fn with_schema_repair(mut self, schema_repair: bool) -> Self {
+        self.schema_repair = schema_repair;
+        self
+    }
+
     /// Run the full orchestration flow. Returns Ok(None) if the model says no tools are needed
-    /// or fails to pick any tools. Otherwise returns the codemode execution result.
+    /// or fails to pick any tools. Otherwise returns the codemode execution result, which
+    /// matches the expected schema when one is set.
     pub async fn call_prompt(&self, prompt: &str) -> Result<Option<Value>> {
+        let Some((snippet, tools)) = self.prepare_snippet(prompt).await? else {
+            return Ok(None);
+        };
+        let raw = match self.execute_snippet(&snippet, &tools).await {
+            Err(err) if self.schema_repair => match err.downcast_ref::<UtcpError>() {
+                Some(UtcpError::SchemaMismatch { mismatches, .. }) => {
+                    tracing::debug!(
+                        error = %err,
+                        "Codemode result did not match the expected schema, asking for a repair"
+                    );
+                    let repaired = self
+                        .repair_snippet(prompt, &tools, &snippet, mismatches)
+                        .await?;
+                    self.execute_snippet(&repaired, &tools).await?
+                }
+                _ => return Err(err),
+            },
+            raw => raw?,
+        };
+
+        tracing::debug!(
+            stdout = %raw.stdout,
+            stderr = %raw.stderr,
+            "Codemode snippet finished"
+        );
+        Ok(Some(raw.value))
+    }
+
+    /// Like [`CodemodeOrchestrator::call_prompt`], but dry-runs the generated snippet and
+    /// returns it with the tool calls it would make, without making any.
+    pub async fn plan_prompt(&self, prompt: &str) -> Result<Option<PromptPlan>> {
+        let Some((snippet, tools)) = self.prepare_snippet(prompt).await? else {
+            return Ok(None);
+        };
+        let raw = self
+            .codemode
+            .execute(CodeModeArgs {
+                code: snippet.clone(),
+                timeout: Some(20_000),
+                dry_run: true,
+                allowed_tools: Some(tools.clone()),
+                engine: None,
+                expected_schema: None,
+            })
+            .await?;
+
+        tracing::debug!(
+            calls = raw.plan.len(),
+            stdout = %raw.stdout,
+            stderr = %raw.stderr,
+            "Codemode snippet planned"
+        );
+        Ok(Some(PromptPlan {
+            code: snippet,
+            tools,
+            calls: raw.plan,
+        }))
+    }
+
+    async fn execute_snippet(&self, code: &str, tools: &[String]) -> Result<CodeModeResult> {
+        self.codemode
+            .execute(CodeModeArgs {
+                code: code.to_string(),
+                timeout: Some(20_000),
+                dry_run: false,
+                allowed_tools: Some(tools.to_vec()),
+                engine: None,
+                expected_schema: self.expected_schema.clone(),
+            })
+            .await
+    }
+
+    /// Steps 1-3: the snippet for `prompt` and the tools selected for it, or None when the
+    /// model wants no tools.
+    async fn prepare_snippet(&self, prompt: &str) -> Result<Option<(String, Vec<String>)>> {
         let specs = self.render_tool_specs().await?;
 
         if !self.decide_if_tools_needed(prompt, &specs).await? {
@@ -399,15 +649,7 @@ impl CodemodeOrchestrator {
         }
 
         let snippet = self.generate_snippet(prompt, &selected, &specs).await?;
-        let raw = self
-            .codemode
-            .execute(CodeModeArgs {
-                code: snippet,
-                timeout: Some(20_000),
-            })
-            .await?;
-
-        Ok(Some(raw.value))
+        Ok(Some((snippet, selected)))
     }
 
     async fn render_tool_specs(&self) -> Result<String> {
@@ -418,11 +660,8 @@ impl CodemodeOrchestrator {
             }
         }
 
-        let tools = self
-            .codemode
-            .search_tools("", 200)
-            .await
-            .unwrap_or_default();
+        let mut tools = self.codemode.list_tools().await.unwrap_or_default();
+        tools.truncate(200);
         let mut rendered =
             String::from("UTCP TOOL REFERENCE (use exact field names and required keys):\n");
         for tool in tools {
@@ -509,19 +748,77 @@ impl CodemodeOrchestrator {
         specs: &str,
     ) -> Result<String> {
         let tool_list = tools.join(", ");
-        let request = format!(
-            "Generate a Rhai snippet that chains UTCP tool calls to satisfy the user request.\n\
+        let request = match self.codemode.config().engine {
+            CodeModeEngine::Rhai => format!(
+                "Generate a Rhai snippet that chains UTCP tool calls to satisfy the user request.\n\
 Use ONLY these tools: {tool_list}.\n\
-Helpers available: call_tool(name, map), call_tool_stream(name, map) -> array of streamed chunks, search_tools(query, limit), sprintf(fmt, list).\n\
+Helpers available: call_tool(name, map), call_tool_stream(name, map) -> array of streamed chunks, search_tools(query, limit), list_tools(), get_tool(name) -> #{{name, description, inputs, required}}, sprintf(fmt, list).\n\
+Utilities: json_parse(string) -> value, json_stringify(value) -> string, base64_encode(string), base64_decode(string), now_ms() -> epoch milliseconds. Use json_parse on tool results that are JSON text and json_stringify to pass structured values to tools that take text.\n\
 Use Rhai map syntax #{{\"field\": value}} with exact input field names; include required fields and never invent new keys.\n\
 You may call multiple tools, store results in variables, and pass them into subsequent tools.\n\
 When using call_tool_stream, treat the returned array as the streamed items and chain it into later calls or the final output.\n\
 Return the final value as the last expression (map/list/scalar). No markdown or commentary, code only.\n\
 \nUSER:\n{prompt}\n\nTOOLS (use exact field names):\n{specs}"
+            ),
+            CodeModeEngine::JavaScript => format!(
+                "Generate a JavaScript snippet that chains UTCP tool calls to satisfy the user request.\n\
+Use ONLY these tools: {tool_list}.\n\
+Helpers available, all synchronous: call_tool(name, args), call_tool_stream(name, args) -> array of streamed chunks, search_tools(query, limit), list_tools(), get_tool(name) -> {{name, description, inputs, required}}, console.log(...).\n\
+Utilities: json_parse(string) -> value, json_stringify(value) -> string, base64_encode(string), base64_decode(string), now_ms() -> epoch milliseconds. Use json_parse on tool results that are JSON text and json_stringify to pass structured values to tools that take text.\n\
+Pass args as an object literal {{\"field\": value}} with exact input field names; include required fields and never invent new keys.\n\
+You may call multiple tools, store results in variables, and pass them into subsequent tools.\n\
+When using call_tool_stream, treat the returned array as the streamed items and chain it into later calls or the final output.\n\
+End with the final value as an expression statement (object/array/scalar), wrapping object literals in parentheses; do not use return, await or import. No markdown or commentary, code only.\n\
+\nUSER:\n{prompt}\n\nTOOLS (use exact field names):\n{specs}"
+            ),
+        };
+        let resp_val = self
+            .model
+            .complete(&self.with_result_schema(request))
+            .await?;
+        Ok(resp_val.as_str().unwrap_or_default().trim().to_string())
+    }
+
+    /// Ask the model to fix a snippet whose value did not match the expected schema.
+    async fn repair_snippet(
+        &self,
+        prompt: &str,
+        tools: &[String],
+        snippet: &str,
+        mismatches: &[SchemaMismatch],
+    ) -> Result<String> {
+        let specs = self.render_tool_specs().await?;
+        let language = match self.codemode.config().engine {
+            CodeModeEngine::Rhai => "Rhai",
+            CodeModeEngine::JavaScript => "JavaScript",
+        };
+        let problems: String = mismatches
+            .iter()
+            .map(|m| format!("- {}: {}\n", m.path, m.message))
+            .collect();
+        let request = format!(
+            "The {language} snippet below was written for the user request, but its final value does not match the result schema.\n\
+Fix the snippet so its final value matches the schema. Use ONLY these tools: {tool_list}. Keep the same helpers and syntax. No markdown or commentary, code only.\n\
+\nMISMATCHES:\n{problems}\nSNIPPET:\n{snippet}\n\nUSER:\n{prompt}\n\nTOOLS (use exact field names):\n{specs}",
+            tool_list = tools.join(", "),
         );
-        let resp_val = self.model.complete(&request).await?;
+        let resp_val = self
+            .model
+            .complete(&self.with_result_schema(request))
+            .await?;
         Ok(resp_val.as_str().unwrap_or_default().trim().to_string())
     }
+
+    /// Append the expected schema, if any, to a snippet request.
+    fn with_result_schema(&self, mut request: String) -> String {
+        if let Some(schema) = &self.expected_schema {
+            let schema = serde_json::to_string(schema).unwrap_or_default();
+            request.push_str(&format!(
+                "\n\nRESULT SCHEMA (the final value must match it):\n{schema}"
+            ));
+        }
+        request
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -530,6 +827,21 @@ pub struct CodeModeArgs {
     pub code: String,
     #[serde(default)]
     pub timeout: Option<u64>,
+    /// Record the tool calls the snippet makes in `CodeModeResult::plan` instead of making
+    /// them; each call returns a stub shaped like the tool's outputs.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// The only tools `call_tool` and `call_tool_stream` may call, by qualified or bare
+    /// name. When `CodeModeConfig::allowed_tools` is also set, a tool must be in both lists.
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+    /// The language of `code`. Falls back to `CodeModeConfig::engine`.
+    #[serde(default)]
+    pub engine: Option<CodeModeEngine>,
+    /// Schema the final value must match. A value that does not fails the execution with
+    /// `UtcpError::SchemaMismatch`; dry runs are not checked.
+    #[serde(default)]
+    pub expected_schema: Option<ToolInputOutputSchema>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -540,6 +852,213 @@ pub struct CodeModeResult {
     pub stdout: String,
     #[serde(default)]
     pub stderr: String,
+    /// The tool calls a dry run would have made, in order.
+    #[serde(default)]
+    pub plan: Vec<PlannedCall>,
+}
+
+/// A tool call recorded by a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlannedCall {
+    pub name: String,
+    pub args: HashMap<String, Value>,
+    /// Whether the script asked for the call's results as a stream.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// An orchestrated snippet and the tool calls it would make, for approval before it runs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PromptPlan {
+    /// The generated snippet; pass it to `CodeModeUtcp::execute` once approved.
+    pub code: String,
+    /// The tools selected for the snippet, to pass as `CodeModeArgs::allowed_tools`.
+    pub tools: Vec<String>,
+    pub calls: Vec<PlannedCall>,
+}
+
+/// A script output stream, holding at most `limit` bytes before the truncation marker.
+#[derive(Debug)]
+struct OutputBuffer {
+    text: String,
+    limit: usize,
+    truncated: bool,
+}
+
+impl OutputBuffer {
+    fn new(limit: usize) -> Self {
+        Self {
+            text: String::new(),
+            limit,
+            truncated: false,
+        }
+    }
+
+    /// Append `line` and a newline, cutting it short once the buffer is full.
+    fn push_line(&mut self, line: &str) {
+        if self.truncated {
+            return;
+        }
+        let room = self.limit - self.text.len();
+        if line.len() < room {
+            self.text.push_str(line);
+            self.text.push('\n');
+            return;
+        }
+        let mut cut = room.min(line.len());
+        while !line.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.text.push_str(&line[..cut]);
+        self.text.push_str(TRUNCATION_MARKER);
+        self.truncated = true;
+    }
+}
+
+/// The stdout and stderr of one script execution, shared with the engine's output hooks.
+#[derive(Debug, Clone)]
+struct OutputCapture {
+    stdout: Arc<Mutex<OutputBuffer>>,
+    stderr: Arc<Mutex<OutputBuffer>>,
+}
+
+impl OutputCapture {
+    fn new(limit: usize) -> Self {
+        Self {
+            stdout: Arc::new(Mutex::new(OutputBuffer::new(limit))),
+            stderr: Arc::new(Mutex::new(OutputBuffer::new(limit))),
+        }
+    }
+
+    fn stdout(&self, line: &str) {
+        if let Ok(mut buf) = self.stdout.lock() {
+            buf.push_line(line);
+        }
+    }
+
+    fn stderr(&self, line: &str) {
+        if let Ok(mut buf) = self.stderr.lock() {
+            buf.push_line(line);
+        }
+    }
+
+    /// Take the captured stdout and stderr, leaving both buffers empty.
+    fn take(&self) -> (String, String) {
+        let take = |buf: &Mutex<OutputBuffer>| {
+            buf.lock()
+                .map(|mut b| std::mem::take(&mut b.text))
+                .unwrap_or_default()
+        };
+        (take(&self.stdout), take(&self.stderr))
+    }
+}
+
+#[cfg(feature = "javascript")]
+fn eval_javascript(
+    code: &str,
+    config: &CodeModeConfig,
+    vars: Option<RetainedVars>,
+    host: &ScriptHost,
+    cancelled: &Arc<AtomicBool>,
+) -> Result<(Value, Option<RetainedVars>)> {
+    javascript::eval_snippet(code, config, vars, host, cancelled)
+}
+
+#[cfg(not(feature = "javascript"))]
+fn eval_javascript(
+    _code: &str,
+    _config: &CodeModeConfig,
+    _vars: Option<RetainedVars>,
+    _host: &ScriptHost,
+    _cancelled: &Arc<AtomicBool>,
+) -> Result<(Value, Option<RetainedVars>)> {
+    Err(anyhow!(
+        "Codemode engine `javascript` requires the `javascript` feature of rs-utcp"
+    ))
+}
+
+/// Variables a session carries between executions, by name.
+type RetainedVars = BTreeMap<String, Value>;
+
+/// The variables in `scope` that convert to JSON; later declarations shadow earlier ones.
+/// Values such as function pointers have no JSON form and are dropped.
+fn scope_vars(scope: &Scope) -> RetainedVars {
+    scope
+        .iter()
+        .filter_map(|(name, _, value)| {
+            let value = rhai::serde::from_dynamic::<Value>(&value).ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Reject a parsed script that uses a construct `config` does not allow.
+fn check_script(ast: &AST, config: &CodeModeConfig) -> Result<()> {
+    if !config.allow_fn_definitions {
+        // Closures compile to anonymous functions, which stay allowed
+        if let Some(f) = ast
+            .iter_functions()
+            .find(|f| !f.name.starts_with(ANONYMOUS_FN_PREFIX))
+        {
+            return Err(anyhow!(
+                "Code contains prohibited pattern: definition of function '{}'",
+                f.name
+            ));
+        }
+    }
+
+    let mut violation = None;
+    ast.walk(&mut |path: &[ASTNode]| {
+        violation = path.last().and_then(|node| prohibited_node(node, config));
+        violation.is_none()
+    });
+    match violation {
+        Some(found) => Err(anyhow!("Code contains prohibited pattern: {}", found)),
+        None => Ok(()),
+    }
+}
+
+/// Describe `node` if it is an import, `eval` call or unbounded loop that `config` forbids.
+///
+/// Loop conditions are checked after Rhai's constant folding, so `while 1 == 1` is caught but
+/// a condition that is merely always true is not; `max_operations` still stops those loops.
+fn prohibited_node(node: &ASTNode, config: &CodeModeConfig) -> Option<String> {
+    let always = |expr: &Expr, value: bool| matches!(expr, Expr::BoolConstant(b, _) if *b == value);
+    match node {
+        ASTNode::Stmt(Stmt::Import(_, pos)) if !config.allow_imports => {
+            Some(format!("import statement at {}", pos))
+        }
+        ASTNode::Stmt(Stmt::FnCall(call, pos)) | ASTNode::Expr(Expr::FnCall(call, pos))
+            if !config.allow_eval && call.name == "eval" =>
+        {
+            Some(format!("call to eval at {}", pos))
+        }
+        // `loop` is a `while` without a condition
+        ASTNode::Stmt(Stmt::While(flow, pos))
+            if !config.allow_unbounded_loops
+                && (matches!(flow.expr, Expr::Unit(_)) || always(&flow.expr, true)) =>
+        {
+            Some(format!("unbounded loop at {}", pos))
+        }
+        // `do .. until` negates its condition
+        ASTNode::Stmt(Stmt::Do(flow, flags, pos))
+            if !config.allow_unbounded_loops
+                && always(&flow.expr, !flags.contains(ASTFlags::NEGATED)) =>
+        {
+            Some(format!("unbounded loop at {}", pos))
+        }
+        _ => None,
+    }
+}
+
+/// What `list_tools` and `get_tool` tell scripts about a tool: enough to build its arguments.
+fn tool_summary(tool: &Tool) -> Value {
+    serde_json::json!({
+        "name": tool.name,
+        "description": tool.description,
+        "inputs": tool.inputs.properties.clone().unwrap_or_default(),
+        "required": tool.inputs.required.clone().unwrap_or_default(),
+    })
 }
 
 fn schema_type_hint(value: &Value) -> String {
@@ -556,15 +1075,106 @@ fn schema_type_hint(value: &Value) -> String {
     }
 }
 
-fn value_to_map(value: Value) -> Result<HashMap<String, Value>, Box<EvalAltResult>> {
+fn value_to_map(value: Value) -> Result<HashMap<String, Value>> {
     match value {
         Value::Object(obj) => Ok(obj.into_iter().collect()),
-        _ => Err(EvalAltResult::ErrorRuntime(
-            "call_tool expects object args".into(),
-            rhai::Position::NONE,
-        )
-        .into()),
+        _ => Err(anyhow!("call_tool expects object args")),
+    }
+}
+
+fn check_tool_name(name: &str) -> Result<()> {
+    // Security: Validate tool name format
+    if name.is_empty() || name.len() > 200 {
+        return Err(anyhow!("Invalid tool name length"));
+    }
+    Ok(())
+}
+
+fn runtime_error(message: String) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorRuntime(message.into(), rhai::Position::NONE).into()
+}
+
+fn map_to_value(map: Map) -> Result<Value, Box<EvalAltResult>> {
+    serde_json::to_value(map).map_err(|e| runtime_error(e.to_string()))
+}
+
+/// Hand a helper's result to a Rhai script, raising its error as a runtime error.
+fn to_script(result: Result<Value>) -> Result<Dynamic, Box<EvalAltResult>> {
+    let value = result.map_err(|e| runtime_error(e.to_string()))?;
+    rhai::serde::to_dynamic(value)
+}
+
+/// Fail when a helper would return a string longer than scripts may hold.
+fn check_string_size(helper: &str, len: usize, max: usize) -> Result<()> {
+    if max > 0 && len > max {
+        return Err(anyhow!(
+            "{} result of {} bytes exceeds maximum {} bytes",
+            helper,
+            len,
+            max
+        ));
     }
+    Ok(())
+}
+
+fn json_parse(text: &str) -> Result<Value> {
+    serde_json::from_str(text).map_err(|e| anyhow!("json_parse: invalid JSON: {}", e))
+}
+
+fn json_stringify(value: &Value, max_string_size: usize) -> Result<String> {
+    let text = value.to_string();
+    check_string_size("json_stringify", text.len(), max_string_size)?;
+    Ok(text)
+}
+
+fn base64_encode(text: &str, max_string_size: usize) -> Result<String> {
+    check_string_size("base64_encode", text.len().div_ceil(3) * 4, max_string_size)?;
+    Ok(BASE64.encode(text))
+}
+
+fn base64_decode(text: &str) -> Result<String> {
+    let bytes = BASE64
+        .decode(text.trim())
+        .map_err(|e| anyhow!("base64_decode: invalid base64: {}", e))?;
+    String::from_utf8(bytes)
+        .map_err(|_| anyhow!("base64_decode: decoded bytes are not valid UTF-8"))
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// JSON, base64 and clock helpers for scripts. Strings they return are bounded by
+/// `max_string_size`; parsed arrays and maps are checked against the engine's own limits.
+fn register_utilities(engine: &mut Engine, max_string_size: usize) {
+    engine.register_fn(
+        "json_parse",
+        |text: &str| -> Result<Dynamic, Box<EvalAltResult>> { to_script(json_parse(text)) },
+    );
+    engine.register_fn(
+        "json_stringify",
+        move |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+            let value: Value = rhai::serde::from_dynamic(&value).map_err(|e| {
+                runtime_error(format!("json_stringify: value has no JSON form: {}", e))
+            })?;
+            json_stringify(&value, max_string_size).map_err(|e| runtime_error(e.to_string()))
+        },
+    );
+    engine.register_fn(
+        "base64_encode",
+        move |text: &str| -> Result<String, Box<EvalAltResult>> {
+            base64_encode(text, max_string_size).map_err(|e| runtime_error(e.to_string()))
+        },
+    );
+    engine.register_fn(
+        "base64_decode",
+        |text: &str| -> Result<String, Box<EvalAltResult>> {
+            base64_decode(text).map_err(|e| runtime_error(e.to_string()))
+        },
+    );
+    engine.register_fn("now_ms", now_ms);
 }
 
 /// Minimal string formatter exposed to Rhai snippets.
@@ -602,40 +1212,405 @@ pub fn sprintf(fmt: &str, args: &[Dynamic]) -> String {
     out
 }
 
-fn block_on_any_runtime<F, T>(fut: F) -> Result<T, anyhow::Error>
-where
-    F: std::future::Future<Output = Result<T, anyhow::Error>>,
-    T: Send + 'static,
-{
-    match tokio::runtime::Handle::try_current() {
-        Ok(handle) => match handle.runtime_flavor() {
-            RuntimeFlavor::MultiThread => tokio::task::block_in_place(|| handle.block_on(fut)),
-            RuntimeFlavor::CurrentThread => {
-                let rt = Builder::new_current_thread().enable_all().build()?;
-                rt.block_on(fut)
-            }
-            _ => {
-                let rt = Builder::new_current_thread().enable_all().build()?;
-                rt.block_on(fut)
-            }
-        },
-        Err(_) => {
-            let rt = Builder::new_current_thread().enable_all().build()?;
-            rt.block_on(fut)
-        }
-    }
+/// A tool operation a script asks the async side of `execute` to perform.
+enum ToolRequest {
+    Call {
+        name: String,
+        args: HashMap<String, Value>,
+        reply: oneshot::Sender<Result<Value>>,
+    },
+    Stream {
+        name: String,
+        args: HashMap<String, Value>,
+        reply: oneshot::Sender<Result<Vec<Value>>>,
+    },
+    Search {
+        query: String,
+        limit: usize,
+        reply: oneshot::Sender<Result<Vec<Tool>>>,
+    },
+    List {
+        reply: oneshot::Sender<Result<Vec<Tool>>>,
+    },
+    Get {
+        name: String,
+        reply: oneshot::Sender<Result<Option<Tool>>>,
+    },
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::tools::Tool;
-    use crate::transports::stream::boxed_vec_stream;
-    use tokio::sync::Mutex;
+/// The script thread's end of the tool request channel.
+#[derive(Clone)]
+struct ToolBridge {
+    requests: mpsc::UnboundedSender<ToolRequest>,
+}
 
-    #[derive(Clone)]
-    struct MockClient {
-        called: Arc<Mutex<Vec<String>>>,
+impl ToolBridge {
+    /// Send a request and block the script thread until the driver answers it.
+    fn request<T>(
+        &self,
+        make: impl FnOnce(oneshot::Sender<Result<T>>) -> ToolRequest,
+    ) -> Result<T> {
+        let (reply, answer) = oneshot::channel();
+        self.requests
+            .send(make(reply))
+            .map_err(|_| anyhow!("Code execution was cancelled"))?;
+        answer
+            .blocking_recv()
+            .map_err(|_| anyhow!("Code execution was cancelled"))?
+    }
+}
+
+/// The host side of the helpers scripts call, whichever engine runs them. Arguments and
+/// results are checked against the sandbox limits here, so both engines enforce the same ones.
+#[derive(Clone)]
+struct ScriptHost {
+    tools: ToolBridge,
+    output: OutputCapture,
+    max_array_size: usize,
+    max_string_size: usize,
+}
+
+impl ScriptHost {
+    fn new(tools: ToolBridge, output: OutputCapture, config: &CodeModeConfig) -> Self {
+        Self {
+            tools,
+            output,
+            max_array_size: config.max_array_size,
+            max_string_size: config.max_string_size,
+        }
+    }
+
+    fn call_tool(&self, name: &str, args: Value) -> Result<Value> {
+        check_tool_name(name)?;
+        let args = value_to_map(args)?;
+        self.tools.request(|reply| ToolRequest::Call {
+            name: name.to_string(),
+            args,
+            reply,
+        })
+    }
+
+    fn call_tool_stream(&self, name: &str, args: Value) -> Result<Value> {
+        check_tool_name(name)?;
+        let args = value_to_map(args)?;
+        let items = self.tools.request(|reply| ToolRequest::Stream {
+            name: name.to_string(),
+            args,
+            reply,
+        })?;
+        Ok(Value::Array(items))
+    }
+
+    fn search_tools(&self, query: &str, limit: i64) -> Result<Value> {
+        // Security: Validate query length
+        if query.len() > 1000 {
+            return Err(anyhow!("Search query too long (max 1000 chars)"));
+        }
+
+        // Security: Enforce reasonable search limit
+        const MAX_SEARCH_LIMIT: i64 = 500;
+        let safe_limit = if limit <= 0 || limit > MAX_SEARCH_LIMIT {
+            MAX_SEARCH_LIMIT
+        } else {
+            limit
+        };
+
+        let tools = self.tools.request(|reply| ToolRequest::Search {
+            query: query.to_string(),
+            limit: safe_limit as usize,
+            reply,
+        })?;
+        Ok(serde_json::to_value(tools)?)
+    }
+
+    fn list_tools(&self) -> Result<Value> {
+        let tools = self.tools.request(|reply| ToolRequest::List { reply })?;
+
+        // Security: Bound the listing by the script's own array and string limits
+        let mut listing = Vec::new();
+        let mut size = 0;
+        for tool in &tools {
+            let summary = tool_summary(tool);
+            size += summary.to_string().len();
+            if listing.len() >= self.max_array_size || size > self.max_string_size {
+                break;
+            }
+            listing.push(summary);
+        }
+        Ok(Value::Array(listing))
+    }
+
+    /// The summary of the named tool, or null when there is no such tool.
+    fn get_tool(&self, name: &str) -> Result<Value> {
+        check_tool_name(name)?;
+        let tool = self.tools.request(|reply| ToolRequest::Get {
+            name: name.to_string(),
+            reply,
+        })?;
+        let Some(tool) = tool else {
+            return Ok(Value::Null);
+        };
+
+        let summary = tool_summary(&tool);
+        let size = summary.to_string().len();
+        if size > self.max_string_size {
+            return Err(anyhow!(
+                "Metadata of tool '{}' is {} bytes, exceeding maximum {} bytes",
+                tool.name,
+                size,
+                self.max_string_size
+            ));
+        }
+        Ok(summary)
+    }
+
+    fn log(&self, level: &str, msg: &str) -> Result<()> {
+        let level = level.to_ascii_lowercase();
+        if !LOG_LEVELS.contains(&level.as_str()) {
+            return Err(anyhow!("Unknown log level '{}'", level));
+        }
+        self.output
+            .stderr(&format!("[{}] {}", level.to_ascii_uppercase(), msg));
+        Ok(())
+    }
+}
+
+/// Serve a script's tool requests until its engine, and with it every sender, is dropped.
+/// Only the `allowed` tools may be called, when set. With a `plan`, tool calls are recorded
+/// there and answered with stubs instead of made.
+async fn drive_tool_requests(
+    client: Arc<dyn UtcpClientInterface>,
+    mut pending: mpsc::UnboundedReceiver<ToolRequest>,
+    max_stream_items: usize,
+    allowlists: [Option<Vec<String>>; 2],
+    mut plan: Option<&mut Vec<PlannedCall>>,
+) {
+    // Each list can only narrow the others: a tool must be in every list that is set
+    let mut allowed: Option<HashSet<String>> = None;
+    for names in allowlists.into_iter().flatten() {
+        let names = resolve_tool_names(client.as_ref(), names).await;
+        allowed = Some(match allowed {
+            Some(allowed) => allowed.intersection(&names).cloned().collect(),
+            None => names,
+        });
+    }
+    while let Some(request) = pending.recv().await {
+        // A dropped reply means the script was cancelled; nothing is waiting for the answer
+        match request {
+            ToolRequest::Call { name, args, reply } => {
+                let permitted = check_allowed(client.as_ref(), allowed.as_ref(), &name).await;
+                let result = match (permitted, plan.as_deref_mut()) {
+                    (Err(err), _) => Err(err),
+                    (Ok(target), Some(plan)) => {
+                        let stub = dry_run_stub(client.as_ref(), &target).await;
+                        plan.push(PlannedCall {
+                            name,
+                            args,
+                            stream: false,
+                        });
+                        Ok(stub)
+                    }
+                    (Ok(target), None) => client.call_tool(&target, args).await,
+                };
+                let _ = reply.send(result);
+            }
+            ToolRequest::Stream { name, args, reply } => {
+                let permitted = check_allowed(client.as_ref(), allowed.as_ref(), &name).await;
+                let result = match (permitted, plan.as_deref_mut()) {
+                    (Err(err), _) => Err(err),
+                    (Ok(target), Some(plan)) => {
+                        let stub = dry_run_stub(client.as_ref(), &target).await;
+                        plan.push(PlannedCall {
+                            name,
+                            args,
+                            stream: true,
+                        });
+                        Ok(vec![stub])
+                    }
+                    (Ok(target), None) => {
+                        collect_stream(client.as_ref(), &target, args, max_stream_items).await
+                    }
+                };
+                let _ = reply.send(result);
+            }
+            ToolRequest::Search {
+                query,
+                limit,
+                reply,
+            } => {
+                let _ = reply.send(client.search_tools(&query, limit).await);
+            }
+            ToolRequest::List { reply } => {
+                let _ = reply.send(client.list_tools().await);
+            }
+            ToolRequest::Get { name, reply } => {
+                let _ = reply.send(client.get_tool(&name).await);
+            }
+        }
+    }
+}
+
+/// `names` with each name the client knows replaced by the qualified name it resolves to, so
+/// bare and qualified spellings of a tool compare equal. A bare name several providers offer
+/// stays as it is and so allows none of them.
+async fn resolve_tool_names(
+    client: &dyn UtcpClientInterface,
+    names: Vec<String>,
+) -> HashSet<String> {
+    let mut resolved = HashSet::new();
+    for name in names {
+        match resolve_unambiguous(client, &name).await {
+            Ok(Some(qualified)) => resolved.insert(qualified),
+            _ => resolved.insert(name),
+        };
+    }
+    resolved
+}
+
+/// Fail unless `name` resolves to one of the `allowed` tools, or there is no allowlist.
+/// Returns the name to call: the one that was checked, so the call cannot resolve to a
+/// different tool than the one the allowlist approved.
+async fn check_allowed(
+    client: &dyn UtcpClientInterface,
+    allowed: Option<&HashSet<String>>,
+    name: &str,
+) -> Result<String> {
+    let Some(allowed) = allowed else {
+        return Ok(name.to_string());
+    };
+    // Checked under the name the client will actually call, not the one the script used
+    let resolved = resolve_unambiguous(client, name)
+        .await?
+        .unwrap_or_else(|| name.to_string());
+    if allowed.contains(&resolved) {
+        return Ok(resolved);
+    }
+    Err(anyhow!(
+        "Tool '{}' is not in the allowed tools for this execution",
+        name
+    ))
+}
+
+/// The qualified name the client resolves `name` to, or `None` for tools it does not know.
+/// Bare names that more than one provider offers are rejected, since which of the tools the
+/// client would call is not something an allowlist can rely on.
+async fn resolve_unambiguous(
+    client: &dyn UtcpClientInterface,
+    name: &str,
+) -> Result<Option<String>> {
+    if !name.contains('.') {
+        let mut providers: Vec<String> = client
+            .list_tools()
+            .await?
+            .into_iter()
+            .filter_map(|tool| match tool.name.split_once('.') {
+                Some((provider, bare)) if bare == name => Some(provider.to_string()),
+                _ => None,
+            })
+            .collect();
+        if providers.len() > 1 {
+            providers.sort();
+            return Err(anyhow!(
+                "Tool '{}' is offered by several providers ({}); call it by its qualified name",
+                name,
+                providers.join(", ")
+            ));
+        }
+    }
+    Ok(client.resolve_tool_name(name).await.ok())
+}
+
+/// What a dry-run call returns: a default value shaped like the tool's declared outputs, or
+/// `{"$dry_run": true}` when the tool is unknown or declares no shape.
+async fn dry_run_stub(client: &dyn UtcpClientInterface, name: &str) -> Value {
+    let shaped = match client.get_tool(name).await {
+        Ok(Some(tool)) => match tool.outputs.properties.as_ref() {
+            Some(props) if !props.is_empty() => Some(Value::Object(
+                props
+                    .iter()
+                    .map(|(key, schema)| (key.clone(), schema_default(schema)))
+                    .collect(),
+            )),
+            _ => match tool.outputs.type_.as_str() {
+                "" | "object" => None,
+                type_ => Some(type_default(type_)),
+            },
+        },
+        _ => None,
+    };
+    shaped.unwrap_or_else(|| serde_json::json!({"$dry_run": true}))
+}
+
+/// Fail with `UtcpError::SchemaMismatch` when the value does not match `expected_schema`.
+fn check_result(args: &CodeModeArgs, value: &Value) -> Result<()> {
+    let Some(schema) = args.expected_schema.as_ref().filter(|_| !args.dry_run) else {
+        return Ok(());
+    };
+    let mismatches = schema.mismatches(value);
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    Err(UtcpError::SchemaMismatch {
+        mismatches,
+        value: value.clone(),
+    }
+    .into())
+}
+
+/// The empty value of a JSON schema: its properties' defaults for an object schema.
+fn schema_default(schema: &Value) -> Value {
+    match schema.get("properties").and_then(Value::as_object) {
+        Some(props) => Value::Object(
+            props
+                .iter()
+                .map(|(key, schema)| (key.clone(), schema_default(schema)))
+                .collect(),
+        ),
+        None => type_default(schema.get("type").and_then(Value::as_str).unwrap_or("")),
+    }
+}
+
+fn type_default(type_: &str) -> Value {
+    match type_ {
+        "string" => Value::String(String::new()),
+        "number" | "integer" => Value::from(0),
+        "boolean" => Value::Bool(false),
+        "array" => Value::Array(Vec::new()),
+        "object" => Value::Object(serde_json::Map::new()),
+        _ => Value::Null,
+    }
+}
+
+/// Collect every item of a tool stream, up to `max_items`.
+async fn collect_stream(
+    client: &dyn UtcpClientInterface,
+    name: &str,
+    args: HashMap<String, Value>,
+    max_items: usize,
+) -> Result<Vec<Value>> {
+    let mut stream = client.call_tool_stream(name, args).await?;
+    let mut items = Vec::new();
+    // Security: Limit maximum number of stream items to prevent memory exhaustion
+    while let Some(value) = stream.next().await? {
+        if items.len() >= max_items {
+            return Err(anyhow!("Stream exceeded maximum {} items", max_items));
+        }
+        items.push(value);
+    }
+    stream.close().await?;
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Tool;
+    use crate::transports::stream::boxed_vec_stream;
+    use tokio::sync::Mutex;
+
+    #[derive(Clone)]
+    struct MockClient {
+        called: Arc<Mutex<Vec<String>>>,
     }
 
     #[async_trait::async_trait]
@@ -669,6 +1644,28 @@ mod tests {
             Ok(vec![])
         }
 
+        async fn list_tools(&self) -> Result<Vec<Tool>> {
+            let tool = |name: &str, required: Value| -> Tool {
+                serde_json::from_value(serde_json::json!({
+                    "name": name,
+                    "description": format!("{name} two numbers"),
+                    "inputs": {
+                        "type": "object",
+                        "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+                        "required": required
+                    },
+                    "outputs": {"type": "number"},
+                    "tags": ["math"]
+                }))
+                .unwrap()
+            };
+            Ok(vec![
+                tool("math.add", serde_json::json!(["a", "b"])),
+                tool("math.sub", serde_json::json!(["a"])),
+                tool("math.neg", serde_json::json!(["a"])),
+            ])
+        }
+
         fn get_transports(&self) -> HashMap<String, Arc<dyn crate::transports::ClientTransport>> {
             HashMap::new()
         }
@@ -716,6 +1713,10 @@ mod tests {
         let args = CodeModeArgs {
             code: code.into(),
             timeout: Some(1000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
         let res = codemode.execute(args).await.unwrap();
         assert_eq!(res.value, serde_json::json!(10));
@@ -732,6 +1733,10 @@ mod tests {
         let args = CodeModeArgs {
             code: code.into(),
             timeout: Some(1_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
         let res = codemode.execute(args).await.unwrap();
         assert_eq!(res.value, serde_json::json!(["chunk"]));
@@ -739,6 +1744,702 @@ mod tests {
         assert_eq!(calls, vec!["stream:demo.tool"]);
     }
 
+    // Used to deadlock: tool calls blocked the only runtime thread
+    #[tokio::test]
+    async fn execute_calls_tools_on_current_thread_runtime() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client.clone());
+
+        let code = r#"
+            let n = call_tool("math.add", #{"a": 1});
+            let chunks = call_tool_stream("demo.tool", #{});
+            let found = search_tools("demo", 3);
+            #{"n": n, "chunks": chunks, "found": found.len()}
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            res.value,
+            serde_json::json!({"n": 5, "chunks": ["chunk"], "found": 0})
+        );
+        let calls = client.called.lock().await.clone();
+        assert_eq!(calls, vec!["math.add", "stream:demo.tool", "search:demo"]);
+    }
+
+    #[tokio::test]
+    async fn scripts_read_tool_metadata() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let code = r#"
+            let add = get_tool("add");
+            #{
+                "required": add.required,
+                "a": add.inputs.a["type"],
+                "description": get_tool("math.sub").description,
+                "names": list_tools().map(|t| t.name),
+                "missing": type_of(get_tool("math.mul"))
+            }
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            res.value,
+            serde_json::json!({
+                "required": ["a", "b"],
+                "a": "number",
+                "description": "math.sub two numbers",
+                "names": ["math.add", "math.sub", "math.neg"],
+                "missing": "()"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn security_bounds_tool_metadata() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::with_config(
+            client.clone(),
+            CodeModeConfig {
+                // One tool summary fits, two do not
+                max_string_size: 200,
+                ..CodeModeConfig::default()
+            },
+        );
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: "list_tools().len()".into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(1));
+
+        let codemode = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                max_string_size: 64,
+                ..CodeModeConfig::default()
+            },
+        );
+        let err = codemode
+            .execute(CodeModeArgs {
+                code: r#"get_tool("math.add")"#.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("exceeding maximum 64 bytes"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn dry_run_records_calls_without_making_them() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client.clone());
+
+        let code = r#"
+            let sum = call_tool("math.add", #{"a": 1, "b": 2});
+            let chunks = call_tool_stream("feed.updates", #{"since": sum});
+            #{"sum": sum, "chunks": chunks}
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(1_000),
+                dry_run: true,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+
+        // math.add declares a number output; feed.updates is unknown
+        assert_eq!(
+            res.value,
+            serde_json::json!({"sum": 0, "chunks": [{"$dry_run": true}]})
+        );
+        assert_eq!(
+            res.plan,
+            vec![
+                PlannedCall {
+                    name: "math.add".into(),
+                    args: HashMap::from([
+                        ("a".to_string(), serde_json::json!(1)),
+                        ("b".to_string(), serde_json::json!(2)),
+                    ]),
+                    stream: false,
+                },
+                PlannedCall {
+                    name: "feed.updates".into(),
+                    args: HashMap::from([("since".to_string(), serde_json::json!(0))]),
+                    stream: true,
+                },
+            ]
+        );
+        assert!(client.called.lock().await.is_empty());
+    }
+
+    #[test]
+    fn schema_default_follows_nested_properties() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "count": {"type": "integer"},
+                "tags": {"type": "array"},
+                "meta": {"type": "object", "properties": {"ok": {"type": "boolean"}}},
+                "extra": {}
+            }
+        });
+        assert_eq!(
+            schema_default(&schema),
+            serde_json::json!({
+                "title": "",
+                "count": 0,
+                "tags": [],
+                "meta": {"ok": false},
+                "extra": null
+            })
+        );
+    }
+
+    /// Answers each prompt with the next scripted reply.
+    #[derive(Default)]
+    struct ScriptedModel {
+        replies: Mutex<Vec<Value>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmModel for ScriptedModel {
+        async fn complete(&self, prompt: &str) -> Result<Value> {
+            self.prompts.lock().await.push(prompt.to_string());
+            Ok(self.replies.lock().await.remove(0))
+        }
+    }
+
+    #[tokio::test]
+    async fn orchestrator_plans_without_calling_tools() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let snippet =
+            r#"let x = call_tool("math.add", #{"a": 1, "b": 2}); call_tool("math.sub", #{"a": x})"#;
+        let model = Arc::new(ScriptedModel {
+            replies: Mutex::new(vec![
+                Value::from("yes"),
+                Value::from("math.add, math.sub"),
+                Value::from(snippet),
+            ]),
+            ..Default::default()
+        });
+        let orchestrator =
+            CodemodeOrchestrator::new(Arc::new(CodeModeUtcp::new(client.clone())), model);
+
+        let plan = orchestrator
+            .plan_prompt("add then subtract")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.code, snippet);
+        assert_eq!(plan.tools, vec!["math.add", "math.sub"]);
+        let names: Vec<_> = plan.calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["math.add", "math.sub"]);
+        assert_eq!(plan.calls[1].args["a"], serde_json::json!(0));
+        assert!(client.called.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn allowed_tools_block_unlisted_calls() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client.clone());
+        let run = |code: &str| {
+            codemode.execute(CodeModeArgs {
+                code: code.to_string(),
+                timeout: Some(1_000),
+                dry_run: false,
+                // A bare name allows the tool under its qualified name too
+                allowed_tools: Some(vec!["add".to_string(), "math.neg".to_string()]),
+                engine: None,
+                expected_schema: None,
+            })
+        };
+
+        let res =
+            run(r#"call_tool("math.add", #{}) + call_tool("add", #{}) + call_tool("neg", #{})"#)
+                .await
+                .unwrap();
+        assert_eq!(res.value, serde_json::json!(15));
+
+        let err = run(r#"call_tool("math.add", #{}); call_tool("math.sub", #{})"#)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Tool 'math.sub' is not in the allowed tools for this execution"),
+            "{}",
+            err
+        );
+        let err = run(r#"call_tool_stream("demo.tool", #{})"#)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Tool 'demo.tool'"), "{}", err);
+
+        // Calls go out under the qualified name the allowlist approved
+        let calls = client.called.lock().await.clone();
+        assert_eq!(calls, vec!["math.add", "math.add", "math.neg", "math.add"]);
+    }
+
+    #[tokio::test]
+    async fn allowed_tools_check_the_tool_the_client_would_call() {
+        use crate::config::UtcpClientConfig;
+        use crate::providers::text::TextProvider;
+        use crate::repository::in_memory::InMemoryToolRepository;
+        use crate::tag::tag_search::TagSearchStrategy;
+        use crate::UtcpClient;
+
+        let repo = Arc::new(InMemoryToolRepository::new());
+        let strategy = Arc::new(TagSearchStrategy::new(repo.clone(), 1.0));
+        let client = Arc::new(
+            UtcpClient::new(UtcpClientConfig::default(), repo, strategy)
+                .await
+                .unwrap(),
+        );
+        for provider in ["a", "b"] {
+            let echo: Tool = serde_json::from_value(serde_json::json!({
+                "name": "echo",
+                "description": "",
+                "inputs": {"type": "object"},
+                "outputs": {"type": "object"},
+                "tags": []
+            }))
+            .unwrap();
+            client
+                .register_tool_provider_with_tools(
+                    Arc::new(TextProvider::new(provider.to_string(), None, None)),
+                    vec![echo],
+                )
+                .await
+                .unwrap();
+        }
+        // Resolving b.echo makes the client remember it under the bare name too
+        let _ = client.call_tool("b.echo", HashMap::new()).await;
+
+        let codemode = CodeModeUtcp::new(client);
+        let run = |code: &str, allowed: &str| {
+            codemode.execute(CodeModeArgs {
+                code: code.to_string(),
+                timeout: Some(1_000),
+                dry_run: true,
+                allowed_tools: Some(vec![allowed.to_string()]),
+                engine: None,
+                expected_schema: None,
+            })
+        };
+
+        let err = run(r#"call_tool("echo", #{})"#, "a.echo")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Tool 'echo' is offered by several providers (a, b)"),
+            "{}",
+            err
+        );
+        let err = run(r#"call_tool("b.echo", #{})"#, "a.echo")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("not in the allowed tools"),
+            "{}",
+            err
+        );
+        let res = run(r#"call_tool("a.echo", #{})"#, "a.echo").await.unwrap();
+        assert_eq!(res.plan.len(), 1);
+
+        // An ambiguous bare name in the allowlist allows neither tool
+        let err = run(r#"call_tool("a.echo", #{})"#, "echo")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("not in the allowed tools"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn allowed_tools_can_only_narrow_the_config() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                allowed_tools: Some(vec!["math.sub".to_string()]),
+                ..CodeModeConfig::default()
+            },
+        );
+        let run = |tool: &str, allowed_tools: Option<Vec<&str>>| {
+            codemode.execute(CodeModeArgs {
+                code: format!(r#"call_tool("{}", #{{}})"#, tool),
+                timeout: Some(1_000),
+                dry_run: true,
+                allowed_tools: allowed_tools
+                    .map(|names| names.into_iter().map(str::to_string).collect()),
+                engine: None,
+                expected_schema: None,
+            })
+        };
+
+        let err = run("math.add", None).await.unwrap_err();
+        assert!(err.to_string().contains("Tool 'math.add'"), "{}", err);
+        let res = run("math.sub", None).await.unwrap();
+        assert_eq!(res.plan.len(), 1);
+        // A wider per-call list does not reach past the config
+        let err = run("math.add", Some(vec!["math.add", "sub"]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Tool 'math.add'"), "{}", err);
+        let res = run("math.sub", Some(vec!["math.add", "sub"]))
+            .await
+            .unwrap();
+        assert_eq!(res.plan.len(), 1);
+        // A narrower one applies on top of it
+        let err = run("math.sub", Some(vec!["math.add"])).await.unwrap_err();
+        assert!(err.to_string().contains("Tool 'math.sub'"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn orchestrator_limits_snippets_to_selected_tools() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let model = Arc::new(ScriptedModel {
+            replies: Mutex::new(vec![
+                Value::from("yes"),
+                Value::from("math.add"),
+                Value::from(r#"call_tool("math.add", #{}) + call_tool("math.sub", #{})"#),
+            ]),
+            ..Default::default()
+        });
+        let orchestrator =
+            CodemodeOrchestrator::new(Arc::new(CodeModeUtcp::new(client.clone())), model);
+
+        let err = orchestrator
+            .call_prompt("add then subtract")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Tool 'math.sub'"), "{}", err);
+        assert_eq!(client.called.lock().await.clone(), vec!["math.add"]);
+    }
+
+    #[cfg(not(feature = "javascript"))]
+    #[tokio::test]
+    async fn javascript_engine_requires_its_feature() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let err = codemode
+            .execute(CodeModeArgs {
+                code: "1 + 1".to_string(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: Some(CodeModeEngine::JavaScript),
+                expected_schema: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("`javascript` feature"), "{}", err);
+    }
+
+    fn summary_schema() -> ToolInputOutputSchema {
+        serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {"total": {"type": "integer"}, "label": {"type": "string"}},
+            "required": ["total", "label"]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn results_are_checked_against_the_expected_schema() {
+        let codemode = CodeModeUtcp::new(Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let args = |code: &str, dry_run: bool| CodeModeArgs {
+            code: code.to_string(),
+            timeout: Some(1_000),
+            dry_run,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: Some(summary_schema()),
+        };
+
+        let res = codemode
+            .execute(args(
+                r#"#{"total": call_tool("math.add", #{}), "label": "sum"}"#,
+                false,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!({"total": 5, "label": "sum"}));
+
+        let err = codemode
+            .execute(args(r#"#{"total": "5"}"#, false))
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::SchemaMismatch { mismatches, value }) => {
+                let paths: Vec<_> = mismatches.iter().map(|m| m.path.as_str()).collect();
+                assert_eq!(paths, vec!["$.label", "$.total"]);
+                assert_eq!(value, &serde_json::json!({"total": "5"}));
+            }
+            other => panic!("expected a schema mismatch, got {:?}", other),
+        }
+        assert!(err
+            .to_string()
+            .contains("$.total: expected integer, got string"));
+
+        // JSON passed as code is checked too, and dry runs are not
+        assert!(codemode.execute(args("[1, 2]", false)).await.is_err());
+        assert!(codemode
+            .execute(args(r#"#{"total": "5"}"#, true))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn orchestrator_repairs_snippets_that_miss_the_schema() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let model = Arc::new(ScriptedModel {
+            replies: Mutex::new(vec![
+                Value::from("yes"),
+                Value::from("math.add"),
+                Value::from(r#"#{"total": call_tool("math.add", #{})}"#),
+                Value::from(r#"#{"total": call_tool("math.add", #{}), "label": "sum"}"#),
+            ]),
+            ..Default::default()
+        });
+        let orchestrator =
+            CodemodeOrchestrator::new(Arc::new(CodeModeUtcp::new(client.clone())), model.clone())
+                .with_expected_schema(summary_schema());
+
+        let value = orchestrator.call_prompt("add").await.unwrap();
+        assert_eq!(value, Some(serde_json::json!({"total": 5, "label": "sum"})));
+        assert_eq!(client.called.lock().await.len(), 2);
+
+        let prompts = model.prompts.lock().await;
+        assert!(prompts[2].contains("RESULT SCHEMA"));
+        assert!(prompts[3].contains("- $.label: required field is missing"));
+        assert!(prompts[3].contains(r#"#{"total": call_tool("math.add", #{})}"#));
+    }
+
+    #[tokio::test]
+    async fn orchestrator_reports_mismatches_it_could_not_repair() {
+        let bad = Value::from(r#"#{"total": "five", "label": "sum"}"#);
+        let orchestrator = |repair: bool, replies: Vec<Value>| {
+            let model = Arc::new(ScriptedModel {
+                replies: Mutex::new(replies),
+                ..Default::default()
+            });
+            let client = Arc::new(MockClient {
+                called: Arc::new(Mutex::new(Vec::new())),
+            });
+            CodemodeOrchestrator::new(Arc::new(CodeModeUtcp::new(client)), model)
+                .with_expected_schema(summary_schema())
+                .with_schema_repair(repair)
+        };
+        let prefix = || vec![Value::from("yes"), Value::from("math.add")];
+
+        // One repair attempt, then the mismatch is returned
+        let replies = [prefix(), vec![bad.clone(), bad.clone()]].concat();
+        let err = orchestrator(true, replies)
+            .call_prompt("add")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.downcast_ref(), Some(UtcpError::SchemaMismatch { .. })),
+            "{}",
+            err
+        );
+
+        // Without repair the first mismatch is final
+        let replies = [prefix(), vec![bad]].concat();
+        let err = orchestrator(false, replies)
+            .call_prompt("add")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("$.total: expected integer"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn json_and_base64_helpers_round_trip() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let code = r#"
+            let args = json_parse("{\"filter\": {\"ids\": [1, 2]}, \"live\": true}");
+            let text = json_stringify(#{"ids": args.filter.ids, "live": args.live});
+            let encoded = base64_encode(text);
+            let started = now_ms();
+            #{
+                "second_id": args.filter.ids[1],
+                "text": text,
+                "encoded": encoded,
+                "decoded": json_parse(base64_decode(encoded)),
+                "clock": started > 1600000000000 && now_ms() >= started
+            }
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        let text = r#"{"ids":[1,2],"live":true}"#;
+        assert_eq!(
+            res.value,
+            serde_json::json!({
+                "second_id": 2,
+                "text": text,
+                "encoded": BASE64.encode(text),
+                "decoded": {"ids": [1, 2], "live": true},
+                "clock": true
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn json_and_base64_helpers_report_invalid_input() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let cases = [
+            (r#"json_parse("{not json")"#, "json_parse: invalid JSON"),
+            (r#"base64_decode("@@@")"#, "base64_decode: invalid base64"),
+            (r#"base64_decode("//79")"#, "not valid UTF-8"),
+            (
+                "json_stringify(|x| x)",
+                "json_stringify: value has no JSON form",
+            ),
+        ];
+        for (code, expected) in cases {
+            let err = codemode
+                .execute(CodeModeArgs {
+                    code: code.to_string(),
+                    timeout: Some(1_000),
+                    dry_run: false,
+                    allowed_tools: None,
+                    engine: None,
+                    expected_schema: None,
+                })
+                .await
+                .expect_err(code)
+                .to_string();
+            assert!(err.contains(expected), "{code}: {err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn security_helpers_respect_size_limits() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                max_string_size: 32,
+                max_array_size: 4,
+                ..CodeModeConfig::default()
+            },
+        );
+
+        let cases = [
+            (
+                r#"json_stringify(#{"name": "a fairly long value!!!"})"#,
+                "json_stringify result of 33 bytes exceeds maximum 32 bytes",
+            ),
+            (
+                r#"base64_encode("twenty-seven characters...")"#,
+                "base64_encode result of 36 bytes exceeds maximum 32 bytes",
+            ),
+            (r#"json_parse("[1, 2, 3, 4, 5]")"#, "array"),
+        ];
+        for (code, expected) in cases {
+            let err = codemode
+                .execute(CodeModeArgs {
+                    code: code.to_string(),
+                    timeout: Some(1_000),
+                    dry_run: false,
+                    allowed_tools: None,
+                    engine: None,
+                    expected_schema: None,
+                })
+                .await
+                .expect_err(code)
+                .to_string();
+            assert!(err.contains(expected), "{code}: {err}");
+        }
+    }
+
     // Security Tests
 
     #[tokio::test(flavor = "multi_thread")]
@@ -753,6 +2454,10 @@ mod tests {
         let args = CodeModeArgs {
             code: large_code,
             timeout: Some(1000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
 
         let result = codemode.execute(args).await;
@@ -780,6 +2485,10 @@ mod tests {
             let args = CodeModeArgs {
                 code: code.to_string(),
                 timeout: Some(1000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
             };
 
             let result = codemode.execute(args).await;
@@ -791,6 +2500,144 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn security_allows_benign_lookalikes() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let benign = [
+            ("// fn helper\n1 + 1", serde_json::json!(2)),
+            (
+                r#"let s = "import this"; s"#,
+                serde_json::json!("import this"),
+            ),
+            (
+                r#""eval(x) and loop { }""#,
+                serde_json::json!("eval(x) and loop { }"),
+            ),
+            (
+                "let double = |x| x * 2; double.call(21)",
+                serde_json::json!(42),
+            ),
+            ("let n = 0; while n < 3 { n += 1; } n", serde_json::json!(3)),
+        ];
+        for (code, expected) in benign {
+            let res = codemode
+                .execute(CodeModeArgs {
+                    code: code.to_string(),
+                    timeout: Some(1_000),
+                    dry_run: false,
+                    allowed_tools: None,
+                    engine: None,
+                    expected_schema: None,
+                })
+                .await
+                .unwrap_or_else(|e| panic!("{code}: {e}"));
+            assert_eq!(res.value, expected, "{code}");
+        }
+    }
+
+    #[tokio::test]
+    async fn security_rejects_offenders_anywhere_in_the_script() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client.clone());
+
+        let offenders = [
+            (
+                "fn helper(x) { x }\nhelper(1)",
+                "definition of function 'helper'",
+            ),
+            (r#"import "os" as os; 1"#, "import statement"),
+            (r#"1 + eval("2")"#, "call to eval"),
+            ("let f = || { loop { } }; 1", "unbounded loop"),
+            ("do { } while true; 1", "unbounded loop"),
+            ("do { } until false; 1", "unbounded loop"),
+            ("while 1 == 1 { } 1", "unbounded loop"),
+            ("while !false { } 1", "unbounded loop"),
+            ("while 1 < 2 && true { } 1", "unbounded loop"),
+            ("do { } while 2 > 1; 1", "unbounded loop"),
+        ];
+        for (code, expected) in offenders {
+            let err = codemode
+                .execute(CodeModeArgs {
+                    code: code.to_string(),
+                    timeout: Some(1_000),
+                    dry_run: false,
+                    allowed_tools: None,
+                    engine: None,
+                    expected_schema: None,
+                })
+                .await
+                .expect_err(code)
+                .to_string();
+            assert!(err.contains("prohibited pattern"), "{code}: {err}");
+            assert!(err.contains(expected), "{code}: {err}");
+        }
+        // Nothing ran
+        assert!(client.called.lock().await.is_empty());
+
+        // An always-true condition that doesn't fold passes the check but hits the limit
+        let err = codemode
+            .execute(CodeModeArgs {
+                code: "let x = 1; while x == x { } 1".to_string(),
+                timeout: Some(5_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("operations"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn config_toggles_each_restriction() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                allow_eval: true,
+                allow_unbounded_loops: true,
+                ..CodeModeConfig::default()
+            },
+        );
+        let run = |code: &str| {
+            codemode.execute(CodeModeArgs {
+                code: code.to_string(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+        };
+
+        let res = run(r#"eval("20 + 1") * 2"#).await.unwrap();
+        assert_eq!(res.value, serde_json::json!(42));
+        let res = run("let i = 0; loop { i += 1; if i > 3 { break; } } i")
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(4));
+
+        // Restrictions that were not lifted still apply
+        let err = run(r#"import "os" as os; 1"#).await.unwrap_err();
+        assert!(err.to_string().contains("import statement"), "{}", err);
+        let err = run("fn f() { 1 } f()").await.unwrap_err();
+        assert!(
+            err.to_string().contains("definition of function"),
+            "{}",
+            err
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn security_enforces_timeout() {
         let client = Arc::new(MockClient {
@@ -803,6 +2650,10 @@ mod tests {
         let args = CodeModeArgs {
             code: code.to_string(),
             timeout: Some(1), // Very short timeout - 1ms
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
 
         let result = codemode.execute(args).await;
@@ -829,6 +2680,10 @@ mod tests {
         let args = CodeModeArgs {
             code: "42".to_string(),
             timeout: Some(60_000), // 60 seconds - over MAX_TIMEOUT_MS
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
 
         let result = codemode.execute(args).await;
@@ -849,6 +2704,10 @@ mod tests {
         let args = CodeModeArgs {
             code: code.to_string(),
             timeout: Some(10_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
         };
 
         let result = codemode.execute(args).await;
@@ -862,6 +2721,225 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn execute_captures_script_output() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let code = r#"
+            print("starting");
+            let n = call_tool("math.add", #{});
+            print(`got ${n}`);
+            debug("checked");
+            eprint("careful");
+            log("warn", "almost done");
+            n
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(5));
+        assert_eq!(res.stdout, "starting\ngot 5\n");
+        let stderr: Vec<&str> = res.stderr.lines().collect();
+        assert_eq!(stderr.len(), 3);
+        assert!(stderr[0].ends_with("| \"checked\""), "{}", stderr[0]);
+        assert_eq!(stderr[1..], ["careful", "[WARN] almost done"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn execute_rejects_unknown_log_level() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        let err = codemode
+            .execute(CodeModeArgs {
+                code: r#"log("loud", "hi"); 1"#.into(),
+                timeout: Some(1_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown log level 'loud'"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn security_truncates_captured_output() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::new(client);
+
+        // 16 lines of 640KB overflow the 10MB stdout buffer
+        let code = r#"
+            let s = "xxxxxxxxxx";
+            for i in 0..16 { s += s; }
+            for i in 0..16 { print(s); }
+            eprint("still here");
+            s.len()
+        "#;
+        let res = codemode
+            .execute(CodeModeArgs {
+                code: code.into(),
+                timeout: Some(10_000),
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(655_360));
+        assert_eq!(res.stdout.len(), MAX_OUTPUT_SIZE + TRUNCATION_MARKER.len());
+        assert!(res.stdout.ends_with(TRUNCATION_MARKER));
+        assert_eq!(res.stderr, "still here\n");
+    }
+
+    #[tokio::test]
+    async fn config_operation_budget_controls_loops() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let code = r#"let sum = 0; for i in 0..50000 { sum += i; } sum"#;
+        let args = CodeModeArgs {
+            code: code.to_string(),
+            timeout: Some(10_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
+        };
+
+        let err = CodeModeUtcp::new(client.clone())
+            .execute(args.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("operations"), "{}", err);
+
+        let raised = CodeModeUtcp::with_config(
+            client.clone(),
+            CodeModeConfig {
+                max_operations: 1_000_000,
+                ..CodeModeConfig::default()
+            },
+        );
+        let res = raised.execute(args).await.unwrap();
+        assert_eq!(res.value, serde_json::json!(1_249_975_000));
+
+        let small = r#"let sum = 0; for i in 0..100 { sum += i; } sum"#;
+        let args = CodeModeArgs {
+            code: small.to_string(),
+            timeout: Some(1_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
+        };
+        let res = CodeModeUtcp::new(client.clone())
+            .execute(args.clone())
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(4950));
+
+        let lowered = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                max_operations: 50,
+                ..CodeModeConfig::default()
+            },
+        );
+        let err = lowered.execute(args).await.unwrap_err();
+        assert!(err.to_string().contains("operations"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn config_controls_code_validation() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let config = CodeModeConfig {
+            max_code_size: 64,
+            max_timeout_ms: 2_000,
+            extra_banned_patterns: vec!["search_tools".to_string()],
+            allow_fn_definitions: true,
+            ..CodeModeConfig::default()
+        };
+        let codemode = CodeModeUtcp::with_config(client, config);
+        let run = |code: &str, timeout: Option<u64>| {
+            codemode.execute(CodeModeArgs {
+                code: code.to_string(),
+                timeout,
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+        };
+
+        let res = run("fn double(x) { x * 2 } double(21)", None)
+            .await
+            .unwrap();
+        assert_eq!(res.value, serde_json::json!(42));
+
+        let err = run(r#"search_tools("a", 1)"#, None).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("prohibited pattern: 'search_tools'"));
+        let err = run(&"1 + ".repeat(20), None).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum allowed 64 bytes"));
+        let err = run("1", Some(3_000)).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum"));
+    }
+
+    #[tokio::test]
+    async fn config_limits_stream_items() {
+        let client = Arc::new(MockClient {
+            called: Arc::new(Mutex::new(Vec::new())),
+        });
+        let codemode = CodeModeUtcp::with_config(
+            client,
+            CodeModeConfig {
+                max_stream_items: 0,
+                ..CodeModeConfig::default()
+            },
+        );
+        let err = codemode
+            .execute(CodeModeArgs {
+                code: r#"call_tool_stream("demo.tool", #{})"#.to_string(),
+                timeout: None,
+                dry_run: false,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Stream exceeded maximum 0 items"));
+    }
+
+    #[test]
+    fn output_buffer_cuts_on_char_boundaries() {
+        let mut buf = OutputBuffer::new(6);
+        buf.push_line("abc");
+        buf.push_line("déjà vu");
+        buf.push_line("dropped");
+        assert_eq!(buf.text, format!("abc\nd{}", TRUNCATION_MARKER));
+    }
+
     #[test]
     fn security_sprintf_limits_format_size() {
         let fmt = "x".repeat(20_000); // Over MAX_FMT_SIZE
@@ -0,0 +1,294 @@
+//! Codemode sessions: a series of executions sharing one set of script variables, so a
+//! snippet can build on what an earlier one fetched or computed.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::{CodeModeArgs, CodeModeResult, CodeModeUtcp, RetainedVars};
+
+/// A series of codemode executions whose top-level variables carry over from one snippet to
+/// the next. Variables are kept as JSON between executions, within the configured
+/// `max_session_bytes`. Create one with [`CodeModeUtcp::session`].
+pub struct CodeModeSession {
+    codemode: CodeModeUtcp,
+    vars: Mutex<RetainedVars>,
+}
+
+impl CodeModeSession {
+    pub(super) fn new(codemode: CodeModeUtcp) -> Self {
+        Self {
+            codemode,
+            vars: Mutex::new(RetainedVars::new()),
+        }
+    }
+
+    /// Execute a snippet with the session's variables in scope, keeping the variables it
+    /// leaves behind. A failed execution or a dry run leaves the session's variables
+    /// unchanged.
+    pub async fn execute(&self, args: CodeModeArgs) -> Result<CodeModeResult> {
+        // Held for the whole execution so snippets of one session run one at a time
+        let mut vars = self.vars.lock().await;
+        let dry_run = args.dry_run;
+        let (result, updated) = self.codemode.run(args, Some(vars.clone())).await?;
+        if dry_run {
+            return Ok(result);
+        }
+        let updated = updated.unwrap_or_default();
+        self.check_budget(&updated)?;
+        *vars = updated;
+        Ok(result)
+    }
+
+    /// Set a variable for the following executions.
+    pub async fn set_var(&self, name: &str, value: Value) -> Result<()> {
+        if !is_identifier(name) {
+            return Err(anyhow!("Invalid variable name '{}'", name));
+        }
+        let mut vars = self.vars.lock().await;
+        let mut updated = vars.clone();
+        updated.insert(name.to_string(), value);
+        self.check_budget(&updated)?;
+        *vars = updated;
+        Ok(())
+    }
+
+    /// The current value of a variable, if the session has it.
+    pub async fn get_var(&self, name: &str) -> Option<Value> {
+        self.vars.lock().await.get(name).cloned()
+    }
+
+    /// Names of the variables the session holds, in order.
+    pub async fn var_names(&self) -> Vec<String> {
+        self.vars.lock().await.keys().cloned().collect()
+    }
+
+    /// Forget every variable.
+    pub async fn reset(&self) {
+        self.vars.lock().await.clear();
+    }
+
+    fn check_budget(&self, vars: &RetainedVars) -> Result<()> {
+        let budget = self.codemode.config.max_session_bytes;
+        let size: usize = vars
+            .iter()
+            .map(|(name, value)| name.len() + serde_json::to_vec(value).map_or(0, |v| v.len()))
+            .sum();
+        if size > budget {
+            return Err(anyhow!(
+                "Session variables need {} bytes, exceeding the budget of {} bytes",
+                size,
+                budget
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::codemode::CodeModeConfig;
+    use crate::tools::Tool;
+    use crate::transports::stream::boxed_vec_stream;
+    use crate::UtcpClientInterface;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts `fetch` calls and answers each with a fixed record.
+    #[derive(Default)]
+    struct CountingClient {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UtcpClientInterface for CountingClient {
+        async fn register_tool_provider(
+            &self,
+            _prov: Arc<dyn crate::providers::base::Provider>,
+        ) -> Result<Vec<Tool>> {
+            Ok(vec![])
+        }
+
+        async fn register_tool_provider_with_tools(
+            &self,
+            _prov: Arc<dyn crate::providers::base::Provider>,
+            tools: Vec<Tool>,
+        ) -> Result<Vec<Tool>> {
+            Ok(tools)
+        }
+
+        async fn deregister_tool_provider(&self, _provider_name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn call_tool(
+            &self,
+            _tool_name: &str,
+            _args: HashMap<String, Value>,
+        ) -> Result<Value> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({"items": [3, 4, 5]}))
+        }
+
+        async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+            Ok(vec![])
+        }
+
+        fn get_transports(&self) -> HashMap<String, Arc<dyn crate::transports::ClientTransport>> {
+            HashMap::new()
+        }
+
+        async fn call_tool_stream(
+            &self,
+            _tool_name: &str,
+            _args: HashMap<String, Value>,
+        ) -> Result<Box<dyn crate::transports::stream::StreamResult>> {
+            Ok(boxed_vec_stream(vec![]))
+        }
+    }
+
+    fn args(code: &str) -> CodeModeArgs {
+        CodeModeArgs {
+            code: code.to_string(),
+            timeout: Some(1_000),
+            dry_run: false,
+            allowed_tools: None,
+            engine: None,
+            expected_schema: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn later_snippets_read_earlier_variables() {
+        let client = Arc::new(CountingClient::default());
+        let session = CodeModeUtcp::new(client.clone()).session();
+
+        session
+            .execute(args(
+                r#"let data = call_tool("data.fetch", #{}); data.items.len()"#,
+            ))
+            .await
+            .unwrap();
+        let res = session
+            .execute(args(
+                "let total = 0; for n in data.items { total += n; } total",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.value, json!(12));
+        assert_eq!(client.fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(session.get_var("total").await, Some(json!(12)));
+        assert_eq!(session.var_names().await, vec!["data", "total"]);
+
+        // Plain executions and other sessions start empty
+        let codemode = CodeModeUtcp::new(client);
+        assert!(codemode.execute(args("data")).await.is_err());
+        assert!(codemode.session().execute(args("data")).await.is_err());
+
+        session.reset().await;
+        assert!(session.execute(args("data")).await.is_err());
+        assert_eq!(session.get_var("total").await, None);
+    }
+
+    #[tokio::test]
+    async fn variables_set_from_rust_are_visible_to_scripts() {
+        let session = CodeModeUtcp::new(Arc::new(CountingClient::default())).session();
+        session
+            .set_var("user", json!({"name": "Ada", "langs": ["en", "fr"]}))
+            .await
+            .unwrap();
+
+        let res = session
+            .execute(args(
+                r#"user.langs.push("de"); `${user.name}: ${user.langs.len()}`"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.value, json!("Ada: 3"));
+        assert_eq!(
+            session.get_var("user").await,
+            Some(json!({"name": "Ada", "langs": ["en", "fr", "de"]}))
+        );
+
+        let err = session.set_var("not a name", json!(1)).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid variable name"));
+    }
+
+    #[tokio::test]
+    async fn dry_runs_leave_variables_alone() {
+        let client = Arc::new(CountingClient::default());
+        let session = CodeModeUtcp::new(client.clone()).session();
+        session.execute(args("let n = 1;")).await.unwrap();
+
+        let res = session
+            .execute(CodeModeArgs {
+                code: r#"n = 2; let data = call_tool("data.fetch", #{}); n"#.to_string(),
+                timeout: Some(1_000),
+                dry_run: true,
+                allowed_tools: None,
+                engine: None,
+                expected_schema: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.value, json!(2));
+        assert_eq!(res.plan.len(), 1);
+        assert_eq!(client.fetches.load(Ordering::SeqCst), 0);
+        assert_eq!(session.var_names().await, vec!["n"]);
+        assert_eq!(session.get_var("n").await, Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn failed_executions_keep_previous_variables() {
+        let session = CodeModeUtcp::new(Arc::new(CountingClient::default())).session();
+        session.execute(args("let n = 1;")).await.unwrap();
+
+        let err = session
+            .execute(args(r#"n = 2; throw "boom""#))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"));
+        assert_eq!(session.get_var("n").await, Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn retained_variables_are_bounded() {
+        let codemode = CodeModeUtcp::with_config(
+            Arc::new(CountingClient::default()),
+            CodeModeConfig {
+                max_session_bytes: 64,
+                ..CodeModeConfig::default()
+            },
+        );
+        let session = codemode.session();
+        session.execute(args(r#"let small = "ok";"#)).await.unwrap();
+
+        let err = session
+            .execute(args(
+                r#"let big = "xxxxxxxxxx"; for i in 0..4 { big += big; } big.len()"#,
+            ))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("exceeding the budget of 64 bytes"),
+            "{}",
+            err
+        );
+        assert_eq!(session.var_names().await, vec!["small"]);
+
+        let err = session
+            .set_var("big", Value::String("x".repeat(100)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeding the budget"));
+        assert_eq!(session.get_var("big").await, None);
+    }
+}
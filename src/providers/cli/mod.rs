@@ -9,11 +9,66 @@ use crate::providers::base::{BaseProvider, Provider, ProviderType};
 pub struct CliProvider {
     #[serde(flatten)]
     pub base: BaseProvider,
+    /// Program and leading arguments, split with shell quoting rules and run without a shell
     pub command_name: String,
+    /// Run `command_name` through `sh -c` (`cmd /C` on Windows) for pipes, globs and other
+    /// shell features, appending the call's arguments quoted for that shell
+    #[serde(default)]
+    pub use_shell: bool,
+    /// Whether each call runs the command anew or talks to one process kept running
+    #[serde(default)]
+    pub mode: CliMode,
+    /// Times a `persistent` process that exits is started again to retry the failed call, with
+    /// a growing delay between attempts. A dead process is replaced by the next call anyway.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub working_dir: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Variables added to the command's environment. Values may reference the client's own
+    /// environment as `${VAR}`.
+    #[serde(default, alias = "env", skip_serializing_if = "Option::is_none")]
     pub env_vars: Option<HashMap<String, String>>,
+    /// How long a command may run before it is killed (default 30000ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Return `{exit_code, stdout, stderr, duration_ms}` for every call, instead of stdout
+    /// alone and an error for a non-zero exit
+    #[serde(default)]
+    pub structured_output: bool,
+    /// Arguments to run the command with, in place of `call <provider> <tool> --flags`.
+    /// `{name}` is replaced by the call argument `name`, and each element stays a single
+    /// argument whatever it contains; `{{` and `}}` stand for literal braces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args_template: Option<Vec<String>>,
+    /// Templates for single tools, by tool name, used instead of `args_template`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_args_templates: HashMap<String, Vec<String>>,
+    /// What happens to call arguments that no template placeholder used
+    #[serde(default)]
+    pub leftover_args: LeftoverArgs,
+}
+
+/// How a CLI provider's command is run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CliMode {
+    /// A new process for every call.
+    #[default]
+    Oneshot,
+    /// One process kept running, sent a JSON request per line on stdin and answering each with
+    /// a JSON line on stdout.
+    Persistent,
+}
+
+/// Where call arguments go when an args template did not use them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeftoverArgs {
+    /// Send them to the command's stdin as a JSON object.
+    #[default]
+    Stdin,
+    /// Leave them out, giving the command no stdin.
+    Drop,
 }
 
 impl Provider for CliProvider {
@@ -41,10 +96,26 @@ impl CliProvider {
                 allowed_communication_protocols: None,
             },
             command_name,
+            use_shell: false,
+            mode: CliMode::Oneshot,
+            max_restarts: None,
             working_dir: None,
             env_vars: None,
+            timeout_ms: None,
+            structured_output: false,
+            args_template: None,
+            tool_args_templates: HashMap::new(),
+            leftover_args: LeftoverArgs::Stdin,
         }
     }
+
+    /// The args template for `tool`, if the provider has one.
+    pub fn args_template_for(&self, tool: &str) -> Option<&[String]> {
+        self.tool_args_templates
+            .get(tool)
+            .or(self.args_template.as_ref())
+            .map(Vec::as_slice)
+    }
 }
 
 #[cfg(test)]
@@ -65,9 +136,40 @@ mod tests {
         assert_eq!(provider.command_name, "echo");
         assert!(provider.working_dir.is_none());
         assert!(provider.env_vars.is_none());
+        assert!(provider.timeout_ms.is_none());
+        assert!(!provider.structured_output);
+        assert!(!provider.use_shell);
+        assert_eq!(provider.mode, CliMode::Oneshot);
+        assert!(provider.args_template.is_none());
+        assert_eq!(provider.leftover_args, LeftoverArgs::Stdin);
         assert_eq!(provider.type_(), ProviderType::Cli);
     }
 
+    #[test]
+    fn deserializes_env_working_dir_and_timeout() {
+        let json = json!({
+            "name": "builder",
+            "provider_type": "cli",
+            "command_name": "make",
+            "working_dir": "/srv/project",
+            "env": {"TOKEN": "${BUILD_TOKEN}"},
+            "timeout_ms": 5000,
+            "structured_output": true
+        });
+
+        let provider: CliProvider = serde_json::from_value(json).unwrap();
+        assert_eq!(provider.working_dir.as_deref(), Some("/srv/project"));
+        assert_eq!(
+            provider.env_vars,
+            Some(HashMap::from([(
+                "TOKEN".to_string(),
+                "${BUILD_TOKEN}".to_string()
+            )]))
+        );
+        assert_eq!(provider.timeout_ms, Some(5000));
+        assert!(provider.structured_output);
+    }
+
     #[test]
     fn cli_provider_new_sets_defaults() {
         let provider = CliProvider::new("builder".to_string(), "make".to_string(), None);
@@ -78,4 +180,36 @@ mod tests {
         assert!(provider.working_dir.is_none());
         assert!(provider.env_vars.is_none());
     }
+
+    #[test]
+    fn tool_templates_take_precedence() {
+        let json = json!({
+            "name": "aws",
+            "provider_type": "cli",
+            "command_name": "aws",
+            "args_template": ["s3", "ls", "{bucket}"],
+            "tool_args_templates": {
+                "copy": ["s3", "cp", "{source}", "{target}"]
+            },
+            "leftover_args": "drop",
+            "mode": "persistent",
+            "max_restarts": 2
+        });
+
+        let provider: CliProvider = serde_json::from_value(json).unwrap();
+        assert_eq!(provider.leftover_args, LeftoverArgs::Drop);
+        assert_eq!(provider.mode, CliMode::Persistent);
+        assert_eq!(provider.max_restarts, Some(2));
+        assert_eq!(
+            provider.args_template_for("copy").unwrap(),
+            ["s3", "cp", "{source}", "{target}"]
+        );
+        assert_eq!(
+            provider.args_template_for("list").unwrap(),
+            ["s3", "ls", "{bucket}"]
+        );
+
+        let plain = CliProvider::new("plain".to_string(), "tool".to_string(), None);
+        assert!(plain.args_template_for("list").is_none());
+    }
 }
@@ -3,6 +3,63 @@ use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
+use crate::providers::http::{HttpTimeouts, ProxyConfig};
+use crate::providers::websocket::WebSocketTlsConfig;
+
+/// WebSocket subprotocol used for subscriptions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphqlWsProtocol {
+    /// The `graphql-ws` library protocol (`subscribe`/`next`).
+    #[default]
+    #[serde(rename = "graphql-transport-ws")]
+    GraphqlTransportWs,
+    /// The legacy subscriptions-transport-ws protocol (`start`/`data`/`stop`).
+    #[serde(rename = "graphql-ws")]
+    GraphqlWs,
+    /// Offer both and follow the server's `Sec-WebSocket-Protocol` choice, falling back to
+    /// `graphql-transport-ws` when it names neither.
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+impl GraphqlWsProtocol {
+    /// Subprotocol name sent in `Sec-WebSocket-Protocol`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GraphqlTransportWs => "graphql-transport-ws",
+            Self::GraphqlWs => "graphql-ws",
+            Self::Auto => "graphql-transport-ws, graphql-ws",
+        }
+    }
+}
+
+/// Re-subscribing after a subscription connection drops before `complete`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphqlReconnect {
+    /// Attempts after each drop before the stream ends with an error.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first attempt, doubled after every failed one.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+impl Default for GraphqlReconnect {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
 
 /// Provider configuration for GraphQL endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +73,44 @@ pub struct GraphqlProvider {
     pub operation_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<HttpTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// TLS settings for `wss://` subscription connections.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<WebSocketTlsConfig>,
+    /// How many levels of nested object fields generated queries select.
+    #[serde(default = "default_selection_depth")]
+    pub selection_depth: usize,
+    /// Subprotocol spoken on subscription connections.
+    #[serde(default)]
+    pub ws_protocol: GraphqlWsProtocol,
+    /// Send automatic persisted queries: the query's SHA-256 hash first, and the full text
+    /// only when the server has not seen it yet.
+    #[serde(default)]
+    pub persisted_queries: bool,
+    /// Return the data of responses that also carry errors, listing the errors under
+    /// `_errors`, instead of failing with [`UtcpError::GraphQL`](crate::errors::UtcpError).
+    #[serde(default)]
+    pub allow_partial_data: bool,
+    /// Interval between client pings on subscription connections; unset sends none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_interval_ms: Option<u64>,
+    /// End (or reconnect) a subscription that receives nothing, pings included, for this long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_ms: Option<u64>,
+    /// Re-subscribe when the connection drops before `complete`; unset ends the stream with an
+    /// error instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<GraphqlReconnect>,
+}
+
+/// Selection depth applied when a provider does not set `selection_depth`.
+pub const DEFAULT_SELECTION_DEPTH: usize = 2;
+
+fn default_selection_depth() -> usize {
+    DEFAULT_SELECTION_DEPTH
 }
 
 impl Provider for GraphqlProvider {
@@ -46,6 +141,16 @@ impl GraphqlProvider {
             operation_type: Self::default_operation(),
             operation_name: None,
             headers: None,
+            timeouts: None,
+            proxy: None,
+            tls: None,
+            selection_depth: DEFAULT_SELECTION_DEPTH,
+            ws_protocol: GraphqlWsProtocol::default(),
+            persisted_queries: false,
+            allow_partial_data: false,
+            ping_interval_ms: None,
+            idle_timeout_ms: None,
+            reconnect: None,
         }
     }
 
@@ -72,6 +177,50 @@ mod tests {
         assert_eq!(provider.operation_type, "query");
         assert!(provider.operation_name.is_none());
         assert!(provider.headers.is_none());
+        assert_eq!(provider.selection_depth, DEFAULT_SELECTION_DEPTH);
+        assert_eq!(provider.ws_protocol, GraphqlWsProtocol::GraphqlTransportWs);
+    }
+
+    #[test]
+    fn graphql_provider_reconnect_defaults() {
+        let provider: GraphqlProvider = serde_json::from_value(json!({
+            "name": "subs",
+            "provider_type": "graphql",
+            "url": "http://localhost:4000/graphql",
+            "ping_interval_ms": 15000,
+            "reconnect": { "max_attempts": 5 }
+        }))
+        .unwrap();
+        assert_eq!(provider.ping_interval_ms, Some(15000));
+        assert!(provider.idle_timeout_ms.is_none());
+        assert_eq!(
+            provider.reconnect,
+            Some(GraphqlReconnect {
+                max_attempts: 5,
+                backoff_ms: 500,
+            })
+        );
+    }
+
+    #[test]
+    fn graphql_provider_parses_ws_protocol() {
+        for (name, protocol) in [
+            (
+                "graphql-transport-ws",
+                GraphqlWsProtocol::GraphqlTransportWs,
+            ),
+            ("graphql-ws", GraphqlWsProtocol::GraphqlWs),
+            ("auto", GraphqlWsProtocol::Auto),
+        ] {
+            let provider: GraphqlProvider = serde_json::from_value(json!({
+                "name": "subs",
+                "provider_type": "graphql",
+                "url": "http://localhost:4000/graphql",
+                "ws_protocol": name,
+            }))
+            .unwrap();
+            assert_eq!(provider.ws_protocol, protocol);
+        }
     }
 
     #[test]
@@ -3,6 +3,29 @@ use serde::{Deserialize, Serialize};
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
 
+/// HTTP/2 keepalive pings on a provider's channel, which detect dead connections that would
+/// otherwise look idle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrpcKeepalive {
+    /// Interval between pings.
+    pub interval_ms: u64,
+    /// How long to wait for a ping to be acknowledged before closing the connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Also ping while no call is in flight.
+    #[serde(default)]
+    pub while_idle: bool,
+}
+
+/// Message compression for a gRPC provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrpcCompression {
+    Gzip,
+    /// Needs the `grpc-zstd` cargo feature.
+    Zstd,
+}
+
 /// Provider definition for gRPC services.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrpcProvider {
@@ -12,6 +35,32 @@ pub struct GrpcProvider {
     pub port: u16,
     #[serde(default)]
     pub use_ssl: bool,
+    /// Discover tools through the server reflection API instead of the UTCP `GetManual` call.
+    /// Every method of every listed service becomes a tool named `package.Service.Method`.
+    #[serde(default)]
+    pub use_reflection: bool,
+    /// Speak gRPC-Web over HTTP/1.1, for servers behind a gRPC-Web proxy such as Envoy. Needs
+    /// the `grpc-web` cargo feature; unary and server-streaming calls only.
+    #[serde(default)]
+    pub grpc_web: bool,
+    /// Deadline for each call, sent to the server as `grpc-timeout`. Streaming calls only bound
+    /// the wait for the server to start responding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keepalive: Option<GrpcKeepalive>,
+    /// Size HTTP/2 flow-control windows from the measured bandwidth-delay product.
+    #[serde(default)]
+    pub http2_adaptive_window: bool,
+    /// Compress requests with this encoding and accept responses compressed with it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<GrpcCompression>,
+    /// Largest response message accepted, in bytes; tonic's default is 4 MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_decoding_message_size: Option<usize>,
+    /// Largest request message sent, in bytes; unlimited by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_encoding_message_size: Option<usize>,
 }
 
 impl Provider for GrpcProvider {
@@ -41,6 +90,14 @@ impl GrpcProvider {
             host,
             port,
             use_ssl: false,
+            use_reflection: false,
+            grpc_web: false,
+            timeout_ms: None,
+            keepalive: None,
+            http2_adaptive_window: false,
+            compression: None,
+            max_decoding_message_size: None,
+            max_encoding_message_size: None,
         }
     }
 }
@@ -64,6 +121,9 @@ mod tests {
         assert_eq!(provider.host, "localhost");
         assert_eq!(provider.port, 50051);
         assert!(!provider.use_ssl);
+        assert!(!provider.use_reflection);
+        assert!(provider.timeout_ms.is_none());
+        assert!(provider.keepalive.is_none());
     }
 
     #[test]
@@ -82,6 +142,36 @@ mod tests {
         assert_eq!(provider.port, 443);
     }
 
+    #[test]
+    fn grpc_provider_reads_channel_settings() {
+        let json = json!({
+            "name": "tuned-grpc",
+            "provider_type": "grpc",
+            "host": "localhost",
+            "port": 50051,
+            "timeout_ms": 2000,
+            "keepalive": { "interval_ms": 30000, "while_idle": true },
+            "http2_adaptive_window": true,
+            "compression": "gzip",
+            "max_decoding_message_size": 16777216
+        });
+
+        let provider: GrpcProvider = serde_json::from_value(json).unwrap();
+        assert_eq!(provider.timeout_ms, Some(2000));
+        assert_eq!(
+            provider.keepalive,
+            Some(GrpcKeepalive {
+                interval_ms: 30000,
+                timeout_ms: None,
+                while_idle: true,
+            })
+        );
+        assert!(provider.http2_adaptive_window);
+        assert_eq!(provider.compression, Some(GrpcCompression::Gzip));
+        assert_eq!(provider.max_decoding_message_size, Some(16 << 20));
+        assert!(provider.max_encoding_message_size.is_none());
+    }
+
     #[test]
     fn grpc_provider_new_sets_defaults() {
         let provider =
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
+use crate::openapi::ConverterOptions;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
 
 /// Provider configuration for HTTP-based tools.
@@ -19,6 +20,191 @@ pub struct HttpProvider {
     pub body_field: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub header_fields: Option<Vec<String>>,
+    /// Names of `{placeholder}` segments in `url` filled from call arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_params: Option<Vec<String>>,
+    /// Serialization rules for query parameters, keyed by argument name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param_styles: Option<HashMap<String, ParamStyle>>,
+    /// Encoding of array and object arguments without a `param_styles` entry.
+    #[serde(default)]
+    pub query_style: QueryEncoding,
+    /// Operations to keep when `url` points at an OpenAPI document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openapi_filters: Option<ConverterOptions>,
+    /// Retries for 429 and 5xx responses; unset uses the transport default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Longest wait before a retry, including waits requested via `Retry-After`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retry_delay_ms: Option<u64>,
+    /// Also retry non-idempotent methods such as POST and PATCH.
+    #[serde(default)]
+    pub retry_non_idempotent: bool,
+    /// Connect, per-request and total time limits; unset fields keep the transport defaults.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<HttpTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// Total size allowed for files in a multipart upload; unset uses the transport default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_upload_bytes: Option<u64>,
+    /// Directory `$file` arguments may read from; relative paths resolve against it. Unset
+    /// rejects uploads from paths.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_root: Option<String>,
+    /// Binary responses larger than this are saved to a temp file instead of inlined as base64.
+    /// The caller owns that file and should delete it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_inline_binary_bytes: Option<u64>,
+    /// Return `{"status", "headers", "body"}` instead of the bare response body.
+    #[serde(default)]
+    pub return_envelope: bool,
+    /// Gzip JSON request bodies larger than 1 KiB and send them with `Content-Encoding: gzip`.
+    #[serde(default)]
+    pub compress_requests: bool,
+    /// Keep cookies set by the server in a per-provider jar and send them on later requests.
+    #[serde(default)]
+    pub use_cookies: bool,
+    /// Redirect handling; unset follows up to 10 redirects with reqwest's defaults.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<RedirectPolicy>,
+    /// Revalidate repeated GET calls with `If-None-Match`/`If-Modified-Since` and reuse the
+    /// cached body on 304 Not Modified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_cache: Option<ResponseCacheConfig>,
+}
+
+/// Time limits for HTTP-based providers, in milliseconds. Unset fields keep the transport default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpTimeouts {
+    /// Establishing the TCP/TLS connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// Each attempt of a non-streaming call, including reading the response body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_ms: Option<u64>,
+    /// The whole call including retries; streaming calls only wait this long for the
+    /// response headers, never for the stream body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+}
+
+/// How an HTTP provider follows 3xx responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectPolicy {
+    /// Redirects followed before the call fails; `0` disables following.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    /// Follow redirects to a different scheme, host or port.
+    #[serde(default = "default_true")]
+    pub follow_cross_origin: bool,
+    /// Keep `Authorization`, cookies and API key headers on cross-origin hops.
+    #[serde(default)]
+    pub preserve_auth_header: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: default_max_redirects(),
+            follow_cross_origin: true,
+            preserve_auth_header: false,
+        }
+    }
+}
+
+fn default_max_redirects() -> usize {
+    10
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Limits for an HTTP provider's conditional-request cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// Responses kept per provider; the oldest is evicted first.
+    #[serde(default = "default_cache_entries")]
+    pub max_entries: usize,
+    /// How long a response may be revalidated before it is fetched in full again.
+    #[serde(default = "default_cache_ttl_ms")]
+    pub ttl_ms: u64,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_cache_entries(),
+            ttl_ms: default_cache_ttl_ms(),
+        }
+    }
+}
+
+fn default_cache_entries() -> usize {
+    128
+}
+
+fn default_cache_ttl_ms() -> u64 {
+    300_000
+}
+
+/// Outbound proxy for HTTP-based providers. WebSocket connections, including GraphQL
+/// subscriptions, are made directly and do not use it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL such as `http://proxy.internal:3128`, used for http and https targets.
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Hosts, domains (`.example.com`), IP addresses or CIDR ranges reached without the proxy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+/// OpenAPI `style` values supported for query parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryStyle {
+    Form,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+}
+
+/// How a query parameter's value is written into the query string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamStyle {
+    pub style: QueryStyle,
+    pub explode: bool,
+}
+
+impl Default for ParamStyle {
+    /// OpenAPI's default for query parameters: `style: form, explode: true`.
+    fn default() -> Self {
+        Self {
+            style: QueryStyle::Form,
+            explode: true,
+        }
+    }
+}
+
+/// How array and object arguments are written into a query string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryEncoding {
+    /// `key=1&key=2`; object fields become `key.field=value`.
+    #[default]
+    Repeat,
+    /// `key=1,2`; objects become `key=field,value`.
+    Comma,
+    /// `key[]=1&key[]=2`; objects become `key[field]=value`.
+    Brackets,
+    /// `key=<JSON text>` for every argument, strings included.
+    Json,
 }
 
 impl Provider for HttpProvider {
@@ -51,6 +237,23 @@ impl HttpProvider {
             headers: None,
             body_field: None,
             header_fields: None,
+            path_params: None,
+            param_styles: None,
+            query_style: QueryEncoding::Repeat,
+            openapi_filters: None,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_upload_bytes: None,
+            upload_root: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            compress_requests: false,
+            use_cookies: false,
+            redirect: None,
+            response_cache: None,
         }
     }
 }
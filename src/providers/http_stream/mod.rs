@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
+use crate::providers::http::{HttpTimeouts, ParamStyle, ProxyConfig, QueryEncoding};
 
 /// Provider definition for streaming HTTP endpoints that emit chunked JSON.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +15,46 @@ pub struct StreamableHttpProvider {
     pub http_method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    /// Encoding of array and object arguments on GET requests.
+    #[serde(default)]
+    pub query_style: QueryEncoding,
+    /// Retries for 429 and 5xx responses to non-streaming calls; unset uses the transport default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Longest wait before a retry, including waits requested via `Retry-After`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retry_delay_ms: Option<u64>,
+    /// Also retry non-idempotent methods such as POST and PATCH.
+    #[serde(default)]
+    pub retry_non_idempotent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<HttpTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// Binary responses to non-streaming calls larger than this are saved to a temp file instead of inlined as base64.
+    /// The caller owns that file and should delete it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_inline_binary_bytes: Option<u64>,
+    /// Return `{"status", "headers", "body"}` from `call_tool`, and start streams with a
+    /// `{"status", "headers"}` item.
+    #[serde(default)]
+    pub return_envelope: bool,
+    /// Keep cookies set by the server in a per-provider jar and send them on later requests.
+    #[serde(default)]
+    pub use_cookies: bool,
+    /// `url` is a complete operation URL, as generated from an OpenAPI document: calls go to
+    /// it with `path_params` filled in instead of to `url/{tool name}`.
+    #[serde(default)]
+    pub operation_url: bool,
+    /// Names of `{placeholder}` segments in an operation `url` filled from call arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_params: Option<Vec<String>>,
+    /// Serialization rules for query parameters of an operation `url`, keyed by argument name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param_styles: Option<HashMap<String, ParamStyle>>,
+    /// Argument sent as the whole body of an operation `url`; other arguments go in the query.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_field: Option<String>,
 }
 
 impl Provider for StreamableHttpProvider {
@@ -43,6 +84,19 @@ impl StreamableHttpProvider {
             url,
             http_method: Self::default_method(),
             headers: None,
+            query_style: QueryEncoding::Repeat,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            use_cookies: false,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
+            body_field: None,
         }
     }
 
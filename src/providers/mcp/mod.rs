@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
+use crate::providers::http::{HttpTimeouts, ProxyConfig};
 
 /// Provider definition for MCP servers reachable over HTTP or stdio.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +15,10 @@ pub struct McpProvider {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<HttpTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
     // Stdio transport fields
     #[serde(skip_serializing_if = "Option::is_none")]
     pub command: Option<String>,
@@ -21,6 +26,14 @@ pub struct McpProvider {
     pub args: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub env_vars: Option<HashMap<String, String>>,
+    /// Times a stdio process that exits is started again to retry the failed request, with a
+    /// growing delay between attempts. A dead process is replaced by the next request anyway.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Milliseconds to wait for each response from a stdio server before the request is
+    /// abandoned; 60 seconds when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout_ms: Option<u64>,
 }
 
 impl Provider for McpProvider {
@@ -49,9 +62,13 @@ impl McpProvider {
             },
             url: Some(url),
             headers: None,
+            timeouts: None,
+            proxy: None,
             command: None,
             args: None,
             env_vars: None,
+            max_restarts: None,
+            request_timeout_ms: None,
         }
     }
 
@@ -71,9 +88,13 @@ impl McpProvider {
             },
             url: None,
             headers: None,
+            timeouts: None,
+            proxy: None,
             command: Some(command),
             args,
             env_vars,
+            max_restarts: None,
+            request_timeout_ms: None,
         }
     }
 
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
+use crate::providers::http::{HttpTimeouts, ParamStyle, ProxyConfig};
 
 /// Provider definition for Server-Sent Events endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,12 +11,89 @@ pub struct SseProvider {
     #[serde(flatten)]
     pub base: BaseProvider,
     pub url: String,
+    /// Method for tool calls: `POST` sends arguments as a JSON body, `GET` as query
+    /// parameters. Discovery always uses GET.
+    #[serde(default = "SseProvider::default_method")]
+    pub http_method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub body_field: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub header_fields: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<HttpTimeouts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// Start each event stream with a `{"status", "headers"}` item.
+    #[serde(default)]
+    pub return_envelope: bool,
+    /// Keep cookies set by the server in a per-provider jar and send them on later requests.
+    #[serde(default)]
+    pub use_cookies: bool,
+    /// Wrap each item as `{"event", "id", "data"}` instead of yielding the bare data.
+    #[serde(default)]
+    pub include_event_metadata: bool,
+    /// Event names to keep (unnamed events are `message`); others are dropped. `error`
+    /// events always end up as stream errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_filter: Option<Vec<String>>,
+    /// End the stream with a timeout error when no bytes, keep-alive comments included,
+    /// arrive for this long. Defaults to 120s; `null` waits indefinitely.
+    #[serde(default = "default_idle_timeout_ms")]
+    pub idle_timeout_ms: Option<u64>,
+    /// Where registration finds the tool manual.
+    #[serde(default)]
+    pub discovery_mode: SseDiscoveryMode,
+    /// Largest `data` payload of a single event; bigger events end the stream with an error.
+    #[serde(default = "default_max_event_bytes")]
+    pub max_event_bytes: usize,
+    /// Largest line kept while waiting for its newline. Over-long data lines end the stream
+    /// with an error; other over-long lines are dropped.
+    #[serde(default = "default_max_buffer_bytes")]
+    pub max_buffer_bytes: usize,
+    /// `url` is a complete operation URL, as generated from an OpenAPI document: calls go to
+    /// it with `path_params` filled in instead of to `url/{tool name}`, and `body_field`
+    /// names the argument sent as the whole body, as for HTTP providers.
+    #[serde(default)]
+    pub operation_url: bool,
+    /// Names of `{placeholder}` segments in an operation `url` filled from call arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_params: Option<Vec<String>>,
+    /// Serialization rules for query parameters of an operation `url`, keyed by argument name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param_styles: Option<HashMap<String, ParamStyle>>,
+}
+
+/// Source of an SSE provider's tool manual.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SseDiscoveryMode {
+    /// `GET` the provider URL and read a JSON manual from the response body.
+    #[default]
+    Endpoint,
+    /// Open the event stream at the provider URL and take the first `event: manual`.
+    Stream,
+}
+
+/// Event size limit applied when a provider does not set `max_event_bytes`.
+pub const DEFAULT_MAX_EVENT_BYTES: usize = 1024 * 1024;
+/// Line buffer limit applied when a provider does not set `max_buffer_bytes`.
+pub const DEFAULT_MAX_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Idle limit applied when a provider does not set `idle_timeout_ms`.
+pub const DEFAULT_IDLE_TIMEOUT_MS: u64 = 120_000;
+
+fn default_idle_timeout_ms() -> Option<u64> {
+    Some(DEFAULT_IDLE_TIMEOUT_MS)
+}
+
+fn default_max_event_bytes() -> usize {
+    DEFAULT_MAX_EVENT_BYTES
+}
+
+fn default_max_buffer_bytes() -> usize {
+    DEFAULT_MAX_BUFFER_BYTES
 }
 
 impl Provider for SseProvider {
@@ -43,11 +121,29 @@ impl SseProvider {
                 allowed_communication_protocols: None,
             },
             url,
+            http_method: Self::default_method(),
             headers: None,
             body_field: None,
             header_fields: None,
+            timeouts: None,
+            proxy: None,
+            return_envelope: false,
+            use_cookies: false,
+            include_event_metadata: false,
+            event_filter: None,
+            idle_timeout_ms: default_idle_timeout_ms(),
+            discovery_mode: SseDiscoveryMode::Endpoint,
+            max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
         }
     }
+
+    fn default_method() -> String {
+        "POST".to_string()
+    }
 }
 
 #[cfg(test)]
@@ -3,6 +3,27 @@ use serde::{Deserialize, Serialize};
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
 
+/// TLS settings for a TCP provider. Connections verify the server against the bundled web PKI
+/// roots unless told otherwise; a client certificate turns on mutual TLS.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TcpTlsConfig {
+    /// PEM file with extra CA certificates to trust, such as an internal CA.
+    #[serde(alias = "ca_cert", skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<String>,
+    /// PEM certificate chain presented as the client identity; needs `client_key_path`.
+    #[serde(alias = "client_cert", skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<String>,
+    /// PEM private key for `client_cert_path`.
+    #[serde(alias = "client_key", skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<String>,
+    /// Name to verify the server certificate against and send as SNI; defaults to `host`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sni_hostname: Option<String>,
+    /// Accept any server certificate. Only meant for self-signed development servers.
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
 /// Provider definition for plain TCP endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TcpProvider {
@@ -12,6 +33,9 @@ pub struct TcpProvider {
     pub port: u16,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    /// Connect over TLS; needs the `tcp-tls` feature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TcpTlsConfig>,
 }
 
 impl Provider for TcpProvider {
@@ -41,6 +65,7 @@ impl TcpProvider {
             host,
             port,
             timeout_ms: Some(30_000),
+            tls: None,
         }
     }
 }
@@ -78,6 +103,34 @@ mod tests {
 
         let provider: TcpProvider = serde_json::from_value(json).unwrap();
         assert_eq!(provider.timeout_ms, Some(5000));
+        assert!(provider.tls.is_none());
+    }
+
+    #[test]
+    fn tcp_provider_reads_tls_settings() {
+        let json = json!({
+            "name": "secure-tcp",
+            "provider_type": "tcp",
+            "host": "10.0.0.5",
+            "port": 9443,
+            "tls": {
+                "ca_cert": "/etc/ssl/internal-ca.pem",
+                "client_cert_path": "/etc/ssl/client.pem",
+                "client_key_path": "/etc/ssl/client.key",
+                "sni_hostname": "tools.internal"
+            }
+        });
+
+        let provider: TcpProvider = serde_json::from_value(json).unwrap();
+        let tls = provider.tls.unwrap();
+        assert_eq!(
+            tls.ca_cert_path.as_deref(),
+            Some("/etc/ssl/internal-ca.pem")
+        );
+        assert_eq!(tls.client_cert_path.as_deref(), Some("/etc/ssl/client.pem"));
+        assert_eq!(tls.client_key_path.as_deref(), Some("/etc/ssl/client.key"));
+        assert_eq!(tls.sni_hostname.as_deref(), Some("tools.internal"));
+        assert!(!tls.insecure_skip_verify);
     }
 
     #[test]
@@ -1,9 +1,29 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
 
+/// Where a text provider's manual comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TextSource {
+    /// A manual file, a directory of manuals, or a glob, like `path`.
+    File { file: String },
+    /// A static manual served over HTTP(S), fetched when the provider is registered.
+    Url {
+        url: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<AuthConfig>,
+    },
+    /// A manual written into the provider definition itself.
+    Inline { inline: Value },
+}
+
 /// Provider definition for file-backed text tools.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextProvider {
@@ -11,6 +31,20 @@ pub struct TextProvider {
     pub base: BaseProvider,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_path: Option<PathBuf>,
+    /// Manual to load instead of `tools.json` (or `tools.yaml`): a JSON or YAML file, a directory of manuals, or a glob such
+    /// as `manuals/*.json`, relative to `base_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// More manuals to load, in the same forms as `path`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Manual to load from a file, a URL or the definition itself; `path` is short for a file
+    /// source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<TextSource>,
+    /// Reload the manuals whenever they change, without registering the provider again
+    #[serde(default)]
+    pub watch: bool,
 }
 
 impl Provider for TextProvider {
@@ -38,6 +72,10 @@ impl TextProvider {
                 allowed_communication_protocols: None,
             },
             base_path,
+            path: None,
+            paths: Vec::new(),
+            source: None,
+            watch: false,
         }
     }
 }
@@ -58,6 +96,10 @@ mod tests {
         let provider: TextProvider = serde_json::from_value(json).unwrap();
         assert_eq!(provider.base.name, "test-text");
         assert!(provider.base_path.is_none());
+        assert!(provider.path.is_none());
+        assert!(provider.paths.is_empty());
+        assert!(provider.source.is_none());
+        assert!(!provider.watch);
         assert_eq!(provider.base.provider_type, ProviderType::Text);
     }
 
@@ -66,11 +108,59 @@ mod tests {
         let json = json!({
             "name": "test-text-path",
             "provider_type": "text",
-            "base_path": "/tmp/tools"
+            "base_path": "/tmp/tools",
+            "watch": true
         });
 
         let provider: TextProvider = serde_json::from_value(json).unwrap();
         assert_eq!(provider.base_path.as_deref(), Some(Path::new("/tmp/tools")));
+        assert!(provider.watch);
+    }
+
+    #[test]
+    fn text_provider_deserializes_manual_paths() {
+        let json = json!({
+            "name": "catalog",
+            "provider_type": "text",
+            "path": "manuals/*.json",
+            "paths": ["extra", "more/tools.json"]
+        });
+
+        let provider: TextProvider = serde_json::from_value(json).unwrap();
+        assert_eq!(provider.path.as_deref(), Some("manuals/*.json"));
+        assert_eq!(provider.paths, vec!["extra", "more/tools.json"]);
+    }
+
+    #[test]
+    fn text_provider_deserializes_each_source_kind() {
+        let source = |source: Value| {
+            let json = json!({ "name": "t", "provider_type": "text", "source": source });
+            serde_json::from_value::<TextProvider>(json)
+                .unwrap()
+                .source
+                .unwrap()
+        };
+
+        assert!(matches!(
+            source(json!({ "file": "manuals" })),
+            TextSource::File { file } if file == "manuals"
+        ));
+        match source(json!({
+            "url": "https://example.com/manual.json",
+            "headers": { "X-Team": "tools" },
+            "auth": { "auth_type": "api_key", "api_key": "k", "var_name": "X-Key", "location": "header" }
+        })) {
+            TextSource::Url { url, headers, auth } => {
+                assert_eq!(url, "https://example.com/manual.json");
+                assert_eq!(headers["X-Team"], "tools");
+                assert!(matches!(auth, Some(AuthConfig::ApiKey(_))));
+            }
+            other => panic!("unexpected source: {:?}", other),
+        }
+        assert!(matches!(
+            source(json!({ "inline": { "tools": [] } })),
+            TextSource::Inline { inline } if inline == json!({ "tools": [] })
+        ));
     }
 
     #[test]
@@ -10,8 +10,17 @@ pub struct UdpProvider {
     pub base: BaseProvider,
     pub host: String,
     pub port: u16,
+    /// How long to wait for each reply datagram (default 30000ms)
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    /// Times to resend the request when no reply arrives within `timeout_ms`. Only safe for
+    /// idempotent tools: the server may have run the request whose reply was lost
+    #[serde(default)]
+    pub retries: u32,
+    /// Reply datagrams a call collects before returning (default 1). Collection also stops at a
+    /// `{"$done": true}` datagram or a pause between datagrams
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_datagrams: Option<usize>,
 }
 
 impl Provider for UdpProvider {
@@ -41,6 +50,8 @@ impl UdpProvider {
             host,
             port,
             timeout_ms: Some(30_000),
+            retries: 0,
+            max_response_datagrams: None,
         }
     }
 }
@@ -78,6 +89,24 @@ mod tests {
 
         let provider: UdpProvider = serde_json::from_value(json).unwrap();
         assert_eq!(provider.timeout_ms, Some(7000));
+        assert_eq!(provider.retries, 0);
+        assert_eq!(provider.max_response_datagrams, None);
+    }
+
+    #[test]
+    fn udp_provider_reads_retries_and_datagram_limit() {
+        let json = json!({
+            "name": "test-udp-retries",
+            "provider_type": "udp",
+            "host": "localhost",
+            "port": 9001,
+            "retries": 2,
+            "max_response_datagrams": 8
+        });
+
+        let provider: UdpProvider = serde_json::from_value(json).unwrap();
+        assert_eq!(provider.retries, 2);
+        assert_eq!(provider.max_response_datagrams, Some(8));
     }
 
     #[test]
@@ -13,19 +13,48 @@ pub struct IceServer {
     pub credential: Option<String>,
 }
 
+/// How the offer and answer travel between the peers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalingKind {
+    /// POST the offer as JSON and read the answer from the response.
+    #[default]
+    Http,
+    /// Send the offer on a WebSocket, then read the answer and trade ICE candidates on it.
+    Websocket,
+}
+
+/// The signaling server a WebRTC provider connects through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signaling {
+    #[serde(default)]
+    pub kind: SignalingKind,
+    pub url: String,
+}
+
 /// Provider definition for peer-to-peer WebRTC transports.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebRtcProvider {
     #[serde(flatten)]
     pub base: BaseProvider,
 
-    /// Signaling server URL (WebSocket or HTTP)
+    /// HTTP signaling server URL, kept as shorthand for `signaling` of the `http` kind
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub signaling_server: String,
 
+    /// Signaling server and protocol; takes precedence over `signaling_server`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signaling: Option<Signaling>,
+
     /// ICE servers (STUN/TURN)
     #[serde(default = "default_ice_servers")]
     pub ice_servers: Vec<IceServer>,
 
+    /// URL answering GET with time-limited TURN credentials, `{username, credential, ttl,
+    /// uris}`, added to `ice_servers` for each connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_endpoint: Option<String>,
+
     /// Data channel label
     #[serde(default = "default_channel_label")]
     pub channel_label: String,
@@ -41,6 +70,29 @@ pub struct WebRtcProvider {
     /// Max retransmits (for unordered channels)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_retransmits: Option<u16>,
+
+    /// How long to wait for ICE gathering before an HTTP offer is sent with the candidates
+    /// found so far (default 2000ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ice_gathering_timeout_ms: Option<u64>,
+
+    /// Largest data channel message to send; bigger payloads are split into `$chunk` frames.
+    /// Unset sends every payload whole
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_message_bytes: Option<usize>,
+
+    /// How long the whole connection setup may take: signaling, ICE and opening the data
+    /// channel. Unset leaves only the limits on each step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_ms: Option<u64>,
+
+    /// How long to wait for the data channel to open once signaling is done (default 10000ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_timeout_ms: Option<u64>,
+
+    /// How long a call waits for its response (default 30000ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_timeout_ms: Option<u64>,
 }
 
 fn default_ice_servers() -> Vec<IceServer> {
@@ -74,6 +126,15 @@ impl Provider for WebRtcProvider {
 }
 
 impl WebRtcProvider {
+    /// The signaling to connect through: `signaling` when set, otherwise HTTP to
+    /// `signaling_server`.
+    pub fn signaling(&self) -> Signaling {
+        self.signaling.clone().unwrap_or_else(|| Signaling {
+            kind: SignalingKind::Http,
+            url: self.signaling_server.clone(),
+        })
+    }
+
     /// Construct a WebRTC provider backed by a signaling server URL.
     pub fn new(name: String, signaling_server: String, auth: Option<AuthConfig>) -> Self {
         Self {
@@ -84,11 +145,18 @@ impl WebRtcProvider {
                 allowed_communication_protocols: None,
             },
             signaling_server,
+            signaling: None,
             ice_servers: default_ice_servers(),
+            credential_endpoint: None,
             channel_label: default_channel_label(),
             ordered: true,
             max_packet_life_time: None,
             max_retransmits: None,
+            ice_gathering_timeout_ms: None,
+            max_message_bytes: None,
+            connect_timeout_ms: None,
+            open_timeout_ms: None,
+            response_timeout_ms: None,
         }
     }
 }
@@ -132,10 +200,14 @@ mod tests {
                     "credential": "pass"
                 }
             ],
+            "credential_endpoint": "https://turn.example.com/credentials",
             "channel_label": "custom-label",
             "ordered": false,
             "max_packet_life_time": 100,
-            "max_retransmits": 5
+            "max_retransmits": 5,
+            "connect_timeout_ms": 15000,
+            "open_timeout_ms": 5000,
+            "response_timeout_ms": 60000
         });
 
         let provider: WebRtcProvider = serde_json::from_value(json).unwrap();
@@ -143,9 +215,45 @@ mod tests {
         assert_eq!(provider.signaling_server, "wss://signal.example.com");
         assert_eq!(provider.ice_servers.len(), 1);
         assert_eq!(provider.ice_servers[0].username.as_deref(), Some("user"));
+        assert_eq!(
+            provider.credential_endpoint.as_deref(),
+            Some("https://turn.example.com/credentials")
+        );
         assert_eq!(provider.channel_label, "custom-label");
         assert!(!provider.ordered);
         assert_eq!(provider.max_packet_life_time, Some(100));
         assert_eq!(provider.max_retransmits, Some(5));
+        assert_eq!(provider.connect_timeout_ms, Some(15000));
+        assert_eq!(provider.open_timeout_ms, Some(5000));
+        assert_eq!(provider.response_timeout_ms, Some(60000));
+    }
+
+    #[test]
+    fn test_webrtc_provider_signaling() {
+        let legacy: WebRtcProvider = serde_json::from_value(json!({
+            "name": "legacy",
+            "provider_type": "webrtc",
+            "signaling_server": "http://localhost:8080/offer"
+        }))
+        .unwrap();
+        assert_eq!(
+            legacy.signaling(),
+            Signaling {
+                kind: SignalingKind::Http,
+                url: "http://localhost:8080/offer".to_string(),
+            }
+        );
+
+        let websocket: WebRtcProvider = serde_json::from_value(json!({
+            "name": "ws",
+            "provider_type": "webrtc",
+            "signaling": { "kind": "websocket", "url": "wss://signal.example.com" }
+        }))
+        .unwrap();
+        assert_eq!(websocket.signaling().kind, SignalingKind::Websocket);
+        assert_eq!(websocket.signaling().url, "wss://signal.example.com");
+        let serialized = serde_json::to_value(&websocket).unwrap();
+        assert!(serialized.get("signaling_server").is_none());
+        assert_eq!(serialized["signaling"]["kind"], "websocket");
     }
 }
@@ -1,9 +1,107 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 use crate::auth::AuthConfig;
 use crate::providers::base::{BaseProvider, Provider, ProviderType};
 
+/// How requests and replies are laid out on a WebSocket connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebSocketFraming {
+    /// Dial `{url}/{tool}`, send the arguments as-is and collect frames until close.
+    #[default]
+    Raw,
+    /// Send `{jsonrpc, id, method, params}` to `url` and match replies by id, so calls can
+    /// share a connection.
+    Jsonrpc,
+}
+
+/// TLS settings for `wss://` connections. Unset fields keep the default of verifying the
+/// server against the bundled web PKI roots without a client certificate.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebSocketTlsConfig {
+    /// PEM file with extra CA certificates to trust, such as an internal CA.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<String>,
+    /// PEM certificate chain presented as the client identity; needs `client_key_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<String>,
+    /// PEM private key for `client_cert_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<String>,
+    /// Accept any server certificate. Only meant for self-signed development servers.
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+/// Reconnection of raw `call_tool_stream` connections that drop without a normal close.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebSocketReconnect {
+    /// Attempts after each drop before the stream ends with an error.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first attempt, doubled after every failed one.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Sent instead of the original arguments after reconnecting, with its `last_seq` field
+    /// set to the sequence number of the last message received.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resume_message: Option<Value>,
+    /// Field of incoming messages that carries their sequence number.
+    #[serde(default = "default_sequence_field")]
+    pub sequence_field: String,
+}
+
+impl Default for WebSocketReconnect {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            resume_message: None,
+            sequence_field: default_sequence_field(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_sequence_field() -> String {
+    "seq".to_string()
+}
+
+/// Serialization of WebSocket payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebSocketEncoding {
+    /// JSON text frames.
+    #[default]
+    Json,
+    /// MessagePack binary frames; requires the `msgpack` feature.
+    Msgpack,
+    /// CBOR binary frames; requires the `cbor` feature.
+    Cbor,
+}
+
+/// When a raw-framed `call_tool` stops reading frames and what it returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketResponseMode {
+    /// Return the first frame as the value and close the connection.
+    Single,
+    /// Return every frame as an array once the server closes the connection.
+    #[default]
+    CollectUntilClose,
+    /// Return the frames before the one matching `terminator` as an array.
+    UntilTerminator,
+}
+
 /// Provider configuration for WebSocket endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketProvider {
@@ -12,10 +110,30 @@ pub struct WebSocketProvider {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol: Option<String>,
+    /// Keep one connection to `url` open and multiplex calls over it, reconnecting when the
+    /// socket fails. Pooled connections always use JSON-RPC framing.
     #[serde(default)]
     pub keep_alive: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub framing: WebSocketFraming,
+    /// Encoding of outgoing payloads and binary replies. Text frames are always read as JSON.
+    #[serde(default)]
+    pub encoding: WebSocketEncoding,
+    #[serde(default)]
+    pub response_mode: WebSocketResponseMode,
+    /// Frame that ends an `until_terminator` call, `{"done": true}` when unset. Object
+    /// sentinels also match frames carrying extra fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminator: Option<Value>,
+    /// Limit for a whole `call_tool`, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<WebSocketTlsConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<WebSocketReconnect>,
 }
 
 impl Provider for WebSocketProvider {
@@ -46,6 +164,13 @@ impl WebSocketProvider {
             protocol: None,
             keep_alive: false,
             headers: None,
+            framing: WebSocketFraming::Raw,
+            encoding: WebSocketEncoding::Json,
+            response_mode: WebSocketResponseMode::CollectUntilClose,
+            terminator: None,
+            timeout_ms: None,
+            tls: None,
+            reconnect: None,
         }
     }
 }
@@ -68,6 +193,12 @@ mod tests {
         assert_eq!(provider.url, "ws://localhost:8080");
         assert!(!provider.keep_alive);
         assert!(provider.protocol.is_none());
+        assert_eq!(provider.framing, WebSocketFraming::Raw);
+        assert_eq!(provider.encoding, WebSocketEncoding::Json);
+        assert_eq!(
+            provider.response_mode,
+            WebSocketResponseMode::CollectUntilClose
+        );
     }
 
     #[test]
@@ -78,6 +209,13 @@ mod tests {
             "url": "wss://example.com/ws",
             "protocol": "json-rpc",
             "keep_alive": true,
+            "framing": "jsonrpc",
+            "encoding": "msgpack",
+            "response_mode": "until_terminator",
+            "terminator": { "status": "end" },
+            "timeout_ms": 5000,
+            "reconnect": { "max_attempts": 5 },
+            "tls": { "ca_cert_path": "/etc/ssl/internal-ca.pem", "insecure_skip_verify": true },
             "headers": {
                 "Authorization": "Bearer token"
             }
@@ -87,6 +225,28 @@ mod tests {
         assert_eq!(provider.url, "wss://example.com/ws");
         assert_eq!(provider.protocol.as_deref(), Some("json-rpc"));
         assert!(provider.keep_alive);
+        assert_eq!(provider.framing, WebSocketFraming::Jsonrpc);
+        assert_eq!(provider.encoding, WebSocketEncoding::Msgpack);
+        assert_eq!(
+            provider.response_mode,
+            WebSocketResponseMode::UntilTerminator
+        );
+        assert_eq!(provider.terminator, Some(json!({ "status": "end" })));
+        assert_eq!(provider.timeout_ms, Some(5000));
+        assert_eq!(
+            provider.reconnect,
+            Some(WebSocketReconnect {
+                max_attempts: 5,
+                ..WebSocketReconnect::default()
+            })
+        );
+        let tls = provider.tls.clone().unwrap();
+        assert_eq!(
+            tls.ca_cert_path.as_deref(),
+            Some("/etc/ssl/internal-ca.pem")
+        );
+        assert!(tls.client_cert_path.is_none());
+        assert!(tls.insecure_skip_verify);
         assert_eq!(
             provider
                 .headers
@@ -0,0 +1,281 @@
+use crate::auth::AuthConfig;
+use crate::config::UtcpClientConfig;
+use crate::loader::load_providers_with_tools_from_url;
+use crate::providers::http::HttpProvider;
+use crate::providers::text::TextProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::transports::http_client::SharedClient;
+use crate::{ProvidersRefresh, UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// The status and body the catalog server answers with.
+type Catalog = Arc<Mutex<(StatusCode, String)>>;
+
+/// Serves the catalog on every path to requests carrying `Authorization: Bearer secret`.
+async fn spawn_catalog_server(catalog: Catalog) -> String {
+    let make_svc = make_service_fn(move |_| {
+        let catalog = catalog.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let catalog = catalog.clone();
+                async move {
+                    let authorized = req
+                        .headers()
+                        .get("authorization")
+                        .is_some_and(|v| v == "Bearer secret");
+                    let (status, body) = if authorized {
+                        catalog.lock().unwrap().clone()
+                    } else {
+                        (StatusCode::UNAUTHORIZED, String::new())
+                    };
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .status(status)
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                }
+            }))
+        }
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// A manual with one tool per `(provider, tool, description)` entry.
+fn manual(tools: &[(&str, &str, &str)]) -> String {
+    let tools: Vec<Value> = tools
+        .iter()
+        .map(|(provider, tool, description)| {
+            json!({
+                "name": tool,
+                "description": description,
+                "inputs": {"type": "object"},
+                "outputs": {"type": "object"},
+                "tool_call_template": {
+                    "call_template_type": "http",
+                    "name": provider,
+                    "url": format!("http://example.com/{}", provider),
+                    "http_method": "GET"
+                }
+            })
+        })
+        .collect();
+    json!({
+        "manual_version": "1.0.0",
+        "utcp_version": "0.2.0",
+        "info": {"title": "catalog", "version": "1.0.0"},
+        "tools": tools
+    })
+    .to_string()
+}
+
+fn config(url: String) -> UtcpClientConfig {
+    UtcpClientConfig::new()
+        .with_providers_url(url)
+        .with_providers_url_header("Authorization".into(), "Bearer ${CATALOG_TOKEN}".into())
+        .with_variable("CATALOG_TOKEN".into(), "secret".into())
+}
+
+async fn client(config: UtcpClientConfig) -> Result<UtcpClient> {
+    UtcpClient::new(
+        config,
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+}
+
+async fn tool_names(client: &UtcpClient) -> Vec<String> {
+    client
+        .list_tools()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn providers_load_from_url_and_refresh_on_demand() {
+    let catalog: Catalog = Arc::new(Mutex::new((
+        StatusCode::OK,
+        manual(&[
+            ("weather", "forecast", "Forecast"),
+            ("units", "convert", "Convert units"),
+        ]),
+    )));
+    let base = spawn_catalog_server(catalog.clone()).await;
+    let client = client(config(format!("{}/catalog.json", base)))
+        .await
+        .unwrap();
+    assert_eq!(
+        tool_names(&client).await,
+        vec!["units.convert", "weather.forecast"]
+    );
+
+    // Providers registered directly are not part of the source
+    client
+        .register_tool_provider_with_tools(
+            Arc::new(TextProvider::new("local".to_string(), None, None)),
+            vec![serde_json::from_value(json!({
+                "name": "read",
+                "description": "",
+                "inputs": {"type": "object"},
+                "outputs": {"type": "object"},
+                "tags": []
+            }))
+            .unwrap()],
+        )
+        .await
+        .unwrap();
+
+    catalog.lock().unwrap().1 = manual(&[
+        ("weather", "forecast", "Forecast, hourly"),
+        ("news", "headlines", "Headlines"),
+    ]);
+    let refresh = client.refresh_providers_from_source().await.unwrap();
+    assert_eq!(
+        refresh,
+        ProvidersRefresh {
+            added: vec!["news".to_string()],
+            updated: vec!["weather".to_string()],
+            removed: vec!["units".to_string()],
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        tool_names(&client).await,
+        vec!["local.read", "news.headlines", "weather.forecast"]
+    );
+    let forecast = client.get_tool("forecast").await.unwrap().unwrap();
+    assert_eq!(forecast.description, "Forecast, hourly");
+
+    let refresh = client.refresh_providers_from_source().await.unwrap();
+    assert_eq!(refresh.unchanged, vec!["weather", "news"]);
+    assert!(refresh.added.is_empty() && refresh.updated.is_empty() && refresh.removed.is_empty());
+
+    // A failed fetch leaves the registered providers alone
+    *catalog.lock().unwrap() = (StatusCode::SERVICE_UNAVAILABLE, String::new());
+    let err = client.refresh_providers_from_source().await.unwrap_err();
+    assert!(err.to_string().contains("HTTP 503"), "{}", err);
+    assert_eq!(
+        tool_names(&client).await,
+        vec!["local.read", "news.headlines", "weather.forecast"]
+    );
+}
+
+#[tokio::test]
+async fn fetch_failures_at_startup_are_errors() {
+    let catalog: Catalog = Arc::new(Mutex::new((
+        StatusCode::OK,
+        manual(&[("weather", "forecast", "Forecast")]),
+    )));
+    let base = spawn_catalog_server(catalog.clone()).await;
+    let url = format!("{}/catalog.json", base);
+
+    let unauthorized = UtcpClientConfig::new().with_providers_url(url.clone());
+    let err = client(unauthorized).await.err().unwrap().to_string();
+    assert!(err.contains(&url), "{}", err);
+    assert!(err.contains("HTTP 401"), "{}", err);
+
+    catalog.lock().unwrap().1 = "providers: [unclosed".to_string();
+    let err = client(config(url.clone())).await.err().unwrap().to_string();
+    assert!(err.starts_with("Failed to parse providers from"), "{}", err);
+
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let unreachable = format!("http://{}/catalog.json", closed.local_addr().unwrap());
+    drop(closed);
+    let err = client(config(unreachable)).await.err().unwrap().to_string();
+    assert!(err.starts_with("Failed to fetch providers from"), "{}", err);
+
+    let both = config(url).with_providers_file("providers.json".into());
+    let err = client(both).await.err().unwrap().to_string();
+    assert!(err.contains("not both"), "{}", err);
+
+    let err = client(UtcpClientConfig::new())
+        .await
+        .unwrap()
+        .refresh_providers_from_source()
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("No providers file or URL"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn yaml_catalogs_take_defaults_but_not_includes() {
+    let catalog: Catalog = Arc::new(Mutex::new((
+        StatusCode::OK,
+        r#"
+defaults:
+  auth: {auth_type: api_key, api_key: "${API_KEY}", var_name: X-Api-Key, location: header}
+providers:
+  - {provider_type: http, name: billing, url: "http://example.com/billing"}
+  - {provider_type: http, name: search, url: "http://example.com/search"}
+"#
+        .to_string(),
+    )));
+    let base = spawn_catalog_server(catalog.clone()).await;
+    let url = format!("{}/providers.yaml", base);
+    let headers = HashMap::from([("Authorization".to_string(), "Bearer secret".to_string())]);
+    let config = UtcpClientConfig::new().with_variable("API_KEY".into(), "k-123".into());
+
+    let loaded =
+        load_providers_with_tools_from_url(&url, &headers, &SharedClient::default(), &config)
+            .await
+            .unwrap();
+    assert_eq!(loaded.len(), 2);
+    for loaded in &loaded {
+        let http = loaded
+            .provider
+            .as_any()
+            .downcast_ref::<HttpProvider>()
+            .unwrap();
+        match http.base.auth.as_ref() {
+            Some(AuthConfig::ApiKey(auth)) => assert_eq!(auth.api_key, "k-123"),
+            other => panic!("expected the default api key, got {:?}", other),
+        }
+    }
+
+    catalog.lock().unwrap().1 = "include: [more.yaml]\nproviders: []\n".to_string();
+    let err = load_providers_with_tools_from_url(&url, &headers, &SharedClient::default(), &config)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("'include' is not supported"),
+        "{}",
+        err
+    );
+}
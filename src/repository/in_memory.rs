@@ -1,24 +1,168 @@
 use crate::providers::base::Provider;
-use crate::repository::ToolRepository;
+use crate::repository::snapshot::{ProviderSnapshot, RepositorySnapshot};
+use crate::repository::{
+    RepositoryEvent, RepositoryEvents, ToolCallOutcome, ToolRepository, ToolStats,
+};
 use crate::tools::Tool;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, RwLock};
+
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// How `InMemoryToolRepository` reacts when a registration would exceed its limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Fail the registration and leave the repository untouched.
+    #[default]
+    Reject,
+    /// Evict the least recently used providers until the new registration fits.
+    LeastRecentlyUsed,
+}
 
 /// Simple in-memory repository for tests and local usage.
 pub struct InMemoryToolRepository {
     tools: RwLock<HashMap<String, Vec<Tool>>>, // provider_name -> tools
     providers: RwLock<HashMap<String, Arc<dyn Provider>>>, // provider_name -> Provider
+    stats: RwLock<HashMap<String, ToolStats>>, // tool_name -> usage stats
+    max_tools: Option<usize>,
+    max_providers: Option<usize>,
+    eviction_policy: EvictionPolicy,
+    last_used: Mutex<HashMap<String, u64>>, // provider_name -> logical access time
+    clock: AtomicU64,
+    events: broadcast::Sender<RepositoryEvent>,
 }
 
 impl InMemoryToolRepository {
     /// Create an empty repository instance.
     pub fn new() -> Self {
+        Self::with_limits(None, None, EvictionPolicy::Reject)
+    }
+
+    /// Create an empty repository that holds at most `max_tools` tools and `max_providers`
+    /// providers, applying `policy` when a registration would exceed either limit.
+    pub fn with_limits(
+        max_tools: Option<usize>,
+        max_providers: Option<usize>,
+        policy: EvictionPolicy,
+    ) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
         Self {
             tools: RwLock::new(HashMap::new()),
             providers: RwLock::new(HashMap::new()),
+            stats: RwLock::new(HashMap::new()),
+            max_tools,
+            max_providers,
+            eviction_policy: policy,
+            last_used: Mutex::new(HashMap::new()),
+            clock: AtomicU64::new(0),
+            events,
+        }
+    }
+
+    fn touch(&self, provider_name: &str) {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut last_used) = self.last_used.lock() {
+            if let Some(entry) = last_used.get_mut(provider_name) {
+                *entry = tick;
+            } else {
+                last_used.insert(provider_name.to_string(), tick);
+            }
+        }
+    }
+
+    fn forget(&self, provider_name: &str) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            last_used.remove(provider_name);
+        }
+    }
+
+    fn emit(&self, event: RepositoryEvent) {
+        // No subscribers is not an error.
+        let _ = self.events.send(event);
+    }
+
+    fn least_recently_used(
+        &self,
+        providers: &HashMap<String, Arc<dyn Provider>>,
+        exclude: &str,
+    ) -> Option<String> {
+        let last_used = self.last_used.lock().ok()?;
+        providers
+            .keys()
+            .filter(|name| name.as_str() != exclude)
+            .min_by_key(|name| last_used.get(name.as_str()).copied().unwrap_or(0))
+            .cloned()
+    }
+
+    /// Check the limits for storing `incoming_tools` tools under `provider_name`, evicting
+    /// providers when the policy allows it. Returns the names of evicted providers.
+    fn make_room(
+        &self,
+        provider_name: &str,
+        incoming_tools: usize,
+        providers: &mut HashMap<String, Arc<dyn Provider>>,
+        tools: &mut HashMap<String, Vec<Tool>>,
+    ) -> Result<Vec<String>> {
+        if let Some(max) = self.max_tools {
+            if incoming_tools > max {
+                return Err(anyhow!(
+                    "provider '{}' offers {} tools, which exceeds the repository limit of {} tools",
+                    provider_name,
+                    incoming_tools,
+                    max
+                ));
+            }
+        }
+
+        let mut evicted = Vec::new();
+        loop {
+            let provider_count =
+                providers.len() + usize::from(!providers.contains_key(provider_name));
+            let tool_count = incoming_tools
+                + tools
+                    .iter()
+                    .filter(|(name, _)| name.as_str() != provider_name)
+                    .map(|(_, list)| list.len())
+                    .sum::<usize>();
+
+            let provider_overflow = self.max_providers.filter(|max| provider_count > *max);
+            let tool_overflow = self.max_tools.filter(|max| tool_count > *max);
+            if provider_overflow.is_none() && tool_overflow.is_none() {
+                return Ok(evicted);
+            }
+
+            let victim = match self.eviction_policy {
+                EvictionPolicy::Reject => None,
+                EvictionPolicy::LeastRecentlyUsed => {
+                    self.least_recently_used(providers, provider_name)
+                }
+            };
+            let Some(victim) = victim else {
+                return Err(match (provider_overflow, tool_overflow) {
+                    (Some(max), _) => anyhow!(
+                        "registering provider '{}' would exceed the repository limit of {} providers",
+                        provider_name,
+                        max
+                    ),
+                    (None, Some(max)) => anyhow!(
+                        "registering provider '{}' would bring the repository to {} tools, exceeding the limit of {}",
+                        provider_name,
+                        tool_count,
+                        max
+                    ),
+                    (None, None) => unreachable!("checked above"),
+                });
+            };
+
+            providers.remove(&victim);
+            tools.remove(&victim);
+            self.forget(&victim);
+            evicted.push(victim);
         }
     }
 }
@@ -32,18 +176,46 @@ impl ToolRepository for InMemoryToolRepository {
     ) -> Result<()> {
         let provider_name = provider.name();
 
-        let mut providers_lock = self.providers.write().await;
-        providers_lock.insert(provider_name.clone(), provider);
+        let evicted = {
+            let mut providers_lock = self.providers.write().await;
+            let mut tools_lock = self.tools.write().await;
+            let evicted = self.make_room(
+                &provider_name,
+                tools.len(),
+                &mut providers_lock,
+                &mut tools_lock,
+            )?;
+
+            providers_lock.insert(provider_name.clone(), provider);
+            tools_lock.insert(provider_name.clone(), tools);
+            evicted
+        };
+        self.touch(&provider_name);
 
-        let mut tools_lock = self.tools.write().await;
-        tools_lock.insert(provider_name, tools);
+        if !evicted.is_empty() {
+            let mut stats = self.stats.write().await;
+            for victim in &evicted {
+                let prefix = format!("{}.", victim);
+                stats.retain(|tool_name, _| !tool_name.starts_with(&prefix));
+            }
+        }
+        for victim in evicted {
+            self.emit(RepositoryEvent::ProviderEvicted {
+                provider_name: victim,
+            });
+        }
+        self.emit(RepositoryEvent::ProviderSaved { provider_name });
 
         Ok(())
     }
 
     async fn get_provider(&self, name: &str) -> Result<Option<Arc<dyn Provider>>> {
         let providers = self.providers.read().await;
-        Ok(providers.get(name).cloned())
+        let provider = providers.get(name).cloned();
+        if provider.is_some() {
+            self.touch(name);
+        }
+        Ok(provider)
     }
 
     async fn remove_provider(&self, name: &str) -> Result<()> {
@@ -53,6 +225,15 @@ impl ToolRepository for InMemoryToolRepository {
         }
         let mut tools = self.tools.write().await;
         tools.remove(name);
+        self.forget(name);
+
+        let prefix = format!("{}.", name);
+        let mut stats = self.stats.write().await;
+        stats.retain(|tool_name, _| !tool_name.starts_with(&prefix));
+
+        self.emit(RepositoryEvent::ProviderRemoved {
+            provider_name: name.to_string(),
+        });
         Ok(())
     }
 
@@ -69,8 +250,277 @@ impl ToolRepository for InMemoryToolRepository {
     async fn get_tools_by_provider(&self, provider_name: &str) -> Result<Vec<Tool>> {
         let tools_map = self.tools.read().await;
         match tools_map.get(provider_name) {
-            Some(tools) => Ok(tools.clone()),
+            Some(tools) => {
+                self.touch(provider_name);
+                Ok(tools.clone())
+            }
             None => Err(anyhow!("no tools found for provider {}", provider_name)),
         }
     }
+
+    async fn record_call(
+        &self,
+        tool_name: &str,
+        outcome: ToolCallOutcome,
+        duration: Duration,
+    ) -> Result<()> {
+        let average_response_size = {
+            let mut stats = self.stats.write().await;
+            let entry = stats
+                .entry(tool_name.to_string())
+                .or_insert_with(|| ToolStats::new(tool_name));
+            entry.record(outcome, duration, SystemTime::now());
+            entry.average_response_size
+        };
+
+        if let Some((provider_name, _)) = tool_name.split_once('.') {
+            self.touch(provider_name);
+        }
+
+        // Surface the observed size on the stored tool so search strategies can use it.
+        if let Some(size) = average_response_size {
+            if let Some((provider_name, _)) = tool_name.split_once('.') {
+                let mut tools_map = self.tools.write().await;
+                if let Some(tool) = tools_map
+                    .get_mut(provider_name)
+                    .and_then(|tools| tools.iter_mut().find(|t| t.name == tool_name))
+                {
+                    tool.average_response_size = Some(size);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_tool_stats(&self, tool_name: &str) -> Result<Option<ToolStats>> {
+        let stats = self.stats.read().await;
+        Ok(stats.get(tool_name).cloned())
+    }
+
+    async fn list_tool_stats(&self) -> Result<Vec<ToolStats>> {
+        let stats = self.stats.read().await;
+        let mut all: Vec<ToolStats> = stats.values().cloned().collect();
+        all.sort_by(|a, b| a.tool_name.cmp(&b.tool_name));
+        Ok(all)
+    }
+
+    fn subscribe(&self) -> Option<RepositoryEvents> {
+        Some(self.events.subscribe().into())
+    }
+
+    async fn export_snapshot(&self) -> Result<RepositorySnapshot> {
+        let providers = self.providers.read().await;
+        let tools_map = self.tools.read().await;
+
+        let mut names: Vec<&String> = providers.keys().collect();
+        names.sort();
+
+        let mut entries = Vec::with_capacity(names.len());
+        for name in names {
+            let tools = tools_map.get(name).cloned().unwrap_or_default();
+            entries.push(ProviderSnapshot::from_provider(
+                providers[name].as_ref(),
+                tools,
+            )?);
+        }
+        Ok(RepositorySnapshot { providers: entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::tools::test_tool;
+
+    fn make_tool(name: &str) -> Tool {
+        test_tool(name, "", &[])
+    }
+
+    fn provider(name: &str) -> Arc<dyn Provider> {
+        Arc::new(BaseProvider {
+            name: name.to_string(),
+            provider_type: ProviderType::Http,
+            auth: None,
+            allowed_communication_protocols: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn record_call_tracks_counts_latency_and_sizes() {
+        let repo = InMemoryToolRepository::new();
+        repo.save_provider_with_tools(provider("p"), vec![make_tool("p.echo")])
+            .await
+            .unwrap();
+
+        repo.record_call(
+            "p.echo",
+            ToolCallOutcome::Success {
+                response_size: Some(100),
+            },
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        repo.record_call("p.echo", ToolCallOutcome::Error, Duration::from_millis(30))
+            .await
+            .unwrap();
+        repo.record_call(
+            "p.echo",
+            ToolCallOutcome::Success {
+                response_size: Some(300),
+            },
+            Duration::from_millis(20),
+        )
+        .await
+        .unwrap();
+
+        let stats = repo.get_tool_stats("p.echo").await.unwrap().unwrap();
+        assert_eq!(stats.call_count, 3);
+        assert_eq!(stats.error_count, 1);
+        assert!(stats.last_called_at.is_some());
+        assert_eq!(stats.average_latency.as_millis(), 20);
+        assert_eq!(stats.average_response_size, Some(200));
+
+        let tools = repo.get_tools_by_provider("p").await.unwrap();
+        assert_eq!(tools[0].average_response_size, Some(200));
+
+        assert!(repo.get_tool_stats("p.other").await.unwrap().is_none());
+        assert_eq!(repo.list_tool_stats().await.unwrap().len(), 1);
+
+        repo.remove_provider("p").await.unwrap();
+        assert!(repo.list_tool_stats().await.unwrap().is_empty());
+    }
+
+    fn tools_for(provider_name: &str, count: usize) -> Vec<Tool> {
+        (0..count)
+            .map(|i| make_tool(&format!("{}.t{}", provider_name, i)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reject_policy_refuses_registrations_past_the_limits() {
+        let repo = InMemoryToolRepository::with_limits(Some(3), Some(2), EvictionPolicy::Reject);
+        repo.save_provider_with_tools(provider("a"), tools_for("a", 2))
+            .await
+            .unwrap();
+        repo.save_provider_with_tools(provider("b"), tools_for("b", 1))
+            .await
+            .unwrap();
+
+        let err = repo
+            .save_provider_with_tools(provider("c"), vec![])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("limit of 2 providers"), "{err}");
+
+        let err = repo
+            .save_provider_with_tools(provider("b"), tools_for("b", 2))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("exceeding the limit of 3"),
+            "{err}"
+        );
+
+        let err = repo
+            .save_provider_with_tools(provider("a"), tools_for("a", 4))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("offers 4 tools"), "{err}");
+
+        // Replacing a provider within its budget is still allowed.
+        repo.save_provider_with_tools(provider("a"), tools_for("a", 1))
+            .await
+            .unwrap();
+        assert_eq!(repo.get_tools().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn lru_policy_evicts_least_recently_used_providers() {
+        let repo = InMemoryToolRepository::with_limits(
+            Some(4),
+            Some(2),
+            EvictionPolicy::LeastRecentlyUsed,
+        );
+        let mut events = repo.subscribe().unwrap();
+
+        repo.save_provider_with_tools(provider("a"), tools_for("a", 2))
+            .await
+            .unwrap();
+        repo.save_provider_with_tools(provider("b"), tools_for("b", 2))
+            .await
+            .unwrap();
+        // Touch "a" so "b" becomes the eviction candidate.
+        repo.get_provider("a").await.unwrap();
+
+        repo.save_provider_with_tools(provider("c"), tools_for("c", 1))
+            .await
+            .unwrap();
+        assert!(repo.get_provider("b").await.unwrap().is_none());
+        assert!(repo.get_provider("a").await.unwrap().is_some());
+
+        // Exceeding the tool limit evicts as many providers as needed.
+        repo.save_provider_with_tools(provider("d"), tools_for("d", 4))
+            .await
+            .unwrap();
+        let remaining: Vec<String> = repo
+            .get_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(remaining.len(), 4);
+        assert!(remaining.iter().all(|n| n.starts_with("d.")));
+
+        let mut evicted = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let RepositoryEvent::ProviderEvicted { provider_name } = event {
+                evicted.push(provider_name);
+            }
+        }
+        evicted.sort();
+        assert_eq!(evicted, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn client_forgets_tools_of_evicted_providers() {
+        use crate::config::UtcpClientConfig;
+        use crate::tools::ToolSearchStrategy;
+        use crate::{UtcpClient, UtcpClientInterface};
+
+        struct NoopSearch;
+
+        #[async_trait]
+        impl ToolSearchStrategy for NoopSearch {
+            async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+                Ok(vec![])
+            }
+        }
+
+        let repo = Arc::new(InMemoryToolRepository::with_limits(
+            None,
+            Some(1),
+            EvictionPolicy::LeastRecentlyUsed,
+        ));
+        let client = UtcpClient::new(UtcpClientConfig::default(), repo, Arc::new(NoopSearch))
+            .await
+            .unwrap();
+
+        client
+            .register_tool_provider_with_tools(provider("old"), vec![make_tool("lookup")])
+            .await
+            .unwrap();
+        client
+            .register_tool_provider_with_tools(provider("new"), vec![make_tool("other")])
+            .await
+            .unwrap();
+
+        let err = client
+            .call_tool("lookup", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Tool not found"), "{err}");
+    }
 }
@@ -1,10 +1,163 @@
 pub mod in_memory;
+pub mod namespaced;
+pub mod snapshot;
 
 use crate::providers::base::Provider;
+use crate::repository::snapshot::{ProviderSnapshot, RepositorySnapshot};
 use crate::tools::Tool;
+use crate::transports::registry::communication_protocols_snapshot;
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+/// Change notifications emitted by repositories that support subscriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepositoryEvent {
+    /// A provider and its tools were stored (new or replaced).
+    ProviderSaved { provider_name: String },
+    /// A provider was removed explicitly.
+    ProviderRemoved { provider_name: String },
+    /// A provider was removed to make room under the repository's capacity limits.
+    ProviderEvicted { provider_name: String },
+}
+
+/// Receiver for a repository's change notifications.
+///
+/// Wraps a broadcast receiver with an optional filter, so a view over part of a repository
+/// (such as a namespace) can drop and rewrite events as they are received rather than
+/// forwarding them through another channel. Lag on the underlying channel is reported as is.
+pub struct RepositoryEvents {
+    rx: broadcast::Receiver<RepositoryEvent>,
+    filter: Option<Arc<EventFilter>>,
+}
+
+type EventFilter = dyn Fn(RepositoryEvent) -> Option<RepositoryEvent> + Send + Sync;
+
+impl RepositoryEvents {
+    /// Receive every event sent on `rx`.
+    pub fn new(rx: broadcast::Receiver<RepositoryEvent>) -> Self {
+        Self { rx, filter: None }
+    }
+
+    /// Receive the events `filter` maps to `Some`, in their mapped form.
+    pub fn filtered(
+        self,
+        filter: impl Fn(RepositoryEvent) -> Option<RepositoryEvent> + Send + Sync + 'static,
+    ) -> Self {
+        let filter: Arc<EventFilter> = match self.filter {
+            Some(outer) => Arc::new(move |event| outer(event).and_then(&filter)),
+            None => Arc::new(filter),
+        };
+        Self {
+            rx: self.rx,
+            filter: Some(filter),
+        }
+    }
+
+    /// Wait for the next event that passes the filter.
+    pub async fn recv(&mut self) -> Result<RepositoryEvent, broadcast::error::RecvError> {
+        loop {
+            let event = self.rx.recv().await?;
+            if let Some(event) = self.apply(event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Take the next pending event that passes the filter, without waiting.
+    pub fn try_recv(&mut self) -> Result<RepositoryEvent, broadcast::error::TryRecvError> {
+        loop {
+            let event = self.rx.try_recv()?;
+            if let Some(event) = self.apply(event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn apply(&self, event: RepositoryEvent) -> Option<RepositoryEvent> {
+        match &self.filter {
+            Some(filter) => filter(event),
+            None => Some(event),
+        }
+    }
+}
+
+impl From<broadcast::Receiver<RepositoryEvent>> for RepositoryEvents {
+    fn from(rx: broadcast::Receiver<RepositoryEvent>) -> Self {
+        Self::new(rx)
+    }
+}
+
+/// Result of a single tool invocation, as reported to the repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCallOutcome {
+    /// The call succeeded; `response_size` is the serialized response size in bytes when known.
+    Success { response_size: Option<usize> },
+    /// The call (or the stream it produced) returned an error.
+    Error,
+    /// The caller closed or dropped the stream before it reached its end.
+    Cancelled,
+}
+
+/// Usage counters tracked per tool, kept separately from the tool schema.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolStats {
+    pub tool_name: String,
+    pub call_count: u64,
+    pub error_count: u64,
+    /// Streams the caller abandoned before they ended.
+    #[serde(default)]
+    pub cancelled_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_called_at: Option<SystemTime>,
+    /// Running mean latency across every recorded call.
+    pub average_latency: Duration,
+    /// Running mean response size in bytes across successful calls that reported one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_response_size: Option<i64>,
+    #[serde(skip)]
+    sized_responses: u64,
+}
+
+impl ToolStats {
+    /// Create empty stats for the given tool.
+    pub fn new(tool_name: impl Into<String>) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Fold a single call into the counters and running averages.
+    pub fn record(&mut self, outcome: ToolCallOutcome, duration: Duration, at: SystemTime) {
+        self.call_count += 1;
+        self.last_called_at = Some(at);
+
+        let prev = self.average_latency.as_secs_f64();
+        let mean = prev + (duration.as_secs_f64() - prev) / self.call_count as f64;
+        self.average_latency = Duration::from_secs_f64(mean.max(0.0));
+
+        match outcome {
+            ToolCallOutcome::Error => self.error_count += 1,
+            ToolCallOutcome::Cancelled => self.cancelled_count += 1,
+            ToolCallOutcome::Success {
+                response_size: Some(size),
+            } => {
+                self.sized_responses += 1;
+                let prev = self.average_response_size.unwrap_or(0) as f64;
+                let mean = prev + (size as f64 - prev) / self.sized_responses as f64;
+                self.average_response_size = Some(mean.round() as i64);
+            }
+            ToolCallOutcome::Success {
+                response_size: None,
+            } => {}
+        }
+    }
+}
 
 /// Persistence abstraction for storing providers and their tools.
 #[async_trait]
@@ -23,4 +176,61 @@ pub trait ToolRepository: Send + Sync {
     async fn get_tools(&self) -> Result<Vec<Tool>>;
     /// Return tools offered by a specific provider.
     async fn get_tools_by_provider(&self, provider_name: &str) -> Result<Vec<Tool>>;
+
+    /// Record the outcome of a tool call. Repositories that do not track usage can ignore it.
+    async fn record_call(
+        &self,
+        _tool_name: &str,
+        _outcome: ToolCallOutcome,
+        _duration: Duration,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Return usage statistics for a tool, if any calls have been recorded.
+    async fn get_tool_stats(&self, _tool_name: &str) -> Result<Option<ToolStats>> {
+        Ok(None)
+    }
+    /// Return usage statistics for every tool with recorded calls.
+    async fn list_tool_stats(&self) -> Result<Vec<ToolStats>> {
+        Ok(Vec::new())
+    }
+
+    /// Subscribe to change notifications. Returns `None` when the repository does not emit events.
+    fn subscribe(&self) -> Option<RepositoryEvents> {
+        None
+    }
+
+    /// Capture the current provider and tool catalog.
+    ///
+    /// The default implementation groups `get_tools` by provider prefix, so providers without
+    /// any tools are omitted; repositories that can enumerate providers should override it.
+    async fn export_snapshot(&self) -> Result<RepositorySnapshot> {
+        let mut grouped: BTreeMap<String, Vec<Tool>> = BTreeMap::new();
+        for tool in self.get_tools().await? {
+            if let Some((provider_name, _)) = tool.name.split_once('.') {
+                grouped
+                    .entry(provider_name.to_string())
+                    .or_default()
+                    .push(tool);
+            }
+        }
+
+        let mut providers = Vec::with_capacity(grouped.len());
+        for (provider_name, tools) in grouped {
+            if let Some(prov) = self.get_provider(&provider_name).await? {
+                providers.push(ProviderSnapshot::from_provider(prov.as_ref(), tools)?);
+            }
+        }
+        Ok(RepositorySnapshot { providers })
+    }
+    /// Load a snapshot, replacing any providers that share a name with a snapshot entry.
+    /// Provider types are validated against the registered communication protocols first,
+    /// and nothing is written if any type is unknown.
+    async fn import_snapshot(&self, snap: RepositorySnapshot) -> Result<()> {
+        snap.validate_provider_types(&communication_protocols_snapshot())?;
+        for (prov, tools) in snap.into_providers()? {
+            self.save_provider_with_tools(prov, tools).await?;
+        }
+        Ok(())
+    }
 }
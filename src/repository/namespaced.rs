@@ -0,0 +1,435 @@
+use crate::providers::base::{Provider, ProviderType};
+use crate::repository::snapshot::RepositorySnapshot;
+use crate::repository::{
+    RepositoryEvent, RepositoryEvents, ToolCallOutcome, ToolRepository, ToolStats,
+};
+use crate::tools::Tool;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+const NAMESPACE_SEPARATOR: &str = "::";
+
+/// Repository wrapper that isolates tenants sharing one underlying repository.
+///
+/// Provider and tool names are stored as `<namespace>::<name>` in the inner repository and
+/// the prefix is stripped again on every read, so callers only ever see their own entries.
+pub struct NamespacedToolRepository {
+    inner: Arc<dyn ToolRepository>,
+    prefix: String,
+}
+
+/// Stored form of a provider under a namespace; reports the prefixed name to the inner repository.
+#[derive(Debug)]
+pub(crate) struct NamespacedProvider {
+    pub(crate) inner: Arc<dyn Provider>,
+    pub(crate) name: String,
+}
+
+impl Provider for NamespacedProvider {
+    fn type_(&self) -> ProviderType {
+        self.inner.type_()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn allowed_protocols(&self) -> Vec<String> {
+        self.inner.allowed_protocols()
+    }
+}
+
+impl NamespacedToolRepository {
+    /// Wrap `inner` so every read and write is scoped to `namespace`.
+    ///
+    /// Fails if `namespace` is empty, contains `.`, which is reserved as the provider/tool
+    /// separator, or contains `:`, which would let one namespace's prefix match another's.
+    pub fn new(inner: Arc<dyn ToolRepository>, namespace: &str) -> Result<Self> {
+        if namespace.is_empty() || namespace.contains(['.', ':']) {
+            return Err(anyhow!(
+                "Invalid namespace '{}': must be non-empty and must not contain '.' or ':'",
+                namespace
+            ));
+        }
+        Ok(Self {
+            inner,
+            prefix: format!("{}{}", namespace, NAMESPACE_SEPARATOR),
+        })
+    }
+
+    /// The namespace this repository is scoped to.
+    pub fn namespace(&self) -> &str {
+        self.prefix.trim_end_matches(NAMESPACE_SEPARATOR)
+    }
+
+    fn scoped(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn unscoped<'a>(&self, name: &'a str) -> Option<&'a str> {
+        name.strip_prefix(self.prefix.as_str())
+    }
+
+    fn unscope_tool(&self, mut tool: Tool) -> Option<Tool> {
+        tool.name = self.unscoped(&tool.name)?.to_string();
+        Some(tool)
+    }
+
+    fn unscope_stats(&self, mut stats: ToolStats) -> Option<ToolStats> {
+        stats.tool_name = self.unscoped(&stats.tool_name)?.to_string();
+        Some(stats)
+    }
+
+    fn unscope_event(prefix: &str, event: RepositoryEvent) -> Option<RepositoryEvent> {
+        let strip = |name: String| name.strip_prefix(prefix).map(str::to_string);
+        Some(match event {
+            RepositoryEvent::ProviderSaved { provider_name } => RepositoryEvent::ProviderSaved {
+                provider_name: strip(provider_name)?,
+            },
+            RepositoryEvent::ProviderRemoved { provider_name } => {
+                RepositoryEvent::ProviderRemoved {
+                    provider_name: strip(provider_name)?,
+                }
+            }
+            RepositoryEvent::ProviderEvicted { provider_name } => {
+                RepositoryEvent::ProviderEvicted {
+                    provider_name: strip(provider_name)?,
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRepository for NamespacedToolRepository {
+    async fn save_provider_with_tools(
+        &self,
+        prov: Arc<dyn Provider>,
+        tools: Vec<Tool>,
+    ) -> Result<()> {
+        let stored = Arc::new(NamespacedProvider {
+            name: self.scoped(&prov.name()),
+            inner: prov,
+        });
+        let tools = tools
+            .into_iter()
+            .map(|mut tool| {
+                tool.name = self.scoped(&tool.name);
+                tool
+            })
+            .collect();
+        self.inner.save_provider_with_tools(stored, tools).await
+    }
+
+    async fn get_provider(&self, name: &str) -> Result<Option<Arc<dyn Provider>>> {
+        let stored = self.inner.get_provider(&self.scoped(name)).await?;
+        Ok(stored.map(
+            |prov| match prov.as_any().downcast_ref::<NamespacedProvider>() {
+                Some(namespaced) => namespaced.inner.clone(),
+                None => prov,
+            },
+        ))
+    }
+
+    async fn remove_provider(&self, name: &str) -> Result<()> {
+        self.inner.remove_provider(&self.scoped(name)).await
+    }
+
+    async fn get_tools(&self) -> Result<Vec<Tool>> {
+        Ok(self
+            .inner
+            .get_tools()
+            .await?
+            .into_iter()
+            .filter_map(|tool| self.unscope_tool(tool))
+            .collect())
+    }
+
+    async fn get_tools_by_provider(&self, provider_name: &str) -> Result<Vec<Tool>> {
+        Ok(self
+            .inner
+            .get_tools_by_provider(&self.scoped(provider_name))
+            .await?
+            .into_iter()
+            .filter_map(|tool| self.unscope_tool(tool))
+            .collect())
+    }
+
+    async fn record_call(
+        &self,
+        tool_name: &str,
+        outcome: ToolCallOutcome,
+        duration: Duration,
+    ) -> Result<()> {
+        self.inner
+            .record_call(&self.scoped(tool_name), outcome, duration)
+            .await
+    }
+
+    fn subscribe(&self) -> Option<RepositoryEvents> {
+        // Keep the inner repository's events for this namespace only, with the prefix removed.
+        let prefix = self.prefix.clone();
+        let events = self.inner.subscribe()?;
+        Some(events.filtered(move |event| Self::unscope_event(&prefix, event)))
+    }
+
+    async fn get_tool_stats(&self, tool_name: &str) -> Result<Option<ToolStats>> {
+        Ok(self
+            .inner
+            .get_tool_stats(&self.scoped(tool_name))
+            .await?
+            .and_then(|stats| self.unscope_stats(stats)))
+    }
+
+    async fn list_tool_stats(&self) -> Result<Vec<ToolStats>> {
+        Ok(self
+            .inner
+            .list_tool_stats()
+            .await?
+            .into_iter()
+            .filter_map(|stats| self.unscope_stats(stats))
+            .collect())
+    }
+
+    async fn export_snapshot(&self) -> Result<RepositorySnapshot> {
+        // Keep this namespace's entries from the inner snapshot, including providers without tools
+        let mut providers = Vec::new();
+        for mut entry in self.inner.export_snapshot().await?.providers {
+            let name = entry.provider.get("name").and_then(Value::as_str);
+            let Some(name) = name.and_then(|name| self.unscoped(name)) else {
+                continue;
+            };
+            entry.provider["name"] = Value::String(name.to_string());
+            entry.tools = entry
+                .tools
+                .into_iter()
+                .filter_map(|tool| self.unscope_tool(tool))
+                .collect();
+            providers.push(entry);
+        }
+        Ok(RepositorySnapshot { providers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UtcpClientConfig;
+    use crate::providers::base::BaseProvider;
+    use crate::repository::in_memory::InMemoryToolRepository;
+    use crate::tools::{test_tool, ToolSearchStrategy};
+    use crate::{UtcpClient, UtcpClientInterface};
+
+    struct NoopSearch;
+
+    #[async_trait]
+    impl ToolSearchStrategy for NoopSearch {
+        async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+            Ok(vec![])
+        }
+    }
+
+    fn make_tool(name: &str) -> Tool {
+        test_tool(name, "", &[])
+    }
+
+    fn provider(name: &str) -> Arc<dyn Provider> {
+        Arc::new(BaseProvider {
+            name: name.to_string(),
+            provider_type: ProviderType::Text,
+            auth: None,
+            allowed_communication_protocols: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn prefixes_on_write_and_strips_on_read() {
+        let shared = Arc::new(InMemoryToolRepository::new());
+        let repo = NamespacedToolRepository::new(shared.clone(), "tenant").unwrap();
+
+        repo.save_provider_with_tools(provider("svc"), vec![make_tool("svc.echo")])
+            .await
+            .unwrap();
+
+        let raw = shared.get_tools().await.unwrap();
+        assert_eq!(raw[0].name, "tenant::svc.echo");
+        assert!(shared.get_provider("tenant::svc").await.unwrap().is_some());
+
+        let tools = repo.get_tools().await.unwrap();
+        assert_eq!(tools[0].name, "svc.echo");
+        let prov = repo.get_provider("svc").await.unwrap().unwrap();
+        assert_eq!(prov.name(), "svc");
+        assert!(prov.as_any().downcast_ref::<BaseProvider>().is_some());
+
+        repo.record_call(
+            "svc.echo",
+            ToolCallOutcome::Success {
+                response_size: None,
+            },
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.list_tool_stats().await.unwrap()[0].tool_name,
+            "svc.echo"
+        );
+
+        repo.remove_provider("svc").await.unwrap();
+        assert!(shared.get_tools().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn clients_in_disjoint_namespaces_cannot_see_each_other() {
+        let shared = Arc::new(InMemoryToolRepository::new());
+        let repo_a = Arc::new(NamespacedToolRepository::new(shared.clone(), "a").unwrap());
+        let repo_b = Arc::new(NamespacedToolRepository::new(shared.clone(), "b").unwrap());
+
+        let client_a = UtcpClient::new(
+            UtcpClientConfig::default(),
+            repo_a.clone(),
+            Arc::new(NoopSearch),
+        )
+        .await
+        .unwrap();
+        let client_b = UtcpClient::new(
+            UtcpClientConfig::default(),
+            repo_b.clone(),
+            Arc::new(NoopSearch),
+        )
+        .await
+        .unwrap();
+
+        client_a
+            .register_tool_provider_with_tools(provider("alpha"), vec![make_tool("secret")])
+            .await
+            .unwrap();
+        client_b
+            .register_tool_provider_with_tools(provider("beta"), vec![make_tool("public")])
+            .await
+            .unwrap();
+
+        // Both qualified and bare names resolve only inside the owning namespace.
+        for name in ["alpha.secret", "secret"] {
+            let err = client_b
+                .call_tool(name, Default::default())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("not found"), "{name}: {err}");
+        }
+        for name in ["beta.public", "public"] {
+            let err = client_a
+                .call_tool(name, Default::default())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("not found"), "{name}: {err}");
+        }
+
+        let names_a: Vec<String> = repo_a
+            .get_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        let names_b: Vec<String> = repo_b
+            .get_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names_a, vec!["alpha.secret".to_string()]);
+        assert_eq!(names_b, vec!["beta.public".to_string()]);
+        assert_eq!(shared.get_tools().await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejects_namespaces_that_could_overlap() {
+        let shared: Arc<dyn ToolRepository> = Arc::new(InMemoryToolRepository::new());
+        for namespace in ["", "a.b", "a::b", "a:b"] {
+            let err = NamespacedToolRepository::new(shared.clone(), namespace)
+                .err()
+                .unwrap_or_else(|| panic!("'{}' was accepted", namespace));
+            assert!(err.to_string().contains("Invalid namespace"), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn exports_and_imports_snapshots_through_a_namespace() {
+        let shared = Arc::new(InMemoryToolRepository::new());
+        let repo = NamespacedToolRepository::new(shared.clone(), "tenant").unwrap();
+        let other = NamespacedToolRepository::new(shared.clone(), "other").unwrap();
+        repo.save_provider_with_tools(provider("svc"), vec![make_tool("svc.echo")])
+            .await
+            .unwrap();
+        repo.save_provider_with_tools(provider("idle"), vec![])
+            .await
+            .unwrap();
+        other
+            .save_provider_with_tools(provider("svc"), vec![make_tool("svc.hidden")])
+            .await
+            .unwrap();
+
+        let snap = repo.export_snapshot().await.unwrap();
+        let names: Vec<&str> = snap
+            .providers
+            .iter()
+            .map(|entry| entry.provider["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["idle", "svc"]);
+        assert_eq!(snap.providers[1].tools[0].name, "svc.echo");
+
+        // The shared repository exports the wrapped providers under their stored names
+        let raw = shared.export_snapshot().await.unwrap();
+        assert_eq!(raw.providers.len(), 3);
+        assert_eq!(raw.providers[2].provider["name"], "tenant::svc");
+
+        let copy = NamespacedToolRepository::new(shared.clone(), "copy").unwrap();
+        copy.import_snapshot(snap).await.unwrap();
+        let tools = copy.get_tools_by_provider("svc").await.unwrap();
+        assert_eq!(tools[0].name, "svc.echo");
+        assert!(copy.get_provider("idle").await.unwrap().is_some());
+        assert!(shared.get_provider("copy::svc").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn subscribers_see_their_events_without_delay_and_learn_of_lag() {
+        use tokio::sync::broadcast::error::TryRecvError;
+
+        let shared = Arc::new(InMemoryToolRepository::new());
+        let repo = NamespacedToolRepository::new(shared.clone(), "tenant").unwrap();
+        let other = NamespacedToolRepository::new(shared.clone(), "other").unwrap();
+        let mut events = repo.subscribe().unwrap();
+
+        other
+            .save_provider_with_tools(provider("svc"), vec![])
+            .await
+            .unwrap();
+        repo.save_provider_with_tools(provider("svc"), vec![])
+            .await
+            .unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            RepositoryEvent::ProviderSaved {
+                provider_name: "svc".to_string()
+            }
+        );
+        assert!(matches!(events.try_recv(), Err(TryRecvError::Empty)));
+
+        let mut lagging = repo.subscribe().unwrap();
+        for i in 0..300 {
+            repo.save_provider_with_tools(provider(&format!("p{}", i)), vec![])
+                .await
+                .unwrap();
+        }
+        assert!(matches!(lagging.try_recv(), Err(TryRecvError::Lagged(_))));
+    }
+}
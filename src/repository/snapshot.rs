@@ -0,0 +1,302 @@
+use crate::loader::{create_provider_from_value, provider_to_value};
+use crate::providers::base::Provider;
+use crate::tools::Tool;
+use crate::transports::registry::CommunicationProtocolRegistry;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A rebuilt provider paired with the tools recorded for it.
+pub type ProviderWithTools = (Arc<dyn Provider>, Vec<Tool>);
+
+/// A provider serialized as type-tagged JSON together with the tools it offered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSnapshot {
+    /// Provider configuration; `provider_type` selects the concrete provider on import.
+    pub provider: Value,
+    pub tools: Vec<Tool>,
+}
+
+impl ProviderSnapshot {
+    /// Capture a provider and its tools.
+    pub fn from_provider(prov: &dyn Provider, tools: Vec<Tool>) -> Result<Self> {
+        Ok(Self {
+            provider: provider_to_value(prov)?,
+            tools,
+        })
+    }
+
+    /// The provider type tag recorded in the snapshot, if any.
+    pub fn provider_type(&self) -> Option<&str> {
+        self.provider
+            .get("provider_type")
+            .or_else(|| self.provider.get("type"))
+            .and_then(Value::as_str)
+    }
+
+    /// Rebuild the concrete provider instance.
+    pub fn to_provider(&self) -> Result<Arc<dyn Provider>> {
+        create_provider_from_value(self.provider.clone(), 0)
+    }
+}
+
+/// Serializable copy of a repository's provider and tool catalog.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepositorySnapshot {
+    pub providers: Vec<ProviderSnapshot>,
+}
+
+impl RepositorySnapshot {
+    /// Ensure every provider type in the snapshot has a registered communication protocol.
+    /// All unknown types are reported together so a bad catalog can be fixed in one pass.
+    pub fn validate_provider_types(&self, registry: &CommunicationProtocolRegistry) -> Result<()> {
+        let mut unknown = Vec::new();
+        for entry in &self.providers {
+            let provider_type = entry.provider_type().unwrap_or("<missing>");
+            if registry.get(provider_type).is_none() && !unknown.contains(&provider_type) {
+                unknown.push(provider_type);
+            }
+        }
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Snapshot contains unknown provider types: {}",
+                unknown.join(", ")
+            ))
+        }
+    }
+
+    /// Rebuild every provider, pairing it with its tools.
+    pub fn into_providers(self) -> Result<Vec<ProviderWithTools>> {
+        self.providers
+            .into_iter()
+            .map(|entry| Ok((entry.to_provider()?, entry.tools)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{ApiKeyAuth, AuthConfig, BasicAuth};
+    use crate::config::UtcpClientConfig;
+    use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::providers::cli::CliProvider;
+    use crate::providers::graphql::GraphqlProvider;
+    use crate::providers::grpc::GrpcProvider;
+    use crate::providers::http::HttpProvider;
+    use crate::providers::http_stream::StreamableHttpProvider;
+    use crate::providers::mcp::McpProvider;
+    use crate::providers::sse::SseProvider;
+    use crate::providers::tcp::TcpProvider;
+    use crate::providers::text::TextProvider;
+    use crate::providers::udp::UdpProvider;
+    use crate::providers::webrtc::WebRtcProvider;
+    use crate::providers::websocket::WebSocketProvider;
+    use crate::repository::in_memory::InMemoryToolRepository;
+    use crate::repository::ToolRepository;
+    use crate::tools::{test_tool, ToolSearchStrategy};
+    use crate::{UtcpClient, UtcpClientInterface};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    struct NoopSearch;
+
+    #[async_trait]
+    impl ToolSearchStrategy for NoopSearch {
+        async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+            Ok(vec![])
+        }
+    }
+
+    fn make_tool(name: &str) -> Tool {
+        test_tool(name, &format!("{name} tool"), &["demo"])
+    }
+
+    fn headers() -> Option<HashMap<String, String>> {
+        Some(HashMap::from([("X-Trace".to_string(), "1".to_string())]))
+    }
+
+    fn all_providers() -> Vec<Arc<dyn Provider>> {
+        let api_key = Some(AuthConfig::ApiKey(ApiKeyAuth::new("secret".to_string())));
+        let basic = Some(AuthConfig::Basic(BasicAuth::new(
+            "user".to_string(),
+            "pass".to_string(),
+        )));
+
+        let mut http = HttpProvider::new(
+            "http".to_string(),
+            "http://example.com/{id}".to_string(),
+            "POST".to_string(),
+            api_key.clone(),
+        );
+        http.headers = headers();
+        http.header_fields = Some(vec!["trace".to_string()]);
+
+        let mut cli = CliProvider::new("cli".to_string(), "echo".to_string(), None);
+        cli.env_vars = Some(HashMap::from([("A".to_string(), "B".to_string())]));
+
+        let mut sse = SseProvider::new(
+            "sse".to_string(),
+            "http://example.com/sse".to_string(),
+            basic.clone(),
+        );
+        sse.headers = headers();
+
+        let mut ws = WebSocketProvider::new(
+            "ws".to_string(),
+            "ws://example.com".to_string(),
+            api_key.clone(),
+        );
+        ws.headers = headers();
+        ws.keep_alive = true;
+
+        let mut graphql = GraphqlProvider::new(
+            "gql".to_string(),
+            "http://example.com/graphql".to_string(),
+            basic.clone(),
+        );
+        graphql.headers = headers();
+
+        let mut stream = StreamableHttpProvider::new(
+            "stream".to_string(),
+            "http://example.com/stream".to_string(),
+            None,
+        );
+        stream.headers = headers();
+
+        let mut mcp = McpProvider::new(
+            "mcp".to_string(),
+            "http://example.com/mcp".to_string(),
+            api_key,
+        );
+        mcp.headers = headers();
+
+        vec![
+            Arc::new(http),
+            Arc::new(cli),
+            Arc::new(sse),
+            Arc::new(ws),
+            Arc::new(GrpcProvider::new(
+                "grpc".to_string(),
+                "localhost".to_string(),
+                50051,
+                basic,
+            )),
+            Arc::new(graphql),
+            Arc::new(TcpProvider::new(
+                "tcp".to_string(),
+                "localhost".to_string(),
+                9000,
+                None,
+            )),
+            Arc::new(UdpProvider::new(
+                "udp".to_string(),
+                "localhost".to_string(),
+                9001,
+                None,
+            )),
+            Arc::new(stream),
+            Arc::new(mcp),
+            Arc::new(WebRtcProvider::new(
+                "rtc".to_string(),
+                "ws://example.com/signal".to_string(),
+                None,
+            )),
+            Arc::new(TextProvider::new(
+                "text".to_string(),
+                Some("/tmp".into()),
+                None,
+            )),
+        ]
+    }
+
+    #[tokio::test]
+    async fn snapshot_round_trips_every_provider_variant() {
+        let source = InMemoryToolRepository::new();
+        for prov in all_providers() {
+            let tool = make_tool(&format!("{}.op", prov.name()));
+            source
+                .save_provider_with_tools(prov, vec![tool])
+                .await
+                .unwrap();
+        }
+
+        let snap = source.export_snapshot().await.unwrap();
+        assert_eq!(snap.providers.len(), 12);
+
+        // Survive a trip through JSON text, as export_catalog does.
+        let text = serde_json::to_string(&snap).unwrap();
+        let snap: RepositorySnapshot = serde_json::from_str(&text).unwrap();
+
+        let target = InMemoryToolRepository::new();
+        target.import_snapshot(snap).await.unwrap();
+
+        for prov in all_providers() {
+            let name = prov.name();
+            let restored = target.get_provider(&name).await.unwrap().unwrap();
+            assert_eq!(restored.type_(), prov.type_());
+            assert_eq!(
+                provider_to_value(restored.as_ref()).unwrap(),
+                provider_to_value(prov.as_ref()).unwrap(),
+                "provider {name} did not round-trip"
+            );
+            let tools = target.get_tools_by_provider(&name).await.unwrap();
+            assert_eq!(tools.len(), 1);
+            assert_eq!(tools[0].name, format!("{name}.op"));
+        }
+    }
+
+    #[tokio::test]
+    async fn import_rejects_unknown_provider_types() {
+        let snap = RepositorySnapshot {
+            providers: vec![
+                ProviderSnapshot {
+                    provider: json!({"name": "a", "provider_type": "carrier_pigeon"}),
+                    tools: vec![],
+                },
+                ProviderSnapshot {
+                    provider: json!({"name": "b", "provider_type": "smoke_signal"}),
+                    tools: vec![],
+                },
+            ],
+        };
+
+        let repo = InMemoryToolRepository::new();
+        let err = repo.import_snapshot(snap).await.unwrap_err().to_string();
+        assert!(err.contains("carrier_pigeon"));
+        assert!(err.contains("smoke_signal"));
+        assert!(repo.get_tools().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_exports_catalog_to_file() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        let client = UtcpClient::new(UtcpClientConfig::default(), repo, Arc::new(NoopSearch))
+            .await
+            .unwrap();
+        let provider = Arc::new(BaseProvider {
+            name: "base".to_string(),
+            provider_type: ProviderType::Cli,
+            auth: None,
+            allowed_communication_protocols: None,
+        });
+        client
+            .register_tool_provider_with_tools(provider, vec![make_tool("op")])
+            .await
+            .unwrap();
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        client.export_catalog(file.path()).await.unwrap();
+
+        let contents = std::fs::read_to_string(file.path()).unwrap();
+        let snap: RepositorySnapshot = serde_json::from_str(&contents).unwrap();
+        assert_eq!(snap.providers.len(), 1);
+        assert_eq!(snap.providers[0].provider_type(), Some("cli"));
+        assert_eq!(snap.providers[0].tools[0].name, "base.op");
+    }
+}
@@ -0,0 +1,168 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::base::Provider;
+use crate::providers::http::HttpProvider;
+use crate::providers::http_stream::StreamableHttpProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::transports::http_client::HttpClientConfig;
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+/// Echoes the request's `User-Agent` back as JSON on every path.
+async fn spawn_user_agent_echo() -> String {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let user_agent = req
+            .headers()
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(Response::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "user_agent": user_agent }).to_string()))
+            .unwrap())
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_svc)
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn tool(name: &str) -> Tool {
+    serde_json::from_value(json!({
+        "name": name,
+        "description": "",
+        "inputs": {"type": "object"},
+        "outputs": {"type": "object"},
+        "tags": []
+    }))
+    .unwrap()
+}
+
+async fn register(client: &UtcpClient, provider: Arc<dyn Provider>) {
+    let name = format!("{}.whoami", provider.name());
+    client
+        .register_tool_provider_with_tools(provider, vec![tool(&name)])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn configured_user_agent_reaches_server_from_each_http_transport() {
+    let base_url = spawn_user_agent_echo().await;
+    let config = UtcpClientConfig::new().with_http_client(HttpClientConfig {
+        user_agent: Some("utcp-test/1.0".to_string()),
+        ..Default::default()
+    });
+    let client = UtcpClient::new(
+        config,
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+
+    let http = HttpProvider::new(
+        "plain".to_string(),
+        format!("{}/whoami", base_url),
+        "GET".to_string(),
+        None,
+    );
+    register(&client, Arc::new(http)).await;
+    let stream = StreamableHttpProvider::new("chunked".to_string(), base_url.clone(), None);
+    register(&client, Arc::new(stream)).await;
+
+    for tool_name in ["plain.whoami", "chunked.whoami"] {
+        let result = client.call_tool(tool_name, HashMap::new()).await.unwrap();
+        assert_eq!(result["user_agent"], "utcp-test/1.0", "{tool_name}");
+    }
+
+    // Clients without an HTTP client config keep reqwest's default of no user agent.
+    let default_client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    let http = HttpProvider::new(
+        "plain".to_string(),
+        format!("{}/whoami", base_url),
+        "GET".to_string(),
+        None,
+    );
+    register(&default_client, Arc::new(http)).await;
+    let result = default_client
+        .call_tool("plain.whoami", HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(result["user_agent"], json!(null));
+}
+
+#[test]
+fn request_timeouts_fall_back_to_each_transports_own_default() {
+    use crate::providers::http::HttpTimeouts;
+    use crate::transports::client_cache::ClientCache;
+    use crate::transports::http_client::SharedClient;
+    use std::time::Duration;
+
+    let timeout_with =
+        |config: HttpClientConfig, timeouts: Option<HttpTimeouts>, default: Option<Duration>| {
+            let cache = ClientCache::new(SharedClient::new(config).unwrap());
+            let builder = reqwest::Client::new().get("http://example.com");
+            cache
+                .apply_request_timeout(builder, timeouts.as_ref(), default)
+                .build()
+                .unwrap()
+                .timeout()
+                .copied()
+        };
+    let sixty = Some(Duration::from_secs(60));
+
+    assert_eq!(HttpClientConfig::default().request_timeout_ms, None);
+    assert_eq!(
+        timeout_with(HttpClientConfig::default(), None, sixty),
+        sixty
+    );
+    assert_eq!(timeout_with(HttpClientConfig::default(), None, None), None);
+    let shared = HttpClientConfig {
+        request_timeout_ms: Some(5_000),
+        ..Default::default()
+    };
+    assert_eq!(
+        timeout_with(shared.clone(), None, sixty),
+        Some(Duration::from_secs(5))
+    );
+    let provider = HttpTimeouts {
+        request_ms: Some(1_000),
+        ..Default::default()
+    };
+    assert_eq!(
+        timeout_with(shared, Some(provider), sixty),
+        Some(Duration::from_secs(1))
+    );
+}
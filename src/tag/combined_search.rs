@@ -0,0 +1,144 @@
+use crate::tools::{SearchOptions, Tool, ToolSearchStrategy};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Rank-fusion constant; dampens the advantage of the very top positions.
+const RANK_FUSION_K: f64 = 60.0;
+
+/// Blends several search strategies using weighted reciprocal rank fusion.
+///
+/// Each strategy ranks the full catalog independently; a tool earns
+/// `weight / (60 + rank)` from every strategy that returned it with a positive score, so
+/// strategies with incomparable score scales (tag hits vs. BM25) can still be mixed.
+/// Zero-score fallbacks, such as tag search listing every tool when nothing matched, earn
+/// nothing.
+#[derive(Default)]
+pub struct CombinedSearchStrategy {
+    strategies: Vec<(Arc<dyn ToolSearchStrategy>, f64)>,
+}
+
+impl CombinedSearchStrategy {
+    /// Create a combined strategy from `(strategy, weight)` pairs.
+    pub fn new(strategies: Vec<(Arc<dyn ToolSearchStrategy>, f64)>) -> Self {
+        Self { strategies }
+    }
+
+    /// Add another strategy with the given weight.
+    pub fn with_strategy(mut self, strategy: Arc<dyn ToolSearchStrategy>, weight: f64) -> Self {
+        self.strategies.push((strategy, weight));
+        self
+    }
+}
+
+#[async_trait]
+impl ToolSearchStrategy for CombinedSearchStrategy {
+    /// Query every strategy and merge their rankings by weighted reciprocal rank.
+    async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
+        let mut fused: HashMap<String, (Tool, f64)> = HashMap::new();
+        for (strategy, weight) in &self.strategies {
+            let ranked = strategy
+                .search_tools_with(query, SearchOptions::default())
+                .await?;
+            let matched = ranked.into_iter().filter(|scored| scored.score > 0.0);
+            for (rank, tool) in matched.map(|scored| scored.tool).enumerate() {
+                let contribution = weight / (RANK_FUSION_K + rank as f64 + 1.0);
+                fused
+                    .entry(tool.name.clone())
+                    .and_modify(|(_, score)| *score += contribution)
+                    .or_insert((tool, contribution));
+            }
+        }
+
+        let mut results: Vec<(Tool, f64)> = fused.into_values().collect();
+        results.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        if limit > 0 {
+            results.truncate(limit);
+        }
+        Ok(results.into_iter().map(|(tool, _)| tool).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_tool;
+
+    struct FixedRanking(Vec<&'static str>);
+
+    #[async_trait]
+    impl ToolSearchStrategy for FixedRanking {
+        async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+            Ok(self.0.iter().map(|name| make_tool(name)).collect())
+        }
+    }
+
+    fn make_tool(name: &str) -> Tool {
+        test_tool(name, "", &[])
+    }
+
+    #[tokio::test]
+    async fn weights_decide_between_disagreeing_strategies() {
+        let tags: Arc<dyn ToolSearchStrategy> = Arc::new(FixedRanking(vec!["p.a", "p.b"]));
+        let text: Arc<dyn ToolSearchStrategy> = Arc::new(FixedRanking(vec!["p.b", "p.c"]));
+
+        let favor_text =
+            CombinedSearchStrategy::new(vec![(tags.clone(), 1.0), (text.clone(), 3.0)]);
+        let names: Vec<String> = favor_text
+            .search_tools("q", 0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["p.b", "p.c", "p.a"]);
+
+        let favor_tags = CombinedSearchStrategy::default()
+            .with_strategy(tags, 5.0)
+            .with_strategy(text, 0.01);
+        let names: Vec<String> = favor_tags
+            .search_tools("q", 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["p.a", "p.b"]);
+    }
+
+    #[tokio::test]
+    async fn tools_no_strategy_matched_are_not_ranked() {
+        use crate::providers::base::{BaseProvider, ProviderType};
+        use crate::repository::in_memory::InMemoryToolRepository;
+        use crate::repository::ToolRepository;
+        use crate::tag::tag_search::TagSearchStrategy;
+
+        let repo = Arc::new(InMemoryToolRepository::new());
+        let mut mail = make_tool("p.send_mail");
+        mail.tags = vec!["email".to_string()];
+        repo.save_provider_with_tools(
+            Arc::new(BaseProvider {
+                name: "p".to_string(),
+                provider_type: ProviderType::Text,
+                auth: None,
+                allowed_communication_protocols: None,
+            }),
+            vec![mail, make_tool("p.resize_image")],
+        )
+        .await
+        .unwrap();
+        let tags: Arc<dyn ToolSearchStrategy> = Arc::new(TagSearchStrategy::new(repo, 1.0));
+        let combined = CombinedSearchStrategy::new(vec![(tags, 1.0)]);
+
+        let names: Vec<String> = combined
+            .search_tools("email", 0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["p.send_mail"]);
+        assert!(combined.search_tools("weather", 0).await.unwrap().is_empty());
+    }
+}
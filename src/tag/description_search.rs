@@ -0,0 +1,368 @@
+use crate::repository::{RepositoryEvent, RepositoryEvents, ToolRepository};
+use crate::tools::{Tool, ToolSearchStrategy};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "by", "for", "from", "in", "is", "it", "of", "on", "or", "the", "to", "with",
+];
+
+/// Relevance search over tool names and descriptions using BM25 scoring.
+///
+/// The inverted index is built from the repository on first use and then kept current from
+/// repository change events; repositories without events are re-indexed on every query.
+/// Tools that share no terms with the query are not returned.
+pub struct DescriptionSearchStrategy {
+    tool_repository: Arc<dyn ToolRepository>,
+    events: Option<Mutex<RepositoryEvents>>,
+    index: Mutex<Option<Bm25Index>>,
+}
+
+impl DescriptionSearchStrategy {
+    /// Build a description search strategy over the given repository.
+    pub fn new(repo: Arc<dyn ToolRepository>) -> Self {
+        let events = repo.subscribe().map(Mutex::new);
+        Self {
+            tool_repository: repo,
+            events,
+            index: Mutex::new(None),
+        }
+    }
+
+    /// Bring the index up to date with the repository.
+    async fn refresh(&self, index: &mut Option<Bm25Index>) -> Result<()> {
+        let Some(events) = &self.events else {
+            *index = Some(Bm25Index::from_tools(
+                self.tool_repository.get_tools().await?,
+            ));
+            return Ok(());
+        };
+
+        let changed = {
+            let mut rx = events.lock().await;
+            changed_providers(&mut rx)
+        };
+
+        match (index.as_mut(), changed) {
+            (Some(existing), Some(changed)) => {
+                for provider_name in changed {
+                    existing.remove_provider(&provider_name);
+                    if let Ok(tools) = self
+                        .tool_repository
+                        .get_tools_by_provider(&provider_name)
+                        .await
+                    {
+                        for tool in tools {
+                            existing.insert(tool);
+                        }
+                    }
+                }
+            }
+            _ => {
+                *index = Some(Bm25Index::from_tools(
+                    self.tool_repository.get_tools().await?,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolSearchStrategy for DescriptionSearchStrategy {
+    /// Rank tools by BM25 relevance of their name and description to the query.
+    async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
+        let mut guard = self.index.lock().await;
+        self.refresh(&mut guard).await?;
+        let Some(index) = guard.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let mut scored = index.score(&tokenize(query));
+        scored.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        if limit > 0 {
+            scored.truncate(limit);
+        }
+        Ok(scored
+            .into_iter()
+            .filter_map(|(name, _)| index.docs.get(name).map(|doc| doc.tool.clone()))
+            .collect())
+    }
+}
+
+struct IndexedTool {
+    tool: Tool,
+    length: usize,
+    term_freqs: HashMap<String, u32>,
+}
+
+/// Inverted index with the statistics BM25 needs.
+#[derive(Default)]
+struct Bm25Index {
+    docs: HashMap<String, IndexedTool>,
+    postings: HashMap<String, HashSet<String>>,
+    total_length: usize,
+}
+
+impl Bm25Index {
+    fn from_tools(tools: Vec<Tool>) -> Self {
+        let mut index = Self::default();
+        for tool in tools {
+            index.insert(tool);
+        }
+        index
+    }
+
+    fn insert(&mut self, tool: Tool) {
+        self.remove(&tool.name.clone());
+
+        let mut terms = tokenize(&tool.name);
+        terms.extend(tokenize(&tool.description));
+        let mut term_freqs: HashMap<String, u32> = HashMap::new();
+        for term in &terms {
+            *term_freqs.entry(term.clone()).or_insert(0) += 1;
+        }
+        for term in term_freqs.keys() {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(tool.name.clone());
+        }
+
+        self.total_length += terms.len();
+        self.docs.insert(
+            tool.name.clone(),
+            IndexedTool {
+                tool,
+                length: terms.len(),
+                term_freqs,
+            },
+        );
+    }
+
+    fn remove(&mut self, tool_name: &str) {
+        let Some(doc) = self.docs.remove(tool_name) else {
+            return;
+        };
+        self.total_length -= doc.length;
+        for term in doc.term_freqs.keys() {
+            if let Some(ids) = self.postings.get_mut(term) {
+                ids.remove(tool_name);
+                if ids.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+    }
+
+    fn remove_provider(&mut self, provider_name: &str) {
+        let prefix = format!("{}.", provider_name);
+        let names: Vec<String> = self
+            .docs
+            .keys()
+            .filter(|name| name.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for name in names {
+            self.remove(&name);
+        }
+    }
+
+    fn score(&self, query_terms: &[String]) -> Vec<(&String, f64)> {
+        if self.docs.is_empty() {
+            return Vec::new();
+        }
+        let doc_count = self.docs.len() as f64;
+        let avg_length = (self.total_length as f64 / doc_count).max(1.0);
+
+        let unique_terms: HashSet<&String> = query_terms.iter().collect();
+        let mut scores: HashMap<&String, f64> = HashMap::new();
+        for term in unique_terms {
+            let Some(ids) = self.postings.get(term) else {
+                continue;
+            };
+            let df = ids.len() as f64;
+            let idf = (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln();
+            for id in ids {
+                let doc = &self.docs[id];
+                let tf = f64::from(doc.term_freqs[term]);
+                let norm = 1.0 - BM25_B + BM25_B * doc.length as f64 / avg_length;
+                *scores.entry(id).or_insert(0.0) +=
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm);
+            }
+        }
+        scores.into_iter().collect()
+    }
+}
+
+/// Drain pending repository events and return the names of providers that changed,
+/// or `None` when events were dropped and the caller must rebuild from scratch.
+pub(crate) fn changed_providers(
+    rx: &mut RepositoryEvents,
+) -> Option<HashSet<String>> {
+    let mut changed = HashSet::new();
+    let mut lagged = false;
+    loop {
+        match rx.try_recv() {
+            Ok(RepositoryEvent::ProviderSaved { provider_name })
+            | Ok(RepositoryEvent::ProviderRemoved { provider_name })
+            | Ok(RepositoryEvent::ProviderEvicted { provider_name }) => {
+                changed.insert(provider_name);
+            }
+            Err(broadcast::error::TryRecvError::Lagged(_)) => lagged = true,
+            Err(_) => break,
+        }
+    }
+    (!lagged).then_some(changed)
+}
+
+/// Split text into lowercase terms, breaking on punctuation and camelCase boundaries,
+/// dropping stop words and folding simple plurals/third-person verbs ("emails" -> "email").
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for ch in text.chars() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = ch.is_lowercase() || ch.is_numeric();
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+        .into_iter()
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .map(|w| {
+            if w.len() > 3 && w.ends_with('s') && !w.ends_with("ss") {
+                w[..w.len() - 1].to_string()
+            } else {
+                w
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::repository::in_memory::InMemoryToolRepository;
+    use crate::tools::test_tool;
+
+    fn make_tool(name: &str, description: &str) -> Tool {
+        test_tool(name, description, &[])
+    }
+
+    fn provider(name: &str) -> Arc<BaseProvider> {
+        Arc::new(BaseProvider {
+            name: name.to_string(),
+            provider_type: ProviderType::Http,
+            auth: None,
+            allowed_communication_protocols: None,
+        })
+    }
+
+    fn catalog() -> Vec<Tool> {
+        vec![
+            make_tool(
+                "ops.sendMail",
+                "Sends an email message to one or more recipients",
+            ),
+            make_tool("ops.list_inbox", "List messages in the inbox folder"),
+            make_tool("ops.send_sms", "Send a text message to a phone number"),
+            make_tool("ops.get_weather", "Current weather conditions for a city"),
+            make_tool("ops.forecast", "Multi-day weather forecast"),
+            make_tool(
+                "ops.create_invoice",
+                "Create a billing invoice for a customer",
+            ),
+            make_tool("ops.refund_payment", "Refund a previously captured payment"),
+            make_tool("ops.list_customers", "List customers in the CRM"),
+            make_tool("ops.create_ticket", "Open a support ticket"),
+            make_tool("ops.close_ticket", "Close a support ticket"),
+            make_tool("ops.upload_file", "Upload a file to object storage"),
+            make_tool("ops.download_file", "Download a file from object storage"),
+            make_tool("ops.translate", "Translate text between languages"),
+            make_tool("ops.summarize", "Summarize a long document"),
+            make_tool("ops.search_web", "Search the web for pages"),
+            make_tool("ops.resize_image", "Resize an image to given dimensions"),
+            make_tool("ops.schedule_meeting", "Schedule a calendar meeting"),
+            make_tool("ops.stock_quote", "Latest stock price quote"),
+            make_tool(
+                "ops.convert_currency",
+                "Convert an amount between currencies",
+            ),
+            make_tool("ops.geocode", "Resolve an address to coordinates"),
+        ]
+    }
+
+    #[test]
+    fn tokenize_splits_case_and_punctuation() {
+        assert_eq!(
+            tokenize("ops.sendMail: Sends emails to the_user"),
+            vec!["ops", "send", "mail", "send", "email", "user"]
+        );
+    }
+
+    #[tokio::test]
+    async fn ranks_by_description_without_tags() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        repo.save_provider_with_tools(provider("ops"), catalog())
+            .await
+            .unwrap();
+        let strategy = DescriptionSearchStrategy::new(repo);
+
+        let results = strategy.search_tools("send email", 3).await.unwrap();
+        assert_eq!(results[0].name, "ops.sendMail");
+
+        let results = strategy.search_tools("weather forecast", 0).await.unwrap();
+        assert_eq!(results[0].name, "ops.forecast");
+        assert_eq!(results[1].name, "ops.get_weather");
+        assert_eq!(results.len(), 2);
+
+        assert!(strategy
+            .search_tools("xylophone", 5)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn index_follows_repository_changes() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        repo.save_provider_with_tools(provider("ops"), catalog())
+            .await
+            .unwrap();
+        let strategy = DescriptionSearchStrategy::new(repo.clone());
+        assert_eq!(strategy.search_tools("email", 0).await.unwrap().len(), 1);
+
+        repo.save_provider_with_tools(
+            provider("mail"),
+            vec![make_tool("mail.draft", "Draft an email reply")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(strategy.search_tools("email", 0).await.unwrap().len(), 2);
+
+        repo.remove_provider("ops").await.unwrap();
+        let results = strategy.search_tools("email", 0).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "mail.draft");
+    }
+}
@@ -0,0 +1,332 @@
+//! Semantic tool search backed by a pluggable [`Embedder`].
+//!
+//! Any embedding model can be used by implementing [`Embedder`]. A remote model such as
+//! OpenAI's embeddings endpoint only needs to POST the batch and return the vectors in
+//! request order:
+//!
+//! ```ignore
+//! struct OpenAiEmbedder { client: reqwest::Client, api_key: String }
+//!
+//! #[async_trait]
+//! impl Embedder for OpenAiEmbedder {
+//!     async fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+//!         let body: serde_json::Value = self.client
+//!             .post("https://api.openai.com/v1/embeddings")
+//!             .bearer_auth(&self.api_key)
+//!             .json(&serde_json::json!({ "model": "text-embedding-3-small", "input": texts }))
+//!             .send().await?.error_for_status()?.json().await?;
+//!         Ok(serde_json::from_value(
+//!             body["data"].as_array().into_iter().flatten()
+//!                 .map(|item| item["embedding"].clone()).collect(),
+//!         )?)
+//!     }
+//! }
+//! ```
+//!
+//! A local model (ONNX, llama.cpp server, ...) is wired the same way. [`HashingEmbedder`]
+//! is a deterministic, dependency-free stand-in for tests and offline use.
+
+use crate::repository::{RepositoryEvents, ToolRepository};
+use crate::tag::description_search::{changed_providers, tokenize};
+use crate::tag::tag_search::TagSearchStrategy;
+use crate::tools::{Tool, ToolSearchStrategy};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Turns text into dense vectors for similarity search.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Embed each text, returning one vector per input in the same order.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Deterministic bag-of-words embedder that hashes terms into a fixed number of buckets.
+///
+/// It captures lexical rather than semantic similarity, but needs no model and always
+/// produces the same vectors, which makes it suitable for tests.
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    /// Create an embedder producing vectors of `dimensions` components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dimensions` is zero.
+    pub fn new(dimensions: usize) -> Self {
+        assert!(dimensions > 0, "embedding dimensions must be non-zero");
+        Self { dimensions }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+#[async_trait]
+impl Embedder for HashingEmbedder {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut vector = vec![0.0f32; self.dimensions];
+                for term in tokenize(text) {
+                    let hash = fnv1a(term.as_bytes());
+                    let bucket = (hash % self.dimensions as u64) as usize;
+                    // The top bit picks a sign so colliding terms tend to cancel out.
+                    vector[bucket] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+                }
+                vector
+            })
+            .collect())
+    }
+}
+
+/// FNV-1a, used instead of `DefaultHasher` so vectors are stable across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Embedded tools keyed by fully qualified tool name.
+type VectorIndex = HashMap<String, (Tool, Vec<f32>)>;
+
+/// Ranks tools by cosine similarity between the query embedding and each tool's
+/// name and description embedding.
+///
+/// Tool vectors are computed lazily: the first search embeds the whole catalog, and later
+/// searches re-embed only the providers that repository change events report as saved, and
+/// drop the vectors of removed ones. Without change events every search re-embeds the
+/// catalog. If the embedder fails, the query is answered by tag search instead.
+pub struct EmbeddingSearchStrategy {
+    tool_repository: Arc<dyn ToolRepository>,
+    embedder: Arc<dyn Embedder>,
+    fallback: TagSearchStrategy,
+    events: Option<Mutex<RepositoryEvents>>,
+    index: Mutex<Option<VectorIndex>>,
+}
+
+impl EmbeddingSearchStrategy {
+    /// Build an embedding search strategy over the given repository.
+    pub fn new(repo: Arc<dyn ToolRepository>, embedder: Arc<dyn Embedder>) -> Self {
+        let events = repo.subscribe().map(Mutex::new);
+        Self {
+            fallback: TagSearchStrategy::new(repo.clone(), 1.0),
+            tool_repository: repo,
+            embedder,
+            events,
+            index: Mutex::new(None),
+        }
+    }
+
+    async fn embed_tools(&self, tools: Vec<Tool>) -> Result<Vec<(Tool, Vec<f32>)>> {
+        if tools.is_empty() {
+            return Ok(Vec::new());
+        }
+        let texts: Vec<String> = tools
+            .iter()
+            .map(|tool| format!("{} {}", tool.name, tool.description))
+            .collect();
+        let vectors = self.embedder.embed(&texts).await?;
+        if vectors.len() != tools.len() {
+            return Err(anyhow!(
+                "Embedder returned {} vectors for {} tools",
+                vectors.len(),
+                tools.len()
+            ));
+        }
+        Ok(tools.into_iter().zip(vectors).collect())
+    }
+
+    /// Bring the vector index up to date with the repository.
+    async fn refresh(&self, index: &mut Option<VectorIndex>) -> Result<()> {
+        let changed = match &self.events {
+            Some(events) => changed_providers(&mut *events.lock().await),
+            None => None,
+        };
+
+        match (index.as_mut(), changed) {
+            (Some(existing), Some(changed)) => {
+                for provider_name in changed {
+                    let prefix = format!("{}.", provider_name);
+                    existing.retain(|name, _| !name.starts_with(&prefix));
+                    let tools = self
+                        .tool_repository
+                        .get_tools_by_provider(&provider_name)
+                        .await
+                        .unwrap_or_default();
+                    for (tool, vector) in self.embed_tools(tools).await? {
+                        existing.insert(tool.name.clone(), (tool, vector));
+                    }
+                }
+            }
+            _ => {
+                let tools = self.tool_repository.get_tools().await?;
+                let embedded = self.embed_tools(tools).await?;
+                *index = Some(
+                    embedded
+                        .into_iter()
+                        .map(|(tool, vector)| (tool.name.clone(), (tool, vector)))
+                        .collect(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn semantic_search(
+        &self,
+        index: &mut Option<VectorIndex>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Tool>> {
+        self.refresh(index).await?;
+        let query_vector = self
+            .embedder
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("Embedder returned no vector for the query"))?;
+
+        let mut scored: Vec<(&Tool, f32)> = index
+            .iter()
+            .flat_map(|entries| entries.values())
+            .map(|(tool, vector)| (tool, cosine_similarity(&query_vector, vector)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scored.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        if limit > 0 {
+            scored.truncate(limit);
+        }
+        Ok(scored.into_iter().map(|(tool, _)| tool.clone()).collect())
+    }
+}
+
+#[async_trait]
+impl ToolSearchStrategy for EmbeddingSearchStrategy {
+    /// Rank tools by embedding similarity, falling back to tag search if embedding fails.
+    async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
+        let mut index = self.index.lock().await;
+        match self.semantic_search(&mut index, query, limit).await {
+            Ok(tools) => Ok(tools),
+            Err(_) => {
+                // A partially refreshed index can't be trusted; rebuild it on the next query.
+                *index = None;
+                drop(index);
+                self.fallback.search_tools(query, limit).await
+            }
+        }
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::repository::in_memory::InMemoryToolRepository;
+    use crate::tools::test_tool;
+
+    struct FailingEmbedder;
+
+    #[async_trait]
+    impl Embedder for FailingEmbedder {
+        async fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Err(anyhow!("model unavailable"))
+        }
+    }
+
+    fn provider(name: &str) -> Arc<BaseProvider> {
+        Arc::new(BaseProvider {
+            name: name.to_string(),
+            provider_type: ProviderType::Http,
+            auth: None,
+            allowed_communication_protocols: None,
+        })
+    }
+
+    async fn seeded_repo() -> Arc<InMemoryToolRepository> {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        repo.save_provider_with_tools(
+            provider("weather"),
+            vec![
+                test_tool(
+                    "weather.forecast",
+                    "Multi-day weather forecast",
+                    &["weather"],
+                ),
+                test_tool("weather.alerts", "Severe storm alerts", &["weather"]),
+            ],
+        )
+        .await
+        .unwrap();
+        repo.save_provider_with_tools(
+            provider("mail"),
+            vec![test_tool("mail.send", "Send an email message", &["email"])],
+        )
+        .await
+        .unwrap();
+        repo
+    }
+
+    #[tokio::test]
+    async fn hashing_embedder_is_deterministic() {
+        let embedder = HashingEmbedder::new(64);
+        let texts = vec!["send email".to_string(), "send email".to_string()];
+        let vectors = embedder.embed(&texts).await.unwrap();
+        assert_eq!(vectors[0], vectors[1]);
+        assert_eq!(vectors[0].len(), 64);
+        assert!((cosine_similarity(&vectors[0], &vectors[1]) - 1.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn ranks_by_similarity_and_drops_deregistered_tools() {
+        let repo = seeded_repo().await;
+        let strategy =
+            EmbeddingSearchStrategy::new(repo.clone(), Arc::new(HashingEmbedder::default()));
+
+        let results = strategy.search_tools("weather forecast", 0).await.unwrap();
+        assert_eq!(results[0].name, "weather.forecast");
+        assert!(results.iter().all(|t| t.name != "mail.send"));
+
+        repo.remove_provider("weather").await.unwrap();
+        assert!(strategy
+            .search_tools("weather forecast", 0)
+            .await
+            .unwrap()
+            .is_empty());
+        let results = strategy.search_tools("email", 0).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "mail.send");
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_tag_search_when_embedder_fails() {
+        let repo = seeded_repo().await;
+        let strategy = EmbeddingSearchStrategy::new(repo, Arc::new(FailingEmbedder));
+
+        let results = strategy.search_tools("email", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "mail.send");
+    }
+}
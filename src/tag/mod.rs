@@ -1 +1,6 @@
+pub mod combined_search;
+pub mod description_search;
+pub mod embedding_search;
+pub mod normalizer;
+pub mod pattern_search;
 pub mod tag_search;
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+/// Normalizes tags and query terms so inconsistent spellings match each other.
+///
+/// Every term is lowercased and reduced to a simple singular form ("payments" -> "payment",
+/// "categories" -> "category"), then mapped through the configured synonym groups to a
+/// single canonical term.
+#[derive(Debug, Clone, Default)]
+pub struct TagNormalizer {
+    canonical: HashMap<String, String>,
+}
+
+impl TagNormalizer {
+    /// A normalizer that only lowercases and singularizes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A normalizer that also folds synonyms: each key is the canonical term and its values
+    /// are treated as the same term, e.g. `"billing" => ["payments", "invoicing"]`.
+    pub fn with_synonyms(synonyms: HashMap<String, Vec<String>>) -> Self {
+        let mut normalizer = Self::default();
+        for (canonical, aliases) in synonyms {
+            normalizer.add_synonyms(&canonical, &aliases);
+        }
+        normalizer
+    }
+
+    /// Register `aliases` as synonyms of `canonical`.
+    pub fn add_synonyms(&mut self, canonical: &str, aliases: &[String]) {
+        let canonical = singularize(&canonical.to_lowercase());
+        for alias in aliases {
+            self.canonical
+                .insert(singularize(&alias.to_lowercase()), canonical.clone());
+        }
+        self.canonical.insert(canonical.clone(), canonical);
+    }
+
+    /// Normalize a single term.
+    pub fn normalize(&self, term: &str) -> String {
+        let singular = singularize(&term.to_lowercase());
+        match self.canonical.get(&singular) {
+            Some(canonical) => canonical.clone(),
+            None => singular,
+        }
+    }
+}
+
+/// Strip common English plural endings; words of three letters or fewer are left alone.
+fn singularize(word: &str) -> String {
+    if word.len() <= 3 || !word.is_ascii() {
+        return word.to_string();
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    for suffix in ["sses", "shes", "ches", "xes", "zes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    if word.ends_with('s') && !["ss", "us", "is"].iter().any(|end| word.ends_with(end)) {
+        return word[..word.len() - 1].to_string();
+    }
+    word.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercases_and_singularizes() {
+        let normalizer = TagNormalizer::new();
+        for (input, expected) in [
+            ("Payments", "payment"),
+            ("categories", "category"),
+            ("addresses", "address"),
+            ("boxes", "box"),
+            ("status", "status"),
+            ("analysis", "analysis"),
+            ("ops", "ops"),
+        ] {
+            assert_eq!(normalizer.normalize(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn folds_synonyms_to_canonical_term() {
+        let normalizer = TagNormalizer::with_synonyms(HashMap::from([(
+            "billing".to_string(),
+            vec!["payments".to_string(), "Invoicing".to_string()],
+        )]));
+        assert_eq!(normalizer.normalize("payment"), "billing");
+        assert_eq!(normalizer.normalize("PAYMENTS"), "billing");
+        assert_eq!(normalizer.normalize("invoicing"), "billing");
+        assert_eq!(normalizer.normalize("billing"), "billing");
+        assert_eq!(normalizer.normalize("refunds"), "refund");
+    }
+}
@@ -0,0 +1,216 @@
+use crate::repository::ToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use std::sync::Arc;
+
+const REGEX_PREFIX: &str = "regex:";
+
+/// Name-pattern search for scripts that want exact selection rather than relevance.
+///
+/// The query is a glob matched against the whole qualified tool name (`*` matches any run of
+/// characters, `?` one character, `[...]`/`[!...]` a character class). Prefixing the query
+/// with `regex:` uses a regular expression instead, which is unanchored unless it uses `^`/`$`.
+/// Matches are returned in lexicographic order.
+pub struct PatternSearchStrategy {
+    tool_repository: Arc<dyn ToolRepository>,
+}
+
+impl PatternSearchStrategy {
+    /// Build a pattern search strategy over the given repository.
+    pub fn new(repo: Arc<dyn ToolRepository>) -> Self {
+        Self {
+            tool_repository: repo,
+        }
+    }
+
+    /// Compile a query into the regex used for matching tool names.
+    pub fn compile(query: &str) -> Result<Regex> {
+        let source = match query.strip_prefix(REGEX_PREFIX) {
+            Some(pattern) => pattern.to_string(),
+            None => glob_to_regex(query)?,
+        };
+        Regex::new(&source).map_err(|e| anyhow!("Invalid search pattern '{}': {}", query, e))
+    }
+}
+
+#[async_trait]
+impl ToolSearchStrategy for PatternSearchStrategy {
+    /// Return tools whose qualified names match the pattern, sorted by name.
+    async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
+        let pattern = Self::compile(query.trim())?;
+        let mut matches: Vec<Tool> = self
+            .tool_repository
+            .get_tools()
+            .await?
+            .into_iter()
+            .filter(|tool| pattern.is_match(&tool.name))
+            .collect();
+        matches.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        if limit > 0 {
+            matches.truncate(limit);
+        }
+        Ok(matches)
+    }
+}
+
+/// Translate a glob into an anchored regular expression.
+pub(crate) fn glob_to_regex(glob: &str) -> Result<String> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                for (i, c) in chars.by_ref().enumerate() {
+                    // A ']' first in the class, after any '!', is a literal: `[]a]`, `[!]a]`
+                    let at_start = class.is_empty() || class == "^";
+                    match c {
+                        ']' if !at_start => {
+                            closed = true;
+                            break;
+                        }
+                        '!' if i == 0 => class.push('^'),
+                        '\\' | '[' | ']' | '^' | '&' | '~' => {
+                            class.push('\\');
+                            class.push(c);
+                        }
+                        _ => class.push(c),
+                    }
+                }
+                if !closed || class.is_empty() || class == "^" {
+                    return Err(anyhow!("Invalid glob '{}': unterminated '['", glob));
+                }
+                regex.push('[');
+                regex.push_str(&class);
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::repository::in_memory::InMemoryToolRepository;
+    use crate::tools::test_tool;
+
+    fn make_tool(name: &str) -> Tool {
+        test_tool(name, "", &[])
+    }
+
+    async fn strategy() -> PatternSearchStrategy {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        for (provider_name, tools) in [
+            (
+                "aws",
+                vec![
+                    "aws.ec2_describe_instances",
+                    "aws.describe_regions",
+                    "aws.s3_put_object",
+                ],
+            ),
+            ("gcp", vec!["gcp.describe_zones"]),
+            ("xaws", vec!["xaws.describe_all"]),
+        ] {
+            let provider = Arc::new(BaseProvider {
+                name: provider_name.to_string(),
+                provider_type: ProviderType::Http,
+                auth: None,
+                allowed_communication_protocols: None,
+            });
+            let tools = tools.into_iter().map(make_tool).collect();
+            repo.save_provider_with_tools(provider, tools)
+                .await
+                .unwrap();
+        }
+        PatternSearchStrategy::new(repo)
+    }
+
+    async fn names(strategy: &PatternSearchStrategy, query: &str, limit: usize) -> Vec<String> {
+        strategy
+            .search_tools(query, limit)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn glob_matches_whole_qualified_name() {
+        let strategy = strategy().await;
+        assert_eq!(
+            names(&strategy, "aws.*describe_*", 0).await,
+            vec!["aws.describe_regions", "aws.ec2_describe_instances"]
+        );
+        assert_eq!(
+            names(&strategy, "*.describe_*", 0).await,
+            vec![
+                "aws.describe_regions",
+                "gcp.describe_zones",
+                "xaws.describe_all"
+            ]
+        );
+        assert_eq!(
+            names(&strategy, "?cp.*", 0).await,
+            vec!["gcp.describe_zones"]
+        );
+        assert_eq!(
+            names(&strategy, "[!x]*.describe_*", 1).await,
+            vec!["aws.describe_regions"]
+        );
+        // Globs are anchored: neither a prefix nor a suffix match is enough.
+        assert!(names(&strategy, "aws", 0).await.is_empty());
+        assert!(names(&strategy, "describe_regions", 0).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn regex_prefix_uses_unanchored_regex() {
+        let strategy = strategy().await;
+        assert_eq!(
+            names(&strategy, "regex:describe_(regions|zones)$", 0).await,
+            vec!["aws.describe_regions", "gcp.describe_zones"]
+        );
+        assert_eq!(
+            names(&strategy, "regex:^aws\\.", 0).await,
+            vec![
+                "aws.describe_regions",
+                "aws.ec2_describe_instances",
+                "aws.s3_put_object"
+            ]
+        );
+        assert_eq!(names(&strategy, "regex:aws\\.", 0).await.len(), 4);
+    }
+
+    #[test]
+    fn leading_bracket_in_a_class_is_literal() {
+        let matches = |glob: &str, name: &str| {
+            Regex::new(&glob_to_regex(glob).unwrap())
+                .unwrap()
+                .is_match(name)
+        };
+        assert!(matches("a[]x]b", "a]b"));
+        assert!(matches("a[]x]b", "axb"));
+        assert!(!matches("a[!]x]b", "a]b"));
+        assert!(!matches("a[!]x]b", "axb"));
+        assert!(matches("a[!]x]b", "ayb"));
+        assert!(matches("a[x^]b", "a^b"));
+        assert!(glob_to_regex("a[!]").is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_patterns_are_errors() {
+        let strategy = strategy().await;
+        assert!(strategy.search_tools("regex:(unclosed", 0).await.is_err());
+        assert!(strategy.search_tools("aws.[abc", 0).await.is_err());
+    }
+}
@@ -1,111 +1,216 @@
-use crate::repository::ToolRepository;
-use crate::tools::{Tool, ToolSearchStrategy};
+use crate::repository::{RepositoryEvents, ToolRepository};
+use crate::tag::description_search::changed_providers;
+use crate::tag::normalizer::TagNormalizer;
+use crate::tools::{ScoredTool, SearchOptions, Tool, ToolSearchStrategy};
 use anyhow::Result;
 use async_trait::async_trait;
 use regex::Regex;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::Mutex;
 
 /// Simple tag/description based search that rewards tag matches and keyword overlap.
+///
+/// Tools are held in an inverted index (tag -> tools, word -> tools) built on first use and
+/// kept current from repository change events and from the explicit
+/// [`index_provider`](ToolSearchStrategy::index_provider) /
+/// [`remove_provider`](ToolSearchStrategy::remove_provider) calls `UtcpClient` makes, so a
+/// query only scores tools that share a tag or word with it.
 pub struct TagSearchStrategy {
     tool_repository: Arc<dyn ToolRepository>,
     description_weight: f64,
-    word_regex: Regex,
+    analyzer: Analyzer,
+    events: Option<Mutex<RepositoryEvents>>,
+    index: Mutex<Option<TagIndex>>,
 }
 
 impl TagSearchStrategy {
     /// Build a new tag search strategy with configurable description weight.
     pub fn new(repo: Arc<dyn ToolRepository>, description_weight: f64) -> Self {
+        let events = repo.subscribe().map(Mutex::new);
         Self {
             tool_repository: repo,
             description_weight,
-            word_regex: Regex::new(r"\w+").unwrap(),
+            analyzer: Analyzer {
+                word_regex: Regex::new(r"\w+").unwrap(),
+                normalizer: None,
+            },
+            events,
+            index: Mutex::new(None),
         }
     }
-}
 
-#[derive(Clone)]
-struct ScoredTool {
-    tool: Tool,
-    score: f64,
+    /// Normalize tags, description words and query terms with `normalizer`, so that
+    /// plural forms and configured synonyms match each other.
+    pub fn with_normalizer(mut self, normalizer: TagNormalizer) -> Self {
+        self.analyzer.normalizer = Some(normalizer);
+        // Terms indexed so far were produced without the normalizer.
+        self.index = Mutex::new(None);
+        self
+    }
+
+    /// Bring the index up to date, building it from the repository on first use. Repositories
+    /// without change events are re-read in full, since nothing reports what changed.
+    async fn refresh(&self, index: &mut Option<TagIndex>) -> Result<()> {
+        let changed = match &self.events {
+            Some(events) => changed_providers(&mut *events.lock().await),
+            None => None,
+        };
+
+        match (index.as_mut(), changed) {
+            (Some(existing), Some(changed)) => {
+                for provider_name in changed {
+                    existing.remove_provider(&provider_name, &self.analyzer);
+                    let tools = self
+                        .tool_repository
+                        .get_tools_by_provider(&provider_name)
+                        .await
+                        .unwrap_or_default();
+                    for tool in tools {
+                        existing.insert(tool, &self.analyzer);
+                    }
+                }
+            }
+            _ => {
+                let mut rebuilt = TagIndex::default();
+                for tool in self.tool_repository.get_tools().await? {
+                    rebuilt.insert(tool, &self.analyzer);
+                }
+                *index = Some(rebuilt);
+            }
+        }
+        Ok(())
+    }
+
+    async fn search_index(
+        &self,
+        query: &str,
+        filter: &(dyn Fn(&Tool) -> bool + Sync),
+        limit: usize,
+    ) -> Result<Vec<ScoredTool>> {
+        let mut guard = self.index.lock().await;
+        self.refresh(&mut guard).await?;
+        Ok(match guard.as_ref() {
+            Some(index) => self.rank(query, index, filter, limit),
+            None => Vec::new(),
+        })
+    }
 }
 
 #[async_trait]
 impl ToolSearchStrategy for TagSearchStrategy {
     /// Score tools by tags and description keywords and return the best matches.
     async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>> {
-        let query_lower = query.trim().to_lowercase();
-        let query_word_set: HashSet<String> = self
-            .word_regex
-            .find_iter(&query_lower)
-            .map(|m| m.as_str().to_string())
-            .collect();
-
-        let tools = self.tool_repository.get_tools().await?;
-        if tools.is_empty() {
-            return Ok(Vec::new());
-        }
+        Ok(self
+            .search_index(query, &|_| true, limit)
+            .await?
+            .into_iter()
+            .map(|st| st.tool)
+            .collect())
+    }
 
-        let mut positives = Vec::new();
-        let mut nonpositives = Vec::new();
+    /// Apply provider and tag filters before scoring, then paginate the ranking.
+    async fn search_tools_with(&self, query: &str, opts: SearchOptions) -> Result<Vec<ScoredTool>> {
+        let ranked = self
+            .search_index(query, &|tool| opts.matches(tool), opts.window())
+            .await?;
+        Ok(opts.paginate(ranked))
+    }
 
-        for tool in tools {
-            let score = self.score_tool(&tool, &query_lower, &query_word_set);
-            let entry = ScoredTool { tool, score };
-            if score > 0.0 {
-                positives.push(entry);
-            } else {
-                nonpositives.push(entry);
+    /// Replace the indexed tools of `provider_name` with `tools`.
+    async fn index_provider(&self, provider_name: &str, tools: &[Tool]) -> Result<()> {
+        if let Some(index) = self.index.lock().await.as_mut() {
+            index.remove_provider(provider_name, &self.analyzer);
+            for tool in tools {
+                index.insert(tool.clone(), &self.analyzer);
             }
         }
+        Ok(())
+    }
 
-        // Unbounded result set keeps full sort for correctness but skips zero-score tools unless necessary.
-        if limit == 0 {
-            if !positives.is_empty() {
-                positives.sort_unstable_by(compare_scored);
-                return Ok(positives.into_iter().map(|st| st.tool).collect());
-            }
-
-            nonpositives.sort_unstable_by(compare_scored);
-            return Ok(nonpositives.into_iter().map(|st| st.tool).collect());
+    /// Drop every indexed tool of `provider_name`.
+    async fn remove_provider(&self, provider_name: &str) -> Result<()> {
+        if let Some(index) = self.index.lock().await.as_mut() {
+            index.remove_provider(provider_name, &self.analyzer);
         }
+        Ok(())
+    }
+}
 
-        if !positives.is_empty() {
-            take_top_n(&mut positives, limit);
-            return Ok(positives.into_iter().map(|st| st.tool).collect());
-        }
+impl TagSearchStrategy {
+    /// Score indexed tools accepted by `filter`, returning the best `limit` (all when zero).
+    /// When nothing scores positively, and for an empty query, tools are returned with a
+    /// score of zero in provider/tool name order.
+    fn rank(
+        &self,
+        query: &str,
+        index: &TagIndex,
+        filter: &(dyn Fn(&Tool) -> bool + Sync),
+        limit: usize,
+    ) -> Vec<ScoredTool> {
+        let unscored = || -> Vec<ScoredTool> {
+            index
+                .tools
+                .values()
+                .filter(|tool| filter(tool))
+                .map(|tool| ScoredTool {
+                    tool: tool.clone(),
+                    score: 0.0,
+                })
+                .collect()
+        };
 
-        if nonpositives.is_empty() {
-            return Ok(Vec::new());
+        let query_lower = query.trim().to_lowercase();
+        if query_lower.is_empty() {
+            let mut all = unscored();
+            take_top_n(&mut all, limit);
+            return all;
         }
+        let query_phrase = self.analyzer.phrase(&query_lower);
+        let query_word_set: HashSet<String> = self.analyzer.words(&query_lower).collect();
+
+        let mut positives: Vec<ScoredTool> = index
+            .candidates(&query_phrase, &query_word_set)
+            .into_iter()
+            .filter_map(|name| index.tools.get(name))
+            .filter(|tool| filter(tool))
+            .filter_map(|tool| {
+                let score = self.score_tool(tool, &query_phrase, &query_word_set);
+                (score > 0.0).then(|| ScoredTool {
+                    tool: tool.clone(),
+                    score,
+                })
+            })
+            .collect();
 
-        take_top_n(&mut nonpositives, limit);
-        Ok(nonpositives.into_iter().map(|st| st.tool).collect())
+        // Zero-score tools are only returned when nothing matched at all.
+        if positives.is_empty() {
+            positives = unscored();
+        }
+        take_top_n(&mut positives, limit);
+        positives
     }
-}
 
-impl TagSearchStrategy {
-    fn score_tool(&self, tool: &Tool, query_lower: &str, query_word_set: &HashSet<String>) -> f64 {
+    fn score_tool(&self, tool: &Tool, query_phrase: &str, query_word_set: &HashSet<String>) -> f64 {
         let mut score = 0.0;
 
         for tag in &tool.tags {
             let tag_lower = tag.to_ascii_lowercase();
 
-            if query_lower.contains(&tag_lower) {
+            if query_phrase.contains(&self.analyzer.phrase(&tag_lower)) {
                 score += 1.0;
             }
 
-            for m in self.word_regex.find_iter(&tag_lower) {
-                if query_word_set.contains(m.as_str()) {
+            for word in self.analyzer.words(&tag_lower) {
+                if query_word_set.contains(&word) {
                     score += self.description_weight;
                 }
             }
         }
 
-        for m in self.word_regex.find_iter(&tool.description) {
-            let word = m.as_str().to_ascii_lowercase();
-            if word.len() > 2 && query_word_set.contains(&word) {
+        for word in self.analyzer.description_words(&tool.description) {
+            if query_word_set.contains(&word) {
                 score += self.description_weight;
             }
         }
@@ -114,9 +219,145 @@ impl TagSearchStrategy {
     }
 }
 
+/// Splits tags, descriptions and queries into comparable terms.
+struct Analyzer {
+    word_regex: Regex,
+    normalizer: Option<TagNormalizer>,
+}
+
+impl Analyzer {
+    fn term(&self, word: &str) -> String {
+        match &self.normalizer {
+            Some(normalizer) => normalizer.normalize(word),
+            None => word.to_string(),
+        }
+    }
+
+    /// Terms of already lowercased text.
+    fn words<'a>(&'a self, text_lower: &'a str) -> impl Iterator<Item = String> + 'a {
+        self.word_regex
+            .find_iter(text_lower)
+            .map(|m| self.term(m.as_str()))
+    }
+
+    /// Description terms; words of two letters or fewer never score.
+    fn description_words<'a>(&'a self, text: &'a str) -> impl Iterator<Item = String> + 'a {
+        self.word_regex
+            .find_iter(text)
+            .map(|m| m.as_str().to_ascii_lowercase())
+            .filter(|word| word.len() > 2)
+            .map(|word| self.term(&word))
+    }
+
+    /// Form of lowercased text used for tag containment checks. Without a normalizer this is
+    /// the text itself; with one, its normalized terms joined by spaces.
+    fn phrase(&self, text_lower: &str) -> String {
+        match self.normalizer {
+            Some(_) => self.words(text_lower).collect::<Vec<_>>().join(" "),
+            None => text_lower.to_string(),
+        }
+    }
+}
+
+/// Inverted index over the terms `score_tool` can match, keyed by tool name.
+#[derive(Default)]
+struct TagIndex {
+    tools: HashMap<String, Tool>,
+    by_tag: HashMap<String, HashSet<String>>,
+    by_word: HashMap<String, HashSet<String>>,
+}
+
+impl TagIndex {
+    /// Tag phrases and the words that can score for a tool.
+    fn terms(tool: &Tool, analyzer: &Analyzer) -> (HashSet<String>, HashSet<String>) {
+        let lowered: Vec<String> = tool.tags.iter().map(|t| t.to_ascii_lowercase()).collect();
+        let tags: HashSet<String> = lowered.iter().map(|tag| analyzer.phrase(tag)).collect();
+        let mut words: HashSet<String> = lowered
+            .iter()
+            .flat_map(|tag| analyzer.words(tag).collect::<Vec<_>>())
+            .collect();
+        words.extend(analyzer.description_words(&tool.description));
+        (tags, words)
+    }
+
+    fn insert(&mut self, tool: Tool, analyzer: &Analyzer) {
+        self.remove(&tool.name, analyzer);
+        let (tags, words) = Self::terms(&tool, analyzer);
+        for tag in tags {
+            self.by_tag
+                .entry(tag)
+                .or_default()
+                .insert(tool.name.clone());
+        }
+        for word in words {
+            self.by_word
+                .entry(word)
+                .or_default()
+                .insert(tool.name.clone());
+        }
+        self.tools.insert(tool.name.clone(), tool);
+    }
+
+    fn remove(&mut self, tool_name: &str, analyzer: &Analyzer) {
+        let Some(tool) = self.tools.remove(tool_name) else {
+            return;
+        };
+        let (tags, words) = Self::terms(&tool, analyzer);
+        unlink(&mut self.by_tag, &tags, tool_name);
+        unlink(&mut self.by_word, &words, tool_name);
+    }
+
+    fn remove_provider(&mut self, provider_name: &str, analyzer: &Analyzer) {
+        let names: Vec<String> = self
+            .tools
+            .values()
+            .filter(|tool| tool.provider_name() == Some(provider_name))
+            .map(|tool| tool.name.clone())
+            .collect();
+        for name in names {
+            self.remove(&name, analyzer);
+        }
+    }
+
+    /// Tools sharing a word with the query or carrying a tag contained in it.
+    fn candidates(&self, query_phrase: &str, query_words: &HashSet<String>) -> HashSet<&String> {
+        let mut candidates = HashSet::new();
+        for (tag, names) in &self.by_tag {
+            if query_phrase.contains(tag.as_str()) {
+                candidates.extend(names);
+            }
+        }
+        for word in query_words {
+            if let Some(names) = self.by_word.get(word) {
+                candidates.extend(names);
+            }
+        }
+        candidates
+    }
+}
+
+/// Remove `tool_name` from the postings of `terms`, dropping postings that become empty.
+fn unlink(
+    postings: &mut HashMap<String, HashSet<String>>,
+    terms: &HashSet<String>,
+    tool_name: &str,
+) {
+    for term in terms {
+        if let Some(names) = postings.get_mut(term) {
+            names.remove(tool_name);
+            if names.is_empty() {
+                postings.remove(term);
+            }
+        }
+    }
+}
+
+/// Highest score first; ties are broken by provider name, then tool name, so results are
+/// reproducible regardless of repository iteration order.
 fn compare_scored(a: &ScoredTool, b: &ScoredTool) -> Ordering {
     b.score
         .total_cmp(&a.score)
+        .then_with(|| a.tool.provider_name().cmp(&b.tool.provider_name()))
         .then_with(|| a.tool.name.cmp(&b.tool.name))
 }
 
@@ -138,35 +379,41 @@ fn take_top_n(scored: &mut Vec<ScoredTool>, limit: usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::UtcpClientConfig;
+    use crate::providers::base::Provider;
     use crate::providers::base::{BaseProvider, ProviderType};
     use crate::repository::in_memory::InMemoryToolRepository;
-    use crate::tools::ToolInputOutputSchema;
+    use crate::tools::test_tool;
+    use crate::{UtcpClient, UtcpClientInterface};
     use std::sync::Arc;
 
-    fn schema() -> ToolInputOutputSchema {
-        ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
+    /// Repository that publishes no change events.
+    struct QuietRepository(InMemoryToolRepository);
+
+    #[async_trait]
+    impl ToolRepository for QuietRepository {
+        async fn save_provider_with_tools(
+            &self,
+            prov: Arc<dyn Provider>,
+            tools: Vec<Tool>,
+        ) -> Result<()> {
+            self.0.save_provider_with_tools(prov, tools).await
         }
-    }
 
-    fn make_tool(name: &str, description: &str, tags: &[&str]) -> Tool {
-        Tool {
-            name: name.to_string(),
-            description: description.to_string(),
-            inputs: schema(),
-            outputs: schema(),
-            tags: tags.iter().map(|t| t.to_string()).collect(),
-            average_response_size: None,
-            provider: None,
+        async fn get_provider(&self, name: &str) -> Result<Option<Arc<dyn Provider>>> {
+            self.0.get_provider(name).await
+        }
+
+        async fn remove_provider(&self, name: &str) -> Result<()> {
+            self.0.remove_provider(name).await
+        }
+
+        async fn get_tools(&self) -> Result<Vec<Tool>> {
+            self.0.get_tools().await
+        }
+
+        async fn get_tools_by_provider(&self, provider_name: &str) -> Result<Vec<Tool>> {
+            self.0.get_tools_by_provider(provider_name).await
         }
     }
 
@@ -187,13 +434,13 @@ mod tests {
     #[tokio::test]
     async fn returns_top_scoring_tools_with_limit() {
         let repo = setup_repo(vec![
-            make_tool(
+            test_tool(
                 "p1.weather_primary",
                 "Weather forecast endpoint",
                 &["weather"],
             ),
-            make_tool("p1.weather_backup", "Weather data service", &["climate"]),
-            make_tool("p1.finance", "Stock price lookup", &["stocks"]),
+            test_tool("p1.weather_backup", "Weather data service", &["climate"]),
+            test_tool("p1.finance", "Stock price lookup", &["stocks"]),
         ])
         .await;
 
@@ -208,9 +455,9 @@ mod tests {
     #[tokio::test]
     async fn falls_back_when_no_positive_scores() {
         let repo = setup_repo(vec![
-            make_tool("p1.alpha", "No overlap here", &["alpha"]),
-            make_tool("p1.beta", "Still nothing useful", &["beta"]),
-            make_tool("p1.gamma", "More unrelated content", &["gamma"]),
+            test_tool("p1.alpha", "No overlap here", &["alpha"]),
+            test_tool("p1.beta", "Still nothing useful", &["beta"]),
+            test_tool("p1.gamma", "More unrelated content", &["gamma"]),
         ])
         .await;
 
@@ -225,9 +472,9 @@ mod tests {
     #[tokio::test]
     async fn ties_are_sorted_by_name_within_limit() {
         let repo = setup_repo(vec![
-            make_tool("p1.alpha", "Math helper", &["math"]),
-            make_tool("p1.beta", "Math helper", &["math"]),
-            make_tool("p1.gamma", "Math helper", &["math"]),
+            test_tool("p1.alpha", "Math helper", &["math"]),
+            test_tool("p1.beta", "Math helper", &["math"]),
+            test_tool("p1.gamma", "Math helper", &["math"]),
         ])
         .await;
 
@@ -238,4 +485,270 @@ mod tests {
         assert_eq!(results[0].name, "p1.alpha");
         assert_eq!(results[1].name, "p1.beta");
     }
+
+    #[tokio::test]
+    async fn search_options_filter_before_scoring() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        for (provider_name, tools) in [
+            (
+                "files",
+                vec![
+                    test_tool("files.read", "Read a file", &["read-only", "fs"]),
+                    test_tool("files.write", "Write a file", &["fs"]),
+                ],
+            ),
+            (
+                "db",
+                vec![test_tool("db.read", "Read a row", &["Read-Only", "sql"])],
+            ),
+        ] {
+            let provider = Arc::new(BaseProvider {
+                name: provider_name.to_string(),
+                provider_type: ProviderType::Http,
+                auth: None,
+                allowed_communication_protocols: None,
+            });
+            repo.save_provider_with_tools(provider, tools)
+                .await
+                .unwrap();
+        }
+        let strategy = TagSearchStrategy::new(repo, 1.0);
+        let names = |results: Vec<ScoredTool>| -> Vec<String> {
+            results.into_iter().map(|st| st.tool.name).collect()
+        };
+
+        let opts = SearchOptions {
+            providers: Some(vec!["files".to_string()]),
+            ..Default::default()
+        };
+        let results = strategy.search_tools_with("read file", opts).await.unwrap();
+        assert_eq!(results[0].tool.name, "files.read");
+        assert!(results[0].score > 0.0);
+        assert_eq!(names(results), vec!["files.read", "files.write"]);
+
+        let opts = SearchOptions {
+            tags_any: Some(vec!["read-only".to_string()]),
+            ..Default::default()
+        };
+        let results = strategy.search_tools_with("read", opts).await.unwrap();
+        assert_eq!(names(results), vec!["db.read", "files.read"]);
+
+        let opts = SearchOptions {
+            tags_all: Some(vec!["read-only".to_string(), "fs".to_string()]),
+            ..Default::default()
+        };
+        let results = strategy.search_tools_with("read", opts).await.unwrap();
+        assert_eq!(names(results), vec!["files.read"]);
+
+        let opts = SearchOptions {
+            limit: 1,
+            offset: 1,
+            tags_any: Some(vec!["read-only".to_string()]),
+            ..Default::default()
+        };
+        let results = strategy.search_tools_with("read", opts).await.unwrap();
+        assert_eq!(names(results), vec!["files.read"]);
+    }
+
+    #[tokio::test]
+    async fn ordering_is_stable_across_searches_and_re_registration() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        let register = |provider_name: &'static str, tool_names: Vec<&'static str>| {
+            let repo = repo.clone();
+            async move {
+                let provider = Arc::new(BaseProvider {
+                    name: provider_name.to_string(),
+                    provider_type: ProviderType::Http,
+                    auth: None,
+                    allowed_communication_protocols: None,
+                });
+                let tools = tool_names
+                    .into_iter()
+                    .map(|name| test_tool(name, "Math helper", &["math"]))
+                    .collect();
+                repo.save_provider_with_tools(provider, tools)
+                    .await
+                    .unwrap();
+            }
+        };
+        // "a-b.x" sorts before "a.y" as a plain string, but provider "a" precedes "a-b".
+        register("a-b", vec!["a-b.x"]).await;
+        register("a", vec!["a.z", "a.y"]).await;
+
+        let strategy = TagSearchStrategy::new(repo.clone(), 1.0);
+        let expected = vec!["a.y", "a.z", "a-b.x"];
+        let names =
+            |tools: Vec<Tool>| -> Vec<String> { tools.into_iter().map(|t| t.name).collect() };
+
+        for _ in 0..5 {
+            assert_eq!(
+                names(strategy.search_tools("math", 0).await.unwrap()),
+                expected
+            );
+            assert_eq!(names(strategy.search_tools("", 0).await.unwrap()), expected);
+        }
+
+        let scored = strategy
+            .search_tools_with("math", SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(scored
+            .iter()
+            .all(|st| st.score == scored[0].score && st.score > 0.0));
+        let empty = strategy
+            .search_tools_with("  ", SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(empty.iter().all(|st| st.score == 0.0));
+
+        repo.remove_provider("a").await.unwrap();
+        register("a", vec!["a.y", "a.z"]).await;
+        assert_eq!(
+            names(strategy.search_tools("math", 0).await.unwrap()),
+            expected
+        );
+        assert_eq!(
+            names(strategy.search_tools("", 2).await.unwrap()),
+            vec!["a.y", "a.z"]
+        );
+    }
+
+    fn text_provider(name: &str) -> Arc<BaseProvider> {
+        Arc::new(BaseProvider {
+            name: name.to_string(),
+            provider_type: ProviderType::Text,
+            auth: None,
+            allowed_communication_protocols: None,
+        })
+    }
+
+    fn names(tools: Vec<Tool>) -> Vec<String> {
+        tools.into_iter().map(|t| t.name).collect()
+    }
+
+    #[tokio::test]
+    async fn index_follows_register_refresh_deregister() {
+        let repo = Arc::new(InMemoryToolRepository::new());
+        let strategy = TagSearchStrategy::new(repo.clone(), 1.0);
+        assert!(strategy
+            .search_tools("weather", 0)
+            .await
+            .unwrap()
+            .is_empty());
+
+        repo.save_provider_with_tools(
+            text_provider("wx"),
+            vec![test_tool("wx.today", "Weather today", &["weather"])],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            names(strategy.search_tools("weather", 0).await.unwrap()),
+            vec!["wx.today"]
+        );
+
+        // Refreshing the provider replaces its tools and their terms.
+        repo.save_provider_with_tools(
+            text_provider("wx"),
+            vec![test_tool("wx.radar", "Precipitation radar", &["radar"])],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            names(strategy.search_tools("radar weather", 0).await.unwrap()),
+            vec!["wx.radar"]
+        );
+
+        repo.remove_provider("wx").await.unwrap();
+        assert!(strategy.search_tools("radar", 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_maintains_index_without_repository_events() {
+        let repo = Arc::new(QuietRepository(InMemoryToolRepository::new()));
+        assert!(repo.subscribe().is_none());
+        let strategy = Arc::new(TagSearchStrategy::new(repo.clone(), 1.0));
+        let client = UtcpClient::new(UtcpClientConfig::default(), repo, strategy.clone())
+            .await
+            .unwrap();
+        assert!(client.search_tools("weather", 0).await.unwrap().is_empty());
+
+        client
+            .register_tool_provider_with_tools(
+                text_provider("wx"),
+                vec![test_tool("today", "Weather today", &["weather"])],
+            )
+            .await
+            .unwrap();
+        client
+            .register_tool_provider_with_tools(
+                text_provider("fx"),
+                vec![test_tool("rates", "Exchange rates", &["currency"])],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            names(client.search_tools("weather", 0).await.unwrap()),
+            vec!["wx.today"]
+        );
+
+        client.deregister_tool_provider("wx").await.unwrap();
+        assert_eq!(
+            names(client.search_tools("weather", 0).await.unwrap()),
+            vec!["fx.rates"]
+        );
+        assert_eq!(
+            names(client.search_tools("currency", 0).await.unwrap()),
+            vec!["fx.rates"]
+        );
+    }
+
+    #[tokio::test]
+    async fn reindexes_repositories_without_events_on_every_search() {
+        let repo = Arc::new(QuietRepository(InMemoryToolRepository::new()));
+        let strategy = TagSearchStrategy::new(repo.clone(), 1.0);
+        assert!(strategy.search_tools("weather", 0).await.unwrap().is_empty());
+
+        // Written straight to the repository, so only a re-read can pick it up
+        repo.save_provider_with_tools(
+            text_provider("wx"),
+            vec![test_tool("wx.today", "Weather today", &["weather"])],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            names(strategy.search_tools("weather", 0).await.unwrap()),
+            vec!["wx.today"]
+        );
+
+        repo.remove_provider("wx").await.unwrap();
+        assert!(strategy.search_tools("weather", 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn normalizer_matches_synonyms_and_plurals() {
+        let repo = setup_repo(vec![
+            test_tool("test.charge", "Charge a card", &["payments"]),
+            test_tool("test.ship", "Ship an order", &["shipping"]),
+        ])
+        .await;
+
+        // Without the synonym nothing matches, so only the zero-score fallback comes back.
+        let plain = TagSearchStrategy::new(repo.clone(), 1.0);
+        let scored = plain
+            .search_tools_with("billing", SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(scored.iter().all(|st| st.score == 0.0));
+
+        let synonyms = HashMap::from([("billing".to_string(), vec!["payment".to_string()])]);
+        let normalized = TagSearchStrategy::new(repo.clone(), 1.0)
+            .with_normalizer(TagNormalizer::with_synonyms(synonyms));
+        let results = normalized.search_tools("billing", 0).await.unwrap();
+        assert_eq!(names(results), vec!["test.charge"]);
+
+        // Plural query terms fold onto the same canonical tag.
+        let results = normalized.search_tools("payments", 0).await.unwrap();
+        assert_eq!(names(results), vec!["test.charge"]);
+    }
 }
@@ -0,0 +1,208 @@
+use crate::config::UtcpClientConfig;
+use crate::errors::UtcpError;
+use crate::providers::text::TextProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::repository::ToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+fn write_manual(path: &Path, names: &[&str]) {
+    let tools: Vec<Value> = names
+        .iter()
+        .map(|name| {
+            json!({
+                "name": name,
+                "description": format!("{} tool", name),
+                "inputs": {"type": "object"},
+                "outputs": {"type": "object"},
+                "tags": []
+            })
+        })
+        .collect();
+    std::fs::write(path, json!({ "tools": tools }).to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn test_text_manual_glob_registers_good_files_and_reports_bad_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let manuals = dir.path().join("manuals");
+    std::fs::create_dir(&manuals).unwrap();
+    write_manual(&manuals.join("files.json"), &["read", "write"]);
+    write_manual(&manuals.join("net.json"), &["fetch", "read"]);
+    std::fs::write(manuals.join("broken.json"), "{ \"tools\": [").unwrap();
+    std::fs::write(dir.path().join("fetch.sh"), "echo '{\"fetched\": true}'\n").unwrap();
+
+    let mut provider =
+        TextProvider::new("catalog".to_string(), Some(dir.path().to_path_buf()), None);
+    provider.path = Some("manuals/*.json".to_string());
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    let err = client
+        .register_tool_provider(Arc::new(provider))
+        .await
+        .expect_err("broken manual reported");
+
+    match err.downcast_ref::<UtcpError>() {
+        Some(UtcpError::ManualErrors { failures, tools }) => {
+            let paths: Vec<_> = failures.iter().map(|f| f.path.as_str()).collect();
+            assert_eq!(paths.len(), 2);
+            assert!(paths[0].ends_with("broken.json"));
+            // `read` is defined by files.json first
+            assert!(paths[1].ends_with("net.json"));
+            assert!(failures[1].error.contains("'read'"));
+            assert_eq!(tools.len(), 3);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    // The tools of the good manuals are registered and callable
+    let mut names: Vec<String> = repo
+        .get_tools_by_provider("catalog")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["catalog.fetch", "catalog.read", "catalog.write"]
+    );
+    let result = client.call_tool("fetch", HashMap::new()).await.unwrap();
+    assert_eq!(result, json!({"fetched": true}));
+}
+
+#[tokio::test]
+async fn test_yaml_providers_file_registers_yaml_manual() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("manuals")).unwrap();
+    std::fs::write(
+        dir.path().join("manuals/greetings.yaml"),
+        r#"
+manual_version: 1.0.0
+utcp_version: 1.0.0
+tools:
+  - name: greet
+    description: Say hello
+    inputs:
+      type: object
+      properties:
+        who: { type: string }
+    outputs: { type: object }
+    tags: [greeting]
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("greet.sh"),
+        "echo '{\"hello\": \"yaml\"}'\n",
+    )
+    .unwrap();
+    let providers_path = dir.path().join("providers.yml");
+    std::fs::write(
+        &providers_path,
+        format!(
+            r#"
+manual_call_templates:
+  - name: greetings
+    call_template_type: text
+    base_path: {}
+    path: manuals/*.yaml
+"#,
+            dir.path().display()
+        ),
+    )
+    .unwrap();
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::new().with_providers_file(providers_path),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+
+    let tools = repo.get_tools_by_provider("greetings").await.unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "greetings.greet");
+    assert_eq!(tools[0].tags, vec!["greeting"]);
+    assert!(tools[0]
+        .inputs
+        .properties
+        .as_ref()
+        .unwrap()
+        .contains_key("who"));
+
+    let result = client
+        .call_tool("greetings.greet", HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(result, json!({"hello": "yaml"}));
+}
+
+#[tokio::test]
+async fn test_inline_text_manual_tools_are_called_through_their_templates() {
+    let provider: TextProvider = serde_json::from_value(json!({
+        "name": "inline",
+        "provider_type": "text",
+        "source": {
+            "inline": {
+                "tools": [{
+                    "name": "status",
+                    "description": "Report status",
+                    "inputs": { "type": "object" },
+                    "outputs": { "type": "object" },
+                    "tool_call_template": {
+                        "call_template_type": "cli",
+                        "command_name": "echo '{\"status\": \"green\"}'",
+                        "args_template": []
+                    }
+                }]
+            }
+        }
+    }))
+    .unwrap();
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    let tools = client
+        .register_tool_provider(Arc::new(provider))
+        .await
+        .unwrap();
+    assert_eq!(tools[0].name, "inline.status");
+
+    let result = client
+        .call_tool("inline.status", HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(result, json!({"status": "green"}));
+}
@@ -0,0 +1,121 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::text::TextProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::repository::ToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+fn write_manual(dir: &Path, names: &[&str]) {
+    let tools: Vec<Value> = names
+        .iter()
+        .map(|name| {
+            json!({
+                "name": name,
+                "description": format!("{} tool", name),
+                "inputs": {"type": "object"},
+                "outputs": {"type": "object"},
+                "tags": []
+            })
+        })
+        .collect();
+    std::fs::write(
+        dir.join("tools.json"),
+        json!({ "tools": tools }).to_string(),
+    )
+    .unwrap();
+}
+
+async fn tool_names(repo: &InMemoryToolRepository) -> Vec<String> {
+    let mut names: Vec<String> = repo
+        .get_tools_by_provider("manuals")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_watched_text_manual_reloads_on_change() {
+    let dir = tempfile::tempdir().unwrap();
+    write_manual(dir.path(), &["first"]);
+    std::fs::write(
+        dir.path().join("second.sh"),
+        "echo '{\"from\": \"second\"}'\n",
+    )
+    .unwrap();
+    let mut provider =
+        TextProvider::new("manuals".to_string(), Some(dir.path().to_path_buf()), None);
+    provider.watch = true;
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    let tools = client
+        .register_tool_provider(Arc::new(provider))
+        .await
+        .unwrap();
+    assert_eq!(tools.len(), 1);
+    assert!(client.call_tool("second", HashMap::new()).await.is_err());
+
+    // The new tool is picked up without registering the provider again
+    write_manual(dir.path(), &["first", "second"]);
+    let result = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(value) = client.call_tool("second", HashMap::new()).await {
+                break value;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("manual reloaded");
+    assert_eq!(result, json!({"from": "second"}));
+    assert_eq!(
+        tool_names(&repo).await,
+        vec!["manuals.first", "manuals.second"]
+    );
+
+    // A manual that no longer parses leaves the tools as they were
+    std::fs::write(dir.path().join("tools.json"), "{ not json").unwrap();
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    assert_eq!(
+        tool_names(&repo).await,
+        vec!["manuals.first", "manuals.second"]
+    );
+
+    // Removing a tool is picked up as well
+    write_manual(dir.path(), &["second"]);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while tool_names(&repo).await != vec!["manuals.second"] {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("tool removed");
+
+    client.deregister_tool_provider("manuals").await.unwrap();
+}
@@ -0,0 +1,65 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::text::TextProvider;
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::tools::{Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+fn tool(name: &str) -> Tool {
+    serde_json::from_value(json!({
+        "name": name,
+        "description": format!("{} tool", name),
+        "inputs": {"type": "object", "required": ["path"]},
+        "outputs": {"type": "object"},
+        "tags": []
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_list_and_get_tools_read_the_repository() {
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        Arc::new(InMemoryToolRepository::new()),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+    for (provider, tools) in [("files", vec!["write", "read"]), ("net", vec!["fetch"])] {
+        client
+            .register_tool_provider_with_tools(
+                Arc::new(TextProvider::new(provider.to_string(), None, None)),
+                tools.into_iter().map(tool).collect(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Listing does not depend on the search strategy
+    let names: Vec<String> = client
+        .list_tools()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names, vec!["files.read", "files.write", "net.fetch"]);
+
+    let qualified = client.get_tool("files.read").await.unwrap().unwrap();
+    assert_eq!(qualified.inputs.required, Some(vec!["path".to_string()]));
+    let bare = client.get_tool("fetch").await.unwrap().unwrap();
+    assert_eq!(bare.name, "net.fetch");
+    assert!(client.get_tool("files.delete").await.unwrap().is_none());
+}
@@ -0,0 +1,174 @@
+use crate::config::UtcpClientConfig;
+use crate::providers::base::{BaseProvider, ProviderType};
+use crate::providers::http::{HttpProvider, QueryEncoding};
+use crate::repository::in_memory::InMemoryToolRepository;
+use crate::repository::ToolRepository;
+use crate::tools::{test_tool, Tool, ToolSearchStrategy};
+use crate::{UtcpClient, UtcpClientInterface};
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{extract::Json, http::StatusCode, routing::post, Router};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+struct MockSearchStrategy;
+
+#[async_trait]
+impl ToolSearchStrategy for MockSearchStrategy {
+    async fn search_tools(&self, _query: &str, _limit: usize) -> Result<Vec<Tool>> {
+        Ok(vec![])
+    }
+}
+
+async fn spawn_mock_server() -> String {
+    async fn handler(Json(payload): Json<Value>) -> (StatusCode, Json<Value>) {
+        if payload
+            .get("fail")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+        {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "boom"})),
+            )
+        } else {
+            (StatusCode::OK, Json(json!({"echo": payload})))
+        }
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_call_tool_records_usage_stats() {
+    let url = spawn_mock_server().await;
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let client = UtcpClient::new(
+        UtcpClientConfig::default(),
+        repo.clone(),
+        Arc::new(MockSearchStrategy),
+    )
+    .await
+    .unwrap();
+
+    let provider = Arc::new(HttpProvider {
+        base: BaseProvider {
+            name: "mock".to_string(),
+            provider_type: ProviderType::Http,
+            auth: None,
+            allowed_communication_protocols: None,
+        },
+        http_method: "POST".to_string(),
+        url,
+        content_type: None,
+        headers: None,
+        body_field: None,
+        header_fields: None,
+        path_params: None,
+        param_styles: None,
+        query_style: QueryEncoding::Repeat,
+        openapi_filters: None,
+        max_retries: None,
+        max_retry_delay_ms: None,
+        retry_non_idempotent: false,
+        timeouts: None,
+        proxy: None,
+        max_upload_bytes: None,
+        upload_root: None,
+        max_inline_binary_bytes: None,
+        return_envelope: false,
+        compress_requests: false,
+        use_cookies: false,
+        redirect: None,
+        response_cache: None,
+    });
+    let tool = test_tool("echo", "Echo", &[]);
+    client
+        .register_tool_provider_with_tools(provider, vec![tool])
+        .await
+        .unwrap();
+
+    let mut ok_args = HashMap::new();
+    ok_args.insert("msg".to_string(), json!("hi"));
+    let mut fail_args = HashMap::new();
+    fail_args.insert("fail".to_string(), json!(true));
+
+    client
+        .call_tool("mock.echo", ok_args.clone())
+        .await
+        .unwrap();
+    assert!(client.call_tool("mock.echo", fail_args).await.is_err());
+    // Bare names must be recorded under the fully qualified tool name.
+    client.call_tool("echo", ok_args).await.unwrap();
+
+    let stats = repo.get_tool_stats("mock.echo").await.unwrap().unwrap();
+    assert_eq!(stats.call_count, 3);
+    assert_eq!(stats.error_count, 1);
+    assert!(stats.last_called_at.is_some());
+    let expected_size = serde_json::to_vec(&json!({"echo": {"msg": "hi"}}))
+        .unwrap()
+        .len() as i64;
+    assert_eq!(stats.average_response_size, Some(expected_size));
+
+    let tools = repo.get_tools_by_provider("mock").await.unwrap();
+    assert_eq!(tools[0].average_response_size, Some(expected_size));
+    assert_eq!(repo.list_tool_stats().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_abandoned_streams_record_cancelled() {
+    use crate::transports::stream::{StreamResult, VecStreamResult};
+    use crate::RecordingStream;
+    use std::time::Instant;
+
+    let repo = Arc::new(InMemoryToolRepository::new());
+    let stream = |tool_name: &str| RecordingStream {
+        inner: Box::new(VecStreamResult::new(vec![json!(1), json!(2)], None)),
+        repo: repo.clone(),
+        tool_name: tool_name.to_string(),
+        started: Instant::now(),
+        response_size: 0,
+        recorded: false,
+    };
+
+    let mut finished = stream("mock.finished");
+    while finished.next().await.unwrap().is_some() {}
+    finished.close().await.unwrap();
+
+    let mut closed = stream("mock.closed");
+    closed.next().await.unwrap();
+    closed.close().await.unwrap();
+
+    let mut dropped = stream("mock.dropped");
+    dropped.next().await.unwrap();
+    drop(dropped);
+    // The drop records from a spawned task
+    for _ in 0..100 {
+        if repo.get_tool_stats("mock.dropped").await.unwrap().is_some() {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+
+    let finished = repo.get_tool_stats("mock.finished").await.unwrap().unwrap();
+    assert_eq!((finished.call_count, finished.cancelled_count), (1, 0));
+    assert!(finished.average_response_size.is_some());
+    for name in ["mock.closed", "mock.dropped"] {
+        let stats = repo.get_tool_stats(name).await.unwrap().unwrap();
+        assert_eq!(stats.call_count, 1, "{}", name);
+        assert_eq!(stats.cancelled_count, 1, "{}", name);
+        assert_eq!(stats.average_response_size, None, "{}", name);
+    }
+}
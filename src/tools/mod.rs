@@ -3,8 +3,14 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod validation;
+
 /// Minimal JSON Schema-like description for tool inputs/outputs.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Fields may be added in later releases; outside this crate, build one with
+/// [`ToolInputOutputSchema::new`] or `Default` and set the fields you need.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ToolInputOutputSchema {
     #[serde(rename = "type")]
     pub type_: String,
@@ -26,6 +32,39 @@ pub struct ToolInputOutputSchema {
     pub maximum: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
+    /// Alternative shapes from `oneOf`/`anyOf`; `properties` holds their union.
+    #[serde(
+        rename = "oneOf",
+        alias = "anyOf",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub one_of: Option<Vec<serde_json::Value>>,
+}
+
+impl ToolInputOutputSchema {
+    /// A schema of the given JSON type with every other field unset.
+    pub fn new(type_: impl Into<String>) -> Self {
+        Self {
+            type_: type_.into(),
+            properties: None,
+            required: None,
+            description: None,
+            title: None,
+            items: None,
+            enum_: None,
+            minimum: None,
+            maximum: None,
+            format: None,
+            one_of: None,
+        }
+    }
+}
+
+impl Default for ToolInputOutputSchema {
+    /// An `object` schema with no constraints.
+    fn default() -> Self {
+        Self::new("object")
+    }
 }
 
 /// Canonical tool definition used by UTCP transports and repositories.
@@ -42,9 +81,139 @@ pub struct Tool {
     pub provider: Option<serde_json::Value>,
 }
 
+impl Tool {
+    /// Name of the provider that owns this tool, taken from the `provider.tool` name.
+    pub fn provider_name(&self) -> Option<&str> {
+        self.name.split_once('.').map(|(provider, _)| provider)
+    }
+}
+
+/// A tool with unconstrained `object` inputs and outputs, for tests.
+#[cfg(test)]
+pub(crate) fn test_tool(name: &str, description: &str, tags: &[&str]) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: description.to_string(),
+        inputs: ToolInputOutputSchema::default(),
+        outputs: ToolInputOutputSchema::default(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        average_response_size: None,
+        provider: None,
+    }
+}
+
+/// Filtering and pagination options for [`ToolSearchStrategy::search_tools_with`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Maximum number of results; `0` means unlimited.
+    #[serde(default)]
+    pub limit: usize,
+    /// Number of ranked results to skip before collecting.
+    #[serde(default)]
+    pub offset: usize,
+    /// Only return tools owned by one of these providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub providers: Option<Vec<String>>,
+    /// Only return tools carrying at least one of these tags (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags_any: Option<Vec<String>>,
+    /// Only return tools carrying all of these tags (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags_all: Option<Vec<String>>,
+}
+
+impl SearchOptions {
+    /// Whether the tool passes the provider and tag filters.
+    pub fn matches(&self, tool: &Tool) -> bool {
+        if let Some(providers) = &self.providers {
+            match tool.provider_name() {
+                Some(name) if providers.iter().any(|p| p == name) => {}
+                _ => return false,
+            }
+        }
+        let has_tag = |wanted: &String| tool.tags.iter().any(|t| t.eq_ignore_ascii_case(wanted));
+        if let Some(tags) = &self.tags_any {
+            if !tags.iter().any(has_tag) {
+                return false;
+            }
+        }
+        if let Some(tags) = &self.tags_all {
+            if !tags.iter().all(has_tag) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Number of ranked results needed to serve this page; `0` means unlimited.
+    pub fn window(&self) -> usize {
+        if self.limit == 0 {
+            0
+        } else {
+            self.offset + self.limit
+        }
+    }
+
+    /// Skip `offset` results and keep at most `limit` of the rest.
+    pub fn paginate<T>(&self, ranked: Vec<T>) -> Vec<T> {
+        let page = ranked.into_iter().skip(self.offset);
+        if self.limit == 0 {
+            page.collect()
+        } else {
+            page.take(self.limit).collect()
+        }
+    }
+
+    /// Filter and paginate an already ranked list, scoring results by position (`1 / rank`).
+    pub fn apply(&self, ranked: Vec<Tool>) -> Vec<ScoredTool> {
+        let scored = ranked
+            .into_iter()
+            .filter(|tool| self.matches(tool))
+            .enumerate()
+            .map(|(rank, tool)| ScoredTool {
+                tool,
+                score: 1.0 / (rank + 1) as f64,
+            })
+            .collect();
+        self.paginate(scored)
+    }
+
+    fn has_filters(&self) -> bool {
+        self.providers.is_some() || self.tags_any.is_some() || self.tags_all.is_some()
+    }
+}
+
+/// A search result together with the score the strategy assigned it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredTool {
+    pub tool: Tool,
+    pub score: f64,
+}
+
 /// Strategy abstraction used to search tools by query string.
 #[async_trait]
 pub trait ToolSearchStrategy: Send + Sync {
     /// Return tools matching the query string, limited to `limit` results when non-zero.
     async fn search_tools(&self, query: &str, limit: usize) -> Result<Vec<Tool>>;
+
+    /// Search with provider/tag filters and pagination, returning scored results.
+    ///
+    /// The default implementation ranks with [`search_tools`](Self::search_tools) and then
+    /// filters, so strategies that can filter before scoring should override it.
+    async fn search_tools_with(&self, query: &str, opts: SearchOptions) -> Result<Vec<ScoredTool>> {
+        let window = if opts.has_filters() { 0 } else { opts.window() };
+        let ranked = self.search_tools(query, window).await?;
+        Ok(opts.apply(ranked))
+    }
+
+    /// Notify the strategy that `provider_name` was registered with `tools`, letting
+    /// index-backed strategies update incrementally. The default does nothing.
+    async fn index_provider(&self, _provider_name: &str, _tools: &[Tool]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Notify the strategy that `provider_name` was deregistered. The default does nothing.
+    async fn remove_provider(&self, _provider_name: &str) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,269 @@
+//! Checking JSON values against tool schemas.
+use serde_json::Value;
+
+use super::ToolInputOutputSchema;
+use crate::errors::SchemaMismatch;
+
+impl ToolInputOutputSchema {
+    /// Every place where `value` does not match this schema; empty when it matches.
+    ///
+    /// Checks `type` (a name or a list of names, with `nullable`), `enum`, `minimum` and
+    /// `maximum`, `required` and `properties` of objects, `items` of arrays, and `oneOf` /
+    /// `anyOf`, where one alternative has to match. Other keywords and undeclared properties
+    /// are not checked.
+    pub fn mismatches(&self, value: &Value) -> Vec<SchemaMismatch> {
+        let mut found = Vec::new();
+        if let Ok(schema) = serde_json::to_value(self) {
+            check(&schema, value, "$", &mut found);
+        }
+        found
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str, found: &mut Vec<SchemaMismatch>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let mut mismatch = |message: String| {
+        found.push(SchemaMismatch {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    let alternatives = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array);
+    if let Some(alternatives) = alternatives.filter(|a| !a.is_empty()) {
+        let matches_one = alternatives.iter().any(|alternative| {
+            let mut inner = Vec::new();
+            check(alternative, value, path, &mut inner);
+            inner.is_empty()
+        });
+        if !matches_one {
+            mismatch(format!(
+                "matches none of the {} alternatives",
+                alternatives.len()
+            ));
+            return;
+        }
+    }
+
+    let nullable = schema.get("nullable").and_then(Value::as_bool) == Some(true);
+    if value.is_null() && nullable {
+        return;
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(type_)) => vec![type_.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let types: Vec<&str> = types.into_iter().filter(|t| !t.is_empty()).collect();
+    if !types.is_empty() && !types.iter().any(|type_| has_type(value, type_)) {
+        mismatch(format!(
+            "expected {}, got {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            mismatch(format!("expected one of {}", Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                mismatch(format!("{} is less than the minimum of {}", value, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                mismatch(format!(
+                    "{} is greater than the maximum of {}",
+                    value, maximum
+                ));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(fields) => {
+            let required = schema.get("required").and_then(Value::as_array);
+            for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    found.push(SchemaMismatch {
+                        path: field_path(path, name),
+                        message: "required field is missing".to_string(),
+                    });
+                }
+            }
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, property) in properties {
+                    if let Some(field) = fields.get(name) {
+                        check(property, field, &field_path(path, name), found);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{}[{}]", path, i), found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, type_: &str) -> bool {
+    match type_ {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // Unknown type names, such as "any", accept everything
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// `path.name`, or `path["name"]` when the name is not an identifier.
+fn field_path(path: &str, name: &str) -> String {
+    let mut chars = name.chars();
+    let identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if identifier {
+        format!("{}.{}", path, name)
+    } else {
+        format!("{}[{}]", path, Value::from(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> ToolInputOutputSchema {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn paths(schema: &ToolInputOutputSchema, value: Value) -> Vec<String> {
+        schema
+            .mismatches(&value)
+            .into_iter()
+            .map(|m| format!("{}: {}", m.path, m.message))
+            .collect()
+    }
+
+    #[test]
+    fn matching_values_have_no_mismatches() {
+        let report = schema(json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "score": {"type": "integer", "minimum": 0, "maximum": 10},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "status": {"type": "string", "enum": ["draft", "final"]},
+                "note": {"type": "string", "nullable": true}
+            },
+            "required": ["title", "score"]
+        }));
+        let value = json!({
+            "title": "Q3",
+            "score": 7.0,
+            "tags": ["a", "b"],
+            "status": "final",
+            "note": null,
+            "extra": true
+        });
+        assert!(report.mismatches(&value).is_empty());
+    }
+
+    #[test]
+    fn mismatches_name_their_paths() {
+        let report = schema(json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "score": {"type": "integer", "maximum": 10},
+                "items": {
+                    "type": "array",
+                    "items": {"type": "object", "properties": {"id": {"type": "integer"}}, "required": ["id"]}
+                },
+                "status": {"enum": ["draft", "final"]}
+            },
+            "required": ["title", "due date"]
+        }));
+        let value = json!({
+            "score": 12,
+            "items": [{"id": 1}, {"id": "two"}, {}],
+            "status": "late"
+        });
+        assert_eq!(
+            paths(&report, value),
+            vec![
+                "$.title: required field is missing",
+                "$[\"due date\"]: required field is missing",
+                "$.items[1].id: expected integer, got string",
+                "$.items[2].id: required field is missing",
+                "$.score: 12 is greater than the maximum of 10",
+                "$.status: expected one of [\"draft\",\"final\"]",
+            ]
+        );
+
+        assert_eq!(
+            paths(&report, json!([1])),
+            vec!["$: expected object, got array"]
+        );
+    }
+
+    #[test]
+    fn alternatives_need_one_match() {
+        let report = schema(json!({
+            "type": "object",
+            "oneOf": [
+                {"type": "object", "properties": {"kind": {"enum": ["card"]}}, "required": ["number"]},
+                {"type": "object", "properties": {"kind": {"enum": ["bank"]}}, "required": ["iban"]}
+            ]
+        }));
+        assert!(report
+            .mismatches(&json!({"kind": "bank", "iban": "DE00"}))
+            .is_empty());
+        assert_eq!(
+            paths(&report, json!({"kind": "bank", "number": "4242"})),
+            vec!["$: matches none of the 2 alternatives"]
+        );
+
+        let either = schema(json!({
+            "type": "object",
+            "properties": {"note": {"type": ["string", "null"]}}
+        }));
+        assert!(either.mismatches(&json!({"note": null})).is_empty());
+        assert_eq!(
+            paths(&either, json!({"note": 3})),
+            vec!["$.note: expected string or null, got number"]
+        );
+    }
+}
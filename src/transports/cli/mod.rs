@@ -1,31 +1,264 @@
 // CLI Transport - executes command-line tools
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::time::Duration;
-use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task::JoinHandle;
 
+use crate::errors::{CommandOutput, UtcpError};
 use crate::providers::base::Provider;
-use crate::providers::cli::CliProvider;
+use crate::providers::cli::{CliMode, CliProvider, LeftoverArgs};
 use crate::tools::Tool;
+use crate::transports::mcp::restart_backoff;
 use crate::transports::{stream::StreamResult, ClientTransport};
 
+mod persistent;
+
+use persistent::PersistentProcess;
+
+/// How long a command may run when the provider sets no `timeout_ms`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to keep reading a killed command's output, in case a child outlived it.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+
+static ENV_REFERENCE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// Replace `${VAR}` references with the client's environment; unset variables become empty.
+fn expand_env(value: &str) -> String {
+    ENV_REFERENCE
+        .replace_all(value, |caps: &regex::Captures| {
+            std::env::var(&caps[1]).unwrap_or_default()
+        })
+        .into_owned()
+}
+
+static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{|\}\}|\{([A-Za-z_][A-Za-z0-9_.-]*)\}").unwrap());
+
+/// Fill an args template from the call arguments. Each element becomes exactly one argument,
+/// with no shell in between. Returns the arguments and the names of the call arguments used.
+fn render_args_template(
+    template: &[String],
+    args: &HashMap<String, Value>,
+) -> Result<(Vec<String>, HashSet<String>)> {
+    let mut used = HashSet::new();
+    let mut rendered = Vec::with_capacity(template.len());
+    for element in template {
+        let mut arg = String::with_capacity(element.len());
+        let mut last = 0;
+        for caps in PLACEHOLDER.captures_iter(element) {
+            let whole = caps.get(0).expect("match");
+            arg.push_str(&element[last..whole.start()]);
+            last = whole.end();
+            let Some(name) = caps.get(1) else {
+                // `{{` or `}}`
+                arg.push_str(&whole.as_str()[..1]);
+                continue;
+            };
+            let name = name.as_str();
+            let value = args.get(name).ok_or_else(|| {
+                UtcpError::ToolCall(format!(
+                    "args_template needs argument '{}', which the call did not pass",
+                    name
+                ))
+            })?;
+            match value {
+                Value::String(text) => arg.push_str(text),
+                Value::Null => {}
+                other => arg.push_str(&other.to_string()),
+            }
+            used.insert(name.to_string());
+        }
+        arg.push_str(&element[last..]);
+        rendered.push(arg);
+    }
+    Ok((rendered, used))
+}
+
+/// Output read from one of a command's pipes as it is written.
+struct Captured {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    reader: JoinHandle<()>,
+}
+
+impl Captured {
+    fn new(pipe: Option<impl AsyncRead + Unpin + Send + 'static>) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&buffer);
+        let reader = tokio::spawn(async move {
+            let Some(mut pipe) = pipe else { return };
+            let mut chunk = [0u8; 8192];
+            while let Ok(len) = pipe.read(&mut chunk).await {
+                if len == 0 {
+                    break;
+                }
+                sink.lock().unwrap().extend_from_slice(&chunk[..len]);
+            }
+        });
+        Self { buffer, reader }
+    }
+
+    /// Everything written, once the pipe closes.
+    async fn finish(self) -> String {
+        let _ = self.reader.await;
+        Self::text(&self.buffer)
+    }
+
+    /// Whatever was written before the command was killed.
+    async fn partial(mut self) -> String {
+        if tokio::time::timeout(DRAIN_TIMEOUT, &mut self.reader)
+            .await
+            .is_err()
+        {
+            self.reader.abort();
+        }
+        Self::text(&self.buffer)
+    }
+
+    fn text(buffer: &Mutex<Vec<u8>>) -> String {
+        String::from_utf8_lossy(&buffer.lock().unwrap()).to_string()
+    }
+}
+
+/// A command that ran to completion.
+struct Finished {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
+    duration: Duration,
+}
+
+impl Finished {
+    /// The structured result, with stdout read as JSON when it parses.
+    fn into_output(self) -> CommandOutput {
+        let stdout = if self.stdout.trim().is_empty() {
+            Value::String(String::new())
+        } else {
+            serde_json::from_str(&self.stdout)
+                .unwrap_or_else(|_| Value::String(self.stdout.trim().replace("\r\n", "\n")))
+        };
+        CommandOutput {
+            exit_code: self.exit_code,
+            stdout,
+            stderr: self.stderr,
+            duration_ms: self.duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Split a command line on whitespace outside double quotes, keeping backslashes as they are
+/// so Windows paths survive. Returns `None` for an unbalanced quote.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn split_windows(command_line: &str) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    parts.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if in_word {
+        parts.push(current);
+    }
+    Some(parts)
+}
+
+/// Run `command_line` with `args` appended, quoted for the platform shell.
+#[cfg(not(windows))]
+fn shell_command(command_line: &str, args: &[String]) -> Result<Command> {
+    let mut line = command_line.to_string();
+    for arg in args {
+        let quoted = shlex::try_quote(arg)
+            .map_err(|e| anyhow!("Cannot pass argument {:?} to the shell: {}", arg, e))?;
+        line.push(' ');
+        line.push_str(&quoted);
+    }
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(line);
+    Ok(cmd)
+}
+
+/// Run `command_line` with `args` appended, quoted for the platform shell.
+#[cfg(windows)]
+fn shell_command(command_line: &str, args: &[String]) -> Result<Command> {
+    let mut line = command_line.to_string();
+    for arg in args {
+        line.push_str(" \"");
+        line.push_str(&arg.replace('"', "\"\""));
+        line.push('"');
+    }
+    let mut cmd = Command::new("cmd");
+    // cmd.exe does its own parsing, so the line goes through untouched
+    cmd.arg("/C").raw_arg(line);
+    Ok(cmd)
+}
+
+/// Kill a command along with any processes it started.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The command leads its own process group, so this reaches its children too
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    let _ = child.start_kill();
+}
+
 /// Transport that shells out to a CLI binary which exposes UTCP-compatible commands.
-pub struct CliTransport;
+pub struct CliTransport {
+    // Map of provider name to its running process in persistent mode
+    processes: AsyncMutex<HashMap<String, Arc<AsyncMutex<PersistentProcess>>>>,
+    // Map of provider name to the times its persistent process was restarted
+    restarts: AsyncMutex<HashMap<String, u32>>,
+}
 
 impl CliTransport {
     /// Create a CLI transport.
     pub fn new() -> Self {
-        Self
+        Self {
+            processes: AsyncMutex::new(HashMap::new()),
+            restarts: AsyncMutex::new(HashMap::new()),
+        }
     }
 
+    /// Split `command_name` into program and arguments, so quoted paths may hold spaces.
+    /// Quoting follows a POSIX shell, or on Windows plain double quotes with backslashes
+    /// kept as path separators.
     fn parse_command(&self, command_name: &str) -> Result<(String, Vec<String>)> {
-        let parts: Vec<String> = command_name
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect();
+        #[cfg(windows)]
+        let parts = split_windows(command_name);
+        #[cfg(not(windows))]
+        let parts = shlex::split(command_name);
+        let parts =
+            parts.ok_or_else(|| anyhow!("Unbalanced quotes in command name: {}", command_name))?;
 
         if parts.is_empty() {
             return Err(anyhow!("Empty command name"));
@@ -34,29 +267,45 @@ impl CliTransport {
         Ok((parts[0].clone(), parts[1..].to_vec()))
     }
 
-    async fn execute_command(
-        &self,
-        cmd_path: &str,
-        args: &[String],
-        env_vars: &Option<HashMap<String, String>>,
-        working_dir: &Option<String>,
-        stdin_input: Option<&str>,
-    ) -> Result<(String, String, i32)> {
-        let mut cmd = Command::new(cmd_path);
-        cmd.args(args);
+    /// The provider's command followed by `args`: spawned directly, or with `use_shell`
+    /// through the platform shell, in the provider's environment and working directory.
+    fn command(&self, prov: &CliProvider, args: &[String]) -> Result<Command> {
+        let mut cmd = if prov.use_shell {
+            shell_command(&prov.command_name, args)?
+        } else {
+            let (program, base_args) = self.parse_command(&prov.command_name)?;
+            let mut cmd = Command::new(program);
+            cmd.args(base_args).args(args);
+            cmd
+        };
 
         // Set environment variables
-        if let Some(env) = env_vars {
+        if let Some(env) = &prov.env_vars {
             for (k, v) in env {
-                cmd.env(k, v);
+                cmd.env(k, expand_env(v));
             }
         }
 
         // Set working directory
-        if let Some(dir) = working_dir {
+        if let Some(dir) = &prov.working_dir {
             cmd.current_dir(dir);
         }
 
+        // Run in a process group of its own so a timeout can kill everything it started
+        #[cfg(unix)]
+        cmd.process_group(0);
+        cmd.kill_on_drop(true);
+        Ok(cmd)
+    }
+
+    async fn execute_command(
+        &self,
+        prov: &CliProvider,
+        args: &[String],
+        stdin_input: Option<&str>,
+    ) -> Result<Finished> {
+        let mut cmd = self.command(prov, args)?;
+
         // Configure stdin/stdout/stderr
         cmd.stdin(if stdin_input.is_some() {
             std::process::Stdio::piped()
@@ -66,25 +315,137 @@ impl CliTransport {
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
 
+        let started = Instant::now();
         let mut child = cmd.spawn()?;
+        let stdout = Captured::new(child.stdout.take());
+        let stderr = Captured::new(child.stderr.take());
 
-        // Write stdin if provided
-        if let Some(input) = stdin_input {
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(input.as_bytes()).await?;
+        // Write stdin if provided, then wait for completion with timeout
+        let timeout = prov
+            .timeout_ms
+            .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+        let stdin = child.stdin.take();
+        let finished = tokio::time::timeout(timeout, async {
+            if let (Some(input), Some(mut stdin)) = (stdin_input, stdin) {
+                // A command that exits without reading its input closes the pipe early
+                match stdin.write_all(input.as_bytes()).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => return Err(err),
+                    _ => {}
+                }
                 drop(stdin); // Close stdin
             }
+            child.wait().await
+        })
+        .await;
+
+        let status = match finished {
+            Ok(status) => status?,
+            Err(_) => {
+                kill(&mut child);
+                let _ = child.wait().await;
+                let stdout = stdout.partial().await;
+                let stderr = stderr.partial().await;
+                return Err(UtcpError::Timeout(format!(
+                    "Command '{}' of CLI provider '{}' timed out after {}ms and was killed\nstdout: {}\nstderr: {}",
+                    prov.command_name,
+                    prov.base.name,
+                    timeout.as_millis(),
+                    stdout.trim(),
+                    stderr.trim()
+                ))
+                .into());
+            }
+        };
+
+        Ok(Finished {
+            stdout: stdout.finish().await,
+            stderr: stderr.finish().await,
+            exit_code: status.code().unwrap_or(1),
+            duration: started.elapsed(),
+        })
+    }
+
+    /// The provider's persistent process, started when it has none or its last one died.
+    async fn persistent_process(
+        &self,
+        prov: &CliProvider,
+    ) -> Result<Arc<AsyncMutex<PersistentProcess>>> {
+        // Waiting for a busy process must not hold up other providers' calls
+        let existing = self.processes.lock().await.get(&prov.base.name).cloned();
+        if let Some(process) = &existing {
+            let mut current = process.lock().await;
+            if current.is_ready() {
+                return Ok(Arc::clone(process));
+            }
+            // Still running but left with an unread answer by a cancelled call
+            if current.is_alive() {
+                current.kill().await;
+            }
         }
 
-        // Wait for completion with timeout
-        let output =
-            tokio::time::timeout(Duration::from_secs(30), child.wait_with_output()).await??;
+        let mut processes = self.processes.lock().await;
+        if let Some(current) = processes.get(&prov.base.name) {
+            let replaced = !matches!(&existing, Some(old) if Arc::ptr_eq(current, old));
+            if replaced {
+                // Another call started a new process in the meantime
+                return Ok(Arc::clone(current));
+            }
+        }
+        let process = PersistentProcess::spawn(&prov.base.name, self.command(prov, &[])?)?;
+        let process = Arc::new(AsyncMutex::new(process));
+        processes.insert(prov.base.name.clone(), Arc::clone(&process));
+        Ok(process)
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let exit_code = output.status.code().unwrap_or(1);
+    /// Send one request line to the provider's persistent process. A request lost with its
+    /// process is retried once on a new process, while the provider's `max_restarts` allows.
+    async fn persistent_request(&self, prov: &CliProvider, request: Value) -> Result<Value> {
+        match self.send_persistent(prov, &request).await {
+            Err(err) if self.restart_process(prov, &err).await => {
+                tracing::warn!(provider = %prov.base.name, error = %err, "Restarted CLI process");
+                self.send_persistent(prov, &request).await
+            }
+            result => result,
+        }
+    }
 
-        Ok((stdout, stderr, exit_code))
+    async fn send_persistent(&self, prov: &CliProvider, request: &Value) -> Result<Value> {
+        let timeout = prov
+            .timeout_ms
+            .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+        let process = self.persistent_process(prov).await?;
+        let mut process = process.lock().await;
+        process.request(request, timeout).await
+    }
+
+    /// Whether the provider's process died, other than by being killed for a timeout, and may
+    /// be restarted. The restart is counted against `max_restarts` and its backoff waited out;
+    /// the next request then starts the new process.
+    async fn restart_process(&self, prov: &CliProvider, err: &anyhow::Error) -> bool {
+        let max_restarts = prov.max_restarts.unwrap_or(0);
+        if max_restarts == 0 || matches!(err.downcast_ref(), Some(UtcpError::Timeout(_))) {
+            return false;
+        }
+        let process = self.processes.lock().await.get(&prov.base.name).cloned();
+        let dead = match process {
+            Some(process) => !process.lock().await.is_alive(),
+            None => false,
+        };
+        if !dead {
+            return false;
+        }
+
+        let attempt = {
+            let mut restarts = self.restarts.lock().await;
+            let count = restarts.entry(prov.base.name.clone()).or_default();
+            if *count >= max_restarts {
+                return false;
+            }
+            *count += 1;
+            *count
+        };
+        tokio::time::sleep(restart_backoff(attempt)).await;
+        true
     }
 
     fn format_arguments(&self, args: &HashMap<String, Value>) -> Vec<String> {
@@ -155,21 +516,21 @@ impl ClientTransport for CliTransport {
             .downcast_ref::<CliProvider>()
             .ok_or_else(|| anyhow!("Provider is not a CliProvider"))?;
 
-        // Parse command name into command and args
-        let (cmd_path, cmd_args) = self.parse_command(&cli_prov.command_name)?;
+        if cli_prov.mode == CliMode::Persistent {
+            let manifest = self
+                .persistent_request(cli_prov, serde_json::json!({"method": "list_tools"}))
+                .await?;
+            return Ok(self.extract_tools_from_output(&manifest.to_string()));
+        }
 
         // Execute discovery command
-        let (stdout, stderr, exit_code) = self
-            .execute_command(
-                &cmd_path,
-                &cmd_args,
-                &cli_prov.env_vars,
-                &cli_prov.working_dir,
-                None,
-            )
-            .await?;
+        let finished = self.execute_command(cli_prov, &[], None).await?;
 
-        let output = if exit_code == 0 { stdout } else { stderr };
+        let output = if finished.exit_code == 0 {
+            finished.stdout
+        } else {
+            finished.stderr
+        };
 
         if output.trim().is_empty() {
             return Ok(vec![]);
@@ -178,8 +539,18 @@ impl ClientTransport for CliTransport {
         Ok(self.extract_tools_from_output(&output))
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
-        // CLI transport is stateless
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        let cli_prov = prov
+            .as_any()
+            .downcast_ref::<CliProvider>()
+            .ok_or_else(|| anyhow!("Provider is not a CliProvider"))?;
+        self.restarts.lock().await.remove(&cli_prov.base.name);
+
+        // A persistent process is stopped; one-shot commands leave nothing behind
+        let process = self.processes.lock().await.remove(&cli_prov.base.name);
+        if let Some(process) = process {
+            process.lock().await.kill().await;
+        }
         Ok(())
     }
 
@@ -194,44 +565,51 @@ impl ClientTransport for CliTransport {
             .downcast_ref::<CliProvider>()
             .ok_or_else(|| anyhow!("Provider is not a CliProvider"))?;
 
-        // Parse command name
-        let (cmd_path, mut cmd_args) = self.parse_command(&cli_prov.command_name)?;
+        if cli_prov.mode == CliMode::Persistent {
+            let request = serde_json::json!({
+                "method": "call_tool",
+                "tool": tool_name,
+                "args": args,
+            });
+            return self.persistent_request(cli_prov, request).await;
+        }
 
-        // Build command: <cmd> call <provider> <tool> [--flags]
-        cmd_args.extend([
-            "call".to_string(),
-            cli_prov.base.name.clone(),
-            tool_name.to_string(),
-        ]);
-        cmd_args.extend(self.format_arguments(&args));
+        let mut cmd_args = Vec::new();
+        let mut input = args;
+        if let Some(template) = cli_prov.args_template_for(tool_name) {
+            // Build command: <cmd> <rendered template>, with the rest on stdin or dropped
+            let (rendered, used) = render_args_template(template, &input)?;
+            cmd_args.extend(rendered);
+            input.retain(|name, _| !used.contains(name));
+        } else {
+            // Build command: <cmd> call <provider> <tool> [--flags]
+            cmd_args.extend([
+                "call".to_string(),
+                cli_prov.base.name.clone(),
+                tool_name.to_string(),
+            ]);
+            cmd_args.extend(self.format_arguments(&input));
+        }
 
         // Prepare JSON input
-        let input_json = serde_json::to_string(&args)?;
+        let input_json = match cli_prov.leftover_args {
+            LeftoverArgs::Stdin => Some(serde_json::to_string(&input)?),
+            LeftoverArgs::Drop => None,
+        };
 
         // Execute command
-        let (stdout, stderr, exit_code) = self
-            .execute_command(
-                &cmd_path,
-                &cmd_args,
-                &cli_prov.env_vars,
-                &cli_prov.working_dir,
-                Some(&input_json),
-            )
-            .await?;
-
-        let output = if exit_code == 0 { stdout } else { stderr };
+        let output = self
+            .execute_command(cli_prov, &cmd_args, input_json.as_deref())
+            .await?
+            .into_output();
 
-        if output.trim().is_empty() {
-            return Ok(Value::String(String::new()));
+        if cli_prov.structured_output {
+            return Ok(serde_json::to_value(output)?);
         }
-
-        // Try to parse as JSON
-        if let Ok(result) = serde_json::from_str::<Value>(&output) {
-            Ok(result)
-        } else {
-            // Return as string if not JSON
-            Ok(Value::String(output.trim().to_string()))
+        if output.exit_code != 0 {
+            return Err(UtcpError::CommandFailed { output }.into());
         }
+        Ok(output.stdout)
     }
 
     async fn call_tool_stream(
@@ -384,6 +762,19 @@ if (mode === "call") {
         script_path
     }
 
+    fn write_shell_script(dir: &std::path::Path, name: &str, body: &str) -> std::path::PathBuf {
+        let script_path = dir.join(name);
+        fs::write(&script_path, format!("#!/bin/sh\n{}", body)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&script_path).unwrap().permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&script_path, perms).unwrap();
+        }
+        script_path
+    }
+
     fn cli_provider(command: &str) -> CliProvider {
         CliProvider {
             base: BaseProvider {
@@ -393,8 +784,16 @@ if (mode === "call") {
                 allowed_communication_protocols: None,
             },
             command_name: command.to_string(),
+            use_shell: false,
+            mode: CliMode::Oneshot,
+            max_restarts: None,
             working_dir: None,
             env_vars: None,
+            timeout_ms: None,
+            structured_output: false,
+            args_template: None,
+            tool_args_templates: HashMap::new(),
+            leftover_args: LeftoverArgs::Stdin,
         }
     }
 
@@ -469,4 +868,548 @@ if (mode === "call") {
             .expect("expected streaming error");
         assert!(err.to_string().contains("Streaming not supported"));
     }
+
+    #[test]
+    fn expand_env_substitutes_client_variables() {
+        let home = std::env::var("HOME").unwrap_or_default();
+        assert_eq!(expand_env("${HOME}/cache"), format!("{}/cache", home));
+        assert_eq!(expand_env("a${UTCP_CLI_UNSET_VARIABLE}b"), "ab");
+        assert_eq!(
+            expand_env("$HOME and ${not valid}"),
+            "$HOME and ${not valid}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn call_tool_applies_env_and_working_dir() {
+        let dir = tempdir().unwrap();
+        let script_path = write_shell_script(
+            dir.path(),
+            "env.sh",
+            r#"printf '{"greeting":"%s","cwd":"%s"}\n' "$GREETING" "$(pwd)""#,
+        );
+        let workdir = tempdir().unwrap();
+
+        let mut provider = cli_provider(&script_path.display().to_string());
+        provider.working_dir = Some(workdir.path().display().to_string());
+        provider.env_vars = Some(HashMap::from([(
+            "GREETING".to_string(),
+            "hello from ${HOME}".to_string(),
+        )]));
+
+        let result = CliTransport::new()
+            .call_tool("env", HashMap::new(), &provider)
+            .await
+            .expect("call tool");
+
+        let home = std::env::var("HOME").unwrap_or_default();
+        assert_eq!(result["greeting"], format!("hello from {}", home));
+        assert_eq!(
+            fs::canonicalize(result["cwd"].as_str().unwrap()).unwrap(),
+            fs::canonicalize(workdir.path()).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn call_tool_kills_commands_that_time_out() {
+        let dir = tempdir().unwrap();
+        let pid_file = dir.path().join("sleep.pid");
+        let script_path = write_shell_script(
+            dir.path(),
+            "hang.sh",
+            "echo started\necho warming up >&2\nsleep 30 &\necho $! > \"$PID_FILE\"\nwait\n",
+        );
+
+        let mut provider = cli_provider(&script_path.display().to_string());
+        provider.env_vars = Some(HashMap::from([(
+            "PID_FILE".to_string(),
+            pid_file.display().to_string(),
+        )]));
+        provider.timeout_ms = Some(300);
+
+        let started = std::time::Instant::now();
+        let err = CliTransport::new()
+            .call_tool("hang", HashMap::new(), &provider)
+            .await
+            .expect_err("command should time out");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+        let message = err.to_string();
+        assert!(message.contains("timed out after 300ms"), "{message}");
+        assert!(message.contains("stdout: started"), "{message}");
+        assert!(message.contains("stderr: warming up"), "{message}");
+
+        // The background `sleep` shared the script's process group and was killed with it
+        let pid: libc::pid_t = fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        loop {
+            let alive = unsafe { libc::kill(pid, 0) } == 0
+                && fs::read_to_string(format!("/proc/{}/stat", pid))
+                    .map(|stat| !stat.contains(") Z "))
+                    .unwrap_or(true);
+            if !alive {
+                break;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "sleep {} survived",
+                pid
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_commands_report_exit_code_and_stderr() {
+        let dir = tempdir().unwrap();
+        let script_path = write_shell_script(
+            dir.path(),
+            "fail.sh",
+            "echo '{\"partial\": true}'\necho 'disk full' >&2\nexit 3\n",
+        );
+        let mut provider = cli_provider(&script_path.display().to_string());
+
+        let err = CliTransport::new()
+            .call_tool("fail", HashMap::new(), &provider)
+            .await
+            .expect_err("non-zero exit");
+        assert_eq!(err.to_string(), "Command exited with status 3: disk full");
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::CommandFailed { output }) => {
+                assert_eq!(output.exit_code, 3);
+                assert_eq!(output.stdout, json!({"partial": true}));
+                assert_eq!(output.stderr, "disk full\n");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        provider.structured_output = true;
+        let result = CliTransport::new()
+            .call_tool("fail", HashMap::new(), &provider)
+            .await
+            .expect("structured result");
+        assert_eq!(result["exit_code"], 3);
+        assert_eq!(result["stdout"], json!({"partial": true}));
+        assert_eq!(result["stderr"], "disk full\n");
+        assert!(result["duration_ms"].is_u64());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn plain_text_stdout_is_kept_beside_chatty_stderr() {
+        let dir = tempdir().unwrap();
+        // More stderr than a pipe buffers, written before any stdout
+        let script_path = write_shell_script(
+            dir.path(),
+            "chatty.sh",
+            "head -c 262144 /dev/zero | tr '\\0' x >&2\necho 'all good'\n",
+        );
+        let mut provider = cli_provider(&script_path.display().to_string());
+        provider.timeout_ms = Some(10_000);
+
+        let result = CliTransport::new()
+            .call_tool("chatty", HashMap::new(), &provider)
+            .await
+            .expect("plain text result");
+        assert_eq!(result, json!("all good"));
+
+        provider.structured_output = true;
+        let result = CliTransport::new()
+            .call_tool("chatty", HashMap::new(), &provider)
+            .await
+            .expect("structured result");
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["stdout"], "all good");
+        assert_eq!(result["stderr"].as_str().unwrap().len(), 262144);
+    }
+
+    #[test]
+    fn split_windows_keeps_backslashes_in_paths() {
+        assert_eq!(
+            split_windows(r#""C:\Program Files\Tools\cli.exe" --dir C:\work\out  -v"#).unwrap(),
+            vec![
+                r"C:\Program Files\Tools\cli.exe",
+                "--dir",
+                r"C:\work\out",
+                "-v"
+            ]
+        );
+        assert_eq!(split_windows(r#"tool """#).unwrap(), vec!["tool", ""]);
+        assert!(split_windows(r#"tool "open"#).is_none());
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn windows_commands_run_directly_and_through_cmd() {
+        let mut provider = cli_provider("cmd /C echo");
+        let result = CliTransport::new()
+            .call_tool("greet", HashMap::new(), &provider)
+            .await
+            .expect("direct call");
+        assert_eq!(result, json!("call cli greet"));
+
+        provider.command_name = "echo one & echo two & rem".to_string();
+        provider.use_shell = true;
+        let result = CliTransport::new()
+            .call_tool("greet", HashMap::new(), &provider)
+            .await
+            .expect("shell call");
+        assert_eq!(result, json!("one \ntwo"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn use_shell_runs_shell_syntax_and_quotes_arguments() {
+        let mut provider = cli_provider("printf 'one\r\ntwo\r\n'; printf '%s|' echo");
+        provider.use_shell = true;
+        provider.args_template = Some(vec!["{text}".to_string()]);
+        provider.leftover_args = LeftoverArgs::Drop;
+
+        let args = HashMap::from([("text".to_string(), json!("$HOME; `id` it's"))]);
+        let result = CliTransport::new()
+            .call_tool("shell", args, &provider)
+            .await
+            .expect("shell call");
+        assert_eq!(result, json!("one\ntwo\necho|$HOME; `id` it's|"));
+
+        // Without the shell, `;` is just part of an argument
+        provider.use_shell = false;
+        provider.command_name = "echo a; echo b".to_string();
+        let args = HashMap::from([("text".to_string(), json!("c"))]);
+        let result = CliTransport::new()
+            .call_tool("shell", args, &provider)
+            .await
+            .expect("direct call");
+        assert_eq!(result, json!("a; echo b c"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn parse_command_honours_quotes() {
+        let transport = CliTransport::new();
+        let (cmd, args) = transport
+            .parse_command(r#"'/opt/my tools/cli' --profile "dev team" plain"#)
+            .unwrap();
+        assert_eq!(cmd, "/opt/my tools/cli");
+        assert_eq!(args, vec!["--profile", "dev team", "plain"]);
+
+        assert!(transport.parse_command("'unbalanced").is_err());
+        assert!(transport.parse_command("\"unbalanced").is_err());
+        assert!(transport.parse_command("   ").is_err());
+    }
+
+    #[test]
+    fn render_args_template_keeps_each_element_one_argument() {
+        let args = HashMap::from([
+            ("path".to_string(), json!("my file; rm -rf /")),
+            ("city".to_string(), json!("Zürich 東京")),
+            ("count".to_string(), json!(3)),
+            ("unused".to_string(), json!(true)),
+        ]);
+        let template = [
+            "--input={path}".to_string(),
+            "{city}".to_string(),
+            "-n".to_string(),
+            "{count}".to_string(),
+            "{{literal}}".to_string(),
+        ];
+
+        let (rendered, used) = render_args_template(&template, &args).unwrap();
+        assert_eq!(
+            rendered,
+            vec![
+                "--input=my file; rm -rf /",
+                "Zürich 東京",
+                "-n",
+                "3",
+                "{literal}"
+            ]
+        );
+        assert_eq!(
+            used,
+            HashSet::from(["path".to_string(), "city".to_string(), "count".to_string()])
+        );
+
+        let err = render_args_template(&["{missing}".to_string()], &args).unwrap_err();
+        assert!(err.to_string().contains("argument 'missing'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn call_tool_renders_args_template_into_argv() {
+        let dir = tempdir().unwrap();
+        let bin_dir = dir.path().join("my tools");
+        fs::create_dir(&bin_dir).unwrap();
+        let script_path = bin_dir.join("print_argv.js");
+        fs::write(
+            &script_path,
+            r#"#!/usr/bin/env node
+let body = "";
+process.stdin.on("data", chunk => body += chunk.toString());
+process.stdin.on("end", () => {
+  console.log(JSON.stringify({ argv: process.argv.slice(2), stdin: body }));
+});
+"#,
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let mut provider = cli_provider(&format!("'{}' --region eu", script_path.display()));
+        provider.args_template = Some(vec!["convert".to_string(), "{source}".to_string()]);
+        provider.tool_args_templates = HashMap::from([(
+            "copy".to_string(),
+            vec!["cp".to_string(), "{source}".to_string()],
+        )]);
+        let args = HashMap::from([
+            ("source".to_string(), json!("clip ü.mp4; rm -rf ~")),
+            ("quality".to_string(), json!("high")),
+        ]);
+
+        let transport = CliTransport::new();
+        let result = transport
+            .call_tool("convert", args.clone(), &provider)
+            .await
+            .expect("call tool");
+        assert_eq!(
+            result["argv"],
+            json!(["--region", "eu", "convert", "clip ü.mp4; rm -rf ~"])
+        );
+        let stdin: Value = serde_json::from_str(result["stdin"].as_str().unwrap()).unwrap();
+        assert_eq!(stdin, json!({"quality": "high"}));
+
+        provider.leftover_args = LeftoverArgs::Drop;
+        let result = transport
+            .call_tool("copy", args, &provider)
+            .await
+            .expect("call tool");
+        assert_eq!(
+            result["argv"],
+            json!(["--region", "eu", "cp", "clip ü.mp4; rm -rf ~"])
+        );
+        assert_eq!(result["stdin"], "");
+
+        let err = transport
+            .call_tool("convert", HashMap::new(), &provider)
+            .await
+            .expect_err("missing argument");
+        assert!(err.to_string().contains("argument 'source'"), "{err}");
+    }
+
+    #[cfg(unix)]
+    fn persistent_provider(dir: &std::path::Path) -> CliProvider {
+        let script_path = write_shell_script(
+            dir,
+            "loop.sh",
+            r#"n=0
+while IFS= read -r line; do
+  n=$((n+1))
+  case "$line" in
+    *list_tools*)
+      echo '{"tools":[{"name":"count","description":"count calls","inputs":{"type":"object"},"outputs":{"type":"object"},"tags":[]}]}' ;;
+    *flaky*)
+      if [ ! -e "$MARKER" ]; then
+        touch "$MARKER"
+        echo "flaky tool crashed" >&2
+        exit 3
+      fi
+      printf '{"pid":%s,"calls":%s}\n' $$ "$n" ;;
+    *hang*)
+      sleep 30 ;;
+    *slow*)
+      sleep 1
+      printf '{"pid":%s,"calls":%s,"slow":true}\n' $$ "$n" ;;
+    *)
+      printf '{"pid":%s,"calls":%s}\n' $$ "$n" ;;
+  esac
+done
+"#,
+        );
+        let mut provider = cli_provider(&script_path.display().to_string());
+        provider.mode = CliMode::Persistent;
+        provider.env_vars = Some(HashMap::from([(
+            "MARKER".to_string(),
+            dir.join("crashed").display().to_string(),
+        )]));
+        provider
+    }
+
+    #[cfg(unix)]
+    fn is_running(pid: u64) -> bool {
+        unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn persistent_calls_reuse_one_process() {
+        let dir = tempdir().unwrap();
+        let provider = persistent_provider(dir.path());
+        let transport = CliTransport::new();
+
+        let tools = transport.register_tool_provider(&provider).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "count");
+
+        let first = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        let second = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_eq!(first["pid"], second["pid"]);
+        assert_eq!(first["calls"], 2);
+        assert_eq!(second["calls"], 3);
+
+        let pid = first["pid"].as_u64().unwrap();
+        assert!(is_running(pid));
+        transport.deregister_tool_provider(&provider).await.unwrap();
+        assert!(!is_running(pid));
+
+        let third = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_ne!(third["pid"], first["pid"]);
+        assert_eq!(third["calls"], 1);
+        transport.deregister_tool_provider(&provider).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn persistent_processes_are_replaced_after_crashing() {
+        let dir = tempdir().unwrap();
+        let mut provider = persistent_provider(dir.path());
+        let transport = CliTransport::new();
+
+        let err = transport
+            .call_tool("flaky", HashMap::new(), &provider)
+            .await
+            .expect_err("process crashed");
+        let message = err.to_string();
+        assert!(message.contains("exited (exit status: 3)"), "{message}");
+        assert!(message.contains("flaky tool crashed"), "{message}");
+
+        // The next call starts a new process
+        let result = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_eq!(result["calls"], 1);
+        transport.deregister_tool_provider(&provider).await.unwrap();
+
+        // With restarts allowed, the call that lost its process is retried on a new one
+        fs::remove_file(dir.path().join("crashed")).unwrap();
+        provider.max_restarts = Some(1);
+        let result = transport
+            .call_tool("flaky", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_eq!(result["calls"], 1);
+        transport.deregister_tool_provider(&provider).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelled_persistent_calls_do_not_leak_answers_or_block_others() {
+        let dir = tempdir().unwrap();
+        let provider = persistent_provider(dir.path());
+        let mut other = persistent_provider(dir.path());
+        other.base.name = "other".to_string();
+        let transport = Arc::new(CliTransport::new());
+
+        let first = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(200),
+            transport.call_tool("slow", HashMap::new(), &provider),
+        )
+        .await;
+        assert!(cancelled.is_err());
+
+        // The late answer to "slow" must not be taken for this call's
+        let result = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert!(result.get("slow").is_none(), "{result}");
+        assert_ne!(result["pid"], first["pid"]);
+        assert!(!is_running(first["pid"].as_u64().unwrap()));
+
+        // A call queued behind a busy process leaves other providers free
+        let busy = {
+            let (transport, provider) = (Arc::clone(&transport), provider.clone());
+            tokio::spawn(
+                async move { transport.call_tool("slow", HashMap::new(), &provider).await },
+            )
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let queued = {
+            let (transport, provider) = (Arc::clone(&transport), provider.clone());
+            tokio::spawn(async move {
+                transport
+                    .call_tool("count", HashMap::new(), &provider)
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        tokio::time::timeout(
+            Duration::from_millis(500),
+            transport.call_tool("count", HashMap::new(), &other),
+        )
+        .await
+        .expect("other provider was blocked")
+        .unwrap();
+        assert_eq!(busy.await.unwrap().unwrap()["slow"], true);
+        assert!(queued.await.unwrap().unwrap().get("slow").is_none());
+
+        transport.deregister_tool_provider(&provider).await.unwrap();
+        transport.deregister_tool_provider(&other).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn persistent_processes_are_killed_on_timeout() {
+        let dir = tempdir().unwrap();
+        let mut provider = persistent_provider(dir.path());
+        provider.timeout_ms = Some(300);
+        provider.max_restarts = Some(3);
+        let transport = CliTransport::new();
+
+        let first = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        let err = transport
+            .call_tool("hang", HashMap::new(), &provider)
+            .await
+            .expect_err("hung call");
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+        assert!(!is_running(first["pid"].as_u64().unwrap()));
+
+        let result = transport
+            .call_tool("count", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_ne!(result["pid"], first["pid"]);
+        transport.deregister_tool_provider(&provider).await.unwrap();
+    }
 }
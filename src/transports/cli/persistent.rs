@@ -0,0 +1,169 @@
+//! Commands kept running between calls by providers in `persistent` mode. Each request is one
+//! JSON line on the process's stdin, answered by one JSON line on its stdout.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::task::JoinHandle;
+
+use super::kill;
+use crate::errors::UtcpError;
+
+/// How many of the last lines a process wrote to stderr are kept for errors.
+const STDERR_LINES: usize = 50;
+
+/// How long to wait for a process whose stdout closed to report its exit status.
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+/// Keep the last [`STDERR_LINES`] lines a process writes to stderr.
+async fn read_stderr(stderr: ChildStderr, tail: Arc<Mutex<VecDeque<String>>>) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == STDERR_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
+
+pub(super) struct PersistentProcess {
+    provider: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    stderr: Arc<Mutex<VecDeque<String>>>,
+    // Reads stderr until the process and its children close it
+    stderr_reader: JoinHandle<()>,
+    // Set while a request is in flight. Still set afterwards when the caller gave up on the
+    // request, so the answer it may yet write would be read as the next request's
+    in_flight: bool,
+}
+
+impl PersistentProcess {
+    /// Start `cmd` with piped stdio.
+    pub(super) fn spawn(provider: &str, mut cmd: Command) -> Result<Self> {
+        cmd.stdin(std::process::Stdio::piped());
+        cmd.stdout(std::process::Stdio::piped());
+        cmd.stderr(std::process::Stdio::piped());
+        let mut child = cmd.spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to get stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Failed to get stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("Failed to get stderr"))?;
+        let tail = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr_reader = tokio::spawn(read_stderr(stderr, Arc::clone(&tail)));
+
+        Ok(Self {
+            provider: provider.to_string(),
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            stderr: tail,
+            stderr_reader,
+            in_flight: false,
+        })
+    }
+
+    /// Whether the process is still running.
+    pub(super) fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Whether the process can take the next request: it is running and no cancelled request
+    /// left an answer pending on its stdout.
+    pub(super) fn is_ready(&mut self) -> bool {
+        !self.in_flight && self.is_alive()
+    }
+
+    /// Write `request` as a line and read the line answering it. A process that takes longer
+    /// than `timeout` is killed, since a late answer would be taken for the next request's;
+    /// if the caller drops the request instead, [`Self::is_ready`] reports the process stale.
+    pub(super) async fn request(&mut self, request: &Value, timeout: Duration) -> Result<Value> {
+        self.in_flight = true;
+        let outcome = tokio::time::timeout(timeout, self.exchange(request)).await;
+        self.in_flight = false;
+        match outcome {
+            Ok(Ok(Some(response))) => Ok(response),
+            Ok(Ok(None)) => Err(self.exited_error().await),
+            Ok(Err(err)) => {
+                if self.is_alive() {
+                    return Err(err);
+                }
+                Err(self.exited_error().await)
+            }
+            Err(_) => {
+                self.kill().await;
+                Err(UtcpError::Timeout(format!(
+                    "CLI process of provider '{}' sent no response within {}ms and was killed",
+                    self.provider,
+                    timeout.as_millis()
+                ))
+                .into())
+            }
+        }
+    }
+
+    /// Send one request line and read the next non-blank line, or `None` once stdout closes.
+    async fn exchange(&mut self, request: &Value) -> Result<Option<Value>> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.stdin.write_all(&line).await?;
+        self.stdin.flush().await?;
+
+        while let Some(line) = self.stdout.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return serde_json::from_str(&line).map(Some).map_err(|e| {
+                anyhow!(
+                    "CLI process of provider '{}' answered with invalid JSON: {}",
+                    self.provider,
+                    e
+                )
+            });
+        }
+        Ok(None)
+    }
+
+    /// The error for a process that can no longer answer, naming how it exited and what it
+    /// last wrote to stderr.
+    async fn exited_error(&mut self) -> anyhow::Error {
+        let mut message = format!("CLI process of provider '{}' exited", self.provider);
+        match tokio::time::timeout(EXIT_GRACE, self.child.wait()).await {
+            Ok(Ok(status)) => message.push_str(&format!(" ({})", status)),
+            _ => {
+                // Its stdout closed but it lingers; it cannot answer either way
+                self.kill().await;
+            }
+        }
+        // Take in what it wrote to stderr before exiting
+        if !self.stderr_reader.is_finished() {
+            let _ = tokio::time::timeout(EXIT_GRACE, &mut self.stderr_reader).await;
+        }
+        let stderr = self.stderr.lock().unwrap();
+        if !stderr.is_empty() {
+            let lines: Vec<&str> = stderr.iter().map(String::as_str).collect();
+            message.push_str(&format!("; stderr:\n{}", lines.join("\n")));
+        }
+        anyhow!(message)
+    }
+
+    /// Stop the process and everything it started.
+    pub(super) async fn kill(&mut self) {
+        kill(&mut self.child);
+        let _ = self.child.wait().await;
+    }
+}
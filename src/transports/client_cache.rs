@@ -0,0 +1,138 @@
+//! Per-provider reqwest clients for settings that can only be fixed when a client is built.
+use anyhow::Result;
+use reqwest::cookie::Jar;
+use reqwest::redirect::Policy;
+use reqwest::{Client, NoProxy, Proxy, RequestBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::providers::http::{HttpTimeouts, ProxyConfig};
+use crate::transports::http_client::SharedClient;
+
+/// Client-level settings a provider can override.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ClientSettings {
+    connect_ms: Option<u64>,
+    proxy: Option<ProxyConfig>,
+    cookies: bool,
+    manual_redirects: bool,
+}
+
+/// The shared client plus dedicated clients for providers with a connect timeout, proxy,
+/// cookie jar or redirect policy, keyed by provider name.
+pub(crate) struct ClientCache {
+    shared: SharedClient,
+    clients: Mutex<HashMap<String, (ClientSettings, Client)>>,
+    jars: Mutex<HashMap<String, Arc<Jar>>>,
+}
+
+impl ClientCache {
+    /// Cache whose dedicated clients start from the settings of `shared`.
+    pub(crate) fn new(shared: SharedClient) -> Self {
+        Self {
+            shared,
+            clients: Mutex::new(HashMap::new()),
+            jars: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The client to use for `provider`: the shared client unless a connect timeout, proxy,
+    /// cookie jar or manual redirect handling is configured. Request and total limits are
+    /// applied per call, so one client serves streaming and non-streaming calls alike.
+    pub(crate) fn client_for(
+        &self,
+        provider: &str,
+        timeouts: Option<&HttpTimeouts>,
+        proxy: Option<&ProxyConfig>,
+        cookies: bool,
+        manual_redirects: bool,
+    ) -> Result<Client> {
+        let settings = ClientSettings {
+            connect_ms: timeouts.and_then(|t| t.connect_ms),
+            proxy: proxy.cloned(),
+            cookies,
+            manual_redirects,
+        };
+        if settings == ClientSettings::default() {
+            return Ok(self.shared.client().clone());
+        }
+
+        let mut clients = self.clients.lock().unwrap();
+        if let Some((cached, client)) = clients.get(provider) {
+            if *cached == settings {
+                return Ok(client.clone());
+            }
+        }
+        let mut builder = self.shared.builder();
+        if let Some(ms) = settings.connect_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+        if let Some(proxy) = &settings.proxy {
+            builder = builder.proxy(build_proxy(proxy)?);
+        }
+        if settings.cookies {
+            // The jar outlives rebuilt clients so a settings change keeps the session.
+            let jar = self
+                .jars
+                .lock()
+                .unwrap()
+                .entry(provider.to_string())
+                .or_default()
+                .clone();
+            builder = builder.cookie_provider(jar);
+        }
+        if settings.manual_redirects {
+            builder = builder.redirect(Policy::none());
+        }
+        let client = builder.build()?;
+        clients.insert(provider.to_string(), (settings, client.clone()));
+        Ok(client)
+    }
+
+    /// Limit each attempt of a non-streaming request to the provider's `request_ms`, falling
+    /// back to the shared client's `request_timeout_ms` and then to the transport's own
+    /// `default`, if it has one.
+    pub(crate) fn apply_request_timeout(
+        &self,
+        builder: RequestBuilder,
+        timeouts: Option<&HttpTimeouts>,
+        default: Option<Duration>,
+    ) -> RequestBuilder {
+        let limit = timeouts
+            .and_then(|t| t.request_ms)
+            .or(self.shared.config().request_timeout_ms)
+            .map(Duration::from_millis)
+            .or(default);
+        match limit {
+            Some(limit) => builder.timeout(limit),
+            None => builder,
+        }
+    }
+
+    /// Drop the cached client and cookies for a deregistered provider.
+    pub(crate) fn remove(&self, provider: &str) {
+        self.clients.lock().unwrap().remove(provider);
+        self.clear_cookies(provider);
+    }
+
+    /// Forget the cookies stored for `provider`. `Jar` cannot be emptied in place, so the jar
+    /// and the client holding it are dropped and a fresh pair is built on the next call.
+    pub(crate) fn clear_cookies(&self, provider: &str) {
+        if self.jars.lock().unwrap().remove(provider).is_some() {
+            self.clients.lock().unwrap().remove(provider);
+        }
+    }
+}
+
+/// Proxy for both http and https targets; `no_proxy` entries are reached directly.
+fn build_proxy(config: &ProxyConfig) -> Result<Proxy> {
+    let mut proxy = Proxy::all(&config.url)?;
+    if let Some(username) = &config.username {
+        proxy = proxy.basic_auth(username, config.password.as_deref().unwrap_or(""));
+    }
+    if !config.no_proxy.is_empty() {
+        proxy = proxy.no_proxy(NoProxy::from_string(&config.no_proxy.join(",")));
+    }
+    Ok(proxy)
+}
@@ -0,0 +1,244 @@
+//! Caller-supplied GraphQL documents passed through the reserved `_query` argument.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Arguments that describe a hand-written operation rather than field arguments.
+const RESERVED: [&str; 3] = ["_query", "_variables", "_operation_name"];
+
+/// A document to run verbatim instead of a generated single-field operation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawOperation {
+    pub query: String,
+    pub variables: HashMap<String, Value>,
+    pub operation_name: Option<String>,
+    /// `query`, `mutation` or `subscription`, for the operation that will run.
+    pub operation_type: String,
+}
+
+impl RawOperation {
+    /// Read `_query`, `_variables` and `_operation_name` from `args`, or `None` when the call
+    /// does not use `_query`. Documents with several operations need `_operation_name`.
+    pub(crate) fn from_args(args: &HashMap<String, Value>) -> Result<Option<Self>> {
+        let query = match args.get("_query") {
+            Some(Value::String(query)) => query.clone(),
+            Some(other) => return Err(anyhow!("_query must be a string, got {}", other)),
+            None => return Ok(None),
+        };
+        if let Some(key) = args.keys().find(|key| !RESERVED.contains(&key.as_str())) {
+            return Err(anyhow!(
+                "Unexpected argument '{}' alongside _query; pass variables in _variables",
+                key
+            ));
+        }
+        let variables = match args.get("_variables") {
+            Some(Value::Object(vars)) => vars.clone().into_iter().collect(),
+            Some(Value::Null) | None => HashMap::new(),
+            Some(other) => return Err(anyhow!("_variables must be an object, got {}", other)),
+        };
+        let operation_name = match args.get("_operation_name") {
+            Some(Value::String(name)) => Some(name.clone()),
+            Some(Value::Null) | None => None,
+            Some(other) => return Err(anyhow!("_operation_name must be a string, got {}", other)),
+        };
+
+        let operations = operations(&query);
+        let selected = match (&operation_name, operations.as_slice()) {
+            (_, []) => return Err(anyhow!("_query does not define an operation")),
+            (None, [only]) => only,
+            (None, many) => {
+                return Err(anyhow!(
+                    "_query defines {} operations; set _operation_name to choose one",
+                    many.len()
+                ))
+            }
+            (Some(name), all) => all
+                .iter()
+                .find(|op| op.name.as_deref() == Some(name.as_str()))
+                .ok_or_else(|| anyhow!("_query has no operation named '{}'", name))?,
+        };
+        let operation_type = selected.operation_type.clone();
+
+        Ok(Some(Self {
+            query,
+            variables,
+            operation_name,
+            operation_type,
+        }))
+    }
+}
+
+/// One operation definition found in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OperationDefinition {
+    pub operation_type: String,
+    pub name: Option<String>,
+}
+
+/// The operations a document defines, in order. Fragments are skipped and a bare `{ ... }`
+/// counts as an anonymous query.
+pub(crate) fn operations(document: &str) -> Vec<OperationDefinition> {
+    let mut found = Vec::new();
+    let mut chars = document.chars().peekable();
+    let (mut braces, mut parens) = (0usize, 0usize);
+    // An operation keyword seen at the top level whose selection set has not opened yet.
+    let mut pending: Option<OperationDefinition> = None;
+    let mut in_fragment = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => skip_string(&mut chars),
+            '(' => parens += 1,
+            ')' => parens = parens.saturating_sub(1),
+            '{' => {
+                if braces == 0 && parens == 0 {
+                    match pending.take() {
+                        Some(operation) => found.push(operation),
+                        None if !in_fragment => found.push(OperationDefinition {
+                            operation_type: "query".to_string(),
+                            name: None,
+                        }),
+                        None => {}
+                    }
+                    in_fragment = false;
+                }
+                braces += 1;
+            }
+            '}' => braces = braces.saturating_sub(1),
+            '@' => {
+                // Directive names are not operation names.
+                read_name(&mut chars, None);
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let word = read_name(&mut chars, Some(c));
+                if braces > 0 || parens > 0 || in_fragment {
+                    continue;
+                }
+                match &mut pending {
+                    Some(operation) if operation.name.is_none() => operation.name = Some(word),
+                    Some(_) => {}
+                    None => match word.as_str() {
+                        "query" | "mutation" | "subscription" => {
+                            pending = Some(OperationDefinition {
+                                operation_type: word,
+                                name: None,
+                            })
+                        }
+                        "fragment" => in_fragment = true,
+                        _ => {}
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+fn read_name(chars: &mut Peekable<Chars<'_>>, first: Option<char>) -> String {
+    let mut name: String = first.into_iter().collect();
+    while let Some(&c) = chars.peek() {
+        if c != '_' && !c.is_ascii_alphanumeric() {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    name
+}
+
+/// Skip a string whose opening quote was just read, including `"""` block strings.
+fn skip_string(chars: &mut Peekable<Chars<'_>>) {
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        if chars.peek() != Some(&'"') {
+            // An empty string.
+            return;
+        }
+        chars.next();
+        let mut quotes = 0;
+        for c in chars.by_ref() {
+            quotes = if c == '"' { quotes + 1 } else { 0 };
+            if quotes == 3 {
+                return;
+            }
+        }
+        return;
+    }
+    let mut escaped = false;
+    for c in chars.by_ref() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return,
+            _ => escaped = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn op(operation_type: &str, name: Option<&str>) -> OperationDefinition {
+        OperationDefinition {
+            operation_type: operation_type.to_string(),
+            name: name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn operations_skip_fragments_strings_and_nested_braces() {
+        let document = r#"
+            # query Commented { x }
+            query Search($filter: Filter = { tags: ["{", "query"] }) @cached(ttl: 5) {
+              search(filter: $filter) { ...Hit note(text: """ } mutation """) }
+            }
+            fragment Hit on Result { id }
+            mutation { reset }
+            { viewer { id } }
+        "#;
+        assert_eq!(
+            operations(document),
+            vec![
+                op("query", Some("Search")),
+                op("mutation", None),
+                op("query", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_operations_need_an_operation_name() {
+        let query = "query A { a } subscription B { b }";
+        let args = HashMap::from([("_query".to_string(), json!(query))]);
+        let err = RawOperation::from_args(&args).unwrap_err();
+        assert!(err.to_string().contains("set _operation_name"), "{err}");
+
+        let args = HashMap::from([
+            ("_query".to_string(), json!(query)),
+            ("_operation_name".to_string(), json!("B")),
+            ("_variables".to_string(), json!({ "id": 1 })),
+        ]);
+        let raw = RawOperation::from_args(&args).unwrap().unwrap();
+        assert_eq!(raw.operation_type, "subscription");
+        assert_eq!(raw.operation_name.as_deref(), Some("B"));
+        assert_eq!(raw.variables["id"], json!(1));
+
+        let args = HashMap::from([
+            ("_query".to_string(), json!(query)),
+            ("_operation_name".to_string(), json!("C")),
+        ]);
+        assert!(RawOperation::from_args(&args).is_err());
+        assert!(RawOperation::from_args(&HashMap::new()).unwrap().is_none());
+    }
+}
@@ -1,49 +1,62 @@
 // GraphQL Transport - queries, mutations, and subscriptions
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use base64::Engine;
-use futures::{SinkExt, StreamExt};
-use reqwest::Client;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, PoisonError};
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 use crate::auth::AuthConfig;
+use crate::errors::{GraphQLErrorDetail, UtcpError};
 use crate::providers::base::Provider;
 use crate::providers::graphql::GraphqlProvider;
 use crate::tools::{Tool, ToolInputOutputSchema};
+use crate::transports::client_cache::ClientCache;
+use crate::transports::http_client::SharedClient;
+use crate::transports::timeouts::{timeout_error, with_total_timeout};
 use crate::transports::{
     stream::{boxed_channel_stream, StreamResult},
     ClientTransport,
 };
 
+mod document;
+mod schema;
+mod subscription;
+
+use document::RawOperation;
+use schema::Schema;
+
+/// What introspection reported about a root field, kept for building its operation.
+#[derive(Debug, Clone, Default)]
+struct RootField {
+    selection: Option<String>,
+    /// Declared argument types, such as `CreateUserInput!`.
+    arg_types: HashMap<String, String>,
+}
+
 /// Transport that maps GraphQL operations to UTCP tools.
 pub struct GraphQLTransport {
-    client: Client,
+    clients: ClientCache,
+    fields: Mutex<HashMap<String, HashMap<String, RootField>>>,
 }
 
 impl GraphQLTransport {
-    /// Create a GraphQL transport using a default reqwest client.
+    /// Create a GraphQL transport on the process-wide shared client.
     pub fn new() -> Self {
+        Self::with_client(SharedClient::default())
+    }
+
+    /// Create a GraphQL transport that sends HTTP requests through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
         Self {
-            client: Client::new(),
+            clients: ClientCache::new(shared),
+            fields: Mutex::new(HashMap::new()),
         }
     }
 
     fn default_schema() -> ToolInputOutputSchema {
-        ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
-        }
+        ToolInputOutputSchema::new("object")
     }
 
     fn infer_operation(operation_type: &str, tool_name: &str) -> String {
@@ -96,6 +109,75 @@ impl GraphQLTransport {
         }
     }
 
+    /// Build the document and variables that call root field `call_name`.
+    ///
+    /// Arguments use the types introspection declared for them and are passed as-is; unknown
+    /// arguments fall back to types guessed from their values. The field's selection set comes
+    /// from the reserved `_selection` argument when given, otherwise from introspection.
+    fn build_operation(
+        &self,
+        prov: &GraphqlProvider,
+        operation_type: &str,
+        call_name: &str,
+        args: HashMap<String, Value>,
+    ) -> Result<(String, HashMap<String, Value>)> {
+        let mut args: BTreeMap<String, Value> = args.into_iter().collect();
+        let field = self
+            .fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&prov.base.name)
+            .and_then(|fields| fields.get(call_name))
+            .cloned()
+            .unwrap_or_default();
+        let selection = match args.remove("_selection") {
+            Some(Value::String(fields)) if fields.trim().is_empty() => None,
+            Some(Value::String(fields)) => Some(format!("{{ {} }}", fields.trim())),
+            Some(other) => {
+                return Err(anyhow!(
+                    "_selection must be a string of field names, got {}",
+                    other
+                ))
+            }
+            None => field.selection,
+        };
+        let selection = selection.map(|set| format!(" {}", set)).unwrap_or_default();
+
+        let operation_name = prov
+            .operation_name
+            .clone()
+            .unwrap_or_else(|| call_name.to_string());
+
+        let mut arg_defs = Vec::new();
+        let mut arg_uses = Vec::new();
+        let mut variables = HashMap::new();
+
+        for (key, value) in args {
+            let (type_name, normalized_value) = match field.arg_types.get(&key) {
+                Some(declared) => (declared.clone(), value),
+                None => Self::normalize_arg_value(&key, value),
+            };
+            arg_defs.push(format!("${}: {}", key, type_name));
+            arg_uses.push(format!("{}: ${}", key, key));
+            variables.insert(key, normalized_value);
+        }
+
+        let query = if !arg_defs.is_empty() {
+            format!(
+                "{} {}({}) {{ {}({}){} }}",
+                operation_type,
+                operation_name,
+                arg_defs.join(", "),
+                call_name,
+                arg_uses.join(", "),
+                selection
+            )
+        } else {
+            format!("{} {{ {}{} }}", operation_type, call_name, selection)
+        };
+        Ok((query, variables))
+    }
+
     fn apply_auth(
         &self,
         builder: reqwest::RequestBuilder,
@@ -125,16 +207,99 @@ impl GraphQLTransport {
         }
     }
 
+    /// Whether the server asked for the full text of a persisted query.
+    fn persisted_query_missing(response: &Value) -> bool {
+        response
+            .get("errors")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .any(|error| {
+                let message = error.get("message").and_then(|v| v.as_str());
+                let code = error
+                    .get("extensions")
+                    .and_then(|ext| ext.get("code"))
+                    .and_then(|v| v.as_str());
+                matches!(
+                    message,
+                    Some("PersistedQueryNotFound" | "PersistedQueryNotSupported")
+                ) || matches!(
+                    code,
+                    Some("PERSISTED_QUERY_NOT_FOUND" | "PERSISTED_QUERY_NOT_SUPPORTED")
+                )
+            })
+    }
+
     async fn execute_query(
         &self,
         prov: &GraphqlProvider,
         query: &str,
         variables: HashMap<String, Value>,
+        operation_name: Option<&str>,
     ) -> Result<Value> {
+        let mut request = json!({ "variables": variables });
+        if let Some(name) = operation_name {
+            request["operationName"] = json!(name);
+        }
+        let result = if prov.persisted_queries {
+            // Automatic persisted queries: try the hash alone, then resend with the text.
+            request["extensions"] = json!({
+                "persistedQuery": { "version": 1, "sha256Hash": query_hash(query) }
+            });
+            let result = self.post(prov, &request).await?;
+            if Self::persisted_query_missing(&result) {
+                request["query"] = json!(query);
+                self.post(prov, &request).await?
+            } else {
+                result
+            }
+        } else {
+            request["query"] = json!(query);
+            self.post(prov, &request).await?
+        };
+        Self::response_data(result, prov.allow_partial_data)
+    }
+
+    /// The `data` of a GraphQL response, or [`UtcpError::GraphQL`] when it reports errors.
+    /// With `allow_partial_data`, object data is returned with the errors under `_errors`.
+    fn response_data(mut response: Value, allow_partial_data: bool) -> Result<Value> {
+        let data = response.get_mut("data").map(Value::take);
+        let errors = match response.get("errors") {
+            Some(Value::Array(errors)) if errors.is_empty() => None,
+            Some(errors) => Some(GraphQLErrorDetail::list_from_value(errors)),
+            None => None,
+        };
+        let errors = match errors {
+            Some(errors) => errors,
+            None => return data.ok_or_else(|| anyhow!("No data in GraphQL response")),
+        };
+        match data {
+            Some(Value::Object(mut data)) if allow_partial_data => {
+                data.insert("_errors".to_string(), serde_json::to_value(&errors)?);
+                Ok(Value::Object(data))
+            }
+            data => Err(UtcpError::GraphQL {
+                errors,
+                partial_data: data.filter(|data| !data.is_null()),
+            }
+            .into()),
+        }
+    }
+
+    /// POST one GraphQL request body and return the decoded response.
+    async fn post(&self, prov: &GraphqlProvider, body: &Value) -> Result<Value> {
+        let timeouts = prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &prov.base.name,
+            timeouts,
+            prov.proxy.as_ref(),
+            false,
+            false,
+        )?;
         let mut req = self
-            .client
-            .post(&prov.url)
-            .json(&json!({ "query": query, "variables": variables }));
+            .clients
+            .apply_request_timeout(client.post(&prov.url), timeouts, None)
+            .json(body);
         if let Some(headers) = &prov.headers {
             for (k, v) in headers {
                 req = req.header(k, v);
@@ -144,23 +309,27 @@ impl GraphQLTransport {
             req = self.apply_auth(req, auth)?;
         }
 
-        let response = req.send().await?;
-        if !response.status().is_success() {
-            return Err(anyhow!("GraphQL request failed: {}", response.status()));
-        }
-
-        let result: Value = response.json().await?;
-        if let Some(errors) = result.get("errors") {
-            return Err(anyhow!("GraphQL errors: {}", errors));
-        }
-
-        result
-            .get("data")
-            .cloned()
-            .ok_or_else(|| anyhow!("No data in GraphQL response"))
+        with_total_timeout(timeouts, async {
+            let response = req.send().await.map_err(timeout_error)?;
+            let status = response.status();
+            if status.is_success() {
+                return response.json().await.map_err(timeout_error);
+            }
+            // Servers may answer 4xx with a GraphQL response describing the errors.
+            match response.json::<Value>().await {
+                Ok(body) if body.get("errors").is_some() => Ok(body),
+                _ => Err(anyhow!("GraphQL request failed: {}", status)),
+            }
+        })
+        .await
     }
 }
 
+/// Hex SHA-256 of `query`, the persisted-query ID sent in its place.
+fn query_hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
 #[async_trait]
 impl ClientTransport for GraphQLTransport {
     async fn register_tool_provider(&self, prov: &dyn Provider) -> Result<Vec<Tool>> {
@@ -169,18 +338,8 @@ impl ClientTransport for GraphQLTransport {
             .downcast_ref::<GraphqlProvider>()
             .ok_or_else(|| anyhow!("Provider is not a GraphqlProvider"))?;
 
-        // Basic introspection to list available operations.
-        let introspection = r#"
-        query IntrospectionQuery {
-          __schema {
-            queryType { fields { name description } }
-            mutationType { fields { name description } }
-            subscriptionType { fields { name description } }
-          }
-        }"#;
-
         let response = self
-            .execute_query(gql_prov, introspection, HashMap::new())
+            .execute_query(gql_prov, schema::INTROSPECTION_QUERY, HashMap::new(), None)
             .await;
 
         if response.is_err() {
@@ -189,9 +348,11 @@ impl ClientTransport for GraphQLTransport {
         let response = response.unwrap_or_default();
 
         let mut tools = Vec::new();
+        let mut root_fields = HashMap::new();
         let default_schema = Self::default_schema();
 
         if let Some(schema) = response.get("__schema") {
+            let types = Schema::new(schema);
             for (op_type, key) in [
                 ("query", "queryType"),
                 ("mutation", "mutationType"),
@@ -209,10 +370,33 @@ impl ClientTransport for GraphQLTransport {
                                 .and_then(|v| v.as_str())
                                 .unwrap_or_default()
                                 .to_string();
+                            let selection = field.get("type").and_then(|type_ref| {
+                                types.selection_set(type_ref, gql_prov.selection_depth)
+                            });
+                            let arg_types = field
+                                .get("args")
+                                .and_then(|v| v.as_array())
+                                .into_iter()
+                                .flatten()
+                                .filter_map(|arg| {
+                                    let name = arg.get("name")?.as_str()?;
+                                    Some((
+                                        name.to_string(),
+                                        schema::type_literal(arg.get("type")?)?,
+                                    ))
+                                })
+                                .collect();
+                            root_fields.insert(
+                                name.to_string(),
+                                RootField {
+                                    selection,
+                                    arg_types,
+                                },
+                            );
                             tools.push(Tool {
                                 name: name.to_string(),
                                 description,
-                                inputs: default_schema.clone(),
+                                inputs: types.input_schema(field),
                                 outputs: default_schema.clone(),
                                 tags: vec![op_type.to_string()],
                                 average_response_size: None,
@@ -224,10 +408,19 @@ impl ClientTransport for GraphQLTransport {
             }
         }
 
+        self.fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(gql_prov.base.name.clone(), root_fields);
         Ok(tools)
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        self.clients.remove(&prov.name());
+        self.fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&prov.name());
         Ok(())
     }
 
@@ -246,38 +439,27 @@ impl ClientTransport for GraphQLTransport {
             .strip_prefix(&format!("{}.", gql_prov.base.name))
             .unwrap_or(tool_name);
 
-        let operation_type = Self::infer_operation(&gql_prov.operation_type, call_name);
-        let operation_name = gql_prov
-            .operation_name
-            .clone()
-            .unwrap_or_else(|| call_name.to_string());
-
-        // Use simple variable typing (String) for portability.
-        let mut arg_defs = Vec::new();
-        let mut arg_uses = Vec::new();
-        let mut variables = HashMap::new();
-
-        for (key, value) in args {
-            let (type_name, normalized_value) = Self::normalize_arg_value(&key, value);
-            arg_defs.push(format!("${}: {}", key, type_name));
-            arg_uses.push(format!("{}: ${}", key, key));
-            variables.insert(key, normalized_value);
+        if let Some(raw) = RawOperation::from_args(&args)? {
+            if raw.operation_type == "subscription" {
+                return Err(anyhow!(
+                    "_query selects a subscription; use call_tool_stream to run it"
+                ));
+            }
+            return self
+                .execute_query(
+                    gql_prov,
+                    &raw.query,
+                    raw.variables,
+                    raw.operation_name.as_deref(),
+                )
+                .await;
         }
 
-        let query = if !arg_defs.is_empty() {
-            format!(
-                "{} {}({}) {{ {}({}) }}",
-                operation_type,
-                operation_name,
-                arg_defs.join(", "),
-                call_name,
-                arg_uses.join(", ")
-            )
-        } else {
-            format!("{} {{ {} }}", operation_type, call_name)
-        };
+        let operation_type = Self::infer_operation(&gql_prov.operation_type, call_name);
+        let (query, variables) =
+            self.build_operation(gql_prov, &operation_type, call_name, args)?;
 
-        self.execute_query(gql_prov, &query, variables).await
+        self.execute_query(gql_prov, &query, variables, None).await
     }
 
     async fn call_tool_stream(
@@ -295,7 +477,11 @@ impl ClientTransport for GraphQLTransport {
             .strip_prefix(&format!("{}.", gql_prov.base.name))
             .unwrap_or(tool_name);
 
-        let operation_type = Self::infer_operation(&gql_prov.operation_type, call_name);
+        let raw = RawOperation::from_args(&args)?;
+        let operation_type = match &raw {
+            Some(raw) => raw.operation_type.clone(),
+            None => Self::infer_operation(&gql_prov.operation_type, call_name),
+        };
 
         // GraphQL subscriptions must be sent over WebSocket
         if operation_type != "subscription" {
@@ -306,236 +492,30 @@ impl ClientTransport for GraphQLTransport {
             ));
         }
 
-        let operation_name = gql_prov
-            .operation_name
-            .clone()
-            .unwrap_or_else(|| call_name.to_string());
-
-        // Build the subscription query with variables
-        let mut arg_defs = Vec::new();
-        let mut arg_uses = Vec::new();
-        let mut variables = HashMap::new();
-
-        for (key, value) in args {
-            let (type_name, normalized_value) = Self::normalize_arg_value(&key, value);
-            arg_defs.push(format!("${}: {}", key, type_name));
-            arg_uses.push(format!("{}: ${}", key, key));
-            variables.insert(key, normalized_value);
-        }
-
-        let subscription_query = if !arg_defs.is_empty() {
-            format!(
-                "{} {}({}) {{ {}({}) }}",
-                operation_type,
-                operation_name,
-                arg_defs.join(", "),
-                call_name,
-                arg_uses.join(", ")
-            )
-        } else {
-            format!("{} {{ {} }}", operation_type, call_name)
-        };
-
-        // Convert HTTP URL to WebSocket URL
-        let mut ws_url = gql_prov
-            .url
-            .replace("http://", "ws://")
-            .replace("https://", "wss://");
-
-        // Handle query-based authentication
-        if let Some(AuthConfig::ApiKey(api_key)) = &gql_prov.base.auth {
-            if api_key.location.to_ascii_lowercase() == "query" {
-                let separator = if ws_url.contains('?') { "&" } else { "?" };
-                ws_url = format!(
-                    "{}{}{}={}",
-                    ws_url, separator, api_key.var_name, api_key.api_key
-                );
-            }
-        }
-
-        // Build the WebSocket request with proper headers
-        let mut req = tokio_tungstenite::tungstenite::http::Request::builder()
-            .uri(&ws_url)
-            .header("Host", ws_url.split('/').nth(2).unwrap_or("localhost"))
-            .header("Connection", "Upgrade")
-            .header("Upgrade", "websocket")
-            .header("Sec-WebSocket-Version", "13")
-            .header(
-                "Sec-WebSocket-Key",
-                tokio_tungstenite::tungstenite::handshake::client::generate_key(),
-            )
-            .header("Sec-WebSocket-Protocol", "graphql-transport-ws")
-            .body(())?;
-
-        // Apply authentication to WebSocket request (except query which was handled above)
-        if let Some(auth) = &gql_prov.base.auth {
-            match auth {
-                AuthConfig::ApiKey(api_key) => {
-                    let location = api_key.location.to_ascii_lowercase();
-                    match location.as_str() {
-                        "header" => {
-                            use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
-                            let name = HeaderName::from_bytes(api_key.var_name.as_bytes())
-                                .map_err(|_| anyhow!("Invalid header name"))?;
-                            let value = HeaderValue::from_str(&api_key.api_key)
-                                .map_err(|_| anyhow!("Invalid header value"))?;
-                            req.headers_mut().insert(name, value);
-                        }
-                        "cookie" => {
-                            use tokio_tungstenite::tungstenite::http::HeaderValue;
-                            let cookie_value = format!("{}={}", api_key.var_name, api_key.api_key);
-                            let value = HeaderValue::from_str(&cookie_value)
-                                .map_err(|_| anyhow!("Invalid cookie value"))?;
-                            req.headers_mut().insert("cookie", value);
-                        }
-                        "query" => {
-                            // Already handled above
-                        }
-                        other => {
-                            return Err(anyhow!(
-                                "Unsupported API key location for WebSocket: {}",
-                                other
-                            ))
-                        }
-                    }
-                }
-                AuthConfig::Basic(basic) => {
-                    use tokio_tungstenite::tungstenite::http::HeaderValue;
-                    let credentials = format!("{}:{}", basic.username, basic.password);
-                    let encoded =
-                        base64::engine::general_purpose::STANDARD.encode(credentials.as_bytes());
-                    let value = HeaderValue::from_str(&format!("Basic {}", encoded))
-                        .map_err(|_| anyhow!("Invalid auth header"))?;
-                    req.headers_mut().insert("authorization", value);
-                }
-                AuthConfig::OAuth2(_) => {
-                    return Err(anyhow!(
-                        "OAuth2 is not supported for GraphQL WebSocket subscriptions"
-                    ));
-                }
+        // Use the caller's document as-is, or build the subscription query with variables
+        let (subscription_query, variables, operation_name) = match raw {
+            Some(raw) => (raw.query, raw.variables, raw.operation_name),
+            None => {
+                let (query, variables) =
+                    self.build_operation(gql_prov, &operation_type, call_name, args)?;
+                (query, variables, None)
             }
-        }
-
-        // Apply custom headers if any
-        if let Some(headers) = &gql_prov.headers {
-            use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
-            for (k, v) in headers {
-                let name = HeaderName::from_bytes(k.as_bytes())
-                    .map_err(|_| anyhow!("Invalid header name: {}", k))?;
-                let value =
-                    HeaderValue::from_str(v).map_err(|_| anyhow!("Invalid header value: {}", v))?;
-                req.headers_mut().insert(name, value);
-            }
-        }
+        };
 
-        let (mut ws_stream, _) = connect_async(req).await?;
-
-        // Send connection_init message (graphql-transport-ws protocol)
-        ws_stream
-            .send(Message::Text(
-                json!({
-                    "type": "connection_init"
-                })
-                .to_string(),
-            ))
-            .await?;
-
-        // Wait for connection_ack
-        if let Some(msg) = ws_stream.next().await {
-            match msg? {
-                Message::Text(text) => {
-                    let ack: Value = serde_json::from_str(&text)?;
-                    if ack.get("type").and_then(|v| v.as_str()) != Some("connection_ack") {
-                        return Err(anyhow!("Expected connection_ack, got: {}", text));
-                    }
-                }
-                _ => return Err(anyhow!("Expected text message for connection_ack")),
-            }
+        let mut payload = json!({ "query": subscription_query, "variables": variables });
+        if let Some(name) = operation_name {
+            payload["operationName"] = json!(name);
         }
-
-        // Send subscription message
-        let subscription_id = "1"; // Simple ID for single subscription
-        let subscribe_msg = json!({
-            "id": subscription_id,
-            "type": "subscribe",
-            "payload": {
-                "query": subscription_query,
-                "variables": variables,
-            }
-        });
-
-        ws_stream
-            .send(Message::Text(subscribe_msg.to_string()))
-            .await?;
+        let connection = subscription::subscribe(gql_prov, &payload).await?;
 
         // Create channel for streaming results
         let (tx, rx) = mpsc::channel(256);
-
-        // Spawn task to handle incoming subscription messages
-        tokio::spawn(async move {
-            while let Some(msg) = ws_stream.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        let parsed = match serde_json::from_str::<Value>(&text) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                let _ = tx
-                                    .send(Err(anyhow!("Failed to parse WebSocket message: {}", e)))
-                                    .await;
-                                break;
-                            }
-                        };
-
-                        let msg_type = parsed.get("type").and_then(|v| v.as_str());
-                        match msg_type {
-                            Some("next") => {
-                                // Extract data from payload
-                                if let Some(payload) = parsed.get("payload") {
-                                    if let Some(data) = payload.get("data") {
-                                        if tx.send(Ok(data.clone())).await.is_err() {
-                                            break;
-                                        }
-                                    }
-                                    // Check for errors in payload
-                                    if let Some(errors) = payload.get("errors") {
-                                        let _ = tx
-                                            .send(Err(anyhow!(
-                                                "GraphQL subscription error: {}",
-                                                errors
-                                            )))
-                                            .await;
-                                        break;
-                                    }
-                                }
-                            }
-                            Some("error") => {
-                                let error_msg = parsed
-                                    .get("payload")
-                                    .map(|p| p.to_string())
-                                    .unwrap_or_else(|| "Unknown error".to_string());
-                                let _ = tx
-                                    .send(Err(anyhow!("GraphQL subscription error: {}", error_msg)))
-                                    .await;
-                                break;
-                            }
-                            Some("complete") => {
-                                // Subscription completed normally
-                                break;
-                            }
-                            _ => {
-                                // Ignore other message types (ping, pong, etc.)
-                            }
-                        }
-                    }
-                    Ok(Message::Close(_)) => break,
-                    Ok(_) => {} // Ignore binary, ping, pong
-                    Err(err) => {
-                        let _ = tx.send(Err(anyhow!("WebSocket error: {}", err))).await;
-                        break;
-                    }
-                }
-            }
-        });
+        tokio::spawn(subscription::forward(
+            connection,
+            gql_prov.clone(),
+            payload,
+            tx,
+        ));
 
         Ok(boxed_channel_stream(rx, None))
     }
@@ -544,9 +524,13 @@ impl ClientTransport for GraphQLTransport {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::graphql::GraphqlWsProtocol;
+    use crate::transports::test_websocket;
     use axum::{extract::Json, routing::post, Router};
     use serde_json::json;
     use std::net::TcpListener;
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::Message;
 
     #[test]
     fn infer_operation_prefers_explicit_value() {
@@ -654,6 +638,16 @@ mod tests {
             operation_type: "query".to_string(),
             operation_name: None,
             headers: None,
+            timeouts: None,
+            proxy: None,
+            tls: None,
+            selection_depth: crate::providers::graphql::DEFAULT_SELECTION_DEPTH,
+            ws_protocol: Default::default(),
+            persisted_queries: false,
+            allow_partial_data: false,
+            ping_interval_ms: None,
+            idle_timeout_ms: None,
+            reconnect: None,
         };
 
         let transport = GraphQLTransport::new();
@@ -721,6 +715,16 @@ mod tests {
             operation_type: "query".to_string(),
             operation_name: None,
             headers: None,
+            timeouts: None,
+            proxy: None,
+            tls: None,
+            selection_depth: crate::providers::graphql::DEFAULT_SELECTION_DEPTH,
+            ws_protocol: Default::default(),
+            persisted_queries: false,
+            allow_partial_data: false,
+            ping_interval_ms: None,
+            idle_timeout_ms: None,
+            reconnect: None,
         };
 
         let mut args = HashMap::new();
@@ -735,6 +739,681 @@ mod tests {
         assert_eq!(result, json!({ "echo": json!({ "msg": "hi" }) }));
     }
 
+    fn provider(url: String) -> GraphqlProvider {
+        let mut prov = GraphqlProvider::new("gql".to_string(), url, None);
+        prov.operation_type = String::new();
+        prov
+    }
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({ "kind": kind, "name": name, "ofType": null })
+    }
+
+    fn wrapped(kind: &str, inner: Value) -> Value {
+        json!({ "kind": kind, "name": null, "ofType": inner })
+    }
+
+    fn field(name: &str, type_ref: Value) -> Value {
+        json!({ "name": name, "description": null, "args": [], "type": type_ref })
+    }
+
+    fn selection_schema() -> Value {
+        let user = named("OBJECT", "User");
+        let avatar = json!({
+            "name": "avatar",
+            "args": [{
+                "name": "size",
+                "defaultValue": null,
+                "type": wrapped("NON_NULL", named("SCALAR", "Int")),
+            }],
+            "type": named("SCALAR", "String"),
+        });
+        json!({
+            "queryType": {
+                "name": "Query",
+                "fields": [
+                    field("version", wrapped("NON_NULL", named("SCALAR", "String"))),
+                    field("me", user.clone()),
+                    field(
+                        "users",
+                        wrapped("NON_NULL", wrapped("LIST", wrapped("NON_NULL", user.clone()))),
+                    ),
+                    field("search", wrapped("LIST", named("UNION", "SearchResult"))),
+                ],
+            },
+            "mutationType": null,
+            "subscriptionType": null,
+            "types": [
+                {
+                    "kind": "OBJECT",
+                    "name": "User",
+                    "fields": [
+                        field("id", wrapped("NON_NULL", named("SCALAR", "ID"))),
+                        field("name", named("SCALAR", "String")),
+                        avatar,
+                        field("orders", wrapped("LIST", named("OBJECT", "Order"))),
+                        field("friend", user),
+                    ],
+                },
+                {
+                    "kind": "OBJECT",
+                    "name": "Order",
+                    "fields": [
+                        field("sku", named("SCALAR", "String")),
+                        field("qty", named("SCALAR", "Int")),
+                    ],
+                },
+                { "kind": "UNION", "name": "SearchResult", "fields": null },
+            ],
+        })
+    }
+
+    #[tokio::test]
+    async fn selection_sets_follow_introspected_return_types() {
+        let queries = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = queries.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| {
+                let seen = seen.clone();
+                async move {
+                    let query = body["query"].as_str().unwrap_or_default().to_string();
+                    if query.contains("__schema") {
+                        return Json(json!({ "data": { "__schema": selection_schema() } }));
+                    }
+                    seen.lock().unwrap().push(query);
+                    Json(json!({ "data": {} }))
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let prov = provider(format!("http://{}", addr));
+        let transport = GraphQLTransport::new();
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools.len(), 4);
+
+        for tool in ["version", "me", "users", "search"] {
+            transport
+                .call_tool(tool, HashMap::new(), &prov)
+                .await
+                .unwrap();
+        }
+        let overridden = HashMap::from([("_selection".to_string(), json!("id name"))]);
+        transport
+            .call_tool("gql.users", overridden, &prov)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *queries.lock().unwrap(),
+            vec![
+                "query { version }",
+                "query { me { id name orders { sku qty } friend { id name } } }",
+                "query { users { id name orders { sku qty } friend { id name } } }",
+                "query { search { __typename } }",
+                "query { users { id name } }",
+            ]
+        );
+    }
+
+    fn input_value(name: &str, type_ref: Value, default: Option<&str>) -> Value {
+        json!({ "name": name, "description": null, "defaultValue": default, "type": type_ref })
+    }
+
+    fn input_schema() -> Value {
+        let create_user = json!({
+            "name": "createUser",
+            "description": "Create a user",
+            "args": [
+                input_value(
+                    "input",
+                    wrapped("NON_NULL", named("INPUT_OBJECT", "CreateUserInput")),
+                    None,
+                ),
+                input_value("role", named("ENUM", "Role"), Some("VIEWER")),
+            ],
+            "type": named("OBJECT", "User"),
+        });
+        json!({
+            "queryType": null,
+            "mutationType": { "name": "Mutation", "fields": [create_user] },
+            "subscriptionType": null,
+            "types": [
+                {
+                    "kind": "INPUT_OBJECT",
+                    "name": "CreateUserInput",
+                    "inputFields": [
+                        input_value("name", wrapped("NON_NULL", named("SCALAR", "String")), None),
+                        input_value(
+                            "tags",
+                            wrapped("LIST", wrapped("NON_NULL", named("SCALAR", "String"))),
+                            None,
+                        ),
+                        input_value("address", named("INPUT_OBJECT", "Address"), None),
+                    ],
+                },
+                {
+                    "kind": "ENUM",
+                    "name": "Role",
+                    "enumValues": [{ "name": "ADMIN" }, { "name": "VIEWER" }],
+                },
+                {
+                    "kind": "OBJECT",
+                    "name": "User",
+                    "fields": [field("id", named("SCALAR", "ID"))],
+                },
+            ],
+        })
+    }
+
+    #[tokio::test]
+    async fn input_objects_and_enums_use_declared_types() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| {
+                let seen = seen.clone();
+                async move {
+                    if body["query"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .contains("__schema")
+                    {
+                        return Json(json!({ "data": { "__schema": input_schema() } }));
+                    }
+                    seen.lock().unwrap().push(body.clone());
+                    Json(json!({ "data": { "createUser": { "id": "u1" } } }))
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let prov = provider(format!("http://{}", addr));
+        let transport = GraphQLTransport::new();
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        let inputs = &tools[0].inputs;
+        assert_eq!(inputs.required, Some(vec!["input".to_string()]));
+        let properties = inputs.properties.as_ref().unwrap();
+        assert_eq!(
+            properties["input"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "address": { "type": "object" },
+                },
+                "required": ["name"],
+            })
+        );
+        assert_eq!(
+            properties["role"],
+            json!({ "type": "string", "enum": ["ADMIN", "VIEWER"] })
+        );
+
+        let input = json!({ "name": "Ada", "tags": ["admin"], "address": { "city": "London" } });
+        let args = HashMap::from([
+            ("input".to_string(), input.clone()),
+            ("role".to_string(), json!("ADMIN")),
+        ]);
+        let result = transport
+            .call_tool("createUser", args, &prov)
+            .await
+            .unwrap();
+        assert_eq!(result["createUser"]["id"], "u1");
+
+        let body = requests.lock().unwrap()[0].clone();
+        assert_eq!(
+            body["query"],
+            "mutation createUser($input: CreateUserInput!, $role: Role) \
+             { createUser(input: $input, role: $role) { id } }"
+        );
+        assert_eq!(
+            body["variables"],
+            json!({ "input": input, "role": "ADMIN" })
+        );
+    }
+
+    #[tokio::test]
+    async fn persisted_queries_resend_text_when_hash_is_unknown() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let known = Arc::new(std::sync::Mutex::new(std::collections::HashSet::new()));
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| {
+                let seen = seen.clone();
+                let known = known.clone();
+                async move {
+                    seen.lock().unwrap().push(body.clone());
+                    let hash = body["extensions"]["persistedQuery"]["sha256Hash"]
+                        .as_str()
+                        .expect("persisted query hash")
+                        .to_string();
+                    match body["query"].as_str() {
+                        Some(query) => {
+                            assert_eq!(hash, format!("{:x}", Sha256::digest(query.as_bytes())));
+                            known.lock().unwrap().insert(hash);
+                        }
+                        None if !known.lock().unwrap().contains(&hash) => {
+                            return Json(json!({
+                                "errors": [{
+                                    "message": "PersistedQueryNotFound",
+                                    "extensions": { "code": "PERSISTED_QUERY_NOT_FOUND" },
+                                }]
+                            }));
+                        }
+                        None => {}
+                    }
+                    Json(json!({ "data": { "status": "ok" } }))
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let mut prov = provider(format!("http://{}", addr));
+        prov.persisted_queries = true;
+        let transport = GraphQLTransport::new();
+        for _ in 0..2 {
+            let result = transport
+                .call_tool("status", HashMap::new(), &prov)
+                .await
+                .unwrap();
+            assert_eq!(result, json!({ "status": "ok" }));
+        }
+
+        let requests = requests.lock().unwrap();
+        let has_query: Vec<bool> = requests.iter().map(|r| r.get("query").is_some()).collect();
+        assert_eq!(has_query, vec![false, true, false]);
+        assert_eq!(requests[1]["query"], "query { status }");
+    }
+
+    #[tokio::test]
+    async fn raw_query_runs_verbatim() {
+        let document = "query Dashboard($id: ID!) { user(id: $id) { name } stats { count } } \
+                        query Other { version }";
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| async move {
+                assert_eq!(body["query"], document);
+                assert_eq!(body["operationName"], "Dashboard");
+                assert_eq!(body["variables"], json!({ "id": "u1" }));
+                Json(json!({ "data": { "user": { "name": "Ada" }, "stats": { "count": 3 } } }))
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let prov = provider(format!("http://{}", addr));
+        let transport = GraphQLTransport::new();
+        let mut args = HashMap::from([("_query".to_string(), json!(document))]);
+        let err = transport
+            .call_tool("gql.anything", args.clone(), &prov)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("_operation_name"), "{err}");
+
+        args.insert("_operation_name".to_string(), json!("Dashboard"));
+        args.insert("_variables".to_string(), json!({ "id": "u1" }));
+        let result = transport
+            .call_tool("gql.anything", args, &prov)
+            .await
+            .unwrap();
+        assert_eq!(result["user"]["name"], "Ada");
+        assert_eq!(result["stats"]["count"], 3);
+    }
+
+    #[tokio::test]
+    async fn raw_subscription_streams_through_passthrough() {
+        use futures_util::SinkExt;
+
+        let document = "subscription OnPrice($symbol: String!) { price(symbol: $symbol) }";
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            assert_eq!(recv_json(&mut ws).await["type"], "connection_init");
+            let ack = json!({ "type": "connection_ack" });
+            ws.send(Message::Text(ack.to_string())).await.unwrap();
+            let subscribe = recv_json(&mut ws).await;
+            assert_eq!(subscribe["payload"]["query"], document);
+            assert_eq!(
+                subscribe["payload"]["variables"],
+                json!({ "symbol": "ACME" })
+            );
+            let id = subscribe["id"].clone();
+            for price in [1, 2] {
+                let next = json!({
+                    "id": id,
+                    "type": "next",
+                    "payload": { "data": { "price": price } },
+                });
+                ws.send(Message::Text(next.to_string())).await.unwrap();
+            }
+            let complete = json!({ "id": id, "type": "complete" });
+            ws.send(Message::Text(complete.to_string())).await.unwrap();
+        });
+
+        // The provider's own operation type does not apply to passthrough documents.
+        let prov = provider(format!("http://{}", addr));
+        let args = HashMap::from([
+            ("_query".to_string(), json!(document)),
+            ("_variables".to_string(), json!({ "symbol": "ACME" })),
+        ]);
+        let transport = GraphQLTransport::new();
+        let mut stream = transport
+            .call_tool_stream("gql.prices", args, &prov)
+            .await
+            .expect("stream created");
+        let mut prices = Vec::new();
+        while let Some(item) = stream.next().await.unwrap() {
+            prices.push(item["price"].clone());
+        }
+        assert_eq!(prices, vec![json!(1), json!(2)]);
+    }
+
+    #[tokio::test]
+    async fn errors_are_structured_and_partial_data_is_optional() {
+        let app = Router::new().route(
+            "/",
+            post(|| async {
+                Json(json!({
+                    "data": { "user": { "name": "Ada", "friends": null } },
+                    "errors": [{
+                        "message": "friends unavailable",
+                        "locations": [{ "line": 1, "column": 21 }],
+                        "path": ["user", "friends"],
+                        "extensions": { "code": "UNAVAILABLE" },
+                    }],
+                }))
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let mut prov = provider(format!("http://{}", addr));
+        let transport = GraphQLTransport::new();
+        let err = transport
+            .call_tool("user", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "GraphQL errors: friends unavailable");
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::GraphQL {
+                errors,
+                partial_data,
+            }) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].code(), Some("UNAVAILABLE"));
+                assert_eq!(errors[0].path, Some(vec![json!("user"), json!("friends")]));
+                assert_eq!(errors[0].locations[0].column, 21);
+                assert_eq!(partial_data.as_ref().unwrap()["user"]["name"], "Ada");
+            }
+            other => panic!("expected a GraphQL error, got {other:?}"),
+        }
+
+        prov.allow_partial_data = true;
+        let result = transport
+            .call_tool("user", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(result["user"]["name"], "Ada");
+        assert_eq!(result["_errors"][0]["message"], "friends unavailable");
+        assert_eq!(result["_errors"][0]["extensions"]["code"], "UNAVAILABLE");
+    }
+
+    #[tokio::test]
+    async fn subscription_errors_are_structured() {
+        use futures_util::SinkExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            recv_json(&mut ws).await;
+            let ack = json!({ "type": "connection_ack" });
+            ws.send(Message::Text(ack.to_string())).await.unwrap();
+            let id = recv_json(&mut ws).await["id"].clone();
+            let error = json!({
+                "id": id,
+                "type": "error",
+                "payload": [{ "message": "not allowed", "extensions": { "code": "FORBIDDEN" } }],
+            });
+            ws.send(Message::Text(error.to_string())).await.unwrap();
+        });
+
+        let mut prov = provider(format!("http://{}", addr));
+        prov.operation_type = "subscription".to_string();
+        let transport = GraphQLTransport::new();
+        let mut stream = transport
+            .call_tool_stream("secrets", HashMap::new(), &prov)
+            .await
+            .expect("stream created");
+        let err = stream.next().await.unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::GraphQL { errors, .. }) => {
+                assert_eq!(errors[0].message, "not allowed");
+                assert_eq!(errors[0].code(), Some("FORBIDDEN"));
+            }
+            other => panic!("expected a GraphQL error, got {other:?}"),
+        }
+    }
+
+    /// Read messages until one of type `kind`, skipping anything else.
+    async fn recv_type(
+        ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+        kind: &str,
+    ) -> Value {
+        loop {
+            let msg = recv_json(ws).await;
+            if msg["type"] == kind {
+                return msg;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn subscriptions_answer_pings_and_resubscribe_after_a_drop() {
+        use futures_util::SinkExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for price in [1, 2] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                recv_type(&mut ws, "connection_init").await;
+                let ack = json!({ "type": "connection_ack" });
+                ws.send(Message::Text(ack.to_string())).await.unwrap();
+                let id = recv_type(&mut ws, "subscribe").await["id"].clone();
+                if price == 1 {
+                    // The server pings and expects a pong.
+                    let ping = json!({ "type": "ping" });
+                    ws.send(Message::Text(ping.to_string())).await.unwrap();
+                    recv_type(&mut ws, "pong").await;
+                } else {
+                    // The client pings on its own interval.
+                    recv_type(&mut ws, "ping").await;
+                }
+                let next = json!({
+                    "id": id,
+                    "type": "next",
+                    "payload": { "data": { "price": price } },
+                });
+                ws.send(Message::Text(next.to_string())).await.unwrap();
+                if price == 2 {
+                    let complete = json!({ "id": id, "type": "complete" });
+                    ws.send(Message::Text(complete.to_string())).await.unwrap();
+                }
+                // Dropping the socket without a close frame ends the first connection.
+            }
+        });
+
+        let mut prov = provider(format!("http://{}", addr));
+        prov.operation_type = "subscription".to_string();
+        prov.ping_interval_ms = Some(20);
+        prov.reconnect = Some(crate::providers::graphql::GraphqlReconnect {
+            max_attempts: 2,
+            backoff_ms: 10,
+        });
+        let transport = GraphQLTransport::new();
+        let mut stream = transport
+            .call_tool_stream("price", HashMap::new(), &prov)
+            .await
+            .expect("stream created");
+        let mut prices = Vec::new();
+        while let Some(item) = stream.next().await.unwrap() {
+            prices.push(item["price"].clone());
+        }
+        assert_eq!(prices, vec![json!(1), json!(2)]);
+    }
+
+    #[tokio::test]
+    async fn silent_subscriptions_time_out() {
+        use futures_util::SinkExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            recv_type(&mut ws, "connection_init").await;
+            let ack = json!({ "type": "connection_ack" });
+            ws.send(Message::Text(ack.to_string())).await.unwrap();
+            recv_type(&mut ws, "subscribe").await;
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        });
+
+        let mut prov = provider(format!("http://{}", addr));
+        prov.operation_type = "subscription".to_string();
+        prov.idle_timeout_ms = Some(100);
+        let transport = GraphQLTransport::new();
+        let mut stream = transport
+            .call_tool_stream("price", HashMap::new(), &prov)
+            .await
+            .expect("stream created");
+        let err = stream.next().await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+    }
+
+    async fn recv_json(
+        ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    ) -> Value {
+        use futures_util::StreamExt;
+        match ws.next().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected message {other:?}"),
+        }
+    }
+
+    /// Serve one subscription over the legacy `graphql-ws` protocol only, answering the
+    /// handshake with that subprotocol.
+    async fn legacy_subscription_server() -> std::net::SocketAddr {
+        use futures_util::SinkExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = test_websocket::accept(stream, |req, resp| {
+                let offered = req.headers()["sec-websocket-protocol"].to_str().unwrap();
+                assert!(offered.contains("graphql-ws"), "offered {offered}");
+                resp.headers_mut()
+                    .insert("sec-websocket-protocol", "graphql-ws".parse().unwrap());
+            })
+            .await;
+            assert_eq!(recv_json(&mut ws).await["type"], "connection_init");
+            for reply in [json!({ "type": "connection_ack" }), json!({ "type": "ka" })] {
+                ws.send(Message::Text(reply.to_string())).await.unwrap();
+            }
+            let start = recv_json(&mut ws).await;
+            assert_eq!(start["type"], "start");
+            assert!(start["payload"]["query"]
+                .as_str()
+                .unwrap()
+                .contains("priceChanged"));
+            let id = start["id"].clone();
+            for price in [10, 11] {
+                let data = json!({
+                    "id": id,
+                    "type": "data",
+                    "payload": { "data": { "priceChanged": { "price": price } } },
+                });
+                ws.send(Message::Text(data.to_string())).await.unwrap();
+            }
+            let complete = json!({ "id": id, "type": "complete" });
+            ws.send(Message::Text(complete.to_string())).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn legacy_graphql_ws_subscriptions_stream_data() {
+        for protocol in [GraphqlWsProtocol::GraphqlWs, GraphqlWsProtocol::Auto] {
+            let addr = legacy_subscription_server().await;
+            let mut prov = provider(format!("http://{}", addr));
+            prov.operation_type = "subscription".to_string();
+            prov.ws_protocol = protocol;
+
+            let transport = GraphQLTransport::new();
+            let mut stream = transport
+                .call_tool_stream("priceChanged", HashMap::new(), &prov)
+                .await
+                .expect("stream created");
+            let mut prices = Vec::new();
+            while let Some(item) = stream.next().await.unwrap() {
+                prices.push(item["priceChanged"]["price"].clone());
+            }
+            assert_eq!(prices, vec![json!(10), json!(11)], "{protocol:?}");
+        }
+    }
+
     #[tokio::test]
     async fn graphql_subscription_streams_data() {
         use futures_util::{SinkExt, StreamExt};
@@ -819,6 +1498,16 @@ mod tests {
             operation_type: "subscription".to_string(),
             operation_name: Some("MessageAdded".to_string()),
             headers: None,
+            timeouts: None,
+            proxy: None,
+            tls: None,
+            selection_depth: crate::providers::graphql::DEFAULT_SELECTION_DEPTH,
+            ws_protocol: Default::default(),
+            persisted_queries: false,
+            allow_partial_data: false,
+            ping_interval_ms: None,
+            idle_timeout_ms: None,
+            reconnect: None,
         };
 
         let transport = GraphQLTransport::new();
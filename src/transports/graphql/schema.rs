@@ -0,0 +1,226 @@
+//! Reading introspection results: root field arguments and return types, the selection sets
+//! generated queries need, and the input schemas tools advertise.
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::tools::ToolInputOutputSchema;
+
+/// Introspection query sent by `register_tool_provider`. Type references are unwrapped four
+/// levels deep, enough for wrappers such as `[[Item!]!]!`.
+pub(crate) const INTROSPECTION_QUERY: &str = r#"
+query IntrospectionQuery {
+  __schema {
+    queryType { name fields { ...FieldInfo } }
+    mutationType { name fields { ...FieldInfo } }
+    subscriptionType { name fields { ...FieldInfo } }
+    types {
+      kind
+      name
+      fields { ...FieldInfo }
+      inputFields { ...InputValue }
+      enumValues { name }
+    }
+  }
+}
+
+fragment FieldInfo on __Field {
+  name
+  description
+  args { ...InputValue }
+  type { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  defaultValue
+  type { ...TypeRef }
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}"#;
+
+/// Named types from an introspection result, used to expand object fields.
+pub(crate) struct Schema<'a> {
+    types: HashMap<&'a str, &'a Value>,
+}
+
+impl<'a> Schema<'a> {
+    pub(crate) fn new(schema: &'a Value) -> Self {
+        let types = schema
+            .get("types")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|ty| Some((ty.get("name")?.as_str()?, ty)))
+            .collect();
+        Self { types }
+    }
+
+    /// Selection set for a field of type `type_ref`, nesting at most `depth` levels.
+    ///
+    /// Scalars and enums (and fields whose type was not reported) need none. Object types
+    /// select their argument-free scalar fields and, while depth remains, their object fields;
+    /// when nothing can be selected the set falls back to `__typename`.
+    pub(crate) fn selection_set(&self, type_ref: &Value, depth: usize) -> Option<String> {
+        let named = named_type(type_ref);
+        if !is_composite(named) {
+            return None;
+        }
+
+        let fields = named
+            .get("name")
+            .and_then(Value::as_str)
+            .and_then(|name| self.types.get(name))
+            .and_then(|ty| ty.get("fields"))
+            .and_then(Value::as_array);
+        let mut selected = Vec::new();
+        for field in fields.into_iter().flatten() {
+            let name = match field.get("name").and_then(Value::as_str) {
+                Some(name) if !has_required_args(field) => name,
+                _ => continue,
+            };
+            let field_type = field.get("type").unwrap_or(&Value::Null);
+            if !is_composite(named_type(field_type)) {
+                selected.push(name.to_string());
+            } else if depth > 1 {
+                if let Some(nested) = self.selection_set(field_type, depth - 1) {
+                    selected.push(format!("{} {}", name, nested));
+                }
+            }
+        }
+        if selected.is_empty() {
+            selected.push("__typename".to_string());
+        }
+        Some(format!("{{ {} }}", selected.join(" ")))
+    }
+}
+
+impl Schema<'_> {
+    /// Tool input schema for the arguments of root field `field`. Input object arguments list
+    /// their own fields one level deep.
+    pub(crate) fn input_schema(&self, field: &Value) -> ToolInputOutputSchema {
+        let (properties, required) = self.object_properties(field.get("args"), 1);
+        ToolInputOutputSchema {
+            properties: (!properties.is_empty()).then(|| properties.into_iter().collect()),
+            required: (!required.is_empty()).then_some(required),
+            ..ToolInputOutputSchema::new("object")
+        }
+    }
+
+    /// Property schemas and required names for a list of `__InputValue`s.
+    fn object_properties(
+        &self,
+        values: Option<&Value>,
+        depth: usize,
+    ) -> (Map<String, Value>, Vec<String>) {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for value in values.and_then(Value::as_array).into_iter().flatten() {
+            let name = match value.get("name").and_then(Value::as_str) {
+                Some(name) => name,
+                None => continue,
+            };
+            let type_ref = value.get("type").unwrap_or(&Value::Null);
+            let mut schema = self.value_schema(type_ref, depth);
+            if let Some(description) = value.get("description").and_then(Value::as_str) {
+                schema["description"] = json!(description);
+            }
+            if is_required(value) {
+                required.push(name.to_string());
+            }
+            properties.insert(name.to_string(), schema);
+        }
+        (properties, required)
+    }
+
+    /// JSON Schema for a value of type `type_ref`; input objects expand `depth` levels.
+    fn value_schema(&self, type_ref: &Value, depth: usize) -> Value {
+        let inner = type_ref.get("ofType").unwrap_or(&Value::Null);
+        let name = type_ref.get("name").and_then(Value::as_str);
+        match kind(type_ref) {
+            Some("NON_NULL") => self.value_schema(inner, depth),
+            Some("LIST") => json!({ "type": "array", "items": self.value_schema(inner, depth) }),
+            Some("ENUM") => {
+                let values: Vec<Value> = name
+                    .and_then(|name| self.types.get(name))
+                    .and_then(|ty| ty.get("enumValues"))
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|value| value.get("name").cloned())
+                    .collect();
+                json!({ "type": "string", "enum": values })
+            }
+            Some("INPUT_OBJECT") => {
+                let mut schema = json!({ "type": "object" });
+                if depth > 0 {
+                    let fields = name
+                        .and_then(|name| self.types.get(name))
+                        .and_then(|ty| ty.get("inputFields"));
+                    let (properties, required) = self.object_properties(fields, depth - 1);
+                    schema["properties"] = Value::Object(properties);
+                    if !required.is_empty() {
+                        schema["required"] = json!(required);
+                    }
+                }
+                schema
+            }
+            _ => match name {
+                Some("Int") => json!({ "type": "integer" }),
+                Some("Float") => json!({ "type": "number" }),
+                Some("Boolean") => json!({ "type": "boolean" }),
+                Some("String" | "ID") => json!({ "type": "string" }),
+                _ => json!({}),
+            },
+        }
+    }
+}
+
+/// The GraphQL spelling of a type reference, such as `[ID!]!`, for variable definitions.
+pub(crate) fn type_literal(type_ref: &Value) -> Option<String> {
+    match kind(type_ref)? {
+        "NON_NULL" => Some(format!("{}!", type_literal(type_ref.get("ofType")?)?)),
+        "LIST" => Some(format!("[{}]", type_literal(type_ref.get("ofType")?)?)),
+        _ => type_ref.get("name")?.as_str().map(str::to_string),
+    }
+}
+
+/// Strip `NON_NULL` and `LIST` wrappers from a type reference.
+pub(crate) fn named_type(type_ref: &Value) -> &Value {
+    let mut current = type_ref;
+    while matches!(kind(current), Some("NON_NULL") | Some("LIST")) {
+        match current.get("ofType") {
+            Some(inner) if !inner.is_null() => current = inner,
+            _ => break,
+        }
+    }
+    current
+}
+
+fn kind(type_ref: &Value) -> Option<&str> {
+    type_ref.get("kind").and_then(Value::as_str)
+}
+
+fn is_composite(named: &Value) -> bool {
+    matches!(kind(named), Some("OBJECT" | "INTERFACE" | "UNION"))
+}
+
+/// Whether selecting `field` needs an argument the caller would have to supply.
+fn has_required_args(field: &Value) -> bool {
+    field
+        .get("args")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .any(is_required)
+}
+
+/// Whether an argument or input field is non-null without a default.
+fn is_required(value: &Value) -> bool {
+    kind(value.get("type").unwrap_or(&Value::Null)) == Some("NON_NULL")
+        && value.get("defaultValue").is_none_or(Value::is_null)
+}
@@ -0,0 +1,373 @@
+//! GraphQL subscriptions over WebSocket: the `graphql-transport-ws` and legacy `graphql-ws`
+//! handshakes, keepalive pings, idle detection and re-subscribing after a dropped connection.
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{Instant, Interval};
+use tokio_tungstenite::tungstenite::{
+    handshake::client::Response,
+    http::Request,
+    protocol::{frame::coding::CloseCode, Message},
+};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use super::GraphQLTransport;
+use crate::auth::AuthConfig;
+use crate::errors::{GraphQLErrorDetail, UtcpError};
+use crate::providers::graphql::{GraphqlProvider, GraphqlWsProtocol};
+use crate::transports::websocket::tls;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Id of the single subscription each connection carries.
+const SUBSCRIPTION_ID: &str = "1";
+
+/// An open subscription connection and the protocol it speaks.
+pub(crate) struct Connection {
+    stream: WsStream,
+    protocol: GraphqlWsProtocol,
+}
+
+/// Dial the provider, complete `connection_init`, and start the subscription in `payload`
+/// (`query`, `variables` and optionally `operationName`).
+pub(crate) async fn subscribe(prov: &GraphqlProvider, payload: &Value) -> Result<Connection> {
+    let (mut ws_stream, response) = tls::handshake(request(prov)?, prov.tls.as_ref()).await?;
+    let protocol = negotiated_protocol(prov.ws_protocol, &response);
+
+    // Send connection_init message (shared by both protocols)
+    ws_stream
+        .send(Message::Text(
+            json!({
+                "type": "connection_init"
+            })
+            .to_string(),
+        ))
+        .await?;
+
+    // Wait for connection_ack; legacy servers may send keep-alives (`ka`) first
+    loop {
+        let text = match ws_stream.next().await {
+            Some(msg) => match msg? {
+                Message::Text(text) => text,
+                _ => return Err(anyhow!("Expected text message for connection_ack")),
+            },
+            None => return Err(anyhow!("Connection closed before connection_ack")),
+        };
+        let ack: Value = serde_json::from_str(&text)?;
+        match ack.get("type").and_then(|v| v.as_str()) {
+            Some("connection_ack") => break,
+            Some("ka") => continue,
+            _ => return Err(anyhow!("Expected connection_ack, got: {}", text)),
+        }
+    }
+
+    let start_type = match protocol {
+        GraphqlWsProtocol::GraphqlWs => "start",
+        _ => "subscribe",
+    };
+    let subscribe_msg = json!({
+        "id": SUBSCRIPTION_ID,
+        "type": start_type,
+        "payload": payload,
+    });
+    ws_stream
+        .send(Message::Text(subscribe_msg.to_string()))
+        .await?;
+
+    Ok(Connection {
+        stream: ws_stream,
+        protocol,
+    })
+}
+
+/// Feed subscription results into `tx` until the server completes the subscription or the
+/// receiver goes away. Drops are re-subscribed per the provider's `reconnect` policy.
+pub(crate) async fn forward(
+    mut connection: Connection,
+    prov: GraphqlProvider,
+    payload: Value,
+    tx: mpsc::Sender<Result<Value>>,
+) {
+    let name = &prov.base.name;
+    loop {
+        let dropped = match read(&mut connection, &prov, &tx).await {
+            Some(dropped) => dropped,
+            None => return,
+        };
+        let policy = match &prov.reconnect {
+            Some(policy) => policy,
+            None => {
+                let _ = tx.send(Err(dropped)).await;
+                return;
+            }
+        };
+        tracing::warn!(provider = %name, error = %dropped, "GraphQL subscription dropped");
+
+        let mut delay = Duration::from_millis(policy.backoff_ms);
+        let mut attempt = 0;
+        connection = loop {
+            if attempt == policy.max_attempts {
+                let _ = tx
+                    .send(Err(anyhow!(
+                        "GraphQL subscription dropped; gave up after {} reconnect attempts",
+                        policy.max_attempts
+                    )))
+                    .await;
+                return;
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            tracing::info!(provider = %name, attempt, "Re-subscribing GraphQL subscription");
+            match subscribe(&prov, &payload).await {
+                Ok(connection) => {
+                    tracing::info!(provider = %name, attempt, "GraphQL subscription reconnected");
+                    break connection;
+                }
+                Err(err) => {
+                    tracing::warn!(provider = %name, attempt, error = %err, "Reconnect failed");
+                }
+            }
+        };
+    }
+}
+
+/// Read one connection until it ends. Returns why it dropped, or `None` when the stream is
+/// finished: completed, failed with an error already sent, or no longer wanted.
+async fn read(
+    connection: &mut Connection,
+    prov: &GraphqlProvider,
+    tx: &mpsc::Sender<Result<Value>>,
+) -> Option<anyhow::Error> {
+    let data_type = match connection.protocol {
+        GraphqlWsProtocol::GraphqlWs => "data",
+        _ => "next",
+    };
+    let ws_stream = &mut connection.stream;
+    let mut pings = prov.ping_interval_ms.map(|ms| {
+        let period = Duration::from_millis(ms.max(1));
+        tokio::time::interval_at(Instant::now() + period, period)
+    });
+    let idle = prov.idle_timeout_ms.map(Duration::from_millis);
+    let mut deadline = idle.map(|idle| Instant::now() + idle);
+
+    loop {
+        let msg = tokio::select! {
+            msg = ws_stream.next() => msg,
+            _ = tick(&mut pings) => {
+                // graphql-transport-ws has protocol pings; the legacy protocol only has frames
+                let ping = match connection.protocol {
+                    GraphqlWsProtocol::GraphqlWs => Message::Ping(Vec::new()),
+                    _ => Message::Text(json!({ "type": "ping" }).to_string()),
+                };
+                if let Err(err) = ws_stream.send(ping).await {
+                    return Some(anyhow!("WebSocket error: {}", err));
+                }
+                continue;
+            }
+            _ = sleep_until(deadline) => {
+                let ms = idle.unwrap_or_default().as_millis();
+                return Some(
+                    UtcpError::Timeout(format!("no subscription message within {}ms", ms)).into(),
+                );
+            }
+        };
+        deadline = idle.map(|idle| Instant::now() + idle);
+
+        let text = match msg {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(frame))) => {
+                if frame.is_some_and(|frame| frame.code != CloseCode::Normal) {
+                    return Some(anyhow!("GraphQL subscription closed before complete"));
+                }
+                return None;
+            }
+            Some(Ok(_)) => continue, // Ignore binary, ping, pong
+            Some(Err(err)) => return Some(anyhow!("WebSocket error: {}", err)),
+            None => return Some(anyhow!("GraphQL subscription closed before complete")),
+        };
+        let parsed = match serde_json::from_str::<Value>(&text) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = tx
+                    .send(Err(anyhow!("Failed to parse WebSocket message: {}", e)))
+                    .await;
+                return None;
+            }
+        };
+
+        match parsed.get("type").and_then(|v| v.as_str()) {
+            Some(t) if t == data_type => {
+                // Payloads are GraphQL responses; errors end the stream
+                let payload = match parsed.get("payload") {
+                    Some(p) if p.get("data").is_some() || p.get("errors").is_some() => p.clone(),
+                    _ => continue,
+                };
+                let item = GraphQLTransport::response_data(payload, prov.allow_partial_data);
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    return None;
+                }
+            }
+            Some("error") => {
+                let errors = parsed
+                    .get("payload")
+                    .map(GraphQLErrorDetail::list_from_value)
+                    .unwrap_or_else(|| {
+                        vec![GraphQLErrorDetail::from_value(&json!(
+                            "Unknown subscription error"
+                        ))]
+                    });
+                let err = UtcpError::GraphQL {
+                    errors,
+                    partial_data: None,
+                };
+                let _ = tx.send(Err(err.into())).await;
+                return None;
+            }
+            Some("complete") => return None,
+            Some("ping") => {
+                let pong = json!({ "type": "pong" }).to_string();
+                if let Err(err) = ws_stream.send(Message::Text(pong)).await {
+                    return Some(anyhow!("WebSocket error: {}", err));
+                }
+            }
+            _ => {
+                // Ignore other message types (pong, ka, etc.)
+            }
+        }
+    }
+}
+
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The subprotocol to speak once the server has answered the handshake.
+fn negotiated_protocol(requested: GraphqlWsProtocol, response: &Response) -> GraphqlWsProtocol {
+    if requested != GraphqlWsProtocol::Auto {
+        return requested;
+    }
+    let chosen = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim);
+    match chosen {
+        Some("graphql-ws") => GraphqlWsProtocol::GraphqlWs,
+        _ => GraphqlWsProtocol::GraphqlTransportWs,
+    }
+}
+
+/// The handshake request for the provider's subscription endpoint, with its auth and headers.
+fn request(gql_prov: &GraphqlProvider) -> Result<Request<()>> {
+    // Convert HTTP URL to WebSocket URL
+    let mut ws_url = gql_prov
+        .url
+        .replace("http://", "ws://")
+        .replace("https://", "wss://");
+
+    // Handle query-based authentication
+    if let Some(AuthConfig::ApiKey(api_key)) = &gql_prov.base.auth {
+        if api_key.location.eq_ignore_ascii_case("query") {
+            let separator = if ws_url.contains('?') { "&" } else { "?" };
+            ws_url = format!(
+                "{}{}{}={}",
+                ws_url, separator, api_key.var_name, api_key.api_key
+            );
+        }
+    }
+
+    // Build the WebSocket request with proper headers
+    let mut req = Request::builder()
+        .uri(&ws_url)
+        .header("Host", ws_url.split('/').nth(2).unwrap_or("localhost"))
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header(
+            "Sec-WebSocket-Key",
+            tokio_tungstenite::tungstenite::handshake::client::generate_key(),
+        )
+        .header("Sec-WebSocket-Protocol", gql_prov.ws_protocol.as_str())
+        .body(())?;
+
+    // Apply authentication to WebSocket request (except query which was handled above)
+    if let Some(auth) = &gql_prov.base.auth {
+        match auth {
+            AuthConfig::ApiKey(api_key) => {
+                let location = api_key.location.to_ascii_lowercase();
+                match location.as_str() {
+                    "header" => {
+                        use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+                        let name = HeaderName::from_bytes(api_key.var_name.as_bytes())
+                            .map_err(|_| anyhow!("Invalid header name"))?;
+                        let value = HeaderValue::from_str(&api_key.api_key)
+                            .map_err(|_| anyhow!("Invalid header value"))?;
+                        req.headers_mut().insert(name, value);
+                    }
+                    "cookie" => {
+                        use tokio_tungstenite::tungstenite::http::HeaderValue;
+                        let cookie_value = format!("{}={}", api_key.var_name, api_key.api_key);
+                        let value = HeaderValue::from_str(&cookie_value)
+                            .map_err(|_| anyhow!("Invalid cookie value"))?;
+                        req.headers_mut().insert("cookie", value);
+                    }
+                    "query" => {
+                        // Already handled above
+                    }
+                    other => {
+                        return Err(anyhow!(
+                            "Unsupported API key location for WebSocket: {}",
+                            other
+                        ))
+                    }
+                }
+            }
+            AuthConfig::Basic(basic) => {
+                use tokio_tungstenite::tungstenite::http::HeaderValue;
+                let credentials = format!("{}:{}", basic.username, basic.password);
+                let encoded =
+                    base64::engine::general_purpose::STANDARD.encode(credentials.as_bytes());
+                let value = HeaderValue::from_str(&format!("Basic {}", encoded))
+                    .map_err(|_| anyhow!("Invalid auth header"))?;
+                req.headers_mut().insert("authorization", value);
+            }
+            AuthConfig::OAuth2(_) => {
+                return Err(anyhow!(
+                    "OAuth2 is not supported for GraphQL WebSocket subscriptions"
+                ));
+            }
+        }
+    }
+
+    // Apply custom headers if any
+    if let Some(headers) = &gql_prov.headers {
+        use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+        for (k, v) in headers {
+            let name = HeaderName::from_bytes(k.as_bytes())
+                .map_err(|_| anyhow!("Invalid header name: {}", k))?;
+            let value =
+                HeaderValue::from_str(v).map_err(|_| anyhow!("Invalid header value: {}", v))?;
+            req.headers_mut().insert(name, value);
+        }
+    }
+
+    Ok(req)
+}
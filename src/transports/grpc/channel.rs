@@ -0,0 +1,246 @@
+//! Channels shared by every call to a provider, the per-call deadline of `timeout_ms`, and the
+//! compression and message size limits applied to each client.
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::{http, Body, BoxFuture, Service, StdError};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Request, Status};
+
+use crate::errors::UtcpError;
+use crate::providers::grpc::{GrpcCompression, GrpcKeepalive, GrpcProvider};
+
+/// Provider settings a channel is built with; a change means the channel must be rebuilt.
+#[derive(Debug, Clone, PartialEq)]
+struct ChannelSettings {
+    uri: String,
+    use_ssl: bool,
+    grpc_web: bool,
+    timeout_ms: Option<u64>,
+    keepalive: Option<GrpcKeepalive>,
+    http2_adaptive_window: bool,
+}
+
+impl ChannelSettings {
+    fn of(prov: &GrpcProvider) -> Self {
+        let scheme = if prov.use_ssl { "https" } else { "http" };
+        Self {
+            uri: format!("{}://{}:{}", scheme, prov.host, prov.port),
+            use_ssl: prov.use_ssl,
+            grpc_web: prov.grpc_web,
+            timeout_ms: prov.timeout_ms,
+            keepalive: prov.keepalive.clone(),
+            http2_adaptive_window: prov.http2_adaptive_window,
+        }
+    }
+
+    async fn connect(&self) -> Result<GrpcChannel> {
+        if self.grpc_web {
+            #[cfg(feature = "grpc-web")]
+            return Ok(GrpcChannel::Web(Box::new(super::web::WebChannel::new(
+                &self.uri,
+                self.timeout_ms.map(Duration::from_millis),
+            )?)));
+            #[cfg(not(feature = "grpc-web"))]
+            return Err(anyhow::anyhow!(
+                "gRPC-Web provider at {} needs the `grpc-web` feature",
+                self.uri
+            ));
+        }
+        Ok(GrpcChannel::Native(self.endpoint()?.connect().await?))
+    }
+
+    fn endpoint(&self) -> Result<Endpoint> {
+        let mut endpoint = Endpoint::from_shared(self.uri.clone())?;
+        if self.use_ssl {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+        }
+        if let Some(ms) = self.timeout_ms {
+            endpoint = endpoint.connect_timeout(Duration::from_millis(ms));
+        }
+        if let Some(keepalive) = &self.keepalive {
+            endpoint = endpoint
+                .http2_keep_alive_interval(Duration::from_millis(keepalive.interval_ms))
+                .keep_alive_while_idle(keepalive.while_idle);
+            if let Some(ms) = keepalive.timeout_ms {
+                endpoint = endpoint.keep_alive_timeout(Duration::from_millis(ms));
+            }
+        }
+        if self.http2_adaptive_window {
+            endpoint = endpoint.http2_adaptive_window(true);
+        }
+        Ok(endpoint)
+    }
+}
+
+/// How calls reach a provider: a native HTTP/2 channel, or gRPC-Web over HTTP/1.1 when the
+/// provider sets `grpc_web`. Both carry the same generated clients.
+#[derive(Clone)]
+pub(crate) enum GrpcChannel {
+    Native(Channel),
+    #[cfg(feature = "grpc-web")]
+    Web(Box<super::web::WebChannel>),
+}
+
+impl Service<http::Request<BoxBody>> for GrpcChannel {
+    type Response = http::Response<BoxBody>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self {
+            Self::Native(channel) => channel.poll_ready(cx).map_err(Into::into),
+            #[cfg(feature = "grpc-web")]
+            Self::Web(channel) => channel.poll_ready(cx),
+        }
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        match self {
+            Self::Native(channel) => {
+                let response = channel.call(request);
+                Box::pin(async move {
+                    let response = response.await?;
+                    Ok(response.map(|body| {
+                        body.map_err(|err| Status::from_error(Box::new(err)))
+                            .boxed_unsync()
+                    }))
+                })
+            }
+            #[cfg(feature = "grpc-web")]
+            Self::Web(channel) => channel.call(request),
+        }
+    }
+}
+
+/// One channel per provider name, dialed on first use. Channels multiplex concurrent calls
+/// and reconnect on their own; a channel is only replaced when the provider's settings change
+/// or a call finds the server unavailable.
+#[derive(Default)]
+pub(crate) struct ChannelCache {
+    channels: Mutex<HashMap<String, (ChannelSettings, GrpcChannel)>>,
+}
+
+impl ChannelCache {
+    /// The channel for `prov`, dialing a new one when none is cached for its settings.
+    pub(crate) async fn get(&self, prov: &GrpcProvider) -> Result<GrpcChannel> {
+        let settings = ChannelSettings::of(prov);
+        if let Some((cached, channel)) = self.channels.lock().unwrap().get(&prov.base.name) {
+            if *cached == settings {
+                return Ok(channel.clone());
+            }
+        }
+
+        let channel = settings.connect().await?;
+        self.channels
+            .lock()
+            .unwrap()
+            .insert(prov.base.name.clone(), (settings, channel.clone()));
+        Ok(channel)
+    }
+
+    /// Drop the channel of `provider` so the next call dials again.
+    pub(crate) fn evict(&self, provider: &str) {
+        self.channels.lock().unwrap().remove(provider);
+    }
+}
+
+/// Run `call` for `tool` within the provider's `timeout_ms`, if any. The limit covers dialing
+/// as well as the call, so an unreachable backend fails as quickly as a slow one.
+pub(crate) async fn with_deadline<T>(
+    prov: &GrpcProvider,
+    tool: &str,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match prov.timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), call)
+            .await
+            .map_err(|_| deadline_error(prov, tool))?,
+        None => call.await,
+    }
+}
+
+/// Send the provider's deadline with a unary request so the server can give up too.
+pub(crate) fn set_deadline<T>(prov: &GrpcProvider, request: &mut Request<T>) {
+    if let Some(ms) = prov.timeout_ms {
+        request.set_timeout(Duration::from_millis(ms));
+    }
+}
+
+/// The encoding named by the provider's `compression`.
+pub(crate) fn compression(prov: &GrpcProvider) -> Result<Option<CompressionEncoding>> {
+    match prov.compression {
+        None => Ok(None),
+        Some(GrpcCompression::Gzip) => Ok(Some(CompressionEncoding::Gzip)),
+        #[cfg(feature = "grpc-zstd")]
+        Some(GrpcCompression::Zstd) => Ok(Some(CompressionEncoding::Zstd)),
+        #[cfg(not(feature = "grpc-zstd"))]
+        Some(GrpcCompression::Zstd) => Err(anyhow::anyhow!(
+            "gRPC provider '{}' uses zstd compression, which needs the `grpc-zstd` feature",
+            prov.base.name
+        )),
+    }
+}
+
+/// Convert the status of a failed call, reporting an expired deadline, whether noticed by the
+/// server or by the channel, as [`UtcpError::Timeout`], and an oversized message as a
+/// [`UtcpError::ToolCall`] naming its size and the limit it broke.
+pub(crate) fn status_error(prov: &GrpcProvider, tool: &str, status: Status) -> anyhow::Error {
+    let expired = status.code() == Code::DeadlineExceeded
+        || (status.code() == Code::Cancelled && status.message() == "Timeout expired");
+    if expired {
+        return deadline_error(prov, tool);
+    }
+    if status.code() == Code::OutOfRange {
+        if let Some((size, limit)) = oversized(status.message()) {
+            return oversize_error(prov, tool, size, limit);
+        }
+    }
+    status.into()
+}
+
+/// Refuse a request of `size` encoded bytes over `max_encoding_message_size`. tonic reports an
+/// oversized request only as a reset stream, so uncompressed requests are measured up front;
+/// a compressed request's size is not known until tonic compresses it.
+pub(crate) fn check_request_size(prov: &GrpcProvider, tool: &str, size: usize) -> Result<()> {
+    match prov.max_encoding_message_size {
+        Some(limit) if prov.compression.is_none() && size > limit => {
+            Err(oversize_error(prov, tool, size, limit))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn oversize_error(prov: &GrpcProvider, tool: &str, size: usize, limit: usize) -> anyhow::Error {
+    UtcpError::ToolCall(format!(
+        "gRPC provider '{}' tool '{}' exchanged a message of {} bytes, over the limit of {} \
+         bytes; raise max_decoding_message_size or max_encoding_message_size",
+        prov.base.name, tool, size, limit
+    ))
+    .into()
+}
+
+/// The size and limit in tonic's "message length too large" status, on either side of the call.
+fn oversized(message: &str) -> Option<(usize, usize)> {
+    let rest = message.split_once("message length too large: found ")?.1;
+    let (size, rest) = rest.split_once(" bytes, the limit is: ")?;
+    let limit = rest.strip_suffix(" bytes")?;
+    Some((size.parse().ok()?, limit.parse().ok()?))
+}
+
+fn deadline_error(prov: &GrpcProvider, tool: &str) -> anyhow::Error {
+    let limit = prov
+        .timeout_ms
+        .map(|ms| format!(" of {}ms", ms))
+        .unwrap_or_default();
+    UtcpError::Timeout(format!(
+        "gRPC provider '{}' tool '{}' missed its deadline{}",
+        prov.base.name, tool, limit
+    ))
+    .into()
+}
@@ -0,0 +1,892 @@
+//! Protobuf messages described at runtime by the file descriptors a server publishes through
+//! reflection: JSON arguments are encoded against a [`DescriptorPool`], responses are decoded
+//! back to JSON, and the same descriptors produce tool schemas.
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use bytes::{Buf, BufMut};
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::Status;
+
+use crate::tools::ToolInputOutputSchema;
+
+/// Levels of nested message fields expanded in generated schemas.
+const SCHEMA_DEPTH: usize = 3;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH_DELIMITED: u8 = 2;
+const FIXED32: u8 = 5;
+
+/// One method of a reflected service.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MethodInfo {
+    /// Fully-qualified service name, such as `pkg.Service`.
+    pub service: String,
+    pub name: String,
+    /// Fully-qualified input and output message names.
+    pub input: String,
+    pub output: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+impl MethodInfo {
+    /// The tool name the method is registered under: `pkg.Service.Method`.
+    pub(crate) fn tool_name(&self) -> String {
+        format!("{}.{}", self.service, self.name)
+    }
+
+    /// The request path the method is served at: `/pkg.Service/Method`.
+    pub(crate) fn path(&self) -> String {
+        format!("/{}/{}", self.service, self.name)
+    }
+
+    pub(crate) fn is_streaming(&self) -> bool {
+        self.client_streaming || self.server_streaming
+    }
+}
+
+/// Files, services, messages and enums by fully-qualified name (without the leading dot).
+#[derive(Debug, Default)]
+pub(crate) struct DescriptorPool {
+    files: HashMap<String, FileDescriptorProto>,
+    services: HashMap<String, ServiceDescriptorProto>,
+    messages: HashMap<String, DescriptorProto>,
+    enums: HashMap<String, EnumDescriptorProto>,
+}
+
+impl DescriptorPool {
+    /// Add a serialized `FileDescriptorProto`. Files already in the pool are ignored.
+    pub(crate) fn add_file(&mut self, bytes: &[u8]) -> Result<()> {
+        let file = FileDescriptorProto::decode(bytes)
+            .map_err(|e| anyhow!("Invalid file descriptor: {}", e))?;
+        if self.files.contains_key(file.name()) {
+            return Ok(());
+        }
+        let package = file.package().to_string();
+        for message in &file.message_type {
+            self.add_message(&package, message);
+        }
+        for enum_type in &file.enum_type {
+            self.enums
+                .insert(qualify(&package, enum_type.name()), enum_type.clone());
+        }
+        for service in &file.service {
+            self.services
+                .insert(qualify(&package, service.name()), service.clone());
+        }
+        self.files.insert(file.name().to_string(), file);
+        Ok(())
+    }
+
+    fn add_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let name = qualify(scope, message.name());
+        for nested in &message.nested_type {
+            self.add_message(&name, nested);
+        }
+        for enum_type in &message.enum_type {
+            self.enums
+                .insert(qualify(&name, enum_type.name()), enum_type.clone());
+        }
+        self.messages.insert(name, message.clone());
+    }
+
+    /// Files imported by the pool's files that have not been added yet.
+    pub(crate) fn missing_dependencies(&self) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .files
+            .values()
+            .flat_map(|file| file.dependency.iter())
+            .filter(|dependency| !self.files.contains_key(*dependency))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    /// The methods of `services`, in declaration order. Unknown services are skipped.
+    pub(crate) fn methods(&self, services: &[String]) -> Vec<MethodInfo> {
+        services
+            .iter()
+            .filter_map(|name| Some((name, self.services.get(name)?)))
+            .flat_map(|(name, service)| {
+                service.method.iter().map(move |method| MethodInfo {
+                    service: name.clone(),
+                    name: method.name().to_string(),
+                    input: trim(method.input_type()).to_string(),
+                    output: trim(method.output_type()).to_string(),
+                    client_streaming: method.client_streaming(),
+                    server_streaming: method.server_streaming(),
+                })
+            })
+            .collect()
+    }
+
+    fn message(&self, name: &str) -> Result<&DescriptorProto> {
+        self.messages
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown message type {}", name))
+    }
+
+    /// The synthetic entry message of a map field, or `None` for other fields.
+    fn map_entry(&self, field: &FieldDescriptorProto) -> Option<&DescriptorProto> {
+        if field.label() != Label::Repeated || field.r#type() != Type::Message {
+            return None;
+        }
+        self.messages
+            .get(trim(field.type_name()))
+            .filter(|message| message.options.as_ref().is_some_and(|o| o.map_entry()))
+    }
+}
+
+impl DescriptorPool {
+    /// Tool schema for message `name`: one property per field, nested messages expanded a few
+    /// levels deep.
+    pub(crate) fn schema(&self, name: &str) -> ToolInputOutputSchema {
+        let properties = self.properties(name, SCHEMA_DEPTH);
+        ToolInputOutputSchema {
+            properties: (!properties.is_empty()).then(|| properties.into_iter().collect()),
+            ..ToolInputOutputSchema::new("object")
+        }
+    }
+
+    fn properties(&self, name: &str, depth: usize) -> Map<String, Value> {
+        let fields = self.messages.get(name).map(|message| &message.field);
+        fields
+            .into_iter()
+            .flatten()
+            .map(|field| (field.name().to_string(), self.field_schema(field, depth)))
+            .collect()
+    }
+
+    fn field_schema(&self, field: &FieldDescriptorProto, depth: usize) -> Value {
+        if let Some(entry) = self.map_entry(field) {
+            let value = entry
+                .field
+                .iter()
+                .find(|f| f.number() == 2)
+                .map_or_else(|| json!({}), |value| self.value_schema(value, depth));
+            return json!({ "type": "object", "additionalProperties": value });
+        }
+        let schema = self.value_schema(field, depth);
+        if field.label() == Label::Repeated {
+            json!({ "type": "array", "items": schema })
+        } else {
+            schema
+        }
+    }
+
+    fn value_schema(&self, field: &FieldDescriptorProto, depth: usize) -> Value {
+        match field.r#type() {
+            Type::Double | Type::Float => json!({ "type": "number" }),
+            Type::Int32 | Type::Sint32 | Type::Sfixed32 | Type::Uint32 | Type::Fixed32 => {
+                json!({ "type": "integer" })
+            }
+            // 64-bit integers may also be given as strings, as in the protobuf JSON mapping.
+            Type::Int64 | Type::Sint64 | Type::Sfixed64 | Type::Uint64 | Type::Fixed64 => {
+                json!({ "type": "integer", "format": "int64" })
+            }
+            Type::Bool => json!({ "type": "boolean" }),
+            Type::String => json!({ "type": "string" }),
+            Type::Bytes => json!({ "type": "string", "format": "byte" }),
+            Type::Enum => {
+                let values: Vec<&str> = self
+                    .enums
+                    .get(trim(field.type_name()))
+                    .into_iter()
+                    .flat_map(|e| e.value.iter().map(|value| value.name()))
+                    .collect();
+                json!({ "type": "string", "enum": values })
+            }
+            Type::Message | Type::Group => {
+                let mut schema = json!({ "type": "object" });
+                if depth > 0 {
+                    let properties = self.properties(trim(field.type_name()), depth - 1);
+                    schema["properties"] = Value::Object(properties);
+                }
+                schema
+            }
+        }
+    }
+}
+
+impl DescriptorPool {
+    /// Encode a JSON object as message `name`. Keys may use either the proto field name or
+    /// its JSON name; `null` values are left unset.
+    pub(crate) fn encode(&self, name: &str, value: &Value) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.encode_message(name, value, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn encode_message(&self, name: &str, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+        let message = self.message(name)?;
+        let object = value
+            .as_object()
+            .ok_or_else(|| anyhow!("Expected an object for {}, got {}", name, value))?;
+        for (key, value) in object {
+            let field = message
+                .field
+                .iter()
+                .find(|field| field.name() == key || json_name(field) == *key)
+                .ok_or_else(|| anyhow!("{} has no field '{}'", name, key))?;
+            if value.is_null() {
+                continue;
+            }
+            if self.map_entry(field).is_some() {
+                let entries = value
+                    .as_object()
+                    .ok_or_else(|| anyhow!("Expected an object for map field '{}'", key))?;
+                for (entry_key, entry_value) in entries {
+                    let entry = json!({ "key": entry_key, "value": entry_value });
+                    let mut nested = Vec::new();
+                    self.encode_message(trim(field.type_name()), &entry, &mut nested)?;
+                    encode_key(field.number(), LENGTH_DELIMITED, buf);
+                    encode_bytes(&nested, buf);
+                }
+            } else if field.label() == Label::Repeated {
+                let items = value
+                    .as_array()
+                    .ok_or_else(|| anyhow!("Expected an array for repeated field '{}'", key))?;
+                for item in items {
+                    self.encode_field(field, item, buf)?;
+                }
+            } else {
+                self.encode_field(field, value, buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_field(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let invalid = || anyhow!("Invalid value for field '{}': {}", field.name(), value);
+        let ty = field.r#type();
+        encode_key(field.number(), wire_type(ty), buf);
+        match ty {
+            Type::Double => buf.put_f64_le(float(value).ok_or_else(invalid)?),
+            Type::Float => buf.put_f32_le(float(value).ok_or_else(invalid)? as f32),
+            Type::Int64 => encode_varint(signed(value).ok_or_else(invalid)? as u64, buf),
+            Type::Int32 => {
+                let number = signed(value).and_then(|n| i32::try_from(n).ok());
+                encode_varint(number.ok_or_else(invalid)? as i64 as u64, buf)
+            }
+            Type::Uint64 => encode_varint(unsigned(value).ok_or_else(invalid)?, buf),
+            Type::Uint32 => {
+                let number = unsigned(value).and_then(|n| u32::try_from(n).ok());
+                encode_varint(u64::from(number.ok_or_else(invalid)?), buf)
+            }
+            Type::Sint64 => encode_varint(zigzag(signed(value).ok_or_else(invalid)?), buf),
+            Type::Sint32 => {
+                let number = signed(value).filter(|n| i32::try_from(*n).is_ok());
+                encode_varint(zigzag(number.ok_or_else(invalid)?), buf)
+            }
+            Type::Fixed64 => buf.put_u64_le(unsigned(value).ok_or_else(invalid)?),
+            Type::Sfixed64 => buf.put_i64_le(signed(value).ok_or_else(invalid)?),
+            Type::Fixed32 => {
+                let number = unsigned(value).and_then(|n| u32::try_from(n).ok());
+                buf.put_u32_le(number.ok_or_else(invalid)?)
+            }
+            Type::Sfixed32 => {
+                let number = signed(value).and_then(|n| i32::try_from(n).ok());
+                buf.put_i32_le(number.ok_or_else(invalid)?)
+            }
+            Type::Bool => encode_varint(u64::from(boolean(value).ok_or_else(invalid)?), buf),
+            Type::Enum => {
+                let number = self.enum_number(field, value).ok_or_else(invalid)?;
+                encode_varint(number as i64 as u64, buf)
+            }
+            Type::String => encode_bytes(value.as_str().ok_or_else(invalid)?.as_bytes(), buf),
+            Type::Bytes => {
+                let decoded = base64::engine::general_purpose::STANDARD
+                    .decode(value.as_str().ok_or_else(invalid)?)
+                    .map_err(|_| invalid())?;
+                encode_bytes(&decoded, buf)
+            }
+            Type::Message => {
+                let mut nested = Vec::new();
+                self.encode_message(trim(field.type_name()), value, &mut nested)?;
+                encode_bytes(&nested, buf)
+            }
+            Type::Group => return Err(anyhow!("Group field '{}' is not supported", field.name())),
+        }
+        Ok(())
+    }
+
+    /// The number of an enum value given by name or number.
+    fn enum_number(&self, field: &FieldDescriptorProto, value: &Value) -> Option<i32> {
+        match value {
+            Value::String(name) => self
+                .enums
+                .get(trim(field.type_name()))?
+                .value
+                .iter()
+                .find(|value| value.name() == name)
+                .map(|value| value.number()),
+            other => signed(other).and_then(|n| i32::try_from(n).ok()),
+        }
+    }
+}
+
+/// A field value read off the wire, before its declared type is applied.
+#[derive(Debug, Clone, Copy)]
+enum Raw<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    Bytes(&'a [u8]),
+}
+
+impl DescriptorPool {
+    /// Decode message `name` to a JSON object keyed by proto field name. Fields the server
+    /// left at their default are omitted, and unknown fields are skipped.
+    pub(crate) fn decode(&self, name: &str, bytes: &[u8]) -> Result<Value> {
+        let message = self.message(name)?;
+        let mut object = Map::new();
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let key = read_varint(&mut bytes)?;
+            let raw = read_raw(&mut bytes, (key & 7) as u8)?;
+            let field = match message.field.iter().find(|f| f.number() as u64 == key >> 3) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            if let Some(entry) = self.map_entry(field) {
+                let decoded = match raw {
+                    Raw::Bytes(entry_bytes) => self.decode(trim(field.type_name()), entry_bytes)?,
+                    _ => return Err(wire_type_error(field)),
+                };
+                let entry_key = decoded
+                    .get("key")
+                    .cloned()
+                    .unwrap_or_else(|| self.default_value(&entry.field, 1));
+                let entry_key = match entry_key {
+                    Value::String(key) => key,
+                    other => other.to_string(),
+                };
+                let entry_value = decoded
+                    .get("value")
+                    .cloned()
+                    .unwrap_or_else(|| self.default_value(&entry.field, 2));
+                if let Value::Object(map) = object.entry(field.name()).or_insert_with(|| json!({}))
+                {
+                    map.insert(entry_key, entry_value);
+                }
+            } else if field.label() == Label::Repeated {
+                let values = match raw {
+                    Raw::Bytes(mut packed) if wire_type(field.r#type()) != LENGTH_DELIMITED => {
+                        let mut values = Vec::new();
+                        while !packed.is_empty() {
+                            let item = read_raw(&mut packed, wire_type(field.r#type()))?;
+                            values.push(self.decode_field(field, item)?);
+                        }
+                        values
+                    }
+                    raw => vec![self.decode_field(field, raw)?],
+                };
+                if let Value::Array(items) = object.entry(field.name()).or_insert_with(|| json!([]))
+                {
+                    items.extend(values);
+                }
+            } else {
+                object.insert(field.name().to_string(), self.decode_field(field, raw)?);
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn decode_field(&self, field: &FieldDescriptorProto, raw: Raw<'_>) -> Result<Value> {
+        Ok(match (field.r#type(), raw) {
+            (Type::Double, Raw::Fixed64(bits)) => float_value(f64::from_bits(bits)),
+            (Type::Float, Raw::Fixed32(bits)) => float_value(f64::from(f32::from_bits(bits))),
+            (Type::Int64, Raw::Varint(v)) => json!(v as i64),
+            (Type::Int32, Raw::Varint(v)) => json!(v as i32),
+            (Type::Uint64, Raw::Varint(v)) => json!(v),
+            (Type::Uint32, Raw::Varint(v)) => json!(v as u32),
+            (Type::Sint64 | Type::Sint32, Raw::Varint(v)) => json!(unzigzag(v)),
+            (Type::Bool, Raw::Varint(v)) => json!(v != 0),
+            (Type::Enum, Raw::Varint(v)) => self.enum_name(field, v as i32),
+            (Type::Fixed64, Raw::Fixed64(v)) => json!(v),
+            (Type::Sfixed64, Raw::Fixed64(v)) => json!(v as i64),
+            (Type::Fixed32, Raw::Fixed32(v)) => json!(v),
+            (Type::Sfixed32, Raw::Fixed32(v)) => json!(v as i32),
+            (Type::String, Raw::Bytes(bytes)) => json!(String::from_utf8_lossy(bytes)),
+            (Type::Bytes, Raw::Bytes(bytes)) => {
+                json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+            }
+            (Type::Message, Raw::Bytes(bytes)) => self.decode(trim(field.type_name()), bytes)?,
+            _ => return Err(wire_type_error(field)),
+        })
+    }
+
+    /// The name of an enum value, or its number when the descriptor does not list it.
+    fn enum_name(&self, field: &FieldDescriptorProto, number: i32) -> Value {
+        self.enums
+            .get(trim(field.type_name()))
+            .and_then(|e| e.value.iter().find(|value| value.number() == number))
+            .map_or_else(|| json!(number), |value| json!(value.name()))
+    }
+
+    /// The proto3 default for field `number` of `fields`, which the wire omits.
+    fn default_value(&self, fields: &[FieldDescriptorProto], number: i32) -> Value {
+        let field = match fields.iter().find(|field| field.number() == number) {
+            Some(field) => field,
+            None => return Value::Null,
+        };
+        match field.r#type() {
+            Type::Bool => json!(false),
+            Type::String | Type::Bytes => json!(""),
+            Type::Enum => self.enum_name(field, 0),
+            Type::Message | Type::Group => json!({}),
+            Type::Double | Type::Float => json!(0.0),
+            _ => json!(0),
+        }
+    }
+}
+
+/// Passes message bodies that are already encoded through tonic unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Vec<u8>;
+    type Decode = Vec<u8>;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RawCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawCodec
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Vec<u8>>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining()).to_vec()))
+    }
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Type references in descriptors are fully qualified with a leading dot.
+fn trim(type_name: &str) -> &str {
+    type_name.strip_prefix('.').unwrap_or(type_name)
+}
+
+/// The field's JSON name, derived as protoc does when the descriptor leaves it out.
+fn json_name(field: &FieldDescriptorProto) -> String {
+    if let Some(name) = &field.json_name {
+        return name.clone();
+    }
+    let mut name = String::new();
+    let mut upper = false;
+    for c in field.name().chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn wire_type(ty: Type) -> u8 {
+    match ty {
+        Type::Double | Type::Fixed64 | Type::Sfixed64 => FIXED64,
+        Type::Float | Type::Fixed32 | Type::Sfixed32 => FIXED32,
+        Type::String | Type::Bytes | Type::Message | Type::Group => LENGTH_DELIMITED,
+        _ => VARINT,
+    }
+}
+
+fn wire_type_error(field: &FieldDescriptorProto) -> anyhow::Error {
+    anyhow!("Field '{}' has an unexpected wire type", field.name())
+}
+
+/// Integers may be JSON numbers or strings, as the protobuf JSON mapping writes 64-bit values.
+fn signed(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn unsigned(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Floats may also be the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Booleans may be strings, as map keys are.
+fn boolean(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn float_value(value: f64) -> Value {
+    match serde_json::Number::from_f64(value) {
+        Some(number) => Value::Number(number),
+        None if value.is_nan() => json!("NaN"),
+        None if value > 0.0 => json!("Infinity"),
+        None => json!("-Infinity"),
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn encode_key(number: i32, wire_type: u8, buf: &mut Vec<u8>) {
+    encode_varint(((number as u64) << 3) | u64::from(wire_type), buf);
+}
+
+fn encode_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| anyhow!("Truncated protobuf message"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Invalid varint in protobuf message"))
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(anyhow!("Truncated protobuf message"));
+    }
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(head)
+}
+
+fn read_raw<'a>(bytes: &mut &'a [u8], wire_type: u8) -> Result<Raw<'a>> {
+    match wire_type {
+        VARINT => Ok(Raw::Varint(read_varint(bytes)?)),
+        FIXED64 => Ok(Raw::Fixed64(take(bytes, 8)?.get_u64_le())),
+        FIXED32 => Ok(Raw::Fixed32(take(bytes, 4)?.get_u32_le())),
+        LENGTH_DELIMITED => {
+            let len = usize::try_from(read_varint(bytes)?)?;
+            Ok(Raw::Bytes(take(bytes, len)?))
+        }
+        other => Err(anyhow!("Unsupported protobuf wire type {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpcpb::generated::{Manual, Tool as GrpcTool, ToolCallRequest};
+    use prost_types::{DescriptorProto, EnumValueDescriptorProto, MessageOptions};
+
+    fn field(name: &str, number: i32, ty: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(ty as i32),
+            ..Default::default()
+        }
+    }
+
+    fn typed(mut field: FieldDescriptorProto, type_name: &str) -> FieldDescriptorProto {
+        field.type_name = Some(type_name.to_string());
+        field
+    }
+
+    fn pool() -> DescriptorPool {
+        let entry = DescriptorProto {
+            name: Some("LabelsEntry".to_string()),
+            field: vec![
+                field("key", 1, Type::String, Label::Optional),
+                field("value", 2, Type::Int32, Label::Optional),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let item = DescriptorProto {
+            name: Some("Item".to_string()),
+            field: vec![
+                field("id", 1, Type::Int64, Label::Optional),
+                field("offset", 2, Type::Sint32, Label::Optional),
+                field("counts", 3, Type::Int32, Label::Repeated),
+                typed(
+                    field("labels", 4, Type::Message, Label::Repeated),
+                    ".shop.Item.LabelsEntry",
+                ),
+                typed(field("kind", 5, Type::Enum, Label::Optional), ".shop.Kind"),
+                field("payload", 6, Type::Bytes, Label::Optional),
+                field("display_name", 7, Type::String, Label::Optional),
+                field("price", 8, Type::Double, Label::Optional),
+                typed(
+                    field("parent", 9, Type::Message, Label::Optional),
+                    ".shop.Item",
+                ),
+            ],
+            nested_type: vec![entry],
+            ..Default::default()
+        };
+        let kind = EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            value: ["UNKNOWN", "BOOK", "GAME"]
+                .iter()
+                .zip(0..)
+                .map(|(name, number)| EnumValueDescriptorProto {
+                    name: Some(name.to_string()),
+                    number: Some(number),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("shop.proto".to_string()),
+            package: Some("shop".to_string()),
+            dependency: vec!["google/protobuf/empty.proto".to_string()],
+            message_type: vec![item],
+            enum_type: vec![kind],
+            ..Default::default()
+        };
+        let mut pool = DescriptorPool::default();
+        pool.add_file(&file.encode_to_vec()).unwrap();
+        pool
+    }
+
+    #[test]
+    fn messages_round_trip_through_json() {
+        let pool = pool();
+        let value = json!({
+            "id": "9007199254740993",
+            "offset": -3,
+            "counts": [1, 2, 3],
+            "labels": { "a": 1 },
+            "kind": "GAME",
+            "payload": "aGk=",
+            "displayName": "Chess",
+            "price": 4.5,
+            "parent": { "id": 1, "kind": 1 }
+        });
+        let bytes = pool.encode("shop.Item", &value).unwrap();
+        assert_eq!(
+            pool.decode("shop.Item", &bytes).unwrap(),
+            json!({
+                "id": 9007199254740993i64,
+                "offset": -3,
+                "counts": [1, 2, 3],
+                "labels": { "a": 1 },
+                "kind": "GAME",
+                "payload": "aGk=",
+                "display_name": "Chess",
+                "price": 4.5,
+                "parent": { "id": 1, "kind": "BOOK" }
+            })
+        );
+        assert_eq!(
+            pool.missing_dependencies(),
+            vec!["google/protobuf/empty.proto"]
+        );
+    }
+
+    #[test]
+    fn decodes_packed_fields_and_map_defaults() {
+        let pool = pool();
+        // counts = [1, 150] packed, then a map entry whose value was left at zero.
+        let bytes = [0x1a, 0x03, 0x01, 0x96, 0x01, 0x22, 0x03, 0x0a, 0x01, b'z'];
+        assert_eq!(
+            pool.decode("shop.Item", &bytes).unwrap(),
+            json!({ "counts": [1, 150], "labels": { "z": 0 } })
+        );
+        assert!(pool.decode("shop.Item", &[0x1a, 0x05, 0x01]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_bad_values() {
+        let pool = pool();
+        let err = pool.encode("shop.Item", &json!({ "nope": 1 })).unwrap_err();
+        assert!(
+            err.to_string().contains("shop.Item has no field 'nope'"),
+            "{err}"
+        );
+        let err = pool
+            .encode("shop.Item", &json!({ "kind": "MOVIE" }))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid value for field 'kind'"),
+            "{err}"
+        );
+        assert!(pool
+            .encode("shop.Item", &json!({ "offset": 1u64 << 40 }))
+            .is_err());
+        assert!(pool.encode("shop.Missing", &json!({})).is_err());
+    }
+
+    #[test]
+    fn matches_prost_wire_format() {
+        let mut pool = DescriptorPool::default();
+        let tool = DescriptorProto {
+            name: Some("Tool".to_string()),
+            field: vec![
+                field("name", 1, Type::String, Label::Optional),
+                field("description", 2, Type::String, Label::Optional),
+            ],
+            ..Default::default()
+        };
+        let manual = DescriptorProto {
+            name: Some("Manual".to_string()),
+            field: vec![
+                field("version", 1, Type::String, Label::Optional),
+                typed(
+                    field("tools", 2, Type::Message, Label::Repeated),
+                    ".grpcpb.Tool",
+                ),
+            ],
+            ..Default::default()
+        };
+        let request = DescriptorProto {
+            name: Some("ToolCallRequest".to_string()),
+            field: vec![
+                field("tool", 1, Type::String, Label::Optional),
+                field("args_json", 2, Type::String, Label::Optional),
+            ],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("utcp.proto".to_string()),
+            package: Some("grpcpb".to_string()),
+            message_type: vec![tool, manual, request],
+            ..Default::default()
+        };
+        pool.add_file(&file.encode_to_vec()).unwrap();
+
+        let bytes = pool
+            .encode(
+                "grpcpb.ToolCallRequest",
+                &json!({ "tool": "echo", "argsJson": "{}" }),
+            )
+            .unwrap();
+        assert_eq!(
+            ToolCallRequest::decode(bytes.as_slice()).unwrap(),
+            ToolCallRequest {
+                tool: "echo".to_string(),
+                args_json: "{}".to_string(),
+            }
+        );
+
+        let manual = Manual {
+            version: "1.0".to_string(),
+            tools: vec![GrpcTool {
+                name: "echo".to_string(),
+                description: "echo tool".to_string(),
+            }],
+        };
+        assert_eq!(
+            pool.decode("grpcpb.Manual", &manual.encode_to_vec())
+                .unwrap(),
+            json!({
+                "version": "1.0",
+                "tools": [{ "name": "echo", "description": "echo tool" }]
+            })
+        );
+    }
+
+    #[test]
+    fn schemas_follow_field_types() {
+        let schema = pool().schema("shop.Item");
+        let properties = schema.properties.unwrap();
+        assert_eq!(
+            properties["counts"],
+            json!({ "type": "array", "items": { "type": "integer" } })
+        );
+        assert_eq!(
+            properties["labels"],
+            json!({ "type": "object", "additionalProperties": { "type": "integer" } })
+        );
+        assert_eq!(
+            properties["kind"],
+            json!({ "type": "string", "enum": ["UNKNOWN", "BOOK", "GAME"] })
+        );
+        assert_eq!(properties["payload"]["format"], json!("byte"));
+        assert_eq!(
+            properties["parent"]["properties"]["display_name"],
+            json!({ "type": "string" })
+        );
+    }
+}
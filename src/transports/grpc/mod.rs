@@ -2,15 +2,18 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use base64::Engine;
+use prost::Message;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
+use tonic::codegen::http::uri::PathAndQuery;
 use tonic::metadata::{MetadataKey, MetadataValue};
-use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
-use tonic::Request;
+use tonic::{Request, Status};
 
 use crate::auth::AuthConfig;
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
 use crate::providers::grpc::GrpcProvider;
 use crate::tools::{Tool, ToolInputOutputSchema};
@@ -20,43 +23,294 @@ use crate::transports::{
 };
 
 use crate::grpcpb::generated::utcp_service_client::UtcpServiceClient;
-use crate::grpcpb::generated::{Empty, ToolCallRequest};
+use crate::grpcpb::generated::{Empty, ToolCallRequest, ToolCallResponse};
+
+mod channel;
+mod dynamic;
+mod reflection;
+#[cfg(feature = "grpc-web")]
+mod web;
+
+use channel::{
+    check_request_size, compression, set_deadline, status_error, with_deadline, ChannelCache,
+    GrpcChannel,
+};
+use dynamic::RawCodec;
+use reflection::ReflectedServices;
 
 /// Transport implementation that communicates with UTCP servers over gRPC.
-pub struct GrpcTransport;
+pub struct GrpcTransport {
+    channels: ChannelCache,
+    /// Services found through reflection, by provider name, for `use_reflection` providers.
+    reflected: Mutex<HashMap<String, Arc<ReflectedServices>>>,
+}
 
 impl GrpcTransport {
     /// Create a gRPC transport instance.
     pub fn new() -> Self {
-        Self
+        Self {
+            channels: ChannelCache::default(),
+            reflected: Mutex::new(HashMap::new()),
+        }
     }
 
     fn default_schema() -> ToolInputOutputSchema {
-        ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
+        ToolInputOutputSchema::new("object")
+    }
+
+    async fn channel(&self, prov: &GrpcProvider) -> Result<GrpcChannel> {
+        self.channels.get(prov).await
+    }
+
+    async fn connect(&self, prov: &GrpcProvider) -> Result<UtcpServiceClient<GrpcChannel>> {
+        let mut client = UtcpServiceClient::new(self.channel(prov).await?);
+        if let Some(encoding) = compression(prov)? {
+            client = client.send_compressed(encoding).accept_compressed(encoding);
+        }
+        if let Some(limit) = prov.max_decoding_message_size {
+            client = client.max_decoding_message_size(limit);
+        }
+        if let Some(limit) = prov.max_encoding_message_size {
+            client = client.max_encoding_message_size(limit);
+        }
+        Ok(client)
+    }
+
+    /// The error for a call to `tool` that failed with `status`. An unavailable server also
+    /// drops the cached channel, so the next call dials again.
+    fn call_failed(&self, prov: &GrpcProvider, tool: &str, status: Status) -> anyhow::Error {
+        if status.code() == tonic::Code::Unavailable {
+            self.channels.evict(&prov.base.name);
+        }
+        status_error(prov, tool, status)
+    }
+
+    /// A generic client for calling reflected methods with already-encoded messages.
+    async fn raw_client(&self, prov: &GrpcProvider) -> Result<tonic::client::Grpc<GrpcChannel>> {
+        let mut client = tonic::client::Grpc::new(self.channel(prov).await?);
+        if let Some(encoding) = compression(prov)? {
+            client = client.send_compressed(encoding).accept_compressed(encoding);
+        }
+        if let Some(limit) = prov.max_decoding_message_size {
+            client = client.max_decoding_message_size(limit);
+        }
+        if let Some(limit) = prov.max_encoding_message_size {
+            client = client.max_encoding_message_size(limit);
+        }
+        client
+            .ready()
+            .await
+            .map_err(|e| anyhow!("gRPC service was not ready: {}", e))?;
+        Ok(client)
+    }
+
+    /// Ask the server which services it offers and remember them for later calls.
+    async fn discover(&self, prov: &GrpcProvider) -> Result<Arc<ReflectedServices>> {
+        if prov.grpc_web {
+            return Err(anyhow!(
+                "gRPC-Web cannot carry the streaming reflection API; provider '{}' must not set \
+                 both grpc_web and use_reflection",
+                prov.base.name
+            ));
+        }
+        let mut auth = Request::new(());
+        self.apply_auth(prov, &mut auth)?;
+        let services = with_deadline(prov, "ServerReflectionInfo", async {
+            let channel = self.channel(prov).await?;
+            reflection::discover(channel, auth.metadata()).await
+        })
+        .await?;
+        let services = Arc::new(services);
+        self.reflected
+            .lock()
+            .unwrap()
+            .insert(prov.base.name.clone(), services.clone());
+        Ok(services)
+    }
+
+    /// The reflected services of `prov`, discovering them if it was never registered.
+    async fn reflected(&self, prov: &GrpcProvider) -> Result<Arc<ReflectedServices>> {
+        let known = self.reflected.lock().unwrap().get(&prov.base.name).cloned();
+        match known {
+            Some(services) => Ok(services),
+            None => self.discover(prov).await,
+        }
+    }
+
+    fn reflected_tools(services: &ReflectedServices) -> Vec<Tool> {
+        services
+            .methods
+            .iter()
+            .map(|method| {
+                let mut tags = vec!["grpc".to_string()];
+                if method.is_streaming() {
+                    tags.push("streaming".to_string());
+                }
+                Tool {
+                    name: method.tool_name(),
+                    description: format!(
+                        "{} method of the {} service",
+                        method.name, method.service
+                    ),
+                    inputs: services.pool.schema(&method.input),
+                    outputs: services.pool.schema(&method.output),
+                    tags,
+                    average_response_size: None,
+                    provider: None,
+                }
+            })
+            .collect()
+    }
+
+    async fn call_reflected(
+        &self,
+        prov: &GrpcProvider,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+    ) -> Result<Value> {
+        let services = self.reflected(prov).await?;
+        let method = services.method(&prov.base.name, tool_name)?;
+        if method.is_streaming() {
+            return Err(anyhow!(
+                "{} is a streaming method; use call_tool_stream",
+                method.tool_name()
+            ));
         }
+        let body = services
+            .pool
+            .encode(&method.input, &Value::Object(args.into_iter().collect()))?;
+        check_request_size(prov, tool_name, body.len())?;
+
+        let mut request = Request::new(body);
+        self.apply_auth(prov, &mut request)?;
+        set_deadline(prov, &mut request);
+        let path = PathAndQuery::from_str(&method.path())?;
+        let response = with_deadline(prov, tool_name, async {
+            let mut client = self.raw_client(prov).await?;
+            client
+                .unary(request, path, RawCodec)
+                .await
+                .map_err(|status| self.call_failed(prov, tool_name, status))
+        })
+        .await?
+        .into_inner();
+        services.pool.decode(&method.output, &response)
     }
 
-    async fn connect(&self, prov: &GrpcProvider) -> Result<UtcpServiceClient<Channel>> {
-        let scheme = if prov.use_ssl { "https" } else { "http" };
-        let endpoint = format!("{}://{}:{}", scheme, prov.host, prov.port);
+    /// Call a streaming method with a single request message and decode each reply.
+    async fn stream_reflected(
+        &self,
+        prov: &GrpcProvider,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+    ) -> Result<Box<dyn StreamResult>> {
+        let services = self.reflected(prov).await?;
+        let method = services.method(&prov.base.name, tool_name)?;
+        if !method.is_streaming() {
+            return Err(anyhow!(
+                "{} is a unary method; use call_tool",
+                method.tool_name()
+            ));
+        }
+        let body = services
+            .pool
+            .encode(&method.input, &Value::Object(args.into_iter().collect()))?;
+        check_request_size(prov, tool_name, body.len())?;
+        let path = PathAndQuery::from_str(&method.path())?;
+        let output = method.output.clone();
+
+        let mut request = Request::new(futures::stream::iter(vec![body]));
+        self.apply_auth(prov, &mut request)?;
+        let mut stream = with_deadline(prov, tool_name, async {
+            let mut client = self.raw_client(prov).await?;
+            client
+                .streaming(request, path, RawCodec)
+                .await
+                .map_err(|status| self.call_failed(prov, tool_name, status))
+        })
+        .await?
+        .into_inner();
+
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Some(item) = stream.message().await.transpose() {
+                let item = item
+                    .map_err(|status| anyhow!("gRPC stream error: {}", status))
+                    .and_then(|bytes| services.pool.decode(&output, &bytes));
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(boxed_channel_stream(rx, None))
+    }
 
-        let mut endpoint = Endpoint::from_shared(endpoint)?;
-        if prov.use_ssl {
-            endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+    /// Open a `CallToolBidi` call to `tool_name`. Each JSON value sent on the returned sender
+    /// goes to the server as one `ToolCallRequest` chunk, and results arrive on the stream as the
+    /// server produces them; dropping the sender ends the input.
+    ///
+    /// The call starts in the background, so servers that wait for input before answering do not
+    /// block it. If the server does not implement `CallToolBidi`, the stream yields
+    /// [`UtcpError::ToolCall`].
+    pub async fn call_tool_duplex(
+        &self,
+        tool_name: &str,
+        prov: &dyn Provider,
+    ) -> Result<(mpsc::Sender<Value>, Box<dyn StreamResult>)> {
+        let grpc_prov = prov
+            .as_any()
+            .downcast_ref::<GrpcProvider>()
+            .ok_or_else(|| anyhow!("Provider is not a GrpcProvider"))?;
+        if grpc_prov.use_reflection {
+            return Err(anyhow!(
+                "call_tool_duplex uses the UTCP CallToolBidi RPC, which use_reflection providers \
+                 do not offer"
+            ));
+        }
+        if grpc_prov.grpc_web {
+            return Err(anyhow!(
+                "call_tool_duplex streams requests, which gRPC-Web providers cannot do"
+            ));
         }
 
-        let channel = endpoint.connect().await?;
-        Ok(UtcpServiceClient::new(channel))
+        let mut client = with_deadline(grpc_prov, tool_name, self.connect(grpc_prov)).await?;
+        let (input_tx, input_rx) = mpsc::channel::<Value>(16);
+        let tool = tool_name.to_string();
+        let chunks = futures::stream::unfold(input_rx, move |mut input_rx| {
+            let tool = tool.clone();
+            async move {
+                let chunk = input_rx.recv().await?;
+                let request = ToolCallRequest {
+                    tool,
+                    args_json: chunk.to_string(),
+                };
+                Some((request, input_rx))
+            }
+        });
+        let mut request = Request::new(chunks);
+        self.apply_auth(grpc_prov, &mut request)?;
+
+        let (tx, rx) = mpsc::channel(16);
+        let endpoint = format!("{}:{}", grpc_prov.host, grpc_prov.port);
+        tokio::spawn(async move {
+            match client.call_tool_bidi(request).await {
+                Ok(response) => forward_responses(response.into_inner(), tx).await,
+                Err(status) if status.code() == tonic::Code::Unimplemented => {
+                    let err = UtcpError::ToolCall(format!(
+                        "gRPC server at {} does not implement CallToolBidi",
+                        endpoint
+                    ));
+                    let _ = tx.send(Err(err.into())).await;
+                }
+                Err(status) => {
+                    let _ = tx.send(Err(anyhow!("gRPC stream error: {}", status))).await;
+                }
+            }
+        });
+
+        Ok((input_tx, boxed_channel_stream(rx, None)))
     }
 
     fn apply_auth<T>(&self, prov: &GrpcProvider, req: &mut Request<T>) -> Result<()> {
@@ -97,11 +351,24 @@ impl ClientTransport for GrpcTransport {
             .downcast_ref::<GrpcProvider>()
             .ok_or_else(|| anyhow!("Provider is not a GrpcProvider"))?;
 
-        let mut client = self.connect(grpc_prov).await?;
+        if grpc_prov.use_reflection {
+            let services = self.discover(grpc_prov).await?;
+            return Ok(Self::reflected_tools(&services));
+        }
+
         let mut request = Request::new(Empty {});
         self.apply_auth(grpc_prov, &mut request)?;
+        set_deadline(grpc_prov, &mut request);
 
-        let manual = client.get_manual(request).await?.into_inner();
+        let manual = with_deadline(grpc_prov, "GetManual", async {
+            let mut client = self.connect(grpc_prov).await?;
+            client
+                .get_manual(request)
+                .await
+                .map_err(|status| self.call_failed(grpc_prov, "GetManual", status))
+        })
+        .await?
+        .into_inner();
         let default_schema = Self::default_schema();
 
         let tools = manual
@@ -121,7 +388,9 @@ impl ClientTransport for GrpcTransport {
         Ok(tools)
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        self.reflected.lock().unwrap().remove(&prov.name());
+        self.channels.evict(&prov.name());
         Ok(())
     }
 
@@ -136,16 +405,30 @@ impl ClientTransport for GrpcTransport {
             .downcast_ref::<GrpcProvider>()
             .ok_or_else(|| anyhow!("Provider is not a GrpcProvider"))?;
 
-        let mut client = self.connect(grpc_prov).await?;
+        if grpc_prov.use_reflection {
+            return self.call_reflected(grpc_prov, tool_name, args).await;
+        }
+
         let args_json = serde_json::to_string(&args)?;
 
-        let mut request = Request::new(ToolCallRequest {
+        let message = ToolCallRequest {
             tool: tool_name.to_string(),
             args_json,
-        });
+        };
+        check_request_size(grpc_prov, tool_name, message.encoded_len())?;
+        let mut request = Request::new(message);
         self.apply_auth(grpc_prov, &mut request)?;
+        set_deadline(grpc_prov, &mut request);
 
-        let response = client.call_tool(request).await?.into_inner();
+        let response = with_deadline(grpc_prov, tool_name, async {
+            let mut client = self.connect(grpc_prov).await?;
+            client
+                .call_tool(request)
+                .await
+                .map_err(|status| self.call_failed(grpc_prov, tool_name, status))
+        })
+        .await?
+        .into_inner();
         if response.result_json.is_empty() {
             return Ok(Value::Null);
         }
@@ -165,55 +448,84 @@ impl ClientTransport for GrpcTransport {
             .downcast_ref::<GrpcProvider>()
             .ok_or_else(|| anyhow!("Provider is not a GrpcProvider"))?;
 
-        let mut client = self.connect(grpc_prov).await?;
+        if grpc_prov.use_reflection {
+            return self.stream_reflected(grpc_prov, tool_name, args).await;
+        }
+
         let args_json = serde_json::to_string(&args)?;
 
-        let mut request = Request::new(ToolCallRequest {
+        let message = ToolCallRequest {
             tool: tool_name.to_string(),
             args_json,
-        });
+        };
+        check_request_size(grpc_prov, tool_name, message.encoded_len())?;
+        let mut request = Request::new(message);
         self.apply_auth(grpc_prov, &mut request)?;
 
-        let mut stream = client.call_tool_stream(request).await?.into_inner();
+        let stream = with_deadline(grpc_prov, tool_name, async {
+            let mut client = self.connect(grpc_prov).await?;
+            client
+                .call_tool_stream(request)
+                .await
+                .map_err(|status| self.call_failed(grpc_prov, tool_name, status))
+        })
+        .await?
+        .into_inner();
         let (tx, rx) = mpsc::channel(16);
-        tokio::spawn(async move {
-            while let Some(item) = stream.message().await.transpose() {
-                match item {
-                    Ok(resp) => {
-                        let parsed = if resp.result_json.is_empty() {
-                            Ok(Value::Null)
-                        } else {
-                            serde_json::from_str::<Value>(&resp.result_json)
-                                .map_err(|e| anyhow!("Failed to parse stream item: {}", e))
-                        };
-                        if tx.send(parsed).await.is_err() {
-                            return;
-                        }
-                    }
-                    Err(status) => {
-                        let _ = tx.send(Err(anyhow!("gRPC stream error: {}", status))).await;
-                        return;
-                    }
-                }
-            }
-        });
+        tokio::spawn(forward_responses(stream, tx));
 
         Ok(boxed_channel_stream(rx, None))
     }
 }
 
+/// Send each streamed `ToolCallResponse` to `tx` as JSON, stopping after a gRPC error.
+async fn forward_responses(
+    mut stream: tonic::Streaming<ToolCallResponse>,
+    tx: mpsc::Sender<Result<Value>>,
+) {
+    while let Some(item) = stream.message().await.transpose() {
+        match item {
+            Ok(resp) => {
+                let parsed = if resp.result_json.is_empty() {
+                    Ok(Value::Null)
+                } else {
+                    serde_json::from_str::<Value>(&resp.result_json)
+                        .map_err(|e| anyhow!("Failed to parse stream item: {}", e))
+                };
+                if tx.send(parsed).await.is_err() {
+                    return;
+                }
+            }
+            Err(status) => {
+                let _ = tx.send(Err(anyhow!("gRPC stream error: {}", status))).await;
+                return;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::auth::{ApiKeyAuth, AuthType, BasicAuth, OAuth2Auth};
     use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::providers::grpc::GrpcCompression;
     use serde_json::json;
     use tokio::net::TcpListener;
     use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
     use tonic::transport::Server;
 
     use crate::grpcpb::generated::utcp_service_server::{UtcpService, UtcpServiceServer};
-    use crate::grpcpb::generated::{Manual, Tool as GrpcTool, ToolCallResponse};
+    use crate::grpcpb::generated::{Manual, Tool as GrpcTool};
+    use crate::grpcpb::reflection::server_reflection_request::MessageRequest;
+    use crate::grpcpb::reflection::server_reflection_response::MessageResponse;
+    use crate::grpcpb::reflection::server_reflection_server::{
+        ServerReflection, ServerReflectionServer,
+    };
+    use crate::grpcpb::reflection::{
+        ErrorResponse, FileDescriptorResponse, ListServiceResponse, ServerReflectionRequest,
+        ServerReflectionResponse, ServiceResponse,
+    };
 
     #[test]
     fn apply_auth_sets_basic_header() {
@@ -303,6 +615,9 @@ mod tests {
             request: Request<ToolCallRequest>,
         ) -> Result<tonic::Response<ToolCallResponse>, tonic::Status> {
             let inner = request.into_inner();
+            if inner.tool == "slow" {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
             let args_value: Value =
                 serde_json::from_str(&inner.args_json).unwrap_or_else(|_| Value::Null);
             Ok(tonic::Response::new(ToolCallResponse {
@@ -333,6 +648,30 @@ mod tests {
             .unwrap();
             Ok(tonic::Response::new(ReceiverStream::new(rx)))
         }
+
+        type CallToolBidiStream = ReceiverStream<Result<ToolCallResponse, tonic::Status>>;
+
+        /// Uppercases the `text` of every chunk as it arrives.
+        async fn call_tool_bidi(
+            &self,
+            request: Request<tonic::Streaming<ToolCallRequest>>,
+        ) -> Result<tonic::Response<Self::CallToolBidiStream>, tonic::Status> {
+            let mut chunks = request.into_inner();
+            let (tx, rx) = tokio::sync::mpsc::channel(4);
+            tokio::spawn(async move {
+                while let Ok(Some(chunk)) = chunks.message().await {
+                    let args: Value = serde_json::from_str(&chunk.args_json).unwrap_or_default();
+                    let text = args["text"].as_str().unwrap_or_default().to_uppercase();
+                    let response = ToolCallResponse {
+                        result_json: json!({ "tool": chunk.tool, "text": text }).to_string(),
+                    };
+                    if tx.send(Ok(response)).await.is_err() {
+                        return;
+                    }
+                }
+            });
+            Ok(tonic::Response::new(ReceiverStream::new(rx)))
+        }
     }
 
     #[tokio::test]
@@ -344,7 +683,7 @@ mod tests {
 
         tokio::spawn(async move {
             Server::builder()
-                .add_service(UtcpServiceServer::new(MockGrpc::default()))
+                .add_service(UtcpServiceServer::new(MockGrpc))
                 .serve_with_incoming_shutdown(incoming, async {
                     let _ = shutdown_rx.await;
                 })
@@ -362,6 +701,14 @@ mod tests {
             host: addr.ip().to_string(),
             port: addr.port(),
             use_ssl: false,
+            use_reflection: false,
+            grpc_web: false,
+            timeout_ms: None,
+            keepalive: None,
+            http2_adaptive_window: false,
+            compression: None,
+            max_decoding_message_size: None,
+            max_encoding_message_size: None,
         };
 
         let transport = GrpcTransport::new();
@@ -391,4 +738,480 @@ mod tests {
 
         let _ = shutdown_tx.send(());
     }
+
+    /// Reflection for the UTCP service, with its messages in a separate imported file.
+    struct MockReflection;
+
+    fn descriptor_files() -> (Vec<u8>, Vec<u8>) {
+        use prost::Message;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+            ServiceDescriptorProto,
+        };
+
+        let field =
+            |name: &str, number: i32, ty: Type, type_name: Option<&str>| FieldDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number),
+                label: Some(if type_name.is_some() {
+                    Label::Repeated
+                } else {
+                    Label::Optional
+                } as i32),
+                r#type: Some(ty as i32),
+                type_name: type_name.map(str::to_string),
+                ..Default::default()
+            };
+        let message = |name: &str, field: Vec<FieldDescriptorProto>| DescriptorProto {
+            name: Some(name.to_string()),
+            field,
+            ..Default::default()
+        };
+        let method =
+            |name: &str, input: &str, output: &str, streaming: bool| MethodDescriptorProto {
+                name: Some(name.to_string()),
+                input_type: Some(format!(".grpcpb.{}", input)),
+                output_type: Some(format!(".grpcpb.{}", output)),
+                server_streaming: Some(streaming),
+                ..Default::default()
+            };
+
+        let messages = FileDescriptorProto {
+            name: Some("messages.proto".to_string()),
+            package: Some("grpcpb".to_string()),
+            message_type: vec![
+                message("Empty", vec![]),
+                message(
+                    "Tool",
+                    vec![
+                        field("name", 1, Type::String, None),
+                        field("description", 2, Type::String, None),
+                    ],
+                ),
+                message(
+                    "Manual",
+                    vec![
+                        field("version", 1, Type::String, None),
+                        field("tools", 2, Type::Message, Some(".grpcpb.Tool")),
+                    ],
+                ),
+                message(
+                    "ToolCallRequest",
+                    vec![
+                        field("tool", 1, Type::String, None),
+                        field("args_json", 2, Type::String, None),
+                    ],
+                ),
+                message(
+                    "ToolCallResponse",
+                    vec![field("result_json", 1, Type::String, None)],
+                ),
+            ],
+            ..Default::default()
+        };
+        let service = FileDescriptorProto {
+            name: Some("service.proto".to_string()),
+            package: Some("grpcpb".to_string()),
+            dependency: vec!["messages.proto".to_string()],
+            service: vec![ServiceDescriptorProto {
+                name: Some("UTCPService".to_string()),
+                method: vec![
+                    method("GetManual", "Empty", "Manual", false),
+                    method("CallTool", "ToolCallRequest", "ToolCallResponse", false),
+                    method(
+                        "CallToolStream",
+                        "ToolCallRequest",
+                        "ToolCallResponse",
+                        true,
+                    ),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        (service.encode_to_vec(), messages.encode_to_vec())
+    }
+
+    #[tonic::async_trait]
+    impl ServerReflection for MockReflection {
+        type ServerReflectionInfoStream =
+            ReceiverStream<Result<ServerReflectionResponse, tonic::Status>>;
+
+        async fn server_reflection_info(
+            &self,
+            request: Request<tonic::Streaming<ServerReflectionRequest>>,
+        ) -> Result<tonic::Response<Self::ServerReflectionInfoStream>, tonic::Status> {
+            let mut requests = request.into_inner();
+            let (service, messages) = descriptor_files();
+            let (tx, rx) = tokio::sync::mpsc::channel(4);
+            tokio::spawn(async move {
+                while let Ok(Some(request)) = requests.message().await {
+                    let files = |file: &Vec<u8>| {
+                        MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                            file_descriptor_proto: vec![file.clone()],
+                        })
+                    };
+                    let response = match &request.message_request {
+                        Some(MessageRequest::ListServices(_)) => {
+                            let names = [
+                                "grpcpb.UTCPService",
+                                "grpc.reflection.v1alpha.ServerReflection",
+                            ];
+                            MessageResponse::ListServicesResponse(ListServiceResponse {
+                                service: names
+                                    .iter()
+                                    .map(|name| ServiceResponse {
+                                        name: name.to_string(),
+                                    })
+                                    .collect(),
+                            })
+                        }
+                        Some(MessageRequest::FileContainingSymbol(symbol))
+                            if symbol == "grpcpb.UTCPService" =>
+                        {
+                            files(&service)
+                        }
+                        Some(MessageRequest::FileByFilename(name)) if name == "messages.proto" => {
+                            files(&messages)
+                        }
+                        _ => MessageResponse::ErrorResponse(ErrorResponse {
+                            error_code: tonic::Code::NotFound as i32,
+                            error_message: "not found".to_string(),
+                        }),
+                    };
+                    let response = ServerReflectionResponse {
+                        valid_host: String::new(),
+                        original_request: Some(request),
+                        message_response: Some(response),
+                    };
+                    if tx.send(Ok(response)).await.is_err() {
+                        return;
+                    }
+                }
+            });
+            Ok(tonic::Response::new(ReceiverStream::new(rx)))
+        }
+    }
+
+    #[tokio::test]
+    async fn reflection_discovers_and_calls_methods() {
+        let (addr, shutdown_tx) = serve(false).await;
+        let mut prov = GrpcProvider::new(
+            "reflected".to_string(),
+            addr.ip().to_string(),
+            addr.port(),
+            None,
+        );
+        prov.use_reflection = true;
+        let transport = GrpcTransport::new();
+
+        let tools = transport
+            .register_tool_provider(&prov)
+            .await
+            .expect("register");
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "grpcpb.UTCPService.GetManual",
+                "grpcpb.UTCPService.CallTool",
+                "grpcpb.UTCPService.CallToolStream",
+            ]
+        );
+        let properties = tools[1].inputs.properties.clone().unwrap();
+        assert_eq!(properties["args_json"], json!({ "type": "string" }));
+        assert_eq!(tools[2].tags, vec!["grpc", "streaming"]);
+
+        let manual = transport
+            .call_tool("grpcpb.UTCPService.GetManual", HashMap::new(), &prov)
+            .await
+            .expect("get manual");
+        assert_eq!(
+            manual,
+            json!({
+                "version": "1.0",
+                "tools": [{ "name": "echo", "description": "echo tool" }]
+            })
+        );
+
+        let args = HashMap::from([
+            ("tool".to_string(), json!("echo")),
+            ("argsJson".to_string(), json!("{\"msg\":\"hi\"}")),
+        ]);
+        let result = transport
+            .call_tool("reflected.grpcpb.UTCPService.CallTool", args.clone(), &prov)
+            .await
+            .expect("call");
+        let result_json: Value =
+            serde_json::from_str(result["result_json"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            result_json,
+            json!({ "tool": "echo", "args": { "msg": "hi" } })
+        );
+
+        let err = transport
+            .call_tool("grpcpb.UTCPService.CallToolStream", args.clone(), &prov)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("use call_tool_stream"), "{err}");
+
+        let mut stream = transport
+            .call_tool_stream("grpcpb.UTCPService.CallToolStream", args, &prov)
+            .await
+            .expect("call stream");
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            json!({ "result_json": "{\"idx\":1}" })
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            json!({ "result_json": "{\"idx\":2}" })
+        );
+        assert!(stream.next().await.unwrap().is_none());
+
+        let _ = shutdown_tx.send(());
+    }
+
+    async fn serve(
+        reflection_only: bool,
+    ) -> (std::net::SocketAddr, tokio::sync::oneshot::Sender<()>) {
+        serve_counting(reflection_only, Arc::default()).await
+    }
+
+    /// Like `serve`, counting accepted connections in `connections`.
+    async fn serve_counting(
+        reflection_only: bool,
+        connections: Arc<std::sync::atomic::AtomicUsize>,
+    ) -> (std::net::SocketAddr, tokio::sync::oneshot::Sender<()>) {
+        use futures::StreamExt;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener).inspect(move |_| {
+            connections.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let utcp = (!reflection_only).then(|| UtcpServiceServer::new(MockGrpc));
+
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(ServerReflectionServer::new(MockReflection))
+                .add_optional_service(utcp)
+                .serve_with_incoming_shutdown(incoming, async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+                .unwrap();
+        });
+        (addr, shutdown_tx)
+    }
+
+    #[tokio::test]
+    async fn duplex_calls_stream_chunks_both_ways() {
+        let (addr, shutdown_tx) = serve(false).await;
+        let prov = GrpcProvider::new("grpc".to_string(), addr.ip().to_string(), addr.port(), None);
+        let transport = GrpcTransport::new();
+
+        let (input, mut output) = transport
+            .call_tool_duplex("transcribe", &prov)
+            .await
+            .expect("duplex");
+        input.send(json!({ "text": "hello" })).await.unwrap();
+        assert_eq!(
+            output.next().await.unwrap().unwrap(),
+            json!({ "tool": "transcribe", "text": "HELLO" })
+        );
+        input.send(json!({ "text": "world" })).await.unwrap();
+        assert_eq!(
+            output.next().await.unwrap().unwrap(),
+            json!({ "tool": "transcribe", "text": "WORLD" })
+        );
+        drop(input);
+        assert!(output.next().await.unwrap().is_none());
+
+        let _ = shutdown_tx.send(());
+    }
+
+    #[tokio::test]
+    async fn duplex_reports_servers_without_bidi() {
+        let (addr, shutdown_tx) = serve(true).await;
+        let prov = GrpcProvider::new("grpc".to_string(), addr.ip().to_string(), addr.port(), None);
+        let transport = GrpcTransport::new();
+
+        let (_input, mut output) = transport
+            .call_tool_duplex("transcribe", &prov)
+            .await
+            .expect("duplex");
+        let err = output.next().await.unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::ToolCall(message)) => {
+                assert!(
+                    message.contains("does not implement CallToolBidi"),
+                    "{message}"
+                )
+            }
+            other => panic!("expected a ToolCall error, got {:?}", other),
+        }
+
+        let _ = shutdown_tx.send(());
+    }
+
+    #[tokio::test]
+    async fn calls_share_a_channel_and_honour_deadlines() {
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (addr, shutdown_tx) = serve_counting(false, connections.clone()).await;
+        let mut prov =
+            GrpcProvider::new("grpc".to_string(), addr.ip().to_string(), addr.port(), None);
+        prov.timeout_ms = Some(100);
+        let transport = GrpcTransport::new();
+
+        for _ in 0..2 {
+            let value = transport
+                .call_tool("echo", HashMap::new(), &prov)
+                .await
+                .expect("call");
+            assert_eq!(value["tool"], json!("echo"));
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let err = transport
+            .call_tool("slow", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::Timeout(message)) => {
+                assert!(message.contains("provider 'grpc' tool 'slow'"), "{message}");
+                assert!(message.contains("100ms"), "{message}");
+            }
+            other => panic!("expected a timeout, got {:?}: {err:#}", other),
+        }
+
+        let _ = shutdown_tx.send(());
+    }
+
+    #[tokio::test]
+    async fn large_messages_need_raised_limits() {
+        use tonic::codec::CompressionEncoding;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener);
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let service = UtcpServiceServer::new(MockGrpc)
+                .max_decoding_message_size(32 << 20)
+                .max_encoding_message_size(32 << 20)
+                .accept_compressed(CompressionEncoding::Gzip)
+                .send_compressed(CompressionEncoding::Gzip);
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming_shutdown(incoming, async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let args = HashMap::from([("blob".to_string(), json!("a".repeat(10 << 20)))]);
+        let mut prov =
+            GrpcProvider::new("grpc".to_string(), addr.ip().to_string(), addr.port(), None);
+        let transport = GrpcTransport::new();
+
+        // The echoed 10MB reply is over tonic's default 4MiB decoding limit.
+        let err = transport
+            .call_tool("echo", args.clone(), &prov)
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::ToolCall(message)) => {
+                assert!(message.contains("provider 'grpc' tool 'echo'"), "{message}");
+                assert!(message.contains("the limit of 4194304 bytes"), "{message}");
+                assert!(message.contains("max_decoding_message_size"), "{message}");
+            }
+            other => panic!("expected a ToolCall error, got {:?}: {err:#}", other),
+        }
+
+        prov.max_encoding_message_size = Some(1 << 20);
+        let err = transport
+            .call_tool("echo", args.clone(), &prov)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("the limit of 1048576 bytes"),
+            "{err:#}"
+        );
+
+        prov.max_decoding_message_size = Some(32 << 20);
+        prov.max_encoding_message_size = Some(32 << 20);
+        let value = transport
+            .call_tool("echo", args.clone(), &prov)
+            .await
+            .expect("raised limits");
+        assert_eq!(value["args"]["blob"].as_str().unwrap().len(), 10 << 20);
+
+        prov.compression = Some(GrpcCompression::Gzip);
+        let value = transport
+            .call_tool("echo", args, &prov)
+            .await
+            .expect("gzip");
+        assert_eq!(value["args"]["blob"].as_str().unwrap().len(), 10 << 20);
+
+        let _ = shutdown_tx.send(());
+    }
+
+    #[cfg(feature = "grpc-web")]
+    #[tokio::test]
+    async fn grpc_web_calls_through_http1() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener);
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            Server::builder()
+                .accept_http1(true)
+                .layer(tonic_web::GrpcWebLayer::new())
+                .add_service(UtcpServiceServer::new(MockGrpc))
+                .serve_with_incoming_shutdown(incoming, async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let mut prov =
+            GrpcProvider::new("web".to_string(), addr.ip().to_string(), addr.port(), None);
+        prov.grpc_web = true;
+        let transport = GrpcTransport::new();
+
+        let tools = transport
+            .register_tool_provider(&prov)
+            .await
+            .expect("register");
+        assert_eq!(tools[0].name, "echo");
+
+        let args = HashMap::from([("msg".to_string(), json!("hi"))]);
+        let value = transport
+            .call_tool("echo", args.clone(), &prov)
+            .await
+            .expect("call");
+        assert_eq!(value, json!({ "tool": "echo", "args": { "msg": "hi" } }));
+
+        let mut stream = transport
+            .call_tool_stream("echo", args, &prov)
+            .await
+            .expect("call stream");
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!({ "idx": 1 }));
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!({ "idx": 2 }));
+        assert!(stream.next().await.unwrap().is_none());
+
+        let err = transport
+            .call_tool_duplex("echo", &prov)
+            .await
+            .err()
+            .expect("duplex over gRPC-Web");
+        assert!(err.to_string().contains("gRPC-Web"), "{err}");
+
+        let _ = shutdown_tx.send(());
+    }
 }
@@ -0,0 +1,127 @@
+//! Discovering a server's services through the gRPC reflection API (`grpc.reflection.v1alpha`).
+use anyhow::{anyhow, Result};
+use tonic::metadata::MetadataMap;
+use tonic::Request;
+
+use super::channel::GrpcChannel;
+use super::dynamic::{DescriptorPool, MethodInfo};
+use crate::grpcpb::reflection::server_reflection_client::ServerReflectionClient;
+use crate::grpcpb::reflection::server_reflection_request::MessageRequest;
+use crate::grpcpb::reflection::server_reflection_response::MessageResponse;
+use crate::grpcpb::reflection::ServerReflectionRequest;
+
+/// Rounds of import fetching before an incomplete descriptor set is reported.
+const MAX_DEPENDENCY_ROUNDS: usize = 16;
+
+/// The services a server publishes, with the descriptors needed to call their methods.
+#[derive(Debug)]
+pub(crate) struct ReflectedServices {
+    pub pool: DescriptorPool,
+    pub methods: Vec<MethodInfo>,
+}
+
+impl ReflectedServices {
+    /// The method behind `tool_name`, which may carry the `provider.` prefix.
+    pub(crate) fn method(&self, provider: &str, tool_name: &str) -> Result<&MethodInfo> {
+        let unprefixed = tool_name.strip_prefix(&format!("{}.", provider));
+        [Some(tool_name), unprefixed]
+            .into_iter()
+            .flatten()
+            .find_map(|name| self.methods.iter().find(|m| m.tool_name() == name))
+            .ok_or_else(|| anyhow!("No gRPC method found for tool '{}'", tool_name))
+    }
+}
+
+/// List the server's services, other than reflection itself, and load the descriptors of the
+/// files defining them along with every file those import. `metadata` is sent with each
+/// reflection request.
+pub(crate) async fn discover(
+    channel: GrpcChannel,
+    metadata: &MetadataMap,
+) -> Result<ReflectedServices> {
+    let mut client = ServerReflectionClient::new(channel);
+
+    let listed = exchange(
+        &mut client,
+        metadata,
+        vec![MessageRequest::ListServices("*".to_string())],
+    )
+    .await?;
+    let services: Vec<String> = listed
+        .into_iter()
+        .filter_map(|response| match response {
+            MessageResponse::ListServicesResponse(list) => Some(list.service),
+            _ => None,
+        })
+        .flatten()
+        .map(|service| service.name)
+        .filter(|name| !name.starts_with("grpc.reflection."))
+        .collect();
+
+    let mut pool = DescriptorPool::default();
+    let mut requests: Vec<MessageRequest> = services
+        .iter()
+        .cloned()
+        .map(MessageRequest::FileContainingSymbol)
+        .collect();
+    for _ in 0..MAX_DEPENDENCY_ROUNDS {
+        if requests.is_empty() {
+            break;
+        }
+        for response in exchange(&mut client, metadata, requests).await? {
+            if let MessageResponse::FileDescriptorResponse(files) = response {
+                for file in files.file_descriptor_proto {
+                    pool.add_file(&file)?;
+                }
+            }
+        }
+        requests = pool
+            .missing_dependencies()
+            .into_iter()
+            .map(MessageRequest::FileByFilename)
+            .collect();
+    }
+    if !requests.is_empty() {
+        return Err(anyhow!(
+            "gRPC reflection did not return every imported file; still missing {}",
+            pool.missing_dependencies().join(", ")
+        ));
+    }
+
+    let methods = pool.methods(&services);
+    Ok(ReflectedServices { pool, methods })
+}
+
+/// Send `requests` on one reflection stream and collect the answers.
+async fn exchange(
+    client: &mut ServerReflectionClient<GrpcChannel>,
+    metadata: &MetadataMap,
+    requests: Vec<MessageRequest>,
+) -> Result<Vec<MessageResponse>> {
+    let messages: Vec<ServerReflectionRequest> = requests
+        .into_iter()
+        .map(|request| ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(request),
+        })
+        .collect();
+    let mut request = Request::new(futures::stream::iter(messages));
+    *request.metadata_mut() = metadata.clone();
+
+    let mut stream = client.server_reflection_info(request).await?.into_inner();
+    let mut responses = Vec::new();
+    while let Some(response) = stream.message().await? {
+        match response.message_response {
+            Some(MessageResponse::ErrorResponse(err)) => {
+                return Err(anyhow!(
+                    "gRPC reflection error {}: {}",
+                    err.error_code,
+                    err.error_message
+                ))
+            }
+            Some(response) => responses.push(response),
+            None => {}
+        }
+    }
+    Ok(responses)
+}
@@ -0,0 +1,55 @@
+//! gRPC-Web over HTTP/1.1, for servers reachable only through a translating proxy such as Envoy.
+use anyhow::Result;
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::body::BoxBody;
+use tonic::codegen::http::{Request, Response, Uri};
+use tonic::codegen::{Body, BoxFuture, Service, StdError};
+use tonic_web::{GrpcWebCall, GrpcWebClientService};
+
+type WebClient =
+    GrpcWebClientService<hyper::Client<HttpsConnector<HttpConnector>, GrpcWebCall<BoxBody>>>;
+
+/// An HTTP/1.1 client that frames each call as gRPC-Web and sends it to `origin`.
+#[derive(Clone)]
+pub(crate) struct WebChannel {
+    client: WebClient,
+    origin: Uri,
+}
+
+impl WebChannel {
+    pub(crate) fn new(origin: &str, connect_timeout: Option<Duration>) -> Result<Self> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(connect_timeout);
+        let client = hyper::Client::builder().build(HttpsConnector::new_with_connector(http));
+        Ok(Self {
+            client: GrpcWebClientService::new(client),
+            origin: origin.parse()?,
+        })
+    }
+}
+
+impl Service<Request<BoxBody>> for WebChannel {
+    type Response = Response<BoxBody>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.client.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: Request<BoxBody>) -> Self::Future {
+        // tonic only fills in the method path; the scheme and authority are the proxy's.
+        let mut parts = self.origin.clone().into_parts();
+        parts.path_and_query = request.uri().path_and_query().cloned();
+        match Uri::from_parts(parts) {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(err) => return Box::pin(async move { Err(err.into()) }),
+        }
+        let response = self.client.call(request);
+        Box::pin(async move { Ok(response.await?.map(|body| body.boxed_unsync())) })
+    }
+}
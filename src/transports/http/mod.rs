@@ -1,42 +1,63 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use reqwest::{header, Client};
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::{header, Client, StatusCode};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::auth::AuthConfig;
 use crate::providers::base::Provider;
-use crate::providers::http::HttpProvider;
+use crate::providers::http::{HttpProvider, ParamStyle, QueryEncoding, QueryStyle};
 use crate::security::{validate_size_limit, validate_url_security};
 use crate::tools::Tool;
+use crate::transports::client_cache::ClientCache;
+use crate::transports::http_client::SharedClient;
+use crate::transports::redirect::send_following_redirects;
+use crate::transports::response::{decode_body, envelope, DEFAULT_MAX_INLINE_BINARY};
+use crate::transports::response_cache::{cache_key, CachedResponse, ResponseCache};
+use crate::transports::retry::RetryPolicy;
+use crate::transports::timeouts::{timeout_error, with_total_timeout};
 use crate::transports::{stream::StreamResult, ClientTransport};
 
 const MAX_RESPONSE_SIZE: usize = 10 * 1024 * 1024; // 10 MB
+/// JSON bodies up to this size are sent uncompressed even when `compress_requests` is set.
+const COMPRESSION_THRESHOLD: usize = 1024;
+const DEFAULT_MAX_UPLOAD_SIZE: usize = 25 * 1024 * 1024; // 25 MB
+/// Per-attempt limit for calls when neither the provider nor the shared client sets one.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Key marking an argument as a file to upload, e.g. `{"$file": {"path": "report.pdf"}}`.
+const FILE_MARKER: &str = "$file";
 
 /// Transport for synchronous HTTP providers that expose JSON APIs.
 pub struct HttpClientTransport {
     pub client: Client,
+    clients: ClientCache,
+    responses: ResponseCache,
 }
 
 impl HttpClientTransport {
-    /// Build a new HTTP client transport with tuned defaults.
+    /// Build a new HTTP client transport on the process-wide shared client.
     pub fn new() -> Self {
-        // Optimized HTTP client with connection pooling and compression
-        let client = Client::builder()
-            .timeout(Duration::from_secs(60)) // Increased timeout for better reliability
-            .pool_max_idle_per_host(100) // Connection pool optimization
-            .pool_idle_timeout(Some(Duration::from_secs(90))) // Keep connections alive longer
-            .tcp_keepalive(Some(Duration::from_secs(30))) // TCP keep-alive
-            .gzip(true) // Enable gzip compression
-            .http2_adaptive_window(true) // HTTP/2 flow control optimization
-            .http2_keep_alive_interval(Some(Duration::from_secs(10))) // HTTP/2 keep-alive
-            .http2_keep_alive_timeout(Duration::from_secs(20))
-            .http2_keep_alive_while_idle(true)
-            .build()
-            .expect("Failed to build HTTP client");
+        Self::with_client(SharedClient::default())
+    }
+
+    /// Build an HTTP client transport that sends requests through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
+        Self {
+            client: shared.client().clone(),
+            clients: ClientCache::new(shared),
+            responses: ResponseCache::new(),
+        }
+    }
 
-        Self { client }
+    /// Drop the session cookies stored for `provider`; later calls start with an empty jar.
+    pub fn clear_cookies(&self, provider: &str) {
+        self.clients.clear_cookies(provider);
     }
 
     /// Attach authentication headers or query params to the request builder.
@@ -82,7 +103,19 @@ impl ClientTransport for HttpClientTransport {
         // Fetch tool definitions from the HTTP endpoint
         // The endpoint should return a UTCP manifest or OpenAPI spec
         validate_url_security(&http_prov.url, false)?;
-        let mut request_builder = self.client.get(&http_prov.url);
+        let timeouts = http_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &http_prov.base.name,
+            timeouts,
+            http_prov.proxy.as_ref(),
+            http_prov.use_cookies,
+            http_prov.redirect.is_some(),
+        )?;
+        let mut request_builder = self.clients.apply_request_timeout(
+            client.get(&http_prov.url),
+            timeouts,
+            Some(DEFAULT_REQUEST_TIMEOUT),
+        );
 
         if let Some(headers) = &http_prov.headers {
             for (key, value) in headers {
@@ -94,21 +127,29 @@ impl ClientTransport for HttpClientTransport {
             request_builder = self.apply_auth(request_builder, auth)?;
         }
 
-        let response = request_builder.send().await?;
+        let body_bytes = with_total_timeout(timeouts, async {
+            let response = send_following_redirects(
+                request_builder,
+                http_prov.redirect.as_ref(),
+                RetryPolicy::NONE,
+                api_key_header(http_prov.base.auth.as_ref()),
+            )
+            .await?;
 
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Failed to fetch tools from {}: {}",
-                http_prov.url,
-                response.status()
-            ));
-        }
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Failed to fetch tools from {}: {}",
+                    http_prov.url,
+                    response.status()
+                ));
+            }
 
-        // Try to parse as UTCP manifest first
-        let body_bytes = response.bytes().await?;
+            response.bytes().await.map_err(timeout_error)
+        })
+        .await?;
         validate_size_limit(&body_bytes, MAX_RESPONSE_SIZE)?;
 
-        // Try parsing as JSON
+        // Try parsing as a UTCP manifest
         if let Ok(manifest) = serde_json::from_slice::<Value>(&body_bytes) {
             // Check if it's a UTCP manifest (has "tools" array)
             if let Some(tools_array) = manifest.get("tools").and_then(|v| v.as_array()) {
@@ -127,14 +168,16 @@ impl ClientTransport for HttpClientTransport {
         Ok(vec![])
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
-        // HTTP transport is stateless, so nothing to do
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        // Drop per-provider clients, cookie jars and cached responses
+        self.clients.remove(&prov.name());
+        self.responses.remove(&prov.name());
         Ok(())
     }
 
     async fn call_tool(
         &self,
-        _tool_name: &str,
+        tool_name: &str,
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Value> {
@@ -144,26 +187,57 @@ impl ClientTransport for HttpClientTransport {
             .downcast_ref::<HttpProvider>()
             .ok_or_else(|| anyhow!("Provider is not an HttpProvider"))?;
 
-        // Handle URL path parameters (e.g., {id} in URL)
-        let mut url = http_prov.url.clone();
-        for (key, value) in &args {
-            let placeholder = format!("{{{}}}", key);
-            if url.contains(&placeholder) {
-                url = url.replace(&placeholder, &value.to_string());
-            }
+        // Only GET calls are revalidated; the key covers every argument, path ones included
+        let cache = http_prov
+            .response_cache
+            .as_ref()
+            .filter(|_| http_prov.http_method.eq_ignore_ascii_case("GET"))
+            .map(|config| (config, cache_key(tool_name, &args)));
+
+        // Fill URL path parameters (e.g., {id} in URL); they are not sent again as query/body
+        let mut args = args;
+        let mut url =
+            substitute_path_params(&http_prov.url, http_prov.path_params.as_deref(), &mut args)?;
+
+        let method_upper = http_prov.http_method.to_uppercase();
+        let sends_body = matches!(method_upper.as_str(), "POST" | "PUT" | "PATCH");
+        // A declared body field supplies the whole body; other args then go in the query string
+        let body_arg = match (&http_prov.body_field, sends_body) {
+            (Some(field), true) => Some(args.remove(field)),
+            _ => None,
+        };
+        if !sends_body || body_arg.is_some() {
+            url = url_with_query(
+                &url,
+                &args,
+                http_prov.param_styles.as_ref(),
+                http_prov.query_style,
+            );
         }
 
         validate_url_security(&url, false)?;
 
-        let method_upper = http_prov.http_method.to_uppercase();
+        let timeouts = http_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &http_prov.base.name,
+            timeouts,
+            http_prov.proxy.as_ref(),
+            http_prov.use_cookies,
+            http_prov.redirect.is_some(),
+        )?;
         let mut request_builder = match method_upper.as_str() {
-            "GET" => self.client.get(&url),
-            "POST" => self.client.post(&url),
-            "PUT" => self.client.put(&url),
-            "DELETE" => self.client.delete(&url),
-            "PATCH" => self.client.patch(&url),
+            "GET" => client.get(&url),
+            "POST" => client.post(&url),
+            "PUT" => client.put(&url),
+            "DELETE" => client.delete(&url),
+            "PATCH" => client.patch(&url),
             method => return Err(anyhow!("Unsupported HTTP method: {}", method)),
         };
+        request_builder = self.clients.apply_request_timeout(
+            request_builder,
+            timeouts,
+            Some(DEFAULT_REQUEST_TIMEOUT),
+        );
 
         // Add headers
         if let Some(headers) = &http_prov.headers {
@@ -176,31 +250,115 @@ impl ClientTransport for HttpClientTransport {
             request_builder = self.apply_auth(request_builder, auth)?;
         }
 
-        // Determine how to send remaining args
-        if method_upper == "POST" || method_upper == "PUT" || method_upper == "PATCH" {
-            // Send as JSON body
-            request_builder = request_builder.json(&args);
-        } else {
-            // Send as query parameters
-            for (key, value) in &args {
-                request_builder = request_builder.query(&[(key, value.to_string())]);
+        if let Some(body_arg) = body_arg {
+            // An omitted body argument sends no body at all
+            if let Some(body) = body_arg {
+                request_builder = json_body(request_builder, &body, http_prov.compress_requests)?;
+            }
+        } else if sends_body {
+            // Send remaining args as the request body
+            let content_type = http_prov.content_type.as_deref().unwrap_or("");
+            if content_type.starts_with("application/x-www-form-urlencoded") {
+                request_builder = request_builder.form(&form_fields(&args));
+            } else if content_type.starts_with("multipart/form-data")
+                || args.values().any(is_file_arg)
+            {
+                let boundary = multipart_boundary();
+                let max_upload = http_prov
+                    .max_upload_bytes
+                    .map_or(DEFAULT_MAX_UPLOAD_SIZE, |limit| limit as usize);
+                let body = encode_multipart(
+                    &args,
+                    &boundary,
+                    http_prov.upload_root.as_deref(),
+                    max_upload,
+                )
+                .await?;
+                request_builder = request_builder
+                    .header(
+                        header::CONTENT_TYPE,
+                        format!("multipart/form-data; boundary={}", boundary),
+                    )
+                    .body(body);
+            } else {
+                // Send as JSON body
+                request_builder = json_body(request_builder, &args, http_prov.compress_requests)?;
             }
         }
 
-        // Send request
-        let response = request_builder.send().await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "HTTP request failed with status: {}",
-                response.status()
-            ));
+        // Send request, retrying rate-limited and failed attempts
+        let policy = RetryPolicy::for_method(
+            &method_upper,
+            http_prov.max_retries,
+            http_prov.max_retry_delay_ms,
+            http_prov.retry_non_idempotent,
+        );
+        let cached = cache
+            .as_ref()
+            .and_then(|(config, key)| self.responses.get(&http_prov.base.name, key, config));
+        if let Some(cached) = &cached {
+            request_builder = cached.conditional(request_builder);
         }
+        let (response, not_modified) = with_total_timeout(timeouts, async {
+            let response = send_following_redirects(
+                request_builder,
+                http_prov.redirect.as_ref(),
+                policy,
+                api_key_header(http_prov.base.auth.as_ref()),
+            )
+            .await?;
 
-        let body_bytes = response.bytes().await?;
+            if response.status() == StatusCode::NOT_MODIFIED {
+                if let Some(cached) = cached {
+                    return Ok((cached, true));
+                }
+            }
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "HTTP request failed with status: {}",
+                    response.status()
+                ));
+            }
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await.map_err(timeout_error)?;
+            Ok((
+                CachedResponse {
+                    status,
+                    headers,
+                    body,
+                },
+                false,
+            ))
+        })
+        .await?;
+        if let Some((config, key)) = cache {
+            if not_modified {
+                self.responses.revalidated(&http_prov.base.name, &key);
+            } else {
+                self.responses
+                    .store(&http_prov.base.name, key, config, response.clone());
+            }
+        }
+        let CachedResponse {
+            status,
+            headers,
+            body: body_bytes,
+        } = response;
         validate_size_limit(&body_bytes, MAX_RESPONSE_SIZE)?;
-        let result: Value = serde_json::from_slice(&body_bytes)?;
-        Ok(result)
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+        let max_inline = http_prov
+            .max_inline_binary_bytes
+            .map_or(DEFAULT_MAX_INLINE_BINARY, |limit| limit as usize);
+        let body = decode_body(content_type, &body_bytes, max_inline).await?;
+        if http_prov.return_envelope {
+            Ok(envelope(status, &headers, body))
+        } else {
+            Ok(body)
+        }
     }
 
     async fn call_tool_stream(
@@ -213,15 +371,469 @@ impl ClientTransport for HttpClientTransport {
     }
 }
 
+/// Header carrying an API key, stripped along with `Authorization` on cross-origin redirects.
+fn api_key_header(auth: Option<&AuthConfig>) -> Option<&str> {
+    match auth {
+        Some(AuthConfig::ApiKey(api_key)) if api_key.location.eq_ignore_ascii_case("header") => {
+            Some(&api_key.var_name)
+        }
+        _ => None,
+    }
+}
+
+/// Attach `value` as a JSON body, gzipping it when `compress` is set and the encoded body
+/// exceeds [`COMPRESSION_THRESHOLD`].
+fn json_body(
+    builder: reqwest::RequestBuilder,
+    value: &impl serde::Serialize,
+    compress: bool,
+) -> Result<reqwest::RequestBuilder> {
+    let body = serde_json::to_vec(value)?;
+    let builder = builder.header(header::CONTENT_TYPE, "application/json");
+    if !compress || body.len() <= COMPRESSION_THRESHOLD {
+        return Ok(builder.body(body));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&body)?;
+    Ok(builder
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(encoder.finish()?))
+}
+
+/// Replace `{name}` segments of the provider URL with percent-encoded argument values,
+/// removing the consumed arguments. Every one of the provider's `path_params`, which the
+/// OpenAPI converter records, must be supplied. Hand-written providers without `path_params`
+/// only have the placeholders that match an argument filled in; other braces stay literal.
+fn substitute_path_params(
+    url: &str,
+    path_params: Option<&[String]>,
+    args: &mut HashMap<String, Value>,
+) -> Result<String> {
+    let mut filled = url.to_string();
+    let names = match path_params {
+        Some(names) => names.to_vec(),
+        None => crate::openapi::path_placeholders(url)
+            .into_iter()
+            .filter(|name| args.contains_key(name))
+            .collect(),
+    };
+    for name in names {
+        let value = args
+            .remove(&name)
+            .ok_or_else(|| anyhow!("Missing required path parameter '{}'", name))?;
+        let raw = match value {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        filled = filled.replace(&format!("{{{}}}", name), &encode_path_segment(&raw));
+    }
+    Ok(filled)
+}
+
+/// URL and JSON body for calling an OpenAPI operation URL from a streaming provider. Path
+/// params are filled in; methods with a body send `body_field`'s argument (or, without one,
+/// all remaining args) as the body, and any args left over go in the query string.
+pub(crate) fn operation_request(
+    url: &str,
+    method: &str,
+    path_params: Option<&[String]>,
+    param_styles: Option<&HashMap<String, ParamStyle>>,
+    body_field: Option<&str>,
+    mut args: HashMap<String, Value>,
+) -> Result<(String, Option<Value>)> {
+    let url = substitute_path_params(url, path_params, &mut args)?;
+    let sends_body = matches!(method, "POST" | "PUT" | "PATCH");
+    let body = match (body_field, sends_body) {
+        (Some(field), true) => args.remove(field),
+        (None, true) => return Ok((url, Some(Value::Object(args.into_iter().collect())))),
+        (_, false) => None,
+    };
+    let url = url_with_query(&url, &args, param_styles, QueryEncoding::Repeat);
+    Ok((url, body))
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters.
+fn encode_path_segment(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Append args to `url` as a query string; `url` is returned unchanged when there are none.
+pub(crate) fn url_with_query(
+    url: &str,
+    args: &HashMap<String, Value>,
+    styles: Option<&HashMap<String, ParamStyle>>,
+    encoding: QueryEncoding,
+) -> String {
+    if args.is_empty() {
+        return url.to_string();
+    }
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!(
+        "{}{}{}",
+        url,
+        separator,
+        build_query(args, styles, encoding)
+    )
+}
+
+/// Encode args as a query string, ordered by name. Args with a declared [`ParamStyle`]
+/// follow the OpenAPI serialization rules; others are written according to `encoding`.
+fn build_query(
+    args: &HashMap<String, Value>,
+    styles: Option<&HashMap<String, ParamStyle>>,
+    encoding: QueryEncoding,
+) -> String {
+    let mut keys: Vec<&String> = args.keys().collect();
+    keys.sort();
+
+    let encode = |v: &Value| encode_path_segment(&form_value(v));
+    let mut pairs: Vec<String> = Vec::new();
+    for key in keys {
+        let value = &args[key];
+        let name = encode_path_segment(key);
+        let style = match (styles.and_then(|styles| styles.get(key)), encoding) {
+            (Some(style), _) => *style,
+            (None, QueryEncoding::Repeat) => match value {
+                // Keep the argument name so fields of different objects cannot collide
+                Value::Object(fields) => {
+                    let mut field_names: Vec<&String> = fields.keys().collect();
+                    field_names.sort();
+                    pairs.extend(field_names.into_iter().map(|field| {
+                        format!(
+                            "{}.{}={}",
+                            name,
+                            encode_path_segment(field),
+                            encode(&fields[field])
+                        )
+                    }));
+                    continue;
+                }
+                _ => ParamStyle::default(),
+            },
+            (None, QueryEncoding::Comma) => ParamStyle {
+                style: QueryStyle::Form,
+                explode: false,
+            },
+            (None, QueryEncoding::Brackets) => match value {
+                Value::Array(items) => {
+                    pairs.extend(
+                        items
+                            .iter()
+                            .map(|item| format!("{}[]={}", name, encode(item))),
+                    );
+                    continue;
+                }
+                _ => ParamStyle {
+                    style: QueryStyle::DeepObject,
+                    explode: true,
+                },
+            },
+            (None, QueryEncoding::Json) => {
+                pairs.push(format!(
+                    "{}={}",
+                    name,
+                    encode_path_segment(&value.to_string())
+                ));
+                continue;
+            }
+        };
+
+        match value {
+            Value::Null => {}
+            Value::Array(items) if style.explode => {
+                pairs.extend(
+                    items
+                        .iter()
+                        .map(|item| format!("{}={}", name, encode(item))),
+                );
+            }
+            Value::Array(items) => {
+                let delimiter = match style.style {
+                    QueryStyle::SpaceDelimited => "%20",
+                    QueryStyle::PipeDelimited => "|",
+                    QueryStyle::Form | QueryStyle::DeepObject => ",",
+                };
+                let joined: Vec<String> = items.iter().map(encode).collect();
+                pairs.push(format!("{}={}", name, joined.join(delimiter)));
+            }
+            Value::Object(fields) => {
+                let mut field_names: Vec<&String> = fields.keys().collect();
+                field_names.sort();
+                match (style.style, style.explode) {
+                    (QueryStyle::DeepObject, _) => {
+                        for field in field_names {
+                            pairs.push(format!(
+                                "{}[{}]={}",
+                                name,
+                                encode_path_segment(field),
+                                encode(&fields[field])
+                            ));
+                        }
+                    }
+                    (_, true) => {
+                        for field in field_names {
+                            pairs.push(format!(
+                                "{}={}",
+                                encode_path_segment(field),
+                                encode(&fields[field])
+                            ));
+                        }
+                    }
+                    (_, false) => {
+                        let joined: Vec<String> = field_names
+                            .into_iter()
+                            .flat_map(|field| [encode_path_segment(field), encode(&fields[field])])
+                            .collect();
+                        pairs.push(format!("{}={}", name, joined.join(",")));
+                    }
+                }
+            }
+            scalar => pairs.push(format!("{}={}", name, encode(scalar))),
+        }
+    }
+    pairs.join("&")
+}
+
+/// Render a form field value; strings are sent verbatim and everything else as JSON text.
+fn form_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Flatten args into ordered form fields, repeating the key for each array element.
+fn form_fields(args: &HashMap<String, Value>) -> Vec<(String, String)> {
+    let mut keys: Vec<&String> = args.keys().collect();
+    keys.sort();
+
+    let mut fields = Vec::new();
+    for key in keys {
+        match &args[key] {
+            Value::Null => {}
+            Value::Array(items) => {
+                for item in items {
+                    fields.push((key.clone(), form_value(item)));
+                }
+            }
+            value => fields.push((key.clone(), form_value(value))),
+        }
+    }
+    fields
+}
+
+/// Generate a boundary that is unique per request within this process.
+fn multipart_boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!(
+        "utcp-boundary-{:x}-{:x}",
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// A file argument resolved to the bytes and metadata of one multipart part.
+struct FilePart {
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+/// True for arguments explicitly marked as files with `{"$file": {...}}`.
+fn is_file_arg(value: &Value) -> bool {
+    value.get(FILE_MARKER).is_some_and(Value::is_object)
+}
+
+/// Read a file argument. Only values marked with `{"$file": {...}}` are files; any other
+/// object is an ordinary form field. The source is either `"path"` or base64
+/// `"base64"`/`"content"`, with optional `"filename"` and `"content_type"`. Paths must lie
+/// inside `root` once symlinks are resolved. `budget` is the number of upload bytes still
+/// allowed.
+async fn file_part(
+    value: &Value,
+    root: Option<&str>,
+    budget: &mut usize,
+) -> Result<Option<FilePart>> {
+    let Some(obj) = value.get(FILE_MARKER).and_then(Value::as_object) else {
+        return Ok(None);
+    };
+
+    let (default_name, data) = if let Some(path) = obj.get("path").and_then(Value::as_str) {
+        let resolved = upload_path(path, root).await?;
+        let size = tokio::fs::metadata(&resolved)
+            .await
+            .map_err(|e| anyhow!("Failed to read file '{}': {}", path, e))?
+            .len();
+        if size > *budget as u64 {
+            return Err(upload_too_large(path));
+        }
+        let data = tokio::fs::read(&resolved)
+            .await
+            .map_err(|e| anyhow!("Failed to read file '{}': {}", path, e))?;
+        let name = resolved
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        (name, data)
+    } else if let Some(content) = obj
+        .get("base64")
+        .or_else(|| obj.get("content"))
+        .and_then(Value::as_str)
+    {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(content)
+            .map_err(|e| anyhow!("Invalid base64 file content: {}", e))?;
+        (None, data)
+    } else {
+        return Err(anyhow!(
+            "File argument needs a \"path\" or \"base64\" source"
+        ));
+    };
+
+    let filename = obj
+        .get("filename")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or(default_name)
+        .unwrap_or_else(|| "file".to_string());
+    if data.len() > *budget {
+        return Err(upload_too_large(&filename));
+    }
+    *budget -= data.len();
+
+    Ok(Some(FilePart {
+        content_type: header_safe(
+            "content type",
+            obj.get("content_type")
+                .and_then(Value::as_str)
+                .unwrap_or("application/octet-stream"),
+        )?
+        .to_string(),
+        filename: header_safe("filename", &filename)?.to_string(),
+        data,
+    }))
+}
+
+/// Resolve an upload path against the provider's upload root, refusing anything that ends
+/// up outside it.
+async fn upload_path(path: &str, root: Option<&str>) -> Result<std::path::PathBuf> {
+    let root = root.ok_or_else(|| {
+        anyhow!(
+            "Cannot upload '{}': the provider has no upload_root for file paths",
+            path
+        )
+    })?;
+    let root = tokio::fs::canonicalize(root)
+        .await
+        .map_err(|e| anyhow!("Invalid upload_root '{}': {}", root, e))?;
+    let resolved = tokio::fs::canonicalize(root.join(path))
+        .await
+        .map_err(|e| anyhow!("Failed to read file '{}': {}", path, e))?;
+    if !resolved.starts_with(&root) {
+        return Err(anyhow!(
+            "Cannot upload '{}': it is outside the provider's upload_root",
+            path
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Reject multipart header values that would break out of their header or quoted string.
+fn header_safe<'a>(what: &str, value: &'a str) -> Result<&'a str> {
+    if value.contains(['"', '\r', '\n']) {
+        return Err(anyhow!(
+            "Invalid multipart {} {:?}: quotes and line breaks are not allowed",
+            what,
+            value
+        ));
+    }
+    Ok(value)
+}
+
+fn upload_too_large(name: &str) -> anyhow::Error {
+    anyhow!(
+        "Upload of '{}' exceeds the provider's upload size limit",
+        name
+    )
+}
+
+/// Encode args as a `multipart/form-data` body. File arguments become file parts and
+/// everything else is encoded like a form field. Files may add up to `max_upload` bytes and
+/// are only read from disk inside `upload_root`.
+async fn encode_multipart(
+    args: &HashMap<String, Value>,
+    boundary: &str,
+    upload_root: Option<&str>,
+    max_upload: usize,
+) -> Result<Vec<u8>> {
+    let mut keys: Vec<&String> = args.keys().collect();
+    keys.sort();
+
+    let mut budget = max_upload;
+    let mut body = Vec::new();
+    for key in keys {
+        let value = &args[key];
+        let key = header_safe("field name", key)?;
+        if let Some(part) = file_part(value, upload_root, &mut budget).await? {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    boundary,
+                    key,
+                    part.filename,
+                    part.content_type
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+            continue;
+        }
+
+        let items = match value {
+            Value::Null => continue,
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        for item in items {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary,
+                    key,
+                    form_value(item)
+                )
+                .as_bytes(),
+            );
+        }
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok(body)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::auth::{ApiKeyAuth, AuthType, BasicAuth, OAuth2Auth};
     use crate::providers::base::{BaseProvider, ProviderType};
-    use crate::providers::http::HttpProvider;
+    use crate::providers::http::{HttpProvider, QueryEncoding};
     use axum::{extract::Json, routing::get, routing::post, Router};
     use serde_json::json;
     use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
 
     #[test]
     fn apply_auth_handles_api_key_locations() {
@@ -365,6 +977,23 @@ mod tests {
             headers: None,
             body_field: None,
             header_fields: None,
+            path_params: None,
+            param_styles: None,
+            query_style: QueryEncoding::Repeat,
+            openapi_filters: None,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_upload_bytes: None,
+            upload_root: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            compress_requests: false,
+            use_cookies: false,
+            redirect: None,
+            response_cache: None,
         };
 
         let transport = HttpClientTransport::new();
@@ -390,4 +1019,351 @@ mod tests {
             .expect("expected streaming error");
         assert!(err.to_string().contains("Streaming not supported"));
     }
+
+    #[test]
+    fn substitutes_and_consumes_path_params() {
+        let url = "http://example.com/items/{item_id}/parts/{part}";
+        let mut args = HashMap::from([
+            ("item_id".to_string(), json!(42)),
+            ("part".to_string(), json!("a b/c")),
+            ("verbose".to_string(), json!(true)),
+        ]);
+        let filled = substitute_path_params(url, None, &mut args).unwrap();
+        assert_eq!(filled, "http://example.com/items/42/parts/a%20b%2Fc");
+        assert_eq!(args.len(), 1);
+        assert!(args.contains_key("verbose"));
+
+        let path_params = ["item_id".to_string(), "part".to_string()];
+        let mut args = HashMap::from([("part".to_string(), json!("x"))]);
+        let err = substitute_path_params(url, Some(&path_params), &mut args).unwrap_err();
+        assert!(err.to_string().contains("'item_id'"), "{err}");
+
+        // Without recorded path params, unmatched braces are left alone
+        let url = "http://example.com/items/{item_id}/{literal}";
+        let mut args = HashMap::from([("item_id".to_string(), json!(7))]);
+        let filled = substitute_path_params(url, None, &mut args).unwrap();
+        assert_eq!(filled, "http://example.com/items/7/{literal}");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn operation_requests_split_args_between_path_query_and_body() {
+        let path_params = ["room".to_string()];
+        let styles = HashMap::from([(
+            "tags".to_string(),
+            ParamStyle {
+                style: QueryStyle::Form,
+                explode: false,
+            },
+        )]);
+        let args = || {
+            HashMap::from([
+                ("room".to_string(), json!("r 1")),
+                ("tags".to_string(), json!(["a", "b"])),
+                ("body".to_string(), json!({"text": "hi"})),
+            ])
+        };
+        let request = |method, body_field| {
+            operation_request(
+                "http://example.com/rooms/{room}/events",
+                method,
+                Some(&path_params),
+                Some(&styles),
+                body_field,
+                args(),
+            )
+            .unwrap()
+        };
+
+        let (url, body) = request("POST", Some("body"));
+        assert_eq!(url, "http://example.com/rooms/r%201/events?tags=a,b");
+        assert_eq!(body, Some(json!({"text": "hi"})));
+
+        let (url, body) = request("POST", None);
+        assert_eq!(url, "http://example.com/rooms/r%201/events");
+        assert_eq!(
+            body,
+            Some(json!({"tags": ["a", "b"], "body": {"text": "hi"}}))
+        );
+
+        let (url, body) = request("GET", Some("body"));
+        assert!(
+            url.starts_with("http://example.com/rooms/r%201/events?"),
+            "{url}"
+        );
+        assert!(url.ends_with("&tags=a,b"), "{url}");
+        assert_eq!(body, None);
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_calls_honoring_retry_after() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route(
+            "/",
+            get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                        (
+                            axum::http::StatusCode::TOO_MANY_REQUESTS,
+                            [(axum::http::header::RETRY_AFTER, "0")],
+                            Json(json!({ "error": "slow down" })),
+                        )
+                    } else {
+                        (
+                            axum::http::StatusCode::OK,
+                            [(axum::http::header::RETRY_AFTER, "0")],
+                            Json(json!({ "ok": true })),
+                        )
+                    }
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let transport = HttpClientTransport::new();
+        let mut provider = HttpProvider::new(
+            "limited".to_string(),
+            format!("http://{}", addr),
+            "GET".to_string(),
+            None,
+        );
+        let result = transport
+            .call_tool("limited.op", HashMap::new(), &provider)
+            .await
+            .expect("call succeeds after retries");
+        assert_eq!(result, json!({ "ok": true }));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Without retries the first 429 is surfaced as-is.
+        hits.store(0, Ordering::SeqCst);
+        provider.max_retries = Some(0);
+        let err = transport
+            .call_tool("limited.op", HashMap::new(), &provider)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("429"), "{err}");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn uploads_file_arguments_as_multipart_parts() {
+        /// Report the file part of a single-file upload plus the plain `title` field.
+        async fn upload(headers: axum::http::HeaderMap, body: bytes::Bytes) -> Json<Value> {
+            let content_type = headers[header::CONTENT_TYPE].to_str().unwrap();
+            let boundary = content_type
+                .strip_prefix("multipart/form-data; boundary=")
+                .unwrap();
+            let body = String::from_utf8_lossy(&body).into_owned();
+            let mut file = json!(null);
+            let mut title = json!(null);
+            for part in body.split(&format!("--{}", boundary)) {
+                let Some((head, content)) = part.split_once("\r\n\r\n") else {
+                    continue;
+                };
+                let content = content.strip_suffix("\r\n").unwrap_or(content);
+                if let Some(name) = head.split("filename=\"").nth(1) {
+                    file = json!({
+                        "filename": name.split('"').next().unwrap(),
+                        "type": head.split("Content-Type: ").nth(1).unwrap(),
+                        "bytes": content,
+                    });
+                } else if head.contains("name=\"title\"") {
+                    title = json!(content);
+                }
+            }
+            Json(json!({ "file": file, "title": title }))
+        }
+
+        let app = Router::new().route("/extract", post(upload));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("q3.pdf"), b"%PDF-1.7 fake").unwrap();
+        let transport = HttpClientTransport::new();
+        let mut provider = HttpProvider::new(
+            "docs".to_string(),
+            format!("http://{}/extract", addr),
+            "POST".to_string(),
+            None,
+        );
+        provider.upload_root = Some(dir.path().to_string_lossy().into_owned());
+
+        // No multipart content type is declared; the `$file` marker alone switches to it.
+        let args = HashMap::from([
+            ("title".to_string(), json!("Q3 report")),
+            (
+                "document".to_string(),
+                json!({ "$file": {
+                    "path": "q3.pdf",
+                    "filename": "report.pdf",
+                    "content_type": "application/pdf"
+                }}),
+            ),
+        ]);
+        let result = transport
+            .call_tool("docs.extract", args.clone(), &provider)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "file": {"filename": "report.pdf", "type": "application/pdf", "bytes": "%PDF-1.7 fake"},
+                "title": "Q3 report"
+            })
+        );
+
+        let inline = HashMap::from([(
+            "document".to_string(),
+            json!({ "$file": { "base64": "aGVsbG8=" } }),
+        )]);
+        let result = transport
+            .call_tool("docs.extract", inline, &provider)
+            .await
+            .unwrap();
+        assert_eq!(
+            result["file"],
+            json!({"filename": "file", "type": "application/octet-stream", "bytes": "hello"})
+        );
+
+        provider.max_upload_bytes = Some(4);
+        let err = transport
+            .call_tool("docs.extract", args, &provider)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("upload size limit"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn file_uploads_stay_inside_the_upload_root() {
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("ok.txt"), b"ok").unwrap();
+        let root = root.path().to_string_lossy().into_owned();
+        let upload = |path: String, root: Option<&str>| {
+            let args = HashMap::from([("f".to_string(), json!({ "$file": { "path": path } }))]);
+            let root = root.map(str::to_string);
+            async move { encode_multipart(&args, "b", root.as_deref(), DEFAULT_MAX_UPLOAD_SIZE).await }
+        };
+
+        assert!(upload("ok.txt".to_string(), Some(&root)).await.is_ok());
+        let secret = outside.path().join("secret.txt");
+        for path in [
+            secret.to_string_lossy().into_owned(),
+            format!(
+                "../{}/secret.txt",
+                outside.path().file_name().unwrap().to_string_lossy()
+            ),
+        ] {
+            let err = upload(path, Some(&root)).await.unwrap_err();
+            assert!(err.to_string().contains("outside"), "{err}");
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&secret, std::path::Path::new(&root).join("link")).unwrap();
+            assert!(upload("link".to_string(), Some(&root)).await.is_err());
+        }
+        let err = upload("ok.txt".to_string(), None).await.unwrap_err();
+        assert!(err.to_string().contains("upload_root"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn multipart_headers_reject_quotes_and_line_breaks() {
+        let encode = |args: HashMap<String, Value>| async move {
+            encode_multipart(&args, "b", None, DEFAULT_MAX_UPLOAD_SIZE).await
+        };
+        let file = |extra: Value| {
+            let mut spec = json!({ "base64": "aGk=" });
+            spec.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            json!({ "$file": spec })
+        };
+
+        for args in [
+            HashMap::from([("a\"; x=\"y".to_string(), json!("v"))]),
+            HashMap::from([("a\r\nX-Injected: 1".to_string(), json!("v"))]),
+            HashMap::from([("f".to_string(), file(json!({ "filename": "a\"b" })))]),
+            HashMap::from([("f".to_string(), file(json!({ "filename": "a\r\nb" })))]),
+            HashMap::from([(
+                "f".to_string(),
+                file(json!({ "content_type": "text/plain\r\nX-Injected: 1" })),
+            )]),
+        ] {
+            assert!(encode(args).await.is_err());
+        }
+        let body = encode(HashMap::from([
+            ("f".to_string(), file(json!({}))),
+            ("n".to_string(), json!("v")),
+        ]))
+        .await
+        .unwrap();
+        assert!(String::from_utf8(body)
+            .unwrap()
+            .contains("name=\"f\"; filename=\"file\""));
+    }
+
+    #[test]
+    fn query_style_controls_array_and_object_encoding() {
+        let args = HashMap::from([
+            ("ids".to_string(), json!([1, 2])),
+            ("filter".to_string(), json!({ "b": "x y", "a": 1 })),
+            ("q".to_string(), json!("rust")),
+        ]);
+        let query = |encoding| build_query(&args, None, encoding);
+
+        assert_eq!(
+            query(QueryEncoding::Repeat),
+            "filter.a=1&filter.b=x%20y&ids=1&ids=2&q=rust"
+        );
+        assert_eq!(
+            query(QueryEncoding::Comma),
+            "filter=a,1,b,x%20y&ids=1,2&q=rust"
+        );
+        assert_eq!(
+            query(QueryEncoding::Brackets),
+            "filter[a]=1&filter[b]=x%20y&ids[]=1&ids[]=2&q=rust"
+        );
+        assert_eq!(
+            query(QueryEncoding::Json),
+            "filter=%7B%22a%22%3A1%2C%22b%22%3A%22x%20y%22%7D&ids=%5B1%2C2%5D&q=%22rust%22"
+        );
+
+        // A declared OpenAPI style still wins over the provider-wide encoding.
+        let styles = HashMap::from([
+            ("ids".to_string(), ParamStyle::default()),
+            ("filter".to_string(), ParamStyle::default()),
+        ]);
+        let only_ids = HashMap::from([("ids".to_string(), json!([1, 2]))]);
+        assert_eq!(
+            build_query(&only_ids, Some(&styles), QueryEncoding::Json),
+            "ids=1&ids=2"
+        );
+        assert_eq!(
+            build_query(&args, Some(&styles), QueryEncoding::Repeat),
+            "a=1&b=x%20y&ids=1&ids=2&q=rust"
+        );
+        assert_eq!(
+            url_with_query("http://h/p?x=1", &only_ids, None, QueryEncoding::Repeat),
+            "http://h/p?x=1&ids=1&ids=2"
+        );
+    }
 }
@@ -0,0 +1,100 @@
+//! The reqwest client shared by the HTTP-based transports (HTTP, SSE, streamable HTTP,
+//! GraphQL and MCP), so they reuse one connection pool and one set of defaults. With the
+//! `compression` feature (on by default) it decodes gzip, deflate and brotli responses.
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use reqwest::{Client, ClientBuilder};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Connection and default-timeout settings for [`SharedClient`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpClientConfig {
+    /// Idle connections kept open per host.
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle pooled connection is kept before closing it.
+    pub pool_idle_timeout_ms: Option<u64>,
+    /// TCP keep-alive interval for open connections.
+    pub tcp_keepalive_ms: Option<u64>,
+    /// `User-Agent` sent with every request.
+    pub user_agent: Option<String>,
+    /// Connect timeout for providers without `timeouts.connect_ms`.
+    pub connect_timeout_ms: Option<u64>,
+    /// Per-attempt limit for non-streaming calls from providers without `timeouts.request_ms`.
+    /// Unset keeps each transport's own default: 60s for HTTP, 120s for MCP over HTTP and
+    /// none for SSE, streamable HTTP and GraphQL.
+    pub request_timeout_ms: Option<u64>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 100,
+            pool_idle_timeout_ms: Some(90_000),
+            tcp_keepalive_ms: Some(30_000),
+            user_agent: None,
+            connect_timeout_ms: None,
+            request_timeout_ms: None,
+        }
+    }
+}
+
+/// A reqwest client built from [`HttpClientConfig`]. Clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct SharedClient {
+    client: Client,
+    config: HttpClientConfig,
+}
+
+static DEFAULT_CLIENT: Lazy<SharedClient> = Lazy::new(|| {
+    SharedClient::new(HttpClientConfig::default()).expect("Failed to build HTTP client")
+});
+
+impl SharedClient {
+    /// Build a client from `config`.
+    pub fn new(config: HttpClientConfig) -> Result<Self> {
+        let client = builder_for(&config).build()?;
+        Ok(Self { client, config })
+    }
+
+    /// The underlying reqwest client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The settings this client was built from.
+    pub fn config(&self) -> &HttpClientConfig {
+        &self.config
+    }
+
+    /// A builder with the same settings, for providers that need a dedicated client.
+    pub(crate) fn builder(&self) -> ClientBuilder {
+        builder_for(&self.config)
+    }
+}
+
+impl Default for SharedClient {
+    /// The process-wide client built from [`HttpClientConfig::default`].
+    fn default() -> Self {
+        DEFAULT_CLIENT.clone()
+    }
+}
+
+fn builder_for(config: &HttpClientConfig) -> ClientBuilder {
+    let mut builder = Client::builder()
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(config.pool_idle_timeout_ms.map(Duration::from_millis))
+        .tcp_keepalive(config.tcp_keepalive_ms.map(Duration::from_millis))
+        .http2_adaptive_window(true)
+        .http2_keep_alive_interval(Some(Duration::from_secs(10)))
+        .http2_keep_alive_timeout(Duration::from_secs(20))
+        .http2_keep_alive_while_idle(true);
+    if let Some(user_agent) = &config.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    if let Some(ms) = config.connect_timeout_ms {
+        builder = builder.connect_timeout(Duration::from_millis(ms));
+    }
+    builder
+}
@@ -2,7 +2,7 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
-use reqwest::{header, Client};
+use reqwest::header;
 use serde_json::{de::Deserializer, Value};
 use std::collections::HashMap;
 use tokio::sync::mpsc;
@@ -11,6 +11,14 @@ use crate::auth::AuthConfig;
 use crate::providers::base::Provider;
 use crate::providers::http_stream::StreamableHttpProvider;
 use crate::tools::Tool;
+use crate::transports::client_cache::ClientCache;
+use crate::transports::http::{operation_request, url_with_query};
+use crate::transports::http_client::SharedClient;
+use crate::transports::response::{
+    decode_body, envelope, response_metadata, DEFAULT_MAX_INLINE_BINARY,
+};
+use crate::transports::retry::{send_with_retry, RetryPolicy};
+use crate::transports::timeouts::{timeout_error, with_total_timeout};
 use crate::transports::{
     stream::{boxed_channel_stream, StreamResult},
     ClientTransport,
@@ -18,17 +26,27 @@ use crate::transports::{
 
 /// Transport for HTTP endpoints that stream newline-delimited JSON or chunked bodies.
 pub struct StreamableHttpTransport {
-    client: Client,
+    clients: ClientCache,
 }
 
 impl StreamableHttpTransport {
-    /// Create a streaming HTTP transport with a default client.
+    /// Create a streaming HTTP transport on the process-wide shared client.
     pub fn new() -> Self {
+        Self::with_client(SharedClient::default())
+    }
+
+    /// Create a streaming HTTP transport that sends requests through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
         Self {
-            client: Client::new(),
+            clients: ClientCache::new(shared),
         }
     }
 
+    /// Drop the session cookies stored for `provider`; later calls start with an empty jar.
+    pub fn clear_cookies(&self, provider: &str) {
+        self.clients.clear_cookies(provider);
+    }
+
     /// Attach authentication headers or query params to the request builder.
     fn apply_auth(
         &self,
@@ -58,6 +76,35 @@ impl StreamableHttpTransport {
             )),
         }
     }
+
+    /// URL and JSON body for a call. Operation URLs are called as-is; otherwise the tool name
+    /// is appended to `url`, GET sends the args as query parameters and other methods as the body.
+    fn call_request(
+        prov: &StreamableHttpProvider,
+        tool_name: &str,
+        method: &str,
+        args: HashMap<String, Value>,
+    ) -> Result<(String, Option<Value>)> {
+        if prov.operation_url {
+            return operation_request(
+                &prov.url,
+                method,
+                prov.path_params.as_deref(),
+                prov.param_styles.as_ref(),
+                prov.body_field.as_deref(),
+                args,
+            );
+        }
+        let call_name = tool_name
+            .strip_prefix(&format!("{}.", prov.base.name))
+            .unwrap_or(tool_name);
+        let url = format!("{}/{}", prov.url.trim_end_matches('/'), call_name);
+        if method == "GET" {
+            Ok((url_with_query(&url, &args, None, prov.query_style), None))
+        } else {
+            Ok((url, Some(Value::Object(args.into_iter().collect()))))
+        }
+    }
 }
 
 #[async_trait]
@@ -67,7 +114,8 @@ impl ClientTransport for StreamableHttpTransport {
         Ok(vec![])
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        self.clients.remove(&prov.name());
         Ok(())
     }
 
@@ -83,19 +131,30 @@ impl ClientTransport for StreamableHttpTransport {
             .downcast_ref::<StreamableHttpProvider>()
             .ok_or_else(|| anyhow!("Provider is not a StreamableHttpProvider"))?;
 
-        let call_name = tool_name
-            .strip_prefix(&format!("{}.", http_prov.base.name))
-            .unwrap_or(tool_name);
-        let url = format!("{}/{}", http_prov.url.trim_end_matches('/'), call_name);
         let method_upper = http_prov.http_method.to_uppercase();
+        let (url, body) = Self::call_request(http_prov, tool_name, &method_upper, args)?;
+        let timeouts = http_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &http_prov.base.name,
+            timeouts,
+            http_prov.proxy.as_ref(),
+            http_prov.use_cookies,
+            false,
+        )?;
         let mut request_builder = match method_upper.as_str() {
-            "GET" => self.client.get(&url).query(&args),
-            "POST" => self.client.post(&url).json(&args),
-            "PUT" => self.client.put(&url).json(&args),
-            "DELETE" => self.client.delete(&url).json(&args),
-            "PATCH" => self.client.patch(&url).json(&args),
+            "GET" => client.get(&url),
+            "POST" => client.post(&url),
+            "PUT" => client.put(&url),
+            "DELETE" => client.delete(&url),
+            "PATCH" => client.patch(&url),
             other => return Err(anyhow!("Unsupported HTTP method: {}", other)),
         };
+        if let Some(body) = &body {
+            request_builder = request_builder.json(body);
+        }
+        request_builder = self
+            .clients
+            .apply_request_timeout(request_builder, timeouts, None);
 
         if let Some(headers) = &http_prov.headers {
             for (k, v) in headers {
@@ -107,17 +166,39 @@ impl ClientTransport for StreamableHttpTransport {
             request_builder = self.apply_auth(request_builder, auth)?;
         }
 
-        let response = request_builder.send().await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "HTTP request failed with status: {}",
-                response.status()
-            ));
-        }
+        let policy = RetryPolicy::for_method(
+            &method_upper,
+            http_prov.max_retries,
+            http_prov.max_retry_delay_ms,
+            http_prov.retry_non_idempotent,
+        );
+        with_total_timeout(timeouts, async {
+            let response = send_with_retry(request_builder, policy).await?;
+
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "HTTP request failed with status: {}",
+                    response.status()
+                ));
+            }
 
-        let value: Value = response.json().await?;
-        Ok(value)
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await.map_err(timeout_error)?;
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok());
+            let max_inline = http_prov
+                .max_inline_binary_bytes
+                .map_or(DEFAULT_MAX_INLINE_BINARY, |limit| limit as usize);
+            let body = decode_body(content_type, &body, max_inline).await?;
+            if http_prov.return_envelope {
+                Ok(envelope(status, &headers, body))
+            } else {
+                Ok(body)
+            }
+        })
+        .await
     }
 
     async fn call_tool_stream(
@@ -131,19 +212,27 @@ impl ClientTransport for StreamableHttpTransport {
             .downcast_ref::<StreamableHttpProvider>()
             .ok_or_else(|| anyhow!("Provider is not a StreamableHttpProvider"))?;
 
-        let call_name = tool_name
-            .strip_prefix(&format!("{}.", http_prov.base.name))
-            .unwrap_or(tool_name);
-        let url = format!("{}/{}", http_prov.url.trim_end_matches('/'), call_name);
         let method_upper = http_prov.http_method.to_uppercase();
+        let (url, body) = Self::call_request(http_prov, tool_name, &method_upper, args)?;
+        let timeouts = http_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &http_prov.base.name,
+            timeouts,
+            http_prov.proxy.as_ref(),
+            http_prov.use_cookies,
+            false,
+        )?;
         let mut req = match method_upper.as_str() {
-            "GET" => self.client.get(url).query(&args),
-            "POST" => self.client.post(url).json(&args),
-            "PUT" => self.client.put(url).json(&args),
-            "DELETE" => self.client.delete(url).json(&args),
-            "PATCH" => self.client.patch(url).json(&args),
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "DELETE" => client.delete(url),
+            "PATCH" => client.patch(url),
             other => return Err(anyhow!("Unsupported HTTP method: {}", other)),
         };
+        if let Some(body) = &body {
+            req = req.json(body);
+        }
 
         if let Some(headers) = &http_prov.headers {
             for (k, v) in headers {
@@ -154,8 +243,12 @@ impl ClientTransport for StreamableHttpTransport {
         if let Some(auth) = &http_prov.base.auth {
             req = self.apply_auth(req, auth)?;
         }
+        // Chunks must reach the parser as they are sent, not in compressed blocks.
+        req = req.header(header::ACCEPT_ENCODING, "identity");
 
-        let response = req.send().await?;
+        // Only the wait for the response headers is limited; the body may stream indefinitely.
+        let response =
+            with_total_timeout(timeouts, async { req.send().await.map_err(timeout_error) }).await?;
 
         if !response.status().is_success() {
             return Err(anyhow!(
@@ -164,11 +257,20 @@ impl ClientTransport for StreamableHttpTransport {
             ));
         }
 
+        let metadata = http_prov
+            .return_envelope
+            .then(|| response_metadata(response.status(), response.headers()));
+
         // Stream response chunks and parse them as JSON values.
         let mut byte_stream = response.bytes_stream();
         let (tx, rx) = mpsc::channel(16);
 
         tokio::spawn(async move {
+            if let Some(metadata) = metadata {
+                if tx.send(Ok(metadata)).await.is_err() {
+                    return;
+                }
+            }
             let mut buffer: Vec<u8> = Vec::new();
             while let Some(chunk_result) = byte_stream.next().await {
                 match chunk_result {
@@ -215,7 +317,8 @@ impl ClientTransport for StreamableHttpTransport {
                 }
             }
 
-            if !buffer.is_empty() {
+            // NDJSON bodies usually end with a newline after the last value
+            if buffer.iter().any(|byte| !byte.is_ascii_whitespace()) {
                 let _ = tx
                     .send(Err(anyhow!("Stream ended with incomplete JSON frame")))
                     .await;
@@ -231,8 +334,11 @@ mod tests {
     use super::*;
     use crate::auth::{ApiKeyAuth, AuthType, BasicAuth, OAuth2Auth};
     use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::providers::http::QueryEncoding;
     use crate::providers::http_stream::StreamableHttpProvider;
-    use axum::{body::Body, extract::Json, http::Response, routing::post, Router};
+    use axum::extract::{Json, RawQuery};
+    use axum::routing::{get, post};
+    use axum::{body::Body, http::Response, Router};
     use bytes::Bytes;
     use serde_json::json;
     use std::net::TcpListener;
@@ -351,6 +457,19 @@ mod tests {
             url: base_url.clone(),
             http_method: "POST".to_string(),
             headers: None,
+            query_style: QueryEncoding::Repeat,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            use_cookies: false,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
+            body_field: None,
         };
 
         let transport = StreamableHttpTransport::new();
@@ -413,6 +532,19 @@ mod tests {
             url: base_url.clone(),
             http_method: "POST".to_string(),
             headers: None,
+            query_style: QueryEncoding::Repeat,
+            max_retries: None,
+            max_retry_delay_ms: None,
+            retry_non_idempotent: false,
+            timeouts: None,
+            proxy: None,
+            max_inline_binary_bytes: None,
+            return_envelope: false,
+            use_cookies: false,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
+            body_field: None,
         };
 
         let transport = StreamableHttpTransport::new();
@@ -422,4 +554,41 @@ mod tests {
             .expect("call tool");
         assert_eq!(value, json!({"ok": true}));
     }
+
+    #[tokio::test]
+    async fn get_requests_encode_array_args_with_query_style() {
+        async fn echo_query(RawQuery(query): RawQuery) -> Json<Value> {
+            Json(json!({ "query": query }))
+        }
+
+        let app = Router::new().route("/search", get(echo_query));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let mut provider =
+            StreamableHttpProvider::new("search".to_string(), format!("http://{}", addr), None);
+        provider.http_method = "GET".to_string();
+        let args = HashMap::from([("tag".to_string(), json!(["a", "b"]))]);
+
+        let transport = StreamableHttpTransport::new();
+        let value = transport
+            .call_tool("search.search", args.clone(), &provider)
+            .await
+            .expect("call tool");
+        assert_eq!(value, json!({ "query": "tag=a&tag=b" }));
+
+        provider.query_style = QueryEncoding::Brackets;
+        let value = transport
+            .call_tool("search.search", args, &provider)
+            .await
+            .expect("call tool");
+        assert_eq!(value, json!({ "query": "tag[]=a&tag[]=b" }));
+    }
 }
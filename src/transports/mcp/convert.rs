@@ -0,0 +1,389 @@
+//! Mapping MCP tool definitions and `tools/call` results onto UTCP's shapes.
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::errors::UtcpError;
+use crate::tools::{Tool, ToolInputOutputSchema};
+
+/// Boolean tool annotations that become tags, without their `Hint` suffix, when set.
+const ANNOTATION_HINTS: &[(&str, &str)] = &[
+    ("readOnlyHint", "readOnly"),
+    ("destructiveHint", "destructive"),
+    ("idempotentHint", "idempotent"),
+    ("openWorldHint", "openWorld"),
+];
+
+/// Convert one entry of a `tools/list` result. MCP describes inputs as `inputSchema` and, since
+/// 2025-06-18, outputs as `outputSchema`; servers written against UTCP's own `inputs`/`outputs`
+/// are accepted too. Annotation hints that are set become tags alongside any `tags`.
+pub(crate) fn tool_from_mcp(tool: &Value) -> Result<Tool> {
+    let name = tool
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("MCP tool definition has no name: {}", tool))?;
+    let annotations = tool.get("annotations");
+    let description = tool
+        .get("description")
+        .or_else(|| annotations.and_then(|a| a.get("title")))
+        .or_else(|| tool.get("title"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let schema = |keys: [&str; 2]| {
+        keys.iter()
+            .find_map(|key| tool.get(*key))
+            .map(schema_from_json)
+            .unwrap_or_else(object_schema)
+    };
+
+    let mut tags: Vec<String> = tool
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(annotations) = annotations {
+        for (hint, tag) in ANNOTATION_HINTS {
+            if annotations.get(*hint).and_then(Value::as_bool) == Some(true) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+
+    Ok(Tool {
+        name: name.to_string(),
+        description: description.to_string(),
+        inputs: schema(["inputSchema", "inputs"]),
+        outputs: schema(["outputSchema", "outputs"]),
+        tags,
+        average_response_size: None,
+        provider: None,
+    })
+}
+
+/// Read a JSON Schema object, keeping nested property schemas as they are. A `type` listing
+/// several types, as in `["string", "null"]`, keeps the first that is not `null`.
+pub(crate) fn schema_from_json(schema: &Value) -> ToolInputOutputSchema {
+    let type_ = match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("object")
+            .to_string(),
+        _ => "object".to_string(),
+    };
+    let object = |key: &str| -> Option<HashMap<String, Value>> {
+        schema
+            .get(key)
+            .and_then(Value::as_object)
+            .map(|map| map.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    };
+    let string = |key: &str| schema.get(key).and_then(Value::as_str).map(str::to_string);
+
+    ToolInputOutputSchema {
+        properties: object("properties"),
+        required: schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            }),
+        description: string("description"),
+        title: string("title"),
+        items: object("items"),
+        enum_: schema.get("enum").and_then(Value::as_array).cloned(),
+        minimum: schema.get("minimum").and_then(Value::as_f64),
+        maximum: schema.get("maximum").and_then(Value::as_f64),
+        format: string("format"),
+        one_of: schema
+            .get("oneOf")
+            .or_else(|| schema.get("anyOf"))
+            .and_then(Value::as_array)
+            .cloned(),
+        ..ToolInputOutputSchema::new(type_)
+    }
+}
+
+fn object_schema() -> ToolInputOutputSchema {
+    schema_from_json(&json!({ "type": "object" }))
+}
+
+/// Unwrap a `tools/call` result:
+/// - `isError` results fail with [`UtcpError::ToolCall`] carrying their text;
+/// - `structuredContent` is returned as is;
+/// - otherwise each `content` item is converted, text to a string and images, audio and blob
+///   resources to `{"$binary": {"content_type", "size", "base64"}}`, and a single item is
+///   returned on its own while several become an array.
+///
+/// Results without a `content` array come from servers that answer with plain JSON and are
+/// returned unchanged.
+pub(crate) fn call_result(result: Value) -> Result<Value> {
+    let content = match result.get("content").and_then(Value::as_array) {
+        Some(content) => content,
+        None => return Ok(result),
+    };
+
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        let message: Vec<&str> = content
+            .iter()
+            .filter_map(|item| item.get("text").and_then(Value::as_str))
+            .collect();
+        return Err(UtcpError::ToolCall(message.join("\n")).into());
+    }
+    if let Some(structured) = result.get("structuredContent") {
+        return Ok(structured.clone());
+    }
+
+    let mut values = content
+        .iter()
+        .map(content_item)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(match values.len() {
+        0 => Value::Null,
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    })
+}
+
+/// One content item: text as a string, images and audio as `$binary`, embedded resources as
+/// their contents, and anything else with its fields kept.
+pub(crate) fn content_item(item: &Value) -> Result<Value> {
+    match item.get("type").and_then(Value::as_str) {
+        Some("text") => Ok(item.get("text").cloned().unwrap_or(Value::Null)),
+        Some("image") | Some("audio") => binary(item, "data"),
+        Some("resource") => resource_contents(item.get("resource").unwrap_or(&Value::Null)),
+        _ => {
+            // resource_link and future item types keep their fields
+            let mut item: Map<String, Value> = item.as_object().cloned().unwrap_or_default();
+            item.remove("type");
+            Ok(Value::Object(item))
+        }
+    }
+}
+
+/// One entry of resource contents: the text of a text resource, or a blob as `$binary`.
+pub(crate) fn resource_contents(contents: &Value) -> Result<Value> {
+    match contents.get("text") {
+        Some(text) => Ok(text.clone()),
+        None => binary(contents, "blob"),
+    }
+}
+
+fn binary(item: &Value, field: &str) -> Result<Value> {
+    let data = item
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("MCP content has no '{}': {}", field, item))?;
+    let size = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| anyhow!("MCP content '{}' is not valid base64: {}", field, e))?
+        .len();
+    let content_type = item
+        .get("mimeType")
+        .and_then(Value::as_str)
+        .unwrap_or("application/octet-stream");
+    Ok(json!({ "$binary": {
+        "content_type": content_type,
+        "size": size,
+        "base64": data,
+    }}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `tools/list` from the reference "everything" and filesystem servers.
+    fn listed_tools() -> Value {
+        json!({
+            "tools": [
+                {
+                    "name": "echo",
+                    "description": "Echoes back the input",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "message": { "type": "string", "description": "Message to echo" }
+                        },
+                        "required": ["message"],
+                        "additionalProperties": false,
+                        "$schema": "http://json-schema.org/draft-07/schema#"
+                    }
+                },
+                {
+                    "name": "sampleLLM",
+                    "description": "Samples from an LLM using MCP's sampling feature",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "prompt": { "type": "string", "description": "The prompt to send" },
+                            "maxTokens": {
+                                "type": "number",
+                                "default": 100,
+                                "description": "Maximum number of tokens to generate"
+                            },
+                            "options": {
+                                "type": "object",
+                                "properties": {
+                                    "temperature": { "type": ["number", "null"] }
+                                }
+                            }
+                        },
+                        "required": ["prompt"]
+                    }
+                },
+                {
+                    "name": "read_text_file",
+                    "title": "Read Text File",
+                    "description": "Read the complete contents of a file as text.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string" },
+                            "tail": { "type": "number" }
+                        },
+                        "required": ["path"]
+                    },
+                    "outputSchema": {
+                        "type": "object",
+                        "properties": { "content": { "type": "string" } },
+                        "required": ["content"]
+                    },
+                    "annotations": { "readOnlyHint": true, "openWorldHint": false }
+                },
+                { "name": "getTinyImage", "inputSchema": { "type": "object", "properties": {} } }
+            ]
+        })
+    }
+
+    #[test]
+    fn tools_map_input_and_output_schemas() {
+        let tools: Vec<Tool> = listed_tools()["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool_from_mcp(tool).unwrap())
+            .collect();
+
+        let echo = &tools[0];
+        assert_eq!(echo.description, "Echoes back the input");
+        assert_eq!(echo.inputs.type_, "object");
+        assert_eq!(echo.inputs.required, Some(vec!["message".to_string()]));
+        assert_eq!(
+            echo.inputs.properties.as_ref().unwrap()["message"],
+            json!({ "type": "string", "description": "Message to echo" })
+        );
+        assert_eq!(echo.outputs.type_, "object");
+        assert!(echo.outputs.properties.is_none());
+        assert!(echo.tags.is_empty());
+
+        let nested = &tools[1].inputs.properties.as_ref().unwrap()["options"];
+        assert_eq!(
+            nested["properties"]["temperature"]["type"],
+            json!(["number", "null"])
+        );
+
+        let read = &tools[2];
+        assert_eq!(read.outputs.required, Some(vec!["content".to_string()]));
+        assert_eq!(read.tags, vec!["readOnly"]);
+
+        assert_eq!(tools[3].description, "");
+        assert_eq!(tools[3].inputs.properties, Some(HashMap::new()));
+    }
+
+    #[test]
+    fn legacy_inputs_and_tags_still_parse() {
+        let tool = tool_from_mcp(&json!({
+            "name": "add",
+            "description": "Add two numbers",
+            "inputs": { "type": "object", "required": ["a", "b"] },
+            "outputs": { "type": "number" },
+            "tags": ["math"]
+        }))
+        .unwrap();
+        assert_eq!(tool.inputs.required.unwrap(), vec!["a", "b"]);
+        assert_eq!(tool.outputs.type_, "number");
+        assert_eq!(tool.tags, vec!["math"]);
+
+        assert!(tool_from_mcp(&json!({ "description": "nameless" })).is_err());
+    }
+
+    #[test]
+    fn schema_types_skip_null() {
+        let schema = schema_from_json(&json!({ "type": ["null", "string"], "format": "uri" }));
+        assert_eq!(schema.type_, "string");
+        assert_eq!(schema.format.as_deref(), Some("uri"));
+    }
+
+    #[test]
+    fn call_results_unwrap_content() {
+        let text = json!({ "content": [{ "type": "text", "text": "Echo: hi" }] });
+        assert_eq!(call_result(text).unwrap(), json!("Echo: hi"));
+
+        let image = json!({
+            "content": [
+                { "type": "text", "text": "This is a tiny image:" },
+                { "type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png" },
+                {
+                    "type": "resource",
+                    "resource": {
+                        "uri": "test://static/resource/1",
+                        "mimeType": "text/plain",
+                        "text": "Resource 1: This is a plaintext resource"
+                    }
+                },
+                {
+                    "type": "resource_link",
+                    "uri": "file:///project/README.md",
+                    "name": "README.md"
+                }
+            ]
+        });
+        assert_eq!(
+            call_result(image).unwrap(),
+            json!([
+                "This is a tiny image:",
+                { "$binary": { "content_type": "image/png", "size": 8, "base64": "iVBORw0KGgo=" } },
+                "Resource 1: This is a plaintext resource",
+                { "uri": "file:///project/README.md", "name": "README.md" }
+            ])
+        );
+
+        let structured = json!({
+            "content": [{ "type": "text", "text": "{\"content\":\"hello\"}" }],
+            "structuredContent": { "content": "hello" }
+        });
+        assert_eq!(
+            call_result(structured).unwrap(),
+            json!({ "content": "hello" })
+        );
+
+        let plain = json!({ "result": 3 });
+        assert_eq!(call_result(plain.clone()).unwrap(), plain);
+    }
+
+    #[test]
+    fn error_results_fail() {
+        let err = call_result(json!({
+            "content": [{ "type": "text", "text": "ENOENT: no such file" }],
+            "isError": true
+        }))
+        .unwrap_err();
+        match err.downcast_ref::<UtcpError>() {
+            Some(UtcpError::ToolCall(message)) => assert_eq!(message, "ENOENT: no such file"),
+            other => panic!("expected a ToolCall error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! MCP resources and prompts offered as tools next to the server's own.
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use super::convert::{content_item, resource_contents, schema_from_json};
+use crate::tools::Tool;
+
+/// A tool standing in for one resources or prompts method, offered when the server
+/// advertises the matching capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FeatureTool {
+    ResourceList,
+    ResourceRead,
+    PromptList,
+    PromptGet,
+}
+
+impl FeatureTool {
+    pub(crate) const ALL: [FeatureTool; 4] = [
+        FeatureTool::ResourceList,
+        FeatureTool::ResourceRead,
+        FeatureTool::PromptList,
+        FeatureTool::PromptGet,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            FeatureTool::ResourceList => "resource_list",
+            FeatureTool::ResourceRead => "resource_read",
+            FeatureTool::PromptList => "prompt_list",
+            FeatureTool::PromptGet => "prompt_get",
+        }
+    }
+
+    /// The server capability the tool depends on.
+    pub(crate) fn capability(self) -> &'static str {
+        match self {
+            FeatureTool::ResourceList | FeatureTool::ResourceRead => "resources",
+            FeatureTool::PromptList | FeatureTool::PromptGet => "prompts",
+        }
+    }
+
+    fn method(self) -> &'static str {
+        match self {
+            FeatureTool::ResourceList => "resources/list",
+            FeatureTool::ResourceRead => "resources/read",
+            FeatureTool::PromptList => "prompts/list",
+            FeatureTool::PromptGet => "prompts/get",
+        }
+    }
+
+    pub(crate) fn tool(self) -> Tool {
+        let cursor = json!({
+            "type": "object",
+            "properties": {
+                "cursor": { "type": "string", "description": "nextCursor of the previous page" }
+            }
+        });
+        let (description, inputs) = match self {
+            FeatureTool::ResourceList => ("List the resources the MCP server offers", cursor),
+            FeatureTool::ResourceRead => (
+                "Read an MCP resource by URI",
+                json!({
+                    "type": "object",
+                    "properties": { "uri": { "type": "string" } },
+                    "required": ["uri"]
+                }),
+            ),
+            FeatureTool::PromptList => ("List the prompts the MCP server offers", cursor),
+            FeatureTool::PromptGet => (
+                "Render an MCP prompt with its arguments",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "arguments": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" }
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+        };
+        Tool {
+            name: self.name().to_string(),
+            description: description.to_string(),
+            inputs: schema_from_json(&inputs),
+            outputs: schema_from_json(&json!({ "type": "object" })),
+            tags: vec!["mcp".to_string(), self.capability().to_string()],
+            average_response_size: None,
+            provider: None,
+        }
+    }
+
+    /// The MCP method and params for a call with `args`.
+    pub(crate) fn request(self, args: HashMap<String, Value>) -> Result<(&'static str, Value)> {
+        let required = |key: &str| match args.get(key) {
+            Some(Value::String(value)) => Ok(value.clone()),
+            _ => Err(anyhow!("{} needs a '{}' string argument", self.name(), key)),
+        };
+        let params = match self {
+            FeatureTool::ResourceList | FeatureTool::PromptList => match args.get("cursor") {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            },
+            FeatureTool::ResourceRead => json!({ "uri": required("uri")? }),
+            FeatureTool::PromptGet => {
+                let mut params = Map::new();
+                params.insert("name".to_string(), Value::String(required("name")?));
+                if let Some(arguments) = args.get("arguments") {
+                    params.insert("arguments".to_string(), arguments.clone());
+                }
+                Value::Object(params)
+            }
+        };
+        Ok((self.method(), params))
+    }
+
+    /// Convert the method's result. Read resources become their text, or `$binary` for blobs,
+    /// with several contents returned as an array; prompt messages have their content
+    /// converted the same way. Listings are returned as they are.
+    pub(crate) fn result(self, mut result: Value) -> Result<Value> {
+        match self {
+            FeatureTool::ResourceList | FeatureTool::PromptList => Ok(result),
+            FeatureTool::ResourceRead => {
+                let contents = result
+                    .get("contents")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| anyhow!("resources/read result has no contents"))?;
+                let mut values = contents
+                    .iter()
+                    .map(resource_contents)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(match values.len() {
+                    1 => values.remove(0),
+                    _ => Value::Array(values),
+                })
+            }
+            FeatureTool::PromptGet => {
+                if let Some(messages) = result.get_mut("messages").and_then(Value::as_array_mut) {
+                    for message in messages {
+                        if let Some(content) = message.get("content") {
+                            message["content"] = content_item(content)?;
+                        }
+                    }
+                }
+                Ok(result)
+            }
+        }
+    }
+}
@@ -1,37 +1,359 @@
 // MCP (Model Context Protocol) Transport
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use reqwest::{header, Client};
+use reqwest::header;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout, Command};
-use tokio::sync::Mutex;
+use tokio::process::{ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, watch, Mutex, OnceCell};
+use tokio_util::sync::CancellationToken;
 
 use crate::auth::AuthConfig;
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
 use crate::providers::mcp::McpProvider;
 use crate::security::{validate_size_limit, validate_url_security};
 use crate::tools::Tool;
-use crate::transports::{stream::StreamResult, ClientTransport};
+use crate::transports::client_cache::ClientCache;
+use crate::transports::http_client::SharedClient;
+use crate::transports::timeouts::{timeout_error, with_total_timeout};
+use crate::transports::{
+    stream::StreamResult, ClientTransport, ToolsChangedCallback, ToolsChangedListeners,
+};
+
+mod convert;
+mod features;
+
+use features::FeatureTool;
 
 const MAX_RESPONSE_SIZE: usize = 10 * 1024 * 1024; // 10 MB
 
+/// Lines of a stdio server's stderr kept for error messages.
+const STDERR_LINES: usize = 50;
+
+/// How long an error about a closed stdio server waits for its exit status and last stderr.
+const EXIT_GRACE: Duration = Duration::from_millis(500);
+
+/// Longest wait for each response from a stdio server when the provider sets no
+/// `request_timeout_ms`.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Per-attempt limit for requests to an HTTP server when neither the provider nor the shared
+/// client sets one.
+const DEFAULT_HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Delay before the first restart of a stdio server, doubled for each one after it.
+const RESTART_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Longest delay between restarts of a stdio server.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Protocol revision requested in `initialize`, the newest this client speaks.
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Revisions accepted when a server answers `initialize` with an older one.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// What a server agreed to in the `initialize` handshake.
+#[derive(Debug, Clone)]
+struct McpSession {
+    protocol_version: String,
+    capabilities: Value,
+}
+
+impl McpSession {
+    /// Read the `initialize` result, rejecting protocol revisions this client cannot speak.
+    fn negotiate(provider: &str, result: &Value) -> Result<Self> {
+        let server = result
+            .get("serverInfo")
+            .map(|info| {
+                let name = info
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown");
+                match info.get("version").and_then(Value::as_str) {
+                    Some(version) => format!("'{}' {}", name, version),
+                    None => format!("'{}'", name),
+                }
+            })
+            .unwrap_or_else(|| "(no serverInfo)".to_string());
+        let version = result
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                anyhow!(
+                    "MCP server {} for provider '{}' did not report a protocolVersion",
+                    server,
+                    provider
+                )
+            })?;
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+            return Err(anyhow!(
+                "MCP server {} for provider '{}' speaks protocol version {}; supported versions \
+                 are {}",
+                server,
+                provider,
+                version,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ));
+        }
+        Ok(Self {
+            protocol_version: version.to_string(),
+            capabilities: result.get("capabilities").cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Whether the server advertised `capability`, such as `tools` or `resources`.
+    fn supports(&self, capability: &str) -> bool {
+        self.capabilities.get(capability).is_some()
+    }
+
+    /// Whether the server streams `tools/call` results, advertised as `experimental.streaming`.
+    fn supports_streaming(&self) -> bool {
+        self.capabilities
+            .get("experimental")
+            .and_then(|experimental| experimental.get("streaming"))
+            .is_some()
+    }
+}
+
+fn initialize_params() -> Value {
+    serde_json::json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// A server answered 404 to a request carrying its `Mcp-Session-Id`: the session has ended and
+/// a new one must be opened.
+#[derive(Debug, thiserror::Error)]
+#[error("MCP session of provider '{0}' has expired")]
+struct SessionExpired(String);
+
+/// Whether an HTTP response is an SSE stream rather than a single JSON body.
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+/// A JSON-RPC notification, which carries no id and gets no response.
+fn notification(method: &str, params: Value) -> Value {
+    let mut message = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": method,
+    });
+    if !params.is_null() {
+        message["params"] = params;
+    }
+    message
+}
+
+/// The notification telling a server to stop working on request `id`.
+fn cancelled_notification(id: u64) -> Value {
+    notification(
+        "notifications/cancelled",
+        serde_json::json!({ "requestId": id, "reason": "Cancelled by the client" }),
+    )
+}
+
+/// Whether `message` is the notification a server sends when its tool list changes.
+fn is_tools_list_changed(message: &Value) -> bool {
+    message.get("id").is_none()
+        && message.get("method").and_then(Value::as_str) == Some("notifications/tools/list_changed")
+}
+
+/// Requests awaiting a stdio server's responses, keyed by JSON-RPC id; `None` once the
+/// server's stdout has closed.
+type PendingRequests =
+    Arc<std::sync::Mutex<Option<HashMap<u64, mpsc::UnboundedSender<Result<Value>>>>>>;
+
+/// A request sent to a stdio server. Its responses, one or several when streamed, are
+/// delivered until it is dropped.
+struct PendingRequest {
+    id: u64,
+    responses: mpsc::UnboundedReceiver<Result<Value>>,
+    pending: PendingRequests,
+}
+
+impl PendingRequest {
+    /// The next message the server sent with this request's id.
+    async fn next(&mut self) -> Result<Value> {
+        self.responses
+            .recv()
+            .await
+            .unwrap_or_else(|| Err(anyhow!("MCP process closed connection")))
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+            pending.remove(&self.id);
+        }
+    }
+}
+
+/// Write one JSON-RPC message as a line on a process's stdin.
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let line = serde_json::to_string(message)?;
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.write_all(b"\n").await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Write the messages queued for a stdio server to its stdin until every sender is gone,
+/// which closes stdin, or a write fails.
+async fn write_messages(
+    provider: String,
+    mut stdin: ChildStdin,
+    mut outgoing: mpsc::UnboundedReceiver<Value>,
+    pending: PendingRequests,
+) {
+    while let Some(message) = outgoing.recv().await {
+        if let Err(err) = write_line(&mut stdin, &message).await {
+            tracing::warn!(provider = %provider, error = %err, "Failed to write to MCP process");
+            // A closed stdin means the process is gone, or about to be.
+            pending.lock().unwrap().take();
+            break;
+        }
+    }
+}
+
+/// Read a stdio server's messages until its stdout closes. Responses go to the request with
+/// their id, `notifications/tools/list_changed` reaches the listeners, and requests from the
+/// server are answered: `ping` with an empty result, anything else as an unknown method.
+async fn read_messages(
+    provider: String,
+    stdout: ChildStdout,
+    outgoing: mpsc::WeakUnboundedSender<Value>,
+    pending: PendingRequests,
+    listeners: ToolsChangedListeners,
+) {
+    // Use larger buffers for better I/O performance (64KB)
+    let mut stdout = BufReader::with_capacity(65536, stdout);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match stdout.read_line(&mut line).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                tracing::warn!(provider = %provider, error = %err, "Failed to read from MCP process");
+                break;
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(provider = %provider, error = %err, "Skipping unparsable MCP message");
+                continue;
+            }
+        };
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        match (method, message.get("id").cloned()) {
+            (None, Some(id)) => {
+                let sender = id
+                    .as_u64()
+                    .and_then(|id| pending.lock().unwrap().as_ref()?.get(&id).cloned());
+                match sender {
+                    Some(sender) => {
+                        let checked = validate_size_limit(line.as_bytes(), MAX_RESPONSE_SIZE)
+                            .map(|_| message);
+                        let _ = sender.send(checked);
+                    }
+                    None => {
+                        // Servers may still answer a request after it was cancelled.
+                        tracing::debug!(provider = %provider, id = %id, "Dropping MCP response to an unknown request")
+                    }
+                }
+            }
+            (Some(_), None) => {
+                if is_tools_list_changed(&message) {
+                    listeners.notify(&provider);
+                }
+            }
+            (Some(method), Some(id)) => {
+                let reply = match method.as_str() {
+                    "ping" => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                    _ => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                    }),
+                };
+                // Once the process is dropped its stdin is closed and nobody awaits an answer.
+                if let Some(outgoing) = outgoing.upgrade() {
+                    let _ = outgoing.send(reply);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Wake every waiting request and refuse new ones.
+    pending.lock().unwrap().take();
+}
+
+/// Keep the last [`STDERR_LINES`] lines a stdio server writes to stderr.
+async fn read_stderr(stderr: ChildStderr, tail: Arc<std::sync::Mutex<VecDeque<String>>>) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == STDERR_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
+
+/// The delay before restart number `attempt` of a stdio server.
+pub(crate) fn restart_backoff(attempt: u32) -> Duration {
+    RESTART_BACKOFF
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_RESTART_BACKOFF)
+}
+
 // Stdio process wrapper for MCP transport
 struct McpStdioProcess {
-    #[allow(dead_code)] // Needed to keep the process alive
-    child: Child,
-    stdin: Arc<Mutex<ChildStdin>>,
-    stdout: Arc<Mutex<BufReader<ChildStdout>>>,
-    request_id: Arc<Mutex<u64>>,
+    // Messages for the task writing to stdin
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: PendingRequests,
+    request_timeout: Duration,
+    next_id: AtomicU64,
+    session: Arc<OnceCell<McpSession>>,
+    // The last lines the process wrote to stderr
+    stderr: Arc<std::sync::Mutex<VecDeque<String>>>,
+    // How the process ended, once it has and its stderr is drained
+    exit: watch::Receiver<Option<String>>,
 }
 
 impl McpStdioProcess {
     async fn new(
+        provider: &str,
         command: &str,
         args: &Option<Vec<String>>,
         env_vars: &Option<HashMap<String, String>>,
+        request_timeout: Duration,
+        listeners: ToolsChangedListeners,
     ) -> Result<Self> {
         // Security: Validate command to prevent injection attacks
         // Allow empty allowlist for flexibility (allows all commands), but validation still checks for dangerous chars.
@@ -69,53 +391,158 @@ impl McpStdioProcess {
             .stdout
             .take()
             .ok_or_else(|| anyhow!("Failed to get stdout"))?;
-
-        // Use larger buffers for better I/O performance (64KB)
-        let buf_reader = BufReader::with_capacity(65536, stdout);
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("Failed to get stderr"))?;
+
+        // One task writes every message and one reads every response, so requests can be in
+        // flight together.
+        let pending: PendingRequests = Arc::new(std::sync::Mutex::new(Some(HashMap::new())));
+        let (outgoing, queued) = mpsc::unbounded_channel();
+        tokio::spawn(write_messages(
+            provider.to_string(),
+            stdin,
+            queued,
+            Arc::clone(&pending),
+        ));
+        tokio::spawn(read_messages(
+            provider.to_string(),
+            stdout,
+            outgoing.downgrade(),
+            Arc::clone(&pending),
+            listeners,
+        ));
+
+        // Watch for the process to exit, failing whatever still waits on it.
+        let tail = Arc::new(std::sync::Mutex::new(VecDeque::new()));
+        let (exited, exit) = watch::channel(None);
+        let monitor_tail = Arc::clone(&tail);
+        let monitor_pending = Arc::clone(&pending);
+        tokio::spawn(async move {
+            let (_, status) = tokio::join!(read_stderr(stderr, monitor_tail), child.wait());
+            let status = match status {
+                Ok(status) => status.to_string(),
+                Err(err) => format!("unknown exit status: {}", err),
+            };
+            monitor_pending.lock().unwrap().take();
+            let _ = exited.send(Some(status));
+        });
 
         Ok(Self {
-            child,
-            stdin: Arc::new(Mutex::new(stdin)),
-            stdout: Arc::new(Mutex::new(buf_reader)),
-            request_id: Arc::new(Mutex::new(1)),
+            outgoing,
+            pending,
+            request_timeout,
+            next_id: AtomicU64::new(1),
+            session: Arc::default(),
+            stderr: tail,
+            exit,
         })
     }
 
-    async fn send_request(&self, method: &str, params: Value) -> Result<Value> {
-        let mut id_guard = self.request_id.lock().await;
-        let id = *id_guard;
-        *id_guard += 1;
-        drop(id_guard);
+    /// Whether the process can still answer requests: its stdout is open and it has not
+    /// exited.
+    fn is_alive(&self) -> bool {
+        self.pending.lock().unwrap().is_some()
+    }
 
-        let request = serde_json::json!({
+    /// The error for a request the process can no longer answer, naming how it exited and
+    /// what it last wrote to stderr.
+    async fn closed_error(&self) -> anyhow::Error {
+        let mut exit = self.exit.clone();
+        let _ = tokio::time::timeout(EXIT_GRACE, exit.wait_for(Option::is_some)).await;
+        let mut message = "MCP process closed connection".to_string();
+        if let Some(status) = exit.borrow().as_ref() {
+            message.push_str(&format!(" ({})", status));
+        }
+        let stderr = self.stderr.lock().unwrap();
+        if !stderr.is_empty() {
+            let lines: Vec<&str> = stderr.iter().map(String::as_str).collect();
+            message.push_str(&format!("; stderr:\n{}", lines.join("\n")));
+        }
+        anyhow!(message)
+    }
+
+    /// Queue one JSON-RPC message for the process's stdin.
+    fn write_message(&self, message: Value) -> Result<()> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| anyhow!("MCP process closed connection"))
+    }
+
+    /// Send a request and return the handle its responses arrive on.
+    fn start_request(&self, method: &str, params: Value) -> Result<PendingRequest> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, responses) = mpsc::unbounded_channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(pending) => pending.insert(id, sender),
+            None => return Err(anyhow!("MCP process closed connection")),
+        };
+        let request = PendingRequest {
+            id,
+            responses,
+            pending: Arc::clone(&self.pending),
+        };
+
+        self.write_message(serde_json::json!({
             "jsonrpc": "2.0",
             "method": method,
             "params": params,
             "id": id,
-        });
-
-        let request_str = serde_json::to_string(&request)?;
-
-        // Write request to stdin
-        let mut stdin = self.stdin.lock().await;
-        stdin.write_all(request_str.as_bytes()).await?;
-        stdin.write_all(b"\n").await?;
-        stdin.flush().await?;
-        drop(stdin);
+        }))?;
+        Ok(request)
+    }
 
-        // Read response from stdout
-        let mut stdout = self.stdout.lock().await;
-        let mut line = String::new();
-        stdout.read_line(&mut line).await?;
-        drop(stdout);
+    /// Tell the server to stop working on request `id`, returning the error for its caller.
+    fn cancel_request(&self, id: u64, method: &str) -> anyhow::Error {
+        // The request is abandoned either way; a server that has exited needs no notice.
+        let _ = self.write_message(cancelled_notification(id));
+        UtcpError::Cancelled(format!("MCP {} request {}", method, id)).into()
+    }
 
-        if line.is_empty() {
-            return Err(anyhow!("MCP process closed connection"));
+    /// Wait for the next response to `request`. A request cancelled by `cancel`, or left
+    /// unanswered for the request timeout, is abandoned and the server told to stop.
+    async fn next_response(
+        &self,
+        request: &mut PendingRequest,
+        method: &str,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        let id = request.id;
+        let response = tokio::select! {
+            response = tokio::time::timeout(self.request_timeout, request.next()) => response,
+            _ = cancel.cancelled() => return Err(self.cancel_request(id, method)),
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(_) => {
+                let _ = self.cancel_request(id, method);
+                return Err(UtcpError::Timeout(format!(
+                    "no response to MCP {} request {} within {}ms",
+                    method,
+                    id,
+                    self.request_timeout.as_millis()
+                ))
+                .into());
+            }
+        };
+        match response {
+            Err(_) if !self.is_alive() => Err(self.closed_error().await),
+            response => response,
         }
+    }
 
-        validate_size_limit(line.as_bytes(), MAX_RESPONSE_SIZE)?;
-
-        let response: Value = serde_json::from_str(&line)?;
+    async fn send_request(
+        &self,
+        method: &str,
+        params: Value,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        let response = match self.start_request(method, params) {
+            Ok(mut request) => self.next_response(&mut request, method, cancel).await?,
+            Err(_) if !self.is_alive() => return Err(self.closed_error().await),
+            Err(err) => return Err(err),
+        };
 
         // Check for JSON-RPC error
         if let Some(error) = response.get("error") {
@@ -131,28 +558,40 @@ impl McpStdioProcess {
 
 /// Transport for MCP providers over HTTP or stdio.
 pub struct McpTransport {
-    client: Client,
+    clients: ClientCache,
     // Map of provider name to stdio process
     stdio_processes: Arc<Mutex<HashMap<String, Arc<McpStdioProcess>>>>,
+    // Map of provider name to the handshake with an HTTP server; stdio processes keep their own
+    http_sessions: Mutex<HashMap<String, Arc<OnceCell<McpSession>>>>,
+    // Map of provider name to the Mcp-Session-Id its HTTP server assigned
+    http_session_ids: std::sync::Mutex<HashMap<String, String>>,
+    // JSON-RPC id of the next HTTP request
+    http_request_id: AtomicU64,
+    // Map of provider name to the resources and prompts tools offered at registration
+    feature_tools: Mutex<HashMap<String, Vec<FeatureTool>>>,
+    // Callbacks told when a provider's tool list changes
+    tools_changed: ToolsChangedListeners,
+    // Map of provider name to the times its stdio process was restarted
+    restarts: Mutex<HashMap<String, u32>>,
 }
 
 impl McpTransport {
-    /// Create an MCP transport with a tuned reqwest client.
+    /// Create an MCP transport on the process-wide shared HTTP client.
     pub fn new() -> Self {
-        // Optimized HTTP client for MCP transport
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(120)) // Longer timeout for MCP operations
-            .pool_max_idle_per_host(50) // Connection pool for MCP servers
-            .pool_idle_timeout(Some(std::time::Duration::from_secs(90)))
-            .tcp_keepalive(Some(std::time::Duration::from_secs(30)))
-            .gzip(true) // Enable compression
-            .http2_adaptive_window(true)
-            .build()
-            .expect("Failed to build MCP HTTP client");
+        Self::with_client(SharedClient::default())
+    }
 
+    /// Create an MCP transport that sends HTTP requests through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
         Self {
-            client,
+            clients: ClientCache::new(shared),
             stdio_processes: Arc::new(Mutex::new(HashMap::new())),
+            http_sessions: Mutex::new(HashMap::new()),
+            http_session_ids: std::sync::Mutex::new(HashMap::new()),
+            http_request_id: AtomicU64::new(1),
+            feature_tools: Mutex::new(HashMap::new()),
+            tools_changed: ToolsChangedListeners::default(),
+            restarts: Mutex::new(HashMap::new()),
         }
     }
 
@@ -185,12 +624,25 @@ impl McpTransport {
         }
     }
 
-    async fn mcp_http_request(
+    /// A POST of `message` to the provider's URL, set up as by [`Self::http_request`].
+    async fn http_post(
         &self,
         prov: &McpProvider,
-        method: &str,
-        params: Value,
-    ) -> Result<Value> {
+        message: &Value,
+    ) -> Result<reqwest::RequestBuilder> {
+        Ok(self
+            .http_request(prov, reqwest::Method::POST)
+            .await?
+            .json(message))
+    }
+
+    /// A request to the provider's URL with its headers and auth applied, naming the session id
+    /// the server assigned and the negotiated protocol version once the handshake is done.
+    async fn http_request(
+        &self,
+        prov: &McpProvider,
+        method: reqwest::Method,
+    ) -> Result<reqwest::RequestBuilder> {
         let url = prov
             .url
             .as_ref()
@@ -198,14 +650,14 @@ impl McpTransport {
 
         validate_url_security(url, false)?;
 
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params,
-            "id": 1,
-        });
-
-        let mut req = self.client.post(url).json(&request);
+        let client = self.clients.client_for(
+            &prov.base.name,
+            prov.timeouts.as_ref(),
+            prov.proxy.as_ref(),
+            false,
+            false,
+        )?;
+        let mut req = client.request(method, url);
         if let Some(headers) = &prov.headers {
             for (k, v) in headers {
                 req = req.header(k, v);
@@ -214,16 +666,180 @@ impl McpTransport {
         if let Some(auth) = &prov.base.auth {
             req = self.apply_auth(req, auth)?;
         }
+        if let Some(id) = self.http_session_ids.lock().unwrap().get(&prov.base.name) {
+            req = req.header("Mcp-Session-Id", id);
+        }
+        let sessions = self.http_sessions.lock().await;
+        if let Some(session) = sessions.get(&prov.base.name).and_then(|cell| cell.get()) {
+            req = req.header("MCP-Protocol-Version", &session.protocol_version);
+        }
+        Ok(req)
+    }
 
-        let response = req.send().await?;
+    /// Fail a response with an error status. A 404 to a request that carried a session id
+    /// means the server ended the session, which is then forgotten and reported as
+    /// [`SessionExpired`] so the caller can open a new one.
+    async fn check_status(
+        &self,
+        prov: &McpProvider,
+        response: &reqwest::Response,
+        what: &str,
+    ) -> Result<()> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == reqwest::StatusCode::NOT_FOUND && self.forget_http_session(prov).await {
+            return Err(SessionExpired(prov.base.name.clone()).into());
+        }
+        Err(anyhow!("MCP {} failed: {}", what, status))
+    }
 
-        if !response.status().is_success() {
-            return Err(anyhow!("MCP request failed: {}", response.status()));
+    /// Keep the `Mcp-Session-Id` a server assigns, normally in its answer to `initialize`.
+    fn remember_session_id(&self, prov: &McpProvider, response: &reqwest::Response) {
+        if let Some(id) = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|value| value.to_str().ok())
+        {
+            self.http_session_ids
+                .lock()
+                .unwrap()
+                .insert(prov.base.name.clone(), id.to_string());
         }
+    }
 
-        let body_bytes = response.bytes().await?;
-        validate_size_limit(&body_bytes, MAX_RESPONSE_SIZE)?;
-        let result: Value = serde_json::from_slice(&body_bytes)?;
+    /// Drop the provider's HTTP session, returning whether the server had assigned it an id.
+    async fn forget_http_session(&self, prov: &McpProvider) -> bool {
+        self.http_sessions.lock().await.remove(&prov.base.name);
+        self.http_session_ids
+            .lock()
+            .unwrap()
+            .remove(&prov.base.name)
+            .is_some()
+    }
+
+    /// End the provider's HTTP session with a DELETE naming its id, if the server assigned one.
+    /// Servers that do not let clients end sessions answer 405, which is not an error.
+    async fn end_http_session(&self, prov: &McpProvider) -> Result<()> {
+        if !self
+            .http_session_ids
+            .lock()
+            .unwrap()
+            .contains_key(&prov.base.name)
+        {
+            return Ok(());
+        }
+        let timeouts = prov.timeouts.as_ref();
+        let req = self.clients.apply_request_timeout(
+            self.http_request(prov, reqwest::Method::DELETE).await?,
+            timeouts,
+            Some(DEFAULT_HTTP_REQUEST_TIMEOUT),
+        );
+        let response =
+            with_total_timeout(timeouts, async { req.send().await.map_err(timeout_error) }).await?;
+        let status = response.status();
+        if status.is_success()
+            || status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+            || status == reqwest::StatusCode::NOT_FOUND
+        {
+            Ok(())
+        } else {
+            Err(anyhow!("MCP session end failed: {}", status))
+        }
+    }
+
+    /// Read an SSE response to request `id` up to the message answering it. Tool list changes
+    /// sent on the way reach the listeners; other server messages are skipped.
+    async fn read_sse_response(
+        &self,
+        prov: &McpProvider,
+        response: reqwest::Response,
+        id: u64,
+    ) -> Result<Value> {
+        use eventsource_stream::Eventsource;
+        use futures::StreamExt;
+
+        let mut events = response.bytes_stream().eventsource();
+        let mut received = 0;
+        while let Some(event) = events.next().await {
+            let event = event.map_err(|e| anyhow!("SSE stream error: {}", e))?;
+            received += event.data.len();
+            if received > MAX_RESPONSE_SIZE {
+                return Err(anyhow!(
+                    "MCP SSE response exceeds maximum allowed size {} bytes",
+                    MAX_RESPONSE_SIZE
+                ));
+            }
+            if event.data.trim().is_empty() {
+                continue;
+            }
+            let message: Value = serde_json::from_str(&event.data)
+                .map_err(|e| anyhow!("Failed to parse SSE event: {}", e))?;
+            if is_tools_list_changed(&message) {
+                self.tools_changed.notify(&prov.base.name);
+            } else if message.get("method").is_none() && message.get("id") == Some(&Value::from(id))
+            {
+                return Ok(message);
+            }
+        }
+        Err(anyhow!(
+            "MCP SSE response ended before answering request {}",
+            id
+        ))
+    }
+
+    async fn mcp_http_request(
+        &self,
+        prov: &McpProvider,
+        method: &str,
+        params: Value,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        let id = self.http_request_id.fetch_add(1, Ordering::Relaxed);
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": id,
+        });
+
+        // Servers may answer with a JSON body or an SSE stream on the same POST
+        let timeouts = prov.timeouts.as_ref();
+        let req = self.clients.apply_request_timeout(
+            self.http_post(prov, &request)
+                .await?
+                .header(header::ACCEPT, "application/json, text/event-stream"),
+            timeouts,
+            Some(DEFAULT_HTTP_REQUEST_TIMEOUT),
+        );
+
+        let exchange = with_total_timeout(timeouts, async {
+            let response = req.send().await.map_err(timeout_error)?;
+            self.check_status(prov, &response, "request").await?;
+            self.remember_session_id(prov, &response);
+
+            if is_event_stream(&response) {
+                return self.read_sse_response(prov, response, id).await;
+            }
+            let body_bytes = response.bytes().await.map_err(timeout_error)?;
+            validate_size_limit(&body_bytes, MAX_RESPONSE_SIZE)?;
+            Ok(serde_json::from_slice::<Value>(&body_bytes)?)
+        });
+        // Dropping the exchange on cancellation aborts the HTTP request
+        let result = tokio::select! {
+            result = exchange => result?,
+            _ = cancel.cancelled() => {
+                if let Err(err) = self.mcp_http_notify(prov, &cancelled_notification(id)).await {
+                    tracing::warn!(provider = %prov.base.name, error = %err, "Failed to send MCP cancellation");
+                }
+                return Err(UtcpError::Cancelled(format!(
+                    "MCP {} request {} to provider '{}'",
+                    method, id, prov.base.name
+                ))
+                .into());
+            }
+        };
 
         // Check for JSON-RPC error
         if let Some(error) = result.get("error") {
@@ -236,14 +852,33 @@ impl McpTransport {
             .ok_or_else(|| anyhow!("No result in MCP response"))
     }
 
+    async fn mcp_http_notify(&self, prov: &McpProvider, message: &Value) -> Result<()> {
+        let timeouts = prov.timeouts.as_ref();
+        let req = self.clients.apply_request_timeout(
+            self.http_post(prov, message)
+                .await?
+                .header(header::ACCEPT, "application/json, text/event-stream"),
+            timeouts,
+            Some(DEFAULT_HTTP_REQUEST_TIMEOUT),
+        );
+        with_total_timeout(timeouts, async {
+            let response = req.send().await.map_err(timeout_error)?;
+            self.check_status(prov, &response, "notification").await
+        })
+        .await
+    }
+
     async fn get_or_create_stdio_process(
         &self,
         prov: &McpProvider,
     ) -> Result<Arc<McpStdioProcess>> {
         let mut processes = self.stdio_processes.lock().await;
 
+        // A process that died is replaced, starting a new session
         if let Some(process) = processes.get(&prov.base.name) {
-            return Ok(Arc::clone(process));
+            if process.is_alive() {
+                return Ok(Arc::clone(process));
+            }
         }
 
         let command = prov
@@ -251,18 +886,269 @@ impl McpTransport {
             .as_ref()
             .ok_or_else(|| anyhow!("No command provided for stdio MCP provider"))?;
 
-        let process = Arc::new(McpStdioProcess::new(command, &prov.args, &prov.env_vars).await?);
+        let process = Arc::new(
+            McpStdioProcess::new(
+                &prov.base.name,
+                command,
+                &prov.args,
+                &prov.env_vars,
+                prov.request_timeout_ms
+                    .map_or(DEFAULT_REQUEST_TIMEOUT, Duration::from_millis),
+                self.tools_changed.clone(),
+            )
+            .await?,
+        );
         processes.insert(prov.base.name.clone(), Arc::clone(&process));
 
         Ok(process)
     }
 
+    /// Send a request once the provider's handshake is done.
     async fn mcp_request(&self, prov: &McpProvider, method: &str, params: Value) -> Result<Value> {
+        self.mcp_request_cancellable(prov, method, params, &CancellationToken::new())
+            .await
+    }
+
+    /// Send a request once the provider's handshake is done, giving up and telling the server
+    /// when `cancel` fires. A request refused because the HTTP session expired is retried once
+    /// in a new session, and a request lost with its stdio process once on a new process,
+    /// while the provider's `max_restarts` allows.
+    async fn mcp_request_cancellable(
+        &self,
+        prov: &McpProvider,
+        method: &str,
+        params: Value,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        self.session(prov).await?;
+        match self
+            .send_request(prov, method, params.clone(), cancel)
+            .await
+        {
+            Err(err) if err.is::<SessionExpired>() => {
+                self.session(prov).await?;
+                self.send_request(prov, method, params, cancel).await
+            }
+            Err(err) if self.restart_stdio_process(prov).await => {
+                tracing::warn!(provider = %prov.base.name, error = %err, "Restarted MCP process");
+                self.session(prov).await?;
+                self.send_request(prov, method, params, cancel).await
+            }
+            result => result,
+        }
+    }
+
+    /// Whether the provider's stdio process has died and may be restarted. The restart is
+    /// counted against `max_restarts` and its backoff waited out; the next request then
+    /// starts the new process.
+    async fn restart_stdio_process(&self, prov: &McpProvider) -> bool {
+        let max_restarts = prov.max_restarts.unwrap_or(0);
+        if !prov.is_stdio() || max_restarts == 0 {
+            return false;
+        }
+        let dead = match self.stdio_processes.lock().await.get(&prov.base.name) {
+            Some(process) => !process.is_alive(),
+            None => false,
+        };
+        if !dead {
+            return false;
+        }
+
+        let attempt = {
+            let mut restarts = self.restarts.lock().await;
+            let count = restarts.entry(prov.base.name.clone()).or_default();
+            if *count >= max_restarts {
+                return false;
+            }
+            *count += 1;
+            *count
+        };
+        tokio::time::sleep(restart_backoff(attempt)).await;
+        true
+    }
+
+    async fn send_request(
+        &self,
+        prov: &McpProvider,
+        method: &str,
+        params: Value,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        if prov.is_http() {
+            self.mcp_http_request(prov, method, params, cancel).await
+        } else if prov.is_stdio() {
+            let process = self.get_or_create_stdio_process(prov).await?;
+            process.send_request(method, params, cancel).await
+        } else {
+            Err(anyhow!(
+                "MCP provider must have either 'url' (HTTP) or 'command' (stdio)"
+            ))
+        }
+    }
+
+    async fn send_notification(
+        &self,
+        prov: &McpProvider,
+        method: &str,
+        params: Value,
+    ) -> Result<()> {
+        let message = notification(method, params);
         if prov.is_http() {
-            self.mcp_http_request(prov, method, params).await
+            self.mcp_http_notify(prov, &message).await
         } else if prov.is_stdio() {
             let process = self.get_or_create_stdio_process(prov).await?;
-            process.send_request(method, params).await
+            process.write_message(message)
+        } else {
+            Err(anyhow!(
+                "MCP provider must have either 'url' (HTTP) or 'command' (stdio)"
+            ))
+        }
+    }
+
+    /// The provider's session, opened on first use by sending `initialize`, checking the
+    /// negotiated protocol version and confirming with `notifications/initialized`. HTTP
+    /// sessions live on the transport; stdio sessions live and die with their process.
+    async fn session(&self, prov: &McpProvider) -> Result<McpSession> {
+        let cell = if prov.is_stdio() {
+            Arc::clone(&self.get_or_create_stdio_process(prov).await?.session)
+        } else {
+            let mut sessions = self.http_sessions.lock().await;
+            Arc::clone(sessions.entry(prov.base.name.clone()).or_default())
+        };
+        let session = cell
+            .get_or_try_init(|| async {
+                let result = self
+                    .send_request(
+                        prov,
+                        "initialize",
+                        initialize_params(),
+                        &CancellationToken::new(),
+                    )
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "MCP initialize with provider '{}' failed: {}",
+                            prov.base.name,
+                            e
+                        )
+                    })?;
+                let session = McpSession::negotiate(&prov.base.name, &result)?;
+                self.send_notification(prov, "notifications/initialized", Value::Null)
+                    .await?;
+                Ok::<_, anyhow::Error>(session)
+            })
+            .await;
+        match session {
+            Ok(session) => Ok(session.clone()),
+            Err(err) => {
+                // A stdio server that refused the handshake will not accept a second one.
+                if prov.is_stdio() {
+                    self.stdio_processes.lock().await.remove(&prov.base.name);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// The resources or prompts tool registered for the provider under `tool_name`, if any.
+    async fn feature_tool(&self, prov: &McpProvider, tool_name: &str) -> Option<FeatureTool> {
+        self.feature_tools
+            .lock()
+            .await
+            .get(&prov.base.name)?
+            .iter()
+            .copied()
+            .find(|feature| feature.name() == tool_name)
+    }
+
+    async fn call_feature_tool(
+        &self,
+        prov: &McpProvider,
+        feature: FeatureTool,
+        args: HashMap<String, Value>,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        let (method, params) = feature.request(args)?;
+        let result = self
+            .mcp_request_cancellable(prov, method, params, cancel)
+            .await?;
+        feature.result(result)
+    }
+
+    /// Call a tool, or a resources or prompts tool, until `cancel` fires.
+    async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: &CancellationToken,
+    ) -> Result<Value> {
+        let mcp_prov = prov
+            .as_any()
+            .downcast_ref::<McpProvider>()
+            .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
+
+        if let Some(feature) = self.feature_tool(mcp_prov, tool_name).await {
+            return self
+                .call_feature_tool(mcp_prov, feature, args, cancel)
+                .await;
+        }
+
+        // MCP tool call format
+        let params = serde_json::json!({
+            "name": tool_name,
+            "arguments": args,
+        });
+
+        // Call the tool via MCP request
+        let result = self
+            .mcp_request_cancellable(mcp_prov, "tools/call", params, cancel)
+            .await?;
+        convert::call_result(result)
+    }
+
+    /// Stream a tool's results until the stream is closed or `cancel` fires, either of which
+    /// tells the server to stop.
+    async fn call_tool_stream_with(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: CancellationToken,
+    ) -> Result<Box<dyn StreamResult>> {
+        let mcp_prov = prov
+            .as_any()
+            .downcast_ref::<McpProvider>()
+            .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
+
+        // Resources, prompts and servers that do not advertise streaming answer with one result
+        let session = self.session(mcp_prov).await?;
+        if !session.supports_streaming() || self.feature_tool(mcp_prov, tool_name).await.is_some() {
+            let result = self.call_tool_with(tool_name, args, prov, &cancel).await?;
+            return Ok(crate::transports::stream::boxed_vec_stream(vec![result]));
+        }
+
+        // MCP tool call format
+        let params = serde_json::json!({
+            "name": tool_name,
+            "arguments": args,
+        });
+
+        if mcp_prov.is_http() {
+            match self
+                .mcp_http_stream(mcp_prov, params.clone(), cancel.child_token())
+                .await
+            {
+                Err(err) if err.is::<SessionExpired>() => {
+                    self.session(mcp_prov).await?;
+                    self.mcp_http_stream(mcp_prov, params, cancel.child_token())
+                        .await
+                }
+                result => result,
+            }
+        } else if mcp_prov.is_stdio() {
+            self.mcp_stdio_stream(mcp_prov, params, cancel.child_token())
+                .await
         } else {
             Err(anyhow!(
                 "MCP provider must have either 'url' (HTTP) or 'command' (stdio)"
@@ -270,65 +1156,86 @@ impl McpTransport {
         }
     }
 
+    /// Stream a `tools/call` over SSE. Closing the stream or firing `cancel` drops the
+    /// connection and posts `notifications/cancelled`.
     async fn mcp_http_stream(
         &self,
         prov: &McpProvider,
         params: Value,
+        cancel: CancellationToken,
     ) -> Result<Box<dyn StreamResult>> {
         use eventsource_stream::Eventsource;
         use futures::StreamExt;
 
-        let url = prov
-            .url
-            .as_ref()
-            .ok_or_else(|| anyhow!("No URL provided for HTTP MCP provider"))?;
-
-        validate_url_security(url, false)?;
-
+        let id = self.http_request_id.fetch_add(1, Ordering::Relaxed);
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "method": "tools/call",
             "params": params,
-            "id": 1,
+            "id": id,
         });
 
-        let mut req = self.client.post(url).json(&request);
-
-        // Add headers
-        if let Some(headers) = &prov.headers {
-            for (k, v) in headers {
-                req = req.header(k, v);
-            }
-        }
-
-        // Add authentication
-        if let Some(auth) = &prov.base.auth {
-            req = self.apply_auth(req, auth)?;
-        }
+        let timeouts = prov.timeouts.as_ref();
+        let mut req = self.http_post(prov, &request).await?;
 
-        // Set Accept header for SSE
-        req = req.header("Accept", "text/event-stream");
+        // Set Accept header for SSE and keep events uncompressed so they arrive as sent
+        req = req
+            .header("Accept", "text/event-stream")
+            .header(header::ACCEPT_ENCODING, "identity");
 
-        let response = req.send().await?;
+        // Only the wait for the response headers is limited; events may arrive indefinitely.
+        let response =
+            with_total_timeout(timeouts, async { req.send().await.map_err(timeout_error) }).await?;
 
-        if !response.status().is_success() {
-            return Err(anyhow!("MCP stream request failed: {}", response.status()));
-        }
+        self.check_status(prov, &response, "stream request").await?;
+        self.remember_session_id(prov, &response);
 
         // Create a channel to stream results
         // Create a channel to stream results with larger buffer
         let (tx, rx) = tokio::sync::mpsc::channel(256);
 
-        // Spawn a task to read SSE events
+        // Built up front so the reader task can send it on its own
+        let cancellation = self.clients.apply_request_timeout(
+            self.http_post(prov, &cancelled_notification(id)).await?,
+            timeouts,
+            Some(DEFAULT_HTTP_REQUEST_TIMEOUT),
+        );
+
+        // Spawn a task to read SSE events, passing tool list changes to the listeners
+        let provider = prov.base.name.clone();
+        let listeners = self.tools_changed.clone();
+        let close = cancel.clone();
         tokio::spawn(async move {
             let byte_stream = response.bytes_stream();
             let mut event_stream = byte_stream.eventsource();
 
-            while let Some(event_result) = event_stream.next().await {
+            loop {
+                let event_result = tokio::select! {
+                    event = event_stream.next() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                    _ = cancel.cancelled() => {
+                        let _ = cancellation.send().await;
+                        let err = UtcpError::Cancelled(format!(
+                            "MCP tools/call request {} to provider '{}'",
+                            id, provider
+                        ));
+                        let _ = tx.send(Err(err.into())).await;
+                        break;
+                    }
+                    _ = tx.closed() => {
+                        let _ = cancellation.send().await;
+                        break;
+                    }
+                };
                 match event_result {
                     Ok(event) => {
                         // Parse the event data as JSON
                         match serde_json::from_str::<Value>(&event.data) {
+                            Ok(value) if is_tools_list_changed(&value) => {
+                                listeners.notify(&provider)
+                            }
                             Ok(value) => {
                                 if tx.send(Ok(value)).await.is_err() {
                                     break; // Receiver dropped
@@ -350,103 +1257,86 @@ impl McpTransport {
             }
         });
 
-        Ok(crate::transports::stream::boxed_channel_stream(rx, None))
+        Ok(crate::transports::stream::boxed_channel_stream(
+            rx,
+            Some(Box::new(move || {
+                close.cancel();
+                Ok(())
+            })),
+        ))
     }
 
+    /// Stream a `tools/call` from a stdio server. Closing the stream or firing `cancel` sends
+    /// `notifications/cancelled` and forgets the request.
     async fn mcp_stdio_stream(
         &self,
         prov: &McpProvider,
         params: Value,
+        cancel: CancellationToken,
     ) -> Result<Box<dyn StreamResult>> {
         let process = self.get_or_create_stdio_process(prov).await?;
+        let mut request = match process.start_request("tools/call", params) {
+            Err(_) if !process.is_alive() => return Err(process.closed_error().await),
+            request => request?,
+        };
 
-        // Generate request ID
-        let mut id_guard = process.request_id.lock().await;
-        let id = *id_guard;
-        *id_guard += 1;
-        drop(id_guard);
-
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "tools/call",
-            "params": params,
-            "id": id,
-        });
-
-        let request_str = serde_json::to_string(&request)?;
-
-        // Write request to stdin
-        let mut stdin = process.stdin.lock().await;
-        stdin.write_all(request_str.as_bytes()).await?;
-        stdin.write_all(b"\n").await?;
-        stdin.flush().await?;
-        drop(stdin);
-
-        // Create a channel to stream results
         // Create a channel to stream results with larger buffer
         let (tx, rx) = tokio::sync::mpsc::channel(256);
 
-        // Clone Arc for the task
-        let stdout = Arc::clone(&process.stdout);
-
-        // Spawn a task to read streaming responses
+        // Spawn a task to forward the responses routed to this request
+        let close = cancel.clone();
         tokio::spawn(async move {
-            let mut stdout_guard = stdout.lock().await;
-
+            let id = request.id;
             loop {
-                let mut line = String::new();
-                match stdout_guard.read_line(&mut line).await {
-                    Ok(0) => break, // EOF
-                    Ok(_) => {
-                        if line.trim().is_empty() {
-                            continue;
-                        }
+                let response = tokio::select! {
+                    response = process.next_response(&mut request, "tools/call", &cancel) => response,
+                    _ = tx.closed() => {
+                        let _ = process.cancel_request(id, "tools/call");
+                        break;
+                    }
+                };
+                let response = match response {
+                    Ok(response) => response,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        break;
+                    }
+                };
 
-                        // Parse the JSON-RPC response
-                        match serde_json::from_str::<Value>(&line) {
-                            Ok(response) => {
-                                // Check if this is an error response
-                                if let Some(error) = response.get("error") {
-                                    let _ = tx.send(Err(anyhow!("MCP error: {}", error))).await;
-                                    break;
-                                }
+                // Check if this is an error response
+                if let Some(error) = response.get("error") {
+                    let _ = tx.send(Err(anyhow!("MCP error: {}", error))).await;
+                    break;
+                }
 
-                                // Check if this is the final result or a stream chunk
-                                if let Some(result) = response.get("result") {
-                                    // Send the result
-                                    if tx.send(Ok(result.clone())).await.is_err() {
-                                        break; // Receiver dropped
-                                    }
-
-                                    // Check if this is marked as the final response
-                                    if response
-                                        .get("final")
-                                        .and_then(|v| v.as_bool())
-                                        .unwrap_or(false)
-                                    {
-                                        break;
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                let _ = tx
-                                    .send(Err(anyhow!("Failed to parse response: {}", e)))
-                                    .await;
-                                break;
-                            }
-                        }
+                // Check if this is the final result or a stream chunk
+                if let Some(result) = response.get("result") {
+                    // Send the result
+                    let result = convert::call_result(result.clone());
+                    let failed = result.is_err();
+                    if tx.send(result).await.is_err() || failed {
+                        break; // Receiver dropped or the tool failed
                     }
-                    Err(e) => {
-                        let _ = tx
-                            .send(Err(anyhow!("Failed to read from stdout: {}", e)))
-                            .await;
+
+                    // Check if this is marked as the final response
+                    if response
+                        .get("final")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false)
+                    {
                         break;
                     }
                 }
             }
         });
 
-        Ok(crate::transports::stream::boxed_channel_stream(rx, None))
+        Ok(crate::transports::stream::boxed_channel_stream(
+            rx,
+            Some(Box::new(move || {
+                close.cancel();
+                Ok(())
+            })),
+        ))
     }
 }
 
@@ -458,20 +1348,36 @@ impl ClientTransport for McpTransport {
             .downcast_ref::<McpProvider>()
             .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
 
-        let params = serde_json::json!({ "cursor": null });
-        let result = self.mcp_request(mcp_prov, "tools/list", params).await?;
-
-        if let Some(tools) = result.get("tools").and_then(|v| v.as_array()) {
-            let mut parsed = Vec::new();
-            for tool in tools {
-                if let Ok(t) = serde_json::from_value::<Tool>(tool.clone()) {
-                    parsed.push(t);
+        let session = self.session(mcp_prov).await?;
+        let mut parsed = Vec::new();
+        if session.supports("tools") {
+            let params = serde_json::json!({ "cursor": null });
+            let result = self.mcp_request(mcp_prov, "tools/list", params).await?;
+            if let Some(tools) = result.get("tools").and_then(|v| v.as_array()) {
+                for tool in tools {
+                    match convert::tool_from_mcp(tool) {
+                        Ok(t) => parsed.push(t),
+                        Err(err) => {
+                            tracing::warn!(provider = %mcp_prov.base.name, error = %err, "Skipping MCP tool")
+                        }
+                    }
                 }
             }
-            return Ok(parsed);
         }
 
-        Ok(vec![])
+        // Resources and prompts become tools too, unless the server has tools of those names
+        let features: Vec<FeatureTool> = FeatureTool::ALL
+            .into_iter()
+            .filter(|feature| session.supports(feature.capability()))
+            .filter(|feature| parsed.iter().all(|tool| tool.name != feature.name()))
+            .collect();
+        parsed.extend(features.iter().map(|feature| feature.tool()));
+        self.feature_tools
+            .lock()
+            .await
+            .insert(mcp_prov.base.name.clone(), features);
+
+        Ok(parsed)
     }
 
     async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
@@ -479,6 +1385,13 @@ impl ClientTransport for McpTransport {
             .as_any()
             .downcast_ref::<McpProvider>()
             .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
+        if let Err(err) = self.end_http_session(mcp_prov).await {
+            tracing::warn!(provider = %mcp_prov.base.name, error = %err, "Failed to end MCP session");
+        }
+        self.clients.remove(&mcp_prov.base.name);
+        self.forget_http_session(mcp_prov).await;
+        self.feature_tools.lock().await.remove(&mcp_prov.base.name);
+        self.restarts.lock().await.remove(&mcp_prov.base.name);
 
         // For stdio processes, terminate the process
         if mcp_prov.is_stdio() {
@@ -488,8 +1401,48 @@ impl ClientTransport for McpTransport {
                 drop(process);
             }
         }
-
-        Ok(())
+
+        Ok(())
+    }
+
+    fn on_tools_changed(&self, callback: &ToolsChangedCallback) {
+        self.tools_changed.add(callback);
+    }
+
+    async fn health_check(&self, prov: &dyn Provider) -> Result<()> {
+        let mcp_prov = prov
+            .as_any()
+            .downcast_ref::<McpProvider>()
+            .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
+
+        // A stdio provider is healthy while its process runs and answers a ping
+        if mcp_prov.is_stdio() {
+            let process = self
+                .stdio_processes
+                .lock()
+                .await
+                .get(&mcp_prov.base.name)
+                .cloned();
+            match process {
+                Some(process) if !process.is_alive() => return Err(process.closed_error().await),
+                Some(_) => {}
+                None => {
+                    return Err(anyhow!(
+                        "MCP provider '{}' has no running process",
+                        mcp_prov.base.name
+                    ))
+                }
+            }
+        }
+        self.session(mcp_prov).await?;
+        self.send_request(
+            mcp_prov,
+            "ping",
+            serde_json::json!({}),
+            &CancellationToken::new(),
+        )
+        .await
+        .map(|_| ())
     }
 
     async fn call_tool(
@@ -498,19 +1451,8 @@ impl ClientTransport for McpTransport {
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Value> {
-        let mcp_prov = prov
-            .as_any()
-            .downcast_ref::<McpProvider>()
-            .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
-
-        // MCP tool call format
-        let params = serde_json::json!({
-            "name": tool_name,
-            "arguments": args,
-        });
-
-        // Call the tool via MCP request
-        self.mcp_request(mcp_prov, "tools/call", params).await
+        self.call_tool_with(tool_name, args, prov, &CancellationToken::new())
+            .await
     }
 
     async fn call_tool_stream(
@@ -519,26 +1461,29 @@ impl ClientTransport for McpTransport {
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Box<dyn StreamResult>> {
-        let mcp_prov = prov
-            .as_any()
-            .downcast_ref::<McpProvider>()
-            .ok_or_else(|| anyhow!("Provider is not an McpProvider"))?;
+        self.call_tool_stream_with(tool_name, args, prov, CancellationToken::new())
+            .await
+    }
 
-        // MCP tool call format
-        let params = serde_json::json!({
-            "name": tool_name,
-            "arguments": args,
-        });
+    async fn call_tool_cancellable(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: CancellationToken,
+    ) -> Result<Value> {
+        self.call_tool_with(tool_name, args, prov, &cancel).await
+    }
 
-        if mcp_prov.is_http() {
-            self.mcp_http_stream(mcp_prov, params).await
-        } else if mcp_prov.is_stdio() {
-            self.mcp_stdio_stream(mcp_prov, params).await
-        } else {
-            Err(anyhow!(
-                "MCP provider must have either 'url' (HTTP) or 'command' (stdio)"
-            ))
-        }
+    async fn call_tool_stream_cancellable(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: CancellationToken,
+    ) -> Result<Box<dyn StreamResult>> {
+        self.call_tool_stream_with(tool_name, args, prov, cancel)
+            .await
     }
 }
 
@@ -586,6 +1531,10 @@ mod tests {
             command: None,
             args: None,
             env_vars: None,
+            timeouts: None,
+            proxy: None,
+            max_restarts: None,
+            request_timeout_ms: None,
         };
 
         let err = transport
@@ -614,6 +1563,15 @@ mod tests {
 
             let method = payload.get("method").and_then(|v| v.as_str()).unwrap_or("");
             match method {
+                "initialize" => Json(json!({
+                    "jsonrpc": "2.0",
+                    "result": {
+                        "protocolVersion": "2025-06-18",
+                        "capabilities": { "tools": {}, "experimental": { "streaming": {} } },
+                        "serverInfo": { "name": "mock", "version": "1.0.0" }
+                    },
+                    "id": 1
+                })),
                 "tools/list" => Json(json!({
                     "jsonrpc": "2.0",
                     "result": {
@@ -683,6 +1641,10 @@ mod tests {
             command: None,
             args: None,
             env_vars: None,
+            timeouts: None,
+            proxy: None,
+            max_restarts: None,
+            request_timeout_ms: None,
         };
 
         let transport = McpTransport::new();
@@ -717,4 +1679,815 @@ mod tests {
         assert_eq!(stream.next().await.unwrap().unwrap(), json!({"idx":2}));
         stream.close().await.unwrap();
     }
+
+    /// An MCP server that answers nothing but `initialize` until the client confirms with
+    /// `notifications/initialized`, and records the `MCP-Protocol-Version` header of each call.
+    #[derive(Clone, Default)]
+    struct StrictServer {
+        initialized: Arc<std::sync::atomic::AtomicBool>,
+        versions: Arc<std::sync::Mutex<Vec<Option<String>>>>,
+        protocol_version: &'static str,
+    }
+
+    async fn serve_strict(server: StrictServer) -> String {
+        async fn handler(
+            axum::extract::State(server): axum::extract::State<StrictServer>,
+            headers: axum::http::HeaderMap,
+            Json(payload): Json<Value>,
+        ) -> (axum::http::StatusCode, Json<Value>) {
+            use std::sync::atomic::Ordering;
+
+            let version = headers
+                .get("MCP-Protocol-Version")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            server.versions.lock().unwrap().push(version);
+            let id = payload.get("id").cloned().unwrap_or(Value::Null);
+            let method = payload.get("method").and_then(|v| v.as_str()).unwrap_or("");
+            let result = match method {
+                "initialize" => json!({
+                    "protocolVersion": server.protocol_version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "strict", "version": "0.9.1" }
+                }),
+                "notifications/initialized" => {
+                    server.initialized.store(true, Ordering::SeqCst);
+                    return (axum::http::StatusCode::ACCEPTED, Json(Value::Null));
+                }
+                _ if !server.initialized.load(Ordering::SeqCst) => {
+                    return (
+                        axum::http::StatusCode::OK,
+                        Json(json!({
+                            "jsonrpc": "2.0",
+                            "error": { "code": -32002, "message": "Server not initialized" },
+                            "id": id
+                        })),
+                    );
+                }
+                "tools/list" => json!({
+                    "tools": [{
+                        "name": "echo",
+                        "description": "echo tool",
+                        "inputs": { "type": "object" },
+                        "outputs": { "type": "object" },
+                        "tags": []
+                    }]
+                }),
+                _ => json!({ "called": payload["params"] }),
+            };
+            (
+                axum::http::StatusCode::OK,
+                Json(json!({ "jsonrpc": "2.0", "result": result, "id": id })),
+            )
+        }
+
+        serve(Router::new().route("/", post(handler)).with_state(server))
+    }
+
+    /// Serve `app` on a local port and return its URL.
+    fn serve(app: Router) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn http_provider_initializes_before_listing_tools() {
+        let server = StrictServer {
+            protocol_version: "2025-03-26",
+            ..Default::default()
+        };
+        let url = serve_strict(server.clone()).await;
+        let prov = McpProvider::new("strict".to_string(), url, None);
+        let transport = McpTransport::new();
+
+        let tools = transport
+            .register_tool_provider(&prov)
+            .await
+            .expect("register");
+        assert_eq!(tools.len(), 1);
+        let value = transport
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .expect("call");
+        assert_eq!(value["called"]["name"], json!("echo"));
+
+        // One handshake, after which every request names the negotiated version.
+        let negotiated = Some("2025-03-26".to_string());
+        assert_eq!(
+            *server.versions.lock().unwrap(),
+            vec![None, None, negotiated.clone(), negotiated]
+        );
+    }
+
+    #[tokio::test]
+    async fn handshake_rejects_unsupported_protocol_versions() {
+        let url = serve_strict(StrictServer {
+            protocol_version: "2023-01-01",
+            ..Default::default()
+        })
+        .await;
+        let prov = McpProvider::new("legacy".to_string(), url, None);
+
+        let err = McpTransport::new()
+            .register_tool_provider(&prov)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'strict' 0.9.1"), "{err}");
+        assert!(err.contains("protocol version 2023-01-01"), "{err}");
+        assert!(err.contains("provider 'legacy'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn stdio_provider_initializes_before_listing_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("server.py");
+        std::fs::write(
+            &script,
+            r#"
+import json, sys
+
+initialized = False
+for line in sys.stdin:
+    message = json.loads(line)
+    method = message.get("method")
+    if "id" not in message:
+        initialized = initialized or method == "notifications/initialized"
+        continue
+    if method == "initialize":
+        result = {
+            "protocolVersion": message["params"]["protocolVersion"],
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "stdio", "version": "1.0"},
+        }
+    elif not initialized:
+        reply = {"code": -32002, "message": "Server not initialized"}
+        print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "error": reply}), flush=True)
+        continue
+    elif method == "tools/list":
+        result = {"tools": [{"name": "echo", "description": "echo tool",
+                             "inputs": {"type": "object"}, "outputs": {"type": "object"},
+                             "tags": []}]}
+    else:
+        result = {"called": message["params"]}
+    print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "result": result}), flush=True)
+"#,
+        )
+        .unwrap();
+        let prov = McpProvider::new_stdio(
+            "stdio".to_string(),
+            "python3".to_string(),
+            Some(vec![script.to_string_lossy().into_owned()]),
+            None,
+        );
+        let transport = McpTransport::new();
+
+        let tools = transport
+            .register_tool_provider(&prov)
+            .await
+            .expect("register");
+        assert_eq!(tools[0].name, "echo");
+        let value = transport
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .expect("call");
+        assert_eq!(value["called"]["name"], json!("echo"));
+        transport.deregister_tool_provider(&prov).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stdio_notifications_between_requests_reach_listeners() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("server.py");
+        std::fs::write(
+            &script,
+            r#"
+import json, sys
+
+def send(message):
+    print(json.dumps(message), flush=True)
+
+tools = [{"name": "add_tool", "inputSchema": {"type": "object"}}]
+pongs = 0
+for line in sys.stdin:
+    message = json.loads(line)
+    method = message.get("method")
+    if method is None:
+        pongs += message.get("id") == "server-ping" and "result" in message
+        continue
+    if "id" not in message:
+        continue
+    if method == "initialize":
+        result = {"protocolVersion": "2025-06-18", "capabilities": {"tools": {"listChanged": True}},
+                  "serverInfo": {"name": "live", "version": "1.0"}}
+    elif method == "tools/list":
+        result = {"tools": tools}
+    elif message["params"]["name"] == "add_tool":
+        tools.append({"name": "added", "inputSchema": {"type": "object"}})
+        # A request and a notification from the server arrive before the response
+        send({"jsonrpc": "2.0", "id": "server-ping", "method": "ping"})
+        send({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"})
+        result = {"content": [{"type": "text", "text": "added"}]}
+    else:
+        result = {"content": [{"type": "text", "text": str(pongs)}],
+                  "structuredContent": {"pongs": pongs}}
+    send({"jsonrpc": "2.0", "id": message["id"], "result": result})
+"#,
+        )
+        .unwrap();
+        let prov = McpProvider::new_stdio(
+            "live".to_string(),
+            "python3".to_string(),
+            Some(vec![script.to_string_lossy().into_owned()]),
+            None,
+        );
+        let transport = McpTransport::new();
+        let (tx, mut changed) = mpsc::unbounded_channel();
+        let callback: ToolsChangedCallback = Arc::new(move |provider: &str| {
+            let _ = tx.send(provider.to_string());
+        });
+        transport.on_tools_changed(&callback);
+
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        let added = transport
+            .call_tool("add_tool", HashMap::new(), &prov)
+            .await
+            .expect("first call");
+        assert_eq!(added, json!("added"));
+        let provider = tokio::time::timeout(std::time::Duration::from_secs(5), changed.recv())
+            .await
+            .expect("notification");
+        assert_eq!(provider.as_deref(), Some("live"));
+
+        // The second request still gets its own response, sent after the server's ping was answered
+        let value = transport
+            .call_tool("added", HashMap::new(), &prov)
+            .await
+            .expect("second call");
+        assert_eq!(value, json!({ "pongs": 1 }));
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["add_tool", "added"]);
+
+        // Dropped callbacks are forgotten
+        drop(callback);
+        transport.tools_changed.notify("live");
+        assert!(transport.tools_changed.0.lock().unwrap().is_empty());
+        transport.deregister_tool_provider(&prov).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stdio_process_restarts_after_exiting() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("server.py");
+        std::fs::write(
+            &script,
+            r#"
+import json, os, sys
+
+for line in sys.stdin:
+    message = json.loads(line)
+    if "id" not in message:
+        continue
+    method = message["method"]
+    if method == "initialize":
+        result = {"protocolVersion": "2025-06-18", "capabilities": {"tools": {}},
+                  "serverInfo": {"name": "fragile", "version": "1.0"}}
+    elif method == "tools/list":
+        result = {"tools": [{"name": "pid", "inputSchema": {"type": "object"}}]}
+    elif method == "ping":
+        result = {}
+    else:
+        result = {"content": [{"type": "text", "text": str(os.getpid())}]}
+    print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "result": result}), flush=True)
+    if method == "tools/call":
+        print("served one call, exiting", file=sys.stderr, flush=True)
+        sys.exit(3)
+"#,
+        )
+        .unwrap();
+        let prov = McpProvider {
+            max_restarts: Some(1),
+            ..McpProvider::new_stdio(
+                "fragile".to_string(),
+                "python3".to_string(),
+                Some(vec![script.to_string_lossy().into_owned()]),
+                None,
+            )
+        };
+        let transport = McpTransport::new();
+        transport.register_tool_provider(&prov).await.unwrap();
+        transport.health_check(&prov).await.expect("healthy");
+
+        let first = transport
+            .call_tool("pid", HashMap::new(), &prov)
+            .await
+            .expect("first call");
+        // The process exited after the first call; the second restarts it and is retried
+        let second = transport
+            .call_tool("pid", HashMap::new(), &prov)
+            .await
+            .expect("second call");
+        assert_ne!(first, second);
+
+        // With its one restart used up, the next failure is reported with exit status and stderr
+        let err = transport
+            .call_tool("pid", HashMap::new(), &prov)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("closed connection (exit status: 3)"), "{err}");
+        assert!(err.contains("served one call, exiting"), "{err}");
+        let unhealthy = transport.health_check(&prov).await.unwrap_err().to_string();
+        assert!(unhealthy.contains("exit status: 3"), "{unhealthy}");
+
+        // The dead process is replaced by the next request all the same
+        let third = transport
+            .call_tool("pid", HashMap::new(), &prov)
+            .await
+            .expect("call after restarts ran out");
+        assert_ne!(third, second);
+        transport.deregister_tool_provider(&prov).await.unwrap();
+    }
+
+    /// The ids of the requests `log` shows cancelled, waiting a while for the first to arrive.
+    async fn cancelled_ids(log: &std::path::Path) -> Vec<Value> {
+        for _ in 0..100 {
+            let ids: Vec<Value> = std::fs::read_to_string(log)
+                .unwrap_or_default()
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .filter(|message| message["method"] == "notifications/cancelled")
+                .map(|message| message["params"]["requestId"].clone())
+                .collect();
+            if !ids.is_empty() {
+                return ids;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Vec::new()
+    }
+
+    /// The id of the `tools/call` request in `log`.
+    fn sent_call_id(log: &std::path::Path) -> Value {
+        std::fs::read_to_string(log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|message| message["method"] == "tools/call")
+            .expect("tools/call was sent")["id"]
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn stdio_calls_and_streams_are_cancelled_on_the_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("server.py");
+        let log = dir.path().join("received.log");
+        std::fs::write(
+            &script,
+            r#"
+import json, sys
+
+log = open(sys.argv[1], "a")
+for line in sys.stdin:
+    log.write(line)
+    log.flush()
+    message = json.loads(line)
+    if "id" not in message:
+        continue
+    method = message["method"]
+    if method == "initialize":
+        result = {"protocolVersion": "2025-06-18",
+                  "capabilities": {"tools": {}, "experimental": {"streaming": {}}},
+                  "serverInfo": {"name": "slow", "version": "1.0"}}
+    elif method == "tools/list":
+        result = {"tools": [{"name": name, "inputSchema": {"type": "object"}}
+                            for name in ("slow", "stuck", "quick")]}
+    elif message["params"]["name"] == "stuck":
+        continue
+    elif message["params"]["name"] == "slow":
+        # One chunk, then nothing until the client gives up
+        result = {"content": [{"type": "text", "text": "started"}]}
+        print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "result": result}), flush=True)
+        continue
+    else:
+        result = {"content": [{"type": "text", "text": "done"}]}
+    print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "result": result,
+                      "final": True}), flush=True)
+"#,
+        )
+        .unwrap();
+        let prov = McpProvider::new_stdio(
+            "slow".to_string(),
+            "python3".to_string(),
+            Some(vec![
+                script.to_string_lossy().into_owned(),
+                log.to_string_lossy().into_owned(),
+            ]),
+            None,
+        );
+        let transport = McpTransport::new();
+        transport.register_tool_provider(&prov).await.unwrap();
+
+        // Closing a stream early tells the server to stop
+        let mut stream = transport
+            .call_tool_stream("slow", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap(), Some(Value::from("started")));
+        stream.close().await.unwrap();
+        let cancelled = cancelled_ids(&log).await;
+        assert_eq!(cancelled.len(), 1, "{cancelled:?}");
+
+        // So does firing the token of a cancellable call, which then fails
+        std::fs::write(&log, "").unwrap();
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+        let err = transport
+            .call_tool_cancellable("stuck", HashMap::new(), &prov, cancel)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<UtcpError>(),
+                Some(UtcpError::Cancelled(_))
+            ),
+            "{err}"
+        );
+        assert_eq!(cancelled_ids(&log).await, vec![sent_call_id(&log)]);
+
+        // Or of a cancellable stream
+        std::fs::write(&log, "").unwrap();
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+        let mut slow = transport
+            .call_tool_stream_cancellable("slow", HashMap::new(), &prov, cancel)
+            .await
+            .unwrap();
+        assert_eq!(slow.next().await.unwrap(), Some(Value::from("started")));
+        let err = slow.next().await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<UtcpError>(),
+                Some(UtcpError::Cancelled(_))
+            ),
+            "{err}"
+        );
+        assert_eq!(cancelled_ids(&log).await, vec![sent_call_id(&log)]);
+
+        // The process keeps serving other calls
+        let cancel = CancellationToken::new();
+        let result = transport
+            .call_tool_cancellable("quick", HashMap::new(), &prov, cancel)
+            .await
+            .unwrap();
+        assert_eq!(result, Value::from("done"));
+        transport.deregister_tool_provider(&prov).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stdio_requests_in_flight_together_get_their_own_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("server.py");
+        std::fs::write(
+            &script,
+            r#"
+import json, random, sys
+
+def reply(message, result):
+    print(json.dumps({"jsonrpc": "2.0", "id": message["id"], "result": result}), flush=True)
+
+held = []
+for line in sys.stdin:
+    message = json.loads(line)
+    if "id" not in message:
+        continue
+    method = message["method"]
+    if method == "initialize":
+        reply(message, {"protocolVersion": "2025-06-18", "capabilities": {"tools": {}},
+                        "serverInfo": {"name": "shuffled", "version": "1.0"}})
+    elif method == "tools/list":
+        reply(message, {"tools": [{"name": name, "inputSchema": {"type": "object"}}
+                                  for name in ("echo", "lost")]})
+    elif message["params"]["name"] == "echo":
+        # Answer only once every call has arrived, in shuffled order
+        held.append(message)
+        if len(held) == 20:
+            random.shuffle(held)
+            for call in held:
+                text = str(call["params"]["arguments"]["n"])
+                reply(call, {"content": [{"type": "text", "text": text}]})
+            held = []
+"#,
+        )
+        .unwrap();
+        let prov = McpProvider {
+            request_timeout_ms: Some(300),
+            ..McpProvider::new_stdio(
+                "shuffled".to_string(),
+                "python3".to_string(),
+                Some(vec![script.to_string_lossy().into_owned()]),
+                None,
+            )
+        };
+        let transport = McpTransport::new();
+        transport.register_tool_provider(&prov).await.unwrap();
+
+        let calls = (0..20).map(|n| {
+            let args = HashMap::from([("n".to_string(), json!(n))]);
+            let (transport, prov) = (&transport, &prov);
+            async move { transport.call_tool("echo", args, prov).await }
+        });
+        let results = futures::future::join_all(calls).await;
+        for (n, result) in results.into_iter().enumerate() {
+            assert_eq!(result.unwrap(), json!(n.to_string()));
+        }
+
+        // A response that never comes times out and leaves nothing behind
+        let err = transport
+            .call_tool("lost", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+        let process = transport.get_or_create_stdio_process(&prov).await.unwrap();
+        assert_eq!(
+            process.pending.lock().unwrap().as_ref().map(HashMap::len),
+            Some(0)
+        );
+        transport.deregister_tool_provider(&prov).await.unwrap();
+    }
+
+    /// A streamable HTTP server that assigns an `Mcp-Session-Id` on `initialize`, refuses
+    /// unknown sessions with 404, answers `tools/call` with an SSE stream and ends sessions on
+    /// DELETE.
+    #[derive(Clone, Default)]
+    struct SessionServer {
+        live: Arc<std::sync::Mutex<Vec<String>>>,
+        issued: Arc<std::sync::atomic::AtomicUsize>,
+        ended: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    fn serve_sessions(server: SessionServer) -> String {
+        use axum::extract::State;
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::{IntoResponse, Response};
+
+        fn session_id(headers: &HeaderMap) -> Option<String> {
+            headers
+                .get("Mcp-Session-Id")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        }
+
+        async fn handler(
+            State(server): State<SessionServer>,
+            headers: HeaderMap,
+            Json(payload): Json<Value>,
+        ) -> Response {
+            use std::sync::atomic::Ordering;
+
+            let id = payload["id"].clone();
+            if payload["method"] == "initialize" {
+                let session = format!(
+                    "session-{}",
+                    server.issued.fetch_add(1, Ordering::SeqCst) + 1
+                );
+                server.live.lock().unwrap().push(session.clone());
+                let result = json!({
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": { "tools": { "listChanged": true } },
+                    "serverInfo": { "name": "sessions", "version": "1.0" }
+                });
+                return (
+                    [("Mcp-Session-Id", session)],
+                    Json(json!({ "jsonrpc": "2.0", "result": result, "id": id })),
+                )
+                    .into_response();
+            }
+            match session_id(&headers) {
+                None => return StatusCode::BAD_REQUEST.into_response(),
+                Some(session) if !server.live.lock().unwrap().contains(&session) => {
+                    return StatusCode::NOT_FOUND.into_response()
+                }
+                Some(_) => {}
+            }
+            match payload["method"].as_str().unwrap_or("") {
+                _ if id.is_null() => StatusCode::ACCEPTED.into_response(),
+                "tools/list" => Json(json!({
+                    "jsonrpc": "2.0",
+                    "result": { "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }] },
+                    "id": id
+                }))
+                .into_response(),
+                _ => {
+                    // A notification comes before the response on the same stream
+                    let changed = json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" });
+                    let result = json!({
+                        "jsonrpc": "2.0",
+                        "result": { "content": [{ "type": "text", "text": "streamed" }] },
+                        "id": id
+                    });
+                    (
+                        [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                        format!("event: message\ndata: {}\n\ndata: {}\n\n", changed, result),
+                    )
+                        .into_response()
+                }
+            }
+        }
+
+        async fn end(State(server): State<SessionServer>, headers: HeaderMap) -> StatusCode {
+            let Some(session) = session_id(&headers) else {
+                return StatusCode::BAD_REQUEST;
+            };
+            server.live.lock().unwrap().retain(|live| *live != session);
+            server.ended.lock().unwrap().push(session);
+            StatusCode::OK
+        }
+
+        serve(
+            Router::new()
+                .route("/", post(handler).delete(end))
+                .with_state(server),
+        )
+    }
+
+    #[tokio::test]
+    async fn http_sessions_are_tracked_renewed_and_ended() {
+        use std::sync::atomic::Ordering;
+
+        let server = SessionServer::default();
+        let prov = McpProvider::new("sessions".to_string(), serve_sessions(server.clone()), None);
+        let transport = McpTransport::new();
+        let (tx, mut changed) = mpsc::unbounded_channel();
+        let callback: ToolsChangedCallback = Arc::new(move |provider: &str| {
+            let _ = tx.send(provider.to_string());
+        });
+        transport.on_tools_changed(&callback);
+
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools[0].name, "echo");
+        let value = transport
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .expect("SSE response");
+        assert_eq!(value, json!("streamed"));
+        assert_eq!(changed.try_recv().ok().as_deref(), Some("sessions"));
+
+        // The server forgets the session; the next call opens a new one and succeeds
+        server.live.lock().unwrap().clear();
+        let value = transport
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .expect("call in a new session");
+        assert_eq!(value, json!("streamed"));
+        assert_eq!(server.issued.load(Ordering::SeqCst), 2);
+
+        transport.deregister_tool_provider(&prov).await.unwrap();
+        assert_eq!(*server.ended.lock().unwrap(), vec!["session-2"]);
+        assert!(server.live.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn resources_and_prompts_are_offered_as_tools() {
+        async fn handler(Json(payload): Json<Value>) -> Json<Value> {
+            let params = &payload["params"];
+            let result = match payload["method"].as_str().unwrap_or("") {
+                "initialize" => json!({
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+                    "serverInfo": { "name": "library", "version": "2.0.0" }
+                }),
+                // A real tool that shadows the synthesized prompt_list
+                "tools/list" => json!({
+                    "tools": [{ "name": "prompt_list", "inputSchema": { "type": "object" } }]
+                }),
+                "tools/call" => json!({ "content": [{ "type": "text", "text": "own tool" }] }),
+                "resources/list" => json!({
+                    "resources": [
+                        { "uri": "file:///notes.txt", "name": "notes.txt", "mimeType": "text/plain" },
+                        { "uri": "file:///logo.png", "name": "logo.png", "mimeType": "image/png" }
+                    ],
+                    "nextCursor": "page-2"
+                }),
+                "resources/read" => match params["uri"].as_str().unwrap() {
+                    "file:///notes.txt" => json!({
+                        "contents": [{
+                            "uri": "file:///notes.txt",
+                            "mimeType": "text/plain",
+                            "text": "remember the milk"
+                        }]
+                    }),
+                    uri => json!({
+                        "contents": [{ "uri": uri, "mimeType": "image/png", "blob": "iVBORw0KGgo=" }]
+                    }),
+                },
+                "prompts/get" => json!({
+                    "description": "Review code",
+                    "messages": [{
+                        "role": "user",
+                        "content": {
+                            "type": "text",
+                            "text": format!("Please review: {}", params["arguments"]["code"])
+                        }
+                    }]
+                }),
+                other => json!({ "unexpected": other }),
+            };
+            Json(json!({ "jsonrpc": "2.0", "result": result, "id": payload["id"] }))
+        }
+
+        let url = serve(Router::new().route("/", post(handler)));
+        let prov = McpProvider::new("library".to_string(), url, None);
+        let transport = McpTransport::new();
+
+        let tools = transport
+            .register_tool_provider(&prov)
+            .await
+            .expect("register");
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "prompt_list",
+                "resource_list",
+                "resource_read",
+                "prompt_get"
+            ]
+        );
+        assert_eq!(tools[2].inputs.required, Some(vec!["uri".to_string()]));
+        assert_eq!(tools[3].tags, vec!["mcp", "prompts"]);
+
+        let listed = transport
+            .call_tool("resource_list", HashMap::new(), &prov)
+            .await
+            .expect("list");
+        assert_eq!(listed["resources"][0]["name"], json!("notes.txt"));
+        assert_eq!(listed["nextCursor"], json!("page-2"));
+
+        let read = |uri: &str| HashMap::from([("uri".to_string(), json!(uri))]);
+        let text = transport
+            .call_tool("resource_read", read("file:///notes.txt"), &prov)
+            .await
+            .expect("read text");
+        assert_eq!(text, json!("remember the milk"));
+        let image = transport
+            .call_tool("resource_read", read("file:///logo.png"), &prov)
+            .await
+            .expect("read blob");
+        assert_eq!(
+            image,
+            json!({ "$binary": { "content_type": "image/png", "size": 8, "base64": "iVBORw0KGgo=" } })
+        );
+
+        let args = HashMap::from([
+            ("name".to_string(), json!("code_review")),
+            ("arguments".to_string(), json!({ "code": "fn main() {}" })),
+        ]);
+        let prompt = transport
+            .call_tool("prompt_get", args, &prov)
+            .await
+            .expect("prompt");
+        assert_eq!(prompt["messages"][0]["role"], json!("user"));
+        assert_eq!(
+            prompt["messages"][0]["content"],
+            json!("Please review: \"fn main() {}\"")
+        );
+
+        let own = transport
+            .call_tool("prompt_list", HashMap::new(), &prov)
+            .await
+            .expect("own tool");
+        assert_eq!(own, json!("own tool"));
+
+        let err = transport
+            .call_tool("resource_read", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("needs a 'uri' string argument"),
+            "{err}"
+        );
+    }
 }
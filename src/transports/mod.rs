@@ -1,14 +1,23 @@
 pub mod cli;
+pub(crate) mod client_cache;
 pub mod graphql;
 pub mod grpc;
 pub mod http;
+pub mod http_client;
 pub mod http_stream;
 pub mod mcp;
+pub(crate) mod redirect;
 pub mod registry;
+pub(crate) mod response;
+pub(crate) mod response_cache;
+pub(crate) mod retry;
 pub mod sse;
+#[cfg(test)]
+pub(crate) mod test_websocket;
 pub mod stream;
 pub mod tcp;
 pub mod text;
+pub(crate) mod timeouts;
 pub mod udp;
 pub mod webrtc;
 pub mod websocket;
@@ -20,6 +29,39 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use tokio_util::sync::CancellationToken;
+
+use crate::errors::UtcpError;
+use crate::transports::stream::CancellableStream;
+
+/// Called with a provider's name when the provider reports that its tool list changed.
+/// Callbacks run on the transport's reader, so they should hand off work rather than block.
+pub type ToolsChangedCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+type WeakCallback = Weak<dyn Fn(&str) + Send + Sync>;
+
+/// Callbacks a transport runs when one of its providers reports changed tools.
+#[derive(Clone, Default)]
+pub(crate) struct ToolsChangedListeners(Arc<std::sync::Mutex<Vec<WeakCallback>>>);
+
+impl ToolsChangedListeners {
+    pub(crate) fn add(&self, callback: &ToolsChangedCallback) {
+        self.0.lock().unwrap().push(Arc::downgrade(callback));
+    }
+
+    /// Run every live callback for `provider`, forgetting those whose owners have gone.
+    pub(crate) fn notify(&self, provider: &str) {
+        let callbacks: Vec<ToolsChangedCallback> = {
+            let mut listeners = self.0.lock().unwrap();
+            listeners.retain(|callback| callback.strong_count() > 0);
+            listeners.iter().filter_map(Weak::upgrade).collect()
+        };
+        for callback in callbacks {
+            callback(provider);
+        }
+    }
+}
 
 /// Core transport abstraction all communication protocols implement.
 #[async_trait]
@@ -42,12 +84,54 @@ pub trait ClientTransport: Send + Sync {
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Box<dyn StreamResult>>;
+    /// Invoke a tool like [`Self::call_tool`], giving up when `cancel` fires. The default drops
+    /// the call, which aborts an HTTP request in flight; transports that can tell the server
+    /// to stop do so as well.
+    async fn call_tool_cancellable(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: CancellationToken,
+    ) -> Result<Value> {
+        tokio::select! {
+            result = self.call_tool(tool_name, args, prov) => result,
+            _ = cancel.cancelled() => Err(UtcpError::Cancelled(format!("tool '{}'", tool_name)).into()),
+        }
+    }
+    /// Stream a tool's results like [`Self::call_tool_stream`], closing the stream when
+    /// `cancel` fires.
+    async fn call_tool_stream_cancellable(
+        &self,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
+        cancel: CancellationToken,
+    ) -> Result<Box<dyn StreamResult>> {
+        let stream = tokio::select! {
+            stream = self.call_tool_stream(tool_name, args, prov) => stream?,
+            _ = cancel.cancelled() => {
+                return Err(UtcpError::Cancelled(format!("tool '{}'", tool_name)).into())
+            }
+        };
+        Ok(Box::new(CancellableStream::new(stream, cancel)))
+    }
+    /// Ask to hear about providers whose tools change after registration. Transports keep
+    /// only a weak reference, so the callback stays registered while the caller holds it.
+    /// Transports that cannot learn of changes ignore it.
+    fn on_tools_changed(&self, _callback: &ToolsChangedCallback) {}
+    /// Check that a registered provider can still be reached, such as that its process is
+    /// running. Transports with nothing to check report every provider healthy.
+    async fn health_check(&self, _prov: &dyn Provider) -> Result<()> {
+        Ok(())
+    }
 }
 
 // CommunicationProtocol is the new name for transports; kept as a re-export for backwards
 // compatibility so plugins can implement the updated terminology without breaking old code.
 pub use ClientTransport as CommunicationProtocol;
 
+pub use http_client::{HttpClientConfig, SharedClient};
 pub use registry::{
     communication_protocols_snapshot, register_communication_protocol,
     CommunicationProtocolRegistry,
@@ -0,0 +1,125 @@
+//! Redirect following for providers that configure a `RedirectPolicy`.
+//!
+//! Such providers use a client with reqwest's automatic redirects turned off, so every hop
+//! is checked here: the hop limit, loops, cross-origin targets and which credentials are
+//! carried along.
+use anyhow::{anyhow, Result};
+use reqwest::header::{
+    HeaderName, AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION,
+    PROXY_AUTHORIZATION,
+};
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode, Url};
+
+use crate::providers::http::RedirectPolicy;
+use crate::transports::retry::{send_with_retry, RetryPolicy};
+
+/// Send `request`, following redirects according to `policy`. Without a policy the client's
+/// own redirect handling applies. `auth_header` names an extra header carrying credentials,
+/// such as an API key, that is stripped alongside `Authorization` on cross-origin hops.
+pub(crate) async fn send_following_redirects(
+    request: RequestBuilder,
+    policy: Option<&RedirectPolicy>,
+    retry: RetryPolicy,
+    auth_header: Option<&str>,
+) -> Result<Response> {
+    let policy = match policy {
+        Some(policy) => policy,
+        None => return send_with_retry(request, retry).await,
+    };
+
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    let mut chain = vec![request.url().clone()];
+    loop {
+        // Bodies that cannot be replayed (streams) are sent once and not redirected.
+        let next = match request.try_clone() {
+            Some(next) => next,
+            None => {
+                return send_with_retry(RequestBuilder::from_parts(client, request), retry).await
+            }
+        };
+        let current = std::mem::replace(&mut request, next);
+        let response =
+            send_with_retry(RequestBuilder::from_parts(client.clone(), current), retry).await?;
+        let target = match redirect_target(&response) {
+            Some(target) => target,
+            None => return Ok(response),
+        };
+
+        let previous = request.url().clone();
+        if chain.contains(&target) {
+            chain.push(target);
+            return Err(anyhow!("Redirect loop: {}", format_chain(&chain)));
+        }
+        chain.push(target.clone());
+        if chain.len() - 1 > policy.max_redirects {
+            return Err(anyhow!(
+                "Too many redirects (limit {}): {}",
+                policy.max_redirects,
+                format_chain(&chain)
+            ));
+        }
+        let cross_origin = target.origin() != previous.origin();
+        if cross_origin && !policy.follow_cross_origin {
+            return Err(anyhow!(
+                "Cross-origin redirect not allowed: {}",
+                format_chain(&chain)
+            ));
+        }
+
+        prepare_hop(&mut request, response.status(), target);
+        if cross_origin && !policy.preserve_auth_header {
+            let headers = request.headers_mut();
+            headers.remove(AUTHORIZATION);
+            headers.remove(PROXY_AUTHORIZATION);
+            headers.remove(COOKIE);
+            if let Some(name) = auth_header.and_then(|n| HeaderName::from_bytes(n.as_bytes()).ok())
+            {
+                headers.remove(name);
+            }
+        }
+    }
+}
+
+/// The absolute URL a 3xx response points at, if it has a usable `Location`.
+fn redirect_target(response: &Response) -> Option<Url> {
+    if !matches!(
+        response.status(),
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+/// Point `request` at `target`. 307 and 308 repeat the request unchanged; 303, and 301/302
+/// after a POST, switch to a body-less GET as browsers and reqwest do.
+fn prepare_hop(request: &mut Request, status: StatusCode, target: Url) {
+    *request.url_mut() = target;
+    let to_get = match status {
+        StatusCode::SEE_OTHER => request.method() != Method::HEAD,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => request.method() == Method::POST,
+        _ => false,
+    };
+    if to_get {
+        *request.method_mut() = Method::GET;
+        *request.body_mut() = None;
+        let headers = request.headers_mut();
+        headers.remove(CONTENT_TYPE);
+        headers.remove(CONTENT_ENCODING);
+        headers.remove(CONTENT_LENGTH);
+    }
+}
+
+fn format_chain(chain: &[Url]) -> String {
+    chain
+        .iter()
+        .map(Url::as_str)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
@@ -1,14 +1,41 @@
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
 
 use once_cell::sync::Lazy;
 
+use crate::transports::graphql::GraphQLTransport;
+use crate::transports::http::HttpClientTransport;
+use crate::transports::http_client::SharedClient;
+use crate::transports::http_stream::StreamableHttpTransport;
+use crate::transports::mcp::McpTransport;
+use crate::transports::sse::SseTransport;
+use crate::transports::text::TextTransport;
 use crate::transports::CommunicationProtocol;
 
+type ProtocolMap = RwLock<HashMap<String, Arc<dyn CommunicationProtocol>>>;
+
 /// Plugin-style registry for communication protocols (formerly transports) keyed by call_template_type/provider_type.
 #[derive(Clone, Default)]
 pub struct CommunicationProtocolRegistry {
-    map: Arc<RwLock<HashMap<String, Arc<dyn CommunicationProtocol>>>>,
+    map: Arc<ProtocolMap>,
+}
+
+/// A handle on a registry that does not keep it alive, for protocols that look up other
+/// protocols in the registry holding them.
+#[derive(Clone, Default)]
+pub(crate) struct WeakProtocolRegistry {
+    map: Weak<ProtocolMap>,
+}
+
+impl WeakProtocolRegistry {
+    /// Look up a protocol by key, or `None` once the registry is gone.
+    pub(crate) fn get(&self, key: &str) -> Option<Arc<dyn CommunicationProtocol>> {
+        let map = self.map.upgrade()?;
+        let guard = map
+            .read()
+            .expect("communication protocol registry poisoned");
+        guard.get(key).cloned()
+    }
 }
 
 impl CommunicationProtocolRegistry {
@@ -64,8 +91,43 @@ impl CommunicationProtocolRegistry {
         );
         self.register(
             "text",
-            Arc::new(crate::transports::text::TextTransport::new()),
+            Arc::new(TextTransport::new().with_protocols(self.downgrade())),
+        );
+    }
+
+    /// A handle on this registry that does not keep it alive.
+    pub(crate) fn downgrade(&self) -> WeakProtocolRegistry {
+        WeakProtocolRegistry {
+            map: Arc::downgrade(&self.map),
+        }
+    }
+
+    /// Copy of this registry whose built-in HTTP-based protocols (`http`, `sse`,
+    /// `http_stream`, `graphql` and `mcp`, and `text` for remote manuals) send requests
+    /// through `shared`. Other entries, including plugins, are kept as they are.
+    pub fn with_http_client(&self, shared: SharedClient) -> Self {
+        let reg = Self {
+            map: Arc::new(RwLock::new(self.as_map())),
+        };
+        reg.register(
+            "http",
+            Arc::new(HttpClientTransport::with_client(shared.clone())),
+        );
+        reg.register("sse", Arc::new(SseTransport::with_client(shared.clone())));
+        reg.register(
+            "http_stream",
+            Arc::new(StreamableHttpTransport::with_client(shared.clone())),
+        );
+        reg.register(
+            "graphql",
+            Arc::new(GraphQLTransport::with_client(shared.clone())),
+        );
+        reg.register(
+            "text",
+            Arc::new(TextTransport::with_client(shared.clone()).with_protocols(reg.downgrade())),
         );
+        reg.register("mcp", Arc::new(McpTransport::with_client(shared)));
+        reg
     }
 
     /// Add a protocol implementation under the provided key.
@@ -0,0 +1,159 @@
+//! Turning HTTP response bodies into tool results based on their `Content-Type`.
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+
+/// Binary payloads larger than this are written to a temp file unless the provider says otherwise.
+pub(crate) const DEFAULT_MAX_INLINE_BINARY: usize = 1024 * 1024; // 1 MB
+
+/// Names tried before giving up on creating a temp file for a large response.
+const TEMP_FILE_ATTEMPTS: usize = 16;
+
+/// Decode a response body:
+/// - JSON media types, and untyped or text-like bodies that parse as JSON, become the
+///   parsed value;
+/// - other text becomes a JSON string;
+/// - anything else becomes `{"$binary": {"content_type", "size", "base64"}}`, or
+///   `{"$binary": {"content_type", "size", "path"}}` once it exceeds `max_inline` bytes.
+///   The file at `path` is readable only by the current user and is never deleted here;
+///   whoever receives the result owns it and should remove it.
+pub(crate) async fn decode_body(
+    content_type: Option<&str>,
+    body: &[u8],
+    max_inline: usize,
+) -> Result<Value> {
+    let essence = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase());
+
+    match essence.as_deref() {
+        Some(ct) if is_json(ct) => Ok(serde_json::from_slice(body)?),
+        // Many servers label JSON as text/plain; those bodies used to be parsed, and still are
+        Some(ct) if is_text(ct) => Ok(serde_json::from_slice(body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))),
+        None => {
+            if let Ok(value) = serde_json::from_slice(body) {
+                return Ok(value);
+            }
+            match std::str::from_utf8(body) {
+                Ok(text) => Ok(Value::String(text.to_string())),
+                Err(_) => binary_value("application/octet-stream", body, max_inline).await,
+            }
+        }
+        Some(ct) => binary_value(ct, body, max_inline).await,
+    }
+}
+
+/// `{"status", "headers"}` for a response. Header names are lowercase and repeated headers
+/// are joined with `", "`.
+pub(crate) fn response_metadata(status: StatusCode, headers: &HeaderMap) -> Value {
+    let mut map = Map::new();
+    for name in headers.keys() {
+        let values: Vec<String> = headers
+            .get_all(name)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+            .collect();
+        map.insert(name.as_str().to_string(), Value::String(values.join(", ")));
+    }
+    json!({ "status": status.as_u16(), "headers": map })
+}
+
+/// Response metadata with the decoded body added under `"body"`.
+pub(crate) fn envelope(status: StatusCode, headers: &HeaderMap, body: Value) -> Value {
+    let mut value = response_metadata(status, headers);
+    value["body"] = body;
+    value
+}
+
+fn is_json(essence: &str) -> bool {
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+fn is_text(essence: &str) -> bool {
+    essence.starts_with("text/")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence,
+            "application/xml" | "application/javascript" | "application/x-www-form-urlencoded"
+        )
+}
+
+async fn binary_value(content_type: &str, body: &[u8], max_inline: usize) -> Result<Value> {
+    if body.len() <= max_inline {
+        return Ok(json!({ "$binary": {
+            "content_type": content_type,
+            "size": body.len(),
+            "base64": base64::engine::general_purpose::STANDARD.encode(body),
+        }}));
+    }
+
+    let path = write_temp_file(body).await?;
+    Ok(json!({ "$binary": {
+        "content_type": content_type,
+        "size": body.len(),
+        "path": path,
+    }}))
+}
+
+/// Write `body` to a new file in the system temp directory that only the current user can
+/// read. The file is created exclusively, so an existing file or symlink planted at the
+/// same name is never followed or overwritten; such names are skipped.
+async fn write_temp_file(body: &[u8]) -> Result<PathBuf> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    for _ in 0..TEMP_FILE_ATTEMPTS {
+        let path = temp_path();
+        let mut file = match options.open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to create temp file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            }
+        };
+        let written = async {
+            file.write_all(body).await?;
+            file.flush().await
+        };
+        if let Err(e) = written.await {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(anyhow!(
+                "Failed to write response to '{}': {}",
+                path.display(),
+                e
+            ));
+        }
+        return Ok(path);
+    }
+    Err(anyhow!(
+        "Failed to find an unused temp file name for the response"
+    ))
+}
+
+/// A candidate file name in the system temp directory.
+fn temp_path() -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "utcp-response-{}-{:x}-{:x}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
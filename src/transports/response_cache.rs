@@ -0,0 +1,190 @@
+//! Conditional-request cache for idempotent HTTP calls.
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::providers::http::ResponseCacheConfig;
+
+/// A response as the transport decodes it, kept so a 304 can be answered locally.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    /// Add `If-None-Match` / `If-Modified-Since` from the stored validators.
+    pub(crate) fn conditional(&self, mut builder: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = self.headers.get(ETAG) {
+            builder = builder.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = self.headers.get(LAST_MODIFIED) {
+            builder = builder.header(IF_MODIFIED_SINCE, modified);
+        }
+        builder
+    }
+
+    fn has_validators(&self) -> bool {
+        self.headers.contains_key(ETAG) || self.headers.contains_key(LAST_MODIFIED)
+    }
+}
+
+struct Entry {
+    response: CachedResponse,
+    stored_at: Instant,
+}
+
+/// Per-provider caches keyed by tool name and arguments.
+#[derive(Default)]
+pub(crate) struct ResponseCache {
+    providers: Mutex<HashMap<String, HashMap<String, Entry>>>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored response for `key`, unless it is older than the configured TTL.
+    pub(crate) fn get(
+        &self,
+        provider: &str,
+        key: &str,
+        config: &ResponseCacheConfig,
+    ) -> Option<CachedResponse> {
+        let mut providers = self.providers.lock().unwrap();
+        let entries = providers.get_mut(provider)?;
+        let entry = entries.get(key)?;
+        if entry.stored_at.elapsed() > ttl(config) {
+            entries.remove(key);
+            return None;
+        }
+        Some(entry.response.clone())
+    }
+
+    /// Remember a successful response that carries an `ETag` or `Last-Modified`, evicting
+    /// expired entries and then the oldest ones beyond `max_entries`.
+    pub(crate) fn store(
+        &self,
+        provider: &str,
+        key: String,
+        config: &ResponseCacheConfig,
+        response: CachedResponse,
+    ) {
+        if config.max_entries == 0 || !response.has_validators() {
+            return;
+        }
+        let mut providers = self.providers.lock().unwrap();
+        let entries = providers.entry(provider.to_string()).or_default();
+        entries.retain(|_, entry| entry.stored_at.elapsed() <= ttl(config));
+        entries.insert(
+            key,
+            Entry {
+                response,
+                stored_at: Instant::now(),
+            },
+        );
+        while entries.len() > config.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    /// Restart the TTL of an entry the server confirmed with 304 Not Modified.
+    pub(crate) fn revalidated(&self, provider: &str, key: &str) {
+        if let Some(entry) = self
+            .providers
+            .lock()
+            .unwrap()
+            .get_mut(provider)
+            .and_then(|entries| entries.get_mut(key))
+        {
+            entry.stored_at = Instant::now();
+        }
+    }
+
+    /// Forget everything cached for `provider`.
+    pub(crate) fn remove(&self, provider: &str) {
+        self.providers.lock().unwrap().remove(provider);
+    }
+}
+
+/// Cache key for one call: the tool name plus its arguments in a stable order.
+pub(crate) fn cache_key(tool_name: &str, args: &HashMap<String, Value>) -> String {
+    let args: serde_json::Map<String, Value> =
+        args.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    format!("{}\n{}", tool_name, Value::Object(args))
+}
+
+fn ttl(config: &ResponseCacheConfig) -> Duration {
+    Duration::from_millis(config.ttl_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    fn response(etag: Option<&str>) -> CachedResponse {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(ETAG, HeaderValue::from_str(etag).unwrap());
+        }
+        CachedResponse {
+            status: StatusCode::OK,
+            headers,
+            body: Bytes::from_static(b"{}"),
+        }
+    }
+
+    #[test]
+    fn key_ignores_argument_order() {
+        let a = HashMap::from([("x".to_string(), json!(1)), ("y".to_string(), json!(2))]);
+        let b = HashMap::from([("y".to_string(), json!(2)), ("x".to_string(), json!(1))]);
+        assert_eq!(cache_key("t", &a), cache_key("t", &b));
+        assert_ne!(cache_key("t", &a), cache_key("u", &a));
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_max_entries_and_skips_unvalidated() {
+        let cache = ResponseCache::new();
+        let config = ResponseCacheConfig {
+            max_entries: 2,
+            ttl_ms: 60_000,
+        };
+        cache.store("p", "no-etag".into(), &config, response(None));
+        assert!(cache.get("p", "no-etag", &config).is_none());
+
+        for key in ["a", "b", "c"] {
+            cache.store("p", key.into(), &config, response(Some("\"v1\"")));
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert!(cache.get("p", "a", &config).is_none());
+        assert!(cache.get("p", "b", &config).is_some());
+        assert!(cache.get("p", "c", &config).is_some());
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let cache = ResponseCache::new();
+        let config = ResponseCacheConfig {
+            max_entries: 8,
+            ttl_ms: 0,
+        };
+        cache.store("p", "a".into(), &config, response(Some("\"v1\"")));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get("p", "a", &config).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+//! Bounded retries for rate-limited or temporarily unavailable HTTP endpoints.
+use anyhow::Result;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::transports::timeouts::timeout_error;
+
+/// Retries attempted when a provider does not configure `max_retries`.
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Longest single wait when a provider does not configure `max_retry_delay_ms`.
+pub(crate) const DEFAULT_MAX_RETRY_DELAY_MS: u64 = 30_000;
+/// First backoff step when the server gives no `Retry-After`.
+const BASE_BACKOFF_MS: u64 = 200;
+
+/// How often and how long to retry 429 and 5xx responses for one request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    pub max_retries: u32,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Send once and return whatever comes back.
+    pub(crate) const NONE: Self = Self {
+        max_retries: 0,
+        max_delay: Duration::ZERO,
+    };
+
+    /// Build the policy for a request. Methods that are not idempotent are never retried
+    /// unless `retry_non_idempotent` is set.
+    pub(crate) fn for_method(
+        method: &str,
+        max_retries: Option<u32>,
+        max_retry_delay_ms: Option<u64>,
+        retry_non_idempotent: bool,
+    ) -> Self {
+        let idempotent = matches!(
+            method.to_ascii_uppercase().as_str(),
+            "GET" | "HEAD" | "OPTIONS" | "PUT" | "DELETE"
+        );
+        Self {
+            max_retries: if idempotent || retry_non_idempotent {
+                max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+            } else {
+                0
+            },
+            max_delay: Duration::from_millis(
+                max_retry_delay_ms.unwrap_or(DEFAULT_MAX_RETRY_DELAY_MS),
+            ),
+        }
+    }
+
+    /// Wait before retry number `attempt` (zero-based): the server's `Retry-After` when
+    /// present, otherwise exponential backoff with jitter, capped at `max_delay`.
+    fn delay(&self, attempt: u32, headers: &HeaderMap) -> Duration {
+        retry_after(headers)
+            .unwrap_or_else(|| backoff(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Send `request`, resending it while the response is 429 or 5xx and retries remain.
+/// The last response is returned as-is so callers report the final status.
+pub(crate) async fn send_with_retry(
+    request: RequestBuilder,
+    policy: RetryPolicy,
+) -> Result<Response> {
+    let mut attempt = 0;
+    loop {
+        // Bodies that cannot be cloned (streams) are sent once.
+        let current = match request.try_clone() {
+            Some(current) if attempt < policy.max_retries => current,
+            _ => return request.send().await.map_err(timeout_error),
+        };
+        let response = current.send().await.map_err(timeout_error)?;
+        if !is_retryable(response.status()) {
+            return Ok(response);
+        }
+        let delay = policy.delay(attempt, response.headers());
+        attempt += 1;
+        tokio::time::sleep(delay).await;
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP-date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(
+        at.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Exponential backoff with jitter: half the step is fixed and half is random.
+fn backoff(attempt: u32) -> Duration {
+    let step = BASE_BACKOFF_MS.saturating_mul(1u64 << attempt.min(16));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+    let half = step / 2;
+    Duration::from_millis(half + nanos % (half + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn non_idempotent_methods_do_not_retry_by_default() {
+        assert_eq!(
+            RetryPolicy::for_method("get", None, None, false).max_retries,
+            DEFAULT_MAX_RETRIES
+        );
+        assert_eq!(
+            RetryPolicy::for_method("POST", Some(5), None, false).max_retries,
+            0
+        );
+        assert_eq!(
+            RetryPolicy::for_method("PATCH", Some(5), None, true).max_retries,
+            5
+        );
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(120));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&later).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn delays_are_capped_by_the_policy() {
+        let policy = RetryPolicy::for_method("GET", None, Some(50), false);
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3600"));
+        assert_eq!(policy.delay(0, &headers), Duration::from_millis(50));
+        assert!(policy.delay(10, &HeaderMap::new()) <= Duration::from_millis(50));
+
+        let first = backoff(0);
+        assert!(first >= Duration::from_millis(100) && first <= Duration::from_millis(200));
+    }
+}
@@ -3,33 +3,56 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::StreamExt;
-use reqwest::{header, Client};
+use reqwest::header;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 use crate::auth::AuthConfig;
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
-use crate::providers::sse::SseProvider;
+use crate::providers::http::QueryEncoding;
+use crate::providers::sse::{
+    SseDiscoveryMode, SseProvider, DEFAULT_MAX_BUFFER_BYTES, DEFAULT_MAX_EVENT_BYTES,
+};
 use crate::tools::Tool;
+use crate::transports::client_cache::ClientCache;
+use crate::transports::http::{operation_request, url_with_query};
+use crate::transports::http_client::SharedClient;
+use crate::transports::response::response_metadata;
+use crate::transports::timeouts::{timeout_error, with_total_timeout};
 use crate::transports::{
     stream::{boxed_channel_stream, StreamResult},
     ClientTransport,
 };
 
+/// How long stream discovery waits for the manual event when `total_ms` is unset.
+const MANUAL_EVENT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Transport for Server-Sent Events endpoints that return event streams per tool call.
 pub struct SseTransport {
-    client: Client,
+    clients: ClientCache,
 }
 
 impl SseTransport {
-    /// Create an SSE transport backed by a reqwest client.
+    /// Create an SSE transport on the process-wide shared client.
     pub fn new() -> Self {
+        Self::with_client(SharedClient::default())
+    }
+
+    /// Create an SSE transport that sends requests through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
         Self {
-            client: Client::new(),
+            clients: ClientCache::new(shared),
         }
     }
 
+    /// Drop the session cookies stored for `provider`; later calls start with an empty jar.
+    pub fn clear_cookies(&self, provider: &str) {
+        self.clients.clear_cookies(provider);
+    }
+
     fn build_payload(&self, prov: &SseProvider, args: HashMap<String, Value>) -> Value {
         if let Some(body_field) = &prov.body_field {
             json!({ body_field: args })
@@ -129,44 +152,109 @@ impl SseTransport {
         vec![]
     }
 
+    /// Read the tool manual from the first `event: manual` on the provider's stream, then
+    /// close the connection. Other events, such as heartbeats, are skipped.
+    async fn discover_from_stream(
+        &self,
+        prov: &SseProvider,
+        client: &reqwest::Client,
+    ) -> Result<Vec<Tool>> {
+        let mut request = client
+            .get(&prov.url)
+            .header(header::ACCEPT_ENCODING, "identity");
+        request = self.apply_headers(request, prov, Some("text/event-stream"), &HashMap::new());
+        if let Some(auth) = &prov.base.auth {
+            request = self.apply_auth(request, auth)?;
+        }
+
+        let limit = prov
+            .timeouts
+            .and_then(|t| t.total_ms)
+            .map_or(MANUAL_EVENT_TIMEOUT, Duration::from_millis);
+        let read_manual = async {
+            let response = request.send().await.map_err(timeout_error)?;
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Failed to open SSE stream at {}: {}",
+                    prov.url,
+                    response.status()
+                ));
+            }
+            let mut stream = response.bytes_stream();
+            let mut parser = SseParser::for_provider(prov);
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk.map_err(timeout_error)?;
+                if let Some(manual) = parser
+                    .feed(&chunk)?
+                    .into_iter()
+                    .find(|event| event.event.as_deref() == Some("manual"))
+                {
+                    return Ok(manual.data);
+                }
+            }
+            Err(anyhow!(
+                "SSE stream at {} ended before a manual event",
+                prov.url
+            ))
+        };
+        let manual = tokio::time::timeout(limit, read_manual)
+            .await
+            .map_err(|_| {
+                UtcpError::Timeout(format!("no manual event within {}ms", limit.as_millis()))
+            })??;
+        Ok(self.parse_tools_from_body(&manual))
+    }
+
+    /// Parse `data:` events from `stream`, emitting `first` (if any) before them.
     fn spawn_sse_reader(
         &self,
         mut stream: impl futures::Stream<Item = Result<Bytes, reqwest::Error>> + Send + Unpin + 'static,
+        first: Option<Value>,
+        mut parser: SseParser,
+        dispatch: EventDispatch,
+        idle_timeout: Option<Duration>,
     ) -> mpsc::Receiver<Result<Value>> {
         let (tx, rx) = mpsc::channel(16);
         tokio::spawn(async move {
-            let mut buffer = String::new();
-            let mut data_buf = String::new();
-
-            while let Some(chunk_res) = stream.next().await {
+            if let Some(first) = first {
+                if tx.send(Ok(first)).await.is_err() {
+                    return;
+                }
+            }
+            loop {
+                // Any bytes, keep-alive comments included, show the upstream is still alive.
+                let next = match idle_timeout {
+                    Some(limit) => match tokio::time::timeout(limit, stream.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            let err = UtcpError::Timeout(format!(
+                                "no SSE data for {}ms",
+                                limit.as_millis()
+                            ));
+                            let _ = tx.send(Err(err.into())).await;
+                            return;
+                        }
+                    },
+                    None => stream.next().await,
+                };
+                let chunk_res = match next {
+                    Some(chunk_res) => chunk_res,
+                    None => break,
+                };
                 match chunk_res {
                     Ok(bytes) => {
-                        buffer.push_str(&String::from_utf8_lossy(&bytes));
-                        loop {
-                            if let Some(pos) = buffer.find('\n') {
-                                let mut line = buffer[..pos].to_string();
-                                buffer.drain(..=pos);
-                                line = line.trim_end_matches('\r').to_string();
-
-                                if line.starts_with("data: ") {
-                                    if !data_buf.is_empty() {
-                                        data_buf.push('\n');
-                                    }
-                                    data_buf.push_str(&line[6..]);
-                                } else if line.is_empty() {
-                                    if !data_buf.is_empty() {
-                                        let parsed = serde_json::from_str::<Value>(&data_buf)
-                                            .map_err(|e| {
-                                                anyhow!("Failed to parse SSE data: {}", e)
-                                            });
-                                        if tx.send(parsed).await.is_err() {
-                                            return;
-                                        }
-                                        data_buf.clear();
-                                    }
+                        let events = match parser.feed(&bytes) {
+                            Ok(events) => events,
+                            Err(err) => {
+                                let _ = tx.send(Err(err)).await;
+                                return;
+                            }
+                        };
+                        for event in events {
+                            if let Some(item) = dispatch.item(event) {
+                                if tx.send(item).await.is_err() {
+                                    return;
                                 }
-                            } else {
-                                break;
                             }
                         }
                     }
@@ -179,20 +267,200 @@ impl SseTransport {
                 }
             }
 
-            // Flush trailing data if present
-            if !data_buf.is_empty() {
-                let _ = tx
-                    .send(
-                        serde_json::from_str::<Value>(&data_buf)
-                            .map_err(|e| anyhow!("Failed to parse SSE data: {}", e)),
-                    )
-                    .await;
+            // Flush a trailing event that was not followed by a blank line
+            let item = match parser.finish() {
+                Ok(event) => event.and_then(|event| dispatch.item(event)),
+                Err(err) => Some(Err(err)),
+            };
+            if let Some(item) = item {
+                let _ = tx.send(item).await;
             }
         });
         rx
     }
 }
 
+/// One event from a `text/event-stream` body.
+#[derive(Debug, Clone, PartialEq)]
+struct SseEvent {
+    event: Option<String>,
+    id: Option<String>,
+    data: String,
+}
+
+/// Incremental parser for the `event:`, `id:` and `data:` fields of an event stream.
+#[derive(Debug)]
+struct SseParser {
+    /// Bytes of a line whose terminator has not arrived yet, kept undecoded so a character
+    /// split across chunks survives.
+    partial: Vec<u8>,
+    /// Set while discarding the rest of an over-long line that carries no data.
+    skipping_line: bool,
+    event: Option<String>,
+    data: Option<String>,
+    /// The last event ID persists across events, as in the EventSource spec.
+    last_id: Option<String>,
+    max_event_bytes: usize,
+    max_buffer_bytes: usize,
+}
+
+impl Default for SseParser {
+    fn default() -> Self {
+        Self::with_limits(DEFAULT_MAX_EVENT_BYTES, DEFAULT_MAX_BUFFER_BYTES)
+    }
+}
+
+impl SseParser {
+    /// Parser that fails once an event's data exceeds `max_event_bytes` or an unterminated
+    /// data line exceeds `max_buffer_bytes`.
+    fn with_limits(max_event_bytes: usize, max_buffer_bytes: usize) -> Self {
+        Self {
+            partial: Vec::new(),
+            skipping_line: false,
+            event: None,
+            data: None,
+            last_id: None,
+            max_event_bytes,
+            max_buffer_bytes,
+        }
+    }
+
+    fn for_provider(prov: &SseProvider) -> Self {
+        Self::with_limits(prov.max_event_bytes, prov.max_buffer_bytes)
+    }
+
+    /// Feed a chunk of the body, returning the events it completes.
+    fn feed(&mut self, mut chunk: &[u8]) -> Result<Vec<SseEvent>> {
+        if self.skipping_line {
+            match chunk.iter().position(|&b| b == b'\n') {
+                Some(pos) => {
+                    self.skipping_line = false;
+                    chunk = &chunk[pos + 1..];
+                }
+                None => return Ok(Vec::new()),
+            }
+        }
+        self.partial.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        let buffered = std::mem::take(&mut self.partial);
+        let mut lines = buffered.split(|&b| b == b'\n');
+        // The piece after the last newline is still incomplete
+        let rest = lines.next_back().unwrap_or_default();
+        for line in lines {
+            events.extend(self.line(String::from_utf8_lossy(line).trim_end_matches('\r'))?);
+        }
+        self.partial = rest.to_vec();
+
+        if self.partial.len() > self.max_buffer_bytes {
+            // Comments and other fields are not needed, so the rest of the line is dropped.
+            if self.partial.starts_with(b"data") {
+                return Err(anyhow!(
+                    "SSE line exceeds max_buffer_bytes ({} bytes) without a newline",
+                    self.max_buffer_bytes
+                ));
+            }
+            self.partial.clear();
+            self.skipping_line = true;
+        }
+        Ok(events)
+    }
+
+    /// Feed one line without its terminator; a blank line completes the pending event.
+    fn line(&mut self, line: &str) -> Result<Option<SseEvent>> {
+        if line.is_empty() {
+            return Ok(self.dispatch());
+        }
+        if line.starts_with(':') {
+            return Ok(None);
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => {
+                let size = self.data.as_ref().map_or(0, |data| data.len() + 1) + value.len();
+                if size > self.max_event_bytes {
+                    return Err(anyhow!(
+                        "SSE event exceeds max_event_bytes ({} bytes)",
+                        self.max_event_bytes
+                    ));
+                }
+                match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                }
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// The event left when the body ends without a final blank line.
+    fn finish(&mut self) -> Result<Option<SseEvent>> {
+        if !self.partial.is_empty() && !self.skipping_line {
+            let line = std::mem::take(&mut self.partial);
+            self.line(String::from_utf8_lossy(&line).trim_end_matches('\r'))?;
+        }
+        Ok(self.dispatch())
+    }
+
+    /// The pending event, if it has any data; events without data are dropped.
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        Some(SseEvent {
+            event,
+            id: self.last_id.clone(),
+            data: self.data.take()?,
+        })
+    }
+}
+
+/// How parsed events become stream items, taken from the provider.
+#[derive(Debug, Clone, Default)]
+struct EventDispatch {
+    include_metadata: bool,
+    filter: Option<Vec<String>>,
+}
+
+impl EventDispatch {
+    fn from_provider(prov: &SseProvider) -> Self {
+        Self {
+            include_metadata: prov.include_event_metadata,
+            filter: prov.event_filter.clone(),
+        }
+    }
+
+    /// The item for `event`, or `None` when the filter drops it. `error` events always
+    /// become stream errors.
+    fn item(&self, event: SseEvent) -> Option<Result<Value>> {
+        let name = event.event.as_deref().unwrap_or("message");
+        if name == "error" {
+            return Some(Err(anyhow!("SSE error event: {}", event.data)));
+        }
+        if let Some(filter) = &self.filter {
+            if !filter.iter().any(|allowed| allowed == name) {
+                return None;
+            }
+        }
+        let data = match serde_json::from_str::<Value>(&event.data) {
+            Ok(data) => data,
+            Err(e) => return Some(Err(anyhow!("Failed to parse SSE data: {}", e))),
+        };
+        Some(Ok(if self.include_metadata {
+            json!({ "event": name, "id": event.id, "data": data })
+        } else {
+            data
+        }))
+    }
+}
+
 #[async_trait]
 impl ClientTransport for SseTransport {
     async fn register_tool_provider(&self, prov: &dyn Provider) -> Result<Vec<Tool>> {
@@ -201,29 +469,44 @@ impl ClientTransport for SseTransport {
             .downcast_ref::<SseProvider>()
             .ok_or_else(|| anyhow!("Provider is not an SseProvider"))?;
 
+        let timeouts = sse_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &sse_prov.base.name,
+            timeouts,
+            sse_prov.proxy.as_ref(),
+            sse_prov.use_cookies,
+            false,
+        )?;
+        if sse_prov.discovery_mode == SseDiscoveryMode::Stream {
+            return self.discover_from_stream(sse_prov, &client).await;
+        }
         let mut request = self
-            .client
-            .get(&sse_prov.url)
+            .clients
+            .apply_request_timeout(client.get(&sse_prov.url), timeouts, None)
             .header("Accept", "application/json");
         request = self.apply_headers(request, sse_prov, None, &HashMap::new());
         if let Some(auth) = &sse_prov.base.auth {
             request = self.apply_auth(request, auth)?;
         }
 
-        let response = request.send().await?;
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Failed to fetch tools from {}: {}",
-                sse_prov.url,
-                response.status()
-            ));
-        }
+        let body = with_total_timeout(timeouts, async {
+            let response = request.send().await.map_err(timeout_error)?;
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Failed to fetch tools from {}: {}",
+                    sse_prov.url,
+                    response.status()
+                ));
+            }
 
-        let body = response.text().await?;
+            response.text().await.map_err(timeout_error)
+        })
+        .await?;
         Ok(self.parse_tools_from_body(&body))
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        self.clients.remove(&prov.name());
         Ok(())
     }
 
@@ -254,28 +537,73 @@ impl ClientTransport for SseTransport {
             .downcast_ref::<SseProvider>()
             .ok_or_else(|| anyhow!("Provider is not an SseProvider"))?;
 
-        let call_name = tool_name
-            .strip_prefix(&format!("{}.", sse_prov.base.name))
-            .unwrap_or(tool_name);
-        let url = format!("{}/{}", sse_prov.url.trim_end_matches('/'), call_name);
         let (header_args, payload_args) = self.split_headers_from_args(sse_prov, args);
-        let payload = self.build_payload(sse_prov, payload_args);
+        let method = sse_prov.http_method.to_uppercase();
+        let (url, body) = if sse_prov.operation_url {
+            operation_request(
+                &sse_prov.url,
+                &method,
+                sse_prov.path_params.as_deref(),
+                sse_prov.param_styles.as_ref(),
+                sse_prov.body_field.as_deref(),
+                payload_args,
+            )?
+        } else {
+            let call_name = tool_name
+                .strip_prefix(&format!("{}.", sse_prov.base.name))
+                .unwrap_or(tool_name);
+            let url = format!("{}/{}", sse_prov.url.trim_end_matches('/'), call_name);
+            if method == "GET" {
+                // Subscriptions over GET carry their arguments in the query string and send no body
+                let url = url_with_query(&url, &payload_args, None, QueryEncoding::Repeat);
+                (url, None)
+            } else {
+                (url, Some(self.build_payload(sse_prov, payload_args)))
+            }
+        };
 
-        let mut request = self
-            .client
-            .post(url)
-            .header("Content-Type", "application/json");
+        let timeouts = sse_prov.timeouts.as_ref();
+        let client = self.clients.client_for(
+            &sse_prov.base.name,
+            timeouts,
+            sse_prov.proxy.as_ref(),
+            sse_prov.use_cookies,
+            false,
+        )?;
+        let mut request = match method.as_str() {
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            other => return Err(anyhow!("Unsupported HTTP method for SSE: {}", other)),
+        };
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
         request = self.apply_headers(request, sse_prov, Some("text/event-stream"), &header_args);
+        // Events must reach the reader as they are sent, not in compressed blocks.
+        request = request.header(header::ACCEPT_ENCODING, "identity");
         if let Some(auth) = &sse_prov.base.auth {
             request = self.apply_auth(request, auth)?;
         }
-        let response = request.json(&payload).send().await?;
+        // Only the wait for the response headers is limited; events may arrive indefinitely.
+        let response = with_total_timeout(timeouts, async {
+            request.send().await.map_err(timeout_error)
+        })
+        .await?;
 
         if !response.status().is_success() {
             return Err(anyhow!("SSE request failed: {}", response.status()));
         }
 
-        let rx = self.spawn_sse_reader(response.bytes_stream());
+        let metadata = sse_prov
+            .return_envelope
+            .then(|| response_metadata(response.status(), response.headers()));
+        let rx = self.spawn_sse_reader(
+            response.bytes_stream(),
+            metadata,
+            SseParser::for_provider(sse_prov),
+            EventDispatch::from_provider(sse_prov),
+            sse_prov.idle_timeout_ms.map(Duration::from_millis),
+        );
         Ok(boxed_channel_stream(rx, None))
     }
 }
@@ -303,9 +631,23 @@ mod tests {
                 allowed_communication_protocols: None,
             },
             url: "http://example.com".to_string(),
+            http_method: "POST".to_string(),
             headers: None,
             body_field: Some("data".to_string()),
             header_fields: None,
+            timeouts: None,
+            proxy: None,
+            return_envelope: false,
+            use_cookies: false,
+            include_event_metadata: false,
+            event_filter: None,
+            idle_timeout_ms: None,
+            discovery_mode: SseDiscoveryMode::Endpoint,
+            max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
         };
 
         let payload = transport.build_payload(&prov, args.clone());
@@ -328,9 +670,23 @@ mod tests {
                 allowed_communication_protocols: None,
             },
             url: "http://example.com".to_string(),
+            http_method: "POST".to_string(),
             headers: Some(HashMap::from([("X-Test".to_string(), "123".to_string())])),
             body_field: None,
             header_fields: None,
+            timeouts: None,
+            proxy: None,
+            return_envelope: false,
+            use_cookies: false,
+            include_event_metadata: false,
+            event_filter: None,
+            idle_timeout_ms: None,
+            discovery_mode: SseDiscoveryMode::Endpoint,
+            max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
         };
 
         let request = transport
@@ -380,9 +736,23 @@ mod tests {
                 allowed_communication_protocols: None,
             },
             url: "http://example.com".to_string(),
+            http_method: "POST".to_string(),
             headers: None,
             body_field: None,
             header_fields: Some(vec!["X-Token".into(), "trace".into()]),
+            timeouts: None,
+            proxy: None,
+            return_envelope: false,
+            use_cookies: false,
+            include_event_metadata: false,
+            event_filter: None,
+            idle_timeout_ms: None,
+            discovery_mode: SseDiscoveryMode::Endpoint,
+            max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
         };
 
         let mut args = HashMap::new();
@@ -456,9 +826,23 @@ mod tests {
                 allowed_communication_protocols: None,
             },
             url: format!("http://{}", addr),
+            http_method: "POST".to_string(),
             headers: None,
             body_field: None,
             header_fields: Some(vec!["X-Trace".into()]),
+            timeouts: None,
+            proxy: None,
+            return_envelope: false,
+            use_cookies: false,
+            include_event_metadata: false,
+            event_filter: None,
+            idle_timeout_ms: None,
+            discovery_mode: SseDiscoveryMode::Endpoint,
+            max_event_bytes: DEFAULT_MAX_EVENT_BYTES,
+            max_buffer_bytes: DEFAULT_MAX_BUFFER_BYTES,
+            operation_url: false,
+            path_params: None,
+            param_styles: None,
         };
 
         let transport = SseTransport::new();
@@ -501,4 +885,350 @@ mod tests {
         );
         let _ = prefixed_stream.close().await;
     }
+
+    const MIXED_EVENTS: &str = "event: progress\nid: 1\ndata: {\"pct\":\ndata: 50}\n\n\
+        : keep-alive\n\
+        data: {\"plain\":true}\n\n\
+        event: result\nid: 2\ndata: {\"done\":true}\n\n\
+        event: error\ndata: quota exceeded\n\n";
+
+    async fn read_events(dispatch: EventDispatch) -> Vec<Result<Value>> {
+        let chunks: Vec<Result<Bytes, reqwest::Error>> = MIXED_EVENTS
+            .as_bytes()
+            .chunks(7)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        let mut rx = SseTransport::new().spawn_sse_reader(
+            futures::stream::iter(chunks),
+            None,
+            SseParser::default(),
+            dispatch,
+            None,
+        );
+        let mut items = Vec::new();
+        while let Some(item) = rx.recv().await {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn parser_reads_event_names_ids_and_multi_line_data() {
+        let mut parser = SseParser::default();
+        let events: Vec<SseEvent> = MIXED_EVENTS
+            .lines()
+            .filter_map(|line| parser.line(line).unwrap())
+            .collect();
+        assert_eq!(
+            events[0],
+            SseEvent {
+                event: Some("progress".to_string()),
+                id: Some("1".to_string()),
+                data: "{\"pct\":\n50}".to_string(),
+            }
+        );
+        // The last event ID carries over to events that do not set one.
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].id.as_deref(), Some("1"));
+        assert_eq!(events[2].event.as_deref(), Some("result"));
+        assert_eq!(events[3].data, "quota exceeded");
+        assert_eq!(events.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn reader_yields_bare_data_by_default_and_errors_on_error_events() {
+        let items = read_events(EventDispatch::default()).await;
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].as_ref().unwrap(), &json!({ "pct": 50 }));
+        assert_eq!(items[1].as_ref().unwrap(), &json!({ "plain": true }));
+        assert_eq!(items[2].as_ref().unwrap(), &json!({ "done": true }));
+        let err = items[3].as_ref().unwrap_err().to_string();
+        assert!(err.contains("quota exceeded"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn reader_wraps_metadata_and_filters_event_names() {
+        let items = read_events(EventDispatch {
+            include_metadata: true,
+            filter: Some(vec!["result".to_string(), "message".to_string()]),
+        })
+        .await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0].as_ref().unwrap(),
+            &json!({ "event": "message", "id": "1", "data": { "plain": true } })
+        );
+        assert_eq!(
+            items[1].as_ref().unwrap(),
+            &json!({ "event": "result", "id": "2", "data": { "done": true } })
+        );
+        assert!(items[2].is_err());
+    }
+
+    async fn read_limited(chunks: Vec<Vec<u8>>, parser: SseParser) -> Vec<Result<Value>> {
+        let chunks: Vec<Result<Bytes, reqwest::Error>> =
+            chunks.into_iter().map(|c| Ok(Bytes::from(c))).collect();
+        let mut rx = SseTransport::new().spawn_sse_reader(
+            futures::stream::iter(chunks),
+            None,
+            parser,
+            EventDispatch::default(),
+            None,
+        );
+        let mut items = Vec::new();
+        while let Some(item) = rx.recv().await {
+            items.push(item);
+        }
+        items
+    }
+
+    #[tokio::test]
+    async fn data_line_without_newline_aborts_once_buffer_limit_is_hit() {
+        let mut parser = SseParser::with_limits(1024, 256);
+        let mut chunks = vec![b"data: {\"ok\":1}\n\ndata: ".to_vec()];
+        // A server that never terminates the line: far more than the limit in total.
+        chunks.extend(std::iter::repeat_n(vec![b'x'; 64], 1000));
+
+        assert!(parser.feed(&chunks[0]).is_ok());
+        let mut failed = None;
+        for chunk in &chunks[1..] {
+            if let Err(err) = parser.feed(chunk) {
+                failed = Some(err.to_string());
+                break;
+            }
+            assert!(parser.partial.len() <= 256 + 64);
+        }
+        let err = failed.expect("buffer limit not enforced");
+        assert!(err.contains("max_buffer_bytes (256 bytes)"), "{err}");
+
+        let items = read_limited(chunks, SseParser::with_limits(1024, 256)).await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), &json!({ "ok": 1 }));
+        assert!(items[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn event_split_over_many_data_lines_aborts_at_event_limit() {
+        let mut chunks = vec![b"data: {\"ok\":1}\n\n".to_vec()];
+        chunks.extend(std::iter::repeat_n(b"data: 0123456789\n".to_vec(), 10_000));
+        chunks.push(b"\n".to_vec());
+
+        let items = read_limited(chunks, SseParser::with_limits(1024, 256)).await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), &json!({ "ok": 1 }));
+        let err = items[1].as_ref().unwrap_err().to_string();
+        assert!(err.contains("max_event_bytes (1024 bytes)"), "{err}");
+    }
+
+    #[test]
+    fn characters_split_across_chunks_are_kept() {
+        let body = "data: {\"city\":\"Zürich\"}\n\n".as_bytes();
+        let split = body.iter().position(|&b| b == 0xc3).unwrap() + 1;
+
+        let mut parser = SseParser::default();
+        assert!(parser.feed(&body[..split]).unwrap().is_empty());
+        let events = parser.feed(&body[split..]).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "{\"city\":\"Zürich\"}");
+    }
+
+    #[tokio::test]
+    async fn over_long_comment_lines_are_dropped() {
+        let mut chunks = vec![b": ".to_vec()];
+        chunks.extend(std::iter::repeat_n(vec![b'-'; 100], 100));
+        chunks.push(b"\nevent: result\ndata: {\"ok\":2}\n\n".to_vec());
+
+        let mut parser = SseParser::with_limits(1024, 256);
+        for chunk in &chunks {
+            parser.feed(chunk).unwrap();
+            assert!(parser.partial.len() <= 256 + 100);
+        }
+
+        let items = read_limited(chunks, SseParser::with_limits(1024, 256)).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].as_ref().unwrap(), &json!({ "ok": 2 }));
+    }
+
+    #[tokio::test]
+    async fn idle_stream_ends_with_timeout_unless_keepalives_arrive() {
+        /// Sends one event, then either goes silent or sends keep-alive comments before a
+        /// final event.
+        async fn events(heartbeat: bool) -> Response<Body> {
+            let (mut tx, body) = Body::channel();
+            tokio::spawn(async move {
+                tx.send_data(Bytes::from_static(b"data: {\"n\":1}\n\n"))
+                    .await
+                    .unwrap();
+                if heartbeat {
+                    for _ in 0..4 {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        tx.send_data(Bytes::from_static(b": keepalive\n"))
+                            .await
+                            .unwrap();
+                    }
+                    tx.send_data(Bytes::from_static(b"data: {\"n\":2}\n\n"))
+                        .await
+                        .unwrap();
+                } else {
+                    // Keep the connection open without sending anything.
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                }
+            });
+            Response::builder()
+                .header("content-type", "text/event-stream")
+                .body(body)
+                .unwrap()
+        }
+
+        let app = Router::new()
+            .route("/stall", post(|| events(false)))
+            .route("/heartbeat", post(|| events(true)));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let mut prov = SseProvider::new("sse".to_string(), format!("http://{}", addr), None);
+        prov.idle_timeout_ms = Some(250);
+        let transport = SseTransport::new();
+
+        let mut stream = transport
+            .call_tool_stream("sse.stall", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": 1 })));
+        let err = stream.next().await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+
+        // 400ms in total, but never more than 100ms without bytes.
+        let events = transport
+            .call_tool("sse.heartbeat", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(events, json!([{ "n": 1 }, { "n": 2 }]));
+    }
+
+    #[tokio::test]
+    async fn get_subscriptions_send_args_as_query_without_body() {
+        async fn notifications(
+            axum::extract::RawQuery(query): axum::extract::RawQuery,
+            headers: axum::http::HeaderMap,
+            body: Bytes,
+        ) -> Response<Body> {
+            assert_eq!(query.as_deref(), Some("channel=alerts&since=42"));
+            assert_eq!(headers["accept"], "text/event-stream");
+            assert!(body.is_empty(), "GET subscriptions must not send a body");
+            Response::builder()
+                .header("content-type", "text/event-stream")
+                .body(Body::from("data: {\"alert\":\"disk\"}\n\n"))
+                .unwrap()
+        }
+
+        let app = Router::new().route("/notifications", get(notifications));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let mut prov = SseProvider::new("notify".to_string(), format!("http://{}", addr), None);
+        prov.http_method = "GET".to_string();
+        let args = HashMap::from([
+            ("channel".to_string(), json!("alerts")),
+            ("since".to_string(), json!(42)),
+        ]);
+
+        let events = SseTransport::new()
+            .call_tool("notify.notifications", args, &prov)
+            .await
+            .unwrap();
+        assert_eq!(events, json!([{ "alert": "disk" }]));
+    }
+
+    #[tokio::test]
+    async fn stream_discovery_reads_manual_event_then_closes() {
+        /// Heartbeats around an optional manual event; the stream itself never ends.
+        async fn events(send_manual: bool) -> Response<Body> {
+            let (mut tx, body) = Body::channel();
+            tokio::spawn(async move {
+                let _ = tx
+                    .send_data(Bytes::from_static(b": hello\nevent: ping\ndata: {}\n\n"))
+                    .await;
+                if send_manual {
+                    let manual = json!({
+                        "tools": [{
+                            "name": "watch",
+                            "description": "streams updates",
+                            "inputs": { "type": "object" },
+                            "outputs": { "type": "object" },
+                            "tags": []
+                        }]
+                    });
+                    let frame = format!("event: manual\ndata: {}\n\n", manual);
+                    let _ = tx.send_data(Bytes::from(frame)).await;
+                }
+                loop {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    if tx
+                        .send_data(Bytes::from_static(b": heartbeat\n"))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+            Response::builder()
+                .header("content-type", "text/event-stream")
+                .body(body)
+                .unwrap()
+        }
+
+        let app = Router::new()
+            .route("/with-manual", get(|| events(true)))
+            .route("/heartbeats-only", get(|| events(false)));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let transport = SseTransport::new();
+        let mut prov = SseProvider::new(
+            "live".to_string(),
+            format!("http://{}/with-manual", addr),
+            None,
+        );
+        prov.discovery_mode = SseDiscoveryMode::Stream;
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "watch");
+
+        prov.url = format!("http://{}/heartbeats-only", addr);
+        prov.timeouts = Some(crate::providers::http::HttpTimeouts {
+            total_ms: Some(300),
+            ..Default::default()
+        });
+        let err = transport.register_tool_provider(&prov).await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+    }
 }
@@ -2,6 +2,9 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::errors::UtcpError;
 
 /// A minimal streaming abstraction that mirrors go-utcp's StreamResult (Next/Close).
 #[async_trait]
@@ -86,6 +89,37 @@ impl StreamResult for VecStreamResult {
     }
 }
 
+/// StreamResult that closes the stream it wraps once a cancellation token fires.
+pub struct CancellableStream {
+    inner: Box<dyn StreamResult>,
+    cancel: CancellationToken,
+}
+
+impl CancellableStream {
+    /// Wrap `inner` so it is closed, and reports cancellation, after `cancel` fires.
+    pub fn new(inner: Box<dyn StreamResult>, cancel: CancellationToken) -> Self {
+        Self { inner, cancel }
+    }
+}
+
+#[async_trait]
+impl StreamResult for CancellableStream {
+    async fn next(&mut self) -> Result<Option<Value>> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => {
+                self.inner.close().await?;
+                Err(UtcpError::Cancelled("stream".to_string()).into())
+            }
+            item = self.inner.next() => item,
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
 /// Helper to box a channel-backed stream result.
 pub fn boxed_channel_stream(
     rx: mpsc::Receiver<Result<Value>>,
@@ -141,4 +175,31 @@ mod tests {
         assert!(format!("{err}").contains("boom"));
         assert_eq!(stream.next().await.unwrap(), None);
     }
+
+    #[tokio::test]
+    async fn cancellable_stream_closes_inner_stream_when_cancelled() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let closed_clone = closed.clone();
+        let (tx, rx) = mpsc::channel(2);
+        tx.send(Ok(json!(1))).await.unwrap();
+        let inner = ChannelStreamResult::new(
+            rx,
+            Some(Box::new(move || {
+                closed_clone.store(true, Ordering::SeqCst);
+                Ok(())
+            })),
+        );
+        let cancel = CancellationToken::new();
+        let mut stream = CancellableStream::new(Box::new(inner), cancel.clone());
+
+        assert_eq!(stream.next().await.unwrap(), Some(json!(1)));
+        cancel.cancel();
+        let err = stream.next().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<UtcpError>(),
+            Some(UtcpError::Cancelled(_))
+        ));
+        assert!(closed.load(Ordering::SeqCst));
+        drop(tx);
+    }
 }
@@ -1,10 +1,14 @@
 // TCP Transport
+mod tls;
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 
@@ -16,7 +20,68 @@ use crate::transports::{
     ClientTransport,
 };
 
+/// How long discovery waits for a manifest when the provider sets no `timeout_ms`.
+const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Key of the line a server sends after the last item of a stream, as `{"$done": true}`.
+const STREAM_DONE: &str = "$done";
+
+/// A plain or TLS connection to a provider.
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// Connect to the provider, over TLS when it sets `tls`. TLS settings are checked before
+/// dialing.
+async fn connect(prov: &TcpProvider) -> Result<Box<dyn Connection>> {
+    let connector = prov
+        .tls
+        .as_ref()
+        .map(|tls| tls::Connector::new(prov, tls))
+        .transpose()?;
+    let stream = TcpStream::connect(format!("{}:{}", prov.host, prov.port)).await?;
+    match connector {
+        Some(connector) => connector.handshake(stream).await,
+        None => Ok(Box::new(stream)),
+    }
+}
+
+fn is_done(item: &Value) -> bool {
+    item.get(STREAM_DONE).and_then(Value::as_bool) == Some(true)
+}
+
+/// Read the next non-empty line as JSON, within `timeout` when set. Returns `None` at the end
+/// of the connection.
+async fn read_json_line<R>(reader: &mut R, timeout: Option<Duration>) -> Result<Option<Value>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    loop {
+        let mut line = String::new();
+        let read = match timeout {
+            Some(duration) => tokio::time::timeout(duration, reader.read_line(&mut line))
+                .await
+                .map_err(|_| anyhow!("TCP stream timed out"))?,
+            None => reader.read_line(&mut line).await,
+        };
+        if read.map_err(|err| anyhow!("TCP stream error: {}", err))? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        return serde_json::from_str(trimmed)
+            .map(Some)
+            .map_err(|err| anyhow!("Failed to parse TCP stream JSON: {}", err));
+    }
+}
+
 /// TCP transport used for simple length-delimited or line-delimited JSON exchanges.
+///
+/// Besides one-shot calls, servers may speak a line protocol: `{"method": "list_tools"}`
+/// is answered with a `{"tools": [...]}` manifest line, and `{"method": "call_tool_stream",
+/// "tool": ..., "args": ...}` with one JSON document per line until `{"$done": true}`.
 pub struct TcpTransport;
 
 impl TcpTransport {
@@ -25,8 +90,8 @@ impl TcpTransport {
         Self
     }
 
-    async fn send_and_receive(&self, address: &str, data: &[u8]) -> Result<Vec<u8>> {
-        let mut stream = TcpStream::connect(address).await?;
+    async fn send_and_receive(&self, prov: &TcpProvider, data: &[u8]) -> Result<Vec<u8>> {
+        let mut stream = connect(prov).await?;
 
         // Send data
         stream.write_all(data).await?;
@@ -41,13 +106,65 @@ impl TcpTransport {
 
         Ok(buffer)
     }
+
+    /// Open a connection, send `request` as one line and close the write half, returning a
+    /// reader for the reply lines.
+    async fn send_line(
+        &self,
+        prov: &TcpProvider,
+        request: &Value,
+    ) -> Result<BufReader<Box<dyn Connection>>> {
+        let mut stream = connect(prov).await?;
+        stream.write_all(&serde_json::to_vec(request)?).await?;
+        stream.write_all(b"\n").await?;
+        stream.flush().await?;
+        stream.shutdown().await?;
+        Ok(BufReader::new(stream))
+    }
+
+    /// Ask the server for its manifest with `{"method": "list_tools"}`.
+    async fn discover_tools(&self, prov: &TcpProvider) -> Result<Vec<Tool>> {
+        let timeout = prov
+            .timeout_ms
+            .map_or(DEFAULT_DISCOVERY_TIMEOUT, Duration::from_millis);
+        let manifest = tokio::time::timeout(timeout, async {
+            let mut reader = self
+                .send_line(prov, &json!({ "method": "list_tools" }))
+                .await?;
+            read_json_line(&mut reader, None).await
+        })
+        .await
+        .map_err(|_| anyhow!("no manifest within {}ms", timeout.as_millis()))??
+        .ok_or_else(|| anyhow!("connection closed without a manifest"))?;
+
+        let tools = manifest
+            .get("tools")
+            .cloned()
+            .ok_or_else(|| anyhow!("manifest has no tools"))?;
+        Ok(serde_json::from_value(tools)?)
+    }
 }
 
 #[async_trait]
 impl ClientTransport for TcpTransport {
-    async fn register_tool_provider(&self, _prov: &dyn Provider) -> Result<Vec<Tool>> {
-        // TCP providers would define tools statically or via initial handshake
-        Ok(vec![])
+    async fn register_tool_provider(&self, prov: &dyn Provider) -> Result<Vec<Tool>> {
+        let tcp_prov = prov
+            .as_any()
+            .downcast_ref::<TcpProvider>()
+            .ok_or_else(|| anyhow!("Provider is not a TcpProvider"))?;
+
+        // Servers that do not answer discovery keep the tools declared for them
+        match self.discover_tools(tcp_prov).await {
+            Ok(tools) => Ok(tools),
+            Err(err) => {
+                tracing::debug!(
+                    provider = %tcp_prov.base.name,
+                    error = %err,
+                    "TCP tool discovery failed; using declared tools"
+                );
+                Ok(vec![])
+            }
+        }
     }
 
     async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
@@ -56,7 +173,7 @@ impl ClientTransport for TcpTransport {
 
     async fn call_tool(
         &self,
-        _tool_name: &str,
+        tool_name: &str,
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Value> {
@@ -66,20 +183,19 @@ impl ClientTransport for TcpTransport {
             .ok_or_else(|| anyhow!("Provider is not a TcpProvider"))?;
 
         let request = serde_json::to_vec(&json!({
-            "tool": _tool_name,
+            "method": "call_tool",
+            "tool": tool_name,
             "args": args
         }))?;
-        let address = format!("{}:{}", tcp_prov.host, tcp_prov.port);
-
         // Optional timeout
         let response = if let Some(timeout) = tcp_prov.timeout_ms {
             tokio::time::timeout(
                 std::time::Duration::from_millis(timeout),
-                self.send_and_receive(&address, &request),
+                self.send_and_receive(tcp_prov, &request),
             )
             .await??
         } else {
-            self.send_and_receive(&address, &request).await?
+            self.send_and_receive(tcp_prov, &request).await?
         };
 
         let result: Value = serde_json::from_slice(&response)?;
@@ -88,7 +204,7 @@ impl ClientTransport for TcpTransport {
 
     async fn call_tool_stream(
         &self,
-        _tool_name: &str,
+        tool_name: &str,
         args: HashMap<String, Value>,
         prov: &dyn Provider,
     ) -> Result<Box<dyn StreamResult>> {
@@ -97,69 +213,39 @@ impl ClientTransport for TcpTransport {
             .downcast_ref::<TcpProvider>()
             .ok_or_else(|| anyhow!("Provider is not a TcpProvider"))?;
 
-        let request = serde_json::to_vec(&json!({
-            "tool": _tool_name,
+        let request = json!({
+            "method": "call_tool_stream",
+            "tool": tool_name,
             "args": args
-        }))?;
-        let address = format!("{}:{}", tcp_prov.host, tcp_prov.port);
-        let mut stream = TcpStream::connect(address).await?;
-        stream.write_all(&request).await?;
-        stream.write_all(b"\n").await?;
-        stream.flush().await?;
-        stream.shutdown().await?;
+        });
+        let mut reader = self.send_line(tcp_prov, &request).await?;
 
+        // The stream ends at the done marker or when the server closes the connection
         let timeout = tcp_prov.timeout_ms.map(Duration::from_millis);
-        let mut reader = BufReader::new(stream);
         let (tx, rx) = mpsc::channel(32);
-
-        tokio::spawn(async move {
+        let reader_task = tokio::spawn(async move {
             loop {
-                let mut line = String::new();
-                let read_future = reader.read_line(&mut line);
-
-                let read_result = if let Some(duration) = timeout {
-                    match tokio::time::timeout(duration, read_future).await {
-                        Ok(res) => res,
-                        Err(_) => {
-                            let _ = tx.send(Err(anyhow!("TCP stream timed out"))).await;
-                            return;
-                        }
-                    }
-                } else {
-                    read_future.await
+                let item = match read_json_line(&mut reader, timeout).await {
+                    Ok(Some(item)) if is_done(&item) => return,
+                    Ok(Some(item)) => Ok(item),
+                    Ok(None) => return,
+                    Err(err) => Err(err),
                 };
-
-                match read_result {
-                    Ok(0) => return,
-                    Ok(_) => {
-                        let trimmed = line.trim();
-                        if trimmed.is_empty() {
-                            continue;
-                        }
-
-                        match serde_json::from_str::<Value>(trimmed) {
-                            Ok(value) => {
-                                if tx.send(Ok(value)).await.is_err() {
-                                    return;
-                                }
-                            }
-                            Err(err) => {
-                                let _ = tx
-                                    .send(Err(anyhow!("Failed to parse TCP stream JSON: {}", err)))
-                                    .await;
-                                return;
-                            }
-                        }
-                    }
-                    Err(err) => {
-                        let _ = tx.send(Err(anyhow!("TCP stream error: {}", err))).await;
-                        return;
-                    }
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    return;
                 }
             }
         });
 
-        Ok(boxed_channel_stream(rx, None))
+        // Closing the stream drops the connection
+        Ok(boxed_channel_stream(
+            rx,
+            Some(Box::new(move || {
+                reader_task.abort();
+                Ok(())
+            })),
+        ))
     }
 }
 
@@ -169,6 +255,7 @@ mod tests {
     use crate::providers::base::{BaseProvider, ProviderType};
     use serde_json::json;
     use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
 
     #[tokio::test]
     async fn call_tool_round_trips_over_tcp() {
@@ -198,6 +285,7 @@ mod tests {
             host: addr.ip().to_string(),
             port: addr.port(),
             timeout_ms: None,
+            tls: None,
         };
 
         let mut args = HashMap::new();
@@ -212,6 +300,104 @@ mod tests {
         assert_eq!(result.get("args"), Some(&json!(args)));
     }
 
+    fn provider(addr: std::net::SocketAddr, timeout_ms: Option<u64>) -> TcpProvider {
+        TcpProvider {
+            timeout_ms,
+            ..TcpProvider::new("tcp".to_string(), addr.ip().to_string(), addr.port(), None)
+        }
+    }
+
+    /// Accept one connection and answer its request line with `reply`, keeping the connection
+    /// open afterwards. The request is passed on once answered.
+    fn serve_lines(
+        listener: TcpListener,
+        reply: impl FnOnce(Value) -> Vec<Value> + Send + 'static,
+    ) -> oneshot::Receiver<Value> {
+        let (request_tx, request_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(socket);
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            let request: Value = serde_json::from_str(&line).unwrap();
+            for message in reply(request.clone()) {
+                let line = serde_json::to_vec(&message).unwrap();
+                reader.get_mut().write_all(&line).await.unwrap();
+                reader.get_mut().write_all(b"\n").await.unwrap();
+            }
+            let _ = request_tx.send(request);
+            // Hold the connection so only the done marker can end a stream
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+        request_rx
+    }
+
+    #[tokio::test]
+    async fn register_tool_provider_discovers_the_manifest() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        serve_lines(listener, |request| {
+            assert_eq!(request, json!({ "method": "list_tools" }));
+            vec![json!({ "tools": [{
+                "name": "count",
+                "description": "Streams numbers",
+                "inputs": { "type": "object" },
+                "outputs": { "type": "object" },
+                "tags": ["tcp"]
+            }] })]
+        });
+
+        let tools = TcpTransport::new()
+            .register_tool_provider(&provider(addr, Some(1000)))
+            .await
+            .unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "count");
+    }
+
+    #[tokio::test]
+    async fn register_tool_provider_falls_back_when_the_server_does_not_answer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        serve_lines(listener, |_| Vec::new());
+
+        let tools = TcpTransport::new()
+            .register_tool_provider(&provider(addr, Some(200)))
+            .await
+            .unwrap();
+        assert!(tools.is_empty());
+    }
+
+    #[tokio::test]
+    async fn call_tool_stream_ends_at_the_done_marker() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = serve_lines(listener, |_| {
+            vec![
+                json!({ "n": 1 }),
+                json!({ "n": 2 }),
+                json!({ "n": 3 }),
+                json!({ "$done": true }),
+            ]
+        });
+
+        let args = HashMap::from([("count".to_string(), json!(3))]);
+        let mut stream = TcpTransport::new()
+            .call_tool_stream("count", args, &provider(addr, None))
+            .await
+            .unwrap();
+        for n in 1..=3 {
+            assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": n })));
+        }
+        assert_eq!(stream.next().await.unwrap(), None);
+        stream.close().await.unwrap();
+
+        let request = request.await.unwrap();
+        assert_eq!(request["method"], "call_tool_stream");
+        assert_eq!(request["tool"], "count");
+        assert_eq!(request["args"], json!({ "count": 3 }));
+    }
+
     #[tokio::test]
     async fn call_tool_stream_reads_newline_delimited_messages() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -240,6 +426,7 @@ mod tests {
             host: addr.ip().to_string(),
             port: addr.port(),
             timeout_ms: None,
+            tls: None,
         };
 
         let mut args = HashMap::new();
@@ -256,4 +443,193 @@ mod tests {
         assert_eq!(stream.next().await.unwrap(), None);
         stream.close().await.unwrap();
     }
+
+    /// A CA with server and client certificates signed by it, written to PEM files.
+    #[cfg(feature = "tcp-tls")]
+    struct Pki {
+        ca: tempfile::NamedTempFile,
+        server_cert: tempfile::NamedTempFile,
+        server_key: tempfile::NamedTempFile,
+        client_cert: tempfile::NamedTempFile,
+        client_key: tempfile::NamedTempFile,
+    }
+
+    #[cfg(feature = "tcp-tls")]
+    impl Pki {
+        fn generate() -> Self {
+            use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+            use std::io::Write;
+
+            fn pem(contents: String) -> tempfile::NamedTempFile {
+                let mut file = tempfile::NamedTempFile::new().unwrap();
+                file.write_all(contents.as_bytes()).unwrap();
+                file
+            }
+
+            let ca_key = KeyPair::generate().unwrap();
+            let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = ca_params.self_signed(&ca_key).unwrap();
+            let leaf = |name: &str| {
+                let key = KeyPair::generate().unwrap();
+                let cert = CertificateParams::new(vec![name.to_string()])
+                    .unwrap()
+                    .signed_by(&key, &ca, &ca_key)
+                    .unwrap();
+                (pem(cert.pem()), pem(key.serialize_pem()))
+            };
+            let (server_cert, server_key) = leaf("localhost");
+            let (client_cert, client_key) = leaf("client");
+            Self {
+                ca: pem(ca.pem()),
+                server_cert,
+                server_key,
+                client_cert,
+                client_key,
+            }
+        }
+
+        fn path(file: &tempfile::NamedTempFile) -> Option<String> {
+            Some(file.path().to_str().unwrap().to_string())
+        }
+
+        /// Accept TLS connections that present a client certificate signed by the CA. Each
+        /// request is read to its end and answered like the one-shot and streaming servers.
+        async fn serve(&self) -> std::net::SocketAddr {
+            use rustls::server::WebPkiClientVerifier;
+            use rustls::{RootCertStore, ServerConfig};
+            use std::sync::Arc;
+
+            let read_certs = |file: &tempfile::NamedTempFile| {
+                rustls_pemfile::certs(&mut std::io::BufReader::new(file.reopen().unwrap()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            };
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(&self.ca) {
+                roots.add(cert).unwrap();
+            }
+            let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(
+                self.server_key.reopen().unwrap(),
+            ))
+            .unwrap()
+            .unwrap();
+            let config = ServerConfig::builder()
+                .with_client_cert_verifier(
+                    WebPkiClientVerifier::builder(Arc::new(roots))
+                        .build()
+                        .unwrap(),
+                )
+                .with_single_cert(read_certs(&self.server_cert), key)
+                .unwrap();
+            let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        let Ok(mut stream) = acceptor.accept(socket).await else {
+                            return;
+                        };
+                        let mut buf = Vec::new();
+                        stream.read_to_end(&mut buf).await.unwrap();
+                        let request: Value = serde_json::from_slice(&buf).unwrap();
+                        let replies = if request["method"] == "call_tool_stream" {
+                            vec![
+                                json!({ "n": 1 }),
+                                json!({ "n": 2 }),
+                                json!({ "$done": true }),
+                            ]
+                        } else {
+                            vec![json!({ "echo": request["args"] })]
+                        };
+                        for reply in replies {
+                            stream
+                                .write_all(&serde_json::to_vec(&reply).unwrap())
+                                .await
+                                .unwrap();
+                            stream.write_all(b"\n").await.unwrap();
+                        }
+                        stream.shutdown().await.unwrap();
+                    });
+                }
+            });
+            addr
+        }
+    }
+
+    #[cfg(feature = "tcp-tls")]
+    #[tokio::test]
+    async fn mutual_tls_carries_calls_and_streams() {
+        let pki = Pki::generate();
+        let addr = pki.serve().await;
+        let prov = TcpProvider {
+            tls: Some(crate::providers::tcp::TcpTlsConfig {
+                ca_cert_path: Pki::path(&pki.ca),
+                client_cert_path: Pki::path(&pki.client_cert),
+                client_key_path: Pki::path(&pki.client_key),
+                sni_hostname: Some("localhost".to_string()),
+                insecure_skip_verify: false,
+            }),
+            ..provider(addr, Some(5000))
+        };
+        let transport = TcpTransport::new();
+
+        let args = HashMap::from([("msg".to_string(), json!("hello"))]);
+        let result = transport.call_tool("echo", args, &prov).await.unwrap();
+        assert_eq!(result, json!({ "echo": { "msg": "hello" } }));
+
+        let mut stream = transport
+            .call_tool_stream("count", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": 1 })));
+        assert_eq!(stream.next().await.unwrap(), Some(json!({ "n": 2 })));
+        assert_eq!(stream.next().await.unwrap(), None);
+    }
+
+    #[cfg(feature = "tcp-tls")]
+    #[tokio::test]
+    async fn handshake_failures_name_the_peer() {
+        let pki = Pki::generate();
+        let addr = pki.serve().await;
+        // The server's CA is not trusted
+        let prov = TcpProvider {
+            tls: Some(crate::providers::tcp::TcpTlsConfig {
+                sni_hostname: Some("localhost".to_string()),
+                ..Default::default()
+            }),
+            ..provider(addr, Some(5000))
+        };
+
+        let err = TcpTransport::new()
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(&format!("TLS handshake with {} failed", addr)),
+            "{err}"
+        );
+        assert!(err.contains("invalid peer certificate"), "{err}");
+    }
+
+    #[cfg(not(feature = "tcp-tls"))]
+    #[tokio::test]
+    async fn tls_needs_the_feature() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let prov = TcpProvider {
+            tls: Some(Default::default()),
+            ..provider(addr, Some(1000))
+        };
+
+        let err = TcpTransport::new()
+            .call_tool("echo", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("`tcp-tls` feature"), "{err}");
+    }
 }
@@ -0,0 +1,119 @@
+//! TLS for TCP providers that set `tls`. Certificates are handled as for `wss://` connections;
+//! the handshake itself needs the `tcp-tls` feature.
+use anyhow::{anyhow, Result};
+use rustls::pki_types::ServerName;
+use rustls::ClientConfig;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+
+use super::Connection;
+use crate::providers::tcp::{TcpProvider, TcpTlsConfig};
+use crate::providers::websocket::WebSocketTlsConfig;
+use crate::transports::websocket::tls as websocket_tls;
+
+/// Everything needed to secure a connection to one provider, checked before dialing.
+#[cfg_attr(not(feature = "tcp-tls"), allow(dead_code))]
+pub(super) struct Connector {
+    config: Arc<ClientConfig>,
+    server_name: ServerName<'static>,
+    peer: String,
+}
+
+impl Connector {
+    pub(super) fn new(prov: &TcpProvider, tls: &TcpTlsConfig) -> Result<Self> {
+        Ok(Self {
+            config: Arc::new(client_config(tls)?),
+            server_name: server_name(prov, tls)?,
+            peer: format!("{}:{}", prov.host, prov.port),
+        })
+    }
+
+    /// Run the TLS handshake over `stream`.
+    #[cfg(feature = "tcp-tls")]
+    pub(super) async fn handshake(&self, stream: TcpStream) -> Result<Box<dyn Connection>> {
+        let connector = tokio_rustls::TlsConnector::from(Arc::clone(&self.config));
+        let stream = connector
+            .connect(self.server_name.clone(), stream)
+            .await
+            .map_err(|err| anyhow!("TLS handshake with {} failed: {}", self.peer, err))?;
+        Ok(Box::new(stream))
+    }
+
+    #[cfg(not(feature = "tcp-tls"))]
+    pub(super) async fn handshake(&self, _stream: TcpStream) -> Result<Box<dyn Connection>> {
+        Err(anyhow!(
+            "TLS connection to {} needs the `tcp-tls` feature",
+            self.peer
+        ))
+    }
+}
+
+/// Build the rustls client configuration described by `tls`.
+fn client_config(tls: &TcpTlsConfig) -> Result<ClientConfig> {
+    websocket_tls::client_config(&WebSocketTlsConfig {
+        ca_cert_path: tls.ca_cert_path.clone(),
+        client_cert_path: tls.client_cert_path.clone(),
+        client_key_path: tls.client_key_path.clone(),
+        insecure_skip_verify: tls.insecure_skip_verify,
+    })
+}
+
+/// The name the server certificate must match: `sni_hostname`, or else the provider's host.
+fn server_name(prov: &TcpProvider, tls: &TcpTlsConfig) -> Result<ServerName<'static>> {
+    let name = tls.sni_hostname.as_deref().unwrap_or(&prov.host);
+    ServerName::try_from(name.to_string())
+        .map_err(|err| anyhow!("Invalid TLS server name '{}': {}", name, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(host: &str) -> TcpProvider {
+        TcpProvider::new("secure".to_string(), host.to_string(), 9443, None)
+    }
+
+    #[test]
+    fn server_name_defaults_to_the_host() {
+        let tls = TcpTlsConfig::default();
+        let name = server_name(&provider("tools.internal"), &tls).unwrap();
+        assert_eq!(name, ServerName::try_from("tools.internal").unwrap());
+
+        let tls = TcpTlsConfig {
+            sni_hostname: Some("override.internal".to_string()),
+            ..Default::default()
+        };
+        let name = server_name(&provider("10.0.0.5"), &tls).unwrap();
+        assert_eq!(name, ServerName::try_from("override.internal").unwrap());
+    }
+
+    #[test]
+    fn config_errors_are_reported() {
+        let tls = TcpTlsConfig {
+            sni_hostname: Some("not a host name".to_string()),
+            ..Default::default()
+        };
+        let err = Connector::new(&provider("localhost"), &tls)
+            .err()
+            .expect("invalid server name");
+        assert!(
+            err.to_string()
+                .contains("Invalid TLS server name 'not a host name'"),
+            "{err}"
+        );
+
+        let tls = TcpTlsConfig {
+            client_key_path: Some("/tmp/client.key".to_string()),
+            ..Default::default()
+        };
+        let err = format!("{:#}", client_config(&tls).unwrap_err());
+        assert!(err.contains("must be set together"), "{err}");
+
+        let tls = TcpTlsConfig {
+            ca_cert_path: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        };
+        let err = format!("{:#}", client_config(&tls).unwrap_err());
+        assert!(err.contains("Failed to open /nonexistent/ca.pem"), "{err}");
+    }
+}
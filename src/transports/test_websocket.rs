@@ -0,0 +1,30 @@
+//! WebSocket handshakes for the test servers of transports that signal over WebSockets.
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::WebSocketStream;
+
+/// Accept one WebSocket connection on `stream`, letting `inspect` read the upgrade request and
+/// add headers to the response.
+pub(crate) async fn accept<F>(stream: TcpStream, inspect: F) -> WebSocketStream<TcpStream>
+where
+    F: FnOnce(&Request, &mut Response) + Unpin,
+{
+    tokio_tungstenite::accept_hdr_async(stream, Handshake(inspect))
+        .await
+        .unwrap()
+}
+
+struct Handshake<F>(F);
+
+impl<F: FnOnce(&Request, &mut Response)> Callback for Handshake<F> {
+    fn on_request(
+        self,
+        request: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        (self.0)(request, &mut response);
+        Ok(response)
+    }
+}
@@ -0,0 +1,334 @@
+//! Finding and merging the manuals a text provider lists in `path` and `paths`. Each entry is
+//! a manual file, a directory of manuals, or a glob such as `manuals/*.yaml` or
+//! `manuals/**/*.json`.
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::read_manual;
+use crate::errors::ManualFailure;
+use crate::security::validate_file_path;
+use crate::tools::Tool;
+
+/// Extensions of the files a manual directory contributes.
+const MANUAL_EXTENSIONS: &[&str] = &["json", "yaml", "yml"];
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// A regex matching the `/`-separated paths `pattern` matches: `*` and `?` stay within one
+/// path segment, and `**/` matches any number of directories.
+fn glob_regex(pattern: &str) -> Result<Regex> {
+    let mut regex = String::from("^");
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            regex.push_str("(?:.*/)?");
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = after;
+            continue;
+        }
+        match c {
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| anyhow!("Invalid manual pattern '{}': {}", pattern, e))
+}
+
+/// The manuals directly inside `dir`, by name.
+fn manuals_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut manuals = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_manual = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MANUAL_EXTENSIONS.contains(&ext));
+        if is_manual && path.is_file() {
+            manuals.push(path);
+        }
+    }
+    manuals.sort();
+    Ok(manuals)
+}
+
+/// Collect the files below `dir`, at most `depth` levels down, whose path relative to `root`
+/// matches `matcher`.
+fn walk(
+    root: &Path,
+    dir: &Path,
+    depth: Option<usize>,
+    matcher: &Regex,
+    found: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if depth == Some(0) {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, depth.map(|d| d - 1), matcher, found)?;
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        if matcher.is_match(&relative.join("/")) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The manual files `source` names: itself, the manuals in it when it is a directory, or the
+/// files matching it when it is a glob.
+pub(crate) fn expand(source: &Path) -> Result<Vec<PathBuf>> {
+    let text = source
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid path encoding"))?;
+    if !is_glob(text) {
+        if source.is_dir() {
+            return manuals_in(source);
+        }
+        return Ok(vec![source.to_path_buf()]);
+    }
+
+    // Walk from the deepest directory named without wildcards
+    let mut root = PathBuf::new();
+    let mut pattern = Vec::new();
+    for component in source.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if pattern.is_empty() && !is_glob(&part) {
+            root.push(component);
+        } else {
+            pattern.push(part.into_owned());
+        }
+    }
+    if root.as_os_str().is_empty() {
+        root.push(".");
+    }
+    let depth = if pattern.iter().any(|part| part.contains("**")) {
+        None
+    } else {
+        Some(pattern.len())
+    };
+    let matcher = glob_regex(&pattern.join("/"))?;
+
+    let mut found = Vec::new();
+    if root.is_dir() {
+        walk(&root, &root, depth, &matcher, &mut found)?;
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// The tools of a set of manuals, and the files that could not contribute theirs.
+#[derive(Debug, Default)]
+pub(super) struct LoadedManuals {
+    pub tools: Vec<Tool>,
+    pub failures: Vec<ManualFailure>,
+}
+
+async fn load_manual(path: &Path, allowed_base: Option<&Path>) -> Result<Vec<Tool>> {
+    // Validate path is safe
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid path encoding"))?;
+    let base_str = allowed_base.and_then(|p| p.to_str());
+    validate_file_path(path_str, base_str)?;
+
+    read_manual(path).await
+}
+
+/// Load and merge the manuals `sources` name, each file at most once and in order. A file
+/// that fails to load is recorded and skipped, and so is a tool whose name an earlier file
+/// already defined. Files must lie within `allowed_base` when it is set.
+pub(super) async fn load_manuals(
+    sources: &[PathBuf],
+    allowed_base: Option<&Path>,
+) -> LoadedManuals {
+    let mut loaded = LoadedManuals::default();
+    let mut seen = HashSet::new();
+    // Map of tool name to the file that defined it
+    let mut origins: HashMap<String, PathBuf> = HashMap::new();
+    for source in sources {
+        let files = match expand(source) {
+            Ok(files) if files.is_empty() => {
+                loaded.failures.push(ManualFailure {
+                    path: source.display().to_string(),
+                    error: "no manual matches this pattern".to_string(),
+                });
+                continue;
+            }
+            Ok(files) => files,
+            Err(err) => {
+                loaded.failures.push(ManualFailure {
+                    path: source.display().to_string(),
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        for file in files {
+            if !seen.insert(file.clone()) {
+                continue;
+            }
+            let tools = match load_manual(&file, allowed_base).await {
+                Ok(tools) => tools,
+                Err(err) => {
+                    loaded.failures.push(ManualFailure {
+                        path: file.display().to_string(),
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+            for tool in tools {
+                if let Some(first) = origins.get(&tool.name) {
+                    loaded.failures.push(ManualFailure {
+                        path: file.display().to_string(),
+                        error: format!(
+                            "tool '{}' is already defined in {}",
+                            tool.name,
+                            first.display()
+                        ),
+                    });
+                    continue;
+                }
+                origins.insert(tool.name.clone(), file.clone());
+                loaded.tools.push(tool);
+            }
+        }
+    }
+    loaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn write_manual(path: &Path, names: &[&str]) {
+        let tools: Vec<_> = names
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "description": "",
+                    "inputs": {"type": "object"},
+                    "outputs": {"type": "object"},
+                    "tags": []
+                })
+            })
+            .collect();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, json!({ "tools": tools }).to_string()).unwrap();
+    }
+
+    fn names(tools: &[Tool]) -> Vec<&str> {
+        tools.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn glob_regex_keeps_wildcards_within_segments() {
+        let single = glob_regex("manuals/*.json").unwrap();
+        assert!(single.is_match("manuals/a.json"));
+        assert!(!single.is_match("manuals/nested/a.json"));
+        assert!(!single.is_match("manuals/a.jsonl"));
+
+        let nested = glob_regex("**/tool?.json").unwrap();
+        assert!(nested.is_match("tool1.json"));
+        assert!(nested.is_match("a/b/tool2.json"));
+        assert!(!nested.is_match("a/tool10.json"));
+    }
+
+    #[test]
+    fn expand_lists_directories_and_globs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_manual(&root.join("b.json"), &[]);
+        write_manual(&root.join("a.json"), &[]);
+        write_manual(&root.join("nested/c.json"), &[]);
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+
+        assert_eq!(
+            expand(root).unwrap(),
+            vec![root.join("a.json"), root.join("b.json")]
+        );
+        assert_eq!(
+            expand(&root.join("*.json")).unwrap(),
+            vec![root.join("a.json"), root.join("b.json")]
+        );
+        assert_eq!(
+            expand(&root.join("**/*.json")).unwrap(),
+            vec![
+                root.join("a.json"),
+                root.join("b.json"),
+                root.join("nested/c.json")
+            ]
+        );
+        assert!(expand(&root.join("missing/*.json")).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn load_manuals_keeps_good_files_and_reports_bad_ones() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_manual(&root.join("manuals/one.json"), &["alpha", "beta"]);
+        write_manual(&root.join("manuals/two.json"), &["gamma", "alpha"]);
+        std::fs::write(root.join("manuals/three.json"), "{ not json").unwrap();
+
+        let loaded = load_manuals(&[root.join("manuals/*.json")], Some(root)).await;
+        assert_eq!(names(&loaded.tools), vec!["alpha", "beta", "gamma"]);
+
+        let failures: Vec<_> = loaded
+            .failures
+            .iter()
+            .map(|f| (f.path.as_str(), f.error.as_str()))
+            .collect();
+        assert_eq!(failures.len(), 2);
+        let three = root.join("manuals/three.json").display().to_string();
+        assert_eq!(failures[0].0, three);
+        assert!(failures[0].1.contains("Failed to parse manual"));
+        let two = root.join("manuals/two.json").display().to_string();
+        assert_eq!(failures[1].0, two);
+        assert!(failures[1].1.contains("tool 'alpha' is already defined in"));
+        assert!(failures[1].1.contains("one.json"));
+    }
+
+    #[tokio::test]
+    async fn load_manuals_reports_unmatched_patterns_and_skips_repeats() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_manual(&root.join("one.json"), &["alpha"]);
+
+        let sources = [
+            root.join("one.json"),
+            root.to_path_buf(),
+            root.join("none/*.json"),
+        ];
+        let loaded = load_manuals(&sources, None).await;
+        assert_eq!(names(&loaded.tools), vec!["alpha"]);
+        assert_eq!(loaded.failures.len(), 1);
+        assert_eq!(
+            loaded.failures[0].path,
+            root.join("none/*.json").display().to_string()
+        );
+    }
+}
@@ -1,20 +1,91 @@
 // Text Transport (for file-based tool definitions and execution)
-use crate::providers::base::Provider;
-use crate::providers::text::TextProvider;
+use crate::auth::AuthConfig;
+use crate::call_templates::call_template_to_provider;
+use crate::errors::UtcpError;
+use crate::loader::{create_provider_from_value, parse_document};
+use crate::providers::base::{Provider, ProviderType};
+use crate::providers::text::{TextProvider, TextSource};
 use crate::security::validate_file_path;
 use crate::tools::Tool;
-use crate::transports::{stream::StreamResult, ClientTransport};
+use crate::transports::http_client::SharedClient;
+use crate::transports::registry::{communication_protocols_snapshot, WeakProtocolRegistry};
+use crate::transports::{
+    stream::StreamResult, ClientTransport, CommunicationProtocol, ToolsChangedCallback,
+    ToolsChangedListeners,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use reqwest::header;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::fs;
 use tokio::process::Command;
 
-/// Transport that loads tools from a directory and executes scripts locally.
+pub(crate) mod manuals;
+mod watch;
+
+use manuals::{load_manuals, LoadedManuals};
+use watch::Watcher;
+
+/// The provider and protocol a call template names, and the tool name to call.
+type TemplateTarget = (Arc<dyn Provider>, Arc<dyn CommunicationProtocol>, String);
+
+/// Manuals a provider without `path` or `paths` reads from its `base_path`, in order of
+/// preference.
+const DEFAULT_MANUALS: &[&str] = &["tools.json", "tools.yaml", "tools.yml"];
+
+/// The tools of a manual: an array of tools, or a UTCP manual with a `tools` list. Entries that
+/// are not tools are skipped, and other documents hold no tools. A tool's `tool_call_template`
+/// is kept as its `tool_provider`.
+fn manual_tools(manifest: Value) -> Vec<Tool> {
+    let entries = match manifest {
+        Value::Array(entries) => entries,
+        Value::Object(mut manual) => match manual.remove("tools") {
+            Some(Value::Array(entries)) => entries,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            let template = entry.get("tool_call_template").cloned();
+            if let Some(obj) = entry.as_object_mut() {
+                obj.entry("tags")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+            }
+            let mut tool: Tool = serde_json::from_value(entry).ok()?;
+            if tool.provider.is_none() {
+                tool.provider = template;
+            }
+            Some(tool)
+        })
+        .collect()
+}
+
+/// Read the tools of a manual file written in JSON or YAML. A file that does not parse is an
+/// error.
+async fn read_manual(path: &Path) -> Result<Vec<Tool>> {
+    let contents = fs::read_to_string(path).await?;
+    let manifest = parse_document(path, &contents)
+        .map_err(|e| anyhow!("Failed to parse manual {}: {}", path.display(), e))?;
+    Ok(manual_tools(manifest))
+}
+
+/// Transport that loads tools from manual files, URLs or inline manuals. Tools that carry a
+/// call template are called through it; others run as scripts in the base directory.
 pub struct TextTransport {
     base_path: Option<PathBuf>,
+    client: SharedClient,
+    // Where call templates find the protocols that carry them out
+    protocols: WeakProtocolRegistry,
+    // Map of provider name to the call templates of its tools, by tool name
+    call_templates: Mutex<HashMap<String, HashMap<String, Value>>>,
+    tools_changed: ToolsChangedListeners,
+    // Map of provider name to the watch over its manual, for providers with `watch` set
+    watchers: Mutex<HashMap<String, Watcher>>,
 }
 
 enum ScriptKind {
@@ -27,7 +98,25 @@ enum ScriptKind {
 impl TextTransport {
     /// Create a text transport without a default base path.
     pub fn new() -> Self {
-        Self { base_path: None }
+        Self::with_client(SharedClient::default())
+    }
+
+    /// Create a text transport that fetches remote manuals through `shared`.
+    pub fn with_client(shared: SharedClient) -> Self {
+        Self {
+            base_path: None,
+            client: shared,
+            protocols: WeakProtocolRegistry::default(),
+            call_templates: Mutex::new(HashMap::new()),
+            tools_changed: ToolsChangedListeners::default(),
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Call templates through the protocols of `protocols` instead of the global registry.
+    pub(crate) fn with_protocols(mut self, protocols: WeakProtocolRegistry) -> Self {
+        self.protocols = protocols;
+        self
     }
 
     /// Configure a base directory that holds tool scripts and manifests.
@@ -36,35 +125,182 @@ impl TextTransport {
         self
     }
 
-    async fn load_tools_from_file(&self, path: &PathBuf) -> Result<Vec<Tool>> {
-        // Validate path is safe
-        let path_str = path
-            .to_str()
-            .ok_or_else(|| anyhow!("Invalid path encoding"))?;
-        let base_str = self.base_path.as_ref().and_then(|p| p.to_str());
-        validate_file_path(path_str, base_str)?;
+    /// Attach authentication headers or query params to the request builder.
+    fn apply_auth(
+        &self,
+        builder: reqwest::RequestBuilder,
+        auth: &AuthConfig,
+    ) -> Result<reqwest::RequestBuilder> {
+        match auth {
+            AuthConfig::ApiKey(api_key) => {
+                let location = api_key.location.to_ascii_lowercase();
+                match location.as_str() {
+                    "header" => Ok(builder.header(&api_key.var_name, &api_key.api_key)),
+                    "query" => {
+                        Ok(builder.query(&[(api_key.var_name.clone(), api_key.api_key.clone())]))
+                    }
+                    "cookie" => {
+                        let cookie_value = format!("{}={}", api_key.var_name, api_key.api_key);
+                        Ok(builder.header(header::COOKIE, cookie_value))
+                    }
+                    other => Err(anyhow!("Unsupported API key location: {}", other)),
+                }
+            }
+            AuthConfig::Basic(basic) => {
+                Ok(builder.basic_auth(&basic.username, Some(&basic.password)))
+            }
+            AuthConfig::OAuth2(_) => Err(anyhow!(
+                "OAuth2 auth is not yet supported for text manual URLs"
+            )),
+        }
+    }
 
-        let contents = fs::read_to_string(path).await?;
+    /// Fetch the manual served at `url`, read as YAML when its path ends in `.yaml` or `.yml`
+    /// and as JSON otherwise.
+    async fn fetch_manual(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        auth: Option<&AuthConfig>,
+    ) -> Result<Vec<Tool>> {
+        let mut request = self.client.client().get(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(auth) = auth {
+            request = self.apply_auth(request, auth)?;
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch manual {}: {}", url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Failed to fetch manual {}: HTTP {}", url, status));
+        }
+        let body = response.text().await?;
+
+        let path = reqwest::Url::parse(url)
+            .map(|url| PathBuf::from(url.path()))
+            .unwrap_or_default();
+        let manifest = parse_document(&path, &body)
+            .map_err(|e| anyhow!("Failed to parse manual {}: {}", url, e))?;
+        Ok(manual_tools(manifest))
+    }
+
+    /// Load the manual files `file` (or the provider's `path`) and `paths` name, or the
+    /// default manual in the base directory when there are none, and watch them for providers
+    /// with `watch` set.
+    async fn load_file_manuals(
+        &self,
+        text_prov: Option<&TextProvider>,
+        file: Option<&String>,
+    ) -> LoadedManuals {
+        let base_path = text_prov
+            .and_then(|p| p.base_path.clone())
+            .or_else(|| self.base_path.clone());
+
+        let listed: Vec<&String> = file
+            .into_iter()
+            .chain(text_prov.map(|p| &p.paths).into_iter().flatten())
+            .collect();
+        let sources: Vec<PathBuf> = if listed.is_empty() {
+            let Some(base_path) = &base_path else {
+                return LoadedManuals::default();
+            };
+            let manual = DEFAULT_MANUALS
+                .iter()
+                .map(|name| base_path.join(name))
+                .find(|path| path.exists())
+                .unwrap_or_else(|| base_path.join(DEFAULT_MANUALS[0]));
+            vec![manual]
+        } else {
+            listed
+                .iter()
+                .map(|path| match &base_path {
+                    Some(base_path) => base_path.join(path),
+                    None => PathBuf::from(path),
+                })
+                .collect()
+        };
 
-        // Try to parse as JSON array of tools
-        if let Ok(tools) = serde_json::from_str::<Vec<Tool>>(&contents) {
-            return Ok(tools);
+        // A provider without manuals of its own has no tools until a default manual appears
+        let loaded = if listed.is_empty() && !sources[0].exists() {
+            LoadedManuals::default()
+        } else {
+            load_manuals(&sources, self.base_path.as_deref()).await
+        };
+        if let Some(prov) = text_prov.filter(|p| p.watch) {
+            self.watch_manuals(&prov.base.name, sources, &loaded.tools)
+                .await;
         }
+        loaded
+    }
 
-        // Try to parse as UTCP manifest
-        if let Ok(manifest) = serde_json::from_str::<Value>(&contents) {
-            if let Some(tools_array) = manifest.get("tools").and_then(|v| v.as_array()) {
-                let mut tools = Vec::new();
-                for tool_value in tools_array {
-                    if let Ok(tool) = serde_json::from_value::<Tool>(tool_value.clone()) {
-                        tools.push(tool);
-                    }
+    /// The provider and protocol that carry out `tool_name` of `provider`, and the name to call
+    /// it by, when the tool came with a call template.
+    fn template_target(&self, provider: &str, tool_name: &str) -> Result<Option<TemplateTarget>> {
+        let template = {
+            let templates = self.call_templates.lock().unwrap();
+            templates
+                .get(provider)
+                .and_then(|tools| tools.get(tool_name))
+                .cloned()
+        };
+        let Some(template) = template else {
+            return Ok(None);
+        };
+
+        // v0.1 manuals describe the provider itself rather than a call template
+        let provider_value = if template.get("call_template_type").is_some() {
+            call_template_to_provider(template)?
+        } else {
+            template
+        };
+        let target = create_provider_from_value(provider_value, 0)?;
+        let provider_type = target.type_();
+        let protocol = self
+            .protocols
+            .get(provider_type.as_key())
+            .or_else(|| communication_protocols_snapshot().get(provider_type.as_key()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "No communication protocol found for call template of tool '{}': {:?}",
+                    tool_name,
+                    provider_type
+                )
+            })?;
+        // Named as the client names tools of that provider type
+        let call_name = match provider_type {
+            ProviderType::Mcp | ProviderType::Text => tool_name.to_string(),
+            _ => format!("{}.{}", target.name(), tool_name),
+        };
+        Ok(Some((target, protocol, call_name)))
+    }
+
+    /// Keep watching the manuals `sources` name for `provider`, unless a watch over them is
+    /// already running.
+    async fn watch_manuals(&self, provider: &str, sources: Vec<PathBuf>, tools: &[Tool]) {
+        {
+            let watchers = self.watchers.lock().unwrap();
+            if let Some(watcher) = watchers.get(provider) {
+                if watcher.sources == sources && watcher.is_running() {
+                    return;
                 }
-                return Ok(tools);
             }
         }
-
-        Ok(vec![])
+        let watcher = Watcher::spawn(
+            provider.to_string(),
+            sources,
+            self.base_path.clone(),
+            tools.to_vec(),
+            self.tools_changed.clone(),
+        )
+        .await;
+        self.watchers
+            .lock()
+            .unwrap()
+            .insert(provider.to_string(), watcher);
     }
 
     fn resolve_script(&self, base_path: &Path, tool_name: &str) -> Option<(ScriptKind, PathBuf)> {
@@ -119,23 +355,58 @@ impl TextTransport {
 #[async_trait]
 impl ClientTransport for TextTransport {
     async fn register_tool_provider(&self, _prov: &dyn Provider) -> Result<Vec<Tool>> {
-        // Load tools from text file
-        let base_path = _prov
-            .as_any()
-            .downcast_ref::<TextProvider>()
-            .and_then(|p| p.base_path.clone())
-            .or_else(|| self.base_path.clone());
+        let text_prov = _prov.as_any().downcast_ref::<TextProvider>();
+        let source = text_prov.and_then(|p| p.source.as_ref());
+        if let Some(prov) = text_prov.filter(|p| p.source.is_some()) {
+            let is_file = matches!(source, Some(TextSource::File { .. }));
+            if prov.path.is_some() || (!is_file && !prov.paths.is_empty()) {
+                return Err(UtcpError::Config(format!(
+                    "Text provider '{}' sets `source` along with `path` or `paths`; use `source` alone, or with `paths` for a file source",
+                    prov.base.name
+                ))
+                .into());
+            }
+        }
+
+        let loaded = match source {
+            Some(TextSource::Url { url, headers, auth }) => LoadedManuals {
+                tools: self.fetch_manual(url, headers, auth.as_ref()).await?,
+                ..Default::default()
+            },
+            Some(TextSource::Inline { inline }) => LoadedManuals {
+                tools: manual_tools(inline.clone()),
+                ..Default::default()
+            },
+            Some(TextSource::File { file }) => self.load_file_manuals(text_prov, Some(file)).await,
+            None => {
+                let path = text_prov.and_then(|p| p.path.as_ref());
+                self.load_file_manuals(text_prov, path).await
+            }
+        };
 
-        if let Some(base_path) = base_path {
-            let tools_file = base_path.join("tools.json");
-            if tools_file.exists() {
-                return self.load_tools_from_file(&tools_file).await;
+        let templates: HashMap<String, Value> = loaded
+            .tools
+            .iter()
+            .filter_map(|tool| Some((tool.name.clone(), tool.provider.clone()?)))
+            .collect();
+        self.call_templates
+            .lock()
+            .unwrap()
+            .insert(_prov.name(), templates);
+
+        if !loaded.failures.is_empty() {
+            return Err(UtcpError::ManualErrors {
+                failures: loaded.failures,
+                tools: loaded.tools,
             }
+            .into());
         }
-        Ok(vec![])
+        Ok(loaded.tools)
     }
 
     async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+        self.watchers.lock().unwrap().remove(&_prov.name());
+        self.call_templates.lock().unwrap().remove(&_prov.name());
         Ok(())
     }
 
@@ -145,6 +416,12 @@ impl ClientTransport for TextTransport {
         args: HashMap<String, Value>,
         _prov: &dyn Provider,
     ) -> Result<Value> {
+        if let Some((target, protocol, call_name)) =
+            self.template_target(&_prov.name(), tool_name)?
+        {
+            return protocol.call_tool(&call_name, args, target.as_ref()).await;
+        }
+
         let base_path = _prov
             .as_any()
             .downcast_ref::<TextProvider>()
@@ -198,8 +475,19 @@ impl ClientTransport for TextTransport {
         _args: HashMap<String, Value>,
         _prov: &dyn Provider,
     ) -> Result<Box<dyn StreamResult>> {
+        if let Some((target, protocol, call_name)) =
+            self.template_target(&_prov.name(), _tool_name)?
+        {
+            return protocol
+                .call_tool_stream(&call_name, _args, target.as_ref())
+                .await;
+        }
         Err(anyhow!("Streaming not supported by Text transport"))
     }
+
+    fn on_tools_changed(&self, callback: &ToolsChangedCallback) {
+        self.tools_changed.add(callback);
+    }
 }
 
 #[cfg(test)]
@@ -326,4 +614,296 @@ mod tests {
             .expect("stream error");
         assert!(err.to_string().contains("Streaming not supported"));
     }
+
+    fn write_manual(path: &Path, names: &[&str]) {
+        let tools: Vec<Value> = names
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "description": "",
+                    "inputs": { "type": "object" },
+                    "outputs": { "type": "object" },
+                    "tags": []
+                })
+            })
+            .collect();
+        stdfs::write(path, json!({ "tools": tools }).to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn register_reads_yaml_manual() {
+        let temp_dir = tempdir().unwrap();
+        stdfs::write(
+            temp_dir.path().join("tools.yaml"),
+            r#"
+tools:
+  - name: sample
+    description: sample tool
+    inputs: { type: object }
+    outputs: { type: object }
+    tags: [demo]
+"#,
+        )
+        .unwrap();
+
+        let transport = TextTransport::new().with_base_path(temp_dir.path().to_path_buf());
+        let tools = transport
+            .register_tool_provider(&MockProvider)
+            .await
+            .unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "sample");
+        assert_eq!(tools[0].tags, vec!["demo"]);
+
+        stdfs::write(temp_dir.path().join("tools.yaml"), "tools:\n  - name: [x\n").unwrap();
+        let err = transport
+            .register_tool_provider(&MockProvider)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("tools.yaml"), "{}", err);
+        assert!(err.contains("invalid YAML"), "{}", err);
+        assert!(err.contains("line "), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn register_merges_manual_directory_and_reports_bad_files() {
+        let temp_dir = tempdir().unwrap();
+        let manuals = temp_dir.path().join("manuals");
+        stdfs::create_dir(&manuals).unwrap();
+        write_manual(&manuals.join("a.json"), &["first", "second"]);
+        write_manual(&manuals.join("b.json"), &["third"]);
+        stdfs::write(manuals.join("c.json"), "[{ broken").unwrap();
+
+        let mut provider = TextProvider::new(
+            "catalog".to_string(),
+            Some(temp_dir.path().to_path_buf()),
+            None,
+        );
+        provider.path = Some("manuals".to_string());
+
+        let transport = TextTransport::new();
+        let err = transport
+            .register_tool_provider(&provider)
+            .await
+            .expect_err("malformed manual");
+        let message = err.to_string();
+        assert!(message.contains("c.json"), "{}", message);
+        assert!(!message.contains("a.json"), "{}", message);
+        match err.downcast::<UtcpError>() {
+            Ok(UtcpError::ManualErrors { failures, tools }) => {
+                assert_eq!(failures.len(), 1);
+                let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+                assert_eq!(names, vec!["first", "second", "third"]);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // Listing only the good manuals registers cleanly
+        provider.path = Some("manuals/a.json".to_string());
+        provider.paths = vec!["manuals/b*.json".to_string()];
+        let tools = transport.register_tool_provider(&provider).await.unwrap();
+        assert_eq!(tools.len(), 3);
+    }
+
+    /// Serves a manual at `/manual.json` to requests carrying the `X-Key: secret` and
+    /// `X-Team: tools` headers, and echoes the body of requests to `/echo`.
+    async fn spawn_manual_server() -> String {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Request, Response, Server, StatusCode};
+        use std::convert::Infallible;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let echo_url = format!("{}/echo", base);
+        let make_svc = make_service_fn(move |_| {
+            let echo_url = echo_url.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let echo_url = echo_url.clone();
+                    async move {
+                        let header = |name: &str| {
+                            req.headers()
+                                .get(name)
+                                .and_then(|v| v.to_str().ok())
+                                .map(str::to_string)
+                        };
+                        let response = match req.uri().path() {
+                            "/manual.json"
+                                if header("x-key").as_deref() == Some("secret")
+                                    && header("x-team").as_deref() == Some("tools") =>
+                            {
+                                let manual = json!({
+                                    "manual_version": "1.0.0",
+                                    "utcp_version": "1.0.0",
+                                    "tools": [{
+                                        "name": "echo",
+                                        "description": "Echo the arguments",
+                                        "inputs": { "type": "object" },
+                                        "outputs": { "type": "object" },
+                                        "tool_call_template": {
+                                            "call_template_type": "http",
+                                            "name": "echo_api",
+                                            "url": echo_url,
+                                            "http_method": "POST"
+                                        }
+                                    }]
+                                });
+                                Response::new(Body::from(manual.to_string()))
+                            }
+                            "/manual.json" => Response::builder()
+                                .status(StatusCode::UNAUTHORIZED)
+                                .body(Body::empty())
+                                .unwrap(),
+                            "/echo" => {
+                                let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                                Response::new(Body::from(body))
+                            }
+                            _ => Response::builder()
+                                .status(StatusCode::NOT_FOUND)
+                                .body(Body::empty())
+                                .unwrap(),
+                        };
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        tokio::spawn(async move {
+            Server::from_tcp(listener)
+                .unwrap()
+                .serve(make_svc)
+                .await
+                .unwrap();
+        });
+        base
+    }
+
+    fn text_provider(source: Value) -> TextProvider {
+        serde_json::from_value(json!({
+            "name": "remote",
+            "provider_type": "text",
+            "source": source
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn register_fetches_url_manual_and_calls_its_templates() {
+        let base = spawn_manual_server().await;
+        let provider = text_provider(json!({
+            "url": format!("{}/manual.json", base),
+            "headers": { "X-Team": "tools" },
+            "auth": {
+                "auth_type": "api_key",
+                "api_key": "secret",
+                "var_name": "X-Key",
+                "location": "header"
+            }
+        }));
+
+        let transport = TextTransport::new();
+        let tools = transport.register_tool_provider(&provider).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+        assert!(tools[0].tags.is_empty());
+
+        let mut args = HashMap::new();
+        args.insert("message".to_string(), json!("hi"));
+        let result = transport.call_tool("echo", args, &provider).await.unwrap();
+        assert_eq!(result, json!({ "message": "hi" }));
+
+        // Without the auth the server refuses, and a missing manual is reported the same way
+        let unauthorized = text_provider(json!({
+            "url": format!("{}/manual.json", base),
+            "headers": { "X-Team": "tools" }
+        }));
+        let err = transport
+            .register_tool_provider(&unauthorized)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&format!("{}/manual.json", base)), "{}", err);
+        assert!(err.contains("401"), "{}", err);
+
+        let missing = text_provider(json!({ "url": format!("{}/missing.yaml", base) }));
+        let err = transport
+            .register_tool_provider(&missing)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/missing.yaml"), "{}", err);
+        assert!(err.contains("404"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn register_reads_inline_manual() {
+        let provider = text_provider(json!({
+            "inline": {
+                "tools": [{
+                    "name": "greet",
+                    "description": "Greet",
+                    "inputs": { "type": "object" },
+                    "outputs": { "type": "object" },
+                    "tool_call_template": {
+                        "call_template_type": "cli",
+                        "name": "greeter",
+                        "command_name": "echo '{\"greeting\": \"hello\"}'",
+                        "args_template": []
+                    }
+                }, {
+                    "name": "not a tool"
+                }]
+            }
+        }));
+
+        let transport = TextTransport::new();
+        let tools = transport.register_tool_provider(&provider).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "greet");
+
+        let result = transport
+            .call_tool("greet", HashMap::new(), &provider)
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "greeting": "hello" }));
+
+        // Tools without a template still run as scripts
+        let err = transport
+            .call_tool("other", HashMap::new(), &provider)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("requires base_path"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn register_reads_file_source_and_rejects_mixed_sources() {
+        let temp_dir = tempdir().unwrap();
+        write_manual(&temp_dir.path().join("a.json"), &["first"]);
+        write_manual(&temp_dir.path().join("b.json"), &["second"]);
+
+        let mut provider = text_provider(json!({ "file": "a.json" }));
+        provider.base_path = Some(temp_dir.path().to_path_buf());
+        provider.paths = vec!["b.json".to_string()];
+        let transport = TextTransport::new();
+        let tools = transport.register_tool_provider(&provider).await.unwrap();
+        let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+
+        provider.path = Some("b.json".to_string());
+        let err = transport
+            .register_tool_provider(&provider)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Config(_))),
+            "{}",
+            err
+        );
+
+        let mut inline = text_provider(json!({ "inline": [] }));
+        inline.paths = vec!["b.json".to_string()];
+        assert!(transport.register_tool_provider(&inline).await.is_err());
+    }
 }
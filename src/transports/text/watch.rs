@@ -0,0 +1,181 @@
+//! Reloading the manuals of a provider with `watch` set whenever their files change.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use super::manuals::{expand, load_manuals};
+use crate::tools::Tool;
+use crate::transports::ToolsChangedListeners;
+
+/// How often a watched manual is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the manuals `sources` name looked like on disk: each file with its modification time
+/// and size, or `None` while it does not exist. Files appearing or going away count as well.
+async fn fingerprint(sources: &[PathBuf]) -> Vec<(PathBuf, Option<(SystemTime, u64)>)> {
+    let mut seen = Vec::new();
+    for path in sources
+        .iter()
+        .flat_map(|source| expand(source).unwrap_or_default())
+    {
+        let stamp = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata.modified().ok().map(|m| (m, metadata.len())),
+            Err(_) => None,
+        };
+        seen.push((path, stamp));
+    }
+    seen
+}
+
+/// How a manual's tools changed between two reads, by tool name.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct ToolDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ToolDiff {
+    pub(super) fn between(old: &[Tool], new: &[Tool]) -> Self {
+        let definitions = |tools: &[Tool]| -> HashMap<String, serde_json::Value> {
+            tools
+                .iter()
+                .map(|tool| {
+                    let definition = serde_json::to_value(tool).unwrap_or_default();
+                    (tool.name.clone(), definition)
+                })
+                .collect()
+        };
+        let old = definitions(old);
+        let new = definitions(new);
+
+        let mut diff = Self::default();
+        for (name, definition) in &new {
+            match old.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(previous) if previous != definition => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A running watch over one provider's manuals.
+pub(super) struct Watcher {
+    pub sources: Vec<PathBuf>,
+    task: JoinHandle<()>,
+}
+
+impl Watcher {
+    /// Watch the manuals `sources` name, starting from the `tools` just registered, and tell
+    /// `listeners` about `provider` whenever their tools change. While any manual fails to
+    /// load, the failures are reported and the previous tools kept.
+    pub(super) async fn spawn(
+        provider: String,
+        sources: Vec<PathBuf>,
+        allowed_base: Option<PathBuf>,
+        mut tools: Vec<Tool>,
+        listeners: ToolsChangedListeners,
+    ) -> Self {
+        let mut last_seen = fingerprint(&sources).await;
+        let watched = sources.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                let seen = fingerprint(&watched).await;
+                if seen == last_seen {
+                    continue;
+                }
+                last_seen = seen;
+
+                let loaded = load_manuals(&watched, allowed_base.as_deref()).await;
+                if !loaded.failures.is_empty() {
+                    tracing::warn!(
+                        provider = %provider,
+                        failures = ?loaded.failures,
+                        "Failed to reload text manuals; keeping their previous tools"
+                    );
+                    continue;
+                }
+                let reloaded = loaded.tools;
+                let diff = ToolDiff::between(&tools, &reloaded);
+                if diff.is_empty() {
+                    continue;
+                }
+                tracing::info!(
+                    provider = %provider,
+                    added = ?diff.added,
+                    removed = ?diff.removed,
+                    changed = ?diff.changed,
+                    "Text manual changed"
+                );
+                tools = reloaded;
+                listeners.notify(&provider);
+            }
+        });
+        Self { sources, task }
+    }
+
+    /// Whether the watch is still running.
+    pub(super) fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, description: &str) -> Tool {
+        serde_json::from_value(json!({
+            "name": name,
+            "description": description,
+            "inputs": {"type": "object"},
+            "outputs": {"type": "object"},
+            "tags": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn diff_names_added_removed_and_changed_tools() {
+        let old = vec![
+            tool("keep", "same"),
+            tool("edit", "before"),
+            tool("drop", ""),
+        ];
+        let new = vec![tool("keep", "same"), tool("edit", "after"), tool("new", "")];
+
+        let diff = ToolDiff::between(&old, &new);
+        assert_eq!(
+            diff,
+            ToolDiff {
+                added: vec!["new".to_string()],
+                removed: vec!["drop".to_string()],
+                changed: vec!["edit".to_string()],
+            }
+        );
+        assert!(ToolDiff::between(&new, &new).is_empty());
+    }
+}
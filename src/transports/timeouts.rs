@@ -0,0 +1,30 @@
+//! Whole-call time limits and timeout errors for the HTTP-based transports.
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::errors::UtcpError;
+use crate::providers::http::HttpTimeouts;
+
+/// Run `call` under the provider's `total_ms` limit, if any.
+pub(crate) async fn with_total_timeout<T>(
+    timeouts: Option<&HttpTimeouts>,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeouts.and_then(|t| t.total_ms) {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), call)
+            .await
+            .map_err(|_| UtcpError::Timeout(format!("no result within {}ms", ms)))?,
+        None => call.await,
+    }
+}
+
+/// Report reqwest timeouts as [`UtcpError::Timeout`] so callers can tell them apart from
+/// refused or reset connections.
+pub(crate) fn timeout_error(err: reqwest::Error) -> anyhow::Error {
+    if err.is_timeout() {
+        UtcpError::Timeout(err.to_string()).into()
+    } else {
+        err.into()
+    }
+}
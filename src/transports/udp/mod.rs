@@ -5,11 +5,50 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::time::Duration;
 use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
 
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
 use crate::providers::udp::UdpProvider;
 use crate::tools::Tool;
-use crate::transports::{stream::StreamResult, ClientTransport};
+use crate::transports::{
+    stream::{boxed_channel_stream, StreamResult},
+    ClientTransport,
+};
+
+/// How long to wait for each reply datagram when the provider sets no `timeout_ms`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pause after which a call stops collecting further reply datagrams.
+const INTER_PACKET_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Key of the datagram a server sends after its last reply, as `{"$done": true}`.
+const STREAM_DONE: &str = "$done";
+
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
+/// Read one reply datagram: `None` for the done marker.
+fn datagram_item(data: &[u8]) -> Result<Option<Value>> {
+    let item: Value = serde_json::from_slice(data)
+        .map_err(|e| anyhow!("Failed to parse UDP reply JSON: {}", e))?;
+    if item.get(STREAM_DONE).and_then(Value::as_bool) == Some(true) {
+        return Ok(None);
+    }
+    Ok(Some(item))
+}
+
+/// Wait up to `timeout` for the next datagram. Returns `None` when none arrives in time.
+async fn receive(socket: &UdpSocket, timeout: Duration) -> Result<Option<Vec<u8>>> {
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    match tokio::time::timeout(timeout, socket.recv_from(&mut buf)).await {
+        Ok(received) => {
+            let (len, _) = received?;
+            buf.truncate(len);
+            Ok(Some(buf))
+        }
+        Err(_) => Ok(None),
+    }
+}
 
 /// Datagram-based transport for lightweight request/response tools.
 pub struct UdpTransport;
@@ -20,19 +59,45 @@ impl UdpTransport {
         Self
     }
 
-    async fn send_and_receive(&self, server_addr: &str, data: &[u8]) -> Result<Vec<u8>> {
+    /// Send `request` and wait for the first reply datagram, resending it up to `retries`
+    /// times when no reply arrives within `timeout_ms`. Returns the socket further replies
+    /// arrive on, along with the first one.
+    async fn send_request(
+        &self,
+        prov: &UdpProvider,
+        request: &Value,
+    ) -> Result<(UdpSocket, Vec<u8>)> {
         // Bind to a random local port
         let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        let address = format!("{}:{}", prov.host, prov.port);
+        let data = serde_json::to_vec(request)?;
+        let timeout = prov
+            .timeout_ms
+            .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
 
-        // Send data
-        socket.send_to(data, server_addr).await?;
-
-        // Receive response
-        let mut buf = vec![0u8; 65535]; // Max UDP packet size
-        let (len, _) = socket.recv_from(&mut buf).await?;
-
-        buf.truncate(len);
-        Ok(buf)
+        let attempts = prov.retries.saturating_add(1);
+        for attempt in 1..=attempts {
+            socket.send_to(&data, &address).await?;
+            if let Some(reply) = receive(&socket, timeout).await? {
+                return Ok((socket, reply));
+            }
+            if attempt < attempts {
+                tracing::debug!(
+                    provider = %prov.base.name,
+                    attempt,
+                    "No UDP reply within {}ms; resending the request",
+                    timeout.as_millis()
+                );
+            }
+        }
+        Err(UtcpError::Timeout(format!(
+            "UDP provider '{}' sent no reply before the {}ms timeout ({} attempt{})",
+            prov.base.name,
+            timeout.as_millis(),
+            attempts,
+            if attempts == 1 { "" } else { "s" }
+        ))
+        .into())
     }
 }
 
@@ -60,37 +125,103 @@ impl ClientTransport for UdpTransport {
 
         // Build request
         let request = serde_json::json!({
+            "method": "call_tool",
             "tool": tool_name,
             "args": args,
         });
+        let (socket, first) = self.send_request(udp_prov, &request).await?;
 
-        let request_bytes = serde_json::to_vec(&request)?;
-
-        // Send request and receive response
-        let address = format!("{}:{}", udp_prov.host, udp_prov.port);
-        let response_bytes = if let Some(timeout) = udp_prov.timeout_ms {
-            tokio::time::timeout(
-                Duration::from_millis(timeout),
-                self.send_and_receive(&address, &request_bytes),
-            )
-            .await??
-        } else {
-            self.send_and_receive(&address, &request_bytes).await?
-        };
+        // Collect replies until the done marker, the datagram limit, or a pause
+        let limit = udp_prov.max_response_datagrams.unwrap_or(1).max(1);
+        let mut documents = Vec::new();
+        let mut datagram = Some(first);
+        let mut received = 0;
+        while let Some(data) = datagram {
+            received += 1;
+            match datagram_item(&data)? {
+                Some(document) => documents.push(document),
+                None => break,
+            }
+            if received >= limit {
+                break;
+            }
+            datagram = receive(&socket, INTER_PACKET_TIMEOUT).await?;
+        }
 
-        // Parse response
-        let result: Value = serde_json::from_slice(&response_bytes)?;
-        Ok(result)
+        Ok(match documents.len() {
+            0 => Value::Null,
+            1 => documents.remove(0),
+            _ => Value::Array(documents),
+        })
     }
 
     async fn call_tool_stream(
         &self,
-        _tool_name: &str,
-        _args: HashMap<String, Value>,
-        _prov: &dyn Provider,
+        tool_name: &str,
+        args: HashMap<String, Value>,
+        prov: &dyn Provider,
     ) -> Result<Box<dyn StreamResult>> {
-        Err(anyhow!(
-            "Streaming not suitable for UDP - use TCP or WebSocket"
+        let udp_prov = prov
+            .as_any()
+            .downcast_ref::<UdpProvider>()
+            .ok_or_else(|| anyhow!("Provider is not a UdpProvider"))?;
+
+        let request = serde_json::json!({
+            "method": "call_tool_stream",
+            "tool": tool_name,
+            "args": args,
+        });
+        let (socket, first) = self.send_request(udp_prov, &request).await?;
+
+        // Each datagram is one item, until the done marker or `max_response_datagrams`
+        let name = udp_prov.base.name.clone();
+        let timeout = udp_prov
+            .timeout_ms
+            .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+        let limit = udp_prov.max_response_datagrams;
+        let (tx, rx) = mpsc::channel(32);
+        let reader_task = tokio::spawn(async move {
+            let mut data = first;
+            let mut received = 0;
+            loop {
+                let item = match datagram_item(&data) {
+                    Ok(Some(item)) => Ok(item),
+                    Ok(None) => return,
+                    Err(err) => Err(err),
+                };
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    return;
+                }
+                received += 1;
+                if limit.is_some_and(|limit| received >= limit) {
+                    return;
+                }
+                data = match receive(&socket, timeout).await {
+                    Ok(Some(data)) => data,
+                    Ok(None) => {
+                        let err = UtcpError::Timeout(format!(
+                            "UDP provider '{}' stream sent nothing before the {}ms timeout",
+                            name,
+                            timeout.as_millis()
+                        ));
+                        let _ = tx.send(Err(err.into())).await;
+                        return;
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err)).await;
+                        return;
+                    }
+                };
+            }
+        });
+
+        Ok(boxed_channel_stream(
+            rx,
+            Some(Box::new(move || {
+                reader_task.abort();
+                Ok(())
+            })),
         ))
     }
 }
@@ -134,6 +265,8 @@ mod tests {
             host: addr.ip().to_string(),
             port: addr.port(),
             timeout_ms: None,
+            retries: 0,
+            max_response_datagrams: None,
         };
 
         let mut args = HashMap::new();
@@ -149,7 +282,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn register_returns_empty_and_stream_error() {
+    async fn register_returns_empty() {
         let prov = UdpProvider {
             base: BaseProvider {
                 name: "udp".to_string(),
@@ -160,6 +293,8 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 0,
             timeout_ms: None,
+            retries: 0,
+            max_response_datagrams: None,
         };
 
         let transport = UdpTransport::new();
@@ -168,13 +303,6 @@ mod tests {
             .await
             .unwrap()
             .is_empty());
-
-        let err = transport
-            .call_tool_stream("tool", HashMap::new(), &prov)
-            .await
-            .err()
-            .expect("stream error");
-        assert!(err.to_string().contains("Streaming not suitable for UDP"));
     }
 
     #[tokio::test]
@@ -189,6 +317,8 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 9, // discard port - we won't listen
             timeout_ms: Some(30),
+            retries: 0,
+            max_response_datagrams: None,
         };
 
         let err = UdpTransport::new()
@@ -200,4 +330,138 @@ mod tests {
                 || err.to_string().to_lowercase().contains("timeout")
         );
     }
+
+    fn provider(addr: std::net::SocketAddr, retries: u32, max: Option<usize>) -> UdpProvider {
+        let mut prov =
+            UdpProvider::new("udp".to_string(), addr.ip().to_string(), addr.port(), None);
+        prov.timeout_ms = Some(100);
+        prov.retries = retries;
+        prov.max_response_datagrams = max;
+        prov
+    }
+
+    /// Answer each request after the first `ignore` with `replies`, one datagram each, and
+    /// report the requests received.
+    fn serve_datagrams(
+        socket: UdpSocket,
+        ignore: usize,
+        replies: Vec<Value>,
+    ) -> mpsc::UnboundedReceiver<Value> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1024];
+            let mut seen = 0;
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let _ = tx.send(serde_json::from_slice(&buf[..len]).unwrap());
+                seen += 1;
+                if seen <= ignore {
+                    continue;
+                }
+                for reply in &replies {
+                    let data = serde_json::to_vec(reply).unwrap();
+                    socket.send_to(&data, peer).await.unwrap();
+                }
+            }
+        });
+        rx
+    }
+
+    #[tokio::test]
+    async fn call_tool_retries_lost_requests() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let mut requests = serve_datagrams(socket, 1, vec![json!({"ok": true})]);
+
+        let result = UdpTransport::new()
+            .call_tool("ping", HashMap::new(), &provider(addr, 2, None))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"ok": true}));
+
+        let first = requests.recv().await.unwrap();
+        assert_eq!(first["method"], "call_tool");
+        assert_eq!(first["tool"], "ping");
+        assert_eq!(requests.recv().await.unwrap(), first);
+        assert!(requests.try_recv().is_err());
+
+        // Without retries the lost request times out
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _requests = serve_datagrams(socket, 1, vec![json!({"ok": true})]);
+        let err = UdpTransport::new()
+            .call_tool("ping", HashMap::new(), &provider(addr, 0, None))
+            .await
+            .expect_err("lost request");
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+        assert!(err.to_string().contains("1 attempt)"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn call_tool_collects_datagrams_into_an_array() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _requests = serve_datagrams(
+            socket,
+            0,
+            vec![
+                json!({"part": 1}),
+                json!({"part": 2}),
+                json!({"$done": true}),
+                json!({"part": "late"}),
+            ],
+        );
+        let result = UdpTransport::new()
+            .call_tool("parts", HashMap::new(), &provider(addr, 0, Some(10)))
+            .await
+            .unwrap();
+        assert_eq!(result, json!([{"part": 1}, {"part": 2}]));
+
+        // The limit stops collection, and a single datagram is returned as it is
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _requests = serve_datagrams(socket, 0, vec![json!({"part": 1}), json!({"part": 2})]);
+        let result = UdpTransport::new()
+            .call_tool("parts", HashMap::new(), &provider(addr, 0, None))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"part": 1}));
+    }
+
+    #[tokio::test]
+    async fn call_tool_stream_yields_each_datagram() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let mut requests = serve_datagrams(
+            socket,
+            0,
+            vec![json!(1), json!(2), json!(3), json!({"$done": true})],
+        );
+
+        let mut stream = UdpTransport::new()
+            .call_tool_stream("count", HashMap::new(), &provider(addr, 0, None))
+            .await
+            .unwrap();
+        let mut items = Vec::new();
+        while let Some(item) = stream.next().await.unwrap() {
+            items.push(item);
+        }
+        assert_eq!(items, vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(requests.recv().await.unwrap()["method"], "call_tool_stream");
+
+        // A server that stops sending ends the stream with a timeout
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _requests = serve_datagrams(socket, 0, vec![json!(1)]);
+        let mut stream = UdpTransport::new()
+            .call_tool_stream("count", HashMap::new(), &provider(addr, 0, None))
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap(), Some(json!(1)));
+        let err = stream.next().await.expect_err("stalled stream");
+        assert!(err.to_string().contains("stream sent nothing"), "{err}");
+    }
 }
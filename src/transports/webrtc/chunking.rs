@@ -0,0 +1,195 @@
+//! Splitting payloads too large for one data channel message into `{"$chunk": ...}` frames,
+//! and putting them back together on arrival. Peers exchanging large messages with the
+//! transport use the same functions.
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Smallest message size honoured, leaving room for the frame around each piece.
+pub const MIN_MESSAGE_BYTES: usize = 256;
+
+/// Room taken by the JSON frame around the data of one piece.
+const FRAME_OVERHEAD: usize = 128;
+
+static NEXT_MESSAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Frame {
+    #[serde(rename = "$chunk")]
+    chunk: Chunk,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Chunk {
+    id: String,
+    seq: usize,
+    total: usize,
+    data_b64: String,
+}
+
+/// Split `payload` into messages of at most `max_message_bytes`. A payload that fits is its
+/// own only message; a larger one becomes `{"$chunk": {id, seq, total, data_b64}}` frames
+/// with `seq` counting from 0.
+pub fn split_message(payload: Vec<u8>, max_message_bytes: usize) -> Vec<Bytes> {
+    if payload.len() <= max_message_bytes {
+        return vec![payload.into()];
+    }
+    // Base64 turns every 3 bytes into 4
+    let piece = (max_message_bytes.max(MIN_MESSAGE_BYTES) - FRAME_OVERHEAD) / 4 * 3;
+    let id = NEXT_MESSAGE_ID.fetch_add(1, Ordering::Relaxed).to_string();
+    let total = payload.len().div_ceil(piece);
+    payload
+        .chunks(piece)
+        .enumerate()
+        .map(|(seq, data)| {
+            let frame = Frame {
+                chunk: Chunk {
+                    id: id.clone(),
+                    seq,
+                    total,
+                    data_b64: base64::engine::general_purpose::STANDARD.encode(data),
+                },
+            };
+            serde_json::to_vec(&frame)
+                .expect("chunk frames serialize")
+                .into()
+        })
+        .collect()
+}
+
+/// Whether `message` is a chunk frame: an object whose first key is `$chunk`.
+fn is_chunk(message: &[u8]) -> bool {
+    let rest = message.trim_ascii_start();
+    match rest.strip_prefix(b"{") {
+        Some(rest) => rest.trim_ascii_start().starts_with(b"\"$chunk\""),
+        None => false,
+    }
+}
+
+/// Pieces of one chunked payload received so far.
+#[derive(Debug)]
+struct Partial {
+    total: usize,
+    pieces: BTreeMap<usize, Vec<u8>>,
+    size: usize,
+}
+
+/// Collects chunk frames until the payload they carry is complete.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    partial: HashMap<String, Partial>,
+}
+
+impl Reassembler {
+    /// Take one received message and return a whole payload: an unchunked message at once,
+    /// a chunked one when its last piece arrives. A payload growing past `max_size` bytes is
+    /// dropped with an error.
+    pub fn push(&mut self, message: Bytes, max_size: usize) -> Result<Option<Bytes>> {
+        if !is_chunk(&message) {
+            return Ok(Some(message));
+        }
+        let Frame { chunk } = serde_json::from_slice(&message)
+            .map_err(|e| anyhow!("Failed to parse chunk frame: {}", e))?;
+        if chunk.seq >= chunk.total {
+            return Err(anyhow!(
+                "Chunk {} of message '{}' is outside its {} pieces",
+                chunk.seq,
+                chunk.id,
+                chunk.total
+            ));
+        }
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&chunk.data_b64)
+            .map_err(|e| anyhow!("Failed to decode chunk data: {}", e))?;
+
+        let partial = self
+            .partial
+            .entry(chunk.id.clone())
+            .or_insert_with(|| Partial {
+                total: chunk.total,
+                pieces: BTreeMap::new(),
+                size: 0,
+            });
+        if partial.total != chunk.total {
+            self.partial.remove(&chunk.id);
+            return Err(anyhow!(
+                "Chunks of message '{}' disagree on their number",
+                chunk.id
+            ));
+        }
+        partial.size += data.len();
+        if let Some(previous) = partial.pieces.insert(chunk.seq, data) {
+            partial.size -= previous.len();
+        }
+        if partial.size > max_size {
+            self.partial.remove(&chunk.id);
+            return Err(anyhow!(
+                "Chunked message '{}' exceeds {} bytes",
+                chunk.id,
+                max_size
+            ));
+        }
+        if partial.pieces.len() < partial.total {
+            return Ok(None);
+        }
+
+        let partial = self.partial.remove(&chunk.id).expect("partial message");
+        Ok(Some(partial.pieces.into_values().flatten().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_payloads_are_sent_as_they_are() {
+        let payload = br#"{"result": 1}"#.to_vec();
+        let messages = split_message(payload.clone(), 1024);
+        assert_eq!(messages, vec![Bytes::from(payload.clone())]);
+
+        let mut reassembler = Reassembler::default();
+        let received = reassembler.push(messages[0].clone(), 1024).unwrap();
+        assert_eq!(received, Some(Bytes::from(payload)));
+    }
+
+    #[test]
+    fn large_payloads_round_trip_in_any_order() {
+        let payload: Vec<u8> = (0..100_000u32).flat_map(|n| n.to_le_bytes()).collect();
+        let mut messages = split_message(payload.clone(), 16 * 1024);
+        assert!(messages.len() > 1);
+        assert!(messages.iter().all(|m| m.len() <= 16 * 1024));
+        messages.reverse();
+
+        let mut reassembler = Reassembler::default();
+        let last = messages.pop().unwrap();
+        for message in messages {
+            assert_eq!(reassembler.push(message, usize::MAX).unwrap(), None);
+        }
+        let received = reassembler.push(last, usize::MAX).unwrap();
+        assert_eq!(received, Some(Bytes::from(payload)));
+        assert!(reassembler.partial.is_empty());
+    }
+
+    #[test]
+    fn oversized_and_malformed_chunks_are_rejected() {
+        let payload = vec![b'x'; 4096];
+        let messages = split_message(payload, MIN_MESSAGE_BYTES);
+        let mut reassembler = Reassembler::default();
+        let err = messages
+            .into_iter()
+            .map(|message| reassembler.push(message, 1000))
+            .find_map(Result::err)
+            .expect("payload over the limit");
+        assert!(err.to_string().contains("exceeds 1000 bytes"), "{err}");
+        assert!(reassembler.partial.is_empty());
+
+        let out_of_range = br#"{"$chunk": {"id": "1", "seq": 2, "total": 2, "data_b64": ""}}"#;
+        assert!(reassembler
+            .push(Bytes::from_static(out_of_range), 1000)
+            .is_err());
+    }
+}
@@ -1,13 +1,28 @@
 // WebRTC Transport - peer-to-peer data channels
+pub mod chunking;
+mod turn;
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio_tungstenite::tungstenite::{
+    client::IntoClientRequest,
+    http::{HeaderName, HeaderValue, Request},
+    Message,
+};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use webrtc::api::APIBuilder;
 use webrtc::data_channel::data_channel_message::DataChannelMessage;
 use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_candidate::{RTCIceCandidate, RTCIceCandidateInit};
+use webrtc::ice_transport::ice_connection_state::RTCIceConnectionState;
 use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
@@ -15,19 +30,174 @@ use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
 use webrtc::peer_connection::RTCPeerConnection;
 
 use crate::auth::AuthConfig;
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
-use crate::providers::webrtc::WebRtcProvider;
+use crate::providers::webrtc::{IceServer, SignalingKind, WebRtcProvider};
 use crate::security::{validate_size_limit, validate_url_security};
 use crate::tools::{Tool, ToolInputOutputSchema};
 use crate::transports::{
     stream::{boxed_channel_stream, StreamResult},
     ClientTransport,
 };
+use chunking::Reassembler;
+use turn::CredentialCache;
+
+const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024; // 10 MB
+
+/// How long an HTTP offer waits for ICE gathering when the provider sets no
+/// `ice_gathering_timeout_ms`.
+const DEFAULT_ICE_GATHERING_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// How long the data channel may take to open once signaling is done when the provider sets
+/// no `open_timeout_ms`.
+const DEFAULT_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a call waits for its response when the provider sets no `response_timeout_ms`.
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key of the message a peer sends after the last item of a stream, as `{"$done": true}`.
+const STREAM_DONE: &str = "$done";
+
+/// Read one message of a streamed call: `None` once the peer marks the stream done, and an
+/// error for an `{"error": ...}` item.
+fn stream_item(data: &[u8]) -> Result<Option<Value>> {
+    validate_size_limit(data, MAX_MESSAGE_SIZE)?;
+    let item: Value =
+        serde_json::from_slice(data).map_err(|e| anyhow!("Failed to parse stream item: {}", e))?;
+    if item.get(STREAM_DONE).and_then(Value::as_bool) == Some(true) {
+        return Ok(None);
+    }
+    if let Some(error) = item.get("error") {
+        return Err(anyhow!("Tool execution error: {}", error));
+    }
+    Ok(Some(item))
+}
+
+/// Send `message` on the data channel, split into `$chunk` frames when it is larger than the
+/// provider's `max_message_bytes`.
+async fn send_message(
+    data_channel: &RTCDataChannel,
+    prov: &WebRtcProvider,
+    message: &Value,
+) -> Result<()> {
+    let payload = serde_json::to_vec(message)?;
+    let limit = prov.max_message_bytes.unwrap_or(usize::MAX);
+    for frame in chunking::split_message(payload, limit) {
+        data_channel.send(&frame).await?;
+    }
+    Ok(())
+}
+
+/// Describe how far a connection got, so a failure shows whether signaling, ICE or DTLS
+/// stalled: the signaling, peer connection and ICE connection states, and the candidate pair
+/// ICE selected, if any.
+async fn connection_diagnostics(peer_connection: &RTCPeerConnection) -> String {
+    let pair = peer_connection
+        .sctp()
+        .transport()
+        .ice_transport()
+        .get_selected_candidate_pair()
+        .await
+        .map_or_else(|| "none".to_string(), |pair| pair.to_string());
+    format!(
+        "signaling state {}, peer connection state {}, ICE connection state {}, selected candidate pair {}",
+        peer_connection.signaling_state(),
+        peer_connection.connection_state(),
+        peer_connection.ice_connection_state(),
+        pair
+    )
+}
+
+/// Wait up to `timeout` for ICE gathering to finish, signalled by `gathered` closing.
+/// Returns whether it finished in time.
+async fn wait_for_gathering(mut gathered: mpsc::Receiver<()>, timeout: Duration) -> bool {
+    tokio::time::timeout(timeout, gathered.recv()).await.is_ok()
+}
+
+type SignalingSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A message on a WebSocket signaling connection, tagged by `type`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SignalMessage {
+    Offer { sdp: String },
+    Answer { sdp: String },
+    Candidate { candidate: RTCIceCandidateInit },
+    Error { message: String },
+}
+
+async fn send_signal<S>(sink: &mut S, message: &SignalMessage) -> Result<()>
+where
+    S: futures::Sink<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    sink.send(Message::Text(serde_json::to_string(message)?))
+        .await?;
+    Ok(())
+}
+
+/// Send the offer on a signaling socket, apply the answer, and trade ICE candidates with the
+/// remote peer until `stop` fires or is dropped. Candidates that arrive before the answer are
+/// held until it is applied.
+async fn trade_over_websocket(
+    socket: SignalingSocket,
+    peer_connection: Arc<RTCPeerConnection>,
+    offer: RTCSessionDescription,
+    mut candidates: mpsc::UnboundedReceiver<RTCIceCandidateInit>,
+    mut stop: oneshot::Receiver<()>,
+) -> Result<()> {
+    let (mut sink, mut messages) = socket.split();
+    send_signal(&mut sink, &SignalMessage::Offer { sdp: offer.sdp }).await?;
+
+    let mut answered = false;
+    let mut early_candidates = Vec::new();
+    loop {
+        tokio::select! {
+            _ = &mut stop => return Ok(()),
+            Some(candidate) = candidates.recv() => {
+                send_signal(&mut sink, &SignalMessage::Candidate { candidate }).await?;
+            }
+            message = messages.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None if answered => return Ok(()),
+                    Some(Ok(Message::Close(_))) | None => {
+                        return Err(anyhow!("Signaling server closed the connection before answering"))
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                };
+                match serde_json::from_str::<SignalMessage>(&text) {
+                    Ok(SignalMessage::Answer { sdp }) => {
+                        peer_connection
+                            .set_remote_description(RTCSessionDescription::answer(sdp)?)
+                            .await?;
+                        answered = true;
+                        for candidate in early_candidates.drain(..) {
+                            peer_connection.add_ice_candidate(candidate).await?;
+                        }
+                    }
+                    Ok(SignalMessage::Candidate { candidate }) if answered => {
+                        peer_connection.add_ice_candidate(candidate).await?;
+                    }
+                    Ok(SignalMessage::Candidate { candidate }) => early_candidates.push(candidate),
+                    Ok(SignalMessage::Error { message }) => {
+                        return Err(anyhow!("Signaling server returned error: {}", message))
+                    }
+                    Ok(SignalMessage::Offer { .. }) | Err(_) => {
+                        tracing::debug!(message = %text, "Ignoring unexpected signaling message");
+                    }
+                }
+            }
+        }
+    }
+}
 
 /// Peer-to-peer transport that relays tool calls over WebRTC data channels.
 pub struct WebRtcTransport {
     // Cache of active peer connections
     connections: Arc<Mutex<HashMap<String, Arc<RTCPeerConnection>>>>,
+    // TURN credentials fetched from providers' credential endpoints
+    turn_credentials: CredentialCache,
 }
 
 impl WebRtcTransport {
@@ -35,47 +205,80 @@ impl WebRtcTransport {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
+            turn_credentials: CredentialCache::default(),
         }
     }
 
     fn default_schema() -> ToolInputOutputSchema {
-        ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
+        ToolInputOutputSchema::new("object")
+    }
+
+    /// The provider's ICE servers, followed by a TURN server with credentials from its
+    /// `credential_endpoint`. Credentials are fetched again once their `ttl` elapses; when
+    /// fetching fails, only the configured servers are used.
+    async fn ice_servers(&self, prov: &WebRtcProvider) -> Vec<IceServer> {
+        let mut servers = prov.ice_servers.clone();
+        let Some(endpoint) = &prov.credential_endpoint else {
+            return servers;
+        };
+        match self.turn_credentials(prov, endpoint).await {
+            Ok(credentials) => servers.push(credentials.ice_server()),
+            Err(err) => tracing::warn!(
+                provider = %prov.base.name,
+                error = %err,
+                "Failed to fetch TURN credentials; using the configured ICE servers"
+            ),
         }
+        servers
     }
 
-    async fn create_peer_connection(
+    async fn turn_credentials(
         &self,
         prov: &WebRtcProvider,
-    ) -> Result<Arc<RTCPeerConnection>> {
+        endpoint: &str,
+    ) -> Result<turn::TurnCredentials> {
+        if let Some(credentials) = self.turn_credentials.get(&prov.base.name, endpoint) {
+            return Ok(credentials);
+        }
+        validate_url_security(endpoint, false)?;
+        let mut request = reqwest::Client::new().get(endpoint);
+        if let Some(auth) = &prov.base.auth {
+            request = self.apply_auth(request, auth)?;
+        }
+        let credentials = turn::fetch(request).await?;
+        self.turn_credentials
+            .insert(&prov.base.name, endpoint, credentials.clone());
+        Ok(credentials)
+    }
+
+    async fn rtc_configuration(&self, prov: &WebRtcProvider) -> RTCConfiguration {
         // Configure ICE servers (credential_type field removed in WebRTC 0.14)
-        let ice_servers: Vec<RTCIceServer> = prov
-            .ice_servers
-            .iter()
+        let ice_servers: Vec<RTCIceServer> = self
+            .ice_servers(prov)
+            .await
+            .into_iter()
             .map(|server| RTCIceServer {
-                urls: server.urls.clone(),
-                username: server.username.clone().unwrap_or_default(),
-                credential: server.credential.clone().unwrap_or_default(),
+                urls: server.urls,
+                username: server.username.unwrap_or_default(),
+                credential: server.credential.unwrap_or_default(),
             })
             .collect();
 
+        RTCConfiguration {
+            ice_servers,
+            ..Default::default()
+        }
+    }
+
+    async fn create_peer_connection(
+        &self,
+        prov: &WebRtcProvider,
+    ) -> Result<Arc<RTCPeerConnection>> {
         // Use the default API to create peer connection
         let api = APIBuilder::new().build();
 
         // Create peer connection configuration
-        let config = RTCConfiguration {
-            ice_servers,
-            ..Default::default()
-        };
+        let config = self.rtc_configuration(prov).await;
 
         // Create the peer connection
         let peer_connection = Arc::new(api.new_peer_connection(config).await?);
@@ -86,18 +289,16 @@ impl WebRtcTransport {
     async fn exchange_sdp(
         &self,
         prov: &WebRtcProvider,
+        url: &str,
         offer: RTCSessionDescription,
     ) -> Result<RTCSessionDescription> {
         // Send offer to signaling server and get answer
-        validate_url_security(&prov.signaling_server, false)?;
         let client = reqwest::Client::new();
 
-        let mut request = client
-            .post(&prov.signaling_server)
-            .json(&serde_json::json!({
-                "type": "offer",
-                "sdp": offer.sdp,
-            }));
+        let mut request = client.post(url).json(&serde_json::json!({
+            "type": "offer",
+            "sdp": offer.sdp,
+        }));
 
         // Apply authentication if configured
         if let Some(auth) = &prov.base.auth {
@@ -123,6 +324,27 @@ impl WebRtcTransport {
         Ok(RTCSessionDescription::answer(answer_sdp.to_string())?)
     }
 
+    /// Open a WebSocket to the signaling server, authenticating the upgrade request the way
+    /// HTTP signaling requests are.
+    async fn connect_signaling(&self, prov: &WebRtcProvider, url: &str) -> Result<SignalingSocket> {
+        let mut builder = reqwest::Client::new().get(url);
+        if let Some(auth) = &prov.base.auth {
+            builder = self.apply_auth(builder, auth)?;
+        }
+        let prepared = builder.build()?;
+
+        let mut request: Request<()> = prepared.url().as_str().into_client_request()?;
+        for (name, value) in prepared.headers() {
+            request.headers_mut().append(
+                HeaderName::from_bytes(name.as_str().as_bytes())?,
+                HeaderValue::from_bytes(value.as_bytes())?,
+            );
+        }
+
+        let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+        Ok(socket)
+    }
+
     fn apply_auth(
         &self,
         builder: reqwest::RequestBuilder,
@@ -148,12 +370,57 @@ impl WebRtcTransport {
         }
     }
 
+    /// Connect to the provider's peer and open a data channel, within `connect_timeout_ms` when
+    /// set. The peer connection is closed if the data channel does not open.
     async fn create_data_channel(
         &self,
         prov: &WebRtcProvider,
     ) -> Result<(Arc<RTCPeerConnection>, Arc<RTCDataChannel>)> {
         let peer_connection = self.create_peer_connection(prov).await?;
 
+        let opened = self.open_data_channel(prov, &peer_connection);
+        let opened = match prov.connect_timeout_ms {
+            Some(ms) => match tokio::time::timeout(Duration::from_millis(ms), opened).await {
+                Ok(opened) => opened,
+                Err(_) => {
+                    let diagnostics = connection_diagnostics(&peer_connection).await;
+                    Err(UtcpError::Timeout(format!(
+                        "WebRTC provider '{}' did not connect within {}ms; {}",
+                        prov.base.name, ms, diagnostics
+                    ))
+                    .into())
+                }
+            },
+            None => opened.await,
+        };
+
+        match opened {
+            Ok(data_channel) => Ok((peer_connection, data_channel)),
+            Err(err) => {
+                let _ = peer_connection.close().await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Signal through the provider's signaling server and wait up to `open_timeout_ms` for the
+    /// data channel to open.
+    async fn open_data_channel(
+        &self,
+        prov: &WebRtcProvider,
+        peer_connection: &Arc<RTCPeerConnection>,
+    ) -> Result<Arc<RTCDataChannel>> {
+        // A failed ICE connection will never open the data channel, so report it right away
+        let (ice_failed_tx, mut ice_failed) = mpsc::channel::<RTCIceConnectionState>(1);
+        peer_connection.on_ice_connection_state_change(Box::new(
+            move |state: RTCIceConnectionState| {
+                if state == RTCIceConnectionState::Failed {
+                    let _ = ice_failed_tx.try_send(state);
+                }
+                Box::pin(async {})
+            },
+        ));
+
         // Create data channel configuration
         let mut init = webrtc::data_channel::data_channel_init::RTCDataChannelInit {
             ordered: Some(prov.ordered),
@@ -173,13 +440,74 @@ impl WebRtcTransport {
             .create_data_channel(&prov.channel_label, Some(init))
             .await?;
 
+        let signaling = prov.signaling();
+        if signaling.url.is_empty() {
+            return Err(anyhow!(
+                "WebRTC provider '{}' has no signaling server",
+                prov.base.name
+            ));
+        }
+        validate_url_security(&signaling.url, false)?;
+
+        // WebSocket signaling trickles candidates as they are gathered, so it listens for them
+        // before the local description starts gathering
+        let socket = match signaling.kind {
+            SignalingKind::Http => None,
+            SignalingKind::Websocket => {
+                let socket = self.connect_signaling(prov, &signaling.url).await?;
+                let (candidate_tx, candidates) = mpsc::unbounded_channel();
+                peer_connection.on_ice_candidate(Box::new(
+                    move |candidate: Option<RTCIceCandidate>| {
+                        let candidate_tx = candidate_tx.clone();
+                        Box::pin(async move {
+                            if let Some(Ok(candidate)) = candidate.map(|c| c.to_json()) {
+                                let _ = candidate_tx.send(candidate);
+                            }
+                        })
+                    },
+                ));
+                Some((socket, candidates))
+            }
+        };
+
         // Create offer
         let offer = peer_connection.create_offer(None).await?;
+        let gathered = peer_connection.gathering_complete_promise().await;
         peer_connection.set_local_description(offer.clone()).await?;
 
-        // Exchange SDP with signaling server
-        let answer = self.exchange_sdp(prov, offer).await?;
-        peer_connection.set_remote_description(answer).await?;
+        // Exchange SDP with signaling server. A signaling socket stays open, trading
+        // candidates, until this function returns.
+        let (_stop_signaling, stop) = oneshot::channel::<()>();
+        let (failed_tx, mut signaling_failed) = oneshot::channel::<anyhow::Error>();
+        match socket {
+            None => {
+                // HTTP signaling cannot trickle, so the offer should carry every candidate
+                let timeout = prov
+                    .ice_gathering_timeout_ms
+                    .map_or(DEFAULT_ICE_GATHERING_TIMEOUT, Duration::from_millis);
+                if !wait_for_gathering(gathered, timeout).await {
+                    tracing::debug!(
+                        provider = %prov.base.name,
+                        "ICE gathering incomplete after {}ms; sending the offer with the candidates found so far",
+                        timeout.as_millis()
+                    );
+                }
+                let offer = peer_connection.local_description().await.unwrap_or(offer);
+                let answer = self.exchange_sdp(prov, &signaling.url, offer).await?;
+                peer_connection.set_remote_description(answer).await?;
+            }
+            Some((socket, candidates)) => {
+                let peer_connection = Arc::clone(peer_connection);
+                tokio::spawn(async move {
+                    let traded =
+                        trade_over_websocket(socket, peer_connection, offer, candidates, stop)
+                            .await;
+                    if let Err(err) = traded {
+                        let _ = failed_tx.send(err);
+                    }
+                });
+            }
+        }
 
         // Wait for data channel to open
         let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
@@ -194,50 +522,91 @@ impl WebRtcTransport {
             })
         }));
 
-        // Wait for channel to open with timeout
-        tokio::time::timeout(std::time::Duration::from_secs(10), open_rx.recv())
-            .await
-            .map_err(|_| anyhow!("Timeout waiting for data channel to open"))?;
+        // Wait for channel to open with timeout, unless signaling fails first
+        let timeout = prov
+            .open_timeout_ms
+            .map_or(DEFAULT_OPEN_TIMEOUT, Duration::from_millis);
+        let opened = tokio::time::timeout(timeout, async {
+            tokio::select! {
+                _ = open_rx.recv() => Ok(()),
+                Ok(err) = &mut signaling_failed => Err(err),
+                Some(state) = ice_failed.recv() => {
+                    let diagnostics = connection_diagnostics(peer_connection).await;
+                    Err(anyhow!(
+                        "ICE connection {} before the data channel opened; {}",
+                        state,
+                        diagnostics
+                    ))
+                }
+            }
+        })
+        .await;
+        match opened {
+            Ok(opened) => opened?,
+            Err(_) => {
+                let diagnostics = connection_diagnostics(peer_connection).await;
+                return Err(UtcpError::Timeout(format!(
+                    "WebRTC provider '{}' data channel did not open within {}ms; {}",
+                    prov.base.name,
+                    timeout.as_millis(),
+                    diagnostics
+                ))
+                .into());
+            }
+        }
 
-        Ok((peer_connection, data_channel))
+        Ok(data_channel)
     }
 
     async fn send_and_receive(
         &self,
         data_channel: &Arc<RTCDataChannel>,
+        prov: &WebRtcProvider,
         request: Value,
     ) -> Result<Value> {
-        let request_bytes = serde_json::to_vec(&request)?;
-
         // Set up receiver before sending
         let (response_tx, mut response_rx) = mpsc::channel::<Result<Value>>(1);
         let response_tx = Arc::new(Mutex::new(Some(response_tx)));
 
+        // Chunks are put back together in arrival order, before the response is parsed
+        let mut reassembler = Reassembler::default();
         data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
             let response_tx = response_tx.clone();
+            let payload = reassembler.push(msg.data, MAX_MESSAGE_SIZE);
             Box::pin(async move {
+                let payload = match payload {
+                    Ok(Some(payload)) => Ok(payload),
+                    Ok(None) => return,
+                    Err(e) => Err(e),
+                };
                 if let Some(tx) = response_tx.lock().await.take() {
-                    // Validate size
-                    if let Err(e) = validate_size_limit(&msg.data, 10 * 1024 * 1024) {
-                        let _ = tx.send(Err(e)).await;
-                        return;
-                    }
-
-                    let result = serde_json::from_slice::<Value>(&msg.data)
-                        .map_err(|e| anyhow!("Failed to parse response: {}", e));
+                    let result = payload.and_then(|data| {
+                        // Validate size
+                        validate_size_limit(&data, MAX_MESSAGE_SIZE)?;
+                        serde_json::from_slice::<Value>(&data)
+                            .map_err(|e| anyhow!("Failed to parse response: {}", e))
+                    });
                     let _ = tx.send(result).await;
                 }
             })
         }));
 
         // Send request
-        data_channel.send(&request_bytes.into()).await?;
+        send_message(data_channel, prov, &request).await?;
 
         // Wait for response with timeout
-        let response_result =
-            tokio::time::timeout(std::time::Duration::from_secs(30), response_rx.recv())
-                .await
-                .map_err(|_| anyhow!("Timeout waiting for response"))?;
+        let timeout = prov
+            .response_timeout_ms
+            .map_or(DEFAULT_RESPONSE_TIMEOUT, Duration::from_millis);
+        let response_result = tokio::time::timeout(timeout, response_rx.recv())
+            .await
+            .map_err(|_| {
+                UtcpError::Timeout(format!(
+                    "WebRTC provider '{}' sent no response within {}ms",
+                    prov.base.name,
+                    timeout.as_millis()
+                ))
+            })?;
 
         let response = match response_result {
             Some(Ok(value)) => value,
@@ -265,7 +634,9 @@ impl ClientTransport for WebRtcTransport {
             "params": {}
         });
 
-        let response = self.send_and_receive(&data_channel, request).await?;
+        let response = self
+            .send_and_receive(&data_channel, webrtc_prov, request)
+            .await?;
 
         // Parse tools from response
         let tools_array = response
@@ -352,7 +723,9 @@ impl ClientTransport for WebRtcTransport {
             }
         });
 
-        let response = self.send_and_receive(&data_channel, request).await?;
+        let response = self
+            .send_and_receive(&data_channel, webrtc_prov, request)
+            .await?;
 
         // Extract result
         if let Some(error) = response.get("error") {
@@ -376,7 +749,44 @@ impl ClientTransport for WebRtcTransport {
             .downcast_ref::<WebRtcProvider>()
             .ok_or_else(|| anyhow!("Provider is not a WebRtcProvider"))?;
 
-        let (_peer_connection, data_channel) = self.create_data_channel(webrtc_prov).await?;
+        let (peer_connection, data_channel) = self.create_data_channel(webrtc_prov).await?;
+
+        // Set up the streaming receiver before sending so no item is missed. The stream ends
+        // when the sender is dropped: after the done marker or an error, or when the data
+        // channel closes.
+        let (tx, rx) = mpsc::channel(16);
+        let sender = Arc::new(Mutex::new(Some(tx)));
+
+        let items = Arc::clone(&sender);
+        let mut reassembler = Reassembler::default();
+        data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
+            let items = Arc::clone(&items);
+            let payload = reassembler.push(msg.data, MAX_MESSAGE_SIZE);
+            Box::pin(async move {
+                let mut sender = items.lock().await;
+                let Some(tx) = sender.as_ref() else {
+                    return;
+                };
+                let item = match payload {
+                    Ok(Some(payload)) => stream_item(&payload),
+                    Ok(None) => return,
+                    Err(e) => Err(e),
+                };
+                let done = !matches!(item, Ok(Some(_)));
+                if let Some(item) = item.transpose() {
+                    let _ = tx.send(item).await;
+                }
+                if done {
+                    sender.take();
+                }
+            })
+        }));
+        data_channel.on_close(Box::new(move || {
+            let sender = Arc::clone(&sender);
+            Box::pin(async move {
+                sender.lock().await.take();
+            })
+        }));
 
         // Send streaming request
         let request = serde_json::json!({
@@ -387,22 +797,18 @@ impl ClientTransport for WebRtcTransport {
             }
         });
 
-        let request_bytes = serde_json::to_vec(&request)?;
-        data_channel.send(&request_bytes.into()).await?;
-
-        // Set up streaming receiver
-        let (tx, rx) = mpsc::channel(16);
-
-        data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
-            let tx = tx.clone();
-            Box::pin(async move {
-                let parsed = serde_json::from_slice::<Value>(&msg.data)
-                    .map_err(|e| anyhow!("Failed to parse stream item: {}", e));
-                let _ = tx.send(parsed).await;
-            })
-        }));
-
-        Ok(boxed_channel_stream(rx, None))
+        send_message(&data_channel, webrtc_prov, &request).await?;
+
+        // The connection lives as long as the stream, and closing the stream closes it
+        Ok(boxed_channel_stream(
+            rx,
+            Some(Box::new(move || {
+                tokio::spawn(async move {
+                    let _ = peer_connection.close().await;
+                });
+                Ok(())
+            })),
+        ))
     }
 }
 
@@ -410,6 +816,9 @@ impl ClientTransport for WebRtcTransport {
 mod tests {
     use super::*;
     use crate::auth::{ApiKeyAuth, AuthType, BasicAuth};
+    use crate::providers::webrtc::Signaling;
+    use crate::transports::test_websocket;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_default_schema() {
@@ -486,6 +895,479 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_wait_for_gathering_gives_up_after_timeout() {
+        let (still_gathering, gathered) = mpsc::channel::<()>(1);
+        let started = std::time::Instant::now();
+        assert!(!wait_for_gathering(gathered, Duration::from_millis(50)).await);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let (done, gathered) = mpsc::channel::<()>(1);
+        drop(done);
+        assert!(wait_for_gathering(gathered, Duration::from_secs(5)).await);
+        drop(still_gathering);
+    }
+
+    #[test]
+    fn test_stream_items() {
+        assert_eq!(
+            stream_item(br#"{"number": 1}"#).unwrap(),
+            Some(serde_json::json!({ "number": 1 }))
+        );
+        assert_eq!(stream_item(br#"{"$done": true}"#).unwrap(), None);
+        let err = stream_item(br#"{"error": "no such tool"}"#).unwrap_err();
+        assert!(err.to_string().contains("no such tool"), "{err}");
+        assert!(stream_item(b"not json").is_err());
+    }
+
+    /// Peer connections of the test peers, kept open until the test ends.
+    type Peers = Arc<Mutex<Vec<Arc<RTCPeerConnection>>>>;
+
+    /// Messages above this size are chunked by the test peer.
+    const PEER_MAX_MESSAGE_BYTES: usize = 16 * 1024;
+
+    /// A peer answering like the example one: `list_tools` lists `stream_numbers`, which
+    /// streams `count` numbers and then the done marker, and `echo` returns its arguments.
+    /// `silent` is never answered. Requests are reassembled and large replies chunked.
+    async fn example_peer(peers: &Peers) -> Arc<RTCPeerConnection> {
+        let api = APIBuilder::new().build();
+        let peer = Arc::new(
+            api.new_peer_connection(RTCConfiguration::default())
+                .await
+                .unwrap(),
+        );
+        peer.on_data_channel(Box::new(|channel: Arc<RTCDataChannel>| {
+            let replies = Arc::clone(&channel);
+            let mut reassembler = Reassembler::default();
+            channel.on_message(Box::new(move |msg: DataChannelMessage| {
+                let channel = Arc::clone(&replies);
+                let request = reassembler.push(msg.data, usize::MAX).unwrap();
+                Box::pin(async move {
+                    let Some(request) = request else {
+                        return;
+                    };
+                    let request: Value = serde_json::from_slice(&request).unwrap();
+                    if request["params"]["tool"] == "silent" {
+                        return;
+                    }
+                    if request["params"]["tool"] == "echo" {
+                        let reply = serde_json::json!({ "result": request["params"]["args"] });
+                        let reply = serde_json::to_vec(&reply).unwrap();
+                        for frame in chunking::split_message(reply, PEER_MAX_MESSAGE_BYTES) {
+                            channel.send(&frame).await.unwrap();
+                        }
+                        return;
+                    }
+                    if request["method"] == "list_tools" {
+                        let tools = serde_json::json!({ "tools": [{
+                            "name": "stream_numbers",
+                            "description": "Streams numbers from 1 to count",
+                            "inputs": { "type": "object" },
+                            "outputs": { "type": "object" },
+                            "tags": []
+                        }] });
+                        channel.send(&tools.to_string().into()).await.unwrap();
+                        return;
+                    }
+                    let count = request["params"]["args"]["count"].as_i64().unwrap_or(0);
+                    for n in 1..=count {
+                        let item = serde_json::json!({ "number": n }).to_string();
+                        channel.send(&item.into()).await.unwrap();
+                    }
+                    let done = serde_json::json!({ "$done": true }).to_string();
+                    channel.send(&done.into()).await.unwrap();
+                })
+            }));
+            Box::pin(async {})
+        }));
+        peers.lock().await.push(Arc::clone(&peer));
+        peer
+    }
+
+    /// Apply a posted offer to `peer` and return the answer, carrying every candidate as HTTP
+    /// signaling cannot trickle them.
+    async fn answer_offer(peer: &RTCPeerConnection, offer: &Value) -> Value {
+        let sdp = offer["sdp"].as_str().unwrap().to_string();
+        peer.set_remote_description(RTCSessionDescription::offer(sdp).unwrap())
+            .await
+            .unwrap();
+        let answer = peer.create_answer(None).await.unwrap();
+        let mut gathered = peer.gathering_complete_promise().await;
+        peer.set_local_description(answer).await.unwrap();
+        let _ = gathered.recv().await;
+        let answer = peer.local_description().await.unwrap();
+        serde_json::json!({ "sdp": answer.sdp })
+    }
+
+    /// Answer offers posted to `/offer` with the example peer. Returns the offer URL.
+    fn serve_http_signaling(peers: Peers) -> String {
+        use axum::{extract::State, routing::post, Json, Router};
+
+        async fn offer(State(peers): State<Peers>, Json(offer): Json<Value>) -> Json<Value> {
+            let peer = example_peer(&peers).await;
+            Json(answer_offer(&peer, &offer).await)
+        }
+
+        let app = Router::new().route("/offer", post(offer)).with_state(peers);
+        format!("http://{}/offer", serve_router(app))
+    }
+
+    /// Answer offers posted to `/offer` from a peer that is closed right after answering, so
+    /// the connection never comes up. Returns the offer URL.
+    fn serve_vanishing_peer_signaling() -> String {
+        use axum::{routing::post, Json, Router};
+
+        async fn offer(Json(offer): Json<Value>) -> Json<Value> {
+            let peers = Peers::default();
+            let peer = example_peer(&peers).await;
+            let answer = answer_offer(&peer, &offer).await;
+            peer.close().await.unwrap();
+            Json(answer)
+        }
+
+        let app = Router::new().route("/offer", post(offer));
+        format!("http://{}/offer", serve_router(app))
+    }
+
+    fn serve_router(app: axum::Router) -> std::net::SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    /// Serve TURN credentials valid for `ttl` seconds at `/credentials` to requests carrying
+    /// `X-Turn-Key: secret`. Returns the URL and the number of credentials handed out.
+    fn serve_turn_credentials(ttl: u64) -> (String, Arc<AtomicUsize>) {
+        use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::get, Json, Router};
+
+        async fn credentials(
+            State((ttl, fetches)): State<(u64, Arc<AtomicUsize>)>,
+            headers: HeaderMap,
+        ) -> Result<Json<Value>, StatusCode> {
+            if headers.get("X-Turn-Key").and_then(|v| v.to_str().ok()) != Some("secret") {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            let n = fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(Json(serde_json::json!({
+                "username": format!("{}:alice", 1_700_000_000 + n),
+                "credential": format!("hmac-{}", n),
+                "ttl": ttl,
+                "uris": ["turn:turn.example.com:3478?transport=udp"]
+            })))
+        }
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/credentials", get(credentials))
+            .with_state((ttl, Arc::clone(&fetches)));
+        (format!("http://{}/credentials", serve_router(app)), fetches)
+    }
+
+    fn turn_provider(credential_endpoint: String, key: &str) -> WebRtcProvider {
+        let auth = AuthConfig::ApiKey(ApiKeyAuth {
+            auth_type: AuthType::ApiKey,
+            api_key: key.to_string(),
+            var_name: "X-Turn-Key".to_string(),
+            location: "header".to_string(),
+        });
+        WebRtcProvider {
+            credential_endpoint: Some(credential_endpoint),
+            ..WebRtcProvider::new(
+                "peer".to_string(),
+                "http://127.0.0.1:9/offer".to_string(),
+                Some(auth),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_credential_endpoint_adds_turn_server() {
+        let (endpoint, fetches) = serve_turn_credentials(3600);
+        let prov = turn_provider(endpoint, "secret");
+        let transport = WebRtcTransport::new();
+
+        let config = transport.rtc_configuration(&prov).await;
+        assert_eq!(config.ice_servers.len(), 2);
+        assert_eq!(config.ice_servers[0].urls, prov.ice_servers[0].urls);
+        let turn = &config.ice_servers[1];
+        assert_eq!(turn.urls, vec!["turn:turn.example.com:3478?transport=udp"]);
+        assert_eq!(turn.username, "1700000000:alice");
+        assert_eq!(turn.credential, "hmac-0");
+
+        // Credentials are reused until their ttl elapses
+        let config = transport.rtc_configuration(&prov).await;
+        assert_eq!(config.ice_servers[1].credential, "hmac-0");
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_turn_credentials_are_fetched_again() {
+        let (endpoint, fetches) = serve_turn_credentials(0);
+        let prov = turn_provider(endpoint, "secret");
+        let transport = WebRtcTransport::new();
+
+        transport.rtc_configuration(&prov).await;
+        let config = transport.rtc_configuration(&prov).await;
+        assert_eq!(config.ice_servers[1].username, "1700000001:alice");
+        assert_eq!(config.ice_servers[1].credential, "hmac-1");
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_credential_fetch_keeps_configured_servers() {
+        let (endpoint, fetches) = serve_turn_credentials(3600);
+        let prov = turn_provider(endpoint, "wrong");
+
+        let config = WebRtcTransport::new().rtc_configuration(&prov).await;
+        assert_eq!(config.ice_servers.len(), 1);
+        assert_eq!(config.ice_servers[0].urls, prov.ice_servers[0].urls);
+        assert_eq!(fetches.load(Ordering::SeqCst), 0);
+    }
+
+    /// Answer one offer sent over a WebSocket with the example peer, trickling candidates both
+    /// ways. Returns the socket URL and the `X-Signal-Key` header of the upgrade request.
+    async fn serve_websocket_signaling(
+        peers: Peers,
+    ) -> (String, Arc<std::sync::Mutex<Option<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let key = Arc::new(std::sync::Mutex::new(None));
+        let seen = Arc::clone(&key);
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let socket = test_websocket::accept(stream, move |request, _| {
+                *seen.lock().unwrap() = request
+                    .headers()
+                    .get("X-Signal-Key")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+            })
+            .await;
+            let (mut sink, mut messages) = socket.split();
+
+            let peer = example_peer(&peers).await;
+            let (candidate_tx, mut candidates) = mpsc::unbounded_channel();
+            peer.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+                let candidate_tx = candidate_tx.clone();
+                Box::pin(async move {
+                    if let Some(candidate) = candidate {
+                        let _ = candidate_tx.send(candidate.to_json().unwrap());
+                    }
+                })
+            }));
+
+            loop {
+                let reply = tokio::select! {
+                    Some(candidate) = candidates.recv() => SignalMessage::Candidate { candidate },
+                    message = messages.next() => {
+                        let Some(Ok(Message::Text(text))) = message else {
+                            break;
+                        };
+                        match serde_json::from_str(&text).unwrap() {
+                            SignalMessage::Offer { sdp } => {
+                                let offer = RTCSessionDescription::offer(sdp).unwrap();
+                                peer.set_remote_description(offer).await.unwrap();
+                                let answer = peer.create_answer(None).await.unwrap();
+                                peer.set_local_description(answer.clone()).await.unwrap();
+                                SignalMessage::Answer { sdp: answer.sdp }
+                            }
+                            SignalMessage::Candidate { candidate } => {
+                                peer.add_ice_candidate(candidate).await.unwrap();
+                                continue;
+                            }
+                            other => panic!("unexpected signaling message {:?}", other),
+                        }
+                    }
+                };
+                if send_signal(&mut sink, &reply).await.is_err() {
+                    break;
+                }
+            }
+        });
+        (format!("ws://{}", addr), key)
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_stream_forwards_items_until_done() {
+        let peers = Peers::default();
+        let prov = WebRtcProvider {
+            // Host candidates are enough between two local peers
+            ice_servers: Vec::new(),
+            ..WebRtcProvider::new(
+                "peer".to_string(),
+                serve_http_signaling(Arc::clone(&peers)),
+                None,
+            )
+        };
+        let transport = WebRtcTransport::new();
+        let args = HashMap::from([("count".to_string(), serde_json::json!(3))]);
+        let mut stream = transport
+            .call_tool_stream("stream_numbers", args, &prov)
+            .await
+            .unwrap();
+
+        for n in 1..=3 {
+            assert_eq!(
+                stream.next().await.unwrap(),
+                Some(serde_json::json!({ "number": n }))
+            );
+        }
+        assert_eq!(stream.next().await.unwrap(), None);
+        stream.close().await.unwrap();
+    }
+
+    /// Accept signaling connections and never answer them, like a server that cannot be
+    /// reached. Returns the offer URL.
+    async fn serve_silent_signaling() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        format!("http://{}/offer", addr)
+    }
+
+    fn assert_timeout(err: &anyhow::Error, expected: &[&str]) {
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+        for text in expected {
+            assert!(err.to_string().contains(text), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_covers_signaling() {
+        let prov = WebRtcProvider {
+            ice_servers: Vec::new(),
+            connect_timeout_ms: Some(300),
+            ..WebRtcProvider::new("peer".to_string(), serve_silent_signaling().await, None)
+        };
+
+        let started = std::time::Instant::now();
+        let err = WebRtcTransport::new()
+            .register_tool_provider(&prov)
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_timeout(
+            &err,
+            &[
+                "did not connect within 300ms",
+                "signaling state have-local-offer",
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_timeout_reports_connection_state() {
+        let prov = WebRtcProvider {
+            ice_servers: Vec::new(),
+            open_timeout_ms: Some(500),
+            ..WebRtcProvider::new("peer".to_string(), serve_vanishing_peer_signaling(), None)
+        };
+
+        let started = std::time::Instant::now();
+        let err = WebRtcTransport::new()
+            .register_tool_provider(&prov)
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_timeout(
+            &err,
+            &[
+                "data channel did not open within 500ms",
+                "signaling state stable",
+                "peer connection state",
+                "ICE connection state",
+                "selected candidate pair",
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_timeout_is_configurable() {
+        let peers = Peers::default();
+        let prov = WebRtcProvider {
+            ice_servers: Vec::new(),
+            response_timeout_ms: Some(200),
+            ..WebRtcProvider::new(
+                "peer".to_string(),
+                serve_http_signaling(Arc::clone(&peers)),
+                None,
+            )
+        };
+
+        let err = WebRtcTransport::new()
+            .call_tool("silent", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert_timeout(&err, &["sent no response within 200ms"]);
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_round_trips_chunked_messages() {
+        let peers = Peers::default();
+        let prov = WebRtcProvider {
+            ice_servers: Vec::new(),
+            max_message_bytes: Some(PEER_MAX_MESSAGE_BYTES),
+            ..WebRtcProvider::new(
+                "peer".to_string(),
+                serve_http_signaling(Arc::clone(&peers)),
+                None,
+            )
+        };
+        let text: String = (0..1024 * 1024)
+            .map(|i| (b'a' + (i % 26) as u8) as char)
+            .collect();
+        let args = HashMap::from([("text".to_string(), serde_json::json!(text))]);
+
+        let result = WebRtcTransport::new()
+            .call_tool("echo", args.clone(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(result, serde_json::json!(args));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_signaling_trickles_candidates() {
+        let peers = Peers::default();
+        let (url, key) = serve_websocket_signaling(Arc::clone(&peers)).await;
+        let auth = AuthConfig::ApiKey(ApiKeyAuth {
+            auth_type: AuthType::ApiKey,
+            api_key: "secret".to_string(),
+            var_name: "X-Signal-Key".to_string(),
+            location: "header".to_string(),
+        });
+        let prov = WebRtcProvider {
+            signaling: Some(Signaling {
+                kind: SignalingKind::Websocket,
+                url,
+            }),
+            ice_servers: Vec::new(),
+            ..WebRtcProvider::new("peer".to_string(), String::new(), Some(auth))
+        };
+
+        let tools = WebRtcTransport::new()
+            .register_tool_provider(&prov)
+            .await
+            .unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "stream_numbers");
+        assert_eq!(key.lock().unwrap().as_deref(), Some("secret"));
+    }
+
     #[test]
     fn test_transport_implements_trait() {
         fn assert_client_transport<T: ClientTransport>() {}
@@ -0,0 +1,121 @@
+//! Time-limited TURN credentials fetched from a provider's `credential_endpoint`, as handed
+//! out by servers using the TURN REST API: a username of `expiry:user`, an HMAC credential,
+//! and a lifetime in seconds.
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::providers::webrtc::IceServer;
+
+/// The body a credential endpoint answers with.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct TurnCredentials {
+    pub username: String,
+    pub credential: String,
+    /// Seconds the credentials stay valid
+    pub ttl: u64,
+    pub uris: Vec<String>,
+}
+
+impl TurnCredentials {
+    pub(crate) fn ice_server(&self) -> IceServer {
+        IceServer {
+            urls: self.uris.clone(),
+            username: Some(self.username.clone()),
+            credential: Some(self.credential.clone()),
+        }
+    }
+}
+
+/// GET the credentials from an endpoint, with the provider's auth already applied to `request`.
+pub(crate) async fn fetch(request: reqwest::RequestBuilder) -> Result<TurnCredentials> {
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "TURN credential endpoint returned error: {}",
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Invalid TURN credentials: {}", e))
+}
+
+struct Cached {
+    endpoint: String,
+    expires: Instant,
+    credentials: TurnCredentials,
+}
+
+/// Credentials fetched per provider, reused until their `ttl` elapses.
+#[derive(Default)]
+pub(crate) struct CredentialCache {
+    entries: Mutex<HashMap<String, Cached>>,
+}
+
+impl CredentialCache {
+    /// The credentials fetched for `provider` from `endpoint`, unless they have expired.
+    pub(crate) fn get(&self, provider: &str, endpoint: &str) -> Option<TurnCredentials> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(provider)
+            .filter(|cached| cached.endpoint == endpoint && Instant::now() < cached.expires)
+            .map(|cached| cached.credentials.clone())
+    }
+
+    pub(crate) fn insert(&self, provider: &str, endpoint: &str, credentials: TurnCredentials) {
+        let cached = Cached {
+            endpoint: endpoint.to_string(),
+            expires: Instant::now() + Duration::from_secs(credentials.ttl),
+            credentials,
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(provider.to_string(), cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(ttl: u64) -> TurnCredentials {
+        serde_json::from_value(serde_json::json!({
+            "username": "1700000000:alice",
+            "credential": "c2VjcmV0",
+            "ttl": ttl,
+            "uris": ["turn:turn.example.com:3478?transport=udp"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn credentials_become_a_turn_server() {
+        let server = credentials(60).ice_server();
+        assert_eq!(
+            server.urls,
+            vec!["turn:turn.example.com:3478?transport=udp"]
+        );
+        assert_eq!(server.username.as_deref(), Some("1700000000:alice"));
+        assert_eq!(server.credential.as_deref(), Some("c2VjcmV0"));
+    }
+
+    #[test]
+    fn cached_credentials_expire_after_their_ttl() {
+        let cache = CredentialCache::default();
+        cache.insert("peer", "https://turn.example.com/creds", credentials(3600));
+        assert_eq!(
+            cache.get("peer", "https://turn.example.com/creds"),
+            Some(credentials(3600))
+        );
+        assert_eq!(cache.get("peer", "https://other.example.com/creds"), None);
+        assert_eq!(cache.get("other", "https://turn.example.com/creds"), None);
+
+        cache.insert("peer", "https://turn.example.com/creds", credentials(0));
+        assert_eq!(cache.get("peer", "https://turn.example.com/creds"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Frame encodings for WebSocket payloads. JSON goes out as text frames; MessagePack and
+//! CBOR go out as binary frames and need the `msgpack` and `cbor` features.
+use anyhow::Result;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::providers::websocket::WebSocketEncoding;
+
+/// Encode `value` as the frame the provider's encoding calls for.
+pub(crate) fn encode(value: &Value, encoding: WebSocketEncoding) -> Result<Message> {
+    match encoding {
+        WebSocketEncoding::Json => Ok(Message::Text(value.to_string())),
+        WebSocketEncoding::Msgpack => encode_msgpack(value).map(Message::Binary),
+        WebSocketEncoding::Cbor => encode_cbor(value).map(Message::Binary),
+    }
+}
+
+/// Decode a binary frame. JSON providers read it as UTF-8 JSON text.
+pub(crate) fn decode_binary(bytes: &[u8], encoding: WebSocketEncoding) -> Result<Value> {
+    match encoding {
+        WebSocketEncoding::Json => Ok(serde_json::from_slice(bytes)?),
+        WebSocketEncoding::Msgpack => decode_msgpack(bytes),
+        WebSocketEncoding::Cbor => decode_cbor(bytes),
+    }
+}
+
+#[cfg(feature = "msgpack")]
+fn encode_msgpack(value: &Value) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec(value)?)
+}
+
+#[cfg(feature = "msgpack")]
+fn decode_msgpack(bytes: &[u8]) -> Result<Value> {
+    Ok(rmp_serde::from_slice(bytes)?)
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn encode_msgpack(_value: &Value) -> Result<Vec<u8>> {
+    Err(missing_feature("msgpack"))
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn decode_msgpack(_bytes: &[u8]) -> Result<Value> {
+    Err(missing_feature("msgpack"))
+}
+
+#[cfg(feature = "cbor")]
+fn encode_cbor(value: &Value) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "cbor")]
+fn decode_cbor(bytes: &[u8]) -> Result<Value> {
+    Ok(ciborium::de::from_reader(bytes)?)
+}
+
+#[cfg(not(feature = "cbor"))]
+fn encode_cbor(_value: &Value) -> Result<Vec<u8>> {
+    Err(missing_feature("cbor"))
+}
+
+#[cfg(not(feature = "cbor"))]
+fn decode_cbor(_bytes: &[u8]) -> Result<Value> {
+    Err(missing_feature("cbor"))
+}
+
+#[cfg(not(all(feature = "msgpack", feature = "cbor")))]
+fn missing_feature(name: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "WebSocket encoding `{0}` requires the `{0}` feature of rs-utcp",
+        name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nested() -> Value {
+        json!({
+            "name": "probe",
+            "tags": ["a", "b"],
+            "limits": { "max": 10, "ratio": 0.5, "enabled": true, "next": null },
+            "rows": [{ "id": 1, "values": [1, 2, 3] }, { "id": 2, "values": [] }],
+        })
+    }
+
+    #[test]
+    fn json_is_sent_as_text() {
+        let msg = encode(&nested(), WebSocketEncoding::Json).unwrap();
+        assert_eq!(msg, Message::Text(nested().to_string()));
+        let bytes = nested().to_string().into_bytes();
+        assert_eq!(
+            decode_binary(&bytes, WebSocketEncoding::Json).unwrap(),
+            nested()
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_round_trips_nested_values() {
+        let msg = encode(&nested(), WebSocketEncoding::Msgpack).unwrap();
+        let bytes = match msg {
+            Message::Binary(bytes) => bytes,
+            other => panic!("expected a binary frame, got {other:?}"),
+        };
+        assert_eq!(
+            decode_binary(&bytes, WebSocketEncoding::Msgpack).unwrap(),
+            nested()
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trips_nested_values() {
+        let msg = encode(&nested(), WebSocketEncoding::Cbor).unwrap();
+        let bytes = match msg {
+            Message::Binary(bytes) => bytes,
+            other => panic!("expected a binary frame, got {other:?}"),
+        };
+        assert_eq!(
+            decode_binary(&bytes, WebSocketEncoding::Cbor).unwrap(),
+            nested()
+        );
+    }
+
+    #[cfg(not(feature = "msgpack"))]
+    #[test]
+    fn msgpack_without_feature_is_reported() {
+        let err = encode(&nested(), WebSocketEncoding::Msgpack).unwrap_err();
+        assert!(err.to_string().contains("`msgpack` feature"), "{err}");
+    }
+}
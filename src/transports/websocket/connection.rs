@@ -0,0 +1,219 @@
+//! Persistent WebSocket connection shared by every call to a `keep_alive` provider.
+//!
+//! Requests go out as `{"jsonrpc": "2.0", "id", "method", "params"}` and replies are routed
+//! back by `id`, so several calls can be in flight on one socket. A reply carries either
+//! `result` or `error`; streamed calls receive any number of `result` frames followed by a
+//! frame with `"complete": true`. Frames with an unknown id are ignored. Envelopes are encoded
+//! with the provider's encoding.
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::{http::Request, protocol::Message};
+
+use super::{codec, tls};
+use crate::providers::websocket::{WebSocketEncoding, WebSocketTlsConfig};
+
+enum Pending {
+    Call(oneshot::Sender<Result<Value>>),
+    Stream(mpsc::Sender<Result<Value>>),
+}
+
+type PendingMap = Arc<Mutex<HashMap<u64, Pending>>>;
+
+pub(crate) struct WsConnection {
+    outgoing: mpsc::UnboundedSender<Message>,
+    pending: PendingMap,
+    next_id: AtomicU64,
+    closed: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+    encoding: WebSocketEncoding,
+}
+
+impl WsConnection {
+    /// Dial `req` and start the tasks that write requests and route replies.
+    pub(crate) async fn connect(
+        req: Request<()>,
+        encoding: WebSocketEncoding,
+        tls_config: Option<&WebSocketTlsConfig>,
+    ) -> Result<Self> {
+        let ws_stream = tls::connect(req, tls_config).await?;
+        let (mut sink, mut stream) = ws_stream.split();
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+        tokio::spawn(async move {
+            while let Some(msg) = outgoing_rx.recv().await {
+                let is_close = matches!(msg, Message::Close(_));
+                if sink.send(msg).await.is_err() || is_close {
+                    break;
+                }
+            }
+        });
+
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let pending = pending.clone();
+            let closed = closed.clone();
+            let outgoing = outgoing.clone();
+            tokio::spawn(async move {
+                let reason = loop {
+                    match stream.next().await {
+                        Some(Ok(Message::Text(text))) => {
+                            if let Ok(frame) = serde_json::from_str(&text) {
+                                route(&pending, frame).await;
+                            }
+                        }
+                        Some(Ok(Message::Binary(bin))) => {
+                            if let Ok(frame) = codec::decode_binary(&bin, encoding) {
+                                route(&pending, frame).await;
+                            }
+                        }
+                        Some(Ok(Message::Ping(data))) => {
+                            let _ = outgoing.send(Message::Pong(data));
+                        }
+                        Some(Ok(Message::Close(_))) | None => {
+                            break "WebSocket connection closed".to_string()
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(err)) => break format!("WebSocket receive error: {}", err),
+                    }
+                };
+                closed.store(true, Ordering::SeqCst);
+                fail_pending(&pending, &reason).await;
+            })
+        };
+
+        Ok(Self {
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            closed,
+            reader,
+            encoding,
+        })
+    }
+
+    /// Whether the socket has gone away and the connection must be replaced.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst) || self.outgoing.is_closed()
+    }
+
+    /// Send a request and wait for the reply with the same id.
+    pub(crate) async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let (tx, rx) = oneshot::channel();
+        self.send(method, params, Pending::Call(tx))?;
+        rx.await
+            .map_err(|_| anyhow!("WebSocket connection closed"))?
+    }
+
+    /// Send a request and receive every `result` frame tagged with its id until `complete`.
+    pub(crate) fn subscribe(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<mpsc::Receiver<Result<Value>>> {
+        let (tx, rx) = mpsc::channel(256);
+        self.send(method, params, Pending::Stream(tx))?;
+        Ok(rx)
+    }
+
+    /// Send a close frame and stop routing replies; pending calls fail.
+    pub(crate) async fn close(&self) {
+        let _ = self.outgoing.send(Message::Close(None));
+        self.reader.abort();
+        self.closed.store(true, Ordering::SeqCst);
+        fail_pending(&self.pending, "WebSocket connection closed").await;
+    }
+
+    fn send(&self, method: &str, params: Value, pending: Pending) -> Result<()> {
+        if self.is_closed() {
+            return Err(anyhow!("WebSocket connection closed"));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let message = codec::encode(&request, self.encoding)?;
+        self.pending.lock().unwrap().insert(id, pending);
+        if self.outgoing.send(message).is_err() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(anyhow!("WebSocket connection closed"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WsConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Deliver one frame to the call or stream waiting on its id.
+async fn route(pending: &PendingMap, frame: Value) {
+    let id = match frame.get("id").and_then(Value::as_u64) {
+        Some(id) => id,
+        None => return,
+    };
+    let error = frame
+        .get("error")
+        .map(|error| anyhow!("WebSocket error response: {}", error));
+    let complete = frame
+        .get("complete")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let entry = {
+        let mut pending = pending.lock().unwrap();
+        let done =
+            error.is_some() || complete || matches!(pending.get(&id), Some(Pending::Call(_)));
+        if done {
+            pending.remove(&id)
+        } else {
+            match pending.get(&id) {
+                Some(Pending::Stream(tx)) => Some(Pending::Stream(tx.clone())),
+                _ => None,
+            }
+        }
+    };
+    let result = frame.get("result").cloned();
+    match (entry, error) {
+        (Some(Pending::Call(tx)), Some(err)) => {
+            let _ = tx.send(Err(err));
+        }
+        (Some(Pending::Call(tx)), None) => {
+            let _ = tx.send(Ok(result.unwrap_or(Value::Null)));
+        }
+        (Some(Pending::Stream(tx)), Some(err)) => {
+            let _ = tx.send(Err(err)).await;
+        }
+        (Some(Pending::Stream(tx)), None) => {
+            if let Some(result) = result {
+                let _ = tx.send(Ok(result)).await;
+            }
+        }
+        (None, _) => {}
+    }
+}
+
+async fn fail_pending(pending: &PendingMap, reason: &str) {
+    let entries: Vec<Pending> = pending.lock().unwrap().drain().map(|(_, p)| p).collect();
+    for entry in entries {
+        match entry {
+            Pending::Call(tx) => {
+                let _ = tx.send(Err(anyhow!("{}", reason)));
+            }
+            Pending::Stream(tx) => {
+                let _ = tx.send(Err(anyhow!("{}", reason))).await;
+            }
+        }
+    }
+}
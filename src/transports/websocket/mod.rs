@@ -4,50 +4,53 @@ use async_trait::async_trait;
 use base64::Engine;
 use futures::{SinkExt, StreamExt};
 use reqwest::Url;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::str::FromStr;
-use tokio::sync::mpsc;
-use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{
-        client::IntoClientRequest,
-        http::{HeaderName, HeaderValue, Request},
-        protocol::Message,
-    },
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+use tokio_tungstenite::tungstenite::{
+    client::IntoClientRequest,
+    handshake::client::generate_key,
+    http::{HeaderName, HeaderValue, Request},
+    protocol::{frame::coding::CloseCode, Message},
 };
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 use crate::auth::AuthConfig;
+use crate::errors::UtcpError;
 use crate::providers::base::Provider;
-use crate::providers::websocket::WebSocketProvider;
+use crate::providers::websocket::{WebSocketFraming, WebSocketProvider, WebSocketResponseMode};
 use crate::tools::{Tool, ToolInputOutputSchema};
 use crate::transports::{
     stream::{boxed_channel_stream, StreamResult},
     ClientTransport,
 };
 
+mod codec;
+mod connection;
+pub(crate) mod tls;
+
+use connection::WsConnection;
+
 /// Transport that communicates with tools over WebSocket connections.
-pub struct WebSocketTransport;
+pub struct WebSocketTransport {
+    // Map of provider name to the open connection of a keep_alive provider
+    connections: Arc<Mutex<HashMap<String, Arc<WsConnection>>>>,
+}
 
 impl WebSocketTransport {
     /// Create a WebSocket transport.
     pub fn new() -> Self {
-        Self
+        Self {
+            connections: Arc::new(Mutex::new(HashMap::new())),
+        }
     }
 
     fn default_schema() -> ToolInputOutputSchema {
-        ToolInputOutputSchema {
-            type_: "object".to_string(),
-            properties: None,
-            required: None,
-            description: None,
-            title: None,
-            items: None,
-            enum_: None,
-            minimum: None,
-            maximum: None,
-            format: None,
-        }
+        ToolInputOutputSchema::new("object")
     }
 
     fn apply_auth_to_url(&self, url: &str, auth: &AuthConfig) -> Result<String> {
@@ -97,6 +100,128 @@ impl WebSocketTransport {
         }
     }
 
+    /// Tools listed in a manual, given either as `{"tools": [...]}` or a bare array.
+    fn parse_tools(manifest: &Value) -> Option<Vec<Tool>> {
+        let tools = manifest
+            .get("tools")
+            .unwrap_or(manifest)
+            .as_array()?
+            .iter()
+            .filter_map(|t| {
+                if let Ok(tool) = serde_json::from_value::<Tool>(t.clone()) {
+                    return Some(tool);
+                }
+                let name = t.get("name").and_then(|v| v.as_str())?;
+                let schema = Self::default_schema();
+                Some(Tool {
+                    name: name.to_string(),
+                    description: t
+                        .get("description")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    inputs: schema.clone(),
+                    outputs: schema,
+                    tags: vec![],
+                    average_response_size: None,
+                    provider: None,
+                })
+            })
+            .collect();
+        Some(tools)
+    }
+
+    /// Pooled connections cannot tell calls apart without request ids, so they always use
+    /// JSON-RPC framing.
+    fn uses_jsonrpc(prov: &WebSocketProvider) -> bool {
+        prov.keep_alive || prov.framing == WebSocketFraming::Jsonrpc
+    }
+
+    /// The pooled connection for keep_alive providers, otherwise a new one for this call.
+    async fn rpc_connection(&self, prov: &WebSocketProvider) -> Result<Arc<WsConnection>> {
+        if prov.keep_alive {
+            return self.pooled_connection(prov).await;
+        }
+        let req = self.build_request(prov, &prov.url)?;
+        Ok(Arc::new(
+            WsConnection::connect(req, prov.encoding, prov.tls.as_ref()).await?,
+        ))
+    }
+
+    /// Send `args` to `{url}/{tool}` and read frames as the provider's response mode says.
+    async fn call_raw(
+        &self,
+        prov: &WebSocketProvider,
+        call_name: &str,
+        args: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        let mut base_url = prov.url.trim_end_matches('/').to_string();
+        if base_url.ends_with("/tools") {
+            base_url = base_url.trim_end_matches("/tools").to_string();
+        }
+        let url = format!("{}/{}", base_url, call_name);
+
+        let req = self.build_request(prov, &url)?;
+        let mut ws_stream = tls::connect(req, prov.tls.as_ref()).await?;
+
+        let payload = serde_json::to_value(args)?;
+        ws_stream
+            .send(codec::encode(&payload, prov.encoding)?)
+            .await?;
+
+        let default_terminator = json!({ "done": true });
+        let terminator = prov.terminator.as_ref().unwrap_or(&default_terminator);
+        let mut results = Vec::new();
+        while let Some(msg) = ws_stream.next().await {
+            let value = match msg {
+                Ok(Message::Text(text)) => {
+                    serde_json::from_str::<Value>(&text).unwrap_or(Value::String(text))
+                }
+                Ok(Message::Binary(bin)) => match codec::decode_binary(&bin, prov.encoding) {
+                    Ok(value) => value,
+                    Err(_) => match String::from_utf8(bin) {
+                        Ok(text) => Value::String(text),
+                        Err(_) => continue,
+                    },
+                },
+                Ok(Message::Close(_)) | Err(_) => break,
+                _ => continue,
+            };
+            match prov.response_mode {
+                WebSocketResponseMode::Single => {
+                    let _ = ws_stream.close(None).await;
+                    return Ok(value);
+                }
+                WebSocketResponseMode::UntilTerminator if is_terminator(&value, terminator) => {
+                    let _ = ws_stream.close(None).await;
+                    return Ok(Value::Array(results));
+                }
+                _ => results.push(value),
+            }
+        }
+
+        if prov.response_mode == WebSocketResponseMode::Single {
+            return Err(anyhow!("WebSocket closed before sending a response"));
+        }
+        Ok(Value::Array(results))
+    }
+
+    /// The provider's pooled connection, dialing `prov.url` when there is none or the
+    /// previous socket has failed.
+    async fn pooled_connection(&self, prov: &WebSocketProvider) -> Result<Arc<WsConnection>> {
+        let mut connections = self.connections.lock().await;
+        if let Some(conn) = connections.get(&prov.base.name) {
+            if !conn.is_closed() {
+                return Ok(Arc::clone(conn));
+            }
+        }
+
+        let req = self.build_request(prov, &prov.url)?;
+        let conn = Arc::new(WsConnection::connect(req, prov.encoding, prov.tls.as_ref()).await?);
+        connections.insert(prov.base.name.clone(), Arc::clone(&conn));
+        Ok(conn)
+    }
+
     fn build_request(&self, prov: &WebSocketProvider, url: &str) -> Result<Request<()>> {
         let mut url = url.to_string();
         if let Some(auth) = &prov.base.auth {
@@ -121,6 +246,129 @@ impl WebSocketTransport {
     }
 }
 
+/// What a raw stream needs to dial again and pick up where it left off.
+struct StreamSource {
+    request: Request<()>,
+    payload: Value,
+    provider: WebSocketProvider,
+}
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Forward the frames of a raw stream to `tx`. Without a reconnect policy the stream ends on
+/// close or error; with one, connections that drop without a normal close are dialed again.
+async fn read_stream(
+    mut ws_stream: WsStream,
+    tx: mpsc::Sender<Result<Value>>,
+    source: StreamSource,
+) {
+    let prov = &source.provider;
+    let name = &prov.base.name;
+    let mut last_seq = Value::Null;
+    loop {
+        let dropped = loop {
+            let parsed = match ws_stream.next().await {
+                Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text)
+                    .map_err(|e| anyhow!("Failed to parse WebSocket message: {}", e)),
+                Some(Ok(Message::Binary(bin))) => codec::decode_binary(&bin, prov.encoding)
+                    .map_err(|e| anyhow!("Failed to parse WebSocket message: {}", e)),
+                Some(Ok(Message::Close(frame))) => {
+                    break frame.is_some_and(|frame| frame.code != CloseCode::Normal)
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    if prov.reconnect.is_none() {
+                        let _ = tx
+                            .send(Err(anyhow!("WebSocket receive error: {}", err)))
+                            .await;
+                        return;
+                    }
+                    tracing::warn!(provider = %name, error = %err, "WebSocket stream dropped");
+                    break true;
+                }
+                None => break true,
+            };
+            if let (Ok(value), Some(policy)) = (&parsed, &prov.reconnect) {
+                if let Some(seq) = value.get(&policy.sequence_field) {
+                    last_seq = seq.clone();
+                }
+            }
+            if tx.send(parsed).await.is_err() {
+                return;
+            }
+        };
+
+        let policy = match &prov.reconnect {
+            Some(policy) if dropped => policy,
+            _ => return,
+        };
+        let resend = match &policy.resume_message {
+            Some(Value::Object(resume)) => {
+                let mut resume = resume.clone();
+                resume.insert("last_seq".to_string(), last_seq.clone());
+                Value::Object(resume)
+            }
+            Some(resume) => resume.clone(),
+            None => source.payload.clone(),
+        };
+
+        let mut delay = Duration::from_millis(policy.backoff_ms);
+        let mut attempt = 0;
+        ws_stream = loop {
+            if attempt == policy.max_attempts {
+                let _ = tx
+                    .send(Err(anyhow!(
+                        "WebSocket stream dropped; gave up after {} reconnect attempts",
+                        policy.max_attempts
+                    )))
+                    .await;
+                return;
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            tracing::info!(provider = %name, attempt, "Reconnecting WebSocket stream");
+            let dialed = async {
+                let mut stream =
+                    tls::connect(clone_request(&source.request), prov.tls.as_ref()).await?;
+                stream.send(codec::encode(&resend, prov.encoding)?).await?;
+                Ok::<_, anyhow::Error>(stream)
+            };
+            match dialed.await {
+                Ok(stream) => {
+                    tracing::info!(provider = %name, attempt, "WebSocket stream reconnected");
+                    break stream;
+                }
+                Err(err) => {
+                    tracing::warn!(provider = %name, attempt, error = %err, "Reconnect failed");
+                }
+            }
+        };
+    }
+}
+
+/// Copy of a handshake request with a fresh `Sec-WebSocket-Key`, for dialing again.
+fn clone_request(req: &Request<()>) -> Request<()> {
+    let mut copy = Request::new(());
+    *copy.method_mut() = req.method().clone();
+    *copy.uri_mut() = req.uri().clone();
+    *copy.headers_mut() = req.headers().clone();
+    if let Ok(key) = HeaderValue::from_str(&generate_key()) {
+        copy.headers_mut().insert("Sec-WebSocket-Key", key);
+    }
+    copy
+}
+
+/// Whether `frame` is the sentinel; object sentinels match any frame containing their fields.
+fn is_terminator(frame: &Value, terminator: &Value) -> bool {
+    match (frame, terminator) {
+        (Value::Object(frame), Value::Object(fields)) => fields
+            .iter()
+            .all(|(key, value)| frame.get(key) == Some(value)),
+        _ => frame == terminator,
+    }
+}
+
 #[async_trait]
 impl ClientTransport for WebSocketTransport {
     async fn register_tool_provider(&self, prov: &dyn Provider) -> Result<Vec<Tool>> {
@@ -129,46 +377,42 @@ impl ClientTransport for WebSocketTransport {
             .downcast_ref::<WebSocketProvider>()
             .ok_or_else(|| anyhow!("Provider is not a WebSocketProvider"))?;
 
+        if Self::uses_jsonrpc(ws_prov) {
+            let conn = self.rpc_connection(ws_prov).await?;
+            let result = conn.call("list_tools", json!({})).await;
+            if !ws_prov.keep_alive {
+                conn.close().await;
+            }
+            return Ok(Self::parse_tools(&result?).unwrap_or_default());
+        }
+
         let req = self.build_request(ws_prov, &ws_prov.url)?;
-        let (mut ws_stream, _) = connect_async(req).await?;
+        let mut ws_stream = tls::connect(req, ws_prov.tls.as_ref()).await?;
 
         // Request manual/tool list
         ws_stream.send(Message::Text("manual".to_string())).await?;
-        if let Some(msg) = ws_stream.next().await {
-            if let Ok(Message::Text(text)) = msg {
-                if let Ok(manifest) = serde_json::from_str::<Value>(&text) {
-                    if let Some(tools) = manifest.get("tools").and_then(|v| v.as_array()) {
-                        let mut parsed = Vec::new();
-                        for t in tools {
-                            if let Ok(tool) = serde_json::from_value::<Tool>(t.clone()) {
-                                parsed.push(tool);
-                            } else if let Some(name) = t.get("name").and_then(|v| v.as_str()) {
-                                let schema = Self::default_schema();
-                                parsed.push(Tool {
-                                    name: name.to_string(),
-                                    description: t
-                                        .get("description")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                    inputs: schema.clone(),
-                                    outputs: schema,
-                                    tags: vec![],
-                                    average_response_size: None,
-                                    provider: None,
-                                });
-                            }
-                        }
-                        return Ok(parsed);
-                    }
-                }
-            }
+        let manifest = match ws_stream.next().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).ok(),
+            Some(Ok(Message::Binary(bin))) => codec::decode_binary(&bin, ws_prov.encoding).ok(),
+            _ => None,
+        };
+        if let Some(tools) = manifest.as_ref().and_then(Self::parse_tools) {
+            return Ok(tools);
         }
 
         Ok(vec![])
     }
 
-    async fn deregister_tool_provider(&self, _prov: &dyn Provider) -> Result<()> {
+    async fn deregister_tool_provider(&self, prov: &dyn Provider) -> Result<()> {
+        let ws_prov = prov
+            .as_any()
+            .downcast_ref::<WebSocketProvider>()
+            .ok_or_else(|| anyhow!("Provider is not a WebSocketProvider"))?;
+
+        let conn = self.connections.lock().await.remove(&ws_prov.base.name);
+        if let Some(conn) = conn {
+            conn.close().await;
+        }
         Ok(())
     }
 
@@ -187,39 +431,25 @@ impl ClientTransport for WebSocketTransport {
             .strip_prefix(&format!("{}.", ws_prov.base.name))
             .unwrap_or(tool_name);
 
-        let mut base_url = ws_prov.url.trim_end_matches('/').to_string();
-        if base_url.ends_with("/tools") {
-            base_url = base_url.trim_end_matches("/tools").to_string();
-        }
-        let url = format!("{}/{}", base_url, call_name);
-
-        let req = self.build_request(ws_prov, &url)?;
-        let (mut ws_stream, _) = connect_async(req).await?;
-
-        let payload = serde_json::to_string(&args)?;
-        ws_stream.send(Message::Text(payload)).await?;
-
-        let mut results = Vec::new();
-        while let Some(msg) = ws_stream.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    let value = serde_json::from_str::<Value>(&text)
-                        .unwrap_or_else(|_| Value::String(text));
-                    results.push(value);
+        let call = async {
+            if Self::uses_jsonrpc(ws_prov) {
+                let conn = self.rpc_connection(ws_prov).await?;
+                let params = json!({ "name": call_name, "arguments": args });
+                let result = conn.call("call_tool", params).await;
+                if !ws_prov.keep_alive {
+                    conn.close().await;
                 }
-                Ok(Message::Binary(bin)) => {
-                    if let Ok(text) = String::from_utf8(bin) {
-                        let value = serde_json::from_str::<Value>(&text)
-                            .unwrap_or_else(|_| Value::String(text));
-                        results.push(value);
-                    }
-                }
-                Ok(Message::Close(_)) | Err(_) => break,
-                _ => {}
+                result
+            } else {
+                self.call_raw(ws_prov, call_name, &args).await
             }
+        };
+        match ws_prov.timeout_ms {
+            Some(ms) => tokio::time::timeout(Duration::from_millis(ms), call)
+                .await
+                .map_err(|_| UtcpError::Timeout(format!("no result within {}ms", ms)))?,
+            None => call.await,
         }
-
-        Ok(Value::Array(results))
     }
 
     async fn call_tool_stream(
@@ -237,6 +467,26 @@ impl ClientTransport for WebSocketTransport {
             .strip_prefix(&format!("{}.", ws_prov.base.name))
             .unwrap_or(tool_name);
 
+        if Self::uses_jsonrpc(ws_prov) {
+            let conn = self.rpc_connection(ws_prov).await?;
+            let params = json!({ "name": call_name, "arguments": args, "stream": true });
+            let mut notifications = conn.subscribe("call_tool", params)?;
+            if ws_prov.keep_alive {
+                return Ok(boxed_channel_stream(notifications, None));
+            }
+            // A per-call connection lives as long as its stream.
+            let (tx, rx) = mpsc::channel(256);
+            tokio::spawn(async move {
+                while let Some(item) = notifications.recv().await {
+                    if tx.send(item).await.is_err() {
+                        break;
+                    }
+                }
+                conn.close().await;
+            });
+            return Ok(boxed_channel_stream(rx, None));
+        }
+
         let mut base_url = ws_prov.url.trim_end_matches('/').to_string();
         if base_url.ends_with("/tools") {
             base_url = base_url.trim_end_matches("/tools").to_string();
@@ -244,43 +494,25 @@ impl ClientTransport for WebSocketTransport {
         let url = format!("{}/{}", base_url, call_name);
 
         let req = self.build_request(ws_prov, &url)?;
-        let (mut ws_stream, _) = connect_async(req).await?;
+        let mut ws_stream = tls::connect(clone_request(&req), ws_prov.tls.as_ref()).await?;
 
         ws_stream
-            .send(Message::Text(serde_json::to_string(&args)?))
+            .send(codec::encode(
+                &serde_json::to_value(&args)?,
+                ws_prov.encoding,
+            )?)
             .await?;
 
         let (tx, rx) = mpsc::channel(256);
-        tokio::spawn(async move {
-            while let Some(msg) = ws_stream.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        let parsed = serde_json::from_str::<Value>(&text)
-                            .map_err(|e| anyhow!("Failed to parse WebSocket message: {}", e));
-                        if tx.send(parsed).await.is_err() {
-                            return;
-                        }
-                    }
-                    Ok(Message::Binary(bin)) => {
-                        if let Ok(text) = String::from_utf8(bin) {
-                            let parsed = serde_json::from_str::<Value>(&text)
-                                .map_err(|e| anyhow!("Failed to parse WebSocket message: {}", e));
-                            if tx.send(parsed).await.is_err() {
-                                return;
-                            }
-                        }
-                    }
-                    Ok(Message::Close(_)) => break,
-                    Ok(_) => {}
-                    Err(err) => {
-                        let _ = tx
-                            .send(Err(anyhow!("WebSocket receive error: {}", err)))
-                            .await;
-                        break;
-                    }
-                }
-            }
-        });
+        tokio::spawn(read_stream(
+            ws_stream,
+            tx,
+            StreamSource {
+                request: req,
+                payload: serde_json::to_value(&args)?,
+                provider: ws_prov.clone(),
+            },
+        ));
 
         Ok(boxed_channel_stream(rx, None))
     }
@@ -291,6 +523,7 @@ mod tests {
     use super::*;
     use crate::auth::{ApiKeyAuth, AuthType, BasicAuth};
     use crate::providers::base::{BaseProvider, ProviderType};
+    use crate::providers::websocket::{WebSocketEncoding, WebSocketReconnect, WebSocketTlsConfig};
     use serde_json::json;
     use std::sync::{
         atomic::{AtomicUsize, Ordering},
@@ -365,6 +598,13 @@ mod tests {
             protocol: Some("json".to_string()),
             keep_alive: false,
             headers: Some(HashMap::from([("X-Custom".to_string(), "1".to_string())])),
+            framing: WebSocketFraming::Raw,
+            encoding: WebSocketEncoding::Json,
+            response_mode: WebSocketResponseMode::CollectUntilClose,
+            terminator: None,
+            timeout_ms: None,
+            tls: None,
+            reconnect: None,
         };
 
         let req = transport.build_request(&prov, &prov.url).unwrap();
@@ -437,6 +677,13 @@ mod tests {
             protocol: None,
             keep_alive: false,
             headers: None,
+            framing: WebSocketFraming::Raw,
+            encoding: WebSocketEncoding::Json,
+            response_mode: WebSocketResponseMode::CollectUntilClose,
+            terminator: None,
+            timeout_ms: None,
+            tls: None,
+            reconnect: None,
         };
 
         let transport = WebSocketTransport::new();
@@ -530,6 +777,13 @@ mod tests {
             protocol: None,
             keep_alive: false,
             headers: None,
+            framing: WebSocketFraming::Raw,
+            encoding: WebSocketEncoding::Json,
+            response_mode: WebSocketResponseMode::CollectUntilClose,
+            terminator: None,
+            timeout_ms: None,
+            tls: None,
+            reconnect: None,
         };
 
         let transport = WebSocketTransport::new();
@@ -551,4 +805,443 @@ mod tests {
         let paths = seen_paths.lock().unwrap().clone();
         assert_eq!(paths, vec!["/tools".to_string(), "/echo".to_string()]);
     }
+
+    /// Serves JSON-RPC-framed calls on every accepted connection, counting connections.
+    /// `call_tool` echoes the arguments; streamed calls get two results and `complete`.
+    /// With `drop_after_reply`, each connection is closed after its first reply.
+    async fn spawn_pooled_server(drop_after_reply: bool) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let req: Value = serde_json::from_str(&text).unwrap();
+                        let id = req["id"].clone();
+                        let params = &req["params"];
+                        let mut replies = Vec::new();
+                        if params["stream"] == json!(true) {
+                            for n in 1..=2 {
+                                replies.push(json!({ "jsonrpc": "2.0", "id": id, "result": n }));
+                            }
+                            replies.push(json!({ "jsonrpc": "2.0", "id": id, "complete": true }));
+                        } else {
+                            // Unrelated pushes on the socket are ignored by the client.
+                            replies.push(json!({ "jsonrpc": "2.0", "method": "heartbeat" }));
+                            replies.push(json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": { "tool": params["name"], "echo": params["arguments"] },
+                            }));
+                        }
+                        for reply in replies {
+                            let _ = ws.send(Message::Text(reply.to_string())).await;
+                        }
+                        if drop_after_reply {
+                            let _ = ws.close(None).await;
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("ws://{}", addr), connections)
+    }
+
+    fn keep_alive_provider(url: String) -> WebSocketProvider {
+        let mut prov = WebSocketProvider::new("pooled".to_string(), url, None);
+        prov.keep_alive = true;
+        prov
+    }
+
+    #[tokio::test]
+    async fn keep_alive_reuses_one_connection_until_deregistered() {
+        let (url, connections) = spawn_pooled_server(false).await;
+        let prov = keep_alive_provider(url);
+        let transport = WebSocketTransport::new();
+
+        for n in 0..3 {
+            let args = HashMap::from([("n".to_string(), json!(n))]);
+            let value = transport
+                .call_tool("pooled.echo", args, &prov)
+                .await
+                .unwrap();
+            assert_eq!(value, json!({ "tool": "echo", "echo": { "n": n } }));
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        let mut stream = transport
+            .call_tool_stream("pooled.count", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!(1));
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!(2));
+        assert!(stream.next().await.unwrap().is_none());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        transport.deregister_tool_provider(&prov).await.unwrap();
+        assert!(transport.connections.lock().await.is_empty());
+        transport
+            .call_tool("pooled.echo", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn keep_alive_reconnects_after_the_socket_closes() {
+        let (url, connections) = spawn_pooled_server(true).await;
+        let prov = keep_alive_provider(url);
+        let transport = WebSocketTransport::new();
+
+        for n in 0..2 {
+            let args = HashMap::from([("n".to_string(), json!(n))]);
+            let value = transport
+                .call_tool("pooled.echo", args, &prov)
+                .await
+                .unwrap();
+            assert_eq!(value["echo"], json!({ "n": n }));
+            // Let the client observe the server closing the socket.
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    /// JSON-RPC server that holds `call_tool` requests until two have arrived and answers
+    /// them in reverse order, pushing an unrelated notification first.
+    async fn spawn_out_of_order_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    let mut held = Vec::new();
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let req: Value = serde_json::from_str(&text).unwrap();
+                        let reply = |result: Value| {
+                            Message::Text(
+                                json!({ "jsonrpc": "2.0", "id": req["id"], "result": result })
+                                    .to_string(),
+                            )
+                        };
+                        match req["method"].as_str() {
+                            Some("list_tools") => {
+                                let tools = json!({ "tools": [{ "name": "echo" }] });
+                                let _ = ws.send(reply(tools)).await;
+                            }
+                            _ if req["params"]["stream"] == json!(true) => {
+                                let _ = ws.send(reply(json!("first"))).await;
+                                let done =
+                                    json!({ "jsonrpc": "2.0", "id": req["id"], "complete": true });
+                                let _ = ws.send(Message::Text(done.to_string())).await;
+                            }
+                            _ => {
+                                held.push(reply(req["params"]["arguments"].clone()));
+                                if held.len() == 2 {
+                                    let note = json!({ "jsonrpc": "2.0", "method": "progress" });
+                                    let _ = ws.send(Message::Text(note.to_string())).await;
+                                    while let Some(msg) = held.pop() {
+                                        let _ = ws.send(msg).await;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        (format!("ws://{}", addr), connections)
+    }
+
+    #[tokio::test]
+    async fn jsonrpc_framing_correlates_out_of_order_replies() {
+        let (url, connections) = spawn_out_of_order_server().await;
+        let mut prov = keep_alive_provider(url);
+        prov.framing = WebSocketFraming::Jsonrpc;
+        let transport = WebSocketTransport::new();
+
+        let first = HashMap::from([("call".to_string(), json!(1))]);
+        let second = HashMap::from([("call".to_string(), json!(2))]);
+        let (a, b) = tokio::join!(
+            transport.call_tool("pooled.echo", first, &prov),
+            transport.call_tool("pooled.echo", second, &prov),
+        );
+        assert_eq!(a.unwrap(), json!({ "call": 1 }));
+        assert_eq!(b.unwrap(), json!({ "call": 2 }));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn jsonrpc_framing_without_keep_alive_dials_per_call() {
+        let (url, connections) = spawn_out_of_order_server().await;
+        let mut prov = WebSocketProvider::new("rpc".to_string(), url, None);
+        prov.framing = WebSocketFraming::Jsonrpc;
+        let transport = WebSocketTransport::new();
+
+        let tools = transport.register_tool_provider(&prov).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+
+        let mut stream = transport
+            .call_tool_stream("rpc.echo", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!("first"));
+        assert!(stream.next().await.unwrap().is_none());
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert!(transport.connections.lock().await.is_empty());
+    }
+
+    /// Raw server that answers with two frames and a `done` frame, then keeps the socket open.
+    async fn spawn_open_ended_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    let _ = ws.next().await;
+                    for frame in [
+                        json!({ "n": 1 }),
+                        json!({ "n": 2 }),
+                        json!({ "done": true, "total": 2 }),
+                    ] {
+                        let _ = ws.send(Message::Text(frame.to_string())).await;
+                    }
+                    // Wait for the client to hang up.
+                    while let Some(Ok(_)) = ws.next().await {}
+                });
+            }
+        });
+        format!("ws://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn response_modes_stop_reading_without_waiting_for_close() {
+        let url = spawn_open_ended_server().await;
+        let transport = WebSocketTransport::new();
+        let provider = |mode, terminator| {
+            let mut prov = WebSocketProvider::new("modes".to_string(), url.clone(), None);
+            prov.response_mode = mode;
+            prov.terminator = terminator;
+            prov.timeout_ms = Some(2_000);
+            prov
+        };
+
+        let single = provider(WebSocketResponseMode::Single, None);
+        let value = transport
+            .call_tool("modes.tool", HashMap::new(), &single)
+            .await
+            .unwrap();
+        assert_eq!(value, json!({ "n": 1 }));
+
+        let until_done = provider(WebSocketResponseMode::UntilTerminator, None);
+        let value = transport
+            .call_tool("modes.tool", HashMap::new(), &until_done)
+            .await
+            .unwrap();
+        assert_eq!(value, json!([{ "n": 1 }, { "n": 2 }]));
+
+        let custom = provider(
+            WebSocketResponseMode::UntilTerminator,
+            Some(json!({ "n": 2 })),
+        );
+        let value = transport
+            .call_tool("modes.tool", HashMap::new(), &custom)
+            .await
+            .unwrap();
+        assert_eq!(value, json!([{ "n": 1 }]));
+    }
+
+    #[tokio::test]
+    async fn collect_until_close_times_out_on_open_sockets() {
+        let url = spawn_open_ended_server().await;
+        let transport = WebSocketTransport::new();
+        let mut prov = WebSocketProvider::new("modes".to_string(), url, None);
+        prov.timeout_ms = Some(200);
+
+        let err = transport
+            .call_tool("modes.tool", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<UtcpError>(), Some(UtcpError::Timeout(_))),
+            "{err}"
+        );
+    }
+
+    /// The first connection sends two messages and drops without a close frame; later
+    /// connections record what the client sent and send two more, unless `accept_again` is
+    /// false, in which case the listener goes away.
+    async fn spawn_flaky_stream_server(accept_again: bool) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let seen = received.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _ = ws.next().await;
+            for seq in 1..=2 {
+                let _ = ws
+                    .send(Message::Text(json!({ "seq": seq }).to_string()))
+                    .await;
+            }
+            drop(ws);
+            if !accept_again {
+                return;
+            }
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                seen.lock()
+                    .unwrap()
+                    .push(serde_json::from_str(&text).unwrap());
+            }
+            for seq in 3..=4 {
+                let _ = ws
+                    .send(Message::Text(json!({ "seq": seq }).to_string()))
+                    .await;
+            }
+            let _ = ws.close(None).await;
+        });
+        (format!("ws://{}", addr), received)
+    }
+
+    fn reconnecting_provider(url: String, resume_message: Option<Value>) -> WebSocketProvider {
+        let mut prov = WebSocketProvider::new("flaky".to_string(), url, None);
+        prov.reconnect = Some(WebSocketReconnect {
+            max_attempts: 2,
+            backoff_ms: 10,
+            resume_message,
+            ..WebSocketReconnect::default()
+        });
+        prov
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_reconnects_and_resumes() {
+        let (url, received) = spawn_flaky_stream_server(true).await;
+        let prov = reconnecting_provider(url, Some(json!({ "resume": true })));
+
+        let mut stream = WebSocketTransport::new()
+            .call_tool_stream("flaky.ticks", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        for seq in 1..=4 {
+            assert_eq!(stream.next().await.unwrap().unwrap(), json!({ "seq": seq }));
+        }
+        assert!(stream.next().await.unwrap().is_none());
+        assert_eq!(
+            received.lock().unwrap().clone(),
+            vec![json!({ "resume": true, "last_seq": 2 })]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconnect_resends_arguments_by_default() {
+        let (url, received) = spawn_flaky_stream_server(true).await;
+        let prov = reconnecting_provider(url, None);
+        let args = HashMap::from([("topic".to_string(), json!("ticks"))]);
+
+        let mut stream = WebSocketTransport::new()
+            .call_tool_stream("flaky.ticks", args, &prov)
+            .await
+            .unwrap();
+        let mut items = Vec::new();
+        while let Some(item) = stream.next().await.unwrap() {
+            items.push(item);
+        }
+        assert_eq!(items.len(), 4);
+        assert_eq!(
+            received.lock().unwrap().clone(),
+            vec![json!({ "topic": "ticks" })]
+        );
+    }
+
+    #[tokio::test]
+    async fn exhausted_reconnects_end_the_stream_with_an_error() {
+        let (url, _) = spawn_flaky_stream_server(false).await;
+        let prov = reconnecting_provider(url, None);
+
+        let mut stream = WebSocketTransport::new()
+            .call_tool_stream("flaky.ticks", HashMap::new(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!({ "seq": 1 }));
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!({ "seq": 2 }));
+        let err = stream.next().await.unwrap_err().to_string();
+        assert!(err.contains("gave up after 2 reconnect attempts"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn tls_config_errors_surface_before_dialing() {
+        let mut prov = WebSocketProvider::new(
+            "secure".to_string(),
+            "wss://127.0.0.1:1/tools".to_string(),
+            None,
+        );
+        prov.tls = Some(WebSocketTlsConfig {
+            ca_cert_path: Some("/nonexistent/internal-ca.pem".to_string()),
+            ..Default::default()
+        });
+
+        let err = WebSocketTransport::new()
+            .call_tool("secure.echo", HashMap::new(), &prov)
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("Failed to open /nonexistent/internal-ca.pem"),
+            "{err:#}"
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn msgpack_payloads_round_trip_and_text_replies_still_parse() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            if let Some(Ok(Message::Binary(bin))) = ws.next().await {
+                let args: Value = rmp_serde::from_slice(&bin).unwrap();
+                let reply = rmp_serde::to_vec(&json!({ "echo": args })).unwrap();
+                let _ = ws.send(Message::Binary(reply)).await;
+                let _ = ws
+                    .send(Message::Text(json!({ "text": true }).to_string()))
+                    .await;
+                let _ = ws.close(None).await;
+            }
+        });
+
+        let mut prov = WebSocketProvider::new("packed".to_string(), format!("ws://{}", addr), None);
+        prov.encoding = WebSocketEncoding::Msgpack;
+        let args = HashMap::from([
+            (
+                "query".to_string(),
+                json!({ "terms": ["a", "b"], "page": { "size": 20 } }),
+            ),
+            ("weights".to_string(), json!([0.25, 1.5, -3])),
+        ]);
+
+        let value = WebSocketTransport::new()
+            .call_tool("packed.search", args.clone(), &prov)
+            .await
+            .unwrap();
+        assert_eq!(value, json!([{ "echo": args }, { "text": true }]));
+    }
 }
@@ -0,0 +1,199 @@
+//! Dialing WebSocket endpoints with a provider's [`WebSocketTlsConfig`]. Shared by the
+//! WebSocket transport and GraphQL subscriptions.
+use anyhow::{anyhow, Context, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::client::Response;
+use tokio_tungstenite::{
+    connect_async, connect_async_tls_with_config, tungstenite::http::Request, Connector,
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::providers::websocket::WebSocketTlsConfig;
+
+/// Open a WebSocket connection, using `tls` for `wss://` URLs when given.
+pub(crate) async fn connect(
+    req: Request<()>,
+    tls: Option<&WebSocketTlsConfig>,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    Ok(handshake(req, tls).await?.0)
+}
+
+/// Like [`connect`], also returning the server's handshake response.
+pub(crate) async fn handshake(
+    req: Request<()>,
+    tls: Option<&WebSocketTlsConfig>,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)> {
+    let connected = match tls {
+        Some(tls) => {
+            let connector = Connector::Rustls(Arc::new(client_config(tls)?));
+            connect_async_tls_with_config(req, None, false, Some(connector)).await?
+        }
+        None => connect_async(req).await?,
+    };
+    Ok(connected)
+}
+
+/// Build the rustls client configuration described by `tls`.
+pub(crate) fn client_config(tls: &WebSocketTlsConfig) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = &tls.ca_cert_path {
+        for cert in read_certs(path)? {
+            roots
+                .add(cert)
+                .with_context(|| format!("Invalid CA certificate in {}", path))?;
+        }
+    }
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+    let mut config = match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => builder
+            .with_client_auth_cert(read_certs(cert_path)?, read_key(key_path)?)
+            .context("Invalid client certificate or key")?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => {
+            return Err(anyhow!(
+                "client_cert_path and client_key_path must be set together"
+            ))
+        }
+    };
+    if tls.insecure_skip_verify {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyCertificate(ring::default_provider())));
+    }
+    Ok(config)
+}
+
+fn open(path: &str) -> Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path))?;
+    Ok(BufReader::new(file))
+}
+
+fn read_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read certificates from {}", path))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No PEM certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .with_context(|| format!("Failed to read private key from {}", path))?
+        .ok_or_else(|| anyhow!("No PEM private key found in {}", path))
+}
+
+/// Verifier for `insecure_skip_verify`: accepts any certificate but still checks that the
+/// handshake is signed by it.
+#[derive(Debug)]
+struct AcceptAnyCertificate(CryptoProvider);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn pem_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn error(tls: WebSocketTlsConfig) -> String {
+        format!("{:#}", client_config(&tls).unwrap_err())
+    }
+
+    #[test]
+    fn default_and_insecure_configs_build() {
+        assert!(client_config(&WebSocketTlsConfig::default()).is_ok());
+        assert!(client_config(&WebSocketTlsConfig {
+            insecure_skip_verify: true,
+            ..Default::default()
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn missing_or_empty_ca_files_are_reported() {
+        let err = error(WebSocketTlsConfig {
+            ca_cert_path: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        });
+        assert!(err.contains("Failed to open /nonexistent/ca.pem"), "{err}");
+
+        let empty = pem_file("not a certificate\n");
+        let path = empty.path().to_str().unwrap().to_string();
+        let err = error(WebSocketTlsConfig {
+            ca_cert_path: Some(path.clone()),
+            ..Default::default()
+        });
+        assert!(
+            err.contains(&format!("No PEM certificates found in {}", path)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn client_identity_needs_both_cert_and_key() {
+        let err = error(WebSocketTlsConfig {
+            client_cert_path: Some("/tmp/client.pem".to_string()),
+            ..Default::default()
+        });
+        assert!(err.contains("must be set together"), "{err}");
+    }
+}